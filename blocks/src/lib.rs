@@ -74,6 +74,11 @@ pub trait BlockExt {
 
     /// Returns the light level emitted by this block.
     fn light_emission(&self) -> u8;
+
+    /// Returns the blast resistance of this block,
+    /// which determines how much of an explosion's
+    /// intensity it absorbs.
+    fn blast_resistance(&self) -> f32;
 }
 
 impl BlockExt for Block {
@@ -253,6 +258,543 @@ impl BlockExt for Block {
             _ => 0,
         }
     }
+
+    fn blast_resistance(&self) -> f32 {
+        // Values from vanilla's block table. Blocks whose
+        // fluid state is water report water's resistance.
+        match self {
+            Block::Bedrock
+            | Block::EndPortal
+            | Block::EndPortalFrame(_)
+            | Block::EndGateway
+            | Block::CommandBlock(_)
+            | Block::ChainCommandBlock(_)
+            | Block::RepeatingCommandBlock(_)
+            | Block::StructureBlock(_)
+            | Block::Barrier => 3_600_000.0,
+            Block::Obsidian
+            | Block::EnchantingTable
+            | Block::Anvil(_)
+            | Block::ChippedAnvil(_)
+            | Block::DamagedAnvil(_) => 1200.0,
+            Block::EnderChest(_) => 600.0,
+            Block::Water(_) | Block::Lava(_) | Block::BubbleColumn(_) => 100.0,
+            Block::EndStone | Block::DragonEgg => 9.0,
+            Block::Stone
+            | Block::Granite
+            | Block::PolishedGranite
+            | Block::Diorite
+            | Block::PolishedDiorite
+            | Block::Andesite
+            | Block::PolishedAndesite
+            | Block::Cobblestone
+            | Block::MossyCobblestone
+            | Block::CobblestoneStairs(_)
+            | Block::CobblestoneWall(_)
+            | Block::MossyCobblestoneWall(_)
+            | Block::Bricks
+            | Block::BrickStairs(_)
+            | Block::StoneBricks
+            | Block::MossyStoneBricks
+            | Block::CrackedStoneBricks
+            | Block::ChiseledStoneBricks
+            | Block::StoneBrickStairs(_)
+            | Block::NetherBricks
+            | Block::NetherBrickFence(_)
+            | Block::NetherBrickStairs(_)
+            | Block::RedNetherBricks
+            | Block::GoldBlock
+            | Block::IronBlock
+            | Block::DiamondBlock
+            | Block::EmeraldBlock
+            | Block::CoalBlock
+            | Block::RedstoneBlock
+            | Block::IronBars(_)
+            | Block::Jukebox(_)
+            | Block::Prismarine
+            | Block::PrismarineBricks
+            | Block::DarkPrismarine
+            | Block::PrismarineStairs(_)
+            | Block::PrismarineBrickStairs(_)
+            | Block::DarkPrismarineStairs(_)
+            | Block::PrismarineSlab(_)
+            | Block::PrismarineBrickSlab(_)
+            | Block::DarkPrismarineSlab(_)
+            | Block::StoneSlab(_)
+            | Block::SandstoneSlab(_)
+            | Block::PetrifiedOakSlab(_)
+            | Block::CobblestoneSlab(_)
+            | Block::BrickSlab(_)
+            | Block::StoneBrickSlab(_)
+            | Block::NetherBrickSlab(_)
+            | Block::QuartzSlab(_)
+            | Block::RedSandstoneSlab(_)
+            | Block::PurpurSlab(_)
+            | Block::SmoothStone
+            | Block::SmoothSandstone
+            | Block::SmoothQuartz
+            | Block::SmoothRedSandstone
+            | Block::PurpurBlock
+            | Block::PurpurPillar(_)
+            | Block::PurpurStairs(_)
+            | Block::TubeCoralBlock
+            | Block::BrainCoralBlock
+            | Block::BubbleCoralBlock
+            | Block::FireCoralBlock
+            | Block::HornCoralBlock
+            | Block::DeadTubeCoralBlock
+            | Block::DeadBrainCoralBlock
+            | Block::DeadBubbleCoralBlock
+            | Block::DeadFireCoralBlock
+            | Block::DeadHornCoralBlock => 6.0,
+            Block::IronDoor(_) | Block::IronTrapdoor(_) | Block::Spawner => 5.0,
+            Block::Hopper(_) => 4.8,
+            Block::Terracotta
+            | Block::WhiteTerracotta
+            | Block::OrangeTerracotta
+            | Block::MagentaTerracotta
+            | Block::LightBlueTerracotta
+            | Block::YellowTerracotta
+            | Block::LimeTerracotta
+            | Block::PinkTerracotta
+            | Block::GrayTerracotta
+            | Block::LightGrayTerracotta
+            | Block::CyanTerracotta
+            | Block::PurpleTerracotta
+            | Block::BlueTerracotta
+            | Block::BrownTerracotta
+            | Block::GreenTerracotta
+            | Block::RedTerracotta
+            | Block::BlackTerracotta => 4.2,
+            Block::Cobweb => 4.0,
+            Block::Dispenser(_) | Block::Dropper(_) | Block::Furnace(_) => 3.5,
+            Block::GoldOre
+            | Block::IronOre
+            | Block::CoalOre
+            | Block::LapisOre
+            | Block::LapisBlock
+            | Block::DiamondOre
+            | Block::RedstoneOre(_)
+            | Block::EmeraldOre
+            | Block::NetherQuartzOre
+            | Block::Beacon
+            | Block::Cocoa(_)
+            | Block::Observer(_)
+            | Block::Conduit(_)
+            | Block::OakPlanks
+            | Block::SprucePlanks
+            | Block::BirchPlanks
+            | Block::JunglePlanks
+            | Block::AcaciaPlanks
+            | Block::DarkOakPlanks
+            | Block::OakStairs(_)
+            | Block::SpruceStairs(_)
+            | Block::BirchStairs(_)
+            | Block::JungleStairs(_)
+            | Block::AcaciaStairs(_)
+            | Block::DarkOakStairs(_)
+            | Block::OakSlab(_)
+            | Block::SpruceSlab(_)
+            | Block::BirchSlab(_)
+            | Block::JungleSlab(_)
+            | Block::AcaciaSlab(_)
+            | Block::DarkOakSlab(_)
+            | Block::OakFence(_)
+            | Block::SpruceFence(_)
+            | Block::BirchFence(_)
+            | Block::JungleFence(_)
+            | Block::AcaciaFence(_)
+            | Block::DarkOakFence(_)
+            | Block::OakFenceGate(_)
+            | Block::SpruceFenceGate(_)
+            | Block::BirchFenceGate(_)
+            | Block::JungleFenceGate(_)
+            | Block::AcaciaFenceGate(_)
+            | Block::DarkOakFenceGate(_)
+            | Block::OakDoor(_)
+            | Block::SpruceDoor(_)
+            | Block::BirchDoor(_)
+            | Block::JungleDoor(_)
+            | Block::AcaciaDoor(_)
+            | Block::DarkOakDoor(_)
+            | Block::OakTrapdoor(_)
+            | Block::SpruceTrapdoor(_)
+            | Block::BirchTrapdoor(_)
+            | Block::JungleTrapdoor(_)
+            | Block::AcaciaTrapdoor(_)
+            | Block::DarkOakTrapdoor(_) => 3.0,
+            Block::BlueIce => 2.8,
+            Block::Chest(_)
+            | Block::TrappedChest(_)
+            | Block::CraftingTable
+            | Block::DriedKelpBlock => 2.5,
+            Block::Cauldron(_)
+            | Block::BoneBlock(_)
+            | Block::WhiteShulkerBox(_)
+            | Block::OrangeShulkerBox(_)
+            | Block::MagentaShulkerBox(_)
+            | Block::LightBlueShulkerBox(_)
+            | Block::YellowShulkerBox(_)
+            | Block::LimeShulkerBox(_)
+            | Block::PinkShulkerBox(_)
+            | Block::GrayShulkerBox(_)
+            | Block::LightGrayShulkerBox(_)
+            | Block::CyanShulkerBox(_)
+            | Block::PurpleShulkerBox(_)
+            | Block::BlueShulkerBox(_)
+            | Block::BrownShulkerBox(_)
+            | Block::GreenShulkerBox(_)
+            | Block::RedShulkerBox(_)
+            | Block::BlackShulkerBox(_)
+            | Block::ShulkerBox(_)
+            | Block::OakLog(_)
+            | Block::SpruceLog(_)
+            | Block::BirchLog(_)
+            | Block::JungleLog(_)
+            | Block::AcaciaLog(_)
+            | Block::DarkOakLog(_)
+            | Block::StrippedOakLog(_)
+            | Block::StrippedSpruceLog(_)
+            | Block::StrippedBirchLog(_)
+            | Block::StrippedJungleLog(_)
+            | Block::StrippedAcaciaLog(_)
+            | Block::StrippedDarkOakLog(_)
+            | Block::OakWood(_)
+            | Block::SpruceWood(_)
+            | Block::BirchWood(_)
+            | Block::JungleWood(_)
+            | Block::AcaciaWood(_)
+            | Block::DarkOakWood(_)
+            | Block::StrippedOakWood(_)
+            | Block::StrippedSpruceWood(_)
+            | Block::StrippedBirchWood(_)
+            | Block::StrippedJungleWood(_)
+            | Block::StrippedAcaciaWood(_)
+            | Block::StrippedDarkOakWood(_) => 2.0,
+            Block::WhiteConcrete
+            | Block::OrangeConcrete
+            | Block::MagentaConcrete
+            | Block::LightBlueConcrete
+            | Block::YellowConcrete
+            | Block::LimeConcrete
+            | Block::PinkConcrete
+            | Block::GrayConcrete
+            | Block::LightGrayConcrete
+            | Block::CyanConcrete
+            | Block::PurpleConcrete
+            | Block::BlueConcrete
+            | Block::BrownConcrete
+            | Block::GreenConcrete
+            | Block::RedConcrete
+            | Block::BlackConcrete => 1.8,
+            Block::Bookshelf => 1.5,
+            Block::WhiteGlazedTerracotta(_)
+            | Block::OrangeGlazedTerracotta(_)
+            | Block::MagentaGlazedTerracotta(_)
+            | Block::LightBlueGlazedTerracotta(_)
+            | Block::YellowGlazedTerracotta(_)
+            | Block::LimeGlazedTerracotta(_)
+            | Block::PinkGlazedTerracotta(_)
+            | Block::GrayGlazedTerracotta(_)
+            | Block::LightGrayGlazedTerracotta(_)
+            | Block::CyanGlazedTerracotta(_)
+            | Block::PurpleGlazedTerracotta(_)
+            | Block::BlueGlazedTerracotta(_)
+            | Block::BrownGlazedTerracotta(_)
+            | Block::GreenGlazedTerracotta(_)
+            | Block::RedGlazedTerracotta(_)
+            | Block::BlackGlazedTerracotta(_) => 1.4,
+            Block::Sign(_)
+            | Block::WallSign(_)
+            | Block::Pumpkin
+            | Block::CarvedPumpkin(_)
+            | Block::JackOLantern(_)
+            | Block::Melon
+            | Block::NetherWartBlock
+            | Block::SkeletonWallSkull(_)
+            | Block::SkeletonSkull(_)
+            | Block::WitherSkeletonWallSkull(_)
+            | Block::WitherSkeletonSkull(_)
+            | Block::ZombieWallHead(_)
+            | Block::ZombieHead(_)
+            | Block::PlayerWallHead(_)
+            | Block::PlayerHead(_)
+            | Block::CreeperWallHead(_)
+            | Block::CreeperHead(_)
+            | Block::DragonWallHead(_)
+            | Block::DragonHead(_)
+            | Block::WhiteBanner(_)
+            | Block::OrangeBanner(_)
+            | Block::MagentaBanner(_)
+            | Block::LightBlueBanner(_)
+            | Block::YellowBanner(_)
+            | Block::LimeBanner(_)
+            | Block::PinkBanner(_)
+            | Block::GrayBanner(_)
+            | Block::LightGrayBanner(_)
+            | Block::CyanBanner(_)
+            | Block::PurpleBanner(_)
+            | Block::BlueBanner(_)
+            | Block::BrownBanner(_)
+            | Block::GreenBanner(_)
+            | Block::RedBanner(_)
+            | Block::BlackBanner(_)
+            | Block::WhiteWallBanner(_)
+            | Block::OrangeWallBanner(_)
+            | Block::MagentaWallBanner(_)
+            | Block::LightBlueWallBanner(_)
+            | Block::YellowWallBanner(_)
+            | Block::LimeWallBanner(_)
+            | Block::PinkWallBanner(_)
+            | Block::GrayWallBanner(_)
+            | Block::LightGrayWallBanner(_)
+            | Block::CyanWallBanner(_)
+            | Block::PurpleWallBanner(_)
+            | Block::BlueWallBanner(_)
+            | Block::BrownWallBanner(_)
+            | Block::GreenWallBanner(_)
+            | Block::RedWallBanner(_)
+            | Block::BlackWallBanner(_) => 1.0,
+            Block::Sandstone
+            | Block::ChiseledSandstone
+            | Block::CutSandstone
+            | Block::SandstoneStairs(_)
+            | Block::RedSandstone
+            | Block::ChiseledRedSandstone
+            | Block::CutRedSandstone
+            | Block::RedSandstoneStairs(_)
+            | Block::QuartzBlock
+            | Block::ChiseledQuartzBlock
+            | Block::QuartzPillar(_)
+            | Block::QuartzStairs(_)
+            | Block::NoteBlock(_)
+            | Block::EndStoneBricks
+            | Block::WhiteWool
+            | Block::OrangeWool
+            | Block::MagentaWool
+            | Block::LightBlueWool
+            | Block::YellowWool
+            | Block::LimeWool
+            | Block::PinkWool
+            | Block::GrayWool
+            | Block::LightGrayWool
+            | Block::CyanWool
+            | Block::PurpleWool
+            | Block::BlueWool
+            | Block::BrownWool
+            | Block::GreenWool
+            | Block::RedWool
+            | Block::BlackWool => 0.8,
+            Block::InfestedStone
+            | Block::InfestedCobblestone
+            | Block::InfestedStoneBricks
+            | Block::InfestedMossyStoneBricks
+            | Block::InfestedCrackedStoneBricks
+            | Block::InfestedChiseledStoneBricks => 0.75,
+            Block::Rail(_)
+            | Block::PoweredRail(_)
+            | Block::DetectorRail(_)
+            | Block::ActivatorRail(_) => 0.7,
+            Block::GrassPath => 0.65,
+            Block::GrassBlock(_)
+            | Block::Farmland(_)
+            | Block::Gravel
+            | Block::Clay
+            | Block::Mycelium(_)
+            | Block::Sponge
+            | Block::WetSponge => 0.6,
+            Block::Dirt
+            | Block::CoarseDirt
+            | Block::Podzol(_)
+            | Block::Sand
+            | Block::RedSand
+            | Block::Ice
+            | Block::PackedIce
+            | Block::FrostedIce(_)
+            | Block::MagmaBlock
+            | Block::SoulSand
+            | Block::HayBlock(_)
+            | Block::Piston(_)
+            | Block::StickyPiston(_)
+            | Block::PistonHead(_)
+            | Block::Lever(_)
+            | Block::StoneButton(_)
+            | Block::StonePressurePlate(_)
+            | Block::LightWeightedPressurePlate(_)
+            | Block::HeavyWeightedPressurePlate(_)
+            | Block::Cake(_)
+            | Block::BrewingStand(_)
+            | Block::TurtleEgg(_)
+            | Block::OakButton(_)
+            | Block::SpruceButton(_)
+            | Block::BirchButton(_)
+            | Block::JungleButton(_)
+            | Block::AcaciaButton(_)
+            | Block::DarkOakButton(_)
+            | Block::OakPressurePlate(_)
+            | Block::SprucePressurePlate(_)
+            | Block::BirchPressurePlate(_)
+            | Block::JunglePressurePlate(_)
+            | Block::AcaciaPressurePlate(_)
+            | Block::DarkOakPressurePlate(_)
+            | Block::WhiteConcretePowder
+            | Block::OrangeConcretePowder
+            | Block::MagentaConcretePowder
+            | Block::LightBlueConcretePowder
+            | Block::YellowConcretePowder
+            | Block::LimeConcretePowder
+            | Block::PinkConcretePowder
+            | Block::GrayConcretePowder
+            | Block::LightGrayConcretePowder
+            | Block::CyanConcretePowder
+            | Block::PurpleConcretePowder
+            | Block::BlueConcretePowder
+            | Block::BrownConcretePowder
+            | Block::GreenConcretePowder
+            | Block::RedConcretePowder
+            | Block::BlackConcretePowder => 0.5,
+            Block::Netherrack
+            | Block::Cactus(_)
+            | Block::Ladder(_)
+            | Block::ChorusPlant(_)
+            | Block::ChorusFlower(_) => 0.4,
+            Block::Glass
+            | Block::GlassPane(_)
+            | Block::Glowstone
+            | Block::RedstoneLamp(_)
+            | Block::SeaLantern
+            | Block::WhiteStainedGlass
+            | Block::OrangeStainedGlass
+            | Block::MagentaStainedGlass
+            | Block::LightBlueStainedGlass
+            | Block::YellowStainedGlass
+            | Block::LimeStainedGlass
+            | Block::PinkStainedGlass
+            | Block::GrayStainedGlass
+            | Block::LightGrayStainedGlass
+            | Block::CyanStainedGlass
+            | Block::PurpleStainedGlass
+            | Block::BlueStainedGlass
+            | Block::BrownStainedGlass
+            | Block::GreenStainedGlass
+            | Block::RedStainedGlass
+            | Block::BlackStainedGlass
+            | Block::WhiteStainedGlassPane(_)
+            | Block::OrangeStainedGlassPane(_)
+            | Block::MagentaStainedGlassPane(_)
+            | Block::LightBlueStainedGlassPane(_)
+            | Block::YellowStainedGlassPane(_)
+            | Block::LimeStainedGlassPane(_)
+            | Block::PinkStainedGlassPane(_)
+            | Block::GrayStainedGlassPane(_)
+            | Block::LightGrayStainedGlassPane(_)
+            | Block::CyanStainedGlassPane(_)
+            | Block::PurpleStainedGlassPane(_)
+            | Block::BlueStainedGlassPane(_)
+            | Block::BrownStainedGlassPane(_)
+            | Block::GreenStainedGlassPane(_)
+            | Block::RedStainedGlassPane(_)
+            | Block::BlackStainedGlassPane(_) => 0.3,
+            Block::SnowBlock
+            | Block::Vine(_)
+            | Block::DaylightDetector(_)
+            | Block::BrownMushroomBlock(_)
+            | Block::RedMushroomBlock(_)
+            | Block::MushroomStem(_)
+            | Block::OakLeaves(_)
+            | Block::SpruceLeaves(_)
+            | Block::BirchLeaves(_)
+            | Block::JungleLeaves(_)
+            | Block::AcaciaLeaves(_)
+            | Block::DarkOakLeaves(_)
+            | Block::WhiteBed(_)
+            | Block::OrangeBed(_)
+            | Block::MagentaBed(_)
+            | Block::LightBlueBed(_)
+            | Block::YellowBed(_)
+            | Block::LimeBed(_)
+            | Block::PinkBed(_)
+            | Block::GrayBed(_)
+            | Block::LightGrayBed(_)
+            | Block::CyanBed(_)
+            | Block::PurpleBed(_)
+            | Block::BlueBed(_)
+            | Block::BrownBed(_)
+            | Block::GreenBed(_)
+            | Block::RedBed(_)
+            | Block::BlackBed(_) => 0.2,
+            Block::Snow(_)
+            | Block::WhiteCarpet
+            | Block::OrangeCarpet
+            | Block::MagentaCarpet
+            | Block::LightBlueCarpet
+            | Block::YellowCarpet
+            | Block::LimeCarpet
+            | Block::PinkCarpet
+            | Block::GrayCarpet
+            | Block::LightGrayCarpet
+            | Block::CyanCarpet
+            | Block::PurpleCarpet
+            | Block::BlueCarpet
+            | Block::BrownCarpet
+            | Block::GreenCarpet
+            | Block::RedCarpet
+            | Block::BlackCarpet => 0.1,
+            _ => 0.0,
+        }
+    }
+}
+
+impl Block {
+    /// Returns the identifier of this block's type,
+    /// e.g. `minecraft:oak_stairs`.
+    pub fn name(&self) -> &'static str {
+        self.to_name_and_props().0
+    }
+
+    /// Returns the value of the state property with the given
+    /// name, or `None` if this block has no such property.
+    pub fn property(&self, name: &str) -> Option<String> {
+        self.to_name_and_props()
+            .1
+            .into_iter()
+            .find(|(prop, _)| *prop == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns a copy of this block with the given state property
+    /// set to `value`. Returns `None` if this block has no such property
+    /// or if the value is not valid for it.
+    pub fn with_property(&self, name: &str, value: &str) -> Option<Self> {
+        let (block_name, props) = self.to_name_and_props();
+
+        let mut found = false;
+        let props: HashMap<String, String> = props
+            .into_iter()
+            .map(|(prop, old_value)| {
+                if prop == name {
+                    found = true;
+                    (prop.to_string(), value.to_string())
+                } else {
+                    (prop.to_string(), old_value)
+                }
+            })
+            .collect();
+
+        if !found {
+            return None;
+        }
+
+        let block = Block::from_name_and_props(block_name, &props)?;
+
+        // Integer properties are not range-checked when
+        // parsed, so make sure the state actually exists.
+        if Block::from_internal_state_id(block.internal_state_id()) == Some(block) {
+            Some(block)
+        } else {
+            None
+        }
+    }
 }
 
 /// Creates the internal ID -> native ID
@@ -295,7 +837,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::blocks::GrassBlockData;
+    use crate::blocks::{GrassBlockData, WaterData};
 
     #[test]
     fn test_native_state_id() {
@@ -314,6 +856,62 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_properties() {
+        let block = Block::GrassBlock(GrassBlockData { snowy: false });
+        assert_eq!(block.name(), "minecraft:grass_block");
+        assert_eq!(block.property("snowy"), Some(String::from("false")));
+        assert_eq!(block.property("facing"), None);
+
+        assert_eq!(
+            block.with_property("snowy", "true"),
+            Some(Block::GrassBlock(GrassBlockData { snowy: true }))
+        );
+        assert_eq!(block.with_property("snowy", "maybe"), None);
+        assert_eq!(block.with_property("facing", "north"), None);
+
+        let water = Block::Water(WaterData { level: 0 });
+        assert_eq!(
+            water.with_property("level", "15"),
+            Some(Block::Water(WaterData { level: 15 }))
+        );
+        assert_eq!(water.with_property("level", "16"), None);
+    }
+
+    #[test]
+    fn test_blast_resistance() {
+        assert_eq!(Block::Stone.blast_resistance(), 6.0);
+        assert_eq!(Block::Obsidian.blast_resistance(), 1200.0);
+        assert_eq!(Block::Bedrock.blast_resistance(), 3_600_000.0);
+        assert_eq!(Block::Air.blast_resistance(), 0.0);
+        assert_eq!(
+            Block::Water(WaterData { level: 0 }).blast_resistance(),
+            100.0
+        );
+        assert_eq!(
+            Block::from_name_and_default_props("minecraft:oak_planks")
+                .unwrap()
+                .blast_resistance(),
+            3.0
+        );
+        assert_eq!(Block::InfestedStone.blast_resistance(), 0.75);
+        assert_eq!(Block::WhiteTerracotta.blast_resistance(), 4.2);
+        assert_eq!(
+            Block::from_name_and_default_props("minecraft:white_glazed_terracotta")
+                .unwrap()
+                .blast_resistance(),
+            1.4
+        );
+        assert_eq!(
+            Block::from_name_and_default_props("minecraft:redstone_ore")
+                .unwrap()
+                .blast_resistance(),
+            3.0
+        );
+        assert_eq!(Block::RedstoneBlock.blast_resistance(), 6.0);
+        assert_eq!(Block::EndStone.blast_resistance(), 9.0);
+    }
+
     #[test]
     fn test_default_props() {
         assert_eq!(
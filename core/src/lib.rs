@@ -30,6 +30,7 @@ pub mod inventory;
 pub mod network;
 pub mod prelude;
 mod save;
+pub mod text;
 
 pub use biomes::Biome;
 pub use entitymeta::EntityMetadata;
@@ -38,6 +39,7 @@ pub use inventory::{ItemStack, Slot};
 pub use item::{Item, ItemExt};
 pub use network::packet::{implementation as packet, Packet, PacketType};
 pub use save::{entity, level, player_data, region};
+pub use text::TextComponent;
 pub use world::{
     block::{self, Block, BlockExt},
     chunk::{Chunk, ChunkSection},
@@ -100,23 +102,62 @@ impl Difficulty {
             Difficulty::Hard => 3,
         }
     }
+
+    pub fn from_id(id: u8) -> Self {
+        match id {
+            0 => Difficulty::Peaceful,
+            1 => Difficulty::Easy,
+            2 => Difficulty::Medium,
+            3 => Difficulty::Hard,
+            _ => Difficulty::Medium,
+        }
+    }
+
+    /// Parses a difficulty from its name, returning
+    /// `None` if the name is not a difficulty.
+    pub fn from_string(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "peaceful" => Some(Difficulty::Peaceful),
+            "easy" => Some(Difficulty::Easy),
+            "normal" | "medium" => Some(Difficulty::Medium),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Dimension {
     Nether,
-    Overwold,
+    Overworld,
     End,
 }
 
 impl Dimension {
+    /// All dimensions.
+    pub const ALL: [Dimension; 3] = [Dimension::Overworld, Dimension::Nether, Dimension::End];
+
     pub fn get_id(self) -> i32 {
         match self {
             Dimension::Nether => -1,
-            Dimension::Overwold => 0,
+            Dimension::Overworld => 0,
             Dimension::End => 1,
         }
     }
+
+    pub fn from_id(id: i32) -> Self {
+        match id {
+            -1 => Dimension::Nether,
+            1 => Dimension::End,
+            _ => Dimension::Overworld,
+        }
+    }
+}
+
+impl Default for Dimension {
+    fn default() -> Self {
+        Dimension::Overworld
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -15,6 +15,10 @@ pub trait McTypeWrite {
     ///
     /// Returns the number of bytes used to encode this integer.
     fn push_var_int(&mut self, x: i32) -> usize;
+    /// Writes a `VarLong` to the object.
+    ///
+    /// Returns the number of bytes used to encode this integer.
+    fn push_var_long(&mut self, x: i64) -> usize;
     /// Writes a string to the object. This method
     /// will first write the length of the string in bytes
     /// encodes as a `VarInt` and will then write
@@ -72,6 +76,26 @@ impl McTypeWrite for BytesMut {
         bytes_written
     }
 
+    fn push_var_long(&mut self, x: i64) -> usize {
+        // Shift as unsigned so negative numbers terminate.
+        let mut x = x as u64;
+        let mut bytes_written = 0;
+        loop {
+            let mut temp = (x & 0b0111_1111) as u8;
+            x >>= 7;
+            if x != 0 {
+                temp |= 0b1000_0000;
+            }
+            self.push_u8(temp);
+            bytes_written += 1;
+            if x == 0 {
+                break;
+            }
+        }
+
+        bytes_written
+    }
+
     /// Writes a string to the object. This method
     /// will first write the length of the string in bytes
     /// encodes as a `VarInt` and will then write
@@ -228,4 +252,19 @@ mod tests {
         buf.extend_from_slice(&[0xff, 0x01]);
         assert_eq!(Cursor::new(&buf).try_get_var_int(), Ok(255));
     }
+
+    #[test]
+    fn test_push_var_long() {
+        // Examples from wiki.vg
+        let mut buf = BytesMut::new();
+        assert_eq!(buf.push_var_long(2_147_483_647), 5);
+        assert_eq!(&buf[..], &[0xff, 0xff, 0xff, 0xff, 0x07]);
+
+        let mut buf = BytesMut::new();
+        assert_eq!(buf.push_var_long(-1), 10);
+        assert_eq!(
+            &buf[..],
+            &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]
+        );
+    }
 }
@@ -5,6 +5,7 @@ use crate::entitymeta::{EntityMetaIo, EntityMetadata};
 use crate::inventory::ItemStack;
 use crate::network::packet::PacketStage::Play;
 use crate::prelude::*;
+use crate::text::TextComponent;
 use crate::world::chunk::Chunk;
 use crate::{Biome, ClientboundAnimation, Hand};
 use bytes::{Buf, BufMut};
@@ -808,9 +809,35 @@ pub struct UseItem {
 }
 
 // CLIENTBOUND
-#[derive(Default, AsAny, new, Packet, Clone)]
+#[derive(Default, AsAny, new, Clone)]
 pub struct DisconnectLogin {
-    pub reason: String,
+    pub reason: TextComponent,
+}
+
+impl Packet for DisconnectLogin {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> Result<(), failure::Error> {
+        self.reason = serde_json::from_str(&buf.try_get_string()?)?;
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        buf.push_string(&self.reason.to_json());
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::DisconnectLogin
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::DisconnectLogin
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
 }
 
 #[derive(Default, AsAny, new, Clone)]
@@ -1199,15 +1226,290 @@ pub struct ServerDifficulty {
     pub difficulty: u8,
 }
 
-#[derive(Default, AsAny, new, Packet, Clone)]
+#[derive(Default, AsAny, new, Clone)]
 pub struct ChatMessageClientbound {
-    pub json_data: String,
+    pub message: TextComponent,
     pub position: u8,
 }
 
+impl Packet for ChatMessageClientbound {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> Result<(), failure::Error> {
+        self.message = serde_json::from_str(&buf.try_get_string()?)?;
+        self.position = buf.try_get_u8()?;
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        buf.push_string(&self.message.to_json());
+        buf.push_u8(self.position);
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::ChatMessageClientbound
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::ChatMessageClientbound
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
 // TODO MultiBlockChange
-// TODO TabCompleteClientbound
-// TODO DeclareCommands
+
+#[derive(Default, AsAny, new, Clone)]
+pub struct TabCompleteClientbound {
+    pub transaction_id: VarInt,
+    /// The start of the text to replace.
+    pub start: VarInt,
+    /// The length of the text to replace.
+    pub length: VarInt,
+    pub matches: Vec<TabCompleteMatch>,
+}
+
+impl Packet for TabCompleteClientbound {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> Result<(), failure::Error> {
+        unimplemented!()
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        buf.push_var_int(self.transaction_id);
+        buf.push_var_int(self.start);
+        buf.push_var_int(self.length);
+        buf.push_var_int(self.matches.len() as i32);
+
+        for m in &self.matches {
+            buf.push_string(&m.text);
+            buf.push_bool(m.tooltip.is_some());
+            if let Some(tooltip) = &m.tooltip {
+                buf.push_string(tooltip);
+            }
+        }
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::TabCompleteClientbound
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::TabCompleteClientbound
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TabCompleteMatch {
+    pub text: String,
+    /// JSON chat component shown when hovering over the match.
+    pub tooltip: Option<String>,
+}
+
+#[derive(Default, AsAny, new, Clone)]
+pub struct DeclareCommands {
+    pub nodes: Vec<CommandNode>,
+    /// The index of the root node in `nodes`.
+    pub root_index: VarInt,
+}
+
+impl Packet for DeclareCommands {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> Result<(), failure::Error> {
+        unimplemented!()
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        buf.push_var_int(self.nodes.len() as i32);
+        for node in &self.nodes {
+            node.write_to(buf);
+        }
+        buf.push_var_int(self.root_index);
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::DeclareCommands
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::DeclareCommands
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
+/// A node of the command graph sent in `DeclareCommands`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommandNode {
+    pub ty: CommandNodeType,
+    /// Whether the command is complete at this node.
+    pub executable: bool,
+    /// The indices of the node's children.
+    pub children: Vec<VarInt>,
+    /// The index of the node parsing continues at, if any.
+    pub redirect: Option<VarInt>,
+    /// The identifier of the suggestions provider
+    /// for an argument, such as `minecraft:ask_server`.
+    pub suggestions: Option<String>,
+}
+
+impl CommandNode {
+    fn write_to(&self, buf: &mut BytesMut) {
+        let mut flags = match self.ty {
+            CommandNodeType::Root => 0,
+            CommandNodeType::Literal(_) => 1,
+            CommandNodeType::Argument { .. } => 2,
+        };
+        if self.executable {
+            flags |= 0x04;
+        }
+        if self.redirect.is_some() {
+            flags |= 0x08;
+        }
+        if self.suggestions.is_some() {
+            flags |= 0x10;
+        }
+        buf.push_u8(flags);
+
+        buf.push_var_int(self.children.len() as i32);
+        for child in &self.children {
+            buf.push_var_int(*child);
+        }
+        if let Some(redirect) = self.redirect {
+            buf.push_var_int(redirect);
+        }
+
+        match &self.ty {
+            CommandNodeType::Root => (),
+            CommandNodeType::Literal(name) => buf.push_string(name),
+            CommandNodeType::Argument { name, parser } => {
+                buf.push_string(name);
+                buf.push_string(parser.identifier());
+                parser.write_properties(buf);
+            }
+        }
+
+        if let Some(suggestions) = &self.suggestions {
+            buf.push_string(suggestions);
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CommandNodeType {
+    Root,
+    Literal(String),
+    Argument { name: String, parser: CommandParser },
+}
+
+/// The parser clients use for an argument node.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CommandParser {
+    Bool,
+    Double { min: Option<f64>, max: Option<f64> },
+    Float { min: Option<f32>, max: Option<f32> },
+    Integer { min: Option<i32>, max: Option<i32> },
+    String(StringParserKind),
+    Entity { single: bool, players_only: bool },
+    GameProfile,
+    BlockPos,
+    Vec3,
+    BlockState,
+    ItemStack,
+    Message,
+}
+
+/// How much text a string argument consumes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StringParserKind {
+    /// A single word.
+    SingleWord,
+    /// A single word, or a phrase in quotes.
+    QuotablePhrase,
+    /// The rest of the command.
+    GreedyPhrase,
+}
+
+impl CommandParser {
+    pub fn identifier(self) -> &'static str {
+        match self {
+            CommandParser::Bool => "brigadier:bool",
+            CommandParser::Double { .. } => "brigadier:double",
+            CommandParser::Float { .. } => "brigadier:float",
+            CommandParser::Integer { .. } => "brigadier:integer",
+            CommandParser::String(_) => "brigadier:string",
+            CommandParser::Entity { .. } => "minecraft:entity",
+            CommandParser::GameProfile => "minecraft:game_profile",
+            CommandParser::BlockPos => "minecraft:block_pos",
+            CommandParser::Vec3 => "minecraft:vec3",
+            CommandParser::BlockState => "minecraft:block_state",
+            CommandParser::ItemStack => "minecraft:item_stack",
+            CommandParser::Message => "minecraft:message",
+        }
+    }
+
+    fn write_properties(self, buf: &mut BytesMut) {
+        fn range_flags<T>(min: Option<T>, max: Option<T>) -> u8 {
+            let mut flags = 0;
+            if min.is_some() {
+                flags |= 0x01;
+            }
+            if max.is_some() {
+                flags |= 0x02;
+            }
+            flags
+        }
+
+        match self {
+            CommandParser::Double { min, max } => {
+                buf.push_u8(range_flags(min, max));
+                min.into_iter().chain(max).for_each(|x| buf.push_f64(x));
+            }
+            CommandParser::Float { min, max } => {
+                buf.push_u8(range_flags(min, max));
+                min.into_iter().chain(max).for_each(|x| buf.push_f32(x));
+            }
+            CommandParser::Integer { min, max } => {
+                buf.push_u8(range_flags(min, max));
+                min.into_iter().chain(max).for_each(|x| buf.push_i32(x));
+            }
+            CommandParser::String(kind) => {
+                buf.push_var_int(match kind {
+                    StringParserKind::SingleWord => 0,
+                    StringParserKind::QuotablePhrase => 1,
+                    StringParserKind::GreedyPhrase => 2,
+                });
+            }
+            CommandParser::Entity {
+                single,
+                players_only,
+            } => {
+                let mut flags = 0;
+                if single {
+                    flags |= 0x01;
+                }
+                if players_only {
+                    flags |= 0x02;
+                }
+                buf.push_u8(flags);
+            }
+            _ => (),
+        }
+    }
+}
 
 #[derive(Default, AsAny, new, Packet, Clone)]
 pub struct ConfirmTransactionClientbound {
@@ -1324,9 +1626,35 @@ pub struct NamedSoundEffect {
     pub pitch: f32,
 }
 
-#[derive(Default, AsAny, new, Packet, Clone)]
+#[derive(Default, AsAny, new, Clone)]
 pub struct DisconnectPlay {
-    pub reason: String, // Chat
+    pub reason: TextComponent,
+}
+
+impl Packet for DisconnectPlay {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> Result<(), failure::Error> {
+        self.reason = serde_json::from_str(&buf.try_get_string()?)?;
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        buf.push_string(&self.reason.to_json());
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::DisconnectPlay
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::DisconnectPlay
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
 }
 
 #[derive(Default, AsAny, new, Packet, Clone)]
@@ -1686,8 +2014,7 @@ impl Packet for PlayerInfo {
 
                 buf.push_var_int(i32::from(gamemode.get_id()));
                 buf.push_var_int(*ping);
-                buf.push_bool(true);
-                buf.push_string(display_name);
+                push_display_name(buf, display_name);
             }
             PlayerInfoAction::UpdateGamemode(gamemode) => {
                 buf.push_var_int(i32::from(gamemode.get_id()));
@@ -1696,8 +2023,7 @@ impl Packet for PlayerInfo {
                 buf.push_var_int(*ping);
             }
             PlayerInfoAction::UpdateDisplayName(display_name) => {
-                buf.push_bool(true);
-                buf.push_string(&display_name);
+                push_display_name(buf, display_name);
             }
             PlayerInfoAction::RemovePlayer => (),
         }
@@ -1726,11 +2052,13 @@ pub enum PlayerInfoAction {
         Vec<(String, String, String)>,
         Gamemode,
         VarInt,
-        String,
+        Option<TextComponent>,
     ),
     UpdateGamemode(Gamemode),
     UpdateLatency(VarInt),
-    UpdateDisplayName(String),
+    /// The name shown in the player list
+    /// instead of the player's username.
+    UpdateDisplayName(Option<TextComponent>),
     RemovePlayer,
 }
 
@@ -1746,6 +2074,13 @@ impl PlayerInfoAction {
     }
 }
 
+fn push_display_name(buf: &mut BytesMut, display_name: &Option<TextComponent>) {
+    buf.push_bool(display_name.is_some());
+    if let Some(display_name) = display_name {
+        buf.push_string(&display_name.to_json());
+    }
+}
+
 // TODO Face Player
 
 #[derive(Default, AsAny, new, Packet, Clone)]
@@ -1874,8 +2209,160 @@ pub struct EntityEquipment {
     pub item: Slot,
 }
 
+#[derive(Default, AsAny, new, Packet, Clone)]
+pub struct UpdateHealth {
+    pub health: f32,
+    pub food: VarInt,
+    pub food_saturation: f32,
+}
+
+#[derive(Default, AsAny, new, Clone)]
+pub struct SetPassengers {
+    pub entity_id: VarInt,
+    pub passengers: Vec<VarInt>,
+}
+
+impl Packet for SetPassengers {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> Result<(), failure::Error> {
+        unimplemented!()
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        buf.push_var_int(self.entity_id);
+        buf.push_var_int(self.passengers.len() as i32);
+
+        for passenger in &self.passengers {
+            buf.push_var_int(*passenger);
+        }
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::SetPassengers
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::SetPassengers
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
 // TODO Select Advancement Tab
-// TODO World Border
+
+#[derive(Default, AsAny, new, Clone)]
+pub struct WorldBorder {
+    pub action: WorldBorderAction,
+}
+
+impl Packet for WorldBorder {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> Result<(), failure::Error> {
+        unimplemented!()
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        buf.push_var_int(self.action.id());
+
+        match self.action {
+            WorldBorderAction::SetSize(diameter) => {
+                buf.push_f64(diameter);
+            }
+            WorldBorderAction::LerpSize(old_diameter, new_diameter, speed) => {
+                buf.push_f64(old_diameter);
+                buf.push_f64(new_diameter);
+                buf.push_var_long(speed);
+            }
+            WorldBorderAction::SetCenter(x, z) => {
+                buf.push_f64(x);
+                buf.push_f64(z);
+            }
+            WorldBorderAction::Initialize {
+                x,
+                z,
+                old_diameter,
+                new_diameter,
+                speed,
+                portal_teleport_boundary,
+                warning_time,
+                warning_blocks,
+            } => {
+                buf.push_f64(x);
+                buf.push_f64(z);
+                buf.push_f64(old_diameter);
+                buf.push_f64(new_diameter);
+                buf.push_var_long(speed);
+                buf.push_var_int(portal_teleport_boundary);
+                buf.push_var_int(warning_time);
+                buf.push_var_int(warning_blocks);
+            }
+            WorldBorderAction::SetWarningTime(warning_time) => {
+                buf.push_var_int(warning_time);
+            }
+            WorldBorderAction::SetWarningBlocks(warning_blocks) => {
+                buf.push_var_int(warning_blocks);
+            }
+        }
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::WorldBorder
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::WorldBorder
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WorldBorderAction {
+    SetSize(f64),
+    /// Old diameter, new diameter and the
+    /// duration of the resize in milliseconds.
+    LerpSize(f64, f64, i64),
+    SetCenter(f64, f64),
+    Initialize {
+        x: f64,
+        z: f64,
+        old_diameter: f64,
+        new_diameter: f64,
+        speed: i64,
+        portal_teleport_boundary: VarInt,
+        warning_time: VarInt,
+        warning_blocks: VarInt,
+    },
+    SetWarningTime(VarInt),
+    SetWarningBlocks(VarInt),
+}
+
+impl Default for WorldBorderAction {
+    fn default() -> Self {
+        WorldBorderAction::SetSize(0.0)
+    }
+}
+
+impl WorldBorderAction {
+    fn id(&self) -> VarInt {
+        match self {
+            WorldBorderAction::SetSize(_) => 0,
+            WorldBorderAction::LerpSize(_, _, _) => 1,
+            WorldBorderAction::SetCenter(_, _) => 2,
+            WorldBorderAction::Initialize { .. } => 3,
+            WorldBorderAction::SetWarningTime(_) => 4,
+            WorldBorderAction::SetWarningBlocks(_) => 5,
+        }
+    }
+}
 
 #[derive(Default, AsAny, new, Packet, Clone)]
 pub struct SpawnPosition {
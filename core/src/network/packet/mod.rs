@@ -457,6 +457,11 @@ lazy_static! {
             PacketType::SpawnObject,
         );
 
+        m.insert(
+            PacketId(0x02, PacketDirection::Clientbound, PacketStage::Play),
+            PacketType::SpawnGlobalEntity,
+        );
+
         m.insert(
             PacketId(0x03, PacketDirection::Clientbound, PacketStage::Play),
             PacketType::SpawnMob,
@@ -472,6 +477,14 @@ lazy_static! {
             PacketType::ChatMessageClientbound,
         );
 
+        m.insert(
+            PacketId(0x10, PacketDirection::Clientbound, PacketStage::Play),
+            PacketType::TabCompleteClientbound,
+        );
+        m.insert(
+            PacketId(0x11, PacketDirection::Clientbound, PacketStage::Play),
+            PacketType::DeclareCommands,
+        );
         m.insert(
             PacketId(0x17, PacketDirection::Clientbound, PacketStage::Play),
             PacketType::SetSlot,
@@ -482,11 +495,26 @@ lazy_static! {
             PacketType::DisconnectPlay,
         );
 
+        m.insert(
+            PacketId(0x1C, PacketDirection::Clientbound, PacketStage::Play),
+            PacketType::EntityStatus,
+        );
+
+        m.insert(
+            PacketId(0x1E, PacketDirection::Clientbound, PacketStage::Play),
+            PacketType::Explosion,
+        );
+
         m.insert(
             PacketId(0x1F, PacketDirection::Clientbound, PacketStage::Play),
             PacketType::UnloadChunk,
         );
 
+        m.insert(
+            PacketId(0x20, PacketDirection::Clientbound, PacketStage::Play),
+            PacketType::ChangeGameState,
+        );
+
         m.insert(
             PacketId(0x21, PacketDirection::Clientbound, PacketStage::Play),
             PacketType::KeepAliveClientbound,
@@ -502,6 +530,11 @@ lazy_static! {
             PacketType::BlockChange,
         );
 
+        m.insert(
+            PacketId(0x0D, PacketDirection::Clientbound, PacketStage::Play),
+            PacketType::ServerDifficulty,
+        );
+
         m.insert(
             PacketId(0x22, PacketDirection::Clientbound, PacketStage::Play),
             PacketType::ChunkData,
@@ -527,6 +560,11 @@ lazy_static! {
             PacketType::EntityLook,
         );
 
+        m.insert(
+            PacketId(0x2B, PacketDirection::Clientbound, PacketStage::Play),
+            PacketType::VehicleMoveClientbound,
+        );
+
         m.insert(
             PacketId(0x30, PacketDirection::Clientbound, PacketStage::Play),
             PacketType::PlayerInfo,
@@ -537,6 +575,11 @@ lazy_static! {
             PacketType::PlayerPositionAndLookClientbound,
         );
 
+        m.insert(
+            PacketId(0x33, PacketDirection::Clientbound, PacketStage::Play),
+            PacketType::UseBed,
+        );
+
         m.insert(
             PacketId(0x35, PacketDirection::Clientbound, PacketStage::Play),
             PacketType::DestroyEntities,
@@ -547,11 +590,21 @@ lazy_static! {
             PacketType::ResourcePackSend,
         );
 
+        m.insert(
+            PacketId(0x38, PacketDirection::Clientbound, PacketStage::Play),
+            PacketType::Respawn,
+        );
+
         m.insert(
             PacketId(0x39, PacketDirection::Clientbound, PacketStage::Play),
             PacketType::EntityHeadLook,
         );
 
+        m.insert(
+            PacketId(0x3B, PacketDirection::Clientbound, PacketStage::Play),
+            PacketType::WorldBorder,
+        );
+
         m.insert(
             PacketId(0x3F, PacketDirection::Clientbound, PacketStage::Play),
             PacketType::EntityMetadata,
@@ -567,6 +620,16 @@ lazy_static! {
             PacketType::EntityEquipment,
         );
 
+        m.insert(
+            PacketId(0x44, PacketDirection::Clientbound, PacketStage::Play),
+            PacketType::UpdateHealth,
+        );
+
+        m.insert(
+            PacketId(0x46, PacketDirection::Clientbound, PacketStage::Play),
+            PacketType::SetPassengers,
+        );
+
         m.insert(
             PacketId(0x49, PacketDirection::Clientbound, PacketStage::Play),
             PacketType::SpawnPosition,
@@ -9,6 +9,12 @@ pub enum EntityData {
     Item(ItemEntityData),
     #[serde(rename = "minecraft:arrow")]
     Arrow(ArrowEntityData),
+    #[serde(rename = "minecraft:tnt")]
+    PrimedTnt(PrimedTntEntityData),
+    #[serde(rename = "minecraft:boat")]
+    Boat(BoatEntityData),
+    #[serde(rename = "minecraft:minecart")]
+    Minecart(MinecartEntityData),
     #[serde(rename = "minecraft:cow")]
     Cow(AnimalData),
     #[serde(rename = "minecraft:pig")]
@@ -45,6 +51,9 @@ impl EntityData {
                 match self {
                     EntityData::Item(_) => "minecraft:item",
                     EntityData::Arrow(_) => "minecraft:arrow",
+                    EntityData::PrimedTnt(_) => "minecraft:tnt",
+                    EntityData::Boat(_) => "minecraft:boat",
+                    EntityData::Minecart(_) => "minecraft:minecart",
                     EntityData::Cow(_) => "minecraft:cow",
                     EntityData::Pig(_) => "minecraft:pig",
                     EntityData::Chicken(_) => "minecraft:chicken",
@@ -64,6 +73,9 @@ impl EntityData {
         match self {
             EntityData::Item(data) => data.write_to_map(&mut map),
             EntityData::Arrow(data) => data.write_to_map(&mut map),
+            EntityData::PrimedTnt(data) => data.write_to_map(&mut map),
+            EntityData::Boat(data) => data.write_to_map(&mut map),
+            EntityData::Minecart(data) => data.write_to_map(&mut map),
             EntityData::Cow(data) => data.write_to_map(&mut map),
             EntityData::Pig(data) => data.write_to_map(&mut map),
             EntityData::Chicken(data) => data.write_to_map(&mut map),
@@ -241,6 +253,23 @@ pub struct ArrowEntityData {
     // See: https://github.com/PistonDevelopers/hematite_nbt/issues/43
     #[serde(rename = "crit")]
     pub critical: u8,
+    /// Who may pick up the arrow: 0 for nobody,
+    /// 1 for survival players and 2 for creative players.
+    #[serde(rename = "pickup", default)]
+    pub pickup: u8,
+    #[serde(rename = "inGround", default)]
+    pub in_ground: u8,
+    /// The number of ticks the arrow has been stuck in the ground.
+    #[serde(rename = "life", default)]
+    pub life: i16,
+    #[serde(rename = "damage", default)]
+    pub damage: f64,
+    #[serde(rename = "xTile", default)]
+    pub tile_x: i32,
+    #[serde(rename = "yTile", default)]
+    pub tile_y: i32,
+    #[serde(rename = "zTile", default)]
+    pub tile_z: i32,
 }
 
 impl ArrowEntityData {
@@ -248,6 +277,69 @@ impl ArrowEntityData {
         self.entity.write_to_map(map);
 
         map.insert(String::from("crit"), Value::Byte(self.critical as i8));
+        map.insert(String::from("pickup"), Value::Byte(self.pickup as i8));
+        map.insert(String::from("inGround"), Value::Byte(self.in_ground as i8));
+        map.insert(String::from("life"), Value::Short(self.life));
+        map.insert(String::from("damage"), Value::Double(self.damage));
+        map.insert(String::from("xTile"), Value::Int(self.tile_x));
+        map.insert(String::from("yTile"), Value::Int(self.tile_y));
+        map.insert(String::from("zTile"), Value::Int(self.tile_z));
+    }
+}
+
+/// Data for a primed TNT entity (`minecraft:tnt`).
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct PrimedTntEntityData {
+    // Inherit base entity data
+    #[serde(flatten)]
+    pub entity: BaseEntityData,
+
+    // Primed TNT-specific tags
+    /// The number of ticks until the TNT explodes.
+    #[serde(rename = "Fuse")]
+    pub fuse: i16,
+}
+
+impl PrimedTntEntityData {
+    fn write_to_map(self, map: &mut HashMap<String, Value>) {
+        self.entity.write_to_map(map);
+
+        map.insert(String::from("Fuse"), Value::Short(self.fuse));
+    }
+}
+
+/// Data for a boat entity (`minecraft:boat`).
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct BoatEntityData {
+    // Inherit base entity data
+    #[serde(flatten)]
+    pub entity: BaseEntityData,
+
+    // Boat-specific tags
+    /// The wood type of the boat, such as `oak` or `dark_oak`.
+    #[serde(rename = "Type", default)]
+    pub boat_type: String,
+}
+
+impl BoatEntityData {
+    fn write_to_map(self, map: &mut HashMap<String, Value>) {
+        self.entity.write_to_map(map);
+
+        map.insert(String::from("Type"), Value::String(self.boat_type));
+    }
+}
+
+/// Data for a rideable minecart entity (`minecraft:minecart`).
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct MinecartEntityData {
+    // Inherit base entity data
+    #[serde(flatten)]
+    pub entity: BaseEntityData,
+}
+
+impl MinecartEntityData {
+    fn write_to_map(self, map: &mut HashMap<String, Value>) {
+        self.entity.write_to_map(map);
     }
 }
 
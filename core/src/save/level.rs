@@ -34,6 +34,18 @@ pub struct LevelData {
     pub border_safe_zone: f64,
     #[serde(rename = "BorderSize")]
     pub border_size: f64,
+    #[serde(default)]
+    #[serde(rename = "BorderSizeLerpTarget")]
+    pub border_size_lerp_target: f64,
+    #[serde(default)]
+    #[serde(rename = "BorderSizeLerpTime")]
+    pub border_size_lerp_time: i64,
+    #[serde(default)]
+    #[serde(rename = "BorderWarningBlocks")]
+    pub border_warning_blocks: f64,
+    #[serde(default)]
+    #[serde(rename = "BorderWarningTime")]
+    pub border_warning_time: f64,
 
     #[serde(rename = "clearWeatherTime")]
     pub clear_weather_time: i32,
@@ -47,6 +59,9 @@ pub struct LevelData {
     pub difficulty_locked: i8,
     #[serde(rename = "GameType")]
     pub game_type: i32,
+    #[serde(rename = "GameRules")]
+    #[serde(default)]
+    pub game_rules: HashMap<String, String>,
 
     pub hardcore: bool,
 
@@ -143,12 +158,27 @@ pub enum LevelGeneratorType {
     Debug,
 }
 
+impl LevelGeneratorType {
+    /// Returns the level type sent to clients in the
+    /// Join Game and Respawn packets.
+    pub fn level_type(&self) -> &'static str {
+        match self {
+            LevelGeneratorType::Default => "default",
+            LevelGeneratorType::Flat => "flat",
+            LevelGeneratorType::LargeBiomes => "largeBiomes",
+            LevelGeneratorType::Amplified => "amplified",
+            LevelGeneratorType::Buffet => "buffet",
+            LevelGeneratorType::Debug => "debug_all_block_states",
+        }
+    }
+}
+
 impl LevelData {
     pub fn generator_type(&self) -> LevelGeneratorType {
         match self.generator_name.to_lowercase().as_str() {
             "default" => LevelGeneratorType::Default,
             "flat" => LevelGeneratorType::Flat,
-            "largeBiomes" => LevelGeneratorType::LargeBiomes,
+            "largebiomes" => LevelGeneratorType::LargeBiomes,
             "amplified" => LevelGeneratorType::Amplified,
             "buffet" => LevelGeneratorType::Buffet,
             "debug_all_block_states" => LevelGeneratorType::Debug,
@@ -190,6 +220,8 @@ mod tests {
         assert_eq!(level.difficulty, 1);
         assert_eq!(level.difficulty_locked, 0);
         assert_eq!(level.game_type, 0);
+        assert_eq!(level.game_rules["doFireTick"], "true");
+        assert_eq!(level.game_rules["maxCommandChainLength"], "65536");
         assert!(!level.hardcore);
         assert!(level.initialized);
         assert_eq!(level.last_played, 1_560_968_104_655);
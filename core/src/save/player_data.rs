@@ -25,6 +25,21 @@ pub struct PlayerData {
     pub gamemode: i32,
     #[serde(rename = "Inventory")]
     pub inventory: Vec<InventorySlot>,
+    #[serde(rename = "Dimension", default)]
+    pub dimension: i32,
+    // Name of the world the player is in, if the server has several
+    #[serde(rename = "WorldName", skip_serializing_if = "Option::is_none", default)]
+    pub world: Option<String>,
+
+    // Spawn point set by sleeping in a bed
+    #[serde(rename = "SpawnX", skip_serializing_if = "Option::is_none", default)]
+    pub spawn_x: Option<i32>,
+    #[serde(rename = "SpawnY", skip_serializing_if = "Option::is_none", default)]
+    pub spawn_y: Option<i32>,
+    #[serde(rename = "SpawnZ", skip_serializing_if = "Option::is_none", default)]
+    pub spawn_z: Option<i32>,
+    #[serde(rename = "SpawnForced", default)]
+    pub spawn_forced: bool,
 }
 
 /// Represents a single inventory slot (including position index).
@@ -96,7 +111,7 @@ impl InventorySlot {
 
 async fn load_from_file<R: AsyncRead + Unpin>(mut reader: R) -> Result<PlayerData, nbt::Error> {
     let mut buf = vec![];
-    reader.read(&mut buf).await?;
+    reader.read_to_end(&mut buf).await?;
     nbt::from_gzip_reader(buf.as_slice())
 }
 
@@ -118,7 +133,9 @@ pub fn save_player_data(world_dir: &Path, uuid: Uuid, data: PlayerData) -> Resul
     save_to_file(file, data)
 }
 
-fn file_path(world_dir: &Path, uuid: Uuid) -> PathBuf {
+/// Returns the path of the data file of the player with
+/// the given UUID within `world_dir`.
+pub fn file_path(world_dir: &Path, uuid: Uuid) -> PathBuf {
     world_dir.join("playerdata").join(format!("{}.dat", uuid))
 }
 
@@ -135,6 +152,46 @@ mod tests {
 
         let player = load_from_file(cursor).unwrap();
         assert_eq!(player.gamemode, i32::from(Gamemode::Creative.get_id()));
+        assert_eq!(player.dimension, 0);
+    }
+
+    #[test]
+    fn test_spawn_point_round_trip() {
+        let data = PlayerData {
+            spawn_x: Some(10),
+            spawn_y: Some(64),
+            spawn_z: Some(-20),
+            spawn_forced: true,
+            ..Default::default()
+        };
+
+        let mut buf = vec![];
+        save_to_file(&mut buf, data).unwrap();
+        let data: PlayerData = nbt::from_gzip_reader(buf.as_slice()).unwrap();
+        assert_eq!(
+            (data.spawn_x, data.spawn_y, data.spawn_z),
+            (Some(10), Some(64), Some(-20))
+        );
+        assert!(data.spawn_forced);
+
+        let mut buf = vec![];
+        save_to_file(&mut buf, PlayerData::default()).unwrap();
+        let data: PlayerData = nbt::from_gzip_reader(buf.as_slice()).unwrap();
+        assert_eq!(data.spawn_x, None);
+        assert!(!data.spawn_forced);
+    }
+
+    #[test]
+    fn test_world_name_round_trip() {
+        let data = PlayerData {
+            world: Some(String::from("creative")),
+            ..Default::default()
+        };
+
+        let mut buf = vec![];
+        save_to_file(&mut buf, data).unwrap();
+        let data: PlayerData = nbt::from_gzip_reader(buf.as_slice()).unwrap();
+        assert_eq!(data.world.as_ref().map(String::as_str), Some("creative"));
     }
 
     #[test]
@@ -2,7 +2,7 @@
 //! This is required due to https://github.com/PistonDevelopers/hematite_nbt/issues/27.
 
 use super::ChunkLevel;
-use super::{ChunkRoot, LevelSection};
+use super::{ChunkRoot, LevelSection, LevelTileTick};
 use nbt::{Blob, Value};
 use std::collections::HashMap;
 
@@ -49,17 +49,21 @@ fn level_to_value(level: ChunkLevel) -> Value {
     map.insert(String::from("Sections"), Value::List(sections));
 
     map.insert(String::from("TileEntities"), Value::List(vec![])); // TODO
-    map.insert(String::from("ToBeTicked"), Value::List(vec![])); // TODO
+    map.insert(
+        String::from("ToBeTicked"),
+        to_be_ticked_to_value(level.to_be_ticked),
+    );
 
-    let mut liquids_to_be_ticked = vec![];
-    (0..16).for_each(|_| liquids_to_be_ticked.push(Value::List(vec![])));
     map.insert(
         String::from("LiquidsToBeTicked"),
-        Value::List(liquids_to_be_ticked),
+        to_be_ticked_to_value(level.liquids_to_be_ticked),
     );
 
-    let mut tile_ticks = vec![];
-    (0..16).for_each(|_| tile_ticks.push(Value::List(vec![])));
+    let tile_ticks = level
+        .tile_ticks
+        .into_iter()
+        .map(tile_tick_to_value)
+        .collect();
     map.insert(String::from("TileTicks"), Value::List(tile_ticks));
 
     let mut post_processing = vec![];
@@ -98,6 +102,28 @@ fn level_to_value(level: ChunkLevel) -> Value {
     Value::Compound(map)
 }
 
+fn to_be_ticked_to_value(sections: Vec<Vec<i16>>) -> Value {
+    Value::List(
+        sections
+            .into_iter()
+            .map(|section| Value::List(section.into_iter().map(Value::Short).collect()))
+            .collect(),
+    )
+}
+
+fn tile_tick_to_value(tick: LevelTileTick) -> Value {
+    let mut map = HashMap::new();
+
+    map.insert(String::from("i"), Value::String(tick.block));
+    map.insert(String::from("x"), Value::Int(tick.x));
+    map.insert(String::from("y"), Value::Int(tick.y));
+    map.insert(String::from("z"), Value::Int(tick.z));
+    map.insert(String::from("t"), Value::Int(tick.delay));
+    map.insert(String::from("p"), Value::Int(tick.priority));
+
+    Value::Compound(map)
+}
+
 fn section_to_value(section: LevelSection) -> Value {
     let mut map = HashMap::new();
 
@@ -158,6 +184,16 @@ mod tests {
                 }],
                 biomes: vec![10],
                 entities: vec![],
+                liquids_to_be_ticked: vec![vec![], vec![17, 273]],
+                to_be_ticked: vec![vec![]; 16],
+                tile_ticks: vec![LevelTileTick {
+                    block: String::from("minecraft:repeater"),
+                    x: 1,
+                    y: 64,
+                    z: 2,
+                    delay: 2,
+                    priority: -1,
+                }],
             },
         };
 
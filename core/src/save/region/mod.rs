@@ -15,7 +15,7 @@ use serde::Deserialize;
 use crate::save::entity::EntityData;
 use crate::world::block::*;
 use crate::world::chunk::{BitArray, Chunk, ChunkSection};
-use crate::world::ChunkPosition;
+use crate::world::{BlockPosition, ChunkPosition};
 use crate::Biome;
 use bitvec::bitvec;
 use bitvec::vec::BitVec;
@@ -56,6 +56,60 @@ pub struct ChunkLevel {
     biomes: Vec<i32>,
     #[serde(rename = "Entities")]
     entities: Vec<EntityData>,
+    /// Fluid ticks pending for this chunk, with one list
+    /// of packed section-local positions per chunk section.
+    #[serde(rename = "LiquidsToBeTicked", default)]
+    liquids_to_be_ticked: Vec<Vec<i16>>,
+    /// Block positions which need to be ticked as soon
+    /// as the chunk is loaded, in the same format as
+    /// `liquids_to_be_ticked`.
+    #[serde(rename = "ToBeTicked", default)]
+    to_be_ticked: Vec<Vec<i16>>,
+    /// Scheduled block ticks pending for this chunk.
+    #[serde(rename = "TileTicks", default)]
+    tile_ticks: Vec<LevelTileTick>,
+}
+
+/// Represents a scheduled block tick in a region file.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LevelTileTick {
+    /// Identifier of the block the tick is for.
+    #[serde(rename = "i")]
+    block: String,
+    x: i32,
+    y: i32,
+    z: i32,
+    /// Number of ticks remaining until the tick runs.
+    #[serde(rename = "t")]
+    delay: i32,
+    /// Priority of the tick. Lower values run first.
+    #[serde(rename = "p")]
+    priority: i32,
+}
+
+/// Ticks which were pending for a chunk when it was saved.
+///
+/// These should be rescheduled once the chunk is loaded again.
+#[derive(Debug, Clone, Default)]
+pub struct ChunkTicks {
+    /// Positions of fluid blocks which are awaiting a tick.
+    pub liquids: Vec<BlockPosition>,
+    /// Scheduled block ticks.
+    pub blocks: Vec<PendingBlockTick>,
+}
+
+/// A scheduled block tick which was pending when its chunk was saved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingBlockTick {
+    /// The position of the block to tick.
+    pub pos: BlockPosition,
+    /// Identifier of the block the tick is for,
+    /// e.g. `minecraft:repeater`.
+    pub block: String,
+    /// Number of ticks remaining until the tick runs.
+    pub delay: i32,
+    /// Priority of the tick. Lower values run first.
+    pub priority: i32,
 }
 
 /// Represents a chunk section in a region file.
@@ -122,7 +176,7 @@ impl RegionHandle {
     pub fn load_chunk(
         &mut self,
         mut pos: ChunkPosition,
-    ) -> Result<(Chunk, Vec<EntityData>), Error> {
+    ) -> Result<(Chunk, Vec<EntityData>, ChunkTicks), Error> {
         // Get a copy of the original position before clipping
         let original_pos = pos;
         // Clip chunk position to region-local coordinates.
@@ -203,7 +257,41 @@ impl RegionHandle {
         // Chunk was not modified, but it thinks it was: disable this
         chunk.check_modified();
 
-        Ok((chunk, level.entities.to_vec()))
+        // Blocks in `ToBeTicked` have no stored type or delay,
+        // so they are ticked immediately as whatever block is there.
+        let to_be_ticked = unpack_to_be_ticked(original_pos, &level.to_be_ticked)
+            .into_iter()
+            .map(|pos| PendingBlockTick {
+                pos,
+                block: chunk
+                    .block_at(
+                        (pos.x - original_pos.x * 16) as usize,
+                        pos.y as usize,
+                        (pos.z - original_pos.z * 16) as usize,
+                    )
+                    .to_name_and_props()
+                    .0
+                    .to_string(),
+                delay: 0,
+                priority: 0,
+            });
+
+        let ticks = ChunkTicks {
+            liquids: unpack_to_be_ticked(original_pos, &level.liquids_to_be_ticked),
+            blocks: level
+                .tile_ticks
+                .iter()
+                .map(|tick| PendingBlockTick {
+                    pos: BlockPosition::new(tick.x, tick.y, tick.z),
+                    block: tick.block.clone(),
+                    delay: tick.delay,
+                    priority: tick.priority,
+                })
+                .chain(to_be_ticked)
+                .collect(),
+        };
+
+        Ok((chunk, level.entities.to_vec(), ticks))
     }
 
     /// Saves the given chunk to this region file. The header will be updated
@@ -211,7 +299,12 @@ impl RegionHandle {
     ///
     /// Behavior may be unexpected if this region file does not contain the given
     /// chunk position.
-    pub fn save_chunk(&mut self, chunk: &Chunk, entities: Vec<EntityData>) -> Result<(), Error> {
+    pub fn save_chunk(
+        &mut self,
+        chunk: &Chunk,
+        entities: Vec<EntityData>,
+        ticks: ChunkTicks,
+    ) -> Result<(), Error> {
         let chunk_pos = chunk.position();
 
         let (local_x, local_z) = (chunk_pos.x % 32, chunk_pos.z % 32);
@@ -225,7 +318,7 @@ impl RegionHandle {
         }
 
         // Write chunk to `ChunkRoot` tag.
-        let root = chunk_to_chunk_root(chunk, entities);
+        let root = chunk_to_chunk_root(chunk, entities, ticks);
 
         let blob = blob::chunk_root_to_blob(root);
 
@@ -346,7 +439,7 @@ fn read_section_into_chunk(section: &LevelSection, chunk: &mut Chunk) -> Result<
     Ok(())
 }
 
-fn chunk_to_chunk_root(chunk: &Chunk, entities: Vec<EntityData>) -> ChunkRoot {
+fn chunk_to_chunk_root(chunk: &Chunk, entities: Vec<EntityData>, ticks: ChunkTicks) -> ChunkRoot {
     ChunkRoot {
         level: ChunkLevel {
             x_pos: chunk.position().x,
@@ -373,11 +466,63 @@ fn chunk_to_chunk_root(chunk: &Chunk, entities: Vec<EntityData>) -> ChunkRoot {
                 .map(|biome| biome.protocol_id())
                 .collect(),
             entities,
+            liquids_to_be_ticked: pack_to_be_ticked(&ticks.liquids),
+            to_be_ticked: vec![vec![]; 16],
+            tile_ticks: ticks
+                .blocks
+                .into_iter()
+                .map(|tick| LevelTileTick {
+                    block: tick.block,
+                    x: tick.pos.x,
+                    y: tick.pos.y,
+                    z: tick.pos.z,
+                    delay: tick.delay,
+                    priority: tick.priority,
+                })
+                .collect(),
         },
         data_version: DATA_VERSION,
     }
 }
 
+/// Packs block positions into per-section lists of
+/// section-local offsets, as used by the `*ToBeTicked` tags.
+///
+/// Each offset is packed as `x | y << 4 | z << 8`.
+fn pack_to_be_ticked(positions: &[BlockPosition]) -> Vec<Vec<i16>> {
+    let mut sections = vec![vec![]; 16];
+
+    for pos in positions {
+        let section = (pos.y >> 4) as usize;
+        if section >= 16 {
+            continue;
+        }
+
+        let packed = (pos.x & 15) | ((pos.y & 15) << 4) | ((pos.z & 15) << 8);
+        sections[section].push(packed as i16);
+    }
+
+    sections
+}
+
+/// The inverse of `pack_to_be_ticked`.
+fn unpack_to_be_ticked(chunk: ChunkPosition, sections: &[Vec<i16>]) -> Vec<BlockPosition> {
+    sections
+        .iter()
+        .enumerate()
+        .flat_map(|(section, offsets)| {
+            offsets.iter().map(move |packed| {
+                let packed = i32::from(*packed);
+                BlockPosition::new(
+                    chunk.x * 16 + (packed & 15),
+                    section as i32 * 16 + ((packed >> 4) & 15),
+                    chunk.z * 16 + ((packed >> 8) & 15),
+                )
+            })
+        })
+        .collect()
+}
+
 fn convert_palette(section: &mut ChunkSection) -> Vec<LevelPaletteEntry> {
     section.convert_palette_to_section();
     raw_palette_to_palette_entries(section.palette().unwrap())
@@ -840,4 +985,20 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_to_be_ticked_roundtrip() {
+        let chunk = ChunkPosition::new(-2, 3);
+        let positions = vec![
+            BlockPosition::new(-32, 0, 48),
+            BlockPosition::new(-17, 64, 63),
+            BlockPosition::new(-20, 255, 50),
+        ];
+
+        let packed = pack_to_be_ticked(&positions);
+        assert_eq!(packed.len(), 16);
+        assert_eq!(packed[4].len(), 1);
+
+        assert_eq!(unpack_to_be_ticked(chunk, &packed), positions);
+    }
 }
@@ -0,0 +1,606 @@
+//! Chat components, the JSON text format used by chat
+//! messages, disconnect reasons, display names and the MOTD.
+//!
+//! A component has some content, such as plain text or a
+//! translation key, a style, and children which inherit
+//! its style. Components are built with the builder methods
+//! on `TextComponent`, or converted from text using legacy
+//! `§` formatting codes.
+
+use std::mem;
+
+/// The character beginning a legacy formatting code.
+pub const LEGACY_PREFIX: char = '§';
+
+/// Removes each `§` and the formatting code following it from `text`.
+pub fn strip_legacy(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == LEGACY_PREFIX {
+            chars.next();
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+/// A chat component.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "Repr")]
+pub struct TextComponent {
+    #[serde(flatten)]
+    pub content: Content,
+    #[serde(flatten)]
+    pub style: Style,
+    /// Children appended after this component's content.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub extra: Vec<TextComponent>,
+}
+
+/// What a component displays.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Content {
+    Text {
+        text: String,
+    },
+    /// A translation key, looked up by the client, with
+    /// components substituted for its placeholders.
+    Translate {
+        translate: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        with: Vec<TextComponent>,
+    },
+    Score {
+        score: Score,
+    },
+    /// An entity selector, replaced by the names of the entities it selects.
+    Selector {
+        selector: String,
+    },
+    /// A key binding, replaced by the key the client has bound to it.
+    Keybind {
+        keybind: String,
+    },
+}
+
+impl Default for Content {
+    fn default() -> Self {
+        Content::Text {
+            text: String::new(),
+        }
+    }
+}
+
+/// The score of an entity in a scoreboard objective.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Score {
+    /// The name of the entity, or a selector.
+    pub name: String,
+    pub objective: String,
+    /// The score to display instead of looking it up.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+}
+
+/// The style of a component. Unset properties
+/// are inherited from the parent component.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Style {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<Color>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bold: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub italic: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub underlined: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strikethrough: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub obfuscated: Option<bool>,
+    /// Text inserted into the chat box when the component is shift-clicked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub insertion: Option<String>,
+    #[serde(rename = "clickEvent", skip_serializing_if = "Option::is_none")]
+    pub click_event: Option<ClickEvent>,
+    #[serde(rename = "hoverEvent", skip_serializing_if = "Option::is_none")]
+    pub hover_event: Option<HoverEvent>,
+}
+
+/// A chat color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Color {
+    Black,
+    DarkBlue,
+    DarkGreen,
+    DarkAqua,
+    DarkRed,
+    DarkPurple,
+    Gold,
+    Gray,
+    DarkGray,
+    Blue,
+    Green,
+    Aqua,
+    Red,
+    LightPurple,
+    Yellow,
+    White,
+    /// Resets the color to the default of where the text is shown.
+    Reset,
+}
+
+impl Color {
+    /// The colors in the order of their legacy codes.
+    const BY_CODE: [Color; 16] = [
+        Color::Black,
+        Color::DarkBlue,
+        Color::DarkGreen,
+        Color::DarkAqua,
+        Color::DarkRed,
+        Color::DarkPurple,
+        Color::Gold,
+        Color::Gray,
+        Color::DarkGray,
+        Color::Blue,
+        Color::Green,
+        Color::Aqua,
+        Color::Red,
+        Color::LightPurple,
+        Color::Yellow,
+        Color::White,
+    ];
+
+    /// Returns the color with the given legacy code, `0`-`9` or `a`-`f`.
+    pub fn from_code(code: char) -> Option<Self> {
+        code.to_digit(16).map(|index| Self::BY_CODE[index as usize])
+    }
+
+    /// Returns the legacy code of this color.
+    pub fn code(self) -> char {
+        match Self::BY_CODE.iter().position(|color| *color == self) {
+            Some(index) => std::char::from_digit(index as u32, 16).unwrap(),
+            None => 'r',
+        }
+    }
+}
+
+/// What happens when a component is clicked.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", content = "value", rename_all = "snake_case")]
+pub enum ClickEvent {
+    OpenUrl(String),
+    /// Sends a chat message or command as the player.
+    RunCommand(String),
+    /// Replaces the contents of the chat box.
+    SuggestCommand(String),
+    /// Turns to a page of a book.
+    #[serde(with = "page_string")]
+    ChangePage(u32),
+}
+
+/// The client expects every click event value to be a
+/// string, so page numbers are written as strings too.
+mod page_string {
+    use serde::de::Error;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S>(page: &u32, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(page)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<u32, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let page: String = serde::Deserialize::deserialize(deserializer)?;
+        page.parse().map_err(D::Error::custom)
+    }
+}
+
+/// What is shown when hovering over a component.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", content = "value", rename_all = "snake_case")]
+pub enum HoverEvent {
+    ShowText(Box<TextComponent>),
+    /// The tooltip of an item, given as SNBT.
+    ShowItem(String),
+    /// The name, type and UUID of an entity, given as SNBT.
+    ShowEntity(String),
+}
+
+impl TextComponent {
+    /// Creates a component displaying plain text.
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::with_content(Content::Text { text: text.into() })
+    }
+
+    /// Creates a component displaying a translation, with
+    /// `with` substituted for the placeholders in the key.
+    pub fn translate(key: impl Into<String>, with: Vec<TextComponent>) -> Self {
+        Self::with_content(Content::Translate {
+            translate: key.into(),
+            with,
+        })
+    }
+
+    /// Creates a component displaying the score of
+    /// an entity in a scoreboard objective.
+    pub fn score(name: impl Into<String>, objective: impl Into<String>) -> Self {
+        Self::with_content(Content::Score {
+            score: Score {
+                name: name.into(),
+                objective: objective.into(),
+                value: None,
+            },
+        })
+    }
+
+    /// Creates a component displaying the names
+    /// of the entities an entity selector selects.
+    pub fn selector(selector: impl Into<String>) -> Self {
+        Self::with_content(Content::Selector {
+            selector: selector.into(),
+        })
+    }
+
+    /// Creates a component displaying the key bound
+    /// to a key binding, such as `key.inventory`.
+    pub fn keybind(keybind: impl Into<String>) -> Self {
+        Self::with_content(Content::Keybind {
+            keybind: keybind.into(),
+        })
+    }
+
+    fn with_content(content: Content) -> Self {
+        Self {
+            content,
+            style: Style::default(),
+            extra: vec![],
+        }
+    }
+
+    /// Converts text containing legacy `§` formatting codes.
+    /// Each run of text with the same formatting becomes a
+    /// child of an empty component. Unknown codes are kept
+    /// as they are.
+    pub fn from_legacy(text: &str) -> Self {
+        let mut parts = vec![];
+        let mut style = Style::default();
+        let mut current = String::new();
+
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            let code = match chars.peek() {
+                Some(code) if c == LEGACY_PREFIX => code.to_ascii_lowercase(),
+                _ => {
+                    current.push(c);
+                    continue;
+                }
+            };
+
+            let mut new_style = style.clone();
+            if let Some(color) = Color::from_code(code) {
+                // Colors reset formatting.
+                new_style = Style {
+                    color: Some(color),
+                    ..Style::default()
+                };
+            } else {
+                match code {
+                    'k' => new_style.obfuscated = Some(true),
+                    'l' => new_style.bold = Some(true),
+                    'm' => new_style.strikethrough = Some(true),
+                    'n' => new_style.underlined = Some(true),
+                    'o' => new_style.italic = Some(true),
+                    'r' => new_style = Style::default(),
+                    _ => {
+                        current.push(c);
+                        continue;
+                    }
+                }
+            }
+            chars.next();
+
+            if !current.is_empty() {
+                parts.push(Self::text(mem::take(&mut current)).style(style));
+            }
+            style = new_style;
+        }
+        if !current.is_empty() {
+            parts.push(Self::text(current).style(style));
+        }
+
+        match parts.len() {
+            0 => Self::text(""),
+            1 => parts.remove(0),
+            _ => Self::text("").children(parts),
+        }
+    }
+
+    /// Sets the style, replacing all style properties.
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.style.color = Some(color);
+        self
+    }
+
+    pub fn bold(mut self, bold: bool) -> Self {
+        self.style.bold = Some(bold);
+        self
+    }
+
+    pub fn italic(mut self, italic: bool) -> Self {
+        self.style.italic = Some(italic);
+        self
+    }
+
+    pub fn underlined(mut self, underlined: bool) -> Self {
+        self.style.underlined = Some(underlined);
+        self
+    }
+
+    pub fn strikethrough(mut self, strikethrough: bool) -> Self {
+        self.style.strikethrough = Some(strikethrough);
+        self
+    }
+
+    pub fn obfuscated(mut self, obfuscated: bool) -> Self {
+        self.style.obfuscated = Some(obfuscated);
+        self
+    }
+
+    /// Sets the text inserted into the chat box when shift-clicked.
+    pub fn insertion(mut self, insertion: impl Into<String>) -> Self {
+        self.style.insertion = Some(insertion.into());
+        self
+    }
+
+    pub fn on_click(mut self, event: ClickEvent) -> Self {
+        self.style.click_event = Some(event);
+        self
+    }
+
+    pub fn on_hover(mut self, event: HoverEvent) -> Self {
+        self.style.hover_event = Some(event);
+        self
+    }
+
+    /// Appends a child component.
+    pub fn child(mut self, child: impl Into<TextComponent>) -> Self {
+        self.extra.push(child.into());
+        self
+    }
+
+    /// Appends several child components.
+    pub fn children(mut self, children: impl IntoIterator<Item = TextComponent>) -> Self {
+        self.extra.extend(children);
+        self
+    }
+
+    /// Serializes this component to JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    /// Returns the text of this component and its children,
+    /// without formatting. Translations are shown as their
+    /// keys, since only the client knows what they say.
+    pub fn to_plain(&self) -> String {
+        let mut plain = String::new();
+        self.write_plain(&mut plain);
+        plain
+    }
+
+    /// Returns this component with its colors and formatting
+    /// removed, including legacy codes in its text, for clients
+    /// which turned chat colors off.
+    pub fn without_formatting(&self) -> Self {
+        let mut component = self.clone();
+        component.strip_formatting();
+        component
+    }
+
+    fn strip_formatting(&mut self) {
+        self.style = Style {
+            insertion: self.style.insertion.take(),
+            click_event: self.style.click_event.take(),
+            hover_event: self.style.hover_event.take(),
+            ..Style::default()
+        };
+        match &mut self.content {
+            Content::Text { text } => *text = strip_legacy(text),
+            Content::Translate { with, .. } => with.iter_mut().for_each(Self::strip_formatting),
+            _ => (),
+        }
+        self.extra.iter_mut().for_each(Self::strip_formatting);
+    }
+
+    fn write_plain(&self, plain: &mut String) {
+        match &self.content {
+            Content::Text { text } => plain.push_str(text),
+            Content::Translate { translate, .. } => plain.push_str(translate),
+            Content::Score { score } => {
+                plain.push_str(score.value.as_ref().unwrap_or(&score.name));
+            }
+            Content::Selector { selector } => plain.push_str(selector),
+            Content::Keybind { keybind } => plain.push_str(keybind),
+        }
+        for child in &self.extra {
+            child.write_plain(plain);
+        }
+    }
+}
+
+impl From<&str> for TextComponent {
+    fn from(text: &str) -> Self {
+        Self::text(text)
+    }
+}
+
+impl From<String> for TextComponent {
+    fn from(text: String) -> Self {
+        Self::text(text)
+    }
+}
+
+/// The forms a component may take in JSON. Besides objects,
+/// strings are plain text components, and arrays are their
+/// first element with the rest appended as children.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Repr {
+    Text(String),
+    List(Vec<TextComponent>),
+    Object(Object),
+}
+
+#[derive(Deserialize)]
+struct Object {
+    #[serde(flatten)]
+    content: Content,
+    #[serde(flatten)]
+    style: Style,
+    #[serde(default)]
+    extra: Vec<TextComponent>,
+}
+
+impl From<Repr> for TextComponent {
+    fn from(repr: Repr) -> Self {
+        match repr {
+            Repr::Text(text) => Self::text(text),
+            Repr::List(components) => {
+                let mut components = components.into_iter();
+                match components.next() {
+                    Some(first) => first.children(components),
+                    None => Self::text(""),
+                }
+            }
+            Repr::Object(object) => Self {
+                content: object.content,
+                style: object.style,
+                extra: object.extra,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialize() {
+        let component = TextComponent::translate(
+            "chat.type.text",
+            vec![
+                "Notch".into(),
+                TextComponent::text("hello").color(Color::Gold),
+            ],
+        )
+        .on_click(ClickEvent::SuggestCommand("/msg Notch ".to_string()));
+
+        assert_eq!(
+            component.to_json(),
+            r#"{"translate":"chat.type.text","with":[{"text":"Notch"},{"text":"hello","color":"gold"}],"clickEvent":{"action":"suggest_command","value":"/msg Notch "}}"#
+        );
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let component: TextComponent = serde_json::from_str(
+            r#"{"text":"a","bold":true,"extra":["b",{"keybind":"key.jump"}],"hoverEvent":{"action":"show_text","value":"c"}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            component,
+            TextComponent::text("a")
+                .bold(true)
+                .on_hover(HoverEvent::ShowText(Box::new("c".into())))
+                .child("b")
+                .child(TextComponent::keybind("key.jump"))
+        );
+
+        let list: TextComponent = serde_json::from_str(r#"["a", {"text": "b"}]"#).unwrap();
+        assert_eq!(list, TextComponent::text("a").child("b"));
+    }
+
+    #[test]
+    fn test_change_page() {
+        let json = r#"{"text":"next","clickEvent":{"action":"change_page","value":"3"}}"#;
+        let component = TextComponent::text("next").on_click(ClickEvent::ChangePage(3));
+
+        assert_eq!(component.to_json(), json);
+        assert_eq!(
+            serde_json::from_str::<TextComponent>(json).unwrap(),
+            component
+        );
+    }
+
+    #[test]
+    fn test_from_legacy() {
+        assert_eq!(
+            TextComponent::from_legacy("plain"),
+            TextComponent::text("plain")
+        );
+        assert_eq!(
+            TextComponent::from_legacy("§cRed §lbold§r reset §z"),
+            TextComponent::text("").children(vec![
+                TextComponent::text("Red ").color(Color::Red),
+                TextComponent::text("bold").color(Color::Red).bold(true),
+                TextComponent::text(" reset §z"),
+            ])
+        );
+        assert_eq!(
+            TextComponent::from_legacy("§aA Minecraft Server").to_plain(),
+            "A Minecraft Server"
+        );
+    }
+
+    #[test]
+    fn test_without_formatting() {
+        let component = TextComponent::translate(
+            "chat.type.text",
+            vec![TextComponent::text("§cNotch").bold(true), "hi".into()],
+        )
+        .color(Color::Gold)
+        .insertion("Notch")
+        .child(TextComponent::from_legacy("§athere"));
+
+        assert_eq!(
+            component.without_formatting(),
+            TextComponent::translate(
+                "chat.type.text",
+                vec![TextComponent::text("Notch"), "hi".into()],
+            )
+            .insertion("Notch")
+            .child(TextComponent::text("there"))
+        );
+    }
+
+    #[test]
+    fn test_strip_legacy() {
+        assert_eq!(strip_legacy("§ahi §lthere§"), "hi there");
+        assert_eq!(strip_legacy("no codes"), "no codes");
+    }
+
+    #[test]
+    fn test_color_codes() {
+        for code in "0123456789abcdef".chars() {
+            assert_eq!(Color::from_code(code).unwrap().code(), code);
+        }
+        assert_eq!(Color::from_code('g'), None);
+    }
+}
@@ -303,8 +303,13 @@ impl ChunkMap {
     }
     /// Retrieves the block at the specified
     /// location. If the chunk in which the block
-    /// exists is not laoded, `None` is returned.
+    /// exists is not laoded, or the position is
+    /// above or below the world, `None` is returned.
     pub fn block_at(&self, pos: BlockPosition) -> Option<Block> {
+        if !is_in_world_bounds(pos) {
+            return None;
+        }
+
         let (x, y, z) = chunk_relative_pos(pos);
         self.chunk_at(pos.chunk_pos())
             .map(|chunk| chunk.block_at(x, y, z))
@@ -313,9 +318,13 @@ impl ChunkMap {
     /// Sets the block at the given position.
     ///
     /// Returns `true` if the block was set, or `false`
-    /// if its chunk was not loaded and thus no operation
-    /// was performed.
+    /// if its chunk was not loaded or the position is
+    /// outside the world and thus no operation was performed.
     pub fn set_block_at(&self, pos: BlockPosition, block: Block) -> bool {
+        if !is_in_world_bounds(pos) {
+            return false;
+        }
+
         let (x, y, z) = chunk_relative_pos(pos);
 
         self.chunk_at_mut(pos.chunk_pos())
@@ -351,6 +360,12 @@ impl Default for ChunkMap {
     }
 }
 
+/// Returns whether the Y coordinate of the given
+/// position is within the bounds of the world.
+pub fn is_in_world_bounds(block_pos: BlockPosition) -> bool {
+    block_pos.y >= 0 && block_pos.y < 256
+}
+
 pub fn chunk_relative_pos(block_pos: BlockPosition) -> (usize, usize, usize) {
     (
         block_pos.x as usize & 0xf,
@@ -438,4 +453,15 @@ mod tests {
             Block::Air
         );
     }
+
+    #[test]
+    fn test_block_at_out_of_bounds() {
+        let mut world = ChunkMap::new();
+        world.insert(Chunk::new(ChunkPosition::new(0, 0)));
+
+        assert!(world.block_at(BlockPosition::new(0, -1, 0)).is_none());
+        assert!(world.block_at(BlockPosition::new(0, 256, 0)).is_none());
+        assert!(!world.set_block_at(BlockPosition::new(0, 256, 0), Block::Stone));
+        assert!(world.block_at(BlockPosition::new(0, 255, 0)).is_some());
+    }
 }
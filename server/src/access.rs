@@ -0,0 +1,403 @@
+//! Who may join the server, use its commands and chat: bans,
+//! operators, the whitelist and mutes.
+//!
+//! Each list is stored in the server directory in the same format as
+//! the vanilla server's `banned-players.json`, `ops.json` and
+//! `whitelist.json`, and is saved whenever it changes. Mutes, which
+//! vanilla lacks, are stored like bans in `muted-players.json`. Players added
+//! while offline are only known by name; they are matched by name
+//! until they next join, when their UUID is recorded.
+
+use crate::config::Config;
+use feather_core::TextComponent;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+pub const BANS_FILE: &str = "banned-players.json";
+pub const OPS_FILE: &str = "ops.json";
+pub const WHITELIST_FILE: &str = "whitelist.json";
+pub const MUTES_FILE: &str = "muted-players.json";
+
+/// The op level of the console and of players made operators.
+pub const MAX_OP_LEVEL: u8 = 4;
+
+/// The player an entry of a list refers to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<Uuid>,
+    pub name: String,
+}
+
+impl Profile {
+    pub fn new(uuid: Option<Uuid>, name: impl Into<String>) -> Self {
+        Self {
+            uuid,
+            name: name.into(),
+        }
+    }
+
+    /// Returns whether this profile refers to the given player.
+    /// Profiles without a UUID are matched by name, ignoring case.
+    pub fn matches(&self, uuid: Option<Uuid>, name: &str) -> bool {
+        match (self.uuid, uuid) {
+            (Some(a), Some(b)) => a == b,
+            _ => self.name.eq_ignore_ascii_case(name),
+        }
+    }
+}
+
+/// An entry of a `PlayerList`.
+pub trait ListEntry {
+    fn profile(&self) -> &Profile;
+
+    fn profile_mut(&mut self) -> &mut Profile;
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BanEntry {
+    #[serde(flatten)]
+    pub profile: Profile,
+    /// The name of whoever issued the ban.
+    pub source: String,
+    /// When the ban expires. Bans issued by
+    /// Feather are permanent, so this is `forever`.
+    pub expires: String,
+    pub reason: String,
+}
+
+impl BanEntry {
+    pub fn new(profile: Profile, source: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            profile,
+            source: source.into(),
+            expires: "forever".to_string(),
+            reason: reason.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpEntry {
+    #[serde(flatten)]
+    pub profile: Profile,
+    /// The op level, from 1 to 4, which determines
+    /// the commands the player may use.
+    pub level: u8,
+    #[serde(default)]
+    pub bypasses_player_limit: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WhitelistEntry {
+    #[serde(flatten)]
+    pub profile: Profile,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MuteEntry {
+    #[serde(flatten)]
+    pub profile: Profile,
+    /// The name of whoever issued the mute.
+    pub source: String,
+    pub reason: String,
+}
+
+macro_rules! list_entry {
+    ($($ty:ty),*) => {
+        $(
+            impl ListEntry for $ty {
+                fn profile(&self) -> &Profile {
+                    &self.profile
+                }
+
+                fn profile_mut(&mut self) -> &mut Profile {
+                    &mut self.profile
+                }
+            }
+        )*
+    };
+}
+
+list_entry!(BanEntry, OpEntry, WhitelistEntry, MuteEntry);
+
+/// A list of players, stored as a JSON array.
+#[derive(Debug, Clone)]
+pub struct PlayerList<E> {
+    path: PathBuf,
+    entries: Vec<E>,
+    /// Whether the file failed to parse, in which case
+    /// it is never saved so that its entries aren't lost.
+    invalid: bool,
+}
+
+impl<E> PlayerList<E>
+where
+    E: ListEntry + Serialize + DeserializeOwned,
+{
+    /// Loads the list stored at `path`. The list is
+    /// empty if the file does not exist or is invalid.
+    /// An invalid file is left untouched until it is fixed
+    /// and the server restarted.
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let (entries, invalid) = match fs::read_to_string(&path) {
+            Ok(json) => match serde_json::from_str(&json) {
+                Ok(entries) => (entries, false),
+                Err(e) => {
+                    error!(
+                        "Failed to parse {}: {}. Changes to it won't be saved.",
+                        path.display(),
+                        e
+                    );
+                    (vec![], true)
+                }
+            },
+            Err(_) => (vec![], false),
+        };
+
+        Self {
+            path,
+            entries,
+            invalid,
+        }
+    }
+
+    /// Writes the list to its file, unless
+    /// the file failed to parse when loaded.
+    pub fn save(&self) {
+        if self.invalid {
+            warn!(
+                "Not saving {}, since it failed to parse",
+                self.path.display()
+            );
+            return;
+        }
+
+        let result = serde_json::to_string_pretty(&self.entries)
+            .map_err(failure::Error::from)
+            .and_then(|json| fs::write(&self.path, json).map_err(failure::Error::from));
+        if let Err(e) = result {
+            error!("Failed to save {}: {}", self.path.display(), e);
+        }
+    }
+
+    pub fn entries(&self) -> &[E] {
+        &self.entries
+    }
+
+    /// Returns the entry for the given player.
+    pub fn get(&self, uuid: Option<Uuid>, name: &str) -> Option<&E> {
+        self.entries
+            .iter()
+            .find(|entry| entry.profile().matches(uuid, name))
+    }
+
+    pub fn contains(&self, uuid: Option<Uuid>, name: &str) -> bool {
+        self.get(uuid, name).is_some()
+    }
+
+    /// Adds an entry, unless its player is already on the
+    /// list. Returns whether the entry was added.
+    pub fn add(&mut self, entry: E) -> bool {
+        let profile = entry.profile();
+        if self.contains(profile.uuid, &profile.name) {
+            return false;
+        }
+
+        self.entries.push(entry);
+        self.save();
+        true
+    }
+
+    /// Removes the entry for the player with the given
+    /// name, ignoring case, and returns it.
+    pub fn remove(&mut self, name: &str) -> Option<E> {
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| entry.profile().name.eq_ignore_ascii_case(name))?;
+        let entry = self.entries.remove(index);
+        self.save();
+        Some(entry)
+    }
+
+    /// Records the UUID and current name of a player on the list.
+    fn update_profile(&mut self, uuid: Uuid, name: &str) {
+        let entry = match self
+            .entries
+            .iter_mut()
+            .find(|entry| entry.profile().matches(Some(uuid), name))
+        {
+            Some(entry) => entry,
+            None => return,
+        };
+
+        let profile = entry.profile_mut();
+        if profile.uuid != Some(uuid) || profile.name != name {
+            profile.uuid = Some(uuid);
+            profile.name = name.to_string();
+            self.save();
+        }
+    }
+}
+
+/// Resource storing the bans, operators, whitelist and mutes.
+#[derive(Resource)]
+pub struct AccessLists {
+    pub bans: PlayerList<BanEntry>,
+    pub ops: PlayerList<OpEntry>,
+    pub whitelist: PlayerList<WhitelistEntry>,
+    /// Players who may not chat.
+    pub mutes: PlayerList<MuteEntry>,
+    /// Whether only whitelisted players and operators may join.
+    pub whitelist_enabled: bool,
+}
+
+impl AccessLists {
+    /// Loads the lists from the server directory.
+    pub fn load(config: &Config) -> Self {
+        Self {
+            bans: PlayerList::load(BANS_FILE),
+            ops: PlayerList::load(OPS_FILE),
+            whitelist: PlayerList::load(WHITELIST_FILE),
+            mutes: PlayerList::load(MUTES_FILE),
+            whitelist_enabled: config.server.whitelist,
+        }
+    }
+
+    /// Returns the op level of the given player,
+    /// which is 0 if the player is not an operator.
+    pub fn op_level(&self, uuid: Uuid, name: &str) -> u8 {
+        self.ops.get(Some(uuid), name).map_or(0, |op| op.level)
+    }
+
+    /// Checks whether a player may join, returning the reason
+    /// they are refused if not. This also records the UUIDs of
+    /// players who were added to lists by name.
+    pub fn check_join(&mut self, uuid: Uuid, name: &str) -> Result<(), TextComponent> {
+        self.bans.update_profile(uuid, name);
+        self.ops.update_profile(uuid, name);
+        self.whitelist.update_profile(uuid, name);
+        self.mutes.update_profile(uuid, name);
+
+        if let Some(ban) = self.bans.get(Some(uuid), name) {
+            return Err(TextComponent::translate(
+                "feather.disconnect.banned",
+                vec![ban.reason.as_str().into()],
+            ));
+        }
+
+        if self.whitelist_enabled
+            && !self.whitelist.contains(Some(uuid), name)
+            && !self.ops.contains(Some(uuid), name)
+        {
+            return Err(TextComponent::translate(
+                "feather.disconnect.notWhitelisted",
+                vec![],
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list<E>(file: &str) -> PlayerList<E>
+    where
+        E: ListEntry + Serialize + DeserializeOwned,
+    {
+        let path = std::env::temp_dir().join(format!("feather-{}-{}", Uuid::new_v4(), file));
+        PlayerList::load(path)
+    }
+
+    #[test]
+    fn test_player_list() {
+        let mut list = list::<WhitelistEntry>(WHITELIST_FILE);
+        let uuid = Uuid::new_v4();
+
+        assert!(list.add(WhitelistEntry {
+            profile: Profile::new(None, "Notch"),
+        }));
+        assert!(!list.add(WhitelistEntry {
+            profile: Profile::new(Some(uuid), "notch"),
+        }));
+        assert!(list.contains(Some(uuid), "Notch"));
+
+        list.update_profile(uuid, "Notch");
+        assert_eq!(list.entries()[0].profile.uuid, Some(uuid));
+        // Once the UUID is known, the name no longer matters.
+        assert!(list.contains(Some(uuid), "jeb_"));
+        assert!(!list.contains(Some(Uuid::new_v4()), "Notch"));
+
+        let reloaded = PlayerList::<WhitelistEntry>::load(list.path.clone());
+        assert_eq!(reloaded.entries(), list.entries());
+
+        assert!(list.remove("NOTCH").is_some());
+        assert!(list.entries().is_empty());
+        fs::remove_file(&list.path).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_list_not_saved() {
+        let path = std::env::temp_dir().join(format!("feather-{}-{}", Uuid::new_v4(), OPS_FILE));
+        fs::write(&path, "[{").unwrap();
+
+        let mut list = PlayerList::<OpEntry>::load(path.clone());
+        assert!(list.entries().is_empty());
+        assert!(list.add(OpEntry {
+            profile: Profile::new(None, "Notch"),
+            level: MAX_OP_LEVEL,
+            bypasses_player_limit: false,
+        }));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[{");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_check_join() {
+        let mut lists = AccessLists {
+            bans: list(BANS_FILE),
+            ops: list(OPS_FILE),
+            whitelist: list(WHITELIST_FILE),
+            mutes: list(MUTES_FILE),
+            whitelist_enabled: false,
+        };
+        let uuid = Uuid::new_v4();
+
+        assert!(lists.check_join(uuid, "Notch").is_ok());
+
+        lists.whitelist_enabled = true;
+        assert!(lists.check_join(uuid, "Notch").is_err());
+        lists.ops.add(OpEntry {
+            profile: Profile::new(Some(uuid), "Notch"),
+            level: MAX_OP_LEVEL,
+            bypasses_player_limit: false,
+        });
+        assert!(lists.check_join(uuid, "Notch").is_ok());
+        assert_eq!(lists.op_level(uuid, "Notch"), MAX_OP_LEVEL);
+
+        lists.bans.add(BanEntry::new(
+            Profile::new(None, "Notch"),
+            "Server",
+            "Griefing",
+        ));
+        assert_eq!(
+            lists.check_join(uuid, "Notch"),
+            Err(TextComponent::translate(
+                "feather.disconnect.banned",
+                vec!["Griefing".into()]
+            ))
+        );
+
+        fs::remove_file(&lists.bans.path).unwrap();
+        fs::remove_file(&lists.ops.path).unwrap();
+    }
+}
@@ -0,0 +1,200 @@
+//! Block directions, as used by `facing` properties
+//! and neighbor updates.
+
+use feather_core::network::packet::implementation::Face;
+use feather_core::BlockPosition;
+
+/// One of the six directions along the axes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Down,
+    Up,
+    North,
+    South,
+    West,
+    East,
+}
+
+impl Direction {
+    /// All directions, in the order in which vanilla
+    /// notifies neighbors of a block update.
+    pub const UPDATE_ORDER: [Direction; 6] = [
+        Direction::West,
+        Direction::East,
+        Direction::Down,
+        Direction::Up,
+        Direction::North,
+        Direction::South,
+    ];
+
+    /// The four horizontal directions.
+    pub const HORIZONTAL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    /// Returns the direction a block face points toward.
+    pub fn from_face(face: Face) -> Self {
+        match face {
+            Face::Bottom => Direction::Down,
+            Face::Top => Direction::Up,
+            Face::North => Direction::North,
+            Face::South => Direction::South,
+            Face::West => Direction::West,
+            Face::East => Direction::East,
+        }
+    }
+
+    /// Returns the horizontal direction an entity
+    /// with the given yaw is facing.
+    pub fn from_yaw(yaw: f32) -> Self {
+        let index = ((yaw / 90.0 + 0.5).floor() as i32) & 3;
+        match index {
+            0 => Direction::South,
+            1 => Direction::West,
+            2 => Direction::North,
+            _ => Direction::East,
+        }
+    }
+
+    /// Returns the direction closest to where an entity
+    /// with the given yaw and pitch is looking.
+    pub fn from_look(yaw: f32, pitch: f32) -> Self {
+        if pitch > 45.0 {
+            Direction::Down
+        } else if pitch < -45.0 {
+            Direction::Up
+        } else {
+            Direction::from_yaw(yaw)
+        }
+    }
+
+    /// Returns the offset to the adjacent block in this direction.
+    pub fn offset(self) -> BlockPosition {
+        match self {
+            Direction::Down => BlockPosition::new(0, -1, 0),
+            Direction::Up => BlockPosition::new(0, 1, 0),
+            Direction::North => BlockPosition::new(0, 0, -1),
+            Direction::South => BlockPosition::new(0, 0, 1),
+            Direction::West => BlockPosition::new(-1, 0, 0),
+            Direction::East => BlockPosition::new(1, 0, 0),
+        }
+    }
+
+    /// Returns the position adjacent to `pos` in this direction.
+    pub fn adjacent(self, pos: BlockPosition) -> BlockPosition {
+        pos + self.offset()
+    }
+
+    /// Returns the opposite direction.
+    pub fn opposite(self) -> Self {
+        match self {
+            Direction::Down => Direction::Up,
+            Direction::Up => Direction::Down,
+            Direction::North => Direction::South,
+            Direction::South => Direction::North,
+            Direction::West => Direction::East,
+            Direction::East => Direction::West,
+        }
+    }
+
+    /// Rotates a horizontal direction 90 degrees clockwise
+    /// (as seen from above). Vertical directions are unchanged.
+    pub fn rotate_cw(self) -> Self {
+        match self {
+            Direction::North => Direction::East,
+            Direction::East => Direction::South,
+            Direction::South => Direction::West,
+            Direction::West => Direction::North,
+            vertical => vertical,
+        }
+    }
+
+    /// Rotates a horizontal direction 90 degrees counterclockwise
+    /// (as seen from above). Vertical directions are unchanged.
+    pub fn rotate_ccw(self) -> Self {
+        self.rotate_cw().opposite()
+    }
+
+    /// Returns whether this direction is horizontal.
+    pub fn is_horizontal(self) -> bool {
+        match self {
+            Direction::Up | Direction::Down => false,
+            _ => true,
+        }
+    }
+
+    /// Returns the name of the axis this direction
+    /// lies on, as used in `axis` properties.
+    pub fn axis(self) -> &'static str {
+        match self {
+            Direction::Down | Direction::Up => "y",
+            Direction::North | Direction::South => "z",
+            Direction::West | Direction::East => "x",
+        }
+    }
+
+    /// Returns the name of this direction, as used
+    /// in block state properties.
+    pub fn name(self) -> &'static str {
+        match self {
+            Direction::Down => "down",
+            Direction::Up => "up",
+            Direction::North => "north",
+            Direction::South => "south",
+            Direction::West => "west",
+            Direction::East => "east",
+        }
+    }
+
+    /// Parses a direction from its property name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "down" => Some(Direction::Down),
+            "up" => Some(Direction::Up),
+            "north" => Some(Direction::North),
+            "south" => Some(Direction::South),
+            "west" => Some(Direction::West),
+            "east" => Some(Direction::East),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation() {
+        for dir in &Direction::HORIZONTAL {
+            assert_eq!(dir.rotate_cw().rotate_ccw(), *dir);
+            assert_eq!(dir.rotate_cw().rotate_cw(), dir.opposite());
+        }
+        assert_eq!(Direction::North.rotate_cw(), Direction::East);
+        assert_eq!(Direction::Up.rotate_cw(), Direction::Up);
+    }
+
+    #[test]
+    fn test_from_yaw() {
+        assert_eq!(Direction::from_yaw(0.0), Direction::South);
+        assert_eq!(Direction::from_yaw(90.0), Direction::West);
+        assert_eq!(Direction::from_yaw(-90.0), Direction::East);
+        assert_eq!(Direction::from_yaw(181.0), Direction::North);
+        assert_eq!(Direction::from_yaw(400.0), Direction::South);
+        assert_eq!(Direction::from_look(0.0, 80.0), Direction::Down);
+    }
+
+    #[test]
+    fn test_name_roundtrip() {
+        for dir in &Direction::UPDATE_ORDER {
+            assert_eq!(Direction::from_name(dir.name()), Some(*dir));
+            assert_eq!(
+                dir.offset() + dir.opposite().offset(),
+                BlockPosition::new(0, 0, 0)
+            );
+        }
+    }
+}
@@ -0,0 +1,343 @@
+//! Blocks which players can interact with by
+//! right-clicking them, such as doors, levers and beds.
+//!
+//! Interactions are dispatched before block placement:
+//! if the clicked block consumes the interaction, no
+//! block is placed.
+
+use crate::block::direction::Direction;
+use crate::block::{facing, prop_bool, prop_int, with_prop, BlockUpdateCause};
+use crate::entity::tnt;
+use crate::p_inventory::{EntityInventory, InventoryUpdateEvent};
+use crate::player::sleep::{is_bed, BedUseEvent};
+use crate::portal::PortalFrame;
+use crate::redstone::{press_button, toggle_lever, Redstone};
+use crate::state::DimensionState;
+use crate::world_border::WorldBorder;
+use feather_blocks::FireData;
+use feather_core::{Block, BlockPosition, Gamemode, Item};
+use legion::entity::Entity;
+use std::mem;
+
+/// Describes a player's interaction with a block.
+#[derive(Debug, Clone)]
+pub struct Interaction {
+    /// The player interacting with the block.
+    pub player: Entity,
+    /// The gamemode of the player.
+    pub gamemode: Gamemode,
+    /// The yaw of the player.
+    pub yaw: f32,
+    /// The position of the clicked block.
+    pub pos: BlockPosition,
+    /// The face of the block which was clicked.
+    pub face: Direction,
+    /// The item in the player's main hand, if any.
+    pub item: Option<Item>,
+}
+
+/// Returns whether interacting with `block` while holding `item`
+/// changes blocks, as opposed to only using the clicked block.
+///
+/// Players without the build permission may open doors or
+/// flip levers, but not, for example, prime TNT or eat cake.
+pub fn changes_blocks(block: Block, item: Option<Item>) -> bool {
+    match block {
+        Block::NoteBlock(_) | Block::Repeater(_) | Block::Comparator(_) | Block::Cake(_) => true,
+        _ => is_igniter(item) && !is_usable(block),
+    }
+}
+
+/// Returns whether `block` is used by interacting with it,
+/// rather than being set on fire by flint and steel.
+fn is_usable(block: Block) -> bool {
+    match block {
+        Block::IronDoor(_) | Block::IronTrapdoor(_) | Block::Lever(_) | Block::Cake(_) => true,
+        _ => {
+            is_button(block)
+                || is_bed(block)
+                || is_door(block)
+                || is_trapdoor(block)
+                || is_fence_gate(block)
+        }
+    }
+}
+
+fn is_igniter(item: Option<Item>) -> bool {
+    match item {
+        Some(Item::FlintAndSteel) | Some(Item::FireCharge) => true,
+        _ => false,
+    }
+}
+
+fn is_button(block: Block) -> bool {
+    match block {
+        Block::StoneButton(_)
+        | Block::OakButton(_)
+        | Block::SpruceButton(_)
+        | Block::BirchButton(_)
+        | Block::JungleButton(_)
+        | Block::AcaciaButton(_)
+        | Block::DarkOakButton(_) => true,
+        _ => false,
+    }
+}
+
+fn is_door(block: Block) -> bool {
+    match block {
+        Block::OakDoor(_)
+        | Block::SpruceDoor(_)
+        | Block::BirchDoor(_)
+        | Block::JungleDoor(_)
+        | Block::AcaciaDoor(_)
+        | Block::DarkOakDoor(_) => true,
+        _ => false,
+    }
+}
+
+fn is_trapdoor(block: Block) -> bool {
+    match block {
+        Block::OakTrapdoor(_)
+        | Block::SpruceTrapdoor(_)
+        | Block::BirchTrapdoor(_)
+        | Block::JungleTrapdoor(_)
+        | Block::AcaciaTrapdoor(_)
+        | Block::DarkOakTrapdoor(_) => true,
+        _ => false,
+    }
+}
+
+fn is_fence_gate(block: Block) -> bool {
+    match block {
+        Block::OakFenceGate(_)
+        | Block::SpruceFenceGate(_)
+        | Block::BirchFenceGate(_)
+        | Block::JungleFenceGate(_)
+        | Block::AcaciaFenceGate(_)
+        | Block::DarkOakFenceGate(_) => true,
+        _ => false,
+    }
+}
+
+/// Performs the interaction with the block at `interaction.pos`.
+///
+/// Returns whether the block consumed the interaction,
+/// in which case no block should be placed.
+pub fn interact(
+    redstone: &mut Redstone,
+    state: &DimensionState,
+    border: &WorldBorder,
+    interaction: &Interaction,
+) -> bool {
+    let pos = interaction.pos;
+    let block = match state.block_at(pos) {
+        Some(block) => block,
+        None => return false,
+    };
+    let cause = BlockUpdateCause::Player(interaction.player);
+
+    let consumed = match block {
+        // Iron doors and trapdoors only open with redstone.
+        Block::IronDoor(_) | Block::IronTrapdoor(_) => false,
+        Block::Lever(_) => toggle_lever(redstone, pos),
+        Block::NoteBlock(_) => {
+            // TODO: play the note with a Block Action packet
+            let note = (prop_int(block, "note") + 1) % 25;
+            state.set_block_at(pos, with_prop(block, "note", note), cause);
+            true
+        }
+        Block::Repeater(_) => {
+            let delay = prop_int(block, "delay") % 4 + 1;
+            state.set_block_at(pos, with_prop(block, "delay", delay), cause);
+            true
+        }
+        Block::Comparator(_) => {
+            let mode = match block.property("mode").as_ref().map(String::as_str) {
+                Some("compare") => "subtract",
+                _ => "compare",
+            };
+            state.set_block_at(pos, with_prop(block, "mode", mode), cause);
+            true
+        }
+        Block::Cake(_) => eat_cake(state, block, interaction),
+        Block::Tnt(_) if is_igniter(interaction.item) => {
+            tnt::prime(state, pos, tnt::DEFAULT_FUSE, cause);
+            use_igniter(state, interaction);
+            true
+        }
+        _ if is_button(block) => {
+            press_button(redstone, pos);
+            true
+        }
+        _ if is_bed(block) => {
+            let event = BedUseEvent {
+                player: interaction.player,
+                pos,
+            };
+            state.exec_with_scheduler(move |_, scheduler| scheduler.trigger(event));
+            true
+        }
+        _ if is_door(block) => {
+            toggle_door(state, block, interaction);
+            true
+        }
+        _ if is_trapdoor(block) => {
+            let open = !prop_bool(block, "open");
+            state.set_block_at(pos, with_prop(block, "open", open), cause);
+            true
+        }
+        _ if is_fence_gate(block) => {
+            toggle_fence_gate(state, block, interaction);
+            true
+        }
+        _ => ignite(state, border, interaction),
+    };
+
+    redstone.run();
+    consumed
+}
+
+/// Sets fire to the block next to the clicked face when using
+/// flint and steel or a fire charge, lighting a nether portal
+/// instead if the fire would be inside an empty portal frame.
+///
+/// Like placed blocks, fire and portals are never
+/// set outside the world border.
+fn ignite(state: &DimensionState, border: &WorldBorder, interaction: &Interaction) -> bool {
+    if !is_igniter(interaction.item) {
+        return false;
+    }
+
+    let pos = interaction.face.adjacent(interaction.pos);
+    if state.block_at(pos) != Some(Block::Air) || !border.contains_block(pos) {
+        return false;
+    }
+
+    let cause = BlockUpdateCause::Player(interaction.player);
+    match PortalFrame::find(state, pos) {
+        Some(frame) => {
+            if !frame.interior().all(|pos| border.contains_block(pos)) {
+                return false;
+            }
+            frame.fill(state, cause);
+        }
+        None => {
+            state.set_block_at(pos, Block::Fire(FireData::default()), cause);
+        }
+    }
+
+    use_igniter(state, interaction);
+    true
+}
+
+/// Uses up the fire charge held by a survival mode player
+/// who lit a block. Item stacks don't track durability,
+/// so flint and steel is never worn down.
+fn use_igniter(state: &DimensionState, interaction: &Interaction) {
+    if interaction.item != Some(Item::FireCharge) || interaction.gamemode != Gamemode::Survival {
+        return;
+    }
+
+    let player = interaction.player;
+    state.exec_with_scheduler(move |world, scheduler| {
+        let slot = match world.get_component_mut::<EntityInventory>(player) {
+            Some(mut inventory) => inventory.consume_item_in_main_hand(),
+            None => return,
+        };
+        scheduler.trigger(InventoryUpdateEvent {
+            slots: smallvec![slot],
+            player,
+        });
+    });
+}
+
+/// Opens or closes both halves of a door.
+fn toggle_door(state: &DimensionState, block: Block, interaction: &Interaction) {
+    let pos = interaction.pos;
+    let other_pos = match block.property("half").as_ref().map(String::as_str) {
+        Some("upper") => Direction::Down.adjacent(pos),
+        _ => Direction::Up.adjacent(pos),
+    };
+
+    let open = !prop_bool(block, "open");
+    let cause = BlockUpdateCause::Player(interaction.player);
+    state.set_block_at(pos, with_prop(block, "open", open), cause.clone());
+
+    if let Some(other) = state.block_at(other_pos) {
+        if mem::discriminant(&other) == mem::discriminant(&block) {
+            state.set_block_at(other_pos, with_prop(other, "open", open), cause);
+        }
+    }
+}
+
+/// Opens or closes a fence gate. A gate opens
+/// away from the player opening it.
+fn toggle_fence_gate(state: &DimensionState, block: Block, interaction: &Interaction) {
+    let mut block = block;
+    let open = !prop_bool(block, "open");
+
+    if open {
+        let player_facing = Direction::from_yaw(interaction.yaw);
+        if facing(block) == Some(player_facing.opposite()) {
+            block = with_prop(block, "facing", player_facing.name());
+        }
+    }
+
+    state.set_block_at(
+        interaction.pos,
+        with_prop(block, "open", open),
+        BlockUpdateCause::Player(interaction.player),
+    );
+}
+
+/// Eats a slice of cake, removing the cake
+/// once the last slice is eaten.
+fn eat_cake(state: &DimensionState, block: Block, interaction: &Interaction) -> bool {
+    // TODO: check and restore the player's hunger
+    match interaction.gamemode {
+        Gamemode::Survival | Gamemode::Adventure => (),
+        _ => return false,
+    }
+
+    let bites = prop_int(block, "bites");
+    let new_block = if bites >= 6 {
+        Block::Air
+    } else {
+        with_prop(block, "bites", bites + 1)
+    };
+
+    state.set_block_at(
+        interaction.pos,
+        new_block,
+        BlockUpdateCause::Player(interaction.player),
+    );
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes_blocks() {
+        let tnt = Block::from_name_and_default_props("minecraft:tnt").unwrap();
+        let door = Block::from_name_and_default_props("minecraft:oak_door").unwrap();
+        let lever = Block::from_name_and_default_props("minecraft:lever").unwrap();
+
+        // Without the build permission, lighting TNT is
+        // rejected before the interaction is performed.
+        assert!(changes_blocks(tnt, Some(Item::FlintAndSteel)));
+        assert!(changes_blocks(tnt, Some(Item::FireCharge)));
+        assert!(!changes_blocks(tnt, None));
+
+        assert!(changes_blocks(Block::Stone, Some(Item::FlintAndSteel)));
+        assert!(!changes_blocks(Block::Stone, None));
+        assert!(!changes_blocks(door, Some(Item::FlintAndSteel)));
+        assert!(!changes_blocks(lever, None));
+
+        // Eating cake removes it slice by slice.
+        let cake = Block::from_name_and_default_props("minecraft:cake").unwrap();
+        assert!(changes_blocks(cake, None));
+        assert!(changes_blocks(cake, Some(Item::FlintAndSteel)));
+    }
+}
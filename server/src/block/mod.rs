@@ -0,0 +1,140 @@
+use crate::block::direction::Direction;
+use crate::worlds::DimensionId;
+use feather_core::{Block, BlockPosition};
+use legion::entity::Entity;
+
+pub mod direction;
+pub mod interaction;
+pub mod placement;
+pub mod rail;
+pub mod random_tick;
+pub mod tick;
+
+/// Event triggered when a block is updated.
+///
+/// This event is triggered *after* the block is updated
+/// in the chunk map.
+#[derive(Debug, Clone)]
+pub struct BlockUpdateEvent {
+    /// The cause of this block update event.
+    pub cause: BlockUpdateCause,
+    /// The dimension of the updated block.
+    pub dimension: DimensionId,
+    /// The location of the block which was updated.
+    pub pos: BlockPosition,
+    /// The block which was previously at the position.
+    pub old_block: Block,
+    /// The new block at the position.
+    pub new_block: Block,
+}
+
+/// The possible causes of a block update event.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum BlockUpdateCause {
+    /// Indicates that a player updated the block.
+    Player(Entity),
+    /// Indicates that a falling block updated the block.
+    FallingBlock,
+    /// Indicates that a flowing fluid updated the block.
+    Fluid,
+    /// Indicates that a scheduled or random block tick updated the block.
+    Tick,
+    /// Indicates that a redstone component updated the block.
+    Redstone,
+    /// Indicates that an explosion destroyed the block.
+    Explosion,
+    /// Indicates that weather, such as lightning
+    /// or snowfall, updated the block.
+    Weather,
+    /// Indicates that the block changed its state
+    /// in response to a neighboring block changing.
+    Neighbor,
+    /// Indicates that the block was part of a portal
+    /// built at a player's destination.
+    Portal,
+}
+
+/// Returns the name of a block without its namespace.
+pub fn block_name(block: Block) -> &'static str {
+    let name = block.name();
+    name.trim_start_matches("minecraft:")
+}
+
+/// Returns the value of a boolean block property.
+///
+/// # Panics
+/// Panics if the block does not have the property.
+pub fn prop_bool(block: Block, name: &str) -> bool {
+    property(block, name) == "true"
+}
+
+/// Returns the value of an integer block property.
+///
+/// # Panics
+/// Panics if the block does not have the property
+/// or the property is not an integer.
+pub fn prop_int(block: Block, name: &str) -> i32 {
+    let value = property(block, name);
+    value
+        .parse()
+        .unwrap_or_else(|_| panic!("{}={} of {} is not an integer", name, value, block.name()))
+}
+
+/// Returns the direction stored in the `facing` property.
+pub fn facing(block: Block) -> Option<Direction> {
+    block
+        .property("facing")
+        .and_then(|value| Direction::from_name(&value))
+}
+
+/// Returns `block` with a property changed.
+///
+/// # Panics
+/// Panics if the block does not have the property or `value`
+/// is not one of its values. Use `Block::with_property` for
+/// values which aren't known to be valid, such as user input.
+pub fn with_prop(block: Block, name: &str, value: impl ToString) -> Block {
+    let value = value.to_string();
+    block
+        .with_property(name, &value)
+        .unwrap_or_else(|| panic!("{} can't have {}={}", block.name(), name, value))
+}
+
+fn property(block: Block, name: &str) -> String {
+    block
+        .property(name)
+        .unwrap_or_else(|| panic!("{} has no property {}", block.name(), name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_blocks::{LeverData, LeverFace, LeverFacing};
+
+    #[test]
+    fn test_properties() {
+        let lever = Block::Lever(LeverData {
+            powered: false,
+            facing: LeverFacing::North,
+            face: LeverFace::Wall,
+        });
+
+        assert!(!prop_bool(lever, "powered"));
+        assert_eq!(facing(lever), Some(Direction::North));
+
+        let powered = with_prop(lever, "powered", true);
+        assert!(prop_bool(powered, "powered"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_with_unknown_property() {
+        with_prop(Block::Lever(LeverData::default()), "power", 15);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_unknown_property() {
+        prop_int(Block::Lever(LeverData::default()), "power");
+    }
+}
@@ -0,0 +1,676 @@
+//! Resolution of the block states placed by players.
+//!
+//! The item in a player's hand only determines the type
+//! of block to place. Its properties (facing, axis, half,
+//! and so on) are derived from how the block was placed: the
+//! player's orientation, the clicked face and the position
+//! of the cursor on that face.
+
+use crate::block::direction::Direction;
+use crate::block::{rail, with_prop, BlockUpdateCause, BlockUpdateEvent};
+use crate::state::{DimensionState, State};
+use feather_core::world::is_in_world_bounds;
+use feather_core::{Block, BlockExt, BlockPosition};
+use smallvec::SmallVec;
+use std::mem;
+
+/// Describes how a block is being placed.
+#[derive(Debug, Clone)]
+pub struct PlacementContext {
+    /// The position at which the block is placed.
+    pub pos: BlockPosition,
+    /// The clicked face, pointing away from
+    /// the block which was clicked.
+    pub face: Direction,
+    /// Whether `pos` is the clicked block itself,
+    /// which happens when it is replaced.
+    pub replacing_clicked: bool,
+    /// The cursor position on the clicked block,
+    /// with each coordinate between 0 and 1.
+    pub cursor: (f32, f32, f32),
+    /// The yaw of the placing player.
+    pub yaw: f32,
+    /// The pitch of the placing player.
+    pub pitch: f32,
+}
+
+impl PlacementContext {
+    /// Returns the horizontal direction the player is facing.
+    fn horizontal_facing(&self) -> Direction {
+        Direction::from_yaw(self.yaw)
+    }
+
+    /// Returns the direction the player is looking.
+    fn look(&self) -> Direction {
+        Direction::from_look(self.yaw, self.pitch)
+    }
+
+    /// Returns whether the upper half of the
+    /// target position was clicked.
+    fn clicked_top_half(&self) -> bool {
+        match self.face {
+            Direction::Down => true,
+            Direction::Up => false,
+            _ => self.cursor.1 > 0.5,
+        }
+    }
+}
+
+/// The blocks set by a placement.
+pub type Placement = SmallVec<[(BlockPosition, Block); 2]>;
+
+fn is_slab(block: Block) -> bool {
+    match block {
+        Block::PrismarineSlab(_)
+        | Block::PrismarineBrickSlab(_)
+        | Block::DarkPrismarineSlab(_)
+        | Block::OakSlab(_)
+        | Block::SpruceSlab(_)
+        | Block::BirchSlab(_)
+        | Block::JungleSlab(_)
+        | Block::AcaciaSlab(_)
+        | Block::DarkOakSlab(_)
+        | Block::StoneSlab(_)
+        | Block::SandstoneSlab(_)
+        | Block::PetrifiedOakSlab(_)
+        | Block::CobblestoneSlab(_)
+        | Block::BrickSlab(_)
+        | Block::StoneBrickSlab(_)
+        | Block::NetherBrickSlab(_)
+        | Block::QuartzSlab(_)
+        | Block::RedSandstoneSlab(_)
+        | Block::PurpurSlab(_) => true,
+        _ => false,
+    }
+}
+
+fn is_stairs(block: Block) -> bool {
+    match block {
+        Block::OakStairs(_)
+        | Block::CobblestoneStairs(_)
+        | Block::BrickStairs(_)
+        | Block::StoneBrickStairs(_)
+        | Block::NetherBrickStairs(_)
+        | Block::SandstoneStairs(_)
+        | Block::SpruceStairs(_)
+        | Block::BirchStairs(_)
+        | Block::JungleStairs(_)
+        | Block::QuartzStairs(_)
+        | Block::AcaciaStairs(_)
+        | Block::DarkOakStairs(_)
+        | Block::PrismarineStairs(_)
+        | Block::PrismarineBrickStairs(_)
+        | Block::DarkPrismarineStairs(_)
+        | Block::RedSandstoneStairs(_)
+        | Block::PurpurStairs(_) => true,
+        _ => false,
+    }
+}
+
+/// Returns whether `a` and `b` are the same
+/// type of block, regardless of their properties.
+fn same_type(a: Block, b: Block) -> bool {
+    mem::discriminant(&a) == mem::discriminant(&b)
+}
+
+/// Returns whether `block` is replaced when
+/// a block is placed into it.
+pub fn is_replaceable(block: Block) -> bool {
+    match block {
+        Block::Air
+        | Block::CaveAir
+        | Block::VoidAir
+        | Block::Water(_)
+        | Block::Lava(_)
+        | Block::Grass
+        | Block::Fern
+        | Block::DeadBush
+        | Block::TallGrass(_)
+        | Block::LargeFern(_)
+        | Block::Seagrass
+        | Block::TallSeagrass(_)
+        | Block::Vine(_) => true,
+        Block::Snow(data) => data.layers == 1,
+        _ => false,
+    }
+}
+
+/// Returns whether placing `block` can replace `existing`,
+/// which is either an unobstructed position or a slab
+/// of the same type which can be doubled.
+pub fn can_place_into(existing: Block, block: Block, ctx: &PlacementContext) -> bool {
+    if is_replaceable(existing) {
+        return true;
+    }
+
+    if !is_slab(block) || !same_type(existing, block) {
+        return false;
+    }
+
+    match existing.property("type").as_ref().map(String::as_str) {
+        Some("bottom") if ctx.replacing_clicked => {
+            ctx.face == Direction::Up || (ctx.face.is_horizontal() && ctx.cursor.1 > 0.5)
+        }
+        Some("top") if ctx.replacing_clicked => {
+            ctx.face == Direction::Down || (ctx.face.is_horizontal() && ctx.cursor.1 <= 0.5)
+        }
+        Some("double") | None => false,
+        _ => true,
+    }
+}
+
+/// Maps a standing block to its wall variant, used
+/// when the block is placed on the side of another.
+fn wall_variant(block: Block) -> Option<Block> {
+    let wall = match block {
+        Block::Torch => Block::WallTorch(Default::default()),
+        Block::RedstoneTorch(_) => Block::RedstoneWallTorch(Default::default()),
+        Block::Sign(_) => Block::WallSign(Default::default()),
+        Block::WhiteBanner(_) => Block::WhiteWallBanner(Default::default()),
+        Block::OrangeBanner(_) => Block::OrangeWallBanner(Default::default()),
+        Block::MagentaBanner(_) => Block::MagentaWallBanner(Default::default()),
+        Block::LightBlueBanner(_) => Block::LightBlueWallBanner(Default::default()),
+        Block::YellowBanner(_) => Block::YellowWallBanner(Default::default()),
+        Block::LimeBanner(_) => Block::LimeWallBanner(Default::default()),
+        Block::PinkBanner(_) => Block::PinkWallBanner(Default::default()),
+        Block::GrayBanner(_) => Block::GrayWallBanner(Default::default()),
+        Block::LightGrayBanner(_) => Block::LightGrayWallBanner(Default::default()),
+        Block::CyanBanner(_) => Block::CyanWallBanner(Default::default()),
+        Block::PurpleBanner(_) => Block::PurpleWallBanner(Default::default()),
+        Block::BlueBanner(_) => Block::BlueWallBanner(Default::default()),
+        Block::BrownBanner(_) => Block::BrownWallBanner(Default::default()),
+        Block::GreenBanner(_) => Block::GreenWallBanner(Default::default()),
+        Block::RedBanner(_) => Block::RedWallBanner(Default::default()),
+        Block::BlackBanner(_) => Block::BlackWallBanner(Default::default()),
+        Block::SkeletonSkull(_) => Block::SkeletonWallSkull(Default::default()),
+        Block::WitherSkeletonSkull(_) => Block::WitherSkeletonWallSkull(Default::default()),
+        Block::ZombieHead(_) => Block::ZombieWallHead(Default::default()),
+        Block::PlayerHead(_) => Block::PlayerWallHead(Default::default()),
+        Block::CreeperHead(_) => Block::CreeperWallHead(Default::default()),
+        Block::DragonHead(_) => Block::DragonWallHead(Default::default()),
+        _ => return None,
+    };
+    Some(wall)
+}
+
+/// Returns the value of a block's `facing` property
+/// when placed, or `None` to leave it unchanged.
+fn placement_facing(block: Block, ctx: &PlacementContext) -> Option<Direction> {
+    let facing = match block {
+        Block::Piston(_) | Block::StickyPiston(_) | Block::Dispenser(_) | Block::Dropper(_) => {
+            ctx.look().opposite()
+        }
+        Block::Observer(_) => ctx.look(),
+        Block::Hopper(_) => match ctx.face.opposite() {
+            Direction::Up => Direction::Down,
+            facing => facing,
+        },
+        Block::EndRod(_) | Block::Ladder(_) => ctx.face,
+        Block::Anvil(_) | Block::ChippedAnvil(_) | Block::DamagedAnvil(_) => {
+            ctx.horizontal_facing().rotate_cw()
+        }
+        Block::Repeater(_) | Block::Comparator(_) => ctx.horizontal_facing().opposite(),
+        Block::ShulkerBox(_)
+        | Block::WhiteShulkerBox(_)
+        | Block::OrangeShulkerBox(_)
+        | Block::MagentaShulkerBox(_)
+        | Block::LightBlueShulkerBox(_)
+        | Block::YellowShulkerBox(_)
+        | Block::LimeShulkerBox(_)
+        | Block::PinkShulkerBox(_)
+        | Block::GrayShulkerBox(_)
+        | Block::LightGrayShulkerBox(_)
+        | Block::CyanShulkerBox(_)
+        | Block::PurpleShulkerBox(_)
+        | Block::BlueShulkerBox(_)
+        | Block::BrownShulkerBox(_)
+        | Block::GreenShulkerBox(_)
+        | Block::RedShulkerBox(_)
+        | Block::BlackShulkerBox(_) => ctx.face,
+        Block::OakTrapdoor(_)
+        | Block::SpruceTrapdoor(_)
+        | Block::BirchTrapdoor(_)
+        | Block::JungleTrapdoor(_)
+        | Block::AcaciaTrapdoor(_)
+        | Block::DarkOakTrapdoor(_)
+        | Block::IronTrapdoor(_) => {
+            if ctx.face.is_horizontal() {
+                ctx.face
+            } else {
+                ctx.horizontal_facing().opposite()
+            }
+        }
+        Block::StoneButton(_)
+        | Block::OakButton(_)
+        | Block::SpruceButton(_)
+        | Block::BirchButton(_)
+        | Block::JungleButton(_)
+        | Block::AcaciaButton(_)
+        | Block::DarkOakButton(_)
+        | Block::Lever(_) => {
+            if ctx.face.is_horizontal() {
+                ctx.face
+            } else {
+                ctx.horizontal_facing()
+            }
+        }
+        Block::WallTorch(_)
+        | Block::RedstoneWallTorch(_)
+        | Block::WallSign(_)
+        | Block::WhiteWallBanner(_)
+        | Block::OrangeWallBanner(_)
+        | Block::MagentaWallBanner(_)
+        | Block::LightBlueWallBanner(_)
+        | Block::YellowWallBanner(_)
+        | Block::LimeWallBanner(_)
+        | Block::PinkWallBanner(_)
+        | Block::GrayWallBanner(_)
+        | Block::LightGrayWallBanner(_)
+        | Block::CyanWallBanner(_)
+        | Block::PurpleWallBanner(_)
+        | Block::BlueWallBanner(_)
+        | Block::BrownWallBanner(_)
+        | Block::GreenWallBanner(_)
+        | Block::RedWallBanner(_)
+        | Block::BlackWallBanner(_)
+        | Block::SkeletonWallSkull(_)
+        | Block::WitherSkeletonWallSkull(_)
+        | Block::ZombieWallHead(_)
+        | Block::PlayerWallHead(_)
+        | Block::CreeperWallHead(_)
+        | Block::DragonWallHead(_)
+        | Block::DeadTubeCoralWallFan(_)
+        | Block::DeadBrainCoralWallFan(_)
+        | Block::DeadBubbleCoralWallFan(_)
+        | Block::DeadFireCoralWallFan(_)
+        | Block::DeadHornCoralWallFan(_)
+        | Block::TubeCoralWallFan(_)
+        | Block::BrainCoralWallFan(_)
+        | Block::BubbleCoralWallFan(_)
+        | Block::FireCoralWallFan(_)
+        | Block::HornCoralWallFan(_) => ctx.face,
+        Block::OakStairs(_)
+        | Block::CobblestoneStairs(_)
+        | Block::BrickStairs(_)
+        | Block::StoneBrickStairs(_)
+        | Block::NetherBrickStairs(_)
+        | Block::SandstoneStairs(_)
+        | Block::SpruceStairs(_)
+        | Block::BirchStairs(_)
+        | Block::JungleStairs(_)
+        | Block::QuartzStairs(_)
+        | Block::AcaciaStairs(_)
+        | Block::DarkOakStairs(_)
+        | Block::PrismarineStairs(_)
+        | Block::PrismarineBrickStairs(_)
+        | Block::DarkPrismarineStairs(_)
+        | Block::RedSandstoneStairs(_)
+        | Block::PurpurStairs(_)
+        | Block::OakDoor(_)
+        | Block::IronDoor(_)
+        | Block::SpruceDoor(_)
+        | Block::BirchDoor(_)
+        | Block::JungleDoor(_)
+        | Block::AcaciaDoor(_)
+        | Block::DarkOakDoor(_)
+        | Block::OakFenceGate(_)
+        | Block::SpruceFenceGate(_)
+        | Block::BirchFenceGate(_)
+        | Block::JungleFenceGate(_)
+        | Block::AcaciaFenceGate(_)
+        | Block::DarkOakFenceGate(_)
+        | Block::WhiteBed(_)
+        | Block::OrangeBed(_)
+        | Block::MagentaBed(_)
+        | Block::LightBlueBed(_)
+        | Block::YellowBed(_)
+        | Block::LimeBed(_)
+        | Block::PinkBed(_)
+        | Block::GrayBed(_)
+        | Block::LightGrayBed(_)
+        | Block::CyanBed(_)
+        | Block::PurpleBed(_)
+        | Block::BlueBed(_)
+        | Block::BrownBed(_)
+        | Block::GreenBed(_)
+        | Block::RedBed(_)
+        | Block::BlackBed(_) => ctx.horizontal_facing(),
+        // Furnaces, chests, pumpkins and the like face the player.
+        _ => ctx.horizontal_facing().opposite(),
+    };
+    Some(facing)
+}
+
+/// Computes the shape of the stairs `block` at `pos`
+/// from the stairs in front of and behind it.
+pub fn stairs_shape(state: &DimensionState, pos: BlockPosition, block: Block) -> &'static str {
+    let facing = match block
+        .property("facing")
+        .and_then(|f| Direction::from_name(&f))
+    {
+        Some(facing) => facing,
+        None => return "straight",
+    };
+    let half = block.property("half");
+
+    let stairs_facing = |pos: BlockPosition| -> Option<Direction> {
+        let other = state.block_at(pos)?;
+        if !is_stairs(other) || other.property("half") != half {
+            return None;
+        }
+        other
+            .property("facing")
+            .and_then(|f| Direction::from_name(&f))
+    };
+
+    // Whether the block in direction `dir` is not stairs
+    // with the same orientation as these stairs.
+    let is_different = |dir: Direction| {
+        let other = dir.adjacent(pos);
+        stairs_facing(other) != Some(facing)
+    };
+
+    if let Some(behind) = stairs_facing(facing.adjacent(pos)) {
+        if behind.axis() != facing.axis() && is_different(behind.opposite()) {
+            return if behind == facing.rotate_ccw() {
+                "outer_left"
+            } else {
+                "outer_right"
+            };
+        }
+    }
+
+    if let Some(front) = stairs_facing(facing.opposite().adjacent(pos)) {
+        if front.axis() != facing.axis() && is_different(front) {
+            return if front == facing.rotate_ccw() {
+                "inner_left"
+            } else {
+                "inner_right"
+            };
+        }
+    }
+
+    "straight"
+}
+
+/// Computes the hinge side of a door placed at `pos`.
+fn door_hinge(state: &DimensionState, block: Block, ctx: &PlacementContext) -> &'static str {
+    let facing = ctx.horizontal_facing();
+    let left = facing.rotate_ccw().adjacent(ctx.pos);
+    let right = facing.rotate_cw().adjacent(ctx.pos);
+
+    let solid = |pos: BlockPosition| {
+        state
+            .block_at(pos)
+            .map_or(false, |block| block.is_solid() && block.is_opaque())
+    };
+    let balance = solid(right) as i32 + solid(Direction::Up.adjacent(right)) as i32
+        - solid(left) as i32
+        - solid(Direction::Up.adjacent(left)) as i32;
+
+    let is_door = |pos: BlockPosition| {
+        state.block_at(pos).map_or(false, |other| {
+            same_type(other, block)
+                && other.property("half").as_ref().map(String::as_str) == Some("lower")
+        })
+    };
+    let door_left = is_door(left);
+    let door_right = is_door(right);
+
+    if (door_left && !door_right) || balance > 0 {
+        return "right";
+    }
+    if (door_right && !door_left) || balance < 0 {
+        return "left";
+    }
+
+    let offset = facing.offset();
+    let (hit_x, hit_z) = (ctx.cursor.0, ctx.cursor.2);
+    if (offset.x < 0 && hit_z < 0.5)
+        || (offset.x > 0 && hit_z > 0.5)
+        || (offset.z < 0 && hit_x > 0.5)
+        || (offset.z > 0 && hit_x < 0.5)
+    {
+        "right"
+    } else {
+        "left"
+    }
+}
+
+/// Returns whether the block at `pos` can be
+/// replaced by the second part of a multi-block structure.
+fn is_free(state: &DimensionState, pos: BlockPosition) -> bool {
+    is_in_world_bounds(pos) && state.block_at(pos).map_or(false, is_replaceable)
+}
+
+/// Resolves the blocks to set when a player places `block`.
+///
+/// Returns `None` if the block cannot be placed, for
+/// example because there is no room for the upper half
+/// of a door.
+pub fn resolve_placement(
+    state: &DimensionState,
+    block: Block,
+    ctx: &PlacementContext,
+) -> Option<Placement> {
+    let existing = state.block_at(ctx.pos)?;
+    let mut result = block;
+
+    // Blocks such as torches and signs have
+    // a separate block for the wall variant.
+    if ctx.face.is_horizontal() {
+        if let Some(wall) = wall_variant(block) {
+            result = wall;
+        }
+    } else if ctx.face == Direction::Down && wall_variant(block).is_some() {
+        // Standing variants can't hang from ceilings.
+        return None;
+    }
+
+    if is_slab(result) {
+        let ty = if same_type(existing, result) {
+            "double"
+        } else if ctx.clicked_top_half() {
+            "top"
+        } else {
+            "bottom"
+        };
+        result = with_prop(result, "type", ty);
+    }
+
+    if result.property("axis").is_some() {
+        result = with_prop(result, "axis", ctx.face.axis());
+    }
+
+    if result.property("face").is_some() {
+        let face = match ctx.face {
+            Direction::Up => "floor",
+            Direction::Down => "ceiling",
+            _ => "wall",
+        };
+        result = with_prop(result, "face", face);
+    }
+
+    if result.property("facing").is_some() {
+        if let Some(facing) = placement_facing(result, ctx) {
+            // Blocks which can't face that way, such as
+            // ladders on the floor, can't be placed.
+            result = result.with_property("facing", facing.name())?;
+        }
+    }
+
+    if result.property("rotation").is_some() {
+        let rotation = (((ctx.yaw + 180.0) * 16.0 / 360.0 + 0.5).floor() as i32) & 15;
+        result = with_prop(result, "rotation", &rotation.to_string());
+    }
+
+    // Leaves placed by players never decay.
+    if result.property("persistent").is_some() {
+        result = with_prop(result, "persistent", true);
+    }
+
+    let half = result.property("half");
+    match half.as_ref().map(String::as_str) {
+        Some("top") | Some("bottom") => {
+            let half = if ctx.clicked_top_half() {
+                "top"
+            } else {
+                "bottom"
+            };
+            result = with_prop(result, "half", half);
+        }
+        _ => (),
+    }
+
+    if result.property("waterlogged").is_some() {
+        let waterlogged = match existing {
+            Block::Water(data) => data.level == 0,
+            _ => false,
+        } && result.property("type").as_ref().map(String::as_str)
+            != Some("double");
+        result = with_prop(result, "waterlogged", &waterlogged.to_string());
+    }
+
+    if is_stairs(result) {
+        result = with_prop(result, "shape", stairs_shape(state, ctx.pos, result));
+    }
+
+    if rail::is_rail(result) {
+        let shape = rail::placement_shape(state, ctx.pos, result);
+        result = with_prop(result, "shape", shape.name());
+    }
+
+    let mut placement = Placement::new();
+
+    // Blocks spanning two positions.
+    match half.as_ref().map(String::as_str) {
+        Some("lower") | Some("upper") => {
+            let upper_pos = Direction::Up.adjacent(ctx.pos);
+            if !is_free(state, upper_pos) {
+                return None;
+            }
+
+            if result.property("hinge").is_some() {
+                result = with_prop(result, "hinge", door_hinge(state, result, ctx));
+            }
+
+            let lower = with_prop(result, "half", "lower");
+            placement.push((ctx.pos, lower));
+            placement.push((upper_pos, with_prop(lower, "half", "upper")));
+            return Some(placement);
+        }
+        _ => (),
+    }
+
+    if result.property("part").is_some() {
+        let facing = ctx.horizontal_facing();
+        let head_pos = facing.adjacent(ctx.pos);
+        if !is_free(state, head_pos) {
+            return None;
+        }
+
+        placement.push((ctx.pos, with_prop(result, "part", "foot")));
+        placement.push((head_pos, with_prop(result, "part", "head")));
+        return Some(placement);
+    }
+
+    placement.push((ctx.pos, result));
+    Some(placement)
+}
+
+/// Event handler which updates the shape of stairs
+/// when a neighboring block changes.
+#[event_handler]
+fn update_stairs_shape(event: &BlockUpdateEvent, state: &State) {
+    let state = &state.dimension(event.dimension);
+    for dir in &Direction::HORIZONTAL {
+        let pos = dir.adjacent(event.pos);
+        let block = match state.block_at(pos) {
+            Some(block) if is_stairs(block) => block,
+            _ => continue,
+        };
+
+        let new_block = with_prop(block, "shape", stairs_shape(state, pos, block));
+        if new_block != block {
+            state.set_block_at(pos, new_block, BlockUpdateCause::Neighbor);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(face: Direction, cursor_y: f32) -> PlacementContext {
+        PlacementContext {
+            pos: BlockPosition::new(0, 64, 0),
+            face,
+            replacing_clicked: true,
+            cursor: (0.5, cursor_y, 0.5),
+            yaw: 0.0,
+            pitch: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_wall_variant() {
+        let wall_name = |name: &str| {
+            let block = Block::from_name_and_default_props(name).unwrap();
+            wall_variant(block).map(|wall| wall.name())
+        };
+
+        assert_eq!(wall_name("minecraft:torch"), Some("minecraft:wall_torch"));
+        assert_eq!(
+            wall_name("minecraft:red_banner"),
+            Some("minecraft:red_wall_banner")
+        );
+        assert_eq!(
+            wall_name("minecraft:creeper_head"),
+            Some("minecraft:creeper_wall_head")
+        );
+        assert_eq!(wall_name("minecraft:piston_head"), None);
+        assert_eq!(wall_name("minecraft:stone"), None);
+    }
+
+    #[test]
+    fn test_can_place_into_slab() {
+        let slab = Block::from_name_and_default_props("minecraft:oak_slab").unwrap();
+        let bottom = with_prop(slab, "type", "bottom");
+        let top = with_prop(slab, "type", "top");
+
+        assert!(can_place_into(bottom, slab, &context(Direction::Up, 0.5)));
+        assert!(!can_place_into(
+            bottom,
+            slab,
+            &context(Direction::North, 0.25)
+        ));
+        assert!(can_place_into(
+            bottom,
+            slab,
+            &context(Direction::North, 0.75)
+        ));
+        assert!(can_place_into(top, slab, &context(Direction::Down, 0.5)));
+        assert!(!can_place_into(top, slab, &context(Direction::Up, 0.5)));
+        assert!(!can_place_into(
+            Block::Stone,
+            slab,
+            &context(Direction::Up, 0.5)
+        ));
+        assert!(can_place_into(
+            Block::Air,
+            Block::Stone,
+            &context(Direction::Up, 0.5)
+        ));
+    }
+
+    #[test]
+    fn test_placement_facing() {
+        let furnace = Block::from_name_and_default_props("minecraft:furnace").unwrap();
+        let stairs = Block::from_name_and_default_props("minecraft:oak_stairs").unwrap();
+
+        // Yaw 0 looks south.
+        let ctx = context(Direction::Up, 0.5);
+        assert_eq!(placement_facing(furnace, &ctx), Some(Direction::North));
+        assert_eq!(placement_facing(stairs, &ctx), Some(Direction::South));
+    }
+}
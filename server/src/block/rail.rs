@@ -0,0 +1,236 @@
+//! Rails, which minecarts follow.
+//!
+//! A rail's `shape` property determines the two directions
+//! in which it connects. Plain rails may also curve; powered,
+//! detector and activator rails are always straight.
+
+use crate::block::block_name;
+use crate::block::direction::Direction;
+use crate::state::DimensionState;
+use feather_core::{Block, BlockPosition};
+
+/// The shape of a rail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RailShape {
+    NorthSouth,
+    EastWest,
+    AscendingNorth,
+    AscendingSouth,
+    AscendingEast,
+    AscendingWest,
+    SouthEast,
+    SouthWest,
+    NorthWest,
+    NorthEast,
+}
+
+impl RailShape {
+    /// Returns the shape of `block`, or `None`
+    /// if it is not a rail.
+    pub fn of(block: Block) -> Option<Self> {
+        if !is_rail(block) {
+            return None;
+        }
+        Self::from_name(&block.property("shape")?)
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        let shape = match name {
+            "north_south" => RailShape::NorthSouth,
+            "east_west" => RailShape::EastWest,
+            "ascending_north" => RailShape::AscendingNorth,
+            "ascending_south" => RailShape::AscendingSouth,
+            "ascending_east" => RailShape::AscendingEast,
+            "ascending_west" => RailShape::AscendingWest,
+            "south_east" => RailShape::SouthEast,
+            "south_west" => RailShape::SouthWest,
+            "north_west" => RailShape::NorthWest,
+            "north_east" => RailShape::NorthEast,
+            _ => return None,
+        };
+        Some(shape)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RailShape::NorthSouth => "north_south",
+            RailShape::EastWest => "east_west",
+            RailShape::AscendingNorth => "ascending_north",
+            RailShape::AscendingSouth => "ascending_south",
+            RailShape::AscendingEast => "ascending_east",
+            RailShape::AscendingWest => "ascending_west",
+            RailShape::SouthEast => "south_east",
+            RailShape::SouthWest => "south_west",
+            RailShape::NorthWest => "north_west",
+            RailShape::NorthEast => "north_east",
+        }
+    }
+
+    /// Returns the straight shape running along `dir`.
+    pub fn straight(dir: Direction) -> Self {
+        match dir.axis() {
+            "x" => RailShape::EastWest,
+            _ => RailShape::NorthSouth,
+        }
+    }
+
+    /// Returns the shape rising toward `dir`.
+    pub fn ascending(dir: Direction) -> Self {
+        match dir {
+            Direction::North => RailShape::AscendingNorth,
+            Direction::South => RailShape::AscendingSouth,
+            Direction::East => RailShape::AscendingEast,
+            _ => RailShape::AscendingWest,
+        }
+    }
+
+    /// Returns the curved shape connecting `a` and `b`, or
+    /// `None` if the directions are not perpendicular.
+    pub fn curve(a: Direction, b: Direction) -> Option<Self> {
+        let shape = match (a, b) {
+            (Direction::South, Direction::East) | (Direction::East, Direction::South) => {
+                RailShape::SouthEast
+            }
+            (Direction::South, Direction::West) | (Direction::West, Direction::South) => {
+                RailShape::SouthWest
+            }
+            (Direction::North, Direction::West) | (Direction::West, Direction::North) => {
+                RailShape::NorthWest
+            }
+            (Direction::North, Direction::East) | (Direction::East, Direction::North) => {
+                RailShape::NorthEast
+            }
+            _ => return None,
+        };
+        Some(shape)
+    }
+
+    /// Returns the two directions in which the rail connects.
+    pub fn exits(self) -> (Direction, Direction) {
+        match self {
+            RailShape::NorthSouth | RailShape::AscendingNorth | RailShape::AscendingSouth => {
+                (Direction::North, Direction::South)
+            }
+            RailShape::EastWest | RailShape::AscendingEast | RailShape::AscendingWest => {
+                (Direction::West, Direction::East)
+            }
+            RailShape::SouthEast => (Direction::South, Direction::East),
+            RailShape::SouthWest => (Direction::South, Direction::West),
+            RailShape::NorthWest => (Direction::North, Direction::West),
+            RailShape::NorthEast => (Direction::North, Direction::East),
+        }
+    }
+
+    /// Returns the direction in which the rail rises,
+    /// or `None` if it is flat.
+    pub fn rising(self) -> Option<Direction> {
+        match self {
+            RailShape::AscendingNorth => Some(Direction::North),
+            RailShape::AscendingSouth => Some(Direction::South),
+            RailShape::AscendingEast => Some(Direction::East),
+            RailShape::AscendingWest => Some(Direction::West),
+            _ => None,
+        }
+    }
+}
+
+/// Returns whether `block` is a rail of any kind.
+pub fn is_rail(block: Block) -> bool {
+    match block_name(block) {
+        "rail" | "powered_rail" | "detector_rail" | "activator_rail" => true,
+        _ => false,
+    }
+}
+
+/// Computes the shape of a rail placed at `pos`
+/// from the rails next to it.
+///
+/// Rails connect to neighboring rails on the same level or
+/// one block lower, and slope up toward rails one block higher.
+pub fn placement_shape(state: &DimensionState, pos: BlockPosition, block: Block) -> RailShape {
+    let is_rail_at = |pos: BlockPosition| state.block_at(pos).map_or(false, is_rail);
+
+    let mut connections = Vec::with_capacity(4);
+    let mut rising = None;
+    for dir in &Direction::HORIZONTAL {
+        let neighbor = dir.adjacent(pos);
+        if is_rail_at(Direction::Up.adjacent(neighbor)) {
+            connections.push(*dir);
+            rising = rising.or(Some(*dir));
+        } else if is_rail_at(neighbor) || is_rail_at(Direction::Down.adjacent(neighbor)) {
+            connections.push(*dir);
+        }
+    }
+
+    if let Some(dir) = rising {
+        return RailShape::ascending(dir);
+    }
+
+    let has = |dir: Direction| connections.contains(&dir);
+    if has(Direction::North) && has(Direction::South) {
+        return RailShape::NorthSouth;
+    }
+    if has(Direction::East) && has(Direction::West) {
+        return RailShape::EastWest;
+    }
+
+    match connections.as_slice() {
+        [] => RailShape::NorthSouth,
+        [dir] => RailShape::straight(*dir),
+        [a, b, ..] => {
+            // Only plain rails can curve.
+            match RailShape::curve(*a, *b) {
+                Some(curve) if block_name(block) == "rail" => curve,
+                _ => RailShape::straight(*a),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shape_names() {
+        for shape in &[
+            RailShape::NorthSouth,
+            RailShape::AscendingWest,
+            RailShape::SouthEast,
+            RailShape::NorthEast,
+        ] {
+            assert_eq!(RailShape::from_name(shape.name()), Some(*shape));
+        }
+        assert_eq!(RailShape::from_name("diagonal"), None);
+    }
+
+    #[test]
+    fn test_exits() {
+        assert_eq!(
+            RailShape::AscendingEast.exits(),
+            (Direction::West, Direction::East)
+        );
+        assert_eq!(
+            RailShape::NorthWest.exits(),
+            (Direction::North, Direction::West)
+        );
+        assert_eq!(RailShape::AscendingSouth.rising(), Some(Direction::South));
+        assert_eq!(RailShape::SouthWest.rising(), None);
+    }
+
+    #[test]
+    fn test_curve() {
+        assert_eq!(
+            RailShape::curve(Direction::East, Direction::North),
+            Some(RailShape::NorthEast)
+        );
+        assert_eq!(RailShape::curve(Direction::East, Direction::West), None);
+    }
+
+    #[test]
+    fn test_of() {
+        let rail = Block::from_name_and_default_props("minecraft:rail").unwrap();
+        assert_eq!(RailShape::of(rail), Some(RailShape::NorthSouth));
+        assert_eq!(RailShape::of(Block::Stone), None);
+    }
+}
@@ -0,0 +1,516 @@
+//! Random block ticks.
+//!
+//! Each tick, `randomTickSpeed` random blocks are chosen
+//! in every non-empty chunk section and ticked. Random ticks
+//! drive slow, probabilistic behavior such as crop growth,
+//! grass spreading, leaf decay and ice melting.
+//!
+//! As in vanilla, leaves store their distance to the nearest
+//! log, which is updated by a scheduled tick whenever a
+//! neighboring block changes. Leaves which are too far from a
+//! log decay on their next random tick, unless they are
+//! persistent, as leaves placed by players are.
+
+use crate::block::tick::{schedule_block_tick, BlockTickEvent, BlockTicks, TickPriority};
+use crate::block::{with_prop, BlockUpdateCause, BlockUpdateEvent};
+use crate::game_rules::{GameRule, WorldGameRules};
+use crate::state::{DimensionState, State};
+use crate::TickCount;
+use feather_blocks::{
+    BeetrootsData, CactusData, CarrotsData, FarmlandData, GrassBlockData, MyceliumData,
+    PotatoesData, SugarCaneData, WaterData, WheatData,
+};
+use feather_core::{Block, BlockExt, BlockPosition};
+use rand::Rng;
+
+/// The distance of leaves which are not connected
+/// to a log within six blocks. Such leaves decay.
+const MAX_LEAVES_DISTANCE: i32 = 7;
+
+/// Delay before leaves update their distance
+/// after a neighboring block changes.
+const LEAVES_UPDATE_DELAY: u64 = 1;
+
+/// Block light level above which ice and snow melt.
+const MELT_LIGHT: u8 = 11;
+
+/// Offsets to all six adjacent blocks.
+const ADJACENT: [BlockPosition; 6] = [
+    BlockPosition::new(0, 1, 0),
+    BlockPosition::new(0, -1, 0),
+    BlockPosition::new(0, 0, -1),
+    BlockPosition::new(0, 0, 1),
+    BlockPosition::new(-1, 0, 0),
+    BlockPosition::new(1, 0, 0),
+];
+
+/// System which performs random ticks.
+#[system]
+fn random_tick(state: &State, rules: &WorldGameRules) {
+    let mut rng = rand::thread_rng();
+
+    for state in state.dimensions() {
+        let speed = rules
+            .get(state.dimension.world)
+            .get_int(GameRule::RandomTickSpeed);
+        if speed > 0 {
+            random_tick_dimension(&state, &mut rng, speed);
+        }
+    }
+}
+
+/// Performs random ticks in a single dimension.
+fn random_tick_dimension(state: &DimensionState, rng: &mut impl Rng, speed: i32) {
+    // Collect blocks first, since ticking them
+    // requires write access to the chunk map.
+    let mut to_tick = vec![];
+    for chunk in state.chunk_map.iter_chunks() {
+        let chunk = chunk.read();
+        let chunk_pos = chunk.position();
+
+        for (section_y, section) in chunk.sections().into_iter().enumerate() {
+            let section = match section {
+                Some(section) if !section.empty() => section,
+                _ => continue,
+            };
+
+            for _ in 0..speed {
+                let (x, y, z) = (
+                    rng.gen_range(0, 16),
+                    rng.gen_range(0, 16),
+                    rng.gen_range(0, 16),
+                );
+                let block = section.block_at(x, y, z);
+
+                if has_random_tick(block) {
+                    let pos = BlockPosition::new(
+                        chunk_pos.x * 16 + x as i32,
+                        (section_y * 16 + y) as i32,
+                        chunk_pos.z * 16 + z as i32,
+                    );
+                    to_tick.push((pos, block));
+                }
+            }
+        }
+    }
+
+    for (pos, block) in to_tick {
+        tick_block(state, rng, pos, block);
+    }
+}
+
+/// Returns whether the given block has random tick behavior.
+fn has_random_tick(block: Block) -> bool {
+    match block {
+        Block::Wheat(_)
+        | Block::Carrots(_)
+        | Block::Potatoes(_)
+        | Block::Beetroots(_)
+        | Block::GrassBlock(_)
+        | Block::Mycelium(_)
+        | Block::SugarCane(_)
+        | Block::Cactus(_)
+        | Block::Farmland(_)
+        | Block::Ice
+        | Block::Snow(_) => true,
+        block => leaves_data(block) == Some((MAX_LEAVES_DISTANCE, false)),
+    }
+}
+
+/// Runs the random tick behavior for a block.
+fn tick_block(state: &DimensionState, rng: &mut impl Rng, pos: BlockPosition, block: Block) {
+    match block {
+        Block::Wheat(_) | Block::Carrots(_) | Block::Potatoes(_) | Block::Beetroots(_) => {
+            grow_crop(state, rng, pos, block)
+        }
+        Block::GrassBlock(_) | Block::Mycelium(_) => spread_grass(state, rng, pos, block),
+        Block::SugarCane(_) | Block::Cactus(_) => grow_column(state, pos, block),
+        Block::Farmland(data) => hydrate_farmland(state, pos, data),
+        Block::Ice | Block::Snow(_) => melt(state, pos, block),
+        block => {
+            if let Some((MAX_LEAVES_DISTANCE, false)) = leaves_data(block) {
+                state.set_block_at(pos, Block::Air, BlockUpdateCause::Tick);
+            }
+        }
+    }
+}
+
+/// Returns the age and maximum age of a crop.
+fn crop_age(block: Block) -> Option<(i32, i32)> {
+    match block {
+        Block::Wheat(data) => Some((data.age, 7)),
+        Block::Carrots(data) => Some((data.age, 7)),
+        Block::Potatoes(data) => Some((data.age, 7)),
+        Block::Beetroots(data) => Some((data.age, 3)),
+        _ => None,
+    }
+}
+
+/// Returns the given crop with its age set to `age`.
+fn with_crop_age(block: Block, age: i32) -> Block {
+    match block {
+        Block::Wheat(_) => Block::Wheat(WheatData { age }),
+        Block::Carrots(_) => Block::Carrots(CarrotsData { age }),
+        Block::Potatoes(_) => Block::Potatoes(PotatoesData { age }),
+        Block::Beetroots(_) => Block::Beetroots(BeetrootsData { age }),
+        block => block,
+    }
+}
+
+/// Grows a crop by one stage, with a chance
+/// depending on the farmland it is planted on.
+fn grow_crop(state: &DimensionState, rng: &mut impl Rng, pos: BlockPosition, block: Block) {
+    let (age, max_age) = match crop_age(block) {
+        Some(age) => age,
+        None => return,
+    };
+
+    if age >= max_age || light_at(state, pos) < 9 {
+        return;
+    }
+
+    let growth = match state.block_at(pos + BlockPosition::new(0, -1, 0)) {
+        Some(Block::Farmland(data)) if data.moisture > 0 => 4,
+        Some(Block::Farmland(_)) => 2,
+        _ => return, // Crops only grow on farmland
+    };
+
+    // Beetroots grow more slowly than other crops.
+    if let Block::Beetroots(_) = block {
+        if rng.gen_range(0, 3) != 0 {
+            return;
+        }
+    }
+
+    if rng.gen_range(0, 25 / growth + 1) == 0 {
+        state.set_block_at(pos, with_crop_age(block, age + 1), BlockUpdateCause::Tick);
+    }
+}
+
+/// Kills grass or mycelium which is covered by an opaque
+/// block, and otherwise spreads it to nearby dirt.
+fn spread_grass(state: &DimensionState, rng: &mut impl Rng, pos: BlockPosition, block: Block) {
+    if !can_be_grass(state, pos) {
+        state.set_block_at(pos, Block::Dirt, BlockUpdateCause::Tick);
+        return;
+    }
+
+    if light_at(state, pos + BlockPosition::new(0, 1, 0)) < 9 {
+        return;
+    }
+
+    for _ in 0..4 {
+        let target = pos
+            + BlockPosition::new(
+                rng.gen_range(-1, 2),
+                rng.gen_range(-3, 2),
+                rng.gen_range(-1, 2),
+            );
+
+        if state.block_at(target) != Some(Block::Dirt) || !can_be_grass(state, target) {
+            continue;
+        }
+
+        let snowy = match state.block_at(target + BlockPosition::new(0, 1, 0)) {
+            Some(Block::Snow(_)) | Some(Block::SnowBlock) => true,
+            _ => false,
+        };
+        let spread = match block {
+            Block::Mycelium(_) => Block::Mycelium(MyceliumData { snowy }),
+            _ => Block::GrassBlock(GrassBlockData { snowy }),
+        };
+        state.set_block_at(target, spread, BlockUpdateCause::Tick);
+    }
+}
+
+/// Returns whether grass can survive at the given position,
+/// i.e. whether the block above it lets light through.
+fn can_be_grass(state: &DimensionState, pos: BlockPosition) -> bool {
+    match state.block_at(pos + BlockPosition::new(0, 1, 0)) {
+        Some(Block::Water(_)) | Some(Block::Lava(_)) => false,
+        Some(above) => !above.is_opaque(),
+        None => true,
+    }
+}
+
+/// Grows sugar cane or cactus upward, up to three blocks tall.
+fn grow_column(state: &DimensionState, pos: BlockPosition, block: Block) {
+    let above_pos = pos + BlockPosition::new(0, 1, 0);
+    if state.block_at(above_pos) != Some(Block::Air) {
+        return;
+    }
+
+    let same_type = |other: Option<Block>| match (block, other) {
+        (Block::SugarCane(_), Some(Block::SugarCane(_))) => true,
+        (Block::Cactus(_), Some(Block::Cactus(_))) => true,
+        _ => false,
+    };
+
+    let mut height = 1;
+    while same_type(state.block_at(pos + BlockPosition::new(0, -height, 0))) {
+        height += 1;
+    }
+    if height >= 3 {
+        return;
+    }
+
+    let (age, with_age): (i32, fn(i32) -> Block) = match block {
+        Block::SugarCane(data) => (data.age, |age| Block::SugarCane(SugarCaneData { age })),
+        Block::Cactus(data) => (data.age, |age| Block::Cactus(CactusData { age })),
+        _ => return,
+    };
+
+    if age == 15 {
+        state.set_block_at(above_pos, with_age(0), BlockUpdateCause::Tick);
+        state.set_block_at(pos, with_age(0), BlockUpdateCause::Tick);
+    } else {
+        state.set_block_at(pos, with_age(age + 1), BlockUpdateCause::Tick);
+    }
+}
+
+/// Moistens farmland near water and dries it out otherwise,
+/// eventually turning it back into dirt.
+fn hydrate_farmland(state: &DimensionState, pos: BlockPosition, data: FarmlandData) {
+    let near_water = (-4..=4).any(|x| {
+        (0..=1).any(|y| {
+            (-4..=4).any(
+                |z| match state.block_at(pos + BlockPosition::new(x, y, z)) {
+                    Some(Block::Water(_)) => true,
+                    _ => false,
+                },
+            )
+        })
+    });
+
+    let new_block = if near_water {
+        Block::Farmland(FarmlandData { moisture: 7 })
+    } else if data.moisture > 0 {
+        Block::Farmland(FarmlandData {
+            moisture: data.moisture - 1,
+        })
+    } else if crop_age(
+        state
+            .block_at(pos + BlockPosition::new(0, 1, 0))
+            .unwrap_or(Block::Air),
+    )
+    .is_some()
+    {
+        return; // Crops keep dry farmland from turning to dirt
+    } else {
+        Block::Dirt
+    };
+
+    if new_block != Block::Farmland(data) {
+        state.set_block_at(pos, new_block, BlockUpdateCause::Tick);
+    }
+}
+
+/// Melts ice and snow layers near bright light sources.
+fn melt(state: &DimensionState, pos: BlockPosition, block: Block) {
+    if block_light_at(state, pos) <= MELT_LIGHT {
+        return;
+    }
+
+    let melted = match block {
+        Block::Ice => Block::Water(WaterData { level: 0 }),
+        _ => Block::Air,
+    };
+    state.set_block_at(pos, melted, BlockUpdateCause::Tick);
+}
+
+/// Returns the distance and persistence of leaves.
+fn leaves_data(block: Block) -> Option<(i32, bool)> {
+    match block {
+        Block::OakLeaves(data) => Some((data.distance, data.persistent)),
+        Block::SpruceLeaves(data) => Some((data.distance, data.persistent)),
+        Block::BirchLeaves(data) => Some((data.distance, data.persistent)),
+        Block::JungleLeaves(data) => Some((data.distance, data.persistent)),
+        Block::AcaciaLeaves(data) => Some((data.distance, data.persistent)),
+        Block::DarkOakLeaves(data) => Some((data.distance, data.persistent)),
+        _ => None,
+    }
+}
+
+/// Returns whether the given block is a log which supports leaves.
+fn is_log(block: Block) -> bool {
+    match block {
+        Block::OakLog(_)
+        | Block::SpruceLog(_)
+        | Block::BirchLog(_)
+        | Block::JungleLog(_)
+        | Block::AcaciaLog(_)
+        | Block::DarkOakLog(_)
+        | Block::StrippedOakLog(_)
+        | Block::StrippedSpruceLog(_)
+        | Block::StrippedBirchLog(_)
+        | Block::StrippedJungleLog(_)
+        | Block::StrippedAcaciaLog(_)
+        | Block::StrippedDarkOakLog(_)
+        | Block::OakWood(_)
+        | Block::SpruceWood(_)
+        | Block::BirchWood(_)
+        | Block::JungleWood(_)
+        | Block::AcaciaWood(_)
+        | Block::DarkOakWood(_) => true,
+        _ => false,
+    }
+}
+
+/// Returns the distance of leaves to a log
+/// if `block` is placed next to them.
+fn distance_through(block: Block) -> i32 {
+    if is_log(block) {
+        return 0;
+    }
+
+    leaves_data(block).map_or(MAX_LEAVES_DISTANCE, |(distance, _)| distance)
+}
+
+/// Returns the leaves at `pos` with their distance
+/// recomputed from the neighboring blocks.
+fn update_leaves_distance(state: &DimensionState, pos: BlockPosition, leaves: Block) -> Block {
+    let distance = ADJACENT
+        .iter()
+        .filter_map(|offset| state.block_at(pos + *offset))
+        .map(|block| distance_through(block) + 1)
+        .min()
+        .unwrap_or(MAX_LEAVES_DISTANCE)
+        .min(MAX_LEAVES_DISTANCE);
+
+    with_prop(leaves, "distance", distance)
+}
+
+/// Event handler which schedules leaves to update their
+/// distance to a log when they or a neighboring block change.
+#[event_handler]
+fn leaves_schedule(
+    events: &[BlockUpdateEvent],
+    state: &State,
+    ticks: &mut BlockTicks,
+    tick_count: &TickCount,
+) {
+    for event in events {
+        let state = &state.dimension(event.dimension);
+        let positions = ADJACENT.iter().map(|offset| event.pos + *offset);
+
+        for pos in std::iter::once(event.pos).chain(positions) {
+            let leaves = match state.block_at(pos) {
+                Some(block) if leaves_data(block).is_some() => block,
+                _ => continue,
+            };
+
+            if update_leaves_distance(state, pos, leaves) != leaves {
+                schedule_block_tick(
+                    ticks,
+                    tick_count,
+                    event.dimension,
+                    pos,
+                    leaves,
+                    LEAVES_UPDATE_DELAY,
+                    TickPriority::Normal,
+                );
+            }
+        }
+    }
+}
+
+/// Event handler which updates the distance of leaves
+/// to a log, which in turn updates their neighbors.
+#[event_handler]
+fn leaves_update(events: &[BlockTickEvent], state: &State) {
+    for event in events {
+        if leaves_data(event.block).is_none() {
+            continue;
+        }
+
+        let state = &state.dimension(event.dimension);
+        let updated = update_leaves_distance(state, event.pos, event.block);
+        if updated != event.block {
+            state.set_block_at(event.pos, updated, BlockUpdateCause::Tick);
+        }
+    }
+}
+
+/// Returns the combined sky and block light level at a position.
+fn light_at(state: &DimensionState, pos: BlockPosition) -> u8 {
+    let sky_light = state
+        .chunk_at(pos.chunk_pos())
+        .filter(|_| pos.y >= 0 && pos.y < 256)
+        .map(|chunk| chunk.sky_light_at(pos.x as usize & 0xf, pos.y as usize, pos.z as usize & 0xf))
+        .unwrap_or(15);
+
+    sky_light.max(block_light_at(state, pos))
+}
+
+/// Returns the block light level at a position.
+///
+/// Block light is not yet propagated, so this is approximated
+/// from the light emitted by blocks near the position.
+fn block_light_at(state: &DimensionState, pos: BlockPosition) -> u8 {
+    const RADIUS: i32 = 3;
+
+    let mut light = 0;
+    for x in -RADIUS..=RADIUS {
+        for y in -RADIUS..=RADIUS {
+            for z in -RADIUS..=RADIUS {
+                let distance = x.abs() + y.abs() + z.abs();
+                if distance > RADIUS {
+                    continue;
+                }
+
+                if let Some(block) = state.block_at(pos + BlockPosition::new(x, y, z)) {
+                    light = light.max(block.light_emission().saturating_sub(distance as u8));
+                }
+            }
+        }
+    }
+
+    light
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crop_age() {
+        let wheat = Block::Wheat(WheatData { age: 3 });
+        assert_eq!(crop_age(wheat), Some((3, 7)));
+        assert_eq!(crop_age(with_crop_age(wheat, 4)), Some((4, 7)));
+
+        let beetroots = Block::Beetroots(BeetrootsData { age: 0 });
+        assert_eq!(crop_age(beetroots), Some((0, 3)));
+        assert_eq!(crop_age(Block::Stone), None);
+    }
+
+    #[test]
+    fn test_has_random_tick() {
+        assert!(has_random_tick(Block::Ice));
+        assert!(has_random_tick(
+            Block::from_name_and_default_props("minecraft:oak_leaves").unwrap()
+        ));
+        assert!(!has_random_tick(Block::Stone));
+        assert!(!has_random_tick(Block::Air));
+    }
+
+    #[test]
+    fn test_leaves_random_tick() {
+        let leaves = Block::from_name_and_default_props("minecraft:oak_leaves").unwrap();
+        let near_log = with_prop(leaves, "distance", 3);
+        let persistent = with_prop(leaves, "persistent", true);
+
+        assert_eq!(leaves_data(leaves), Some((MAX_LEAVES_DISTANCE, false)));
+        assert!(!has_random_tick(near_log));
+        assert!(!has_random_tick(persistent));
+    }
+
+    #[test]
+    fn test_distance_through() {
+        let log = Block::from_name_and_default_props("minecraft:oak_log").unwrap();
+        let leaves = Block::from_name_and_default_props("minecraft:birch_leaves").unwrap();
+
+        assert_eq!(distance_through(log), 0);
+        assert_eq!(distance_through(with_prop(leaves, "distance", 2)), 2);
+        assert_eq!(distance_through(Block::Stone), MAX_LEAVES_DISTANCE);
+    }
+}
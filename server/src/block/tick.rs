@@ -0,0 +1,472 @@
+//! Scheduled block ticks.
+//!
+//! Blocks such as repeaters or redstone torches need to
+//! update themselves some time after something happens to them.
+//! This is implemented by scheduling a tick in the `BlockTicks`
+//! queue, which triggers a `BlockTickEvent` once the delay has elapsed.
+//!
+//! Pending ticks are saved with their chunk in the `TileTicks`
+//! tag and rescheduled when the chunk is loaded again.
+//!
+//! Each dimension has its own tick queue.
+
+use crate::chunk_logic::ChunkLoadEvent;
+use crate::state::State;
+use crate::worlds::DimensionId;
+use crate::TickCount;
+use feather_core::region::PendingBlockTick;
+use feather_core::{Block, BlockPosition, ChunkPosition};
+use hashbrown::HashMap;
+use std::collections::BTreeMap;
+use std::mem;
+use tonks::Trigger;
+
+/// The priority of a scheduled tick. Ticks
+/// scheduled for the same game tick are run
+/// in order of priority, highest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TickPriority {
+    ExtremelyHigh = -3,
+    VeryHigh = -2,
+    High = -1,
+    Normal = 0,
+    Low = 1,
+    VeryLow = 2,
+    ExtremelyLow = 3,
+}
+
+impl TickPriority {
+    /// Returns the priority with the given
+    /// numeric value, clamped to the valid range.
+    pub fn from_value(value: i32) -> Self {
+        match value {
+            value if value <= -3 => TickPriority::ExtremelyHigh,
+            -2 => TickPriority::VeryHigh,
+            -1 => TickPriority::High,
+            0 => TickPriority::Normal,
+            1 => TickPriority::Low,
+            2 => TickPriority::VeryLow,
+            _ => TickPriority::ExtremelyLow,
+        }
+    }
+
+    /// Returns the numeric value of this priority,
+    /// as stored in the world save.
+    pub fn value(self) -> i32 {
+        self as i32
+    }
+}
+
+impl Default for TickPriority {
+    fn default() -> Self {
+        TickPriority::Normal
+    }
+}
+
+/// Key which orders ticks within a `TickQueue`: first
+/// by time, then by priority, then by scheduling order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct TickKey {
+    at: u64,
+    priority: TickPriority,
+    id: u64,
+}
+
+/// A tick which has been removed from a `TickQueue`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduledTick<T> {
+    /// The position to tick.
+    pub pos: BlockPosition,
+    /// The tick count at which the tick was to run.
+    pub at: u64,
+    /// The priority of the tick.
+    pub priority: TickPriority,
+    /// Data stored with the tick.
+    pub data: T,
+}
+
+/// A queue of ticks scheduled for block positions.
+///
+/// At most one tick is pending for any given position;
+/// scheduling a position which already has a tick pending
+/// keeps whichever of the two ticks would run first.
+#[derive(Debug)]
+pub struct TickQueue<T> {
+    queue: BTreeMap<TickKey, BlockPosition>,
+    scheduled: HashMap<BlockPosition, (TickKey, T)>,
+    next_id: u64,
+}
+
+impl<T> Default for TickQueue<T> {
+    fn default() -> Self {
+        Self {
+            queue: BTreeMap::new(),
+            scheduled: HashMap::new(),
+            next_id: 0,
+        }
+    }
+}
+
+impl<T> TickQueue<T> {
+    /// Schedules a tick for `pos` at tick count `at`.
+    pub fn schedule(&mut self, pos: BlockPosition, at: u64, priority: TickPriority, data: T) {
+        let key = TickKey {
+            at,
+            priority,
+            id: self.next_id,
+        };
+
+        if let Some((existing, _)) = self.scheduled.get(&pos) {
+            if *existing <= key {
+                return;
+            }
+            self.queue.remove(existing);
+        }
+
+        self.next_id += 1;
+        self.queue.insert(key, pos);
+        self.scheduled.insert(pos, (key, data));
+    }
+
+    /// Returns whether a tick is pending for the given position.
+    pub fn is_scheduled(&self, pos: BlockPosition) -> bool {
+        self.scheduled.contains_key(&pos)
+    }
+
+    /// Returns the number of pending ticks.
+    pub fn len(&self) -> usize {
+        self.scheduled.len()
+    }
+
+    /// Returns whether no ticks are pending.
+    pub fn is_empty(&self) -> bool {
+        self.scheduled.is_empty()
+    }
+
+    /// Removes and returns all ticks which are due at or
+    /// before tick count `now`, in the order they should run.
+    pub fn drain_due(&mut self, now: u64) -> Vec<ScheduledTick<T>> {
+        let mut due = vec![];
+
+        while let Some(key) = self.queue.keys().next().copied() {
+            if key.at > now {
+                break;
+            }
+
+            let pos = self.queue.remove(&key).unwrap();
+            let (_, data) = self.scheduled.remove(&pos).unwrap();
+            due.push(ScheduledTick {
+                pos,
+                at: key.at,
+                priority: key.priority,
+                data,
+            });
+        }
+
+        due
+    }
+
+    /// Removes and returns all pending ticks in
+    /// the given chunk. This is used when saving chunks.
+    pub fn drain_chunk(&mut self, chunk: ChunkPosition) -> Vec<ScheduledTick<T>> {
+        let positions: Vec<BlockPosition> = self
+            .scheduled
+            .keys()
+            .copied()
+            .filter(|pos| pos.chunk_pos() == chunk)
+            .collect();
+
+        let mut ticks: Vec<ScheduledTick<T>> = positions
+            .into_iter()
+            .map(|pos| {
+                let (key, data) = self.scheduled.remove(&pos).unwrap();
+                self.queue.remove(&key);
+                ScheduledTick {
+                    pos,
+                    at: key.at,
+                    priority: key.priority,
+                    data,
+                }
+            })
+            .collect();
+
+        ticks.sort_by_key(|tick| (tick.at, tick.priority));
+        ticks
+    }
+
+    /// Returns copies of all pending ticks in the given
+    /// chunk without removing them from the queue.
+    pub fn chunk_ticks(&self, chunk: ChunkPosition) -> Vec<ScheduledTick<T>>
+    where
+        T: Clone,
+    {
+        let mut ticks: Vec<ScheduledTick<T>> = self
+            .scheduled
+            .iter()
+            .filter(|(pos, _)| pos.chunk_pos() == chunk)
+            .map(|(pos, (key, data))| ScheduledTick {
+                pos: *pos,
+                at: key.at,
+                priority: key.priority,
+                data: data.clone(),
+            })
+            .collect();
+
+        ticks.sort_by_key(|tick| (tick.at, tick.priority));
+        ticks
+    }
+}
+
+/// A set of tick queues, one for each dimension.
+#[derive(Debug)]
+pub struct DimensionTicks<T>(HashMap<DimensionId, TickQueue<T>>);
+
+impl<T> Default for DimensionTicks<T> {
+    fn default() -> Self {
+        Self(HashMap::new())
+    }
+}
+
+impl<T> DimensionTicks<T> {
+    /// Returns the tick queue for the given dimension.
+    pub fn queue(&mut self, dimension: DimensionId) -> &mut TickQueue<T> {
+        self.0.entry(dimension).or_default()
+    }
+
+    /// Removes and returns all ticks which are due at or
+    /// before tick count `now` in every dimension.
+    pub fn drain_due(&mut self, now: u64) -> Vec<(DimensionId, ScheduledTick<T>)> {
+        self.0
+            .iter_mut()
+            .flat_map(|(dimension, queue)| {
+                let dimension = *dimension;
+                queue
+                    .drain_due(now)
+                    .into_iter()
+                    .map(move |tick| (dimension, tick))
+            })
+            .collect()
+    }
+}
+
+/// The queues of scheduled block ticks. The data
+/// stored with each tick is the block which
+/// the tick was scheduled for.
+#[derive(Debug, Default, Resource, Deref, DerefMut)]
+pub struct BlockTicks(pub DimensionTicks<Block>);
+
+impl BlockTicks {
+    /// Removes the pending ticks in the given chunk
+    /// and converts them to the format stored in
+    /// the world save.
+    pub fn drain_chunk_for_save(
+        &mut self,
+        dimension: DimensionId,
+        chunk: ChunkPosition,
+        now: u64,
+    ) -> Vec<PendingBlockTick> {
+        self.queue(dimension)
+            .drain_chunk(chunk)
+            .into_iter()
+            .map(|tick| pending_block_tick(tick, now))
+            .collect()
+    }
+
+    /// Returns the pending ticks in the given chunk in the
+    /// format stored in the world save, leaving them queued.
+    pub fn chunk_ticks_for_save(
+        &mut self,
+        dimension: DimensionId,
+        chunk: ChunkPosition,
+        now: u64,
+    ) -> Vec<PendingBlockTick> {
+        self.queue(dimension)
+            .chunk_ticks(chunk)
+            .into_iter()
+            .map(|tick| pending_block_tick(tick, now))
+            .collect()
+    }
+}
+
+fn pending_block_tick(tick: ScheduledTick<Block>, now: u64) -> PendingBlockTick {
+    PendingBlockTick {
+        pos: tick.pos,
+        block: tick.data.to_name_and_props().0.to_string(),
+        delay: tick.at.saturating_sub(now) as i32,
+        priority: tick.priority.value(),
+    }
+}
+
+/// Event triggered when a scheduled tick runs
+/// for a block.
+///
+/// This is only triggered if the block at the
+/// position is of the same type as when the tick
+/// was scheduled.
+#[derive(Debug, Clone)]
+pub struct BlockTickEvent {
+    /// The dimension of the ticked block.
+    pub dimension: DimensionId,
+    /// The position of the ticked block.
+    pub pos: BlockPosition,
+    /// The block at the position.
+    pub block: Block,
+}
+
+/// Schedules a tick for the block at `pos` to run after `delay` ticks.
+pub fn schedule_block_tick(
+    ticks: &mut BlockTicks,
+    tick_count: &TickCount,
+    dimension: DimensionId,
+    pos: BlockPosition,
+    block: Block,
+    delay: u64,
+    priority: TickPriority,
+) {
+    ticks
+        .queue(dimension)
+        .schedule(pos, tick_count.0 + delay, priority, block);
+}
+
+/// System which runs scheduled block ticks which are due.
+#[system]
+fn block_tick(
+    state: &State,
+    ticks: &mut BlockTicks,
+    tick_count: &TickCount,
+    trigger: &mut Trigger<BlockTickEvent>,
+) {
+    for (dimension, tick) in ticks.drain_due(tick_count.0) {
+        let block = match state.dimension(dimension).block_at(tick.pos) {
+            Some(block) => block,
+            None => continue,
+        };
+
+        // Ticks are only valid for the block type they were scheduled for.
+        if mem::discriminant(&block) != mem::discriminant(&tick.data) {
+            continue;
+        }
+
+        trigger.trigger(BlockTickEvent {
+            dimension,
+            pos: tick.pos,
+            block,
+        });
+    }
+}
+
+/// Event handler which reschedules block ticks
+/// which were pending when a chunk was saved.
+#[event_handler]
+fn block_tick_schedule_on_chunk_load(
+    event: &ChunkLoadEvent,
+    ticks: &mut BlockTicks,
+    tick_count: &TickCount,
+) {
+    for tick in &event.ticks.blocks {
+        let block = match Block::from_name_and_default_props(&tick.block) {
+            Some(block) => block,
+            None => {
+                warn!("Unknown block {} in saved block tick", tick.block);
+                continue;
+            }
+        };
+
+        schedule_block_tick(
+            ticks,
+            tick_count,
+            event.dimension,
+            tick.pos,
+            block,
+            tick.delay.max(0) as u64,
+            TickPriority::from_value(tick.priority),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tick_queue_order() {
+        let mut queue = TickQueue::default();
+        let a = BlockPosition::new(0, 0, 0);
+        let b = BlockPosition::new(1, 0, 0);
+        let c = BlockPosition::new(2, 0, 0);
+
+        queue.schedule(a, 10, TickPriority::Normal, 'a');
+        queue.schedule(b, 10, TickPriority::High, 'b');
+        queue.schedule(c, 5, TickPriority::Low, 'c');
+
+        let order: Vec<char> = queue.drain_due(10).into_iter().map(|t| t.data).collect();
+        assert_eq!(order, vec!['c', 'b', 'a']);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_tick_queue_earliest_wins() {
+        let mut queue = TickQueue::default();
+        let pos = BlockPosition::new(0, 64, 0);
+
+        queue.schedule(pos, 30, TickPriority::Normal, 30);
+        queue.schedule(pos, 5, TickPriority::Normal, 5);
+        queue.schedule(pos, 10, TickPriority::Normal, 10);
+        assert_eq!(queue.len(), 1);
+
+        assert!(queue.drain_due(4).is_empty());
+        let due = queue.drain_due(5);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].data, 5);
+        assert!(!queue.is_scheduled(pos));
+        assert!(queue.drain_due(30).is_empty());
+    }
+
+    #[test]
+    fn test_tick_queue_drain_chunk() {
+        let mut queue = TickQueue::default();
+        let inside = BlockPosition::new(17, 64, 3);
+        let outside = BlockPosition::new(-1, 64, 3);
+
+        queue.schedule(inside, 5, TickPriority::Normal, ());
+        queue.schedule(outside, 5, TickPriority::Normal, ());
+
+        let drained = queue.drain_chunk(ChunkPosition::new(1, 0));
+        assert_eq!(drained.len(), 1);
+        assert_eq!(drained[0].pos, inside);
+
+        let due = queue.drain_due(5);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].pos, outside);
+    }
+
+    #[test]
+    fn test_tick_queue_chunk_ticks() {
+        let mut queue = TickQueue::default();
+        let inside = BlockPosition::new(17, 64, 3);
+        let outside = BlockPosition::new(-1, 64, 3);
+
+        queue.schedule(inside, 5, TickPriority::Normal, ());
+        queue.schedule(outside, 5, TickPriority::Normal, ());
+
+        let ticks = queue.chunk_ticks(ChunkPosition::new(1, 0));
+        assert_eq!(ticks.len(), 1);
+        assert_eq!(ticks[0].pos, inside);
+
+        assert_eq!(queue.len(), 2);
+        assert!(queue.is_scheduled(inside));
+    }
+
+    #[test]
+    fn test_tick_priority_from_value() {
+        for priority in &[
+            TickPriority::ExtremelyHigh,
+            TickPriority::Normal,
+            TickPriority::ExtremelyLow,
+        ] {
+            assert_eq!(TickPriority::from_value(priority.value()), *priority);
+        }
+        assert_eq!(TickPriority::from_value(-10), TickPriority::ExtremelyHigh);
+        assert_eq!(TickPriority::from_value(10), TickPriority::ExtremelyLow);
+    }
+}
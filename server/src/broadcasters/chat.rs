@@ -1,19 +1,27 @@
 //! Broadcasting of chat messages
 
-use crate::player::chat::{ChatBroadcastEvent, ChatPosition};
-use crate::state::State;
+use crate::locale::{Locale, Translations};
+use crate::network::Network;
+use crate::player::chat::{ChatBroadcastEvent, ChatSettings};
 use feather_core::network::packet::implementation::ChatMessageClientbound;
+use legion::query::Read;
+use tonks::{PreparedWorld, Query};
 
-/// System that broadcasts chat messages to all players
+/// System that broadcasts chat messages to all players whose
+/// chat mode shows them, in each player's language
 #[event_handler]
-fn broadcast_chat(event: &ChatBroadcastEvent, state: &State) {
-    let packet = ChatMessageClientbound {
-        json_data: event.json_data.clone(),
-        position: match event.position {
-            ChatPosition::Chat => 0,
-            ChatPosition::SystemMessage => 1,
-            ChatPosition::GameInfo => 2,
-        },
-    };
-    state.broadcast_global(packet, None);
+fn broadcast_chat(
+    event: &ChatBroadcastEvent,
+    translations: &Translations,
+    query: &mut Query<(Read<Network>, Read<ChatSettings>, Read<Locale>)>,
+    world: &mut PreparedWorld,
+) {
+    query.par_entities_for_each(world, |(_, (network, settings, locale))| {
+        if settings.mode.shows(event.position) {
+            network.send(ChatMessageClientbound {
+                message: settings.apply_colors(translations.render(&locale.0, &event.message)),
+                position: event.position.id(),
+            });
+        }
+    });
 }
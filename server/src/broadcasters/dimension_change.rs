@@ -0,0 +1,67 @@
+use crate::chunk_logic::ChunkHolders;
+use crate::entity::{DimensionChangeEvent, EntityId, SpawnPacketCreator};
+use crate::network::Network;
+use crate::state::State;
+use feather_core::network::packet::implementation::DestroyEntities;
+use feather_core::Position;
+use legion::query::Read;
+use rayon::prelude::*;
+use tonks::{PreparedWorld, Query, QueryAccessor};
+
+/// When an entity changes dimension, despawns it for the clients
+/// in its old dimension and spawns it for those in its new one.
+#[event_handler]
+fn broadcast_dimension_change(
+    events: &[DimensionChangeEvent],
+    holders: &ChunkHolders,
+    accessor: &QueryAccessor<Read<SpawnPacketCreator>>,
+    _query: &mut Query<(Read<Network>, Read<Position>, Read<EntityId>)>,
+    world: &mut PreparedWorld,
+    state: &State,
+) {
+    events.par_iter().for_each(|event: &DimensionChangeEvent| {
+        let id = world.get_component::<EntityId>(event.entity).unwrap().0;
+
+        let old_chunk = event.old_position.chunk_pos();
+        for client in holders
+            .holders_for(event.old_dimension, old_chunk)
+            .unwrap_or(&[])
+        {
+            if *client == event.entity {
+                continue;
+            }
+            if let Some(network) = world.get_component::<Network>(*client) {
+                network.send(DestroyEntities {
+                    entity_ids: vec![id],
+                });
+                state.register_entity_unload(event.entity, *client);
+            }
+        }
+
+        let accessor = match accessor.find(event.entity) {
+            Some(accessor) => accessor,
+            None => return,
+        };
+        let packet_creator = match accessor.get_component::<SpawnPacketCreator>(world) {
+            Some(packet_creator) => packet_creator,
+            None => return,
+        };
+
+        let new_chunk = world
+            .get_component::<Position>(event.entity)
+            .unwrap()
+            .chunk_pos();
+        for client in holders
+            .holders_for(event.new_dimension, new_chunk)
+            .unwrap_or(&[])
+        {
+            if *client == event.entity {
+                continue;
+            }
+            if let Some(network) = world.get_component::<Network>(*client) {
+                network.send_boxed(packet_creator.get(&accessor, world));
+                state.register_entity_send(event.entity, *client);
+            }
+        }
+    });
+}
@@ -3,6 +3,7 @@ use crate::entity::{CreationPacketCreator, EntityCreateEvent, SpawnPacketCreator
 use crate::network::Network;
 use crate::player::PlayerJoinEvent;
 use crate::state::State;
+use crate::worlds::DimensionId;
 use feather_core::Position;
 use legion::query::Read;
 use rayon::prelude::*;
@@ -18,6 +19,7 @@ fn broadcast_entity_creation(
     accessor2: &QueryAccessor<Read<SpawnPacketCreator>>,
     _query: &mut Query<(
         Read<Position>,
+        Read<DimensionId>,
         Read<crate::metadata::Metadata>,
         Read<crate::entity::EntityId>,
         Read<crate::network::Network>,
@@ -26,6 +28,11 @@ fn broadcast_entity_creation(
     holders: &ChunkHolders,
 ) {
     events.par_iter().for_each(|event: &EntityCreateEvent| {
+        let dimension = match world.get_component::<DimensionId>(event.entity) {
+            Some(dimension) => *dimension,
+            None => return,
+        };
+
         if let Some(accessor) = accessor1.find(event.entity) {
             if let Some(packet_creator) = accessor.get_component::<CreationPacketCreator>(world) {
                 let packet = packet_creator.get(&accessor, world);
@@ -44,7 +51,7 @@ fn broadcast_entity_creation(
                         .get_component::<Position>(event.entity)
                         .unwrap()
                         .chunk_pos();
-                    for entity in holders.holders_for(chunk).unwrap_or(&[]) {
+                    for entity in holders.holders_for(dimension, chunk).unwrap_or(&[]) {
                         if let Some(network) =
                             world.get_component::<crate::network::Network>(*entity)
                         {
@@ -70,7 +77,7 @@ fn broadcast_entity_creation(
             .get_component::<Position>(event.entity)
             .unwrap()
             .chunk_pos();
-        for entity in holders.holders_for(chunk).unwrap_or(&[]) {
+        for entity in holders.holders_for(dimension, chunk).unwrap_or(&[]) {
             state.register_entity_send(event.entity, *entity);
         }
     });
@@ -17,10 +17,10 @@ fn broadcast_entity_deletion(
     state: &State,
 ) {
     events.par_iter().for_each(|event: &EntityDeleteEvent| {
-        if let Some(pos) = event.position {
+        if let (Some(pos), Some(dimension)) = (event.position, event.dimension) {
             let chunk = pos.chunk_pos();
 
-            for entity in holders.holders_for(chunk).unwrap_or(&[]) {
+            for entity in holders.holders_for(dimension, chunk).unwrap_or(&[]) {
                 if let Some(network) = world.get_component::<Network>(*entity) {
                     network.send(DestroyEntities {
                         entity_ids: vec![event.id.0],
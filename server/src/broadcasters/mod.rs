@@ -9,6 +9,7 @@
 mod animation;
 mod block;
 mod chat;
+mod dimension_change;
 pub mod entity_creation;
 pub mod entity_deletion;
 mod inventory;
@@ -5,6 +5,7 @@ use crate::entity::{EntityId, EntityMoveEvent, Velocity, VelocityUpdateEvent};
 use crate::network::Network;
 use crate::state::State;
 use crate::util::{calculate_relative_move, degrees_to_stops, protocol_velocity};
+use crate::worlds::DimensionId;
 use feather_core::network::packet::implementation::{
     EntityHeadLook, EntityLook, EntityLookAndRelativeMove, EntityRelativeMove, EntityVelocity,
 };
@@ -28,6 +29,7 @@ fn broadcast_move(
     _query: &mut Query<(
         Read<Network>,
         Read<Position>,
+        Read<DimensionId>,
         Write<LastKnownPositions>,
         Read<EntityId>,
     )>,
@@ -37,10 +39,11 @@ fn broadcast_move(
     events.iter().for_each(|event: &EntityMoveEvent| {
         // Find position of entity.
         let pos = *world.get_component::<Position>(event.entity).unwrap();
+        let dimension = *world.get_component::<DimensionId>(event.entity).unwrap();
 
         // Find clients which can see the entity.
         let chunk = pos.chunk_pos();
-        let clients = chunk_holders.holders_for(chunk).unwrap_or(&[]);
+        let clients = chunk_holders.holders_for(dimension, chunk).unwrap_or(&[]);
 
         let entity_id = world.get_component::<EntityId>(event.entity).unwrap().0;
 
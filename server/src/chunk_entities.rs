@@ -1,5 +1,8 @@
-use crate::entity::{EntityCreateEvent, EntityDeleteEvent, EntityMoveEvent, PreviousPosition};
+use crate::entity::{
+    DimensionChangeEvent, EntityCreateEvent, EntityDeleteEvent, EntityMoveEvent, PreviousPosition,
+};
 use crate::state::State;
+use crate::worlds::DimensionId;
 use feather_core::{ChunkPosition, Position};
 use hashbrown::HashMap;
 use legion::entity::Entity;
@@ -20,6 +23,7 @@ static EMPTY_VEC: Vec<Entity> = Vec::new();
 ///
 /// This structure is internally stored in `State`, using
 /// a `RwLock` for concurrent access. (TODO: remove lock.)
+/// Each dimension has its own `ChunkEntities`.
 ///
 /// Do note that the information in this structure is not necessarily up to date,
 /// although a best effort is made to update the data.
@@ -43,6 +47,24 @@ impl ChunkEntities {
             }
         })
     }
+
+    /// Adds an entity to the given chunk.
+    fn insert(&self, chunk: ChunkPosition, entity: Entity) {
+        self.0
+            .write()
+            .entry(chunk)
+            .or_insert_with(|| vec![])
+            .push(entity);
+    }
+
+    /// Removes an entity from the given chunk.
+    fn remove(&self, chunk: ChunkPosition, entity: Entity) {
+        self.0
+            .write()
+            .entry(chunk)
+            .or_insert_with(|| vec![])
+            .remove_item(&entity);
+    }
 }
 
 impl Default for ChunkEntities {
@@ -56,7 +78,7 @@ impl Default for ChunkEntities {
 fn chunk_entities_handle_movement(
     events: &[EntityMoveEvent],
     state: &State,
-    _query: &mut Query<(Read<Position>, Read<PreviousPosition>)>,
+    _query: &mut Query<(Read<Position>, Read<PreviousPosition>, Read<DimensionId>)>,
     world: &mut PreparedWorld,
 ) {
     events.par_iter().for_each(|event| {
@@ -65,13 +87,14 @@ fn chunk_entities_handle_movement(
             .unwrap()
             .0;
         let new_pos = *world.get_component::<Position>(event.entity).unwrap();
+        let dimension = *world.get_component::<DimensionId>(event.entity).unwrap();
 
         let old_chunk = old_pos.chunk_pos();
         let new_chunk = new_pos.chunk_pos();
 
         if old_chunk != new_chunk {
             // Update chunk entities
-            let mut map = state.chunk_entities.0.write();
+            let mut map = state.dimension(dimension).chunk_entities.0.write();
             map.entry(new_chunk)
                 .or_insert_with(|| vec![])
                 .push(event.entity);
@@ -86,27 +109,47 @@ fn chunk_entities_handle_movement(
 fn chunk_entities_insert(
     event: &EntityCreateEvent,
     state: &State,
-    _query: &mut Query<Read<Position>>,
+    _query: &mut Query<(Read<Position>, Read<DimensionId>)>,
     world: &mut PreparedWorld,
 ) {
-    if let Some(position) = world.get_component::<Position>(event.entity) {
-        let chunk = position.chunk_pos();
-        let mut map = state.chunk_entities.0.write();
-
-        map.entry(chunk)
-            .or_insert_with(|| vec![])
-            .push(event.entity);
+    if let (Some(position), Some(dimension)) = (
+        world.get_component::<Position>(event.entity),
+        world.get_component::<DimensionId>(event.entity),
+    ) {
+        state
+            .dimension(*dimension)
+            .chunk_entities
+            .insert(position.chunk_pos(), event.entity);
     }
 }
 
 #[event_handler]
 fn chunk_entities_remove(event: &EntityDeleteEvent, state: &State) {
-    if let Some(position) = event.position {
-        let chunk = position.chunk_pos();
-        let mut map = state.chunk_entities.0.write();
-
-        map.entry(chunk)
-            .or_insert_with(|| vec![])
-            .remove_item(&event.entity);
+    if let (Some(position), Some(dimension)) = (event.position, event.dimension) {
+        state
+            .dimension(dimension)
+            .chunk_entities
+            .remove(position.chunk_pos(), event.entity);
     }
 }
+
+/// Event handler which moves entities between the
+/// `ChunkEntities` of dimensions when they change dimension.
+#[event_handler]
+fn chunk_entities_dimension_change(
+    event: &DimensionChangeEvent,
+    state: &State,
+    _query: &mut Query<Read<Position>>,
+    world: &mut PreparedWorld,
+) {
+    state
+        .dimension(event.old_dimension)
+        .chunk_entities
+        .remove(event.old_position.chunk_pos(), event.entity);
+
+    let position = *world.get_component::<Position>(event.entity).unwrap();
+    state
+        .dimension(event.new_dimension)
+        .chunk_entities
+        .insert(position.chunk_pos(), event.entity);
+}
@@ -9,64 +9,98 @@ use feather_core::world::ChunkPosition;
 
 use rayon::prelude::*;
 
-use crate::entity::EntityDeleteEvent;
-use crate::state::State;
+use crate::block::tick::BlockTicks;
+use crate::entity::{EntityDataCreator, EntityDeleteEvent, Velocity};
+use crate::fluid::FluidTicks;
+use crate::state::{DimensionState, State};
+use crate::worlds::{DimensionId, WorldId};
 use crate::{chunk_worker, current_time_in_millis, TickCount, TPS};
 use feather_core::entity::EntityData;
-use feather_core::Chunk;
+use feather_core::region::ChunkTicks;
+use feather_core::{Chunk, Position};
 use hashbrown::HashSet;
 use legion::entity::Entity;
 use legion::query::Read;
 use multimap::MultiMap;
 use std::collections::VecDeque;
 use std::sync::Arc;
-use tonks::{PreparedWorld, Query, Trigger};
+use tonks::{PreparedWorld, Query, QueryAccessor, Trigger};
 
-/// A handle for interacting with the chunk
+/// A handle for interacting with a chunk
 /// worker thread.
-#[derive(Debug, Clone, Resource)]
+#[derive(Debug, Clone)]
 pub struct ChunkWorkerHandle {
     pub sender: Sender<chunk_worker::Request>,
     pub receiver: Receiver<chunk_worker::Reply>,
 }
 
+/// The handles of each world's chunk worker, indexed by `WorldId`.
+#[derive(Debug, Clone, Resource)]
+pub struct ChunkWorkers(pub Vec<ChunkWorkerHandle>);
+
+impl ChunkWorkers {
+    /// Returns the handle of the given world's chunk worker.
+    pub fn get(&self, world: WorldId) -> &ChunkWorkerHandle {
+        &self.0[world.0]
+    }
+}
+
 /// Event which is triggered when a chunk is loaded.
 #[derive(Debug, Clone)]
 pub struct ChunkLoadEvent {
+    pub dimension: DimensionId,
     pub pos: ChunkPosition,
     pub entities: Vec<EntityData>,
+    /// Ticks which were pending when the chunk was saved.
+    pub ticks: ChunkTicks,
 }
 
 /// Event which is triggered when a chunk fails to load.
 #[derive(Debug, Clone, Copy)]
 pub struct ChunkLoadFailEvent {
+    pub dimension: DimensionId,
     pub pos: ChunkPosition,
 }
 
-/// System for receiving loaded chunks from the chunk worker thread.
+/// System for receiving loaded chunks from the chunk worker threads.
 #[system]
 fn chunk_load_system(
     state: &State,
-    handle: &ChunkWorkerHandle,
+    workers: &ChunkWorkers,
     fail_events: &mut Trigger<ChunkLoadFailEvent>,
 ) {
-    while let Ok(reply) = handle.receiver.try_recv() {
-        if let chunk_worker::Reply::LoadedChunk(pos, result) = reply {
+    let replies = workers.0.iter().enumerate().flat_map(|(world, handle)| {
+        handle
+            .receiver
+            .try_iter()
+            .map(move |reply| (WorldId(world), reply))
+    });
+    for (world, reply) in replies {
+        if let chunk_worker::Reply::LoadedChunk(kind, pos, result) = reply {
+            let dimension = DimensionId::new(world, kind);
             match result {
-                Ok((chunk, entities)) => {
-                    state.lazy_insert_chunk(chunk);
+                Ok((chunk, entities, ticks)) => {
+                    state.dimension(dimension).lazy_insert_chunk(chunk);
 
                     // Trigger event - lazily so it happens after the chunk is inserted into the chunk map
-                    let event = ChunkLoadEvent { pos, entities };
+                    let event = ChunkLoadEvent {
+                        dimension,
+                        pos,
+                        entities,
+                        ticks,
+                    };
                     state.exec_with_scheduler(move |_, scheduler| {
                         scheduler.trigger(event);
                     });
 
-                    trace!("Loaded chunk at {:?}", pos);
+                    trace!("Loaded chunk at {:?} in {:?}", pos, dimension);
                 }
                 Err(err) => {
-                    warn!("Failed to load chunk at {:?}: {}", pos, err);
-                    let event = ChunkLoadFailEvent { pos };
+                    warn!(
+                        "Failed to load chunk at {:?} in {:?}: {}",
+                        pos, dimension, err
+                    );
+                    let event = ChunkLoadFailEvent { dimension, pos };
                     fail_events.trigger(event);
                 }
             }
@@ -76,6 +110,8 @@ fn chunk_load_system(
 
 /// The chunk holder map contains a mapping
 /// of chunk positions to any number of entities, called "holders."
+/// Since each dimension has its own chunks, positions are
+/// qualified by their dimension.
 /// When a chunk position has no holders, it will be queued
 /// for unloading.
 ///
@@ -87,31 +123,34 @@ fn chunk_load_system(
 /// avoids constant nearby entity queries.
 #[derive(Default, Clone, Debug, Resource)]
 pub struct ChunkHolders {
-    inner: MultiMap<ChunkPosition, Entity>,
+    inner: MultiMap<(DimensionId, ChunkPosition), Entity>,
 }
 
 impl ChunkHolders {
-    pub fn holders_for(&self, chunk: ChunkPosition) -> Option<&[Entity]> {
-        self.inner.get_vec(&chunk).map(|holders| holders.as_slice())
+    pub fn holders_for(&self, dimension: DimensionId, chunk: ChunkPosition) -> Option<&[Entity]> {
+        self.inner
+            .get_vec(&(dimension, chunk))
+            .map(|holders| holders.as_slice())
     }
 
-    pub fn chunk_has_holders(&self, chunk: ChunkPosition) -> bool {
-        let holders = self.holders_for(chunk);
+    pub fn chunk_has_holders(&self, dimension: DimensionId, chunk: ChunkPosition) -> bool {
+        let holders = self.holders_for(dimension, chunk);
 
         !(holders.is_none() || holders.unwrap().is_empty())
     }
 
-    pub fn insert_holder(&mut self, chunk: ChunkPosition, holder: Entity) {
-        self.inner.insert(chunk, holder);
+    pub fn insert_holder(&mut self, dimension: DimensionId, chunk: ChunkPosition, holder: Entity) {
+        self.inner.insert((dimension, chunk), holder);
     }
 
     pub fn remove_holder(
         &mut self,
+        dimension: DimensionId,
         chunk: ChunkPosition,
         holder: Entity,
         trigger: &mut Trigger<ChunkHolderReleaseEvent>,
     ) {
-        if let Some(vec) = self.inner.get_vec_mut(&chunk) {
+        if let Some(vec) = self.inner.get_vec_mut(&(dimension, chunk)) {
             let index = vec.iter().position(|e| *e == holder);
             if let Some(index) = index {
                 vec.remove(index);
@@ -119,6 +158,7 @@ impl ChunkHolders {
                 // Trigger event
                 let event = ChunkHolderReleaseEvent {
                     entity: holder,
+                    dimension,
                     chunk,
                 };
                 trigger.trigger(event);
@@ -132,6 +172,8 @@ impl ChunkHolders {
 pub struct ChunkHolderReleaseEvent {
     /// The entity which previously held the chunk.
     pub entity: Entity,
+    /// The dimension of the chunk.
+    pub dimension: DimensionId,
     /// The chunk which the holder was released from.
     pub chunk: ChunkPosition,
 }
@@ -147,6 +189,8 @@ pub struct ChunkUnloadQueue {
 /// A chunk to be unloaded.
 #[derive(Clone, Debug, Default, Resource)]
 struct ChunkUnload {
+    /// The dimension of this chunk.
+    dimension: DimensionId,
     /// The position of this chunk.
     chunk: ChunkPosition,
     /// The tick count at which to unload the chunk.
@@ -174,6 +218,12 @@ fn chunk_unload(
     unload_queue: &mut ChunkUnloadQueue,
     holders: &ChunkHolders,
     tick_count: &TickCount,
+    workers: &ChunkWorkers,
+    fluid_ticks: &mut FluidTicks,
+    block_ticks: &mut BlockTicks,
+    accessor: &QueryAccessor<Read<EntityDataCreator>>,
+    _query: &mut Query<(Read<Position>, Read<Velocity>)>,
+    world: &mut PreparedWorld,
 ) {
     // Unload chunks which are finished in the queue.
 
@@ -185,12 +235,32 @@ fn chunk_unload(
     while let Some(unload) = unload_queue.queue.front() {
         if tick_count.0 >= unload.time {
             // Don't unload if new chunk holders have appeared.
-            if holders.chunk_has_holders(unload.chunk) {
+            if holders.chunk_has_holders(unload.dimension, unload.chunk) {
                 unload_queue.queue.pop_front();
                 continue;
             }
 
-            // Unload chunk and pop from queue.
+            let state = state.dimension(unload.dimension);
+
+            // Save chunk along with its pending ticks,
+            // then unload it and pop from queue.
+            if let Some(chunk) = state.chunk_at(unload.chunk) {
+                let ticks = drain_ticks(
+                    fluid_ticks,
+                    block_ticks,
+                    unload.dimension,
+                    unload.chunk,
+                    tick_count.0,
+                );
+                let entities = save_entities(&state, accessor, world, unload.chunk);
+                save_chunk(
+                    workers,
+                    unload.dimension,
+                    Arc::new(chunk.clone()),
+                    entities,
+                    ticks,
+                );
+            }
             state.lazy_remove_chunk(unload.chunk);
             unload_queue.queue.pop_front();
         } else {
@@ -202,6 +272,118 @@ fn chunk_unload(
     }
 }
 
+/// Event which causes all loaded chunks to be saved. This is
+/// triggered when the server shuts down and by `/save-all`.
+#[derive(Debug, Clone, Copy)]
+pub struct SaveAllChunksEvent;
+
+/// Event handler which saves every loaded chunk, along with
+/// its pending ticks and entities, without unloading it.
+/// The ticks stay queued so the chunk keeps running.
+#[event_handler]
+fn save_all_chunks(
+    _event: &SaveAllChunksEvent,
+    state: &State,
+    tick_count: &TickCount,
+    workers: &ChunkWorkers,
+    fluid_ticks: &mut FluidTicks,
+    block_ticks: &mut BlockTicks,
+    accessor: &QueryAccessor<Read<EntityDataCreator>>,
+    _query: &mut Query<(Read<Position>, Read<Velocity>)>,
+    world: &mut PreparedWorld,
+) {
+    for dimension_state in state.dimensions() {
+        let dimension = dimension_state.dimension;
+        for chunk in dimension_state.chunk_map.iter_chunks() {
+            let chunk = chunk.read();
+            let pos = chunk.position();
+            let ticks = copy_ticks(fluid_ticks, block_ticks, dimension, pos, tick_count.0);
+            let entities = entity_data(&dimension_state, accessor, world, pos)
+                .into_iter()
+                .map(|(_, data)| data)
+                .collect();
+            save_chunk(workers, dimension, Arc::new(chunk.clone()), entities, ticks);
+        }
+    }
+}
+
+/// Removes the pending ticks in `chunk` and
+/// returns them in the format of the world save.
+fn drain_ticks(
+    fluid_ticks: &mut FluidTicks,
+    block_ticks: &mut BlockTicks,
+    dimension: DimensionId,
+    chunk: ChunkPosition,
+    now: u64,
+) -> ChunkTicks {
+    ChunkTicks {
+        liquids: fluid_ticks
+            .queue(dimension)
+            .drain_chunk(chunk)
+            .into_iter()
+            .map(|tick| tick.pos)
+            .collect(),
+        blocks: block_ticks.drain_chunk_for_save(dimension, chunk, now),
+    }
+}
+
+/// Returns the pending ticks in `chunk` in the format
+/// of the world save, leaving them queued.
+fn copy_ticks(
+    fluid_ticks: &mut FluidTicks,
+    block_ticks: &mut BlockTicks,
+    dimension: DimensionId,
+    chunk: ChunkPosition,
+    now: u64,
+) -> ChunkTicks {
+    ChunkTicks {
+        liquids: fluid_ticks
+            .queue(dimension)
+            .chunk_ticks(chunk)
+            .into_iter()
+            .map(|tick| tick.pos)
+            .collect(),
+        blocks: block_ticks.chunk_ticks_for_save(dimension, chunk, now),
+    }
+}
+
+/// Returns the data for the entities in `chunk` which
+/// are to be saved, and removes those entities.
+fn save_entities(
+    state: &DimensionState,
+    accessor: &QueryAccessor<Read<EntityDataCreator>>,
+    world: &PreparedWorld,
+    chunk: ChunkPosition,
+) -> Vec<EntityData> {
+    entity_data(state, accessor, world, chunk)
+        .into_iter()
+        .map(|(entity, data)| {
+            state.delete_entity(entity);
+            data
+        })
+        .collect()
+}
+
+/// Returns the entities in `chunk` which are
+/// to be saved, along with their data.
+fn entity_data(
+    state: &DimensionState,
+    accessor: &QueryAccessor<Read<EntityDataCreator>>,
+    world: &PreparedWorld,
+    chunk: ChunkPosition,
+) -> Vec<(Entity, EntityData)> {
+    let entities = state.chunk_entities.entities_in_chunk(chunk).to_vec();
+
+    entities
+        .into_iter()
+        .filter_map(|entity| {
+            let accessor = accessor.find(entity)?;
+            let creator = accessor.get_component::<EntityDataCreator>(world)?;
+            Some((entity, creator.get(&accessor, world)))
+        })
+        .collect()
+}
+
 /// Event handler which handles holder release events. If
 /// a chunk has no more holders, then a chunk unload is queued.
 #[event_handler]
@@ -213,8 +395,9 @@ pub fn chunk_unload_no_holders(
 ) {
     // Handle holder release events.
     // If the chunk now has zero holders, queue it for unloading.
-    if !holders.chunk_has_holders(event.chunk) {
+    if !holders.chunk_has_holders(event.dimension, event.chunk) {
         let unload = ChunkUnload {
+            dimension: event.dimension,
             chunk: event.chunk,
             time: tick_count.0 + CHUNK_UNLOAD_TIME,
         };
@@ -232,6 +415,8 @@ pub fn chunk_unload_no_holders(
 /// finding which chunks a given entity has
 /// a hold on, rather than having
 /// to linear search all chunks (obviously ridiculous).
+///
+/// All holds are in the entity's current dimension.
 #[derive(Default)]
 pub struct ChunkHolder {
     pub holds: HashSet<ChunkPosition>,
@@ -248,7 +433,7 @@ impl ChunkHolder {
 #[event_handler]
 fn chunk_holder_remove(
     event: &EntityDeleteEvent,
-    _query: &mut Query<Read<ChunkHolder>>,
+    _query: &mut Query<(Read<ChunkHolder>, Read<DimensionId>)>,
     world: &mut PreparedWorld,
     holders: &mut ChunkHolders,
     release_events: &mut Trigger<ChunkHolderReleaseEvent>,
@@ -256,8 +441,9 @@ fn chunk_holder_remove(
     // If entity had chunk holds, remove them all
     if let Some(holder_comp) = world.get_component::<ChunkHolder>(event.entity) {
         debug!("Removing chunk holds for entity {:?}", event.entity);
+        let dimension = *world.get_component::<DimensionId>(event.entity).unwrap();
         holder_comp.holds.iter().for_each(|chunk| {
-            holders.remove_holder(*chunk, event.entity, release_events);
+            holders.remove_holder(dimension, *chunk, event.entity, release_events);
         });
     }
 }
@@ -286,9 +472,11 @@ fn chunk_optimize(state: &State, tick_count: &TickCount) {
     let start_time = current_time_in_millis();
     let count = AtomicU32::new(0);
 
-    state.chunk_map.par_iter_chunks().for_each(|chunk| {
-        count.fetch_add(chunk.write().optimize(), Ordering::Relaxed);
-    });
+    for dimension in state.dimensions() {
+        dimension.chunk_map.par_iter_chunks().for_each(|chunk| {
+            count.fetch_add(chunk.write().optimize(), Ordering::Relaxed);
+        });
+    }
 
     let end_time = current_time_in_millis();
     let elapsed = end_time - start_time;
@@ -306,10 +494,11 @@ pub fn hold_chunk(
     entity: Entity,
     holder: &mut ChunkHolder,
     holders: &mut ChunkHolders,
+    dimension: DimensionId,
     chunk: ChunkPosition,
 ) {
     holder.holds.insert(chunk);
-    holders.inner.insert(chunk, entity);
+    holders.inner.insert((dimension, chunk), entity);
 }
 
 /// Releases a hold for a chunk for the given entity.
@@ -317,11 +506,12 @@ pub fn release_chunk(
     entity: Entity,
     holder: &mut ChunkHolder,
     holders: &mut ChunkHolders,
+    dimension: DimensionId,
     chunk: ChunkPosition,
     trigger: &mut Trigger<ChunkHolderReleaseEvent>,
 ) {
     holder.holds.remove(&chunk);
-    if let Some(vec) = holders.inner.get_vec_mut(&chunk) {
+    if let Some(vec) = holders.inner.get_vec_mut(&(dimension, chunk)) {
         let mut index = None;
         for (i, e) in vec.iter().enumerate() {
             if *e == entity {
@@ -333,7 +523,11 @@ pub fn release_chunk(
             vec.swap_remove(index);
         }
     }
-    trigger.trigger(ChunkHolderReleaseEvent { entity, chunk })
+    trigger.trigger(ChunkHolderReleaseEvent {
+        entity,
+        dimension,
+        chunk,
+    })
 }
 
 /// Asynchronously loads the chunk at the given position.
@@ -342,18 +536,31 @@ pub fn release_chunk(
 ///
 /// In the event that the requested chunk does not exist
 /// in the world save, it will be generated asynchronously.
-pub fn load_chunk(handle: &ChunkWorkerHandle, pos: ChunkPosition) {
-    // Send request to chunk worker thread
-    handle
+pub fn load_chunk(workers: &ChunkWorkers, dimension: DimensionId, pos: ChunkPosition) {
+    // Send request to the world's chunk worker thread
+    workers
+        .get(dimension.world)
         .sender
-        .send(chunk_worker::Request::LoadChunk(pos))
+        .send(chunk_worker::Request::LoadChunk(dimension.kind, pos))
         .unwrap();
 }
 
 /// Asynchronously saves the chunk at the given position.
-pub fn save_chunk(handle: &ChunkWorkerHandle, chunk: Arc<Chunk>, entities: Vec<EntityData>) {
-    handle
+pub fn save_chunk(
+    workers: &ChunkWorkers,
+    dimension: DimensionId,
+    chunk: Arc<Chunk>,
+    entities: Vec<EntityData>,
+    ticks: ChunkTicks,
+) {
+    workers
+        .get(dimension.world)
         .sender
-        .send(chunk_worker::Request::SaveChunk(chunk, entities))
+        .send(chunk_worker::Request::SaveChunk(
+            dimension.kind,
+            chunk,
+            entities,
+            ticks,
+        ))
         .unwrap();
 }
@@ -4,13 +4,18 @@
 //!
 //! If a chunk cannot be loaded, it is generated on the Rayon thread pool
 //! instead.
+//!
+//! Each dimension has its own region files and world generator. As in
+//! vanilla, the overworld is stored in the world directory itself, while
+//! the Nether and the End are stored in its `DIM-1` and `DIM1` subdirectories.
 use crate::worldgen::WorldGenerator;
 use crossbeam::channel::{Receiver, Sender};
 use feather_core::entity::EntityData;
 use feather_core::region;
-use feather_core::region::{RegionHandle, RegionPosition};
+use feather_core::region::{ChunkTicks, RegionHandle, RegionPosition};
 use feather_core::world::chunk::Chunk;
 use feather_core::world::ChunkPosition;
+use feather_core::Dimension;
 use hashbrown::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -18,14 +23,18 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 #[allow(clippy::large_enum_variant)]
 pub enum Reply {
-    LoadedChunk(ChunkPosition, Result<(Chunk, Vec<EntityData>), Error>),
-    SavedChunk(ChunkPosition),
+    LoadedChunk(
+        Dimension,
+        ChunkPosition,
+        Result<(Chunk, Vec<EntityData>, ChunkTicks), Error>,
+    ),
+    SavedChunk(Dimension, ChunkPosition),
 }
 
 #[derive(Clone)]
 pub enum Request {
-    LoadChunk(ChunkPosition),
-    SaveChunk(Arc<Chunk>, Vec<EntityData>),
+    LoadChunk(Dimension, ChunkPosition),
+    SaveChunk(Dimension, Arc<Chunk>, Vec<EntityData>, ChunkTicks),
     ShutDown,
 }
 
@@ -78,10 +87,20 @@ struct ChunkWorker {
     receiver: Receiver<Request>,
 
     /// A map of currently open region files
-    open_regions: HashMap<RegionPosition, RegionFile>,
+    open_regions: HashMap<(Dimension, RegionPosition), RegionFile>,
 
-    /// World generator for new chunks.
-    world_generator: Arc<dyn WorldGenerator>,
+    /// World generators for new chunks in each dimension.
+    world_generators: HashMap<Dimension, Arc<dyn WorldGenerator>>,
+}
+
+/// Returns the directory in which the region
+/// files of a dimension are stored.
+pub fn dimension_dir(world_dir: &Path, dimension: Dimension) -> PathBuf {
+    match dimension {
+        Dimension::Overworld => world_dir.to_path_buf(),
+        Dimension::Nether => world_dir.join("DIM-1"),
+        Dimension::End => world_dir.join("DIM1"),
+    }
 }
 
 /// Starts a chunk worker on a new thread.
@@ -89,7 +108,7 @@ struct ChunkWorker {
 /// to communicate with the worker.
 pub fn start(
     world_dir: &Path,
-    world_gen: Arc<dyn WorldGenerator>,
+    world_generators: HashMap<Dimension, Arc<dyn WorldGenerator>>,
 ) -> (Sender<Request>, Receiver<Reply>) {
     let (request_tx, request_rx) = crossbeam::channel::unbounded();
     let (reply_tx, reply_rx) = crossbeam::channel::unbounded();
@@ -99,7 +118,7 @@ pub fn start(
         sender: reply_tx,
         receiver: request_rx,
         open_regions: HashMap::new(),
-        world_generator: world_gen,
+        world_generators,
     };
 
     // Without changing the stack size,
@@ -120,11 +139,11 @@ fn run(mut worker: ChunkWorker) {
     while let Ok(request) = worker.receiver.recv() {
         match request {
             Request::ShutDown => break,
-            Request::SaveChunk(chunk, entities) => {
-                save_chunk(&mut worker, &chunk, entities);
+            Request::SaveChunk(dimension, chunk, entities, ticks) => {
+                save_chunk(&mut worker, dimension, &chunk, entities, ticks);
             }
-            Request::LoadChunk(pos) => {
-                if let Some(reply) = load_chunk(&mut worker, pos) {
+            Request::LoadChunk(dimension, pos) => {
+                if let Some(reply) = load_chunk(&mut worker, dimension, pos) {
                     worker.sender.send(reply).unwrap();
                 }
             }
@@ -135,20 +154,22 @@ fn run(mut worker: ChunkWorker) {
 }
 
 /// Attempts to load the chunk at the specified position.
-fn load_chunk(worker: &mut ChunkWorker, pos: ChunkPosition) -> Option<Reply> {
+fn load_chunk(worker: &mut ChunkWorker, dimension: Dimension, pos: ChunkPosition) -> Option<Reply> {
     let rpos = RegionPosition::from_chunk(pos);
 
-    let file = worker_region(&mut worker.open_regions, &worker.dir, rpos);
+    let file = worker_region(&mut worker.open_regions, &worker.dir, dimension, rpos);
     // Load from region file
     load_chunk_from_handle(
+        dimension,
         pos,
         &mut file.handle,
         &Arc::from(worker.sender.clone()),
-        &worker.world_generator,
+        &worker.world_generators[&dimension],
     )
 }
 
 fn load_chunk_from_handle(
+    dimension: Dimension,
     pos: ChunkPosition,
     handle: &mut RegionHandle,
     sender: &Arc<Sender<Reply>>,
@@ -157,13 +178,17 @@ fn load_chunk_from_handle(
     let result = handle.load_chunk(pos);
 
     match result {
-        Ok(chunk) => Some(Reply::LoadedChunk(pos, Ok(chunk))),
+        Ok(chunk) => Some(Reply::LoadedChunk(dimension, pos, Ok(chunk))),
         Err(e) => match e {
             region::Error::ChunkNotExist => {
-                schedule_generate_new_chunk(sender, pos, generator);
+                schedule_generate_new_chunk(sender, dimension, pos, generator);
                 None
             }
-            err => Some(Reply::LoadedChunk(pos, Err(Error::LoadError(err)))),
+            err => Some(Reply::LoadedChunk(
+                dimension,
+                pos,
+                Err(Error::LoadError(err)),
+            )),
         },
     }
 }
@@ -172,51 +197,71 @@ fn load_chunk_from_handle(
 /// sending the result to the provided Sender.
 fn schedule_generate_new_chunk(
     sender: &Arc<Sender<Reply>>,
+    dimension: Dimension,
     pos: ChunkPosition,
     generator: &Arc<dyn WorldGenerator>,
 ) {
     let sender = sender.clone();
     let generator = Arc::clone(generator);
     rayon::spawn(move || {
-        sender.send(generate_new_chunk(pos, &generator)).unwrap();
+        sender
+            .send(generate_new_chunk(dimension, pos, &generator))
+            .unwrap();
     });
 }
 
 /// Generates a new chunk synchronously,
 /// returning a Reply to send to a Sender.
-fn generate_new_chunk(pos: ChunkPosition, generator: &Arc<dyn WorldGenerator>) -> Reply {
-    Reply::LoadedChunk(pos, Ok((generator.generate_chunk(pos), vec![])))
+fn generate_new_chunk(
+    dimension: Dimension,
+    pos: ChunkPosition,
+    generator: &Arc<dyn WorldGenerator>,
+) -> Reply {
+    Reply::LoadedChunk(
+        dimension,
+        pos,
+        Ok((generator.generate_chunk(pos), vec![], ChunkTicks::default())),
+    )
 }
 
 /// Saves the chunk at the specified position.
-fn save_chunk(worker: &mut ChunkWorker, chunk: &Chunk, entities: Vec<EntityData>) {
+fn save_chunk(
+    worker: &mut ChunkWorker,
+    dimension: Dimension,
+    chunk: &Chunk,
+    entities: Vec<EntityData>,
+    ticks: ChunkTicks,
+) {
     let rpos = RegionPosition::from_chunk(chunk.position());
 
-    let file = worker_region(&mut worker.open_regions, &worker.dir, rpos);
+    let file = worker_region(&mut worker.open_regions, &worker.dir, dimension, rpos);
 
-    file.handle.save_chunk(chunk, entities).unwrap();
+    file.handle.save_chunk(chunk, entities, ticks).unwrap();
     worker
         .sender
-        .send(Reply::SavedChunk(chunk.position()))
+        .send(Reply::SavedChunk(dimension, chunk.position()))
         .unwrap();
 }
 
 /// Returns whether the given chunk's region
 /// is already loaded.
 fn is_region_loaded(
-    open_regions: &HashMap<RegionPosition, RegionFile>,
+    open_regions: &HashMap<(Dimension, RegionPosition), RegionFile>,
+    dimension: Dimension,
     rpos: RegionPosition,
 ) -> bool {
-    open_regions.contains_key(&rpos)
+    open_regions.contains_key(&(dimension, rpos))
 }
 
 fn worker_region<'a>(
-    open_regions: &'a mut HashMap<RegionPosition, RegionFile>,
-    dir: &PathBuf,
+    open_regions: &'a mut HashMap<(Dimension, RegionPosition), RegionFile>,
+    world_dir: &PathBuf,
+    dimension: Dimension,
     rpos: RegionPosition,
 ) -> &'a mut RegionFile {
-    if !is_region_loaded(open_regions, rpos) {
+    if !is_region_loaded(open_regions, dimension, rpos) {
         // Need to load region into memory
+        let dir = dimension_dir(world_dir, dimension);
         let mut handle = region::load_region(&dir, rpos);
         if handle.is_err() {
             // Create a new region file
@@ -235,7 +280,7 @@ fn worker_region<'a>(
             _last_used: last_used,
         };
 
-        open_regions.insert(rpos, file);
+        open_regions.insert((dimension, rpos), file);
     }
-    open_regions.get_mut(&rpos).unwrap()
+    open_regions.get_mut(&(dimension, rpos)).unwrap()
 }
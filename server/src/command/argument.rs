@@ -0,0 +1,779 @@
+//! Command arguments: their types, parsing and suggestions.
+
+use super::selector::{SelectorVariable, TargetSelector};
+use super::CommandError;
+use crate::permissions::PermissionSet;
+use feather_core::network::packet::implementation::{CommandParser, StringParserKind};
+use feather_core::{Block, BlockPosition, Item, Position, TextComponent};
+use smallvec::SmallVec;
+
+/// Reads a command from left to right.
+#[derive(Debug, Clone)]
+pub struct StringReader<'a> {
+    input: &'a str,
+    cursor: usize,
+}
+
+impl<'a> StringReader<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self { input, cursor: 0 }
+    }
+
+    /// Returns the byte offset of the next character to read.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn set_cursor(&mut self, cursor: usize) {
+        self.cursor = cursor;
+    }
+
+    /// Returns the input which has not been read yet.
+    pub fn remaining(&self) -> &'a str {
+        &self.input[self.cursor..]
+    }
+
+    pub fn at_end(&self) -> bool {
+        self.cursor >= self.input.len()
+    }
+
+    pub fn peek(&self) -> Option<char> {
+        self.remaining().chars().next()
+    }
+
+    /// Skips the next character.
+    pub fn skip(&mut self) {
+        if let Some(c) = self.peek() {
+            self.cursor += c.len_utf8();
+        }
+    }
+
+    pub fn skip_whitespace(&mut self) {
+        while self.peek() == Some(' ') {
+            self.skip();
+        }
+    }
+
+    /// Reads up to the next of the given delimiters,
+    /// the next space or the end of the input.
+    pub fn read_until(&mut self, delimiters: &[char]) -> &'a str {
+        let remaining = self.remaining();
+        let len = remaining
+            .find(|c| c == ' ' || delimiters.contains(&c))
+            .unwrap_or_else(|| remaining.len());
+        self.cursor += len;
+        &remaining[..len]
+    }
+
+    /// Reads up to the next space or the end of the input.
+    pub fn read_word(&mut self) -> &'a str {
+        let remaining = self.remaining();
+        let len = remaining.find(' ').unwrap_or_else(|| remaining.len());
+        self.cursor += len;
+        &remaining[..len]
+    }
+
+    /// Reads a word, or a phrase in double quotes in
+    /// which quotes and backslashes are escaped with `\`.
+    pub fn read_quotable(&mut self) -> Result<String, CommandError> {
+        if self.peek() != Some('"') {
+            return Ok(self.read_word().to_string());
+        }
+        self.skip();
+
+        let mut result = String::new();
+        let mut escaped = false;
+        while let Some(c) = self.peek() {
+            self.skip();
+            match c {
+                '\\' if !escaped => escaped = true,
+                '"' if !escaped => return Ok(result),
+                c => {
+                    result.push(c);
+                    escaped = false;
+                }
+            }
+        }
+        Err(self.error("feather.argument.quote.unclosed", vec![]))
+    }
+
+    /// Reads the rest of the input.
+    pub fn read_rest(&mut self) -> &'a str {
+        let rest = self.remaining();
+        self.cursor = self.input.len();
+        rest
+    }
+
+    /// Returns a syntax error at the current position, with
+    /// a message from the translation catalogue.
+    pub fn error(&self, key: &str, args: Vec<TextComponent>) -> CommandError {
+        CommandError::Syntax(TextComponent::translate(key, args), self.cursor)
+    }
+}
+
+/// The type of a command argument, which determines
+/// how it is parsed and how clients display it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArgumentType {
+    Bool,
+    Integer {
+        min: Option<i32>,
+        max: Option<i32>,
+    },
+    Double {
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+    /// A single word.
+    Word,
+    /// A word or a phrase in quotes.
+    String,
+    /// The rest of the command.
+    GreedyString,
+    /// One or more entities. See `EntitySelector`.
+    Entities {
+        single: bool,
+        players_only: bool,
+    },
+    /// The name of a player, who need not be online.
+    GameProfile,
+    BlockPos,
+    Vec3,
+    BlockState,
+    Item,
+    /// A chat message: the rest of the command.
+    Message,
+}
+
+impl ArgumentType {
+    /// An integer with no bounds.
+    pub const INTEGER: Self = ArgumentType::Integer {
+        min: None,
+        max: None,
+    };
+    /// A single entity of any type.
+    pub const ENTITY: Self = ArgumentType::Entities {
+        single: true,
+        players_only: false,
+    };
+    /// Any number of entities.
+    pub const ENTITIES: Self = ArgumentType::Entities {
+        single: false,
+        players_only: false,
+    };
+    /// A single player.
+    pub const PLAYER: Self = ArgumentType::Entities {
+        single: true,
+        players_only: true,
+    };
+    /// Any number of players.
+    pub const PLAYERS: Self = ArgumentType::Entities {
+        single: false,
+        players_only: true,
+    };
+
+    /// Parses an argument of this type.
+    pub fn parse(self, reader: &mut StringReader) -> Result<ArgumentValue, CommandError> {
+        let start = reader.cursor();
+        let value = match self {
+            ArgumentType::Bool => match reader.read_word() {
+                "true" => ArgumentValue::Bool(true),
+                "false" => ArgumentValue::Bool(false),
+                word => {
+                    reader.set_cursor(start);
+                    return Err(reader.error("feather.argument.bool.invalid", vec![word.into()]));
+                }
+            },
+            ArgumentType::Integer { min, max } => {
+                let word = reader.read_word();
+                reader.set_cursor(start);
+                let value: i32 = word.parse().map_err(|_| {
+                    reader.error("feather.argument.integer.invalid", vec![word.into()])
+                })?;
+                check_range("feather.argument.integer", value, min, max, reader)?;
+                reader.read_word();
+                ArgumentValue::Integer(value)
+            }
+            ArgumentType::Double { min, max } => {
+                let word = reader.read_word();
+                reader.set_cursor(start);
+                let value: f64 = word.parse().map_err(|_| {
+                    reader.error("feather.argument.double.invalid", vec![word.into()])
+                })?;
+                check_range("feather.argument.double", value, min, max, reader)?;
+                reader.read_word();
+                ArgumentValue::Double(value)
+            }
+            ArgumentType::Word | ArgumentType::GameProfile => {
+                let word = reader.read_word();
+                if word.is_empty() {
+                    return Err(reader.error("feather.argument.string.expected", vec![]));
+                }
+                ArgumentValue::String(word.to_string())
+            }
+            ArgumentType::String => ArgumentValue::String(reader.read_quotable()?),
+            ArgumentType::GreedyString | ArgumentType::Message => {
+                ArgumentValue::String(reader.read_rest().to_string())
+            }
+            ArgumentType::Entities {
+                single,
+                players_only,
+            } => {
+                let selector = EntitySelector::parse(reader)?;
+                if let EntitySelector::Selector(selector) = &selector {
+                    if single && selector.max_results() > 1 {
+                        reader.set_cursor(start);
+                        return Err(reader.error("feather.argument.entity.toomany", vec![]));
+                    }
+                    if players_only
+                        && selector.includes_entities()
+                        && selector.variable != SelectorVariable::Sender
+                    {
+                        reader.set_cursor(start);
+                        return Err(reader.error("feather.argument.player.entities", vec![]));
+                    }
+                }
+                ArgumentValue::Entities(selector)
+            }
+            ArgumentType::BlockPos => ArgumentValue::Coordinates(Coordinates::parse(reader, true)?),
+            ArgumentType::Vec3 => ArgumentValue::Coordinates(Coordinates::parse(reader, false)?),
+            ArgumentType::BlockState => ArgumentValue::Block(parse_block_state(reader)?),
+            ArgumentType::Item => {
+                let word = reader.read_word();
+                match Item::from_identifier(&namespaced(word)) {
+                    Some(item) => ArgumentValue::Item(item),
+                    None => {
+                        reader.set_cursor(start);
+                        return Err(
+                            reader.error("feather.argument.item.unknown", vec![word.into()])
+                        );
+                    }
+                }
+            }
+        };
+        Ok(value)
+    }
+
+    /// Returns the parser clients use for arguments of this type.
+    pub fn parser(self) -> CommandParser {
+        match self {
+            ArgumentType::Bool => CommandParser::Bool,
+            ArgumentType::Integer { min, max } => CommandParser::Integer { min, max },
+            ArgumentType::Double { min, max } => CommandParser::Double { min, max },
+            ArgumentType::Word => CommandParser::String(StringParserKind::SingleWord),
+            ArgumentType::String => CommandParser::String(StringParserKind::QuotablePhrase),
+            ArgumentType::GreedyString => CommandParser::String(StringParserKind::GreedyPhrase),
+            ArgumentType::Entities {
+                single,
+                players_only,
+            } => CommandParser::Entity {
+                single,
+                players_only,
+            },
+            ArgumentType::GameProfile => CommandParser::GameProfile,
+            ArgumentType::BlockPos => CommandParser::BlockPos,
+            ArgumentType::Vec3 => CommandParser::Vec3,
+            ArgumentType::BlockState => CommandParser::BlockState,
+            ArgumentType::Item => CommandParser::ItemStack,
+            ArgumentType::Message => CommandParser::Message,
+        }
+    }
+
+    /// Returns whether clients ask the server
+    /// for suggestions for this argument.
+    pub fn asks_server(self) -> bool {
+        match self {
+            ArgumentType::Entities { .. } | ArgumentType::GameProfile => true,
+            _ => false,
+        }
+    }
+
+    /// Returns suggestions for the partially typed argument `partial`.
+    pub fn suggest(self, partial: &str, ctx: &SuggestionCtx) -> Vec<String> {
+        let candidates: Vec<String> = match self {
+            ArgumentType::Bool => vec!["true".to_string(), "false".to_string()],
+            ArgumentType::Entities { .. } => ctx
+                .player_names
+                .iter()
+                .cloned()
+                .chain(EntitySelector::VARIABLES.iter().map(|s| s.to_string()))
+                .collect(),
+            ArgumentType::GameProfile => ctx.player_names.clone(),
+            ArgumentType::BlockPos | ArgumentType::Vec3 => vec!["~ ~ ~".to_string()],
+            _ => vec![],
+        };
+
+        candidates
+            .into_iter()
+            .filter(|candidate| {
+                candidate
+                    .to_lowercase()
+                    .starts_with(&partial.to_lowercase())
+            })
+            .collect()
+    }
+}
+
+/// Information used to suggest arguments.
+#[derive(Debug, Clone, Default)]
+pub struct SuggestionCtx {
+    /// The names of all online players.
+    pub player_names: Vec<String>,
+    /// The permissions of the player completing the command.
+    pub permissions: PermissionSet,
+}
+
+/// Checks that a number is within range. The errors use the
+/// keys `<key>.low` and `<key>.big` of the translation catalogue.
+fn check_range<T: PartialOrd + std::fmt::Display>(
+    key: &str,
+    value: T,
+    min: Option<T>,
+    max: Option<T>,
+    reader: &StringReader,
+) -> Result<(), CommandError> {
+    if let Some(min) = min {
+        if value < min {
+            return Err(reader.error(
+                &format!("{}.low", key),
+                vec![min.to_string().into(), value.to_string().into()],
+            ));
+        }
+    }
+    if let Some(max) = max {
+        if value > max {
+            return Err(reader.error(
+                &format!("{}.big", key),
+                vec![max.to_string().into(), value.to_string().into()],
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Adds the `minecraft:` namespace to `id` if it has none.
+pub(super) fn namespaced(id: &str) -> String {
+    if id.contains(':') {
+        id.to_string()
+    } else {
+        format!("minecraft:{}", id)
+    }
+}
+
+/// Parses a block state such as `oak_stairs[facing=east,half=top]`.
+/// Properties which are not given take their default values.
+fn parse_block_state(reader: &mut StringReader) -> Result<Block, CommandError> {
+    let start = reader.cursor();
+    let word = reader.read_word();
+    let (name, props) = match word.find('[') {
+        Some(index) => (&word[..index], Some(&word[index..])),
+        None => (word, None),
+    };
+
+    let mut block = Block::from_name_and_default_props(&namespaced(name)).ok_or_else(|| {
+        let mut reader = reader.clone();
+        reader.set_cursor(start);
+        reader.error("feather.argument.block.id.invalid", vec![name.into()])
+    })?;
+
+    if let Some(props) = props {
+        if !props.ends_with(']') {
+            return Err(reader.error("feather.argument.block.property.unclosed", vec![]));
+        }
+        let props = &props[1..props.len() - 1];
+        for prop in props.split(',').filter(|prop| !prop.is_empty()) {
+            let mut parts = prop.splitn(2, '=');
+            let key = parts.next().unwrap_or_default();
+            let value = parts.next().unwrap_or_default();
+            block = block.with_property(key, value).ok_or_else(|| {
+                reader.error(
+                    "feather.argument.block.property.invalid",
+                    vec![name.into(), value.into(), key.into()],
+                )
+            })?;
+        }
+    }
+
+    Ok(block)
+}
+
+/// A single coordinate of a position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Coordinate {
+    /// A coordinate in the world, such as `5`.
+    Absolute(f64),
+    /// An offset from the sender's position, such as `~5`.
+    Relative(f64),
+    /// An offset along the sender's axes, such as `^5`.
+    Local(f64),
+}
+
+/// A position given in a command.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coordinates {
+    pub x: Coordinate,
+    pub y: Coordinate,
+    pub z: Coordinate,
+}
+
+impl Coordinates {
+    /// Parses three coordinates. Block positions must
+    /// use whole numbers for absolute coordinates; other
+    /// positions given as whole numbers are centered
+    /// on the block horizontally.
+    fn parse(reader: &mut StringReader, block: bool) -> Result<Self, CommandError> {
+        let start = reader.cursor();
+        let mut coordinates: SmallVec<[Coordinate; 3]> = SmallVec::new();
+
+        for i in 0..3 {
+            if i > 0 {
+                if reader.peek() != Some(' ') {
+                    return Err(reader.error("feather.argument.pos3d.incomplete", vec![]));
+                }
+                reader.skip();
+            }
+
+            let coordinate_start = reader.cursor();
+            let word = reader.read_word();
+            reader.set_cursor(coordinate_start);
+
+            let coordinate = match word.chars().next() {
+                Some(prefix @ '~') | Some(prefix @ '^') => {
+                    let offset = match &word[1..] {
+                        "" => 0.0,
+                        number => number.parse::<f64>().map_err(|_| {
+                            reader.error("feather.argument.double.expected", vec![])
+                        })?,
+                    };
+                    if prefix == '~' {
+                        Coordinate::Relative(offset)
+                    } else {
+                        Coordinate::Local(offset)
+                    }
+                }
+                _ if block => Coordinate::Absolute(
+                    word.parse::<i32>()
+                        .map(f64::from)
+                        .map_err(|_| reader.error("feather.argument.integer.expected", vec![]))?,
+                ),
+                _ => {
+                    let value = word
+                        .parse::<f64>()
+                        .map_err(|_| reader.error("feather.argument.double.expected", vec![]))?;
+                    // Whole x and z coordinates refer to the block center.
+                    if i != 1 && !word.contains('.') {
+                        Coordinate::Absolute(value + 0.5)
+                    } else {
+                        Coordinate::Absolute(value)
+                    }
+                }
+            };
+            reader.read_word();
+            coordinates.push(coordinate);
+        }
+
+        let locals = coordinates
+            .iter()
+            .filter(|c| match c {
+                Coordinate::Local(_) => true,
+                _ => false,
+            })
+            .count();
+        if locals != 0 && locals != 3 {
+            reader.set_cursor(start);
+            return Err(reader.error("feather.argument.pos.mixed", vec![]));
+        }
+
+        Ok(Self {
+            x: coordinates[0],
+            y: coordinates[1],
+            z: coordinates[2],
+        })
+    }
+
+    /// Returns the position these coordinates refer to
+    /// for a sender at `origin`.
+    pub fn resolve(self, origin: Position) -> Position {
+        let mut pos = origin;
+
+        if let Coordinate::Local(left) = self.x {
+            let up = match self.y {
+                Coordinate::Local(up) => up,
+                _ => 0.0,
+            };
+            let forward = match self.z {
+                Coordinate::Local(forward) => forward,
+                _ => 0.0,
+            };
+
+            let forward_axis = origin.direction();
+            let mut up_origin = origin;
+            up_origin.pitch -= 90.0;
+            let up_axis = up_origin.direction();
+            let left_axis = glm::cross(&up_axis, &forward_axis);
+
+            let offset = left_axis * left + up_axis * up + forward_axis * forward;
+            pos.x += offset.x;
+            pos.y += offset.y;
+            pos.z += offset.z;
+            return pos;
+        }
+
+        let resolve = |coordinate: Coordinate, origin: f64| match coordinate {
+            Coordinate::Absolute(value) => value,
+            Coordinate::Relative(offset) => origin + offset,
+            Coordinate::Local(_) => origin,
+        };
+        pos.x = resolve(self.x, origin.x);
+        pos.y = resolve(self.y, origin.y);
+        pos.z = resolve(self.z, origin.z);
+        pos
+    }
+
+    /// Returns the block these coordinates refer
+    /// to for a sender at `origin`.
+    pub fn resolve_block(self, origin: Position) -> BlockPosition {
+        self.resolve(origin).block_pos()
+    }
+}
+
+/// Entities given in a command.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EntitySelector {
+    /// The player with the given name.
+    Player(String),
+    /// A target selector, such as `@p`.
+    Selector(TargetSelector),
+}
+
+impl EntitySelector {
+    /// The selector variables suggested to players.
+    const VARIABLES: [&'static str; 5] = ["@a", "@e", "@p", "@r", "@s"];
+
+    fn parse(reader: &mut StringReader) -> Result<Self, CommandError> {
+        if reader.peek() == Some('@') {
+            return TargetSelector::parse(reader).map(EntitySelector::Selector);
+        }
+
+        let start = reader.cursor();
+        let word = reader.read_word();
+        if word.is_empty() || word.len() > 16 {
+            reader.set_cursor(start);
+            return Err(reader.error("feather.argument.player.invalid", vec![]));
+        }
+        Ok(EntitySelector::Player(word.to_string()))
+    }
+}
+
+/// The parsed value of an argument.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgumentValue {
+    Bool(bool),
+    Integer(i32),
+    Double(f64),
+    String(String),
+    Entities(EntitySelector),
+    Coordinates(Coordinates),
+    Block(Block),
+    Item(Item),
+}
+
+/// The arguments of a parsed command, by name.
+#[derive(Debug, Clone, Default)]
+pub struct Arguments(Vec<(&'static str, ArgumentValue)>);
+
+impl Arguments {
+    pub(super) fn push(&mut self, name: &'static str, value: ArgumentValue) {
+        self.0.push((name, value));
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub(super) fn truncate(&mut self, len: usize) {
+        self.0.truncate(len);
+    }
+
+    /// Returns the value of the argument `name`,
+    /// or `None` if it was not given.
+    pub fn get(&self, name: &str) -> Option<&ArgumentValue> {
+        self.0
+            .iter()
+            .find(|(arg, _)| *arg == name)
+            .map(|(_, value)| value)
+    }
+
+    pub fn bool(&self, name: &str) -> Option<bool> {
+        match self.get(name)? {
+            ArgumentValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn integer(&self, name: &str) -> Option<i32> {
+        match self.get(name)? {
+            ArgumentValue::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn double(&self, name: &str) -> Option<f64> {
+        match self.get(name)? {
+            ArgumentValue::Double(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn string(&self, name: &str) -> Option<&str> {
+        match self.get(name)? {
+            ArgumentValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn entities(&self, name: &str) -> Option<&EntitySelector> {
+        match self.get(name)? {
+            ArgumentValue::Entities(selector) => Some(selector),
+            _ => None,
+        }
+    }
+
+    pub fn coordinates(&self, name: &str) -> Option<Coordinates> {
+        match self.get(name)? {
+            ArgumentValue::Coordinates(coordinates) => Some(*coordinates),
+            _ => None,
+        }
+    }
+
+    pub fn block(&self, name: &str) -> Option<Block> {
+        match self.get(name)? {
+            ArgumentValue::Block(block) => Some(*block),
+            _ => None,
+        }
+    }
+
+    pub fn item(&self, name: &str) -> Option<Item> {
+        match self.get(name)? {
+            ArgumentValue::Item(item) => Some(*item),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(ty: ArgumentType, input: &str) -> Result<ArgumentValue, CommandError> {
+        ty.parse(&mut StringReader::new(input))
+    }
+
+    #[test]
+    fn test_reader() {
+        let mut reader = StringReader::new("give \"a \\\"b\\\"\" rest of it");
+        assert_eq!(reader.read_word(), "give");
+        reader.skip();
+        assert_eq!(reader.read_quotable().unwrap(), "a \"b\"");
+        reader.skip();
+        assert_eq!(reader.read_rest(), "rest of it");
+        assert!(reader.at_end());
+    }
+
+    #[test]
+    fn test_integer() {
+        let ty = ArgumentType::Integer {
+            min: Some(0),
+            max: Some(64),
+        };
+        assert_eq!(parse(ty, "5"), Ok(ArgumentValue::Integer(5)));
+        assert_eq!(
+            parse(ty, "100").map_err(|e| e.to_string()),
+            Err("Integer must not be more than 64, found 100".to_string())
+        );
+        assert_eq!(
+            parse(ty, "five"),
+            Err(CommandError::Syntax(
+                TextComponent::translate("feather.argument.integer.invalid", vec!["five".into()]),
+                0
+            ))
+        );
+    }
+
+    #[test]
+    fn test_coordinates() {
+        let origin = position!(10.0, 64.0, -3.0);
+
+        let coordinates = match parse(ArgumentType::Vec3, "~ ~2 5").unwrap() {
+            ArgumentValue::Coordinates(coordinates) => coordinates,
+            value => panic!("unexpected value {:?}", value),
+        };
+        assert_eq!(coordinates.resolve(origin), position!(10.0, 66.0, 5.5));
+
+        let coordinates = match parse(ArgumentType::BlockPos, "1 ~-1 ~").unwrap() {
+            ArgumentValue::Coordinates(coordinates) => coordinates,
+            value => panic!("unexpected value {:?}", value),
+        };
+        assert_eq!(
+            coordinates.resolve_block(origin),
+            BlockPosition::new(1, 63, -3)
+        );
+
+        assert!(parse(ArgumentType::BlockPos, "1.5 2 3").is_err());
+        assert!(parse(ArgumentType::Vec3, "^ ~ ^").is_err());
+        assert!(parse(ArgumentType::Vec3, "1 2").is_err());
+    }
+
+    #[test]
+    fn test_local_coordinates() {
+        // Facing south, left is east.
+        let origin = position!(0.0, 64.0, 0.0);
+        let coordinates = match parse(ArgumentType::Vec3, "^1 ^ ^2").unwrap() {
+            ArgumentValue::Coordinates(coordinates) => coordinates,
+            value => panic!("unexpected value {:?}", value),
+        };
+        let pos = coordinates.resolve(origin);
+        assert!((pos.x - 1.0).abs() < 1.0e-9);
+        assert!((pos.y - 64.0).abs() < 1.0e-9);
+        assert!((pos.z - 2.0).abs() < 1.0e-9);
+    }
+
+    #[test]
+    fn test_block_state() {
+        let block = match parse(ArgumentType::BlockState, "lever[powered=true]").unwrap() {
+            ArgumentValue::Block(block) => block,
+            value => panic!("unexpected value {:?}", value),
+        };
+        assert_eq!(
+            block.property("powered").as_ref().map(String::as_str),
+            Some("true")
+        );
+
+        assert!(parse(ArgumentType::BlockState, "lever[powered=maybe]").is_err());
+        assert!(parse(ArgumentType::BlockState, "not_a_block").is_err());
+    }
+
+    #[test]
+    fn test_item_and_selector() {
+        assert_eq!(
+            parse(ArgumentType::Item, "minecraft:diamond"),
+            Ok(ArgumentValue::Item(Item::Diamond))
+        );
+        assert_eq!(
+            parse(ArgumentType::Item, "diamond"),
+            Ok(ArgumentValue::Item(Item::Diamond))
+        );
+        assert_eq!(
+            parse(ArgumentType::PLAYER, "@s"),
+            Ok(ArgumentValue::Entities(EntitySelector::Selector(
+                TargetSelector::new(SelectorVariable::Sender)
+            )))
+        );
+        assert_eq!(
+            parse(ArgumentType::PLAYER, "Notch"),
+            Ok(ArgumentValue::Entities(EntitySelector::Player(
+                "Notch".to_string()
+            )))
+        );
+    }
+}
@@ -0,0 +1,96 @@
+//! The `/gamemode` command.
+
+use super::{
+    argument, literal, ArgumentType, Arguments, CommandCtx, CommandDispatcher, CommandError,
+    CommandFn, CommandNode,
+};
+use crate::network::Network;
+use feather_core::network::packet::implementation::{
+    ChangeGameState, PlayerInfo, PlayerInfoAction,
+};
+use feather_core::{Gamemode, TextComponent};
+use uuid::Uuid;
+
+/// The Change Game State reason for a gamemode change.
+const CHANGE_GAMEMODE: u8 = 3;
+
+pub fn register(dispatcher: &mut CommandDispatcher) {
+    dispatcher.register(
+        literal("gamemode")
+            .requires(2)
+            .then(mode("survival", survival))
+            .then(mode("creative", creative))
+            .then(mode("adventure", adventure))
+            .then(mode("spectator", spectator)),
+    );
+}
+
+/// Creates the node for `/gamemode <name> [<target>]`.
+fn mode(name: &'static str, executor: CommandFn) -> CommandNode {
+    literal(name)
+        .executes(executor)
+        .then(argument("target", ArgumentType::PLAYERS).executes(executor))
+}
+
+fn survival(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    set_gamemode(ctx, args, Gamemode::Survival)
+}
+
+fn creative(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    set_gamemode(ctx, args, Gamemode::Creative)
+}
+
+fn adventure(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    set_gamemode(ctx, args, Gamemode::Adventure)
+}
+
+fn spectator(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    set_gamemode(ctx, args, Gamemode::Spectator)
+}
+
+fn set_gamemode(
+    ctx: &mut CommandCtx,
+    args: &Arguments,
+    gamemode: Gamemode,
+) -> Result<(), CommandError> {
+    let targets = match args.entities("target") {
+        Some(selector) => ctx.entities(selector)?,
+        None => vec![ctx.player()?],
+    };
+
+    for target in &targets {
+        match ctx.world.get_component_mut::<Gamemode>(*target) {
+            Some(mut component) => *component = gamemode,
+            None => continue,
+        }
+
+        if let Some(network) = ctx.world.get_component::<Network>(*target) {
+            network.send(ChangeGameState {
+                reason: CHANGE_GAMEMODE,
+                value: f32::from(gamemode.get_id()),
+            });
+        }
+        let uuid = *ctx.world.get_component::<Uuid>(*target).unwrap();
+        ctx.broadcast(PlayerInfo {
+            action: PlayerInfoAction::UpdateGamemode(gamemode),
+            uuid,
+        });
+    }
+
+    ctx.send_translated(
+        "feather.command.gamemode.success",
+        vec![ctx.describe(&targets), gamemode_name(gamemode)],
+    );
+    Ok(())
+}
+
+/// Returns the translated name of a game mode.
+fn gamemode_name(gamemode: Gamemode) -> TextComponent {
+    let key = match gamemode {
+        Gamemode::Survival => "feather.gamemode.survival",
+        Gamemode::Creative => "feather.gamemode.creative",
+        Gamemode::Adventure => "feather.gamemode.adventure",
+        Gamemode::Spectator => "feather.gamemode.spectator",
+    };
+    TextComponent::translate(key, vec![])
+}
@@ -0,0 +1,87 @@
+//! The `/give` command.
+
+use super::{
+    argument, literal, ArgumentType, Arguments, CommandCtx, CommandDispatcher, CommandError,
+};
+use crate::p_inventory::{EntityInventory, InventoryUpdateEvent};
+use feather_core::inventory::max_size;
+use feather_core::ItemStack;
+
+/// The most items which can be given at once.
+const MAX_COUNT: i32 = 6400;
+
+pub fn register(dispatcher: &mut CommandDispatcher) {
+    dispatcher.register(
+        literal("give").requires(2).then(
+            argument("targets", ArgumentType::PLAYERS).then(
+                argument("item", ArgumentType::Item).executes(give).then(
+                    argument(
+                        "count",
+                        ArgumentType::Integer {
+                            min: Some(1),
+                            max: Some(MAX_COUNT),
+                        },
+                    )
+                    .executes(give),
+                ),
+            ),
+        ),
+    );
+}
+
+/// Adds items to the inventories of players. Items which
+/// do not fit in an inventory are not given, and the number
+/// of items each player actually received is reported.
+fn give(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let targets = ctx.entities(args.entities("targets").unwrap())?;
+    let item = args.item("item").unwrap();
+    let count = args.integer("count").unwrap_or(1);
+    let stack_size = i32::from(max_size(item));
+
+    let mut given = Vec::with_capacity(targets.len());
+    for target in &targets {
+        let mut remaining = count;
+        while remaining > 0 {
+            let amount = remaining.min(stack_size);
+
+            let (slots, left) = match ctx.world.get_component_mut::<EntityInventory>(*target) {
+                Some(mut inventory) => inventory.collect_item(ItemStack::new(item, amount as u8)),
+                None => break,
+            };
+            remaining -= amount - i32::from(left);
+            ctx.scheduler.trigger(InventoryUpdateEvent {
+                slots,
+                player: *target,
+            });
+            if left > 0 {
+                break;
+            }
+        }
+        given.push(count - remaining);
+    }
+
+    if given.iter().all(|amount| *amount == count) {
+        ctx.send_translated(
+            "feather.command.give.success",
+            vec![
+                count.to_string().into(),
+                item.identifier().into(),
+                ctx.describe(&targets),
+            ],
+        );
+        return Ok(());
+    }
+
+    // Some inventories were full, so report each player separately.
+    for (target, amount) in targets.iter().zip(given) {
+        ctx.send_translated(
+            "feather.command.give.success",
+            vec![
+                amount.to_string().into(),
+                item.identifier().into(),
+                ctx.describe(&[*target]),
+            ],
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,43 @@
+//! The `/help` command.
+
+use super::{
+    argument, literal, ArgumentType, Arguments, CommandCtx, CommandDispatcher, CommandError,
+};
+
+pub fn register(dispatcher: &mut CommandDispatcher) {
+    dispatcher.register(
+        literal("help")
+            .executes(help_all)
+            .then(argument("command", ArgumentType::GreedyString).executes(help_command)),
+    );
+}
+
+/// Lists the usage of every command.
+fn help_all(ctx: &mut CommandCtx, _args: &Arguments) -> Result<(), CommandError> {
+    let permissions = ctx.permissions();
+    let dispatcher = ctx.resource::<CommandDispatcher>();
+    for command in dispatcher.root().children() {
+        if !command.can_use(&permissions) {
+            continue;
+        }
+        ctx.send_message(&format!("/{}", command.smart_usage()));
+    }
+    Ok(())
+}
+
+/// Lists every usage of a single command.
+fn help_command(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let name = args.string("command").unwrap_or_default();
+    let permissions = ctx.permissions();
+    let dispatcher = ctx.resource::<CommandDispatcher>();
+
+    let command = dispatcher
+        .root()
+        .child(name)
+        .filter(|command| command.can_use(&permissions))
+        .ok_or_else(|| CommandError::failed("feather.command.help.failed", vec![]))?;
+    for usage in command.all_usage() {
+        ctx.send_message(&format!("/{}", usage));
+    }
+    Ok(())
+}
@@ -0,0 +1,36 @@
+//! The `/kill` command.
+
+use super::{
+    argument, literal, ArgumentType, Arguments, CommandCtx, CommandDispatcher, CommandError,
+};
+use crate::lazy::delete_entity_now;
+use crate::player::death::{kill_now, Dead};
+
+pub fn register(dispatcher: &mut CommandDispatcher) {
+    dispatcher.register(
+        literal("kill")
+            .requires(2)
+            .executes(kill)
+            .then(argument("targets", ArgumentType::ENTITIES).executes(kill)),
+    );
+}
+
+/// Kills players, who may then respawn, and removes other entities.
+fn kill(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let targets = match args.entities("targets") {
+        Some(selector) => ctx.entities(selector)?,
+        None => vec![ctx.player()?],
+    };
+    let described = ctx.describe(&targets);
+
+    for target in targets {
+        if ctx.world.get_component::<Dead>(target).is_some() {
+            kill_now(ctx.world, target);
+        } else {
+            delete_entity_now(ctx.world, ctx.scheduler, target);
+        }
+    }
+
+    ctx.send_translated("feather.command.kill.success", vec![described]);
+    Ok(())
+}
@@ -0,0 +1,105 @@
+//! Commands for talking to players: `/say`, `/tell` (also
+//! `/msg` and `/w`), `/teammsg` (also `/tm`) and `/list`.
+//!
+//! Private and team messages pass through the chat
+//! pipeline, so they are filtered and muted like chat.
+
+use super::{
+    argument, literal, ArgumentType, Arguments, CommandCtx, CommandDispatcher, CommandError,
+};
+use crate::entity::Name;
+use crate::player::chat::{
+    self, send_chat, ChatBroadcastEvent, ChatKind, ChatMessage, ChatPosition,
+};
+use crate::state::StateInner;
+use feather_core::TextComponent;
+use legion::query::{IntoQuery, Read};
+
+pub fn register(dispatcher: &mut CommandDispatcher) {
+    dispatcher.register(
+        literal("say")
+            .requires(2)
+            .then(argument("message", ArgumentType::Message).executes(say)),
+    );
+    for name in &["tell", "msg", "w"] {
+        dispatcher.register(
+            literal(*name).then(
+                argument("targets", ArgumentType::PLAYERS)
+                    .then(argument("message", ArgumentType::Message).executes(tell)),
+            ),
+        );
+    }
+    for name in &["teammsg", "tm"] {
+        dispatcher.register(
+            literal(*name).then(argument("message", ArgumentType::Message).executes(teammsg)),
+        );
+    }
+    dispatcher.register(literal("list").executes(list));
+}
+
+/// Broadcasts a message in the name of the sender.
+fn say(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let name = ctx.sender_name();
+    let message = args.string("message").unwrap();
+
+    ctx.scheduler.trigger(ChatBroadcastEvent {
+        message: TextComponent::translate(
+            "chat.type.announcement",
+            vec![name.as_str().into(), message.into()],
+        ),
+        position: ChatPosition::Chat,
+    });
+
+    info!("[{}] {}", name, message);
+    Ok(())
+}
+
+/// Sends a private message to players.
+fn tell(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let targets = ctx.entities(args.entities("targets").unwrap())?;
+    let message = ChatMessage::new(
+        ctx.sender,
+        ctx.sender_name(),
+        args.string("message").unwrap().to_string(),
+        ChatKind::Whisper(targets),
+    );
+
+    send_chat(ctx, message);
+    Ok(())
+}
+
+/// Sends a message to the players on the sender's team.
+fn teammsg(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    ctx.player()?;
+    let name = ctx.sender_name();
+    let team = chat::team(&ctx.resource::<StateInner>().config.chat.teams, &name)
+        .ok_or_else(|| CommandError::failed("feather.command.teammsg.noteam", vec![]))?;
+
+    let message = ChatMessage::new(
+        ctx.sender,
+        name,
+        args.string("message").unwrap().to_string(),
+        ChatKind::Team(team),
+    );
+    send_chat(ctx, message);
+    Ok(())
+}
+
+/// Lists the online players.
+fn list(ctx: &mut CommandCtx, _args: &Arguments) -> Result<(), CommandError> {
+    let names: Vec<String> = <Read<Name>>::query()
+        .iter_entities_immutable(ctx.world)
+        .map(|(_, name)| name.0.clone())
+        .collect();
+    let max_players = ctx.resource::<StateInner>().config.server.max_players;
+
+    ctx.send_translated(
+        "feather.command.list.players",
+        vec![
+            names.len().to_string().into(),
+            max_players.to_string().into(),
+            names.join(", ").into(),
+        ],
+    );
+    Ok(())
+}
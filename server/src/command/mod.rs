@@ -0,0 +1,485 @@
+//! Commands, such as `/help` and `/tp`.
+//!
+//! Commands are registered with the `CommandDispatcher` as trees of
+//! `CommandNode`s: literals, such as the command name, and typed
+//! arguments. The same tree is sent to clients in the Declare Commands
+//! packet, so they can highlight syntax and complete commands as they
+//! are typed; arguments which need information only the server has,
+//! like player names, are completed through Tab-Complete packets.
+//!
+//! A command is parsed and executed lazily, with full access to
+//! the world and to resources through `CommandCtx`. Each command
+//! has a permission node, `minecraft.command.<name>` by default,
+//! and an op level which grants it to players for whom the node
+//! is not set (see `crate::permissions`). The console may use
+//! every command.
+//!
+//! Feedback and errors are sent as `translate` components with keys
+//! from the server's translation catalogue, so each player sees them
+//! in their own language (see `crate::locale`).
+
+use crate::access::AccessLists;
+use crate::entity::Name;
+use crate::locale::{Translations, DEFAULT_LOCALE};
+use crate::network::Network;
+use crate::permissions::{PermissionSet, Permissions};
+use crate::player::chat::ChatSettings;
+use crate::player::PlayerJoinEvent;
+use crate::state::{State, StateInner};
+use crate::worlds::{DimensionId, WorldId};
+use feather_core::network::packet::implementation::ChatMessageClientbound;
+use feather_core::text::Color;
+use feather_core::{Dimension, Packet, Position, TextComponent};
+use legion::entity::Entity;
+use legion::query::{IntoQuery, Read};
+use legion::world::World;
+use std::fmt;
+use std::sync::Arc;
+use tonks::{PreparedWorld, Query, Scheduler};
+use uuid::Uuid;
+
+pub mod argument;
+mod gamemode;
+mod give;
+mod help;
+mod kill;
+mod message;
+mod moderation;
+pub mod node;
+mod permission;
+pub mod selector;
+mod teleport;
+mod time;
+mod weather;
+mod world;
+
+pub use argument::{ArgumentType, Arguments, EntitySelector, SuggestionCtx};
+pub use node::{argument, literal, CommandNode, NodeKind, Suggestions};
+
+/// The number of characters before an error
+/// shown when reporting a syntax error.
+const ERROR_CONTEXT_LENGTH: usize = 10;
+
+/// A function run when a command is executed.
+pub type CommandFn = fn(&mut CommandCtx, &Arguments) -> Result<(), CommandError>;
+
+/// The sender of a command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandSender {
+    Player(Entity),
+    /// The server console.
+    Console,
+}
+
+/// Event triggered when a command is sent.
+#[derive(Debug, Clone)]
+pub struct CommandEvent {
+    pub sender: CommandSender,
+    /// The command, without the leading slash.
+    pub command: String,
+}
+
+/// An error from parsing or executing a command. Its message is a
+/// component, usually a translation from the server's catalogue.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandError {
+    /// The command could not be parsed. The second
+    /// field is the byte offset of the error.
+    Syntax(TextComponent, usize),
+    /// The command was parsed, but could not be executed.
+    Failed(TextComponent),
+}
+
+impl CommandError {
+    /// Creates an error from a key in the translation catalogue.
+    pub fn failed(key: &str, args: Vec<TextComponent>) -> Self {
+        CommandError::Failed(TextComponent::translate(key, args))
+    }
+
+    /// Returns the message shown to the sender.
+    pub fn message(&self) -> &TextComponent {
+        match self {
+            CommandError::Syntax(message, _) | CommandError::Failed(message) => message,
+        }
+    }
+}
+
+/// Displays the message in the default language.
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = Translations::builtin_ref().render(DEFAULT_LOCALE, self.message());
+        write!(f, "{}", message.to_plain())
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+/// Resource storing the registered commands.
+///
+/// Clones share the command tree, which lets the console
+/// complete commands against the tree the server executes.
+/// Commands must therefore be registered before cloning.
+#[derive(Clone, Resource)]
+pub struct CommandDispatcher {
+    root: Arc<CommandNode>,
+}
+
+impl CommandDispatcher {
+    /// Creates a dispatcher with the built-in commands registered.
+    pub fn builtin() -> Self {
+        let mut dispatcher = Self {
+            root: Arc::new(CommandNode::new(NodeKind::Root)),
+        };
+        help::register(&mut dispatcher);
+        teleport::register(&mut dispatcher);
+        give::register(&mut dispatcher);
+        gamemode::register(&mut dispatcher);
+        time::register(&mut dispatcher);
+        weather::register(&mut dispatcher);
+        kill::register(&mut dispatcher);
+        moderation::register(&mut dispatcher);
+        permission::register(&mut dispatcher);
+        message::register(&mut dispatcher);
+        world::register(&mut dispatcher);
+        dispatcher
+    }
+
+    /// Registers a command, whose root must be a literal.
+    /// A command with the same name is replaced.
+    pub fn register(&mut self, mut command: CommandNode) {
+        let name = match command.kind() {
+            NodeKind::Literal(name) => *name,
+            _ => panic!("commands must begin with a literal"),
+        };
+        if command.permission_node().is_none() {
+            command = command.permission(format!("minecraft.command.{}", name));
+        }
+        Arc::get_mut(&mut self.root)
+            .expect("commands must be registered before the dispatcher is cloned")
+            .add_child(command);
+    }
+
+    /// Returns the root of the command tree.
+    pub fn root(&self) -> &CommandNode {
+        &self.root
+    }
+
+    /// See `CommandNode::parse()`.
+    pub fn parse(
+        &self,
+        command: &str,
+        permissions: &PermissionSet,
+    ) -> Result<(CommandFn, Arguments), CommandError> {
+        self.root.parse(command, permissions)
+    }
+
+    /// See `CommandNode::suggest()`.
+    pub fn suggest(&self, command: &str, ctx: &SuggestionCtx) -> Suggestions {
+        self.root.suggest(command, ctx)
+    }
+}
+
+/// The context in which a command is executed.
+pub struct CommandCtx<'a> {
+    pub sender: CommandSender,
+    pub world: &'a mut World,
+    pub scheduler: &'a mut Scheduler,
+}
+
+impl<'a> CommandCtx<'a> {
+    /// Returns a resource.
+    pub fn resource<T: Send + Sync + 'static>(&self) -> &T {
+        self.scheduler.resources().get::<T>()
+    }
+
+    /// Returns a resource mutably.
+    pub fn resource_mut<T: Send + Sync + 'static>(&mut self) -> &mut T {
+        self.scheduler.resources_mut().get_mut::<T>()
+    }
+
+    /// Returns the player who sent the command, or an
+    /// error if it was sent from the console.
+    pub fn player(&self) -> Result<Entity, CommandError> {
+        match self.sender {
+            CommandSender::Player(player) => Ok(player),
+            CommandSender::Console => Err(CommandError::failed(
+                "feather.command.error.playerRequired",
+                vec![],
+            )),
+        }
+    }
+
+    /// Returns the permissions of the sender.
+    pub fn permissions(&self) -> PermissionSet {
+        match self.sender {
+            CommandSender::Player(player) => self.player_permissions(player),
+            CommandSender::Console => PermissionSet::console(),
+        }
+    }
+
+    /// Returns whether the sender has a permission node, which
+    /// is granted by the given op level if it is not set.
+    pub fn has_permission(&self, node: &str, default_level: u8) -> bool {
+        self.permissions().has(node, default_level)
+    }
+
+    /// Returns the permissions of a player.
+    pub fn player_permissions(&self, player: Entity) -> PermissionSet {
+        match (
+            self.world.get_component::<Uuid>(player),
+            self.world.get_component::<Name>(player),
+        ) {
+            (Some(uuid), Some(name)) => self.resource::<Permissions>().for_player(
+                self.resource::<AccessLists>(),
+                *uuid,
+                &name.0,
+            ),
+            _ => PermissionSet::default(),
+        }
+    }
+
+    /// Returns the name of the sender.
+    pub fn sender_name(&self) -> String {
+        match self.sender {
+            CommandSender::Player(player) => self
+                .world
+                .get_component::<Name>(player)
+                .map(|name| name.0.clone())
+                .unwrap_or_default(),
+            CommandSender::Console => "Server".to_string(),
+        }
+    }
+
+    /// Returns the position relative coordinates are
+    /// resolved against: the position of the sending player,
+    /// or the spawn point of the main world for the console.
+    pub fn origin(&self) -> Position {
+        match self.sender {
+            CommandSender::Player(player) => *self.world.get_component::<Position>(player).unwrap(),
+            CommandSender::Console => {
+                let level = &self.resource::<StateInner>().world(WorldId::MAIN).level;
+                position!(
+                    f64::from(level.spawn_x) + 0.5,
+                    f64::from(level.spawn_y),
+                    f64::from(level.spawn_z) + 0.5
+                )
+            }
+        }
+    }
+
+    /// Returns the dimension of the sender, which
+    /// is the main overworld for the console.
+    pub fn dimension(&self) -> DimensionId {
+        match self.sender {
+            CommandSender::Player(player) => {
+                *self.world.get_component::<DimensionId>(player).unwrap()
+            }
+            CommandSender::Console => DimensionId::new(WorldId::MAIN, Dimension::Overworld),
+        }
+    }
+
+    /// Describes entities in feedback messages: by name
+    /// if there is only one, or else by their number.
+    pub fn describe(&self, entities: &[Entity]) -> TextComponent {
+        match entities {
+            [entity] => self.world.get_component::<Name>(*entity).map_or_else(
+                || TextComponent::translate("feather.command.entity", vec![]),
+                |name| TextComponent::text(name.0.clone()),
+            ),
+            _ => TextComponent::translate(
+                "feather.command.entities",
+                vec![entities.len().to_string().into()],
+            ),
+        }
+    }
+
+    /// Returns the entities an entity argument refers
+    /// to, or an error if it refers to none.
+    pub fn entities(&self, selector: &EntitySelector) -> Result<Vec<Entity>, CommandError> {
+        match selector {
+            EntitySelector::Selector(selector) => {
+                let entities = selector.select(self);
+                if !entities.is_empty() {
+                    Ok(entities)
+                } else if selector.includes_entities() {
+                    Err(CommandError::failed(
+                        "feather.command.error.noEntity",
+                        vec![],
+                    ))
+                } else {
+                    Err(CommandError::failed(
+                        "feather.command.error.noPlayer",
+                        vec![],
+                    ))
+                }
+            }
+            EntitySelector::Player(name) => find_player(self.world, name)
+                .map(|player| vec![player])
+                .ok_or_else(|| CommandError::failed("feather.command.error.noPlayer", vec![])),
+        }
+    }
+
+    /// Sends a packet to every online player.
+    pub fn broadcast<P: Packet + Clone>(&self, packet: P) {
+        for (_, network) in <Read<Network>>::query().iter_entities_immutable(self.world) {
+            network.send(packet.clone());
+        }
+    }
+
+    /// Sends a packet to every player in the given world.
+    pub fn broadcast_world<P: Packet + Clone>(&self, world: WorldId, packet: P) {
+        let query = <(Read<Network>, Read<DimensionId>)>::query();
+        for (_, (network, dimension)) in query.iter_entities_immutable(self.world) {
+            if dimension.world == world {
+                network.send(packet.clone());
+            }
+        }
+    }
+
+    /// Sends a player the commands they may use. This should
+    /// be called after their permissions change.
+    pub fn send_commands(&self, player: Entity) {
+        let permissions = self.player_permissions(player);
+        if let Some(network) = self.world.get_component::<Network>(player) {
+            network.send(
+                self.resource::<CommandDispatcher>()
+                    .root()
+                    .to_packet(&permissions),
+            );
+        }
+    }
+
+    /// Sends a message to the sender.
+    pub fn send_message(&self, message: &str) {
+        self.send_component(TextComponent::text(message));
+    }
+
+    /// Reports an error to the sender. Syntax errors
+    /// show where in `command` the error occurred.
+    pub fn send_error(&self, error: &CommandError, command: &str) {
+        let message = error.message().clone();
+        let cursor = match error {
+            CommandError::Syntax(_, cursor) => *cursor,
+            CommandError::Failed(_) => {
+                self.send_component(message.color(Color::Red));
+                return;
+            }
+        };
+
+        let cursor = cursor.min(command.len());
+        let context_start = command[..cursor]
+            .char_indices()
+            .rev()
+            .nth(ERROR_CONTEXT_LENGTH - 1)
+            .map_or(0, |(index, _)| index);
+        let ellipsis = if context_start > 0 { "..." } else { "" };
+        let context = &command[context_start..cursor];
+        let rest = &command[cursor..];
+
+        self.send_component(
+            TextComponent::text("")
+                .child(message.color(Color::Red))
+                .child("\n")
+                .child(
+                    TextComponent::text(format!("{}{}", ellipsis, context))
+                        .color(Color::Gray)
+                        .child(TextComponent::text(rest).color(Color::Red).underlined(true))
+                        .child(
+                            TextComponent::translate("feather.command.context.here", vec![])
+                                .color(Color::Red)
+                                .italic(true),
+                        ),
+                ),
+        );
+    }
+
+    /// Sends a chat component to a player, in their language,
+    /// or logs its plain text in English for the console.
+    pub fn send_component(&self, message: TextComponent) {
+        match self.sender {
+            CommandSender::Player(player) => {
+                let settings = self
+                    .world
+                    .get_component::<ChatSettings>(player)
+                    .map_or_else(ChatSettings::default, |settings| *settings);
+                if let Some(network) = self.world.get_component::<Network>(player) {
+                    network.send(ChatMessageClientbound {
+                        message: settings.apply_colors(self.localize(player, &message)),
+                        position: 1,
+                    });
+                }
+            }
+            CommandSender::Console => {
+                let message = self
+                    .resource::<Translations>()
+                    .render(DEFAULT_LOCALE, &message);
+                info!("{}", message.to_plain());
+            }
+        }
+    }
+
+    /// Sends the sender a message from the translation
+    /// catalogue, in their language (see `crate::locale`).
+    pub fn send_translated(&self, key: &str, args: Vec<TextComponent>) {
+        self.send_component(TextComponent::translate(key, args));
+    }
+
+    /// Renders the server's translations in a message
+    /// in the language of the player receiving it.
+    pub fn localize(&self, player: Entity, message: &TextComponent) -> TextComponent {
+        self.resource::<Translations>()
+            .render_for(self.world, player, message)
+    }
+}
+
+/// Returns the online player with the given name, ignoring case.
+pub fn find_player(world: &World, name: &str) -> Option<Entity> {
+    <Read<Name>>::query()
+        .iter_entities_immutable(world)
+        .find(|(_, player_name)| player_name.0.eq_ignore_ascii_case(name))
+        .map(|(player, _)| player)
+}
+
+/// Parses and executes commands when they are sent.
+#[event_handler]
+fn execute_commands(event: &CommandEvent, state: &State) {
+    let sender = event.sender;
+    let command = event.command.clone();
+
+    state.exec_with_scheduler(move |world, scheduler| {
+        let mut ctx = CommandCtx {
+            sender,
+            world,
+            scheduler,
+        };
+        if let CommandSender::Player(_) = sender {
+            info!("{} issued server command: /{}", ctx.sender_name(), command);
+        }
+
+        let parsed = ctx
+            .resource::<CommandDispatcher>()
+            .parse(&command, &ctx.permissions());
+        let result = parsed.and_then(|(executor, args)| executor(&mut ctx, &args));
+        if let Err(e) = result {
+            ctx.send_error(&e, &command);
+        }
+    });
+}
+
+/// Sends players the commands they may use when they join.
+#[event_handler]
+fn send_commands(
+    event: &PlayerJoinEvent,
+    dispatcher: &CommandDispatcher,
+    access: &AccessLists,
+    permissions: &Permissions,
+    _query: &mut Query<(Read<Uuid>, Read<Name>, Read<Network>)>,
+    world: &mut PreparedWorld,
+) {
+    let uuid = *world.get_component::<Uuid>(event.player).unwrap();
+    let name = world.get_component::<Name>(event.player).unwrap();
+    let permissions = permissions.for_player(access, uuid, &name.0);
+
+    let network = world.get_component::<Network>(event.player).unwrap();
+    network.send(dispatcher.root().to_packet(&permissions));
+}
@@ -0,0 +1,308 @@
+//! Commands for moderating players: `/kick`, `/ban`, `/pardon`,
+//! `/op`, `/deop`, `/whitelist`, `/mute` and `/unmute`.
+
+use super::{
+    argument, find_player, literal, ArgumentType, Arguments, CommandCtx, CommandDispatcher,
+    CommandError,
+};
+use crate::access::{
+    AccessLists, BanEntry, MuteEntry, OpEntry, PlayerList, Profile, WhitelistEntry, MAX_OP_LEVEL,
+    WHITELIST_FILE,
+};
+use crate::entity::Name;
+use crate::network::Network;
+use crate::util::disconnect_player_now;
+use feather_core::TextComponent;
+use uuid::Uuid;
+
+pub fn register(dispatcher: &mut CommandDispatcher) {
+    dispatcher.register(
+        literal("kick").requires(3).then(
+            argument("targets", ArgumentType::PLAYERS)
+                .executes(kick)
+                .then(argument("reason", ArgumentType::Message).executes(kick)),
+        ),
+    );
+    dispatcher.register(
+        literal("ban").requires(3).then(
+            argument("targets", ArgumentType::GameProfile)
+                .executes(ban)
+                .then(argument("reason", ArgumentType::Message).executes(ban)),
+        ),
+    );
+    dispatcher.register(
+        literal("pardon")
+            .requires(3)
+            .then(argument("targets", ArgumentType::GameProfile).executes(pardon)),
+    );
+    dispatcher.register(
+        literal("op")
+            .requires(3)
+            .then(argument("targets", ArgumentType::GameProfile).executes(op)),
+    );
+    dispatcher.register(
+        literal("deop")
+            .requires(3)
+            .then(argument("targets", ArgumentType::GameProfile).executes(deop)),
+    );
+    dispatcher.register(
+        literal("whitelist")
+            .requires(3)
+            .then(literal("on").executes(whitelist_on))
+            .then(literal("off").executes(whitelist_off))
+            .then(literal("list").executes(whitelist_list))
+            .then(literal("reload").executes(whitelist_reload))
+            .then(
+                literal("add")
+                    .then(argument("targets", ArgumentType::GameProfile).executes(whitelist_add)),
+            )
+            .then(
+                literal("remove").then(
+                    argument("targets", ArgumentType::GameProfile).executes(whitelist_remove),
+                ),
+            ),
+    );
+    dispatcher.register(
+        literal("mute").requires(3).then(
+            argument("targets", ArgumentType::GameProfile)
+                .executes(mute)
+                .then(argument("reason", ArgumentType::Message).executes(mute)),
+        ),
+    );
+    dispatcher.register(
+        literal("unmute")
+            .requires(3)
+            .then(argument("targets", ArgumentType::GameProfile).executes(unmute)),
+    );
+}
+
+/// Returns the profile of the player named by the `targets`
+/// argument. Offline players are only known by name.
+pub(super) fn target_profile(ctx: &CommandCtx, args: &Arguments) -> Profile {
+    let name = args.string("targets").unwrap();
+    match find_player(ctx.world, name) {
+        Some(player) => Profile::new(
+            ctx.world.get_component::<Uuid>(player).map(|uuid| *uuid),
+            ctx.world.get_component::<Name>(player).unwrap().0.clone(),
+        ),
+        None => Profile::new(None, name),
+    }
+}
+
+fn kick(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let targets = ctx.entities(args.entities("targets").unwrap())?;
+    let reason = match args.string("reason") {
+        Some(reason) => TextComponent::text(reason),
+        None => TextComponent::translate("feather.command.kick.reason", vec![]),
+    };
+
+    for target in targets {
+        if ctx.world.get_component::<Network>(target).is_none() {
+            continue;
+        }
+        let described = ctx.describe(&[target]);
+        disconnect_player_now(ctx.world, ctx.scheduler, target, &reason);
+        ctx.send_translated(
+            "feather.command.kick.success",
+            vec![described, reason.clone()],
+        );
+    }
+    Ok(())
+}
+
+fn ban(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let profile = target_profile(ctx, args);
+    let reason = args.string("reason").unwrap_or("Banned by an operator.");
+    let entry = BanEntry::new(profile.clone(), ctx.sender_name(), reason);
+
+    if !ctx.resource_mut::<AccessLists>().bans.add(entry) {
+        return Err(CommandError::failed("feather.command.ban.failed", vec![]));
+    }
+
+    if let Some(player) = find_player(ctx.world, &profile.name) {
+        let message = TextComponent::translate("feather.disconnect.banned", vec![reason.into()]);
+        disconnect_player_now(ctx.world, ctx.scheduler, player, &message);
+    }
+    ctx.send_translated(
+        "feather.command.ban.success",
+        vec![profile.name.into(), reason.into()],
+    );
+    Ok(())
+}
+
+fn pardon(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let name = args.string("targets").unwrap();
+    let entry = ctx
+        .resource_mut::<AccessLists>()
+        .bans
+        .remove(name)
+        .ok_or_else(|| CommandError::failed("feather.command.pardon.failed", vec![]))?;
+
+    ctx.send_translated(
+        "feather.command.pardon.success",
+        vec![entry.profile.name.into()],
+    );
+    Ok(())
+}
+
+fn mute(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let profile = target_profile(ctx, args);
+    let reason = args.string("reason").unwrap_or("Muted by an operator.");
+    let entry = MuteEntry {
+        profile: profile.clone(),
+        source: ctx.sender_name(),
+        reason: reason.to_string(),
+    };
+
+    if !ctx.resource_mut::<AccessLists>().mutes.add(entry) {
+        return Err(CommandError::failed("feather.command.mute.failed", vec![]));
+    }
+
+    ctx.send_translated(
+        "feather.command.mute.success",
+        vec![profile.name.as_str().into(), reason.into()],
+    );
+    Ok(())
+}
+
+fn unmute(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let name = args.string("targets").unwrap();
+    let entry = ctx
+        .resource_mut::<AccessLists>()
+        .mutes
+        .remove(name)
+        .ok_or_else(|| CommandError::failed("feather.command.unmute.failed", vec![]))?;
+
+    ctx.send_translated(
+        "feather.command.unmute.success",
+        vec![entry.profile.name.into()],
+    );
+    Ok(())
+}
+
+fn op(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let profile = target_profile(ctx, args);
+    let entry = OpEntry {
+        profile: profile.clone(),
+        level: MAX_OP_LEVEL,
+        bypasses_player_limit: false,
+    };
+
+    if !ctx.resource_mut::<AccessLists>().ops.add(entry) {
+        return Err(CommandError::failed("feather.command.op.failed", vec![]));
+    }
+
+    if let Some(player) = find_player(ctx.world, &profile.name) {
+        ctx.send_commands(player);
+    }
+    ctx.send_translated("feather.command.op.success", vec![profile.name.into()]);
+    Ok(())
+}
+
+fn deop(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let name = args.string("targets").unwrap();
+    let entry = ctx
+        .resource_mut::<AccessLists>()
+        .ops
+        .remove(name)
+        .ok_or_else(|| CommandError::failed("feather.command.deop.failed", vec![]))?;
+
+    if let Some(player) = find_player(ctx.world, name) {
+        ctx.send_commands(player);
+    }
+    ctx.send_translated(
+        "feather.command.deop.success",
+        vec![entry.profile.name.into()],
+    );
+    Ok(())
+}
+
+fn whitelist_on(ctx: &mut CommandCtx, _args: &Arguments) -> Result<(), CommandError> {
+    set_whitelist_enabled(ctx, true)
+}
+
+fn whitelist_off(ctx: &mut CommandCtx, _args: &Arguments) -> Result<(), CommandError> {
+    set_whitelist_enabled(ctx, false)
+}
+
+/// Turns the whitelist on or off until the server restarts.
+fn set_whitelist_enabled(ctx: &mut CommandCtx, enabled: bool) -> Result<(), CommandError> {
+    let access = ctx.resource_mut::<AccessLists>();
+    if access.whitelist_enabled == enabled {
+        let key = if enabled {
+            "feather.command.whitelist.alreadyOn"
+        } else {
+            "feather.command.whitelist.alreadyOff"
+        };
+        return Err(CommandError::failed(key, vec![]));
+    }
+
+    access.whitelist_enabled = enabled;
+    let key = if enabled {
+        "feather.command.whitelist.enabled"
+    } else {
+        "feather.command.whitelist.disabled"
+    };
+    ctx.send_translated(key, vec![]);
+    Ok(())
+}
+
+fn whitelist_list(ctx: &mut CommandCtx, _args: &Arguments) -> Result<(), CommandError> {
+    let names: Vec<&str> = ctx
+        .resource::<AccessLists>()
+        .whitelist
+        .entries()
+        .iter()
+        .map(|entry| entry.profile.name.as_str())
+        .collect();
+
+    if names.is_empty() {
+        ctx.send_translated("feather.command.whitelist.none", vec![]);
+    } else {
+        ctx.send_translated(
+            "feather.command.whitelist.list",
+            vec![names.len().to_string().into(), names.join(", ").into()],
+        );
+    }
+    Ok(())
+}
+
+fn whitelist_reload(ctx: &mut CommandCtx, _args: &Arguments) -> Result<(), CommandError> {
+    ctx.resource_mut::<AccessLists>().whitelist = PlayerList::load(WHITELIST_FILE);
+    ctx.send_translated("feather.command.whitelist.reloaded", vec![]);
+    Ok(())
+}
+
+fn whitelist_add(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let profile = target_profile(ctx, args);
+    let name = profile.name.clone();
+
+    if !ctx
+        .resource_mut::<AccessLists>()
+        .whitelist
+        .add(WhitelistEntry { profile })
+    {
+        return Err(CommandError::failed(
+            "feather.command.whitelist.add.failed",
+            vec![],
+        ));
+    }
+
+    ctx.send_translated("feather.command.whitelist.add.success", vec![name.into()]);
+    Ok(())
+}
+
+fn whitelist_remove(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let name = args.string("targets").unwrap();
+    let entry = ctx
+        .resource_mut::<AccessLists>()
+        .whitelist
+        .remove(name)
+        .ok_or_else(|| CommandError::failed("feather.command.whitelist.remove.failed", vec![]))?;
+
+    ctx.send_translated(
+        "feather.command.whitelist.remove.success",
+        vec![entry.profile.name.into()],
+    );
+    Ok(())
+}
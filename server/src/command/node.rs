@@ -0,0 +1,638 @@
+//! The command tree.
+
+use super::argument::{ArgumentType, Arguments, StringReader, SuggestionCtx};
+use super::{CommandError, CommandFn};
+use crate::permissions::PermissionSet;
+use feather_core::network::packet::implementation::{
+    CommandNode as PacketNode, CommandNodeType, DeclareCommands,
+};
+use feather_core::TextComponent;
+
+/// The suggestions provider for arguments
+/// completed through Tab-Complete packets.
+const ASK_SERVER: &str = "minecraft:ask_server";
+
+/// The kind of a command node.
+#[derive(Debug, Clone)]
+pub enum NodeKind {
+    /// The root of the tree, whose children are commands.
+    Root,
+    /// A literal word, such as the name of a command.
+    Literal(&'static str),
+    /// An argument of the given type.
+    Argument {
+        name: &'static str,
+        ty: ArgumentType,
+    },
+}
+
+/// A node of the command tree.
+///
+/// A command is parsed by following a path from the root,
+/// where each node consumes one word or argument. Nodes
+/// with an executor may end a command. Nodes the sender
+/// lacks the permission for are hidden.
+#[derive(Clone)]
+pub struct CommandNode {
+    kind: NodeKind,
+    children: Vec<CommandNode>,
+    executor: Option<CommandFn>,
+    permission: Option<String>,
+    permission_level: u8,
+}
+
+/// Creates a node matching the word `name`.
+pub fn literal(name: &'static str) -> CommandNode {
+    CommandNode::new(NodeKind::Literal(name))
+}
+
+/// Creates a node parsing an argument of type `ty`.
+pub fn argument(name: &'static str, ty: ArgumentType) -> CommandNode {
+    CommandNode::new(NodeKind::Argument { name, ty })
+}
+
+/// Suggestions for the end of a command.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Suggestions {
+    /// The byte offset of the text replaced by a suggestion.
+    pub start: usize,
+    pub matches: Vec<String>,
+}
+
+impl CommandNode {
+    pub fn new(kind: NodeKind) -> Self {
+        Self {
+            kind,
+            children: vec![],
+            executor: None,
+            permission: None,
+            permission_level: 0,
+        }
+    }
+
+    /// Adds a child to this node.
+    pub fn then(mut self, child: CommandNode) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Sets the function run by commands ending at this node.
+    pub fn executes(mut self, executor: CommandFn) -> Self {
+        self.executor = Some(executor);
+        self
+    }
+
+    /// Sets the op level, from 0 to 4, needed to use this
+    /// node and its children if its permission node is not
+    /// set for the sender.
+    pub fn requires(mut self, permission_level: u8) -> Self {
+        self.permission_level = permission_level;
+        self
+    }
+
+    /// Sets the permission node needed to use this node and its
+    /// children. Commands registered without one are given
+    /// `minecraft.command.<name>`.
+    pub fn permission(mut self, node: impl Into<String>) -> Self {
+        self.permission = Some(node.into());
+        self
+    }
+
+    pub fn permission_node(&self) -> Option<&str> {
+        self.permission.as_ref().map(String::as_str)
+    }
+
+    /// Returns whether a sender with the given
+    /// permissions may use this node.
+    pub fn can_use(&self, permissions: &PermissionSet) -> bool {
+        match &self.permission {
+            Some(node) => permissions.has(node, self.permission_level),
+            None => permissions.op_level >= self.permission_level,
+        }
+    }
+
+    pub fn kind(&self) -> &NodeKind {
+        &self.kind
+    }
+
+    pub fn children(&self) -> &[CommandNode] {
+        &self.children
+    }
+
+    /// Returns the child literal with the given name.
+    pub fn child(&self, name: &str) -> Option<&CommandNode> {
+        self.children.iter().find(|child| match child.kind {
+            NodeKind::Literal(literal) => literal == name,
+            _ => false,
+        })
+    }
+
+    /// Merges `child` into the children of this node,
+    /// replacing any existing literal with the same name.
+    pub(super) fn add_child(&mut self, child: CommandNode) {
+        if let NodeKind::Literal(name) = child.kind {
+            self.children.retain(|existing| match existing.kind {
+                NodeKind::Literal(literal) => literal != name,
+                _ => true,
+            });
+        }
+        self.children.push(child);
+    }
+
+    /// Parses `input` as sent by a sender with the given permissions,
+    /// returning the executor of the command and its arguments.
+    ///
+    /// If no path through the tree matches, the error
+    /// furthest into the input is returned.
+    pub fn parse(
+        &self,
+        input: &str,
+        permissions: &PermissionSet,
+    ) -> Result<(CommandFn, Arguments), CommandError> {
+        let mut reader = StringReader::new(input);
+        let mut args = Arguments::default();
+        let mut error = None;
+
+        match self.parse_children(&mut reader, &mut args, permissions, &mut error) {
+            Some(executor) => Ok((executor, args)),
+            None => Err(error.unwrap_or_else(|| {
+                let message = TextComponent::translate("feather.command.unknown.command", vec![]);
+                CommandError::Syntax(message, 0)
+            })),
+        }
+    }
+
+    fn parse_children(
+        &self,
+        reader: &mut StringReader,
+        args: &mut Arguments,
+        permissions: &PermissionSet,
+        error: &mut Option<CommandError>,
+    ) -> Option<CommandFn> {
+        let start = reader.cursor();
+        let arg_count = args.len();
+
+        for child in self.usable_children(permissions) {
+            if let Some(executor) = child.parse_path(reader, args, permissions, error) {
+                return Some(executor);
+            }
+            reader.set_cursor(start);
+            args.truncate(arg_count);
+        }
+
+        None
+    }
+
+    /// Parses this node and, if the input continues, its children.
+    fn parse_path(
+        &self,
+        reader: &mut StringReader,
+        args: &mut Arguments,
+        permissions: &PermissionSet,
+        error: &mut Option<CommandError>,
+    ) -> Option<CommandFn> {
+        let start = reader.cursor();
+        if let Err(e) = self.parse_token(reader, args) {
+            record_error(error, e);
+            return None;
+        }
+
+        if reader.at_end() {
+            if self.executor.is_none() {
+                record_error(
+                    error,
+                    reader.error("feather.command.unknown.incomplete", vec![]),
+                );
+            }
+            return self.executor;
+        }
+
+        if reader.peek() != Some(' ') {
+            record_error(
+                error,
+                reader.error("feather.command.expected.separator", vec![]),
+            );
+            return None;
+        }
+
+        if self.usable_children(permissions).next().is_none() {
+            record_error(
+                error,
+                reader.error("feather.command.unknown.argument", vec![]),
+            );
+            return None;
+        }
+
+        reader.skip();
+        let result = self.parse_children(reader, args, permissions, error);
+        if result.is_none() {
+            reader.set_cursor(start);
+        }
+        result
+    }
+
+    /// Parses the word or argument of this node.
+    fn parse_token(
+        &self,
+        reader: &mut StringReader,
+        args: &mut Arguments,
+    ) -> Result<(), CommandError> {
+        match self.kind {
+            NodeKind::Root => Ok(()),
+            NodeKind::Literal(name) => {
+                let start = reader.cursor();
+                if reader.read_word() == name {
+                    Ok(())
+                } else {
+                    reader.set_cursor(start);
+                    let key = if start == 0 {
+                        "feather.command.unknown.command"
+                    } else {
+                        "feather.command.unknown.argument"
+                    };
+                    Err(reader.error(key, vec![]))
+                }
+            }
+            NodeKind::Argument { name, ty } => {
+                let value = ty.parse(reader)?;
+                args.push(name, value);
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns suggestions for the last word of `input`.
+    pub fn suggest(&self, input: &str, ctx: &SuggestionCtx) -> Suggestions {
+        let mut found = vec![];
+        self.suggest_children(&mut StringReader::new(input), ctx, &mut found);
+
+        // Suggestions may start at different words; extend
+        // them all to begin at the earliest one.
+        let start = match found.iter().map(|(start, _)| *start).min() {
+            Some(start) => start,
+            None => return Suggestions::default(),
+        };
+        let mut matches: Vec<String> = found
+            .into_iter()
+            .map(|(offset, text)| format!("{}{}", &input[start..offset], text))
+            .collect();
+        matches.sort();
+        matches.dedup();
+
+        Suggestions { start, matches }
+    }
+
+    fn suggest_children(
+        &self,
+        reader: &mut StringReader,
+        ctx: &SuggestionCtx,
+        found: &mut Vec<(usize, String)>,
+    ) {
+        let start = reader.cursor();
+        let partial = reader.remaining();
+        let last_word = !partial.contains(' ');
+
+        for child in self.usable_children(&ctx.permissions) {
+            match child.kind {
+                NodeKind::Root => (),
+                NodeKind::Literal(name) => {
+                    if last_word && name.starts_with(partial) {
+                        found.push((start, name.to_string()));
+                    }
+                }
+                NodeKind::Argument { ty, .. } => {
+                    if last_word {
+                        found.extend(
+                            ty.suggest(partial, ctx)
+                                .into_iter()
+                                .map(|text| (start, text)),
+                        );
+                    }
+                }
+            }
+
+            let mut args = Arguments::default();
+            if child.parse_token(reader, &mut args).is_ok() && reader.peek() == Some(' ') {
+                reader.skip();
+                child.suggest_children(reader, ctx, found);
+            }
+            reader.set_cursor(start);
+        }
+    }
+
+    /// Returns a short description of how to use the command
+    /// at this node, such as `gamemode <gamemode> [<target>]`.
+    pub fn smart_usage(&self) -> String {
+        let mut usage = self.token_usage();
+        if self.children.is_empty() {
+            return usage;
+        }
+
+        let children: Vec<String> = self
+            .children
+            .iter()
+            .map(|child| {
+                if child.children.is_empty() {
+                    child.token_usage()
+                } else {
+                    format!("{} ...", child.token_usage())
+                }
+            })
+            .collect();
+        let children = if children.len() == 1 {
+            children.into_iter().next().unwrap()
+        } else {
+            format!("({})", children.join("|"))
+        };
+
+        if self.executor.is_some() {
+            usage.push_str(&format!(" [{}]", children));
+        } else {
+            usage.push(' ');
+            usage.push_str(&children);
+        }
+        usage
+    }
+
+    /// Returns every complete usage of the command at this node,
+    /// such as `tp <targets> <location>`.
+    pub fn all_usage(&self) -> Vec<String> {
+        let mut usages = vec![];
+        self.collect_usage(String::new(), &mut usages);
+        usages
+    }
+
+    fn collect_usage(&self, prefix: String, usages: &mut Vec<String>) {
+        let usage = if prefix.is_empty() {
+            self.token_usage()
+        } else {
+            format!("{} {}", prefix, self.token_usage())
+        };
+
+        if self.executor.is_some() {
+            usages.push(usage.clone());
+        }
+        for child in &self.children {
+            child.collect_usage(usage.clone(), usages);
+        }
+    }
+
+    fn token_usage(&self) -> String {
+        match self.kind {
+            NodeKind::Root => String::new(),
+            NodeKind::Literal(name) => name.to_string(),
+            NodeKind::Argument { name, .. } => format!("<{}>", name),
+        }
+    }
+
+    /// Creates the Declare Commands packet describing the
+    /// part of this tree usable with the given permissions.
+    pub fn to_packet(&self, permissions: &PermissionSet) -> DeclareCommands {
+        let mut nodes = vec![];
+        let root_index = self.flatten(&mut nodes, permissions);
+        DeclareCommands { nodes, root_index }
+    }
+
+    /// Appends this node and its usable descendants
+    /// to `nodes`, returning the index of this node.
+    fn flatten(&self, nodes: &mut Vec<PacketNode>, permissions: &PermissionSet) -> i32 {
+        let (ty, suggestions) = match self.kind {
+            NodeKind::Root => (CommandNodeType::Root, None),
+            NodeKind::Literal(name) => (CommandNodeType::Literal(name.to_string()), None),
+            NodeKind::Argument { name, ty } => (
+                CommandNodeType::Argument {
+                    name: name.to_string(),
+                    parser: ty.parser(),
+                },
+                if ty.asks_server() {
+                    Some(ASK_SERVER.to_string())
+                } else {
+                    None
+                },
+            ),
+        };
+
+        let index = nodes.len();
+        nodes.push(PacketNode {
+            ty,
+            executable: self.executor.is_some(),
+            children: vec![],
+            redirect: None,
+            suggestions,
+        });
+
+        let children = self
+            .usable_children(permissions)
+            .map(|child| child.flatten(nodes, permissions))
+            .collect();
+        nodes[index].children = children;
+
+        index as i32
+    }
+
+    /// Returns the children a sender with
+    /// the given permissions may use.
+    fn usable_children<'a>(
+        &'a self,
+        permissions: &'a PermissionSet,
+    ) -> impl Iterator<Item = &'a CommandNode> {
+        self.children
+            .iter()
+            .filter(move |child| child.can_use(permissions))
+    }
+}
+
+/// Keeps whichever of `error` and `new` is furthest into the input.
+fn record_error(error: &mut Option<CommandError>, new: CommandError) {
+    let replace = match (&*error, &new) {
+        (Some(CommandError::Syntax(_, old)), CommandError::Syntax(_, new)) => new > old,
+        (None, _) => true,
+        _ => false,
+    };
+    if replace {
+        *error = Some(new);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::CommandCtx;
+    use crate::permissions::Nodes;
+
+    fn nothing(_: &mut CommandCtx, _: &Arguments) -> Result<(), CommandError> {
+        Ok(())
+    }
+
+    fn tree() -> CommandNode {
+        CommandNode::new(NodeKind::Root)
+            .then(
+                literal("gamemode").then(
+                    argument("gamemode", ArgumentType::Word)
+                        .executes(nothing)
+                        .then(argument("target", ArgumentType::PLAYER).executes(nothing)),
+                ),
+            )
+            .then(
+                literal("give").requires(2).then(
+                    argument("target", ArgumentType::PLAYERS).then(
+                        argument("item", ArgumentType::Item).executes(nothing).then(
+                            argument(
+                                "count",
+                                ArgumentType::Integer {
+                                    min: Some(1),
+                                    max: None,
+                                },
+                            )
+                            .executes(nothing),
+                        ),
+                    ),
+                ),
+            )
+    }
+
+    #[test]
+    fn test_parse() {
+        let tree = tree();
+
+        let (_, args) = tree
+            .parse("give Notch diamond 5", &PermissionSet::new(2))
+            .unwrap();
+        assert_eq!(args.integer("count"), Some(5));
+        assert_eq!(args.item("item"), Some(feather_core::Item::Diamond));
+
+        let (_, args) = tree
+            .parse("gamemode creative", &PermissionSet::new(0))
+            .unwrap();
+        assert_eq!(args.string("gamemode"), Some("creative"));
+        assert!(args.entities("target").is_none());
+    }
+
+    /// Returns the English message and position of a syntax error.
+    fn parse_error(tree: &CommandNode, input: &str, level: u8) -> Option<(String, usize)> {
+        let e = tree.parse(input, &PermissionSet::new(level)).err()?;
+        match &e {
+            CommandError::Syntax(_, cursor) => Some((e.to_string(), *cursor)),
+            CommandError::Failed(_) => None,
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let tree = tree();
+        let error = |message: &str, cursor| Some((message.to_string(), cursor));
+
+        assert_eq!(parse_error(&tree, "foo", 0), error("Unknown command", 0));
+        assert_eq!(
+            parse_error(&tree, "give Notch diamond", 1),
+            error("Unknown command", 0)
+        );
+        assert_eq!(
+            parse_error(&tree, "give Notch", 2),
+            error("Unknown or incomplete command, see below for error", 10)
+        );
+        assert_eq!(
+            parse_error(&tree, "give Notch diamond 0", 2),
+            error("Integer must not be less than 1, found 0", 19)
+        );
+        assert_eq!(
+            parse_error(&tree, "gamemode creative Notch extra", 0),
+            error("Incorrect argument for command", 23)
+        );
+    }
+
+    #[test]
+    fn test_suggest() {
+        let tree = tree();
+        let mut ctx = SuggestionCtx {
+            player_names: vec!["Notch".to_string(), "jeb_".to_string()],
+            permissions: PermissionSet::new(2),
+        };
+
+        assert_eq!(
+            tree.suggest("g", &ctx),
+            Suggestions {
+                start: 0,
+                matches: vec!["gamemode".to_string(), "give".to_string()],
+            }
+        );
+        assert_eq!(
+            tree.suggest("give n", &ctx),
+            Suggestions {
+                start: 5,
+                matches: vec!["Notch".to_string()],
+            }
+        );
+        assert_eq!(tree.suggest("foo ", &ctx), Suggestions::default());
+
+        ctx.permissions = PermissionSet::new(0);
+        assert_eq!(
+            tree.suggest("g", &ctx),
+            Suggestions {
+                start: 0,
+                matches: vec!["gamemode".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_permission_node() {
+        let tree = CommandNode::new(NodeKind::Root).then(
+            literal("give")
+                .requires(2)
+                .permission("minecraft.command.give")
+                .executes(nothing),
+        );
+        let mut nodes = Nodes::new();
+        nodes.insert("minecraft.command.*".to_string(), true);
+
+        assert!(tree.parse("give", &PermissionSet::new(0)).is_err());
+        assert!(tree.parse("give", &PermissionSet::new(2)).is_ok());
+        let granted = PermissionSet::new(0).with_layer(nodes.clone());
+        assert!(tree.parse("give", &granted).is_ok());
+
+        nodes.insert("minecraft.command.give".to_string(), false);
+        let denied = PermissionSet::new(4).with_layer(nodes);
+        assert!(tree.parse("give", &denied).is_err());
+    }
+
+    #[test]
+    fn test_usage() {
+        let tree = tree();
+        let give = tree.child("give").unwrap();
+
+        assert_eq!(give.smart_usage(), "give <target> ...");
+        assert_eq!(
+            give.all_usage(),
+            vec![
+                "give <target> <item>".to_string(),
+                "give <target> <item> <count>".to_string(),
+            ]
+        );
+        assert_eq!(
+            tree.child("gamemode").unwrap().smart_usage(),
+            "gamemode <gamemode> ..."
+        );
+    }
+
+    #[test]
+    fn test_packet() {
+        let packet = tree().to_packet(&PermissionSet::new(0));
+        assert_eq!(packet.nodes.len(), 4);
+        assert_eq!(packet.nodes[0].children, vec![1]);
+
+        let packet = tree().to_packet(&PermissionSet::new(2));
+        assert_eq!(packet.root_index, 0);
+        assert_eq!(packet.nodes.len(), 8);
+        assert_eq!(packet.nodes[0].children, vec![1, 4]);
+        assert_eq!(
+            packet.nodes[1].ty,
+            CommandNodeType::Literal("gamemode".to_string())
+        );
+        assert!(packet.nodes[2].executable);
+        assert_eq!(
+            packet.nodes[3].suggestions.as_ref().map(String::as_str),
+            Some(ASK_SERVER)
+        );
+    }
+}
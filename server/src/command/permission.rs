@@ -0,0 +1,286 @@
+//! The `/permission` command, which sets the permission
+//! nodes and groups of players and groups.
+
+use super::moderation::target_profile;
+use super::{
+    argument, find_player, literal, ArgumentType, Arguments, CommandCtx, CommandDispatcher,
+    CommandError,
+};
+use crate::network::Network;
+use crate::permissions::Permissions;
+use legion::entity::Entity;
+use legion::query::{IntoQuery, Read};
+
+pub fn register(dispatcher: &mut CommandDispatcher) {
+    let node = || argument("node", ArgumentType::Word);
+    let value = || argument("value", ArgumentType::Bool);
+
+    dispatcher.register(
+        literal("permission")
+            .requires(4)
+            .then(
+                literal("player").then(
+                    argument("targets", ArgumentType::GameProfile)
+                        .then(literal("set").then(node().then(value().executes(player_set))))
+                        .then(literal("unset").then(node().executes(player_unset)))
+                        .then(literal("check").then(node().executes(player_check)))
+                        .then(
+                            literal("group")
+                                .then(literal("add").then(
+                                    argument("group", ArgumentType::Word).executes(player_join),
+                                ))
+                                .then(literal("remove").then(
+                                    argument("group", ArgumentType::Word).executes(player_leave),
+                                )),
+                        ),
+                ),
+            )
+            .then(
+                literal("group").then(
+                    argument("group", ArgumentType::Word)
+                        .then(literal("set").then(node().then(value().executes(group_set))))
+                        .then(literal("unset").then(node().executes(group_unset)))
+                        .then(
+                            literal("parent")
+                                .then(literal("add").then(
+                                    argument("parent", ArgumentType::Word).executes(parent_add),
+                                ))
+                                .then(literal("remove").then(
+                                    argument("parent", ArgumentType::Word).executes(parent_remove),
+                                )),
+                        ),
+                ),
+            ),
+    );
+}
+
+/// `/permission player <targets> set <node> <value>`
+fn player_set(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let profile = target_profile(ctx, args);
+    let node = args.string("node").unwrap();
+    let value = args.bool("value").unwrap();
+
+    if !ctx
+        .resource_mut::<Permissions>()
+        .set_player(profile.clone(), node, Some(value))
+    {
+        return Err(CommandError::failed(
+            "feather.command.permission.player.set.failed",
+            vec![node.into(), value.to_string().into(), profile.name.into()],
+        ));
+    }
+
+    send_commands_to(ctx, &profile.name);
+    ctx.send_translated(
+        "feather.command.permission.player.set.success",
+        vec![node.into(), value.to_string().into(), profile.name.into()],
+    );
+    Ok(())
+}
+
+/// `/permission player <targets> unset <node>`
+fn player_unset(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let profile = target_profile(ctx, args);
+    let node = args.string("node").unwrap();
+
+    if !ctx
+        .resource_mut::<Permissions>()
+        .set_player(profile.clone(), node, None)
+    {
+        return Err(CommandError::failed(
+            "feather.command.permission.player.unset.failed",
+            vec![node.into(), profile.name.into()],
+        ));
+    }
+
+    send_commands_to(ctx, &profile.name);
+    ctx.send_translated(
+        "feather.command.permission.player.unset.success",
+        vec![node.into(), profile.name.into()],
+    );
+    Ok(())
+}
+
+/// `/permission player <targets> check <node>`
+fn player_check(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let profile = target_profile(ctx, args);
+    let node = args.string("node").unwrap();
+
+    let value = ctx
+        .resource::<Permissions>()
+        .value(profile.uuid, &profile.name, node);
+    match value {
+        Some(true) => ctx.send_translated(
+            "feather.command.permission.player.check.granted",
+            vec![profile.name.into(), node.into()],
+        ),
+        Some(false) => ctx.send_translated(
+            "feather.command.permission.player.check.denied",
+            vec![profile.name.into(), node.into()],
+        ),
+        None => ctx.send_translated(
+            "feather.command.permission.player.check.unset",
+            vec![node.into(), profile.name.into()],
+        ),
+    }
+    Ok(())
+}
+
+/// `/permission player <targets> group add <group>`
+fn player_join(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let profile = target_profile(ctx, args);
+    let group = args.string("group").unwrap();
+
+    if !ctx
+        .resource_mut::<Permissions>()
+        .add_player_group(profile.clone(), group)
+    {
+        return Err(CommandError::failed(
+            "feather.command.permission.player.join.failed",
+            vec![profile.name.into(), group.into()],
+        ));
+    }
+
+    send_commands_to(ctx, &profile.name);
+    ctx.send_translated(
+        "feather.command.permission.player.join.success",
+        vec![profile.name.into(), group.into()],
+    );
+    Ok(())
+}
+
+/// `/permission player <targets> group remove <group>`
+fn player_leave(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let name = args.string("targets").unwrap();
+    let group = args.string("group").unwrap();
+
+    if !ctx
+        .resource_mut::<Permissions>()
+        .remove_player_group(name, group)
+    {
+        return Err(CommandError::failed(
+            "feather.command.permission.player.leave.failed",
+            vec![name.into(), group.into()],
+        ));
+    }
+
+    send_commands_to(ctx, name);
+    ctx.send_translated(
+        "feather.command.permission.player.leave.success",
+        vec![name.into(), group.into()],
+    );
+    Ok(())
+}
+
+/// `/permission group <group> set <node> <value>`
+fn group_set(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let group = args.string("group").unwrap();
+    let node = args.string("node").unwrap();
+    let value = args.bool("value").unwrap();
+
+    if !ctx
+        .resource_mut::<Permissions>()
+        .set_group(group, node, Some(value))
+    {
+        return Err(CommandError::failed(
+            "feather.command.permission.group.set.failed",
+            vec![node.into(), value.to_string().into(), group.into()],
+        ));
+    }
+
+    send_commands_to_all(ctx);
+    ctx.send_translated(
+        "feather.command.permission.group.set.success",
+        vec![node.into(), value.to_string().into(), group.into()],
+    );
+    Ok(())
+}
+
+/// `/permission group <group> unset <node>`
+fn group_unset(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let group = args.string("group").unwrap();
+    let node = args.string("node").unwrap();
+
+    if !ctx
+        .resource_mut::<Permissions>()
+        .set_group(group, node, None)
+    {
+        return Err(CommandError::failed(
+            "feather.command.permission.group.unset.failed",
+            vec![node.into(), group.into()],
+        ));
+    }
+
+    send_commands_to_all(ctx);
+    ctx.send_translated(
+        "feather.command.permission.group.unset.success",
+        vec![node.into(), group.into()],
+    );
+    Ok(())
+}
+
+/// `/permission group <group> parent add <parent>`
+fn parent_add(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let group = args.string("group").unwrap();
+    let parent = args.string("parent").unwrap();
+
+    if !ctx
+        .resource_mut::<Permissions>()
+        .add_group_parent(group, parent)
+    {
+        return Err(CommandError::failed(
+            "feather.command.permission.parent.add.failed",
+            vec![group.into(), parent.into()],
+        ));
+    }
+
+    send_commands_to_all(ctx);
+    ctx.send_translated(
+        "feather.command.permission.parent.add.success",
+        vec![group.into(), parent.into()],
+    );
+    Ok(())
+}
+
+/// `/permission group <group> parent remove <parent>`
+fn parent_remove(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let group = args.string("group").unwrap();
+    let parent = args.string("parent").unwrap();
+
+    if !ctx
+        .resource_mut::<Permissions>()
+        .remove_group_parent(group, parent)
+    {
+        return Err(CommandError::failed(
+            "feather.command.permission.parent.remove.failed",
+            vec![group.into(), parent.into()],
+        ));
+    }
+
+    send_commands_to_all(ctx);
+    ctx.send_translated(
+        "feather.command.permission.parent.remove.success",
+        vec![group.into(), parent.into()],
+    );
+    Ok(())
+}
+
+/// Updates the commands of a player whose permissions
+/// changed, if they are online.
+fn send_commands_to(ctx: &CommandCtx, name: &str) {
+    if let Some(player) = find_player(ctx.world, name) {
+        ctx.send_commands(player);
+    }
+}
+
+/// Updates the commands of every online player
+/// after the permissions of a group change.
+fn send_commands_to_all(ctx: &CommandCtx) {
+    let players: Vec<Entity> = <Read<Network>>::query()
+        .iter_entities_immutable(ctx.world)
+        .map(|(player, _)| player)
+        .collect();
+    for player in players {
+        ctx.send_commands(player);
+    }
+}
@@ -0,0 +1,771 @@
+//! Target selectors, such as `@p` or `@e[type=boat,distance=..10]`.
+//!
+//! A selector is parsed along with its command and evaluated
+//! against the world when the command is executed. Selectors
+//! with a `distance` or a volume (`dx`, `dy` and `dz`) only find
+//! entities in the sender's dimension; others search every world.
+
+use super::argument::{namespaced, StringReader};
+use super::{CommandCtx, CommandError, CommandSender};
+use crate::entity::{EntityKind, Name};
+use crate::physics::nearby_entities_with;
+use crate::state::StateInner;
+use crate::worlds::DimensionId;
+use feather_core::{Gamemode, Position};
+use legion::entity::Entity;
+use legion::query::{IntoQuery, Read};
+use rand::seq::SliceRandom;
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+/// The variable of a selector: the letter after the `@`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectorVariable {
+    /// `@p`
+    NearestPlayer,
+    /// `@a`
+    AllPlayers,
+    /// `@r`
+    RandomPlayer,
+    /// `@e`
+    AllEntities,
+    /// `@s`
+    Sender,
+}
+
+impl SelectorVariable {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            'p' => Some(SelectorVariable::NearestPlayer),
+            'a' => Some(SelectorVariable::AllPlayers),
+            'r' => Some(SelectorVariable::RandomPlayer),
+            'e' => Some(SelectorVariable::AllEntities),
+            's' => Some(SelectorVariable::Sender),
+            _ => None,
+        }
+    }
+}
+
+/// A range of values such as `1..5`, `..5`, `1..` or `3`.
+/// Both bounds are inclusive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Range<T> {
+    pub min: Option<T>,
+    pub max: Option<T>,
+}
+
+impl<T: Copy + PartialOrd + FromStr> Range<T> {
+    pub fn contains(&self, value: T) -> bool {
+        self.min.map_or(true, |min| value >= min) && self.max.map_or(true, |max| value <= max)
+    }
+
+    fn parse(reader: &mut StringReader) -> Result<Self, CommandError> {
+        let start = reader.cursor();
+        let word = read_value(reader);
+        reader.set_cursor(start);
+
+        let bound = |bound: &str| -> Result<Option<T>, CommandError> {
+            if bound.is_empty() {
+                return Ok(None);
+            }
+            bound
+                .parse()
+                .map(Some)
+                .map_err(|_| reader.error("feather.argument.range.invalid", vec![bound.into()]))
+        };
+        let range = match word.find("..") {
+            Some(index) => Self {
+                min: bound(&word[..index])?,
+                max: bound(&word[index + 2..])?,
+            },
+            None => {
+                let value = bound(word)?;
+                Self {
+                    min: value,
+                    max: value,
+                }
+            }
+        };
+
+        match (range.min, range.max) {
+            (None, None) => return Err(reader.error("feather.argument.range.empty", vec![])),
+            (Some(min), Some(max)) if min > max => {
+                return Err(reader.error("feather.argument.range.swapped", vec![]))
+            }
+            _ => (),
+        }
+
+        read_value(reader);
+        Ok(range)
+    }
+
+    fn is_negative(&self, zero: T) -> bool {
+        self.min.map_or(false, |min| min < zero) || self.max.map_or(false, |max| max < zero)
+    }
+}
+
+/// The order in which selected entities are returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sort {
+    Nearest,
+    Furthest,
+    Random,
+    /// The order in which entities are stored.
+    Arbitrary,
+}
+
+/// A value an option must have, or must not
+/// have if the value was prefixed with `!`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition<T> {
+    pub value: T,
+    pub inverted: bool,
+}
+
+impl<T: PartialEq> Condition<T> {
+    /// Returns whether an entity with the given
+    /// value, if it has one, meets this condition.
+    pub fn matches(&self, value: Option<&T>) -> bool {
+        (value == Some(&self.value)) != self.inverted
+    }
+}
+
+/// A parsed target selector.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetSelector {
+    pub variable: SelectorVariable,
+    /// Overrides the position distances and volumes are
+    /// measured from, which is the sender's by default.
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+    pub z: Option<f64>,
+    pub distance: Option<Range<f64>>,
+    /// The size of the volume entities must be in,
+    /// starting at the position measured from.
+    pub dx: Option<f64>,
+    pub dy: Option<f64>,
+    pub dz: Option<f64>,
+    pub kinds: Vec<Condition<EntityKind>>,
+    pub names: Vec<Condition<String>>,
+    pub tags: Vec<Condition<String>>,
+    pub gamemodes: Vec<Condition<Gamemode>>,
+    pub level: Option<Range<i32>>,
+    pub limit: Option<usize>,
+    pub sort: Option<Sort>,
+}
+
+impl TargetSelector {
+    /// Creates a selector with no options.
+    pub fn new(variable: SelectorVariable) -> Self {
+        Self {
+            variable,
+            x: None,
+            y: None,
+            z: None,
+            distance: None,
+            dx: None,
+            dy: None,
+            dz: None,
+            kinds: vec![],
+            names: vec![],
+            tags: vec![],
+            gamemodes: vec![],
+            level: None,
+            limit: None,
+            sort: None,
+        }
+    }
+
+    /// Parses a selector, starting at its `@`.
+    pub fn parse(reader: &mut StringReader) -> Result<Self, CommandError> {
+        let start = reader.cursor();
+        reader.skip();
+        let variable = match reader.peek() {
+            Some(c) => SelectorVariable::from_char(c).ok_or_else(|| {
+                let mut reader = reader.clone();
+                reader.set_cursor(start);
+                reader.error(
+                    "feather.argument.entity.selector.unknown",
+                    vec![format!("@{}", c).into()],
+                )
+            })?,
+            None => return Err(reader.error("feather.argument.entity.selector.missing", vec![])),
+        };
+        reader.skip();
+
+        let mut selector = Self::new(variable);
+        if reader.peek() == Some('[') {
+            reader.skip();
+            selector.parse_options(reader)?;
+        }
+        Ok(selector)
+    }
+
+    /// Parses the options in brackets, after the opening bracket.
+    fn parse_options(&mut self, reader: &mut StringReader) -> Result<(), CommandError> {
+        reader.skip_whitespace();
+        while reader.peek() != Some(']') {
+            let start = reader.cursor();
+            let key = reader.read_until(&['=', ',', ']']);
+            if key.is_empty() {
+                return Err(reader.error("feather.argument.entity.options.expected", vec![]));
+            }
+            reader.skip_whitespace();
+            if reader.peek() != Some('=') {
+                reader.set_cursor(start);
+                return Err(reader.error(
+                    "feather.argument.entity.options.valueless",
+                    vec![key.into()],
+                ));
+            }
+            reader.skip();
+            reader.skip_whitespace();
+
+            self.parse_option(key, reader).map_err(|e| match e {
+                OptionError::NotApplicable => {
+                    let mut reader = reader.clone();
+                    reader.set_cursor(start);
+                    reader.error(
+                        "feather.argument.entity.options.inapplicable",
+                        vec![key.into()],
+                    )
+                }
+                OptionError::Unknown => {
+                    let mut reader = reader.clone();
+                    reader.set_cursor(start);
+                    reader.error("feather.argument.entity.options.unknown", vec![key.into()])
+                }
+                OptionError::Invalid(e) => e,
+            })?;
+
+            reader.skip_whitespace();
+            match reader.peek() {
+                Some(',') => {
+                    reader.skip();
+                    reader.skip_whitespace();
+                }
+                Some(']') => (),
+                _ => {
+                    return Err(reader.error("feather.argument.entity.options.unterminated", vec![]))
+                }
+            }
+        }
+        reader.skip();
+        Ok(())
+    }
+
+    fn parse_option(&mut self, key: &str, reader: &mut StringReader) -> Result<(), OptionError> {
+        let start = reader.cursor();
+        match key {
+            "x" => set_once(&mut self.x, read_double(reader)?),
+            "y" => set_once(&mut self.y, read_double(reader)?),
+            "z" => set_once(&mut self.z, read_double(reader)?),
+            "dx" => set_once(&mut self.dx, read_double(reader)?),
+            "dy" => set_once(&mut self.dy, read_double(reader)?),
+            "dz" => set_once(&mut self.dz, read_double(reader)?),
+            "distance" => {
+                let range = Range::<f64>::parse(reader)?;
+                if range.is_negative(0.0) {
+                    reader.set_cursor(start);
+                    return Err(reader
+                        .error("feather.argument.entity.options.distance.negative", vec![])
+                        .into());
+                }
+                set_once(&mut self.distance, range)
+            }
+            "level" => {
+                let range = Range::<i32>::parse(reader)?;
+                if range.is_negative(0) {
+                    reader.set_cursor(start);
+                    return Err(reader
+                        .error("feather.argument.entity.options.level.negative", vec![])
+                        .into());
+                }
+                set_once(&mut self.level, range)
+            }
+            "limit" => {
+                if self.variable == SelectorVariable::Sender {
+                    return Err(OptionError::NotApplicable);
+                }
+                let word = read_value(reader);
+                let limit = word.parse::<i32>().map_err(|_| {
+                    reader.set_cursor(start);
+                    reader.error("feather.argument.integer.invalid", vec![word.into()])
+                })?;
+                if limit < 1 {
+                    reader.set_cursor(start);
+                    return Err(reader
+                        .error("feather.argument.entity.options.limit.toosmall", vec![])
+                        .into());
+                }
+                set_once(&mut self.limit, limit as usize)
+            }
+            "sort" => {
+                if self.variable == SelectorVariable::Sender {
+                    return Err(OptionError::NotApplicable);
+                }
+                let sort = match read_value(reader) {
+                    "nearest" => Sort::Nearest,
+                    "furthest" => Sort::Furthest,
+                    "random" => Sort::Random,
+                    "arbitrary" => Sort::Arbitrary,
+                    word => {
+                        reader.set_cursor(start);
+                        return Err(reader
+                            .error(
+                                "feather.argument.entity.options.sort.irreversible",
+                                vec![word.into()],
+                            )
+                            .into());
+                    }
+                };
+                set_once(&mut self.sort, sort)
+            }
+            "gamemode" => {
+                let inverted = read_inverted(reader);
+                let value = match read_value(reader) {
+                    "survival" => Gamemode::Survival,
+                    "creative" => Gamemode::Creative,
+                    "adventure" => Gamemode::Adventure,
+                    "spectator" => Gamemode::Spectator,
+                    word => {
+                        reader.set_cursor(start);
+                        return Err(reader
+                            .error(
+                                "feather.argument.entity.options.mode.invalid",
+                                vec![word.into()],
+                            )
+                            .into());
+                    }
+                };
+                add_condition(&mut self.gamemodes, Condition { value, inverted })
+            }
+            "type" => {
+                let inverted = read_inverted(reader);
+                let word = read_value(reader);
+                let value = EntityKind::from_identifier(&namespaced(word)).ok_or_else(|| {
+                    reader.set_cursor(start);
+                    reader.error(
+                        "feather.argument.entity.options.type.invalid",
+                        vec![word.into()],
+                    )
+                })?;
+                add_condition(&mut self.kinds, Condition { value, inverted })
+            }
+            "name" => {
+                let inverted = read_inverted(reader);
+                let value = if reader.peek() == Some('"') {
+                    reader.read_quotable()?
+                } else {
+                    read_value(reader).to_string()
+                };
+                add_condition(&mut self.names, Condition { value, inverted })
+            }
+            "tag" => {
+                let inverted = read_inverted(reader);
+                let value = read_value(reader).to_string();
+                self.tags.push(Condition { value, inverted });
+                Ok(())
+            }
+            _ => Err(OptionError::Unknown),
+        }
+    }
+
+    /// Returns the most entities this selector can select.
+    pub fn max_results(&self) -> usize {
+        match (self.limit, self.variable) {
+            (Some(limit), _) => limit,
+            (None, SelectorVariable::NearestPlayer)
+            | (None, SelectorVariable::RandomPlayer)
+            | (None, SelectorVariable::Sender) => 1,
+            (None, _) => usize::max_value(),
+        }
+    }
+
+    /// Returns whether this selector can select entities
+    /// other than players. `@s` is treated as selecting
+    /// any entity, though only players send commands.
+    pub fn includes_entities(&self) -> bool {
+        match self.variable {
+            SelectorVariable::AllEntities => !self
+                .kinds
+                .iter()
+                .any(|kind| !kind.inverted && kind.value == EntityKind::Player),
+            SelectorVariable::Sender => true,
+            _ => false,
+        }
+    }
+
+    /// Returns whether this selector only finds
+    /// entities in the sender's dimension.
+    fn current_dimension_only(&self) -> bool {
+        self.distance.is_some() || self.dx.is_some() || self.dy.is_some() || self.dz.is_some()
+    }
+
+    /// Returns the volume entities must be in, as
+    /// its lowest and highest corners, if one was given.
+    fn volume(&self, origin: Position) -> Option<(Position, Position)> {
+        if self.dx.is_none() && self.dy.is_none() && self.dz.is_none() {
+            return None;
+        }
+
+        let axis = |start: f64, size: Option<f64>| {
+            let end = start + size.unwrap_or(0.0);
+            (start.min(end), start.max(end) + 1.0)
+        };
+        let (min_x, max_x) = axis(origin.x, self.dx);
+        let (min_y, max_y) = axis(origin.y, self.dy);
+        let (min_z, max_z) = axis(origin.z, self.dz);
+        Some((
+            position!(min_x, min_y, min_z),
+            position!(max_x, max_y, max_z),
+        ))
+    }
+
+    /// Returns the entities this selector refers to when
+    /// evaluated for the sender of a command.
+    pub fn select(&self, ctx: &CommandCtx) -> Vec<Entity> {
+        let mut origin = ctx.origin();
+        origin.x = self.x.unwrap_or(origin.x);
+        origin.y = self.y.unwrap_or(origin.y);
+        origin.z = self.z.unwrap_or(origin.z);
+        let dimension = ctx.dimension();
+        let volume = self.volume(origin);
+
+        let candidates: Vec<Entity> = if self.variable == SelectorVariable::Sender {
+            match ctx.sender {
+                CommandSender::Player(player) => vec![player],
+                CommandSender::Console => vec![],
+            }
+        } else if let Some((center, radius)) = self.search_area(origin, volume) {
+            let chunk_entities = &ctx
+                .resource::<StateInner>()
+                .dimension_data(dimension)
+                .chunk_entities;
+            nearby_entities_with(chunk_entities, center, radius, |entity| {
+                ctx.world.get_component::<Position>(entity).map(|pos| *pos)
+            })
+            .into_vec()
+        } else {
+            <Read<DimensionId>>::query()
+                .iter_entities_immutable(ctx.world)
+                .filter(|(_, entity_dimension)| {
+                    !self.current_dimension_only() || **entity_dimension == dimension
+                })
+                .map(|(entity, _)| entity)
+                .collect()
+        };
+
+        let mut selected: Vec<(Entity, DimensionId, f64)> = candidates
+            .into_iter()
+            .filter(|entity| self.matches(ctx, *entity, volume))
+            .filter_map(|entity| {
+                let pos = *ctx.world.get_component::<Position>(entity)?;
+                let entity_dimension = *ctx.world.get_component::<DimensionId>(entity)?;
+                Some((entity, entity_dimension, distance(pos, origin)))
+            })
+            .filter(|(_, _, entity_distance)| {
+                self.distance
+                    .map_or(true, |range| range.contains(*entity_distance))
+            })
+            .collect();
+
+        // Entities in other dimensions are always further away.
+        let by_distance = |a: &(Entity, DimensionId, f64), b: &(Entity, DimensionId, f64)| {
+            (a.1 != dimension)
+                .cmp(&(b.1 != dimension))
+                .then(a.2.partial_cmp(&b.2).unwrap_or(Ordering::Equal))
+        };
+        match self.sort() {
+            Sort::Nearest => selected.sort_by(by_distance),
+            Sort::Furthest => selected.sort_by(|a, b| by_distance(b, a)),
+            Sort::Random => selected.shuffle(&mut rand::thread_rng()),
+            Sort::Arbitrary => (),
+        }
+
+        selected
+            .into_iter()
+            .map(|(entity, _, _)| entity)
+            .take(self.max_results())
+            .collect()
+    }
+
+    fn sort(&self) -> Sort {
+        self.sort.unwrap_or(match self.variable {
+            SelectorVariable::NearestPlayer => Sort::Nearest,
+            SelectorVariable::RandomPlayer => Sort::Random,
+            _ => Sort::Arbitrary,
+        })
+    }
+
+    /// Returns the center and radius of the area to search
+    /// for entities in, if the selector is limited to one.
+    fn search_area(
+        &self,
+        origin: Position,
+        volume: Option<(Position, Position)>,
+    ) -> Option<(Position, glm::DVec3)> {
+        if let Some(max) = self.distance.and_then(|range| range.max) {
+            return Some((origin, glm::vec3(max, max, max)));
+        }
+
+        volume.map(|(min, max)| {
+            let center = position!(
+                (min.x + max.x) / 2.0,
+                (min.y + max.y) / 2.0,
+                (min.z + max.z) / 2.0
+            );
+            let radius = glm::vec3(
+                (max.x - min.x) / 2.0,
+                (max.y - min.y) / 2.0,
+                (max.z - min.z) / 2.0,
+            );
+            (center, radius)
+        })
+    }
+
+    /// Returns whether an entity meets the conditions of this
+    /// selector, other than its distance from the origin.
+    fn matches(
+        &self,
+        ctx: &CommandCtx,
+        entity: Entity,
+        volume: Option<(Position, Position)>,
+    ) -> bool {
+        let world = &*ctx.world;
+        let kind = world.get_component::<EntityKind>(entity).map(|kind| *kind);
+        let is_player = kind == Some(EntityKind::Player);
+
+        match self.variable {
+            SelectorVariable::NearestPlayer
+            | SelectorVariable::AllPlayers
+            | SelectorVariable::RandomPlayer
+                if !is_player =>
+            {
+                return false
+            }
+            _ => (),
+        }
+
+        if !self.kinds.iter().all(|c| c.matches(kind.as_ref())) {
+            return false;
+        }
+
+        if let Some((min, max)) = volume {
+            let pos = match world.get_component::<Position>(entity) {
+                Some(pos) => *pos,
+                None => return false,
+            };
+            let inside = pos.x >= min.x
+                && pos.x < max.x
+                && pos.y >= min.y
+                && pos.y < max.y
+                && pos.z >= min.z
+                && pos.z < max.z;
+            if !inside {
+                return false;
+            }
+        }
+
+        if !self.names.is_empty() {
+            let name = world
+                .get_component::<Name>(entity)
+                .map(|name| name.0.clone());
+            if !self.names.iter().all(|c| c.matches(name.as_ref())) {
+                return false;
+            }
+        }
+
+        // Entities have no tags yet.
+        if !self.tags.iter().all(|c| c.matches(None)) {
+            return false;
+        }
+
+        // Game modes and levels only apply to players.
+        if !self.gamemodes.is_empty() {
+            let gamemode = match world.get_component::<Gamemode>(entity) {
+                Some(gamemode) if is_player => *gamemode,
+                _ => return false,
+            };
+            if !self.gamemodes.iter().all(|c| c.matches(Some(&gamemode))) {
+                return false;
+            }
+        }
+
+        if let Some(level) = self.level {
+            // Players have no experience yet, so all are level 0.
+            if !is_player || !level.contains(0) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// An error while parsing a selector option.
+enum OptionError {
+    /// The option was already given, or
+    /// does not apply to the variable.
+    NotApplicable,
+    Unknown,
+    Invalid(CommandError),
+}
+
+impl From<CommandError> for OptionError {
+    fn from(e: CommandError) -> Self {
+        OptionError::Invalid(e)
+    }
+}
+
+fn set_once<T>(option: &mut Option<T>, value: T) -> Result<(), OptionError> {
+    if option.is_some() {
+        return Err(OptionError::NotApplicable);
+    }
+    *option = Some(value);
+    Ok(())
+}
+
+/// Adds a condition to those of an option which
+/// may be given once, unless it is inverted.
+fn add_condition<T>(
+    conditions: &mut Vec<Condition<T>>,
+    condition: Condition<T>,
+) -> Result<(), OptionError> {
+    if !condition.inverted && conditions.iter().any(|c| !c.inverted) {
+        return Err(OptionError::NotApplicable);
+    }
+    conditions.push(condition);
+    Ok(())
+}
+
+/// Reads the value of an option, which ends at the
+/// next comma, closing bracket or space.
+fn read_value<'a>(reader: &mut StringReader<'a>) -> &'a str {
+    reader.read_until(&[',', ']'])
+}
+
+fn read_double(reader: &mut StringReader) -> Result<f64, CommandError> {
+    let start = reader.cursor();
+    let word = read_value(reader);
+    word.parse().map_err(|_| {
+        reader.set_cursor(start);
+        reader.error("feather.argument.double.invalid", vec![word.into()])
+    })
+}
+
+/// Reads the `!` which inverts a condition, if there is one.
+fn read_inverted(reader: &mut StringReader) -> bool {
+    if reader.peek() == Some('!') {
+        reader.skip();
+        reader.skip_whitespace();
+        true
+    } else {
+        false
+    }
+}
+
+fn distance(a: Position, b: Position) -> f64 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2) + (a.z - b.z).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Result<TargetSelector, CommandError> {
+        TargetSelector::parse(&mut StringReader::new(input))
+    }
+
+    #[test]
+    fn test_variables() {
+        let selector = parse("@p").unwrap();
+        assert_eq!(selector.variable, SelectorVariable::NearestPlayer);
+        assert_eq!(selector.max_results(), 1);
+        assert_eq!(selector.sort(), Sort::Nearest);
+        assert!(!selector.includes_entities());
+
+        let selector = parse("@e").unwrap();
+        assert_eq!(selector.max_results(), usize::max_value());
+        assert!(selector.includes_entities());
+
+        assert_eq!(
+            parse("@x").map_err(|e| e.to_string()),
+            Err("Unknown selector type '@x'".to_string())
+        );
+    }
+
+    #[test]
+    fn test_options() {
+        let selector =
+            parse("@e[type=!boat, distance=..5.5,name=\"a b\",limit=2,sort=furthest]").unwrap();
+        assert_eq!(
+            selector.kinds,
+            vec![Condition {
+                value: EntityKind::Boat,
+                inverted: true
+            }]
+        );
+        assert_eq!(
+            selector.distance,
+            Some(Range {
+                min: None,
+                max: Some(5.5)
+            })
+        );
+        assert_eq!(selector.names[0].value, "a b");
+        assert_eq!(selector.max_results(), 2);
+        assert_eq!(selector.sort(), Sort::Furthest);
+
+        let selector = parse("@e[type=player]").unwrap();
+        assert!(!selector.includes_entities());
+
+        let selector = parse("@a[level=3,gamemode=!creative,x=1,dy=-2]").unwrap();
+        assert_eq!(
+            selector.level,
+            Some(Range {
+                min: Some(3),
+                max: Some(3)
+            })
+        );
+        assert_eq!(selector.gamemodes[0].value, Gamemode::Creative);
+        let (min, max) = selector.volume(position!(1.0, 64.0, 0.0)).unwrap();
+        assert_eq!((min.y, max.y), (62.0, 65.0));
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        let error = |input| match parse(input) {
+            Err(e @ CommandError::Syntax(..)) => e.to_string(),
+            result => panic!("unexpected result {:?}", result),
+        };
+        assert_eq!(error("@e[foo=1]"), "Unknown option 'foo'");
+        assert_eq!(error("@e[limit=0]"), "Limit must be at least 1");
+        assert_eq!(error("@e[distance=-1..]"), "Distance cannot be negative");
+        assert_eq!(error("@e[distance=5..1]"), "Min cannot be bigger than max");
+        assert_eq!(
+            error("@e[type=creeper]"),
+            "Invalid or unknown entity type 'creeper'"
+        );
+        assert_eq!(error("@e[x=1,x=2]"), "Option 'x' isn't applicable here");
+        assert_eq!(
+            error("@e[name=a,name=b]"),
+            "Option 'name' isn't applicable here"
+        );
+        assert_eq!(error("@s[limit=1]"), "Option 'limit' isn't applicable here");
+        assert_eq!(error("@e[limit=1"), "Expected end of options");
+        assert_eq!(error("@e[limit]"), "Expected value for option 'limit'");
+    }
+
+    #[test]
+    fn test_range() {
+        let range = Range::<f64>::parse(&mut StringReader::new("1..5")).unwrap();
+        assert!(range.contains(1.0));
+        assert!(range.contains(5.0));
+        assert!(!range.contains(5.5));
+
+        let range = Range::<i32>::parse(&mut StringReader::new("3..")).unwrap();
+        assert!(!range.contains(2));
+        assert!(range.contains(100));
+    }
+}
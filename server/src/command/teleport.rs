@@ -0,0 +1,116 @@
+//! The `/teleport` command, also available as `/tp`.
+
+use super::{
+    argument, literal, ArgumentType, Arguments, CommandCtx, CommandDispatcher, CommandError,
+};
+use crate::network::Network;
+use crate::util::teleport_now;
+use crate::worlds::{move_player_now, DimensionId};
+use feather_core::Position;
+use legion::entity::Entity;
+
+pub fn register(dispatcher: &mut CommandDispatcher) {
+    for name in &["teleport", "tp"] {
+        dispatcher.register(
+            literal(*name)
+                .requires(2)
+                .then(argument("location", ArgumentType::Vec3).executes(to_location))
+                .then(argument("destination", ArgumentType::ENTITY).executes(to_entity))
+                .then(
+                    argument("targets", ArgumentType::ENTITIES)
+                        .then(
+                            argument("location", ArgumentType::Vec3).executes(targets_to_location),
+                        )
+                        .then(
+                            argument("destination", ArgumentType::ENTITY)
+                                .executes(targets_to_entity),
+                        ),
+                ),
+        );
+    }
+}
+
+/// `/tp <location>`
+fn to_location(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let targets = vec![ctx.player()?];
+    teleport_to_location(ctx, args, targets)
+}
+
+/// `/tp <destination>`
+fn to_entity(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let targets = vec![ctx.player()?];
+    teleport_to_entity(ctx, args, targets)
+}
+
+/// `/tp <targets> <location>`
+fn targets_to_location(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let targets = ctx.entities(args.entities("targets").unwrap())?;
+    teleport_to_location(ctx, args, targets)
+}
+
+/// `/tp <targets> <destination>`
+fn targets_to_entity(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let targets = ctx.entities(args.entities("targets").unwrap())?;
+    teleport_to_entity(ctx, args, targets)
+}
+
+fn teleport_to_location(
+    ctx: &mut CommandCtx,
+    args: &Arguments,
+    targets: Vec<Entity>,
+) -> Result<(), CommandError> {
+    let location = args.coordinates("location").unwrap().resolve(ctx.origin());
+    let dimension = ctx.dimension();
+
+    for target in &targets {
+        let rotation = *ctx.world.get_component::<Position>(*target).unwrap();
+        let position = Position {
+            yaw: rotation.yaw,
+            pitch: rotation.pitch,
+            ..location
+        };
+        teleport(ctx, *target, dimension, position);
+    }
+
+    ctx.send_translated(
+        "feather.command.teleport.location",
+        vec![
+            ctx.describe(&targets),
+            format!("{:.1}", location.x).into(),
+            format!("{:.1}", location.y).into(),
+            format!("{:.1}", location.z).into(),
+        ],
+    );
+    Ok(())
+}
+
+fn teleport_to_entity(
+    ctx: &mut CommandCtx,
+    args: &Arguments,
+    targets: Vec<Entity>,
+) -> Result<(), CommandError> {
+    let destination = ctx.entities(args.entities("destination").unwrap())?[0];
+    let position = *ctx.world.get_component::<Position>(destination).unwrap();
+    let dimension = *ctx.world.get_component::<DimensionId>(destination).unwrap();
+
+    for target in &targets {
+        teleport(ctx, *target, dimension, position);
+    }
+
+    ctx.send_translated(
+        "feather.command.teleport.entity",
+        vec![ctx.describe(&targets), ctx.describe(&[destination])],
+    );
+    Ok(())
+}
+
+/// Teleports an entity, moving players to another dimension
+/// if needed. Other entities cannot change dimension.
+fn teleport(ctx: &mut CommandCtx, entity: Entity, dimension: DimensionId, position: Position) {
+    let current = *ctx.world.get_component::<DimensionId>(entity).unwrap();
+    if current == dimension {
+        teleport_now(ctx.world, ctx.scheduler, entity, position);
+    } else if ctx.world.get_component::<Network>(entity).is_some() {
+        move_player_now(ctx.world, ctx.scheduler, entity, dimension, position);
+    }
+}
@@ -0,0 +1,106 @@
+//! The `/time` command, which acts on the sender's world.
+
+use super::{
+    argument, literal, ArgumentType, Arguments, CommandCtx, CommandDispatcher, CommandError,
+};
+use crate::game_rules::{GameRule, WorldGameRules};
+use crate::time::{Time, WorldTimes};
+
+const DAY: u64 = 1000;
+const NOON: u64 = 6000;
+const NIGHT: u64 = 13000;
+const MIDNIGHT: u64 = 18000;
+
+const TIME: ArgumentType = ArgumentType::Integer {
+    min: Some(0),
+    max: None,
+};
+
+pub fn register(dispatcher: &mut CommandDispatcher) {
+    dispatcher.register(
+        literal("time")
+            .requires(2)
+            .then(
+                literal("set")
+                    .then(literal("day").executes(set_day))
+                    .then(literal("noon").executes(set_noon))
+                    .then(literal("night").executes(set_night))
+                    .then(literal("midnight").executes(set_midnight))
+                    .then(argument("time", TIME).executes(set)),
+            )
+            .then(literal("add").then(argument("time", TIME).executes(add)))
+            .then(
+                literal("query")
+                    .then(literal("daytime").executes(query_daytime))
+                    .then(literal("gametime").executes(query_gametime))
+                    .then(literal("day").executes(query_day)),
+            ),
+    );
+}
+
+fn set_day(ctx: &mut CommandCtx, _args: &Arguments) -> Result<(), CommandError> {
+    set_time(ctx, DAY)
+}
+
+fn set_noon(ctx: &mut CommandCtx, _args: &Arguments) -> Result<(), CommandError> {
+    set_time(ctx, NOON)
+}
+
+fn set_night(ctx: &mut CommandCtx, _args: &Arguments) -> Result<(), CommandError> {
+    set_time(ctx, NIGHT)
+}
+
+fn set_midnight(ctx: &mut CommandCtx, _args: &Arguments) -> Result<(), CommandError> {
+    set_time(ctx, MIDNIGHT)
+}
+
+fn set(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    set_time(ctx, args.integer("time").unwrap() as u64)
+}
+
+fn add(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let day_time = time(ctx).day_time + args.integer("time").unwrap() as u64;
+    set_time(ctx, day_time)
+}
+
+/// Returns the time of the sender's world.
+fn time(ctx: &CommandCtx) -> Time {
+    *ctx.resource::<WorldTimes>().get(ctx.dimension().world)
+}
+
+/// Sets the time of the day/night cycle in the sender's world.
+fn set_time(ctx: &mut CommandCtx, day_time: u64) -> Result<(), CommandError> {
+    let world = ctx.dimension().world;
+    let time = ctx.resource_mut::<WorldTimes>().get_mut(world);
+    time.day_time = day_time;
+
+    let time = *time;
+    let daylight_cycle = ctx
+        .resource::<WorldGameRules>()
+        .get(world)
+        .get_bool(GameRule::DoDaylightCycle);
+    ctx.broadcast_world(world, time.update_packet(daylight_cycle));
+
+    ctx.send_translated(
+        "feather.command.time.set",
+        vec![day_time.to_string().into()],
+    );
+    Ok(())
+}
+
+fn query_daytime(ctx: &mut CommandCtx, _args: &Arguments) -> Result<(), CommandError> {
+    query(ctx, time(ctx).time_of_day())
+}
+
+fn query_gametime(ctx: &mut CommandCtx, _args: &Arguments) -> Result<(), CommandError> {
+    query(ctx, time(ctx).world_age())
+}
+
+fn query_day(ctx: &mut CommandCtx, _args: &Arguments) -> Result<(), CommandError> {
+    query(ctx, time(ctx).day_time / 24_000)
+}
+
+fn query(ctx: &CommandCtx, value: u64) -> Result<(), CommandError> {
+    ctx.send_translated("feather.command.time.query", vec![value.to_string().into()]);
+    Ok(())
+}
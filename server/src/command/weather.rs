@@ -0,0 +1,75 @@
+//! The `/weather` command, which acts on the sender's world.
+
+use super::{
+    argument, literal, ArgumentType, Arguments, CommandCtx, CommandDispatcher, CommandError,
+};
+use crate::weather::{Weather, WorldWeather};
+use rand::Rng;
+
+/// The longest duration which can be given, in seconds.
+const MAX_DURATION: i32 = 1_000_000;
+
+pub fn register(dispatcher: &mut CommandDispatcher) {
+    let duration = || {
+        argument(
+            "duration",
+            ArgumentType::Integer {
+                min: Some(1),
+                max: Some(MAX_DURATION),
+            },
+        )
+    };
+
+    dispatcher.register(
+        literal("weather")
+            .requires(2)
+            .then(
+                literal("clear")
+                    .executes(clear)
+                    .then(duration().executes(clear)),
+            )
+            .then(
+                literal("rain")
+                    .executes(rain)
+                    .then(duration().executes(rain)),
+            )
+            .then(
+                literal("thunder")
+                    .executes(thunder)
+                    .then(duration().executes(thunder)),
+            ),
+    );
+}
+
+/// Returns the duration argument in ticks, or a random
+/// duration of five to fifteen minutes if it is absent.
+fn duration(args: &Arguments) -> i32 {
+    let seconds = args
+        .integer("duration")
+        .unwrap_or_else(|| 300 + rand::thread_rng().gen_range(0, 600));
+    seconds * 20
+}
+
+/// Returns the weather of the sender's world.
+fn weather<'a>(ctx: &'a mut CommandCtx) -> &'a mut Weather {
+    let world = ctx.dimension().world;
+    ctx.resource_mut::<WorldWeather>().get_mut(world)
+}
+
+fn clear(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    weather(ctx).set_clear(duration(args));
+    ctx.send_translated("feather.command.weather.clear", vec![]);
+    Ok(())
+}
+
+fn rain(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    weather(ctx).set_rain(duration(args));
+    ctx.send_translated("feather.command.weather.rain", vec![]);
+    Ok(())
+}
+
+fn thunder(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    weather(ctx).set_thunder(duration(args));
+    ctx.send_translated("feather.command.weather.thunder", vec![]);
+    Ok(())
+}
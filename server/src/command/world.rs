@@ -0,0 +1,85 @@
+//! Commands concerning the worlds and the server itself:
+//! `/seed`, `/setworldspawn`, `/save-all` and `/stop`.
+
+use super::{
+    argument, literal, ArgumentType, Arguments, CommandCtx, CommandDispatcher, CommandError,
+};
+use crate::chunk_logic::SaveAllChunksEvent;
+use crate::network::Network;
+use crate::shutdown::{self, ShutdownHandle};
+use crate::state::StateInner;
+use crate::worlds::DimensionId;
+use feather_core::network::packet::implementation::SpawnPosition;
+use legion::query::{IntoQuery, Read};
+
+pub fn register(dispatcher: &mut CommandDispatcher) {
+    dispatcher.register(literal("seed").requires(2).executes(seed));
+    dispatcher.register(
+        literal("setworldspawn")
+            .requires(2)
+            .executes(set_world_spawn)
+            .then(argument("pos", ArgumentType::BlockPos).executes(set_world_spawn)),
+    );
+    dispatcher.register(literal("save-all").requires(4).executes(save_all));
+    dispatcher.register(literal("stop").requires(4).executes(stop));
+}
+
+/// Shows the seed of the sender's world.
+fn seed(ctx: &mut CommandCtx, _args: &Arguments) -> Result<(), CommandError> {
+    let world = ctx.dimension().world;
+    let seed = ctx.resource::<StateInner>().world(world).level.seed;
+    ctx.send_translated(
+        "feather.command.seed.success",
+        vec![seed.to_string().into()],
+    );
+    Ok(())
+}
+
+/// Sets the spawn point of the sender's world.
+fn set_world_spawn(ctx: &mut CommandCtx, args: &Arguments) -> Result<(), CommandError> {
+    let world = ctx.dimension().world;
+    let pos = match args.coordinates("pos") {
+        Some(coordinates) => coordinates.resolve_block(ctx.origin()),
+        None => ctx.origin().block_pos(),
+    };
+
+    let level = &mut ctx.resource_mut::<StateInner>().world_mut(world).level;
+    level.spawn_x = pos.x;
+    level.spawn_y = pos.y;
+    level.spawn_z = pos.z;
+
+    for (_, (dimension, network)) in
+        <(Read<DimensionId>, Read<Network>)>::query().iter_entities_immutable(ctx.world)
+    {
+        if dimension.world == world {
+            network.send(SpawnPosition { location: pos });
+        }
+    }
+
+    ctx.send_translated(
+        "feather.command.setworldspawn.success",
+        vec![
+            pos.x.to_string().into(),
+            pos.y.to_string().into(),
+            pos.z.to_string().into(),
+        ],
+    );
+    Ok(())
+}
+
+/// Saves the level.dat of every world and the data of every
+/// online player, and hands every loaded chunk to the chunk
+/// workers along with its entities and pending ticks.
+fn save_all(ctx: &mut CommandCtx, _args: &Arguments) -> Result<(), CommandError> {
+    shutdown::save_levels(ctx.scheduler.resources());
+    shutdown::save_players(ctx.world, ctx.scheduler.resources());
+    ctx.scheduler.trigger(SaveAllChunksEvent);
+    ctx.send_translated("feather.command.save.success", vec![]);
+    Ok(())
+}
+
+fn stop(ctx: &mut CommandCtx, _args: &Arguments) -> Result<(), CommandError> {
+    ctx.send_translated("feather.command.stop.stopping", vec![]);
+    ctx.resource::<ShutdownHandle>().shut_down();
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use failure::_core::time::Duration;
+use std::collections::HashMap;
 use std::fs::read_to_string;
 
 #[derive(Debug, Fail)]
@@ -15,9 +16,13 @@ pub struct Config {
     pub proxy: Proxy,
     pub server: Server,
     pub gameplay: Gameplay,
+    #[serde(default)]
+    pub chat: Chat,
     pub log: Log,
     pub resource_pack: ResourcePack,
     pub world: World,
+    #[serde(default)]
+    pub extra_worlds: Vec<ExtraWorld>,
 }
 
 pub const DEFAULT_CONFIG_STR: &str = include_str!("../config/feather.toml");
@@ -47,6 +52,10 @@ pub struct Server {
     pub address: String,
     pub port: u16,
     pub default_gamemode: String,
+    #[serde(default)]
+    pub difficulty: String,
+    #[serde(default)]
+    pub whitelist: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -57,6 +66,30 @@ pub struct Gameplay {
     pub nerf_spawner_mobs: bool,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Chat {
+    /// The format of chat messages, with `{prefix}`, `{name}` and
+    /// `{message}` placeholders and legacy formatting codes. If
+    /// empty, messages are formatted like on vanilla servers.
+    #[serde(default)]
+    pub format: String,
+    /// The prefix shown before the names of players,
+    /// by the permission group the player is in.
+    #[serde(default)]
+    pub prefixes: HashMap<String, String>,
+    /// How far chat messages are heard, in blocks.
+    /// If 0, every player hears every message.
+    #[serde(default)]
+    pub local_radius: f64,
+    /// Words replaced with asterisks in chat messages.
+    #[serde(default)]
+    pub filtered_words: Vec<String>,
+    /// The teams players may message with `/teammsg`,
+    /// by name, each with the names of its players.
+    #[serde(default)]
+    pub teams: HashMap<String, Vec<String>>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Log {
     pub level: String,
@@ -77,6 +110,15 @@ pub struct World {
     pub save_interval: Duration,
 }
 
+/// A world hosted in addition to the main world.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ExtraWorld {
+    pub name: String,
+    pub generator: String,
+    #[serde(default)]
+    pub seed: String,
+}
+
 /// Loads the configuration from the given file/
 pub fn load_from_file(path: &str) -> Result<Config, ConfigError> {
     let input = read_to_string(path).map_err(ConfigError::Io)?;
@@ -116,6 +158,8 @@ mod tests {
         assert_eq!(server.motd, "A Feather server");
         assert_eq!(server.max_players, 16);
         assert_eq!(server.default_gamemode, "creative");
+        assert_eq!(server.difficulty, "none");
+        assert_eq!(server.whitelist, false);
         assert_eq!(server.view_distance, 6);
         assert_eq!(server.address, "0.0.0.0");
         assert_eq!(server.port, 25565);
@@ -126,6 +170,12 @@ mod tests {
         assert_eq!(gameplay.pvp, true);
         assert_eq!(gameplay.nerf_spawner_mobs, false);
 
+        let chat = &config.chat;
+        assert_eq!(chat.format, "");
+        assert!(chat.prefixes.is_empty());
+        assert_eq!(chat.local_radius, 0.0);
+        assert!(chat.filtered_words.is_empty());
+
         let log = &config.log;
         assert_eq!(log.level, "debug");
 
@@ -138,8 +188,23 @@ mod tests {
         assert_eq!(world.generator, "default");
         assert_eq!(world.seed, "");
         assert_eq!(world.save_interval.as_millis(), 1000 * 60);
+        assert!(config.extra_worlds.is_empty());
 
         let proxy = &config.proxy;
         assert_eq!(proxy.proxy_mode, ProxyMode::None);
     }
+
+    #[test]
+    fn test_load_extra_worlds() {
+        let input = format!(
+            "{}\n[[extra_worlds]]\nname = \"lobby\"\ngenerator = \"flat\"\n",
+            include_str!("../config/feather.toml")
+        );
+
+        let config = load(input).expect("Config load failed");
+        assert_eq!(config.extra_worlds.len(), 1);
+        assert_eq!(config.extra_worlds[0].name, "lobby");
+        assert_eq!(config.extra_worlds[0].generator, "flat");
+        assert_eq!(config.extra_worlds[0].seed, "");
+    }
 }
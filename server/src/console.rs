@@ -0,0 +1,294 @@
+//! The interactive server console.
+//!
+//! Lines typed into the terminal are read on their own thread
+//! and executed as commands by the console sender. The input
+//! line keeps a history and completes commands against the
+//! command tree. Log output is printed above the input line,
+//! so it never clobbers what is being typed.
+//!
+//! If the terminal does not support line editing, such as
+//! when input is piped in, lines are read from stdin as-is.
+
+use crate::command::{CommandDispatcher, CommandEvent, CommandSender, SuggestionCtx, Suggestions};
+use crate::entity::Name;
+use crate::network::Network;
+use crate::permissions::PermissionSet;
+use crate::{TickCount, TPS};
+use chrono::Local;
+use crossbeam::queue::SegQueue;
+use crossbeam::{Receiver, Sender};
+use legion::query::Read;
+use linefeed::{
+    Completer, Completion, DefaultTerminal, Interface, Prompter, ReadResult, Signal, Terminal,
+};
+use log::{Level, Log, Metadata, Record};
+use parking_lot::RwLock;
+use std::io::{self, BufRead};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tonks::{PreparedWorld, Query, Trigger};
+
+const PROMPT: &str = "> ";
+
+/// How long the console thread waits for input
+/// before checking whether the console was closed.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Handle to the console, owned by the main thread.
+pub struct Console {
+    /// `None` if the terminal does not support line editing.
+    interface: Option<Arc<Interface<DefaultTerminal>>>,
+    closed: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+/// Initializes the console and installs it as the logger.
+pub fn init(level: Level) -> Console {
+    let interface = match Interface::new("feather") {
+        Ok(interface) => Arc::new(interface),
+        Err(_) => {
+            simple_logger::init_with_level(level).unwrap();
+            return Console {
+                interface: None,
+                closed: Arc::new(AtomicBool::new(false)),
+                thread: None,
+            };
+        }
+    };
+
+    let logger = ConsoleLogger {
+        interface: Arc::clone(&interface),
+        level,
+    };
+    log::set_boxed_logger(Box::new(logger)).unwrap();
+    log::set_max_level(level.to_level_filter());
+
+    Console {
+        interface: Some(interface),
+        closed: Arc::new(AtomicBool::new(false)),
+        thread: None,
+    }
+}
+
+impl Console {
+    /// Starts reading commands on the console thread. Pressing
+    /// Ctrl-C at the prompt shuts down the server through `shutdown`.
+    pub fn start(
+        &mut self,
+        dispatcher: CommandDispatcher,
+        shutdown: Sender<()>,
+    ) -> ConsoleCommands {
+        let (lines_tx, lines) = crossbeam::unbounded();
+        let player_names = Arc::new(RwLock::new(vec![]));
+
+        let thread = match &self.interface {
+            Some(interface) => {
+                interface.set_completer(Arc::new(CommandCompleter {
+                    dispatcher,
+                    player_names: Arc::clone(&player_names),
+                }));
+                interface.set_report_signal(Signal::Interrupt, true);
+                interface.set_prompt(PROMPT).unwrap();
+
+                let interface = Arc::clone(interface);
+                let closed = Arc::clone(&self.closed);
+                thread::Builder::new()
+                    .name("console".to_string())
+                    .spawn(move || read_lines(&interface, &closed, &lines_tx, &shutdown))
+            }
+            None => thread::Builder::new()
+                .name("console".to_string())
+                .spawn(move || read_stdin(&lines_tx)),
+        };
+        self.thread = Some(thread.unwrap());
+
+        ConsoleCommands {
+            lines,
+            player_names,
+        }
+    }
+
+    /// Stops reading commands and restores the terminal.
+    pub fn close(mut self) {
+        if self.interface.is_none() {
+            // The stdin thread cannot be interrupted, but
+            // it does not change the terminal either.
+            return;
+        }
+
+        self.closed.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Reads lines with line editing until the console is closed.
+fn read_lines(
+    interface: &Interface<DefaultTerminal>,
+    closed: &AtomicBool,
+    lines: &Sender<String>,
+    shutdown: &Sender<()>,
+) {
+    while !closed.load(Ordering::Relaxed) {
+        match interface.read_line_step(Some(POLL_INTERVAL)) {
+            Ok(None) => (),
+            Ok(Some(ReadResult::Input(line))) => {
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+                interface.add_history_unique(line.to_string());
+                if lines.send(line.to_string()).is_err() {
+                    break;
+                }
+            }
+            Ok(Some(ReadResult::Signal(Signal::Interrupt))) => {
+                let _ = shutdown.send(());
+            }
+            Ok(Some(ReadResult::Signal(_))) => (),
+            Ok(Some(ReadResult::Eof)) => break,
+            Err(e) => {
+                error!("Failed to read from the console: {}", e);
+                break;
+            }
+        }
+    }
+
+    let _ = interface.cancel_read_line();
+}
+
+/// Reads lines from stdin until it is closed.
+fn read_stdin(lines: &Sender<String>) {
+    let stdin = io::stdin();
+    for line in stdin.lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => return,
+        };
+        let line = line.trim();
+        if !line.is_empty() && lines.send(line.to_string()).is_err() {
+            return;
+        }
+    }
+}
+
+/// Logger printing records above the input line.
+struct ConsoleLogger {
+    interface: Arc<Interface<DefaultTerminal>>,
+    level: Level,
+}
+
+impl Log for ConsoleLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let _ = writeln!(
+            self.interface,
+            "{} {:<5} [{}] {}",
+            Local::now().format("%Y-%m-%d %H:%M:%S,%3f"),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+    }
+
+    fn flush(&self) {}
+}
+
+/// Completes console input against the command tree.
+struct CommandCompleter {
+    dispatcher: CommandDispatcher,
+    player_names: Arc<RwLock<Vec<String>>>,
+}
+
+impl CommandCompleter {
+    /// Returns suggestions for a line, which may
+    /// begin with a slash, but doesn't need to.
+    fn suggest(&self, line: &str) -> Suggestions {
+        let offset = if line.starts_with('/') { 1 } else { 0 };
+        let ctx = SuggestionCtx {
+            player_names: self.player_names.read().clone(),
+            permissions: PermissionSet::console(),
+        };
+        let mut suggestions = self.dispatcher.suggest(&line[offset..], &ctx);
+        suggestions.start += offset;
+        suggestions
+    }
+}
+
+impl<Term: Terminal> Completer<Term> for CommandCompleter {
+    fn complete(
+        &self,
+        _word: &str,
+        prompter: &Prompter<Term>,
+        _start: usize,
+        end: usize,
+    ) -> Option<Vec<Completion>> {
+        let suggestions = self.suggest(&prompter.buffer()[..end]);
+        Some(
+            suggestions
+                .matches
+                .into_iter()
+                .map(Completion::simple)
+                .collect(),
+        )
+    }
+
+    fn word_start(&self, line: &str, end: usize, _prompter: &Prompter<Term>) -> usize {
+        // Suggestions may replace more than the last word,
+        // such as the coordinates of a position.
+        self.suggest(&line[..end]).start
+    }
+}
+
+/// Resource receiving the commands entered into the console.
+#[derive(Resource)]
+pub struct ConsoleCommands {
+    lines: Receiver<String>,
+    /// The names of online players, for completion.
+    player_names: Arc<RwLock<Vec<String>>>,
+}
+
+/// Executes commands entered into the console, and refreshes
+/// the player names it completes once a second.
+#[system]
+fn console_commands(
+    console: &ConsoleCommands,
+    tick_count: &TickCount,
+    trigger: &mut Trigger<CommandEvent>,
+    query: &mut Query<(Read<Name>, Read<Network>)>,
+    world: &mut PreparedWorld,
+) {
+    while let Ok(line) = console.lines.try_recv() {
+        let command = if line.starts_with('/') {
+            &line[1..]
+        } else {
+            &line
+        };
+        trigger.trigger(CommandEvent {
+            sender: CommandSender::Console,
+            command: command.to_string(),
+        });
+    }
+
+    if tick_count.0 % TPS == 0 {
+        let names = SegQueue::new();
+        query.par_entities_for_each(world, |(_, (name, _))| {
+            names.push(name.0.clone());
+        });
+        let mut player_names = vec![];
+        while let Ok(name) = names.pop() {
+            player_names.push(name);
+        }
+        *console.player_names.write() = player_names;
+    }
+}
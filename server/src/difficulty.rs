@@ -0,0 +1,177 @@
+//! World difficulty and hardcore mode.
+//!
+//! Each world's difficulty is read from its `level.dat` unless
+//! the config overrides it. Hardcore worlds are always on hard
+//! difficulty, and locked difficulties can't be changed.
+//!
+//! The difficulty scales the damage explosions deal to players.
+//! Mobs and hunger are not implemented, so it has no other effect.
+
+use crate::config::Config;
+use crate::network::Network;
+use crate::player::PlayerJoinEvent;
+use crate::state::State;
+use crate::worlds::{DimensionId, PerWorld, WorldId};
+use feather_core::level::LevelData;
+use feather_core::packet::ServerDifficulty;
+use feather_core::Difficulty;
+use legion::query::Read;
+use tonks::{PreparedWorld, Query};
+
+/// Flag set in the gamemode sent in Join Game
+/// to tell the client the world is hardcore.
+pub const HARDCORE_FLAG: u8 = 0x8;
+
+/// The difficulty settings of a world.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DifficultySettings {
+    /// The current difficulty.
+    pub difficulty: Difficulty,
+    /// Whether the difficulty can no longer be changed.
+    pub locked: bool,
+    /// Whether the world is in hardcore mode.
+    pub hardcore: bool,
+}
+
+impl DifficultySettings {
+    /// Creates the difficulty settings from the values stored
+    /// in `level.dat` and the difficulty set in the config.
+    pub fn from_level(level: &LevelData, config: &Config) -> Self {
+        let configured = match config.server.difficulty.as_str() {
+            "" | "none" => None,
+            name => {
+                let difficulty = Difficulty::from_string(name);
+                if difficulty.is_none() {
+                    warn!("Unknown difficulty '{}' in config; ignoring", name);
+                }
+                difficulty
+            }
+        };
+        let locked = level.difficulty_locked != 0;
+
+        let difficulty = if level.hardcore {
+            Difficulty::Hard
+        } else {
+            match configured {
+                Some(difficulty) if !locked => difficulty,
+                _ => Difficulty::from_id(level.difficulty as u8),
+            }
+        };
+
+        Self {
+            difficulty,
+            locked,
+            hardcore: level.hardcore,
+        }
+    }
+
+    /// Writes the difficulty settings into `level`
+    /// so they can be saved to `level.dat`.
+    pub fn write_to_level(self, level: &mut LevelData) {
+        level.difficulty = self.difficulty.get_id() as i8;
+        level.difficulty_locked = self.locked as i8;
+        level.hardcore = self.hardcore;
+    }
+
+    /// Changes the difficulty and notifies the
+    /// players in the world with these settings.
+    ///
+    /// Returns `false` if the difficulty can't be
+    /// changed because it is locked or the world is hardcore.
+    pub fn set(&mut self, state: &State, world: WorldId, difficulty: Difficulty) -> bool {
+        if self.locked || self.hardcore {
+            return false;
+        }
+
+        self.difficulty = difficulty;
+        state.broadcast_world(
+            world,
+            ServerDifficulty {
+                difficulty: difficulty.get_id(),
+            },
+            None,
+        );
+        true
+    }
+
+    /// Scales damage dealt to players by sources which
+    /// depend on the difficulty, such as explosions.
+    pub fn scale_damage(self, damage: u32) -> u32 {
+        match self.difficulty {
+            Difficulty::Peaceful => 0,
+            Difficulty::Easy => (damage / 2 + 1).min(damage),
+            Difficulty::Medium => damage,
+            Difficulty::Hard => damage * 3 / 2,
+        }
+    }
+}
+
+/// The difficulty settings of each world.
+#[derive(Debug, Clone, Resource, Deref, DerefMut)]
+pub struct WorldDifficulty(pub PerWorld<DifficultySettings>);
+
+/// Event handler for sending the difficulty to players.
+#[event_handler]
+fn difficulty_send(
+    event: &PlayerJoinEvent,
+    settings: &WorldDifficulty,
+    _query: &mut Query<(Read<Network>, Read<DimensionId>)>,
+    world: &mut PreparedWorld,
+) {
+    let network = world.get_component::<Network>(event.player).unwrap();
+    let dimension = world.get_component::<DimensionId>(event.player).unwrap();
+    network.send(ServerDifficulty {
+        difficulty: settings.get(dimension.world).difficulty.get_id(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(
+        difficulty: &str,
+        level_difficulty: i8,
+        locked: bool,
+        hardcore: bool,
+    ) -> Difficulty {
+        let mut config = Config::default();
+        config.server.difficulty = difficulty.to_string();
+
+        let mut level = LevelData::default();
+        level.difficulty = level_difficulty;
+        level.difficulty_locked = locked as i8;
+        level.hardcore = hardcore;
+
+        DifficultySettings::from_level(&level, &config).difficulty
+    }
+
+    #[test]
+    fn test_from_level() {
+        assert_eq!(settings("none", 1, false, false), Difficulty::Easy);
+        assert_eq!(settings("hard", 1, false, false), Difficulty::Hard);
+        assert_eq!(settings("hard", 1, true, false), Difficulty::Easy);
+        assert_eq!(settings("peaceful", 1, false, true), Difficulty::Hard);
+        assert_eq!(settings("invalid", 0, false, false), Difficulty::Peaceful);
+    }
+
+    #[test]
+    fn test_scale_damage() {
+        let mut settings = DifficultySettings {
+            difficulty: Difficulty::Peaceful,
+            locked: false,
+            hardcore: false,
+        };
+        assert_eq!(settings.scale_damage(10), 0);
+
+        settings.difficulty = Difficulty::Easy;
+        assert_eq!(settings.scale_damage(10), 6);
+        assert_eq!(settings.scale_damage(1), 1);
+
+        settings.difficulty = Difficulty::Medium;
+        assert_eq!(settings.scale_damage(10), 10);
+
+        settings.difficulty = Difficulty::Hard;
+        assert_eq!(settings.scale_damage(10), 15);
+    }
+}
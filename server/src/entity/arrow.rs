@@ -0,0 +1,544 @@
+//! Arrow entities.
+//!
+//! Arrows are simulated by `arrow_tick` rather than by the
+//! generic physics systems: they need to detect hits
+//! against entities along their path, and they stick
+//! in the first block they hit.
+
+use crate::entity;
+use crate::entity::item::ItemCollectEvent;
+use crate::entity::{
+    EntityDataCreator, EntityId, EntityKind, EntityMoveEvent, SpawnPacketCreator, Velocity,
+    VelocityUpdateEvent,
+};
+use crate::health::{DamageCause, EntityDamageEvent, Health};
+use crate::lazy::EntityBuilder;
+use crate::metadata::{ArrowBitMask, Metadata};
+use crate::p_inventory::{EntityInventory, InventoryUpdateEvent};
+use crate::physics::{block_impacted_by_ray, nearby_entities};
+use crate::state::{DimensionState, State};
+use crate::util::{degrees_to_stops, protocol_velocity};
+use crate::worlds::DimensionId;
+use crossbeam::queue::SegQueue;
+use feather_core::entity::{ArrowEntityData, BaseEntityData, EntityData};
+use feather_core::network::packet::implementation::SpawnObject;
+use feather_core::{Block, BlockExt, BlockPosition, Gamemode, Item, ItemStack, Packet, Position};
+use glm::DVec3;
+use hashbrown::HashSet;
+use legion::entity::Entity;
+use legion::query::{Read, Write};
+use rand::Rng;
+use tonks::{EntityAccessor, PreparedWorld, Query, Trigger};
+use uuid::Uuid;
+
+/// The damage dealt by an arrow moving at one block per tick.
+pub const BASE_DAMAGE: f64 = 2.0;
+/// Gravitational acceleration of arrows.
+const GRAVITY: f64 = -0.05;
+/// Drag applied to arrows each tick in air.
+const DRAG: f64 = 0.99;
+/// Drag applied to arrows each tick in water.
+const WATER_DRAG: f64 = 0.6;
+/// The number of ticks after which an arrow
+/// stuck in a block despawns.
+const DESPAWN_TICKS: u64 = 1200;
+/// The number of ticks during which an arrow
+/// can't hit the entity which shot it.
+const SHOOTER_IMMUNITY_TICKS: u64 = 5;
+/// Half the width of a player's bounding box,
+/// grown by the size of an arrow.
+const TARGET_HALF_WIDTH: f64 = 0.6;
+/// The height of a player's bounding box,
+/// grown by the size of an arrow.
+const TARGET_HEIGHT: f64 = 2.1;
+
+/// Who may pick up an arrow once it is stuck in a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArrowPickup {
+    /// Nobody may pick up the arrow.
+    Disallowed = 0,
+    /// Any player may pick up the arrow.
+    Allowed = 1,
+    /// Only creative mode players may pick up
+    /// the arrow, which gives them no item.
+    CreativeOnly = 2,
+}
+
+impl ArrowPickup {
+    /// Returns the pickup rule stored as `value` in arrow NBT.
+    pub fn from_byte(value: u8) -> Self {
+        match value {
+            1 => ArrowPickup::Allowed,
+            2 => ArrowPickup::CreativeOnly,
+            _ => ArrowPickup::Disallowed,
+        }
+    }
+}
+
+/// Component for arrow entities.
+#[derive(Debug, Clone, Copy)]
+pub struct Arrow {
+    /// The entity which shot the arrow, if any.
+    pub shooter: Option<Entity>,
+    /// Whether the arrow was shot with a fully drawn bow.
+    pub critical: bool,
+    /// Who may pick up the arrow.
+    pub pickup: ArrowPickup,
+    /// The damage dealt by the arrow, before
+    /// accounting for its speed.
+    pub damage: f64,
+    /// The block the arrow is stuck in, if any.
+    pub stuck_in: Option<BlockPosition>,
+    /// The number of ticks the arrow has been flying.
+    pub ticks_in_air: u64,
+    /// The number of ticks the arrow has been stuck.
+    pub ticks_in_ground: u64,
+}
+
+impl Arrow {
+    /// Creates an arrow shot by `shooter`.
+    pub fn new(shooter: Option<Entity>, critical: bool, pickup: ArrowPickup) -> Self {
+        Self {
+            shooter,
+            critical,
+            pickup,
+            damage: BASE_DAMAGE,
+            stuck_in: None,
+            ticks_in_air: 0,
+            ticks_in_ground: 0,
+        }
+    }
+}
+
+/// Returns the yaw and pitch of an arrow
+/// flying with the given velocity.
+pub fn rotation(velocity: DVec3) -> (f32, f32) {
+    let horizontal = (velocity.x * velocity.x + velocity.z * velocity.z).sqrt();
+    let yaw = velocity.x.atan2(velocity.z).to_degrees();
+    let pitch = velocity.y.atan2(horizontal).to_degrees();
+    (yaw as f32, pitch as f32)
+}
+
+/// Returns the damage dealt by an arrow with the given
+/// base damage hitting an entity at the given speed,
+/// without the bonus for critical arrows.
+fn hit_damage(speed: f64, damage: f64) -> u32 {
+    (speed * damage).ceil().max(0.0) as u32
+}
+
+/// Returns the fraction of the segment from `start` to `end`
+/// at which it enters the box from `min` to `max`, or `None`
+/// if the segment misses the box.
+fn segment_intersects_box(start: DVec3, end: DVec3, min: DVec3, max: DVec3) -> Option<f64> {
+    let delta = end - start;
+    let mut t_min: f64 = 0.0;
+    let mut t_max: f64 = 1.0;
+
+    for axis in 0..3 {
+        if delta[axis].abs() < std::f64::EPSILON {
+            if start[axis] < min[axis] || start[axis] > max[axis] {
+                return None;
+            }
+            continue;
+        }
+
+        let t1 = (min[axis] - start[axis]) / delta[axis];
+        let t2 = (max[axis] - start[axis]) / delta[axis];
+        t_min = t_min.max(t1.min(t2));
+        t_max = t_max.min(t1.max(t2));
+
+        if t_min > t_max {
+            return None;
+        }
+    }
+
+    Some(t_min)
+}
+
+/// Finds the first living entity hit by an
+/// arrow moving from `start` to `end`.
+fn entity_hit(
+    state: &DimensionState,
+    world: &PreparedWorld,
+    entity: Entity,
+    arrow: &Arrow,
+    start: DVec3,
+    end: DVec3,
+) -> Option<Entity> {
+    let delta = end - start;
+    let radius = glm::vec3(
+        delta.x.abs() + TARGET_HALF_WIDTH,
+        delta.y.abs() + TARGET_HEIGHT,
+        delta.z.abs() + TARGET_HALF_WIDTH,
+    );
+
+    nearby_entities(state.chunk_entities, world, start.into(), radius)
+        .into_iter()
+        .filter(|other| *other != entity && world.get_component::<Health>(*other).is_some())
+        .filter(|other| {
+            Some(*other) != arrow.shooter || arrow.ticks_in_air >= SHOOTER_IMMUNITY_TICKS
+        })
+        .filter_map(|other| {
+            let pos: DVec3 = (*world.get_component::<Position>(other)?).into();
+            let min = pos - glm::vec3(TARGET_HALF_WIDTH, 0.3, TARGET_HALF_WIDTH);
+            let max = pos + glm::vec3(TARGET_HALF_WIDTH, TARGET_HEIGHT, TARGET_HALF_WIDTH);
+            segment_intersects_box(start, end, min, max).map(|t| (other, t))
+        })
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+        .map(|(other, _)| other)
+}
+
+/// System which moves arrows, detects hits and
+/// despawns arrows stuck in blocks for too long.
+#[system]
+fn arrow_tick(
+    state: &State,
+    query: &mut Query<(
+        Write<Position>,
+        Write<Velocity>,
+        Write<Arrow>,
+        Read<DimensionId>,
+    )>,
+    _query: &mut Query<(Read<Health>,)>,
+    world: &mut PreparedWorld,
+    move_events: &mut Trigger<EntityMoveEvent>,
+    velocity_events: &mut Trigger<VelocityUpdateEvent>,
+    damage_events: &mut Trigger<EntityDamageEvent>,
+) {
+    let arrows = SegQueue::new();
+    query.par_entities_for_each(world, |(entity, (pos, velocity, arrow, dimension))| {
+        arrows.push((entity, *pos, velocity.0, *arrow, *dimension));
+    });
+
+    while let Ok((entity, pos, mut velocity, mut arrow, dimension)) = arrows.pop() {
+        let state = &state.dimension(dimension);
+        if let Some(block) = arrow.stuck_in {
+            if state
+                .block_at(block)
+                .map_or(false, |block| block.is_solid())
+            {
+                arrow.ticks_in_ground += 1;
+                if arrow.ticks_in_ground >= DESPAWN_TICKS {
+                    state.delete_entity(entity);
+                } else {
+                    *world.get_component_mut::<Arrow>(entity).unwrap() = arrow;
+                }
+                continue;
+            }
+
+            // The block was removed, so the arrow falls.
+            arrow.stuck_in = None;
+            arrow.ticks_in_air = 0;
+            arrow.ticks_in_ground = 0;
+            velocity = glm::vec3(0.0, 0.0, 0.0);
+        }
+
+        let start: DVec3 = pos.into();
+        let impact = block_impacted_by_ray(state, start, velocity, glm::length2(&velocity));
+        let end = impact
+            .as_ref()
+            .map_or(start + velocity, |impact| impact.pos.into());
+
+        if let Some(target) = entity_hit(state, world, entity, &arrow, start, end) {
+            let mut damage = hit_damage(glm::length(&velocity), arrow.damage);
+            if arrow.critical {
+                damage += rand::thread_rng().gen_range(0, damage / 2 + 2);
+            }
+
+            damage_events.trigger(EntityDamageEvent {
+                entity: target,
+                damage,
+                cause: DamageCause::Arrow {
+                    shooter: arrow.shooter,
+                },
+            });
+            state.delete_entity(entity);
+            continue;
+        }
+
+        let mut new_pos = Position::from(end);
+        match impact {
+            Some(impact) => {
+                // Stick in the block.
+                new_pos.yaw = pos.yaw;
+                new_pos.pitch = pos.pitch;
+                new_pos.on_ground = true;
+                velocity = glm::vec3(0.0, 0.0, 0.0);
+                arrow.stuck_in = Some(impact.block);
+            }
+            None => {
+                let (yaw, pitch) = rotation(velocity);
+                new_pos.yaw = yaw;
+                new_pos.pitch = pitch;
+                new_pos.on_ground = false;
+
+                let drag = match state.block_at(new_pos.block_pos()) {
+                    Some(Block::Water(_)) => WATER_DRAG,
+                    Some(_) => DRAG,
+                    None => {
+                        // The arrow flew into unloaded chunks.
+                        state.delete_entity(entity);
+                        continue;
+                    }
+                };
+                velocity = velocity * drag + glm::vec3(0.0, GRAVITY, 0.0);
+                arrow.ticks_in_air += 1;
+            }
+        }
+
+        *world.get_component_mut::<Position>(entity).unwrap() = new_pos;
+        world.get_component_mut::<Velocity>(entity).unwrap().0 = velocity;
+        *world.get_component_mut::<Arrow>(entity).unwrap() = arrow;
+
+        move_events.trigger(EntityMoveEvent { entity });
+        velocity_events.trigger(VelocityUpdateEvent { entity });
+    }
+}
+
+/// Event handler which lets players pick up
+/// arrows stuck in blocks near them.
+#[event_handler]
+fn arrow_collect(
+    events: &[EntityMoveEvent],
+    state: &State,
+    _query: &mut Query<(
+        Read<Arrow>,
+        Read<Gamemode>,
+        Read<Position>,
+        Read<DimensionId>,
+        Write<EntityInventory>,
+    )>,
+    world: &mut PreparedWorld,
+    inventory_updates: &mut Trigger<InventoryUpdateEvent>,
+    item_collects: &mut Trigger<ItemCollectEvent>,
+) {
+    let mut collected = HashSet::new();
+
+    for event in events {
+        let gamemode = match world.get_component::<Gamemode>(event.entity) {
+            Some(gamemode) => *gamemode,
+            None => continue,
+        };
+        let pos = *world.get_component::<Position>(event.entity).unwrap();
+        let dimension = *world.get_component::<DimensionId>(event.entity).unwrap();
+
+        let nearby = nearby_entities(
+            state.dimension(dimension).chunk_entities,
+            world,
+            pos + glm::vec3(0.0, 0.9, 0.0),
+            glm::vec3(1.3, 1.4, 1.3),
+        );
+
+        for other in nearby {
+            let arrow = match world.get_component::<Arrow>(other) {
+                Some(arrow) if arrow.stuck_in.is_some() => *arrow,
+                _ => continue,
+            };
+
+            if collected.contains(&other) {
+                continue;
+            }
+
+            let creative = gamemode == Gamemode::Creative;
+            match arrow.pickup {
+                ArrowPickup::Disallowed => continue,
+                ArrowPickup::CreativeOnly if !creative => continue,
+                ArrowPickup::Allowed if !creative => {
+                    let mut inventory =
+                        match world.get_component_mut::<EntityInventory>(event.entity) {
+                            Some(inventory) => inventory,
+                            None => continue,
+                        };
+                    let (slots, left) = inventory.collect_item(ItemStack::new(Item::Arrow, 1));
+                    if left > 0 {
+                        continue;
+                    }
+
+                    inventory_updates.trigger(InventoryUpdateEvent {
+                        slots,
+                        player: event.entity,
+                    });
+                }
+                _ => (),
+            }
+
+            collected.insert(other);
+            item_collects.trigger(ItemCollectEvent {
+                item: other,
+                collector: event.entity,
+                amount: 1,
+            });
+            state.delete_entity(other);
+        }
+    }
+}
+
+/// Returns an entity builder to create an arrow
+/// entity at `pos`. `shooter` is the UUID of the
+/// entity which shot the arrow.
+pub fn create(
+    state: &DimensionState,
+    pos: Position,
+    arrow: Arrow,
+    shooter: Option<Uuid>,
+) -> EntityBuilder {
+    let meta = {
+        let mut meta = crate::metadata::Arrow::default();
+        let mut bit_mask = ArrowBitMask::empty();
+        bit_mask.set(ArrowBitMask::CRITICAL, arrow.critical);
+        meta.set_arrow_bit_mask(bit_mask.bits());
+        meta.set_shooter(shooter);
+        Metadata::Arrow(meta)
+    };
+
+    entity::base(state, EntityKind::Arrow, pos)
+        .with_component(arrow)
+        .with_component(SpawnPacketCreator(&create_spawn_packet))
+        .with_component(EntityDataCreator(&create_entity_data))
+        .with_component(meta)
+}
+
+/// Returns an entity builder to recreate
+/// an arrow from saved entity data.
+pub fn load(state: &DimensionState, data: &ArrowEntityData) -> Option<EntityBuilder> {
+    let pos = data.entity.read_position()?;
+    let velocity = data.entity.read_velocity()?;
+
+    let stuck_in = if data.in_ground != 0 {
+        Some(BlockPosition::new(data.tile_x, data.tile_y, data.tile_z))
+    } else {
+        None
+    };
+
+    let arrow = Arrow {
+        shooter: None,
+        critical: data.critical != 0,
+        pickup: ArrowPickup::from_byte(data.pickup),
+        damage: if data.damage > 0.0 {
+            data.damage
+        } else {
+            BASE_DAMAGE
+        },
+        stuck_in,
+        ticks_in_air: 0,
+        ticks_in_ground: data.life.max(0) as u64,
+    };
+
+    Some(create(state, pos, arrow, None).with_component(Velocity(velocity)))
+}
+
+fn create_spawn_packet(accessor: &EntityAccessor, world: &PreparedWorld) -> Box<dyn Packet> {
+    let position = *accessor.get_component::<Position>(world).unwrap();
+    let velocity = *accessor.get_component::<Velocity>(world).unwrap();
+    let entity_id = accessor.get_component::<EntityId>(world).unwrap().0;
+    let arrow = *accessor.get_component::<Arrow>(world).unwrap();
+
+    let shooter_id = arrow
+        .shooter
+        .and_then(|shooter| world.get_component::<EntityId>(shooter))
+        .map_or(entity_id, |id| id.0);
+
+    let (velocity_x, velocity_y, velocity_z) = protocol_velocity(velocity.0);
+
+    let packet = SpawnObject {
+        entity_id,
+        object_uuid: Uuid::new_v4(),
+        ty: 60, // Type 60 for arrow
+        x: position.x,
+        y: position.y,
+        z: position.z,
+        pitch: degrees_to_stops(position.pitch),
+        yaw: degrees_to_stops(position.yaw),
+        data: shooter_id + 1, // Nonzero so that the client applies the velocity
+        velocity_x,
+        velocity_y,
+        velocity_z,
+    };
+
+    Box::new(packet)
+}
+
+fn create_entity_data(accessor: &EntityAccessor, world: &PreparedWorld) -> EntityData {
+    let position = *accessor.get_component::<Position>(world).unwrap();
+    let velocity = *accessor.get_component::<Velocity>(world).unwrap();
+    let arrow = *accessor.get_component::<Arrow>(world).unwrap();
+
+    let tile = arrow.stuck_in.unwrap_or_default();
+
+    EntityData::Arrow(ArrowEntityData {
+        entity: BaseEntityData::new(position, velocity.0),
+        critical: arrow.critical as u8,
+        pickup: arrow.pickup as u8,
+        in_ground: arrow.stuck_in.is_some() as u8,
+        life: arrow.ticks_in_ground as i16,
+        damage: arrow.damage,
+        tile_x: tile.x,
+        tile_y: tile.y,
+        tile_z: tile.z,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segment_intersects_box() {
+        let min = glm::vec3(0.0, 0.0, 0.0);
+        let max = glm::vec3(1.0, 2.0, 1.0);
+
+        let t = segment_intersects_box(
+            glm::vec3(-1.0, 1.0, 0.5),
+            glm::vec3(3.0, 1.0, 0.5),
+            min,
+            max,
+        );
+        assert_eq!(t, Some(0.25));
+
+        // Starts inside the box.
+        let t =
+            segment_intersects_box(glm::vec3(0.5, 0.5, 0.5), glm::vec3(5.0, 0.5, 0.5), min, max);
+        assert_eq!(t, Some(0.0));
+
+        // Passes above the box.
+        assert_eq!(
+            segment_intersects_box(
+                glm::vec3(-1.0, 3.0, 0.5),
+                glm::vec3(3.0, 3.0, 0.5),
+                min,
+                max
+            ),
+            None
+        );
+
+        // Stops short of the box.
+        assert_eq!(
+            segment_intersects_box(
+                glm::vec3(-3.0, 1.0, 0.5),
+                glm::vec3(-1.0, 1.0, 0.5),
+                min,
+                max
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_hit_damage() {
+        assert_eq!(hit_damage(3.0, BASE_DAMAGE), 6);
+        assert_eq!(hit_damage(2.9, BASE_DAMAGE), 6);
+        assert_eq!(hit_damage(0.0, BASE_DAMAGE), 0);
+    }
+
+    #[test]
+    fn test_pickup_from_byte() {
+        for pickup in &[
+            ArrowPickup::Disallowed,
+            ArrowPickup::Allowed,
+            ArrowPickup::CreativeOnly,
+        ] {
+            assert_eq!(ArrowPickup::from_byte(*pickup as u8), *pickup);
+        }
+        assert_eq!(ArrowPickup::from_byte(7), ArrowPickup::Disallowed);
+    }
+}
@@ -0,0 +1,313 @@
+//! Boats.
+//!
+//! A ridden boat is moved by its driver's client; see
+//! `packet_handlers::vehicle`. Boats without a driver
+//! float on water and fall when not supported.
+
+use crate::block::direction::Direction;
+use crate::entity;
+use crate::entity::{
+    EntityDataCreator, EntityId, EntityKind, EntityMoveEvent, SpawnPacketCreator, Velocity,
+};
+use crate::lazy::EntityBuilder;
+use crate::metadata::Metadata;
+use crate::player::PLAYER_EYE_HEIGHT;
+use crate::state::{DimensionState, State};
+use crate::util::{degrees_to_stops, protocol_velocity};
+use crate::vehicle::{Vehicle, VehicleKind};
+use crate::worlds::DimensionId;
+use crossbeam::queue::SegQueue;
+use feather_core::entity::{BaseEntityData, BoatEntityData, EntityData};
+use feather_core::network::packet::implementation::SpawnObject;
+use feather_core::{Block, BlockExt, Item, Packet, Position};
+use legion::query::{Read, Write};
+use tonks::{EntityAccessor, PreparedWorld, Query, Trigger};
+use uuid::Uuid;
+
+/// The distance from which players can place boats.
+const PLACE_DISTANCE: f64 = 5.0;
+/// Downward acceleration of boats out of water.
+const GRAVITY: f64 = -0.04;
+/// Upward acceleration of boats in water.
+const BUOYANCY: f64 = 0.04;
+/// The maximum speed at which boats rise in water.
+const MAX_RISE_SPEED: f64 = 0.1;
+
+/// The wood a boat is made of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoatType {
+    Oak,
+    Spruce,
+    Birch,
+    Jungle,
+    Acacia,
+    DarkOak,
+}
+
+impl BoatType {
+    /// Returns the type of boat placed by `item`,
+    /// or `None` if it is not a boat.
+    pub fn from_item(item: Item) -> Option<Self> {
+        let ty = match item {
+            Item::OakBoat => BoatType::Oak,
+            Item::SpruceBoat => BoatType::Spruce,
+            Item::BirchBoat => BoatType::Birch,
+            Item::JungleBoat => BoatType::Jungle,
+            Item::AcaciaBoat => BoatType::Acacia,
+            Item::DarkOakBoat => BoatType::DarkOak,
+            _ => return None,
+        };
+        Some(ty)
+    }
+
+    /// Returns the item dropped by the boat.
+    pub fn item(self) -> Item {
+        match self {
+            BoatType::Oak => Item::OakBoat,
+            BoatType::Spruce => Item::SpruceBoat,
+            BoatType::Birch => Item::BirchBoat,
+            BoatType::Jungle => Item::JungleBoat,
+            BoatType::Acacia => Item::AcaciaBoat,
+            BoatType::DarkOak => Item::DarkOakBoat,
+        }
+    }
+
+    /// Returns the name of the type, as saved in entity data.
+    pub fn name(self) -> &'static str {
+        match self {
+            BoatType::Oak => "oak",
+            BoatType::Spruce => "spruce",
+            BoatType::Birch => "birch",
+            BoatType::Jungle => "jungle",
+            BoatType::Acacia => "acacia",
+            BoatType::DarkOak => "dark_oak",
+        }
+    }
+
+    /// Parses a type from its name, defaulting to oak.
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "spruce" => BoatType::Spruce,
+            "birch" => BoatType::Birch,
+            "jungle" => BoatType::Jungle,
+            "acacia" => BoatType::Acacia,
+            "dark_oak" => BoatType::DarkOak,
+            _ => BoatType::Oak,
+        }
+    }
+
+    /// Returns the ID of the type, as sent in entity metadata.
+    pub fn id(self) -> i32 {
+        match self {
+            BoatType::Oak => 0,
+            BoatType::Spruce => 1,
+            BoatType::Birch => 2,
+            BoatType::Jungle => 3,
+            BoatType::Acacia => 4,
+            BoatType::DarkOak => 5,
+        }
+    }
+}
+
+/// Component for boat entities.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Boat {
+    pub ty: BoatType,
+}
+
+/// Places a boat on the water or block a player at
+/// `player_pos` is looking at. Returns whether the
+/// boat was placed.
+pub fn place(state: &DimensionState, player_pos: Position, ty: BoatType) -> bool {
+    let eye = player_pos + glm::vec3(0.0, PLAYER_EYE_HEIGHT, 0.0);
+    let direction = player_pos.direction();
+
+    // Step along the player's line of sight until
+    // it meets water or a solid block.
+    let steps = (PLACE_DISTANCE * 10.0) as usize;
+    for step in 0..=steps {
+        let point = eye + direction * (step as f64 / 10.0);
+        let block_pos = point.block_pos();
+        let block = match state.block_at(block_pos) {
+            Some(block) => block,
+            None => return false,
+        };
+
+        let surface = match block {
+            Block::Water(_) => true,
+            block => block.is_solid(),
+        };
+        if !surface {
+            continue;
+        }
+
+        let above = Direction::Up.adjacent(block_pos);
+        if state.block_at(above) != Some(Block::Air) {
+            return false;
+        }
+
+        let pos = position!(point.x, f64::from(above.y), point.z, 0.0, player_pos.yaw);
+        create(state, pos, ty).build();
+        return true;
+    }
+
+    false
+}
+
+/// System which moves boats without a driver.
+#[system]
+fn boat_tick(
+    state: &State,
+    query: &mut Query<(
+        Read<Boat>,
+        Read<Vehicle>,
+        Write<Position>,
+        Write<Velocity>,
+        Read<DimensionId>,
+    )>,
+    world: &mut PreparedWorld,
+    move_events: &mut Trigger<EntityMoveEvent>,
+) {
+    let moved = SegQueue::new();
+
+    query.par_entities_for_each(
+        world,
+        |(entity, (_, vehicle, mut pos, mut velocity, dimension))| {
+            if vehicle.driver().is_some() {
+                return;
+            }
+
+            let state = state.dimension(*dimension);
+            let block_at = |pos: Position| state.block_at(pos.block_pos());
+            let (inside, below) = match (block_at(*pos), block_at(*pos - glm::vec3(0.0, 0.01, 0.0)))
+            {
+                (Some(inside), Some(below)) => (inside, below),
+                _ => return, // Unloaded chunk
+            };
+
+            let is_water = |block: Block| match block {
+                Block::Water(_) => true,
+                _ => false,
+            };
+
+            if is_water(inside) {
+                velocity.y = (velocity.y + BUOYANCY).min(MAX_RISE_SPEED);
+                velocity.x *= 0.9;
+                velocity.z *= 0.9;
+            } else if is_water(below) || below.is_solid() {
+                velocity.y = 0.0;
+                let friction = if is_water(below) { 0.9 } else { 0.5 };
+                velocity.x *= friction;
+                velocity.z *= friction;
+            } else {
+                velocity.y += GRAVITY;
+            }
+
+            let mut new_pos = *pos + velocity.0;
+            if block_at(new_pos).map_or(true, |block| block.is_solid()) {
+                // Land on top of the block below, or stop
+                // when running into a block.
+                if velocity.y < 0.0 {
+                    new_pos.y = new_pos.y.floor() + 1.0;
+                    velocity.y = 0.0;
+                }
+                if block_at(new_pos).map_or(true, |block| block.is_solid()) {
+                    new_pos.x = pos.x;
+                    new_pos.z = pos.z;
+                    velocity.x = 0.0;
+                    velocity.z = 0.0;
+                }
+            }
+
+            if glm::length2(&velocity.0) < 1.0e-6 {
+                velocity.0 = glm::vec3(0.0, 0.0, 0.0);
+            }
+
+            if new_pos != *pos {
+                *pos = new_pos;
+                moved.push(entity);
+            }
+        },
+    );
+
+    while let Ok(entity) = moved.pop() {
+        move_events.trigger(EntityMoveEvent { entity });
+    }
+}
+
+/// Returns an entity builder to create a boat.
+pub fn create(state: &DimensionState, pos: Position, ty: BoatType) -> EntityBuilder {
+    let meta = {
+        let mut meta = crate::metadata::Boat::default();
+        meta.set_boat_type(ty.id());
+        Metadata::Boat(meta)
+    };
+
+    entity::base(state, EntityKind::Boat, pos)
+        .with_component(Boat { ty })
+        .with_component(Vehicle::new(VehicleKind::Boat, ty.item()))
+        .with_component(SpawnPacketCreator(&create_spawn_packet))
+        .with_component(EntityDataCreator(&create_entity_data))
+        .with_component(meta)
+}
+
+/// Returns an entity builder to recreate
+/// a boat from saved entity data.
+pub fn load(state: &DimensionState, data: &BoatEntityData) -> Option<EntityBuilder> {
+    let pos = data.entity.read_position()?;
+    let velocity = data.entity.read_velocity()?;
+    let ty = BoatType::from_name(&data.boat_type);
+
+    Some(create(state, pos, ty).with_component(Velocity(velocity)))
+}
+
+fn create_spawn_packet(accessor: &EntityAccessor, world: &PreparedWorld) -> Box<dyn Packet> {
+    let position = *accessor.get_component::<Position>(world).unwrap();
+    let velocity = *accessor.get_component::<Velocity>(world).unwrap();
+    let entity_id = accessor.get_component::<EntityId>(world).unwrap().0;
+
+    let (velocity_x, velocity_y, velocity_z) = protocol_velocity(velocity.0);
+
+    let packet = SpawnObject {
+        entity_id,
+        object_uuid: Uuid::new_v4(),
+        ty: 1, // Type 1 for boats
+        x: position.x,
+        y: position.y,
+        z: position.z,
+        pitch: degrees_to_stops(position.pitch),
+        yaw: degrees_to_stops(position.yaw),
+        data: 0,
+        velocity_x,
+        velocity_y,
+        velocity_z,
+    };
+
+    Box::new(packet)
+}
+
+fn create_entity_data(accessor: &EntityAccessor, world: &PreparedWorld) -> EntityData {
+    let position = *accessor.get_component::<Position>(world).unwrap();
+    let velocity = *accessor.get_component::<Velocity>(world).unwrap();
+    let boat = *accessor.get_component::<Boat>(world).unwrap();
+
+    EntityData::Boat(BoatEntityData {
+        entity: BaseEntityData::new(position, velocity.0),
+        boat_type: boat.ty.name().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_boat_type() {
+        for ty in &[BoatType::Oak, BoatType::Birch, BoatType::DarkOak] {
+            assert_eq!(BoatType::from_item(ty.item()), Some(*ty));
+            assert_eq!(BoatType::from_name(ty.name()), *ty);
+        }
+        assert_eq!(BoatType::from_item(Item::Minecart), None);
+        assert_eq!(BoatType::from_name("maple"), BoatType::Oak);
+    }
+}
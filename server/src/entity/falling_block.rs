@@ -0,0 +1,442 @@
+//! Falling block entities.
+//!
+//! Blocks affected by gravity, such as sand, gravel,
+//! concrete powder and anvils, turn into falling block
+//! entities when the block below them is removed. Once
+//! the entity lands, it places itself again, or drops
+//! as an item if it can't be placed. Falling anvils
+//! damage the entities they land on.
+
+use crate::block::direction::Direction;
+use crate::block::placement::is_replaceable;
+use crate::block::tick::{schedule_block_tick, BlockTickEvent, BlockTicks, TickPriority};
+use crate::block::{BlockUpdateCause, BlockUpdateEvent};
+use crate::entity::item;
+use crate::entity::{EntityId, EntityKind, SpawnPacketCreator, Velocity};
+use crate::game_rules::{GameRule, GameRules, WorldGameRules};
+use crate::health::{DamageCause, EntityDamageEvent, Health};
+use crate::lazy::EntityBuilder;
+use crate::metadata::Metadata;
+use crate::physics::{nearby_entities, PhysicsBuilder};
+use crate::state::{DimensionState, State};
+use crate::util::{degrees_to_stops, protocol_velocity};
+use crate::worlds::DimensionId;
+use crate::{entity, TickCount};
+use crossbeam::queue::SegQueue;
+use feather_blocks::{ChippedAnvilData, DamagedAnvilData};
+use feather_core::network::packet::implementation::SpawnObject;
+use feather_core::{Block, BlockExt, BlockPosition, ItemStack, Packet, Position};
+use feather_item_block::BlockToItem;
+use legion::entity::Entity;
+use legion::query::Read;
+use rand::Rng;
+use tonks::{EntityAccessor, PreparedWorld, Query, Trigger};
+use uuid::Uuid;
+
+/// The number of ticks between a block losing
+/// its support and the block starting to fall.
+const FALL_DELAY: u64 = 2;
+/// The number of ticks after which a falling
+/// block which hasn't landed drops as an item.
+const MAX_FALL_TICKS: u64 = 600;
+
+/// Component for falling block entities.
+#[derive(Debug, Clone, Copy)]
+pub struct FallingBlock {
+    /// The block which is falling.
+    pub block: Block,
+    /// The position from which the block started falling.
+    pub origin: BlockPosition,
+    /// The tick at which the block started falling.
+    pub spawned_at: u64,
+}
+
+/// The damage dealt by a falling anvil per block fallen.
+const ANVIL_DAMAGE_PER_BLOCK: u32 = 2;
+/// The maximum damage dealt by a falling anvil.
+const MAX_ANVIL_DAMAGE: u32 = 40;
+
+/// Returns whether `block` is affected by gravity.
+pub fn has_gravity(block: Block) -> bool {
+    match block {
+        Block::Sand | Block::RedSand | Block::Gravel | Block::DragonEgg => true,
+        block => is_anvil(block) || hardened(block).is_some(),
+    }
+}
+
+/// Returns whether `block` is an anvil of any damage stage.
+fn is_anvil(block: Block) -> bool {
+    match block {
+        Block::Anvil(_) | Block::ChippedAnvil(_) | Block::DamagedAnvil(_) => true,
+        _ => false,
+    }
+}
+
+/// Returns whether falling blocks fall through `block`.
+fn can_fall_through(block: Block) -> bool {
+    match block {
+        Block::Fire(_) => true,
+        block => is_replaceable(block),
+    }
+}
+
+/// Returns whether the block at `pos` has nothing
+/// to support it.
+fn is_unsupported(state: &DimensionState, pos: BlockPosition) -> bool {
+    pos.y > 0
+        && state
+            .block_at(Direction::Down.adjacent(pos))
+            .map_or(false, can_fall_through)
+}
+
+/// Returns the concrete which `block` hardens into,
+/// if it is concrete powder.
+fn hardened(block: Block) -> Option<Block> {
+    Some(match block {
+        Block::WhiteConcretePowder => Block::WhiteConcrete,
+        Block::OrangeConcretePowder => Block::OrangeConcrete,
+        Block::MagentaConcretePowder => Block::MagentaConcrete,
+        Block::LightBlueConcretePowder => Block::LightBlueConcrete,
+        Block::YellowConcretePowder => Block::YellowConcrete,
+        Block::LimeConcretePowder => Block::LimeConcrete,
+        Block::PinkConcretePowder => Block::PinkConcrete,
+        Block::GrayConcretePowder => Block::GrayConcrete,
+        Block::LightGrayConcretePowder => Block::LightGrayConcrete,
+        Block::CyanConcretePowder => Block::CyanConcrete,
+        Block::PurpleConcretePowder => Block::PurpleConcrete,
+        Block::BlueConcretePowder => Block::BlueConcrete,
+        Block::BrownConcretePowder => Block::BrownConcrete,
+        Block::GreenConcretePowder => Block::GreenConcrete,
+        Block::RedConcretePowder => Block::RedConcrete,
+        Block::BlackConcretePowder => Block::BlackConcrete,
+        _ => return None,
+    })
+}
+
+/// Returns whether the block at `pos` touches water
+/// on any side other than its bottom.
+fn touches_water(state: &DimensionState, pos: BlockPosition) -> bool {
+    Direction::UPDATE_ORDER
+        .iter()
+        .filter(|dir| **dir != Direction::Down)
+        .any(|dir| match state.block_at(dir.adjacent(pos)) {
+            Some(Block::Water(_)) => true,
+            _ => false,
+        })
+}
+
+/// Returns the next damage stage of an anvil,
+/// or `None` if the anvil breaks.
+fn damage_anvil(block: Block) -> Option<Block> {
+    // Each damage stage has its own facing type,
+    // so the facing is carried over by property.
+    match block {
+        Block::Anvil(data) => Some(Block::ChippedAnvil(
+            ChippedAnvilData::from_map(&data.to_map()).unwrap_or_default(),
+        )),
+        Block::ChippedAnvil(data) => Some(Block::DamagedAnvil(
+            DamagedAnvilData::from_map(&data.to_map()).unwrap_or_default(),
+        )),
+        Block::DamagedAnvil(_) => None,
+        block => Some(block),
+    }
+}
+
+/// Returns the damage dealt by an anvil
+/// which fell `distance` blocks.
+fn anvil_damage(distance: i32) -> u32 {
+    let distance = (distance - 1).max(0) as u32;
+    (distance * ANVIL_DAMAGE_PER_BLOCK).min(MAX_ANVIL_DAMAGE)
+}
+
+/// Event handler which schedules gravity-affected blocks
+/// to fall when the block below them changes. Concrete
+/// powder next to water hardens instead.
+#[event_handler]
+fn falling_block_schedule(
+    events: &[BlockUpdateEvent],
+    state: &State,
+    ticks: &mut BlockTicks,
+    tick_count: &TickCount,
+) {
+    for event in events {
+        let state = &state.dimension(event.dimension);
+        for pos in &[event.pos, Direction::Up.adjacent(event.pos)] {
+            let block = match state.block_at(*pos) {
+                Some(block) if has_gravity(block) => block,
+                _ => continue,
+            };
+
+            if let Some(concrete) = hardened(block) {
+                if touches_water(state, *pos) {
+                    state.set_block_at(*pos, concrete, BlockUpdateCause::Fluid);
+                    continue;
+                }
+            }
+
+            if is_unsupported(state, *pos) {
+                schedule_block_tick(
+                    ticks,
+                    tick_count,
+                    event.dimension,
+                    *pos,
+                    block,
+                    FALL_DELAY,
+                    TickPriority::Normal,
+                );
+            }
+        }
+    }
+}
+
+/// Event handler which turns unsupported blocks into
+/// falling block entities.
+#[event_handler]
+fn falling_block_spawn(events: &[BlockTickEvent], state: &State, tick_count: &TickCount) {
+    for event in events {
+        let state = &state.dimension(event.dimension);
+        if !has_gravity(event.block) || !is_unsupported(state, event.pos) {
+            continue;
+        }
+
+        state.set_block_at(event.pos, Block::Air, BlockUpdateCause::FallingBlock);
+        create(state, event.pos, event.block, tick_count.0).build();
+    }
+}
+
+/// System which places falling blocks once they land.
+/// Anvils damage the living entities they land on.
+#[system]
+fn falling_block_land(
+    state: &State,
+    query: &mut Query<(Read<Position>, Read<DimensionId>, Read<FallingBlock>)>,
+    _query: &mut Query<(Read<Health>,)>,
+    world: &mut PreparedWorld,
+    tick_count: &TickCount,
+    rules: &WorldGameRules,
+    damage_events: &mut Trigger<EntityDamageEvent>,
+) {
+    let landed = SegQueue::new();
+
+    query.par_entities_for_each_immutable(world, |(entity, (pos, dimension, falling))| {
+        let in_water = hardened(falling.block).is_some()
+            && match state.dimension(*dimension).block_at(pos.block_pos()) {
+                Some(Block::Water(_)) => true,
+                _ => false,
+            };
+        let expired = tick_count.0 >= falling.spawned_at + MAX_FALL_TICKS;
+
+        if pos.on_ground || in_water || expired {
+            landed.push((entity, *pos, *dimension, *falling, expired));
+        }
+    });
+
+    while let Ok((entity, pos, dimension, falling, expired)) = landed.pop() {
+        let state = &state.dimension(dimension);
+        if !expired && is_anvil(falling.block) {
+            let damage = anvil_damage(falling.origin.y - pos.block_pos().y);
+            if damage > 0 {
+                let entities =
+                    nearby_entities(state.chunk_entities, world, pos, glm::vec3(0.8, 1.0, 0.8));
+                for entity in entities {
+                    if world.get_component::<Health>(entity).is_some() {
+                        damage_events.trigger(EntityDamageEvent {
+                            entity,
+                            damage,
+                            cause: DamageCause::FallingBlock,
+                        });
+                    }
+                }
+            }
+        }
+
+        land(
+            state,
+            tick_count,
+            rules.get(dimension.world),
+            entity,
+            pos,
+            falling,
+            expired,
+        );
+    }
+}
+
+/// Places a falling block where it landed, or drops
+/// it as an item if the position is obstructed or
+/// the block fell for too long without landing.
+fn land(
+    state: &DimensionState,
+    tick_count: &TickCount,
+    rules: &GameRules,
+    entity: Entity,
+    pos: Position,
+    falling: FallingBlock,
+    expired: bool,
+) {
+    state.delete_entity(entity);
+
+    let block_pos = pos.block_pos();
+    let existing = match state.block_at(block_pos) {
+        Some(existing) => existing,
+        None => return,
+    };
+
+    if expired || !can_fall_through(existing) {
+        if !rules.get_bool(GameRule::DoEntityDrops) {
+            return;
+        }
+        if let Some(item) = falling.block.to_item() {
+            item::create(state, pos, ItemStack::new(item, 1), tick_count.0).build();
+        }
+        return;
+    }
+
+    let in_water = match existing {
+        Block::Water(_) => true,
+        _ => false,
+    };
+
+    let block = match hardened(falling.block) {
+        Some(concrete) if in_water || touches_water(state, block_pos) => Some(concrete),
+        _ => {
+            // Anvils are damaged with a chance
+            // increasing with the distance fallen.
+            let distance = (falling.origin.y - block_pos.y).max(0) as f64;
+            let chance = 0.05 + distance * 0.05;
+            if distance > 1.0 && rand::thread_rng().gen_bool(chance.min(1.0)) {
+                damage_anvil(falling.block)
+            } else {
+                Some(falling.block)
+            }
+        }
+    };
+
+    if let Some(block) = block {
+        state.set_block_at(block_pos, block, BlockUpdateCause::FallingBlock);
+    }
+}
+
+/// Returns an entity builder to create a
+/// falling block entity for `block`, which
+/// starts falling from `origin`.
+pub fn create(
+    state: &DimensionState,
+    origin: BlockPosition,
+    block: Block,
+    spawned_at: u64,
+) -> EntityBuilder {
+    let meta = {
+        let mut meta = crate::metadata::FallingBlock::default();
+        meta.set_spawn_position(origin);
+        Metadata::FallingBlock(meta)
+    };
+
+    let pos = position!(
+        f64::from(origin.x) + 0.5,
+        f64::from(origin.y),
+        f64::from(origin.z) + 0.5,
+        0.0,
+        0.0,
+        false
+    );
+
+    entity::base(state, EntityKind::FallingBlock, pos)
+        .with_component(FallingBlock {
+            block,
+            origin,
+            spawned_at,
+        })
+        .with_component(SpawnPacketCreator(&create_spawn_packet))
+        .with_component(meta)
+        .with_component(
+            PhysicsBuilder::new()
+                .bbox(0.98, 0.98, 0.98)
+                .drag(0.98)
+                .gravity(-0.04)
+                .build(),
+        )
+}
+
+fn create_spawn_packet(accessor: &EntityAccessor, world: &PreparedWorld) -> Box<dyn Packet> {
+    let position = *accessor.get_component::<Position>(world).unwrap();
+    let velocity = *accessor.get_component::<Velocity>(world).unwrap();
+    let entity_id = accessor.get_component::<EntityId>(world).unwrap().0;
+    let falling = *accessor.get_component::<FallingBlock>(world).unwrap();
+
+    let (velocity_x, velocity_y, velocity_z) = protocol_velocity(velocity.0);
+
+    let packet = SpawnObject {
+        entity_id,
+        object_uuid: Uuid::new_v4(),
+        ty: 70, // Type 70 for falling block
+        x: position.x,
+        y: position.y,
+        z: position.z,
+        pitch: degrees_to_stops(position.pitch),
+        yaw: degrees_to_stops(position.yaw),
+        data: i32::from(falling.block.native_state_id()),
+        velocity_x,
+        velocity_y,
+        velocity_z,
+    };
+
+    Box::new(packet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::block_name;
+
+    fn block(name: &str) -> Block {
+        Block::from_name_and_default_props(&format!("minecraft:{}", name)).unwrap()
+    }
+
+    #[test]
+    fn test_has_gravity() {
+        assert!(has_gravity(Block::Sand));
+        assert!(has_gravity(Block::Gravel));
+        assert!(has_gravity(block("lime_concrete_powder")));
+        assert!(has_gravity(block("damaged_anvil")));
+        assert!(!has_gravity(Block::Stone));
+        assert!(!has_gravity(block("lime_concrete")));
+    }
+
+    #[test]
+    fn test_can_fall_through() {
+        assert!(can_fall_through(Block::Air));
+        assert!(can_fall_through(block("fire")));
+        assert!(!can_fall_through(Block::Stone));
+    }
+
+    #[test]
+    fn test_hardened() {
+        assert_eq!(
+            hardened(block("red_concrete_powder")),
+            Some(block("red_concrete"))
+        );
+        assert_eq!(hardened(Block::Sand), None);
+    }
+
+    #[test]
+    fn test_damage_anvil() {
+        let anvil = block("anvil").with_property("facing", "east").unwrap();
+        let chipped = damage_anvil(anvil).unwrap();
+        assert_eq!(block_name(chipped), "chipped_anvil");
+        assert_eq!(chipped.property("facing").unwrap(), "east");
+
+        let damaged = damage_anvil(chipped).unwrap();
+        assert_eq!(block_name(damaged), "damaged_anvil");
+        assert_eq!(damage_anvil(damaged), None);
+        assert_eq!(damage_anvil(Block::Sand), Some(Block::Sand));
+    }
+
+    #[test]
+    fn test_anvil_damage() {
+        assert_eq!(anvil_damage(1), 0);
+        assert_eq!(anvil_damage(2), 2);
+        assert_eq!(anvil_damage(11), 20);
+        assert_eq!(anvil_damage(100), MAX_ANVIL_DAMAGE);
+    }
+}
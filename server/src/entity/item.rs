@@ -1,13 +1,14 @@
 //! Handling of item entities.
 
-use crate::entity::{EntityId, EntityMoveEvent, SpawnPacketCreator, Velocity};
+use crate::entity::{EntityId, EntityKind, EntityMoveEvent, SpawnPacketCreator, Velocity};
 use crate::lazy::EntityBuilder;
 use crate::metadata::Metadata;
 use crate::p_inventory::{EntityInventory, InventoryUpdateEvent};
 use crate::physics::{nearby_entities, PhysicsBuilder};
 use crate::player::PLAYER_EYE_HEIGHT;
-use crate::state::State;
+use crate::state::{DimensionState, State};
 use crate::util::{degrees_to_stops, protocol_velocity};
+use crate::worlds::DimensionId;
 use crate::{entity, TickCount, TPS};
 use feather_core::inventory::SlotIndex;
 use feather_core::network::packet::implementation::SpawnObject;
@@ -61,10 +62,11 @@ pub struct IsRemoved(AtomicBool);
 pub fn item_spawn(
     event: &ItemDropEvent,
     state: &State,
-    _query: &mut Query<Read<Position>>,
+    _query: &mut Query<(Read<Position>, Read<DimensionId>)>,
     world: &mut PreparedWorld,
     tick: &TickCount,
 ) {
+    let dimension = *world.get_component::<DimensionId>(event.player).unwrap();
     let mut rng = rand::thread_rng();
 
     // Spawn item entity.
@@ -94,9 +96,14 @@ pub fn item_spawn(
         vel
     };
 
-    create(state, pos, event.stack.clone(), tick.0 + TPS)
-        .with_component(Velocity(velocity))
-        .build();
+    create(
+        &state.dimension(dimension),
+        pos,
+        event.stack.clone(),
+        tick.0 + TPS,
+    )
+    .with_component(Velocity(velocity))
+    .build();
 }
 
 /// System to add items to entity inventories.
@@ -110,6 +117,7 @@ pub fn item_collect(
         Write<ItemStack>,
         Write<EntityInventory>,
         Read<Position>,
+        Read<DimensionId>,
     )>,
     world: &mut PreparedWorld,
     inventory_updates: &mut Trigger<InventoryUpdateEvent>,
@@ -125,9 +133,14 @@ pub fn item_collect(
         }
 
         let pos = *world.get_component::<Position>(event.entity).unwrap();
+        let dimension = *world.get_component::<DimensionId>(event.entity).unwrap();
         // Find nearby items.
-        let nearby_entities =
-            nearby_entities(&state.chunk_entities, world, pos, glm::vec3(1.0, 0.5, 1.0));
+        let nearby_entities = nearby_entities(
+            state.dimension(dimension).chunk_entities,
+            world,
+            pos,
+            glm::vec3(1.0, 0.5, 1.0),
+        );
 
         for other in nearby_entities {
             if let Some(item_stack) = world.get_component::<ItemStack>(other).map(|item| *item) {
@@ -189,7 +202,7 @@ pub fn item_collect(
 /// Returns an entity builder to create an item entity
 /// with the given stack and collectable tick.
 pub fn create(
-    state: &State,
+    state: &DimensionState,
     pos: Position,
     stack: ItemStack,
     collectable_at: u64,
@@ -200,7 +213,7 @@ pub fn create(
         Metadata::Item(meta_item)
     };
 
-    entity::base(state, pos)
+    entity::base(state, EntityKind::Item, pos)
         .with_component(stack)
         .with_component(CollectableAt(collectable_at))
         .with_component(SpawnPacketCreator(&create_spawn_packet))
@@ -0,0 +1,437 @@
+//! Rideable minecarts.
+//!
+//! Minecarts are moved by the server. On rails, a minecart
+//! follows the track: slopes pull it downhill, powered rails
+//! accelerate or brake it and powered activator rails eject its
+//! passenger. Detector rails are pressed by minecarts; see
+//! `crate::redstone`. Off rails, minecarts fall and slide to a stop.
+
+use crate::block::direction::Direction;
+use crate::block::rail::{is_rail, RailShape};
+use crate::entity;
+use crate::entity::{
+    EntityDataCreator, EntityId, EntityKind, EntityMoveEvent, SpawnPacketCreator, Velocity,
+};
+use crate::lazy::EntityBuilder;
+use crate::metadata::Metadata;
+use crate::state::{DimensionState, State};
+use crate::util::{degrees_to_stops, protocol_velocity};
+use crate::vehicle::{dismount_all, Vehicle, VehicleKind};
+use crate::worlds::DimensionId;
+use crossbeam::queue::SegQueue;
+use feather_core::entity::{BaseEntityData, EntityData, MinecartEntityData};
+use feather_core::network::packet::implementation::SpawnObject;
+use feather_core::{Block, BlockExt, BlockPosition, Item, Packet, Position};
+use glm::DVec3;
+use legion::query::{Read, Write};
+use tonks::{EntityAccessor, PreparedWorld, Query, Trigger};
+use uuid::Uuid;
+
+/// The maximum speed of a minecart, in blocks per tick.
+pub const MAX_SPEED: f64 = 0.4;
+/// The height of a minecart above the rail it is on.
+const RAIL_HEIGHT: f64 = 0.0625;
+/// The acceleration of minecarts down slopes.
+const SLOPE_ACCELERATION: f64 = 0.0078125;
+/// The acceleration of minecarts on powered rails.
+const POWERED_RAIL_ACCELERATION: f64 = 0.06;
+/// The speed at which a powered rail starts a
+/// stationary minecart moving away from a block.
+const POWERED_RAIL_START_SPEED: f64 = 0.02;
+/// Downward acceleration of minecarts off rails.
+const GRAVITY: f64 = -0.04;
+
+/// Marker component for minecart entities.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Minecart;
+
+/// The result of moving a minecart for one tick.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Step {
+    pos: Position,
+    velocity: DVec3,
+    /// Whether the minecart is on a powered activator rail.
+    activated: bool,
+}
+
+/// Places a minecart on the rail at `pos`. Returns `false`
+/// if there is no rail at the position.
+pub fn place(state: &DimensionState, pos: BlockPosition) -> bool {
+    match state.block_at(pos) {
+        Some(block) if is_rail(block) => (),
+        _ => return false,
+    }
+
+    let pos = position!(
+        f64::from(pos.x) + 0.5,
+        f64::from(pos.y) + RAIL_HEIGHT,
+        f64::from(pos.z) + 0.5
+    );
+    create(state, pos).build();
+    true
+}
+
+/// System which moves minecarts.
+#[system]
+fn minecart_tick(
+    state: &State,
+    query: &mut Query<(
+        Read<Minecart>,
+        Read<Vehicle>,
+        Write<Position>,
+        Write<Velocity>,
+        Read<DimensionId>,
+    )>,
+    world: &mut PreparedWorld,
+    move_events: &mut Trigger<EntityMoveEvent>,
+) {
+    let moved = SegQueue::new();
+    let activated = SegQueue::new();
+
+    query.par_entities_for_each(
+        world,
+        |(entity, (_, vehicle, mut pos, mut velocity, dimension))| {
+            let state = state.dimension(*dimension);
+            let ridden = !vehicle.passengers.is_empty();
+            let step = match step(|pos| state.block_at(pos), *pos, velocity.0, ridden) {
+                Some(step) => step,
+                None => return, // Unloaded chunk
+            };
+
+            velocity.0 = step.velocity;
+            if step.activated && ridden {
+                activated.push(entity);
+            }
+            if step.pos != *pos {
+                *pos = step.pos;
+                moved.push(entity);
+            }
+        },
+    );
+
+    while let Ok(entity) = moved.pop() {
+        move_events.trigger(EntityMoveEvent { entity });
+    }
+    while let Ok(entity) = activated.pop() {
+        dismount_all(state, world, entity);
+    }
+}
+
+/// Returns the unit vector pointing toward `dir`.
+fn unit(dir: Direction) -> DVec3 {
+    let offset = dir.offset();
+    glm::vec3(
+        f64::from(offset.x),
+        f64::from(offset.y),
+        f64::from(offset.z),
+    )
+}
+
+/// Returns the rail a minecart at `pos` is on, if any.
+///
+/// The block below is checked as well, since a minecart
+/// moving down a slope is above the rail it is on.
+fn rail_at(
+    block_at: &impl Fn(BlockPosition) -> Option<Block>,
+    pos: Position,
+) -> Option<Option<(BlockPosition, Block)>> {
+    let feet = pos.block_pos();
+    for candidate in &[feet, Direction::Down.adjacent(feet)] {
+        let block = block_at(*candidate)?;
+        if is_rail(block) {
+            return Some(Some((*candidate, block)));
+        }
+    }
+    Some(None)
+}
+
+/// Computes the movement of a minecart at `pos` during one tick.
+///
+/// Returns `None` if the minecart is in an unloaded chunk.
+fn step(
+    block_at: impl Fn(BlockPosition) -> Option<Block>,
+    pos: Position,
+    velocity: DVec3,
+    ridden: bool,
+) -> Option<Step> {
+    match rail_at(&block_at, pos)? {
+        Some((rail_pos, rail)) => step_on_rail(&block_at, pos, velocity, ridden, rail_pos, rail),
+        None => step_off_rail(&block_at, pos, velocity),
+    }
+}
+
+fn step_on_rail(
+    block_at: &impl Fn(BlockPosition) -> Option<Block>,
+    pos: Position,
+    velocity: DVec3,
+    ridden: bool,
+    rail_pos: BlockPosition,
+    rail: Block,
+) -> Option<Step> {
+    let shape = RailShape::of(rail).unwrap_or(RailShape::NorthSouth);
+    let (from, to) = shape.exits();
+    let axis = (unit(to) - unit(from)).normalize();
+
+    // Keep the speed when turning onto the track.
+    let along = velocity.x * axis.x + velocity.z * axis.z;
+    let horizontal = (velocity.x * velocity.x + velocity.z * velocity.z).sqrt();
+    let mut speed = if along.abs() < 1.0e-9 {
+        0.0
+    } else {
+        horizontal * along.signum()
+    };
+
+    if let Some(rising) = shape.rising() {
+        speed -= SLOPE_ACCELERATION * glm::dot(&unit(rising), &axis);
+    }
+
+    let mut activated = false;
+    match rail {
+        Block::PoweredRail(data) if data.powered => {
+            if speed.abs() > 0.01 {
+                speed += POWERED_RAIL_ACCELERATION * speed.signum();
+            } else {
+                // Start moving away from a block at the end of the track.
+                let solid = |dir: Direction| {
+                    block_at(dir.adjacent(rail_pos)).map_or(false, |block| block.is_solid())
+                };
+                if solid(from) {
+                    speed = POWERED_RAIL_START_SPEED;
+                } else if solid(to) {
+                    speed = -POWERED_RAIL_START_SPEED;
+                }
+            }
+        }
+        Block::PoweredRail(_) => {
+            speed = if speed.abs() < 0.03 { 0.0 } else { speed * 0.5 };
+        }
+        Block::ActivatorRail(data) => activated = data.powered,
+        _ => (),
+    }
+
+    speed *= if ridden { 0.997 } else { 0.96 };
+    speed = speed.max(-MAX_SPEED).min(MAX_SPEED);
+
+    // Move along the track, keeping the minecart on the
+    // line between the two points where the rail connects.
+    let center = glm::vec3(
+        f64::from(rail_pos.x) + 0.5,
+        0.0,
+        f64::from(rail_pos.z) + 0.5,
+    );
+    let start = center + unit(from) * 0.5;
+    let moved = glm::vec3(pos.x, 0.0, pos.z) + axis * speed;
+    let along_track = glm::dot(&(moved - start), &axis);
+    let mut target = start + axis * along_track;
+
+    target.y = f64::from(rail_pos.y) + RAIL_HEIGHT;
+    if let Some(rising) = shape.rising() {
+        let height = glm::dot(&(target - center), &unit(rising)) + 0.5;
+        target.y += height.max(0.0).min(1.0);
+    }
+
+    let mut new_pos = pos;
+    new_pos.x = target.x;
+    new_pos.y = target.y;
+    new_pos.z = target.z;
+    new_pos.on_ground = true;
+
+    // Stop at blocks across the end of the track.
+    let blocked =
+        block_at(new_pos.block_pos()).map_or(true, |block| block.is_solid() && !is_rail(block));
+    if blocked {
+        new_pos = pos;
+        speed = 0.0;
+    }
+
+    Some(Step {
+        pos: new_pos,
+        velocity: axis * speed,
+        activated,
+    })
+}
+
+fn step_off_rail(
+    block_at: &impl Fn(BlockPosition) -> Option<Block>,
+    pos: Position,
+    velocity: DVec3,
+) -> Option<Step> {
+    let mut velocity = velocity;
+    let below = block_at((pos - glm::vec3(0.0, 0.01, 0.0)).block_pos())?;
+
+    if below.is_solid() {
+        velocity.y = velocity.y.max(0.0);
+        velocity.x *= 0.5;
+        velocity.z *= 0.5;
+    } else {
+        velocity.y += GRAVITY;
+        velocity.x *= 0.95;
+        velocity.z *= 0.95;
+    }
+
+    let is_solid = |pos: Position| block_at(pos.block_pos()).map_or(true, |block| block.is_solid());
+
+    let mut new_pos = pos + velocity;
+    if is_solid(new_pos) && velocity.y < 0.0 {
+        new_pos.y = new_pos.y.floor() + 1.0;
+        velocity.y = 0.0;
+    }
+    if is_solid(new_pos) {
+        new_pos.x = pos.x;
+        new_pos.z = pos.z;
+        velocity.x = 0.0;
+        velocity.z = 0.0;
+    }
+    new_pos.on_ground = velocity.y == 0.0;
+
+    if glm::length2(&velocity) < 1.0e-6 {
+        velocity = glm::vec3(0.0, 0.0, 0.0);
+    }
+
+    Some(Step {
+        pos: new_pos,
+        velocity,
+        activated: false,
+    })
+}
+
+/// Returns an entity builder to create a minecart.
+pub fn create(state: &DimensionState, pos: Position) -> EntityBuilder {
+    let meta = Metadata::Minecart(crate::metadata::Minecart::default());
+
+    entity::base(state, EntityKind::Minecart, pos)
+        .with_component(Minecart)
+        .with_component(Vehicle::new(VehicleKind::Minecart, Item::Minecart))
+        .with_component(SpawnPacketCreator(&create_spawn_packet))
+        .with_component(EntityDataCreator(&create_entity_data))
+        .with_component(meta)
+}
+
+/// Returns an entity builder to recreate a
+/// minecart from saved entity data.
+pub fn load(state: &DimensionState, data: &MinecartEntityData) -> Option<EntityBuilder> {
+    let pos = data.entity.read_position()?;
+    let velocity = data.entity.read_velocity()?;
+
+    Some(create(state, pos).with_component(Velocity(velocity)))
+}
+
+fn create_spawn_packet(accessor: &EntityAccessor, world: &PreparedWorld) -> Box<dyn Packet> {
+    let position = *accessor.get_component::<Position>(world).unwrap();
+    let velocity = *accessor.get_component::<Velocity>(world).unwrap();
+    let entity_id = accessor.get_component::<EntityId>(world).unwrap().0;
+
+    let (velocity_x, velocity_y, velocity_z) = protocol_velocity(velocity.0);
+
+    let packet = SpawnObject {
+        entity_id,
+        object_uuid: Uuid::new_v4(),
+        ty: 10, // Type 10 for minecarts
+        x: position.x,
+        y: position.y,
+        z: position.z,
+        pitch: degrees_to_stops(position.pitch),
+        yaw: degrees_to_stops(position.yaw),
+        data: 0, // Rideable minecart
+        velocity_x,
+        velocity_y,
+        velocity_z,
+    };
+
+    Box::new(packet)
+}
+
+fn create_entity_data(accessor: &EntityAccessor, world: &PreparedWorld) -> EntityData {
+    let position = *accessor.get_component::<Position>(world).unwrap();
+    let velocity = *accessor.get_component::<Velocity>(world).unwrap();
+
+    EntityData::Minecart(MinecartEntityData {
+        entity: BaseEntityData::new(position, velocity.0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::with_prop;
+    use hashbrown::HashMap;
+
+    fn rail(name: &str, shape: RailShape) -> Block {
+        let block = Block::from_name_and_default_props(&format!("minecraft:{}", name)).unwrap();
+        with_prop(block, "shape", shape.name())
+    }
+
+    /// Returns a block lookup for a world of air containing `blocks`.
+    fn world(blocks: Vec<(BlockPosition, Block)>) -> impl Fn(BlockPosition) -> Option<Block> {
+        let blocks: HashMap<_, _> = blocks.into_iter().collect();
+        move |pos| Some(blocks.get(&pos).copied().unwrap_or(Block::Air))
+    }
+
+    #[test]
+    fn test_follow_straight_rail() {
+        let block_at = world(vec![(
+            BlockPosition::new(0, 64, 0),
+            rail("rail", RailShape::EastWest),
+        )]);
+        let pos = position!(0.5, 64.0 + RAIL_HEIGHT, 0.5);
+
+        // Velocity across the track is turned along it.
+        let step = step(&block_at, pos, glm::vec3(0.1, 0.0, 0.05), false).unwrap();
+        assert!(step.velocity.x > 0.0);
+        assert!(step.velocity.z.abs() < 1.0e-9);
+        assert!((step.pos.z - 0.5).abs() < 1.0e-9);
+        assert!(step.pos.x > 0.5);
+    }
+
+    #[test]
+    fn test_slope() {
+        let block_at = world(vec![(
+            BlockPosition::new(0, 64, 0),
+            rail("rail", RailShape::AscendingEast),
+        )]);
+        let pos = position!(0.5, 64.5 + RAIL_HEIGHT, 0.5);
+
+        let step = step(&block_at, pos, glm::vec3(0.0, 0.0, 0.0), false).unwrap();
+        assert!(step.velocity.x < 0.0);
+        assert!(step.pos.y < pos.y);
+    }
+
+    #[test]
+    fn test_powered_rail() {
+        let powered = with_prop(rail("powered_rail", RailShape::NorthSouth), "powered", true);
+        let block_at = world(vec![(BlockPosition::new(0, 64, 0), powered)]);
+        let pos = position!(0.5, 64.0 + RAIL_HEIGHT, 0.5);
+
+        let step = step(&block_at, pos, glm::vec3(0.0, 0.0, 0.1), true).unwrap();
+        assert!(step.velocity.z > 0.1);
+
+        let unpowered = rail("powered_rail", RailShape::NorthSouth);
+        let block_at = world(vec![(BlockPosition::new(0, 64, 0), unpowered)]);
+        let step = super::step(&block_at, pos, glm::vec3(0.0, 0.0, 0.1), true).unwrap();
+        assert!(step.velocity.z < 0.1);
+    }
+
+    #[test]
+    fn test_activator_rail() {
+        let powered = with_prop(
+            rail("activator_rail", RailShape::NorthSouth),
+            "powered",
+            true,
+        );
+        let block_at = world(vec![(BlockPosition::new(0, 64, 0), powered)]);
+        let pos = position!(0.5, 64.0 + RAIL_HEIGHT, 0.5);
+
+        let step = step(&block_at, pos, glm::vec3(0.0, 0.0, 0.0), true).unwrap();
+        assert!(step.activated);
+    }
+
+    #[test]
+    fn test_off_rail() {
+        let block_at = world(vec![(BlockPosition::new(0, 63, 0), Block::Stone)]);
+        let pos = position!(0.5, 65.0, 0.5);
+
+        let step = step(&block_at, pos, glm::vec3(0.0, 0.0, 0.0), false).unwrap();
+        assert!(step.pos.y < pos.y);
+        assert!(step.velocity.y < 0.0);
+    }
+}
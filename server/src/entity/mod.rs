@@ -3,10 +3,18 @@
 //! block entities, monsters, etc. Player entities are handled in `crate::player`,
 //! not here.
 
+pub mod arrow;
+pub mod boat;
+pub mod falling_block;
 pub mod item;
+pub mod minecart;
+pub mod tnt;
 
+use crate::chunk_logic::ChunkLoadEvent;
 use crate::lazy::EntityBuilder;
-use crate::state::State;
+use crate::state::{DimensionState, State};
+use crate::worlds::DimensionId;
+use feather_core::entity::EntityData;
 use feather_core::{Packet, Position};
 use legion::prelude::Entity;
 use legion::query::{Read, Write};
@@ -41,6 +49,7 @@ pub struct EntitySendEvent {
 pub struct EntityDeleteEvent {
     pub entity: Entity,
     pub position: Option<Position>,
+    pub dimension: Option<DimensionId>,
     pub id: EntityId,
     pub uuid: Uuid,
 }
@@ -52,6 +61,19 @@ pub struct EntityMoveEvent {
     pub entity: Entity,
 }
 
+/// Event triggered when an entity is moved to another dimension,
+/// which may be in another world.
+///
+/// When this is triggered, the entity's `DimensionId` and `Position`
+/// components have already been updated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DimensionChangeEvent {
+    pub entity: Entity,
+    pub old_dimension: DimensionId,
+    pub old_position: Position,
+    pub new_dimension: DimensionId,
+}
+
 /// Event triggered when an entity's velocity changes.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct VelocityUpdateEvent {
@@ -89,6 +111,56 @@ impl DerefMut for Velocity {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Name(pub String);
 
+/// The type of an entity, such as `minecraft:boat`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntityKind {
+    Player,
+    Item,
+    Arrow,
+    Tnt,
+    Boat,
+    Minecart,
+    FallingBlock,
+}
+
+impl EntityKind {
+    pub const ALL: [EntityKind; 7] = [
+        EntityKind::Player,
+        EntityKind::Item,
+        EntityKind::Arrow,
+        EntityKind::Tnt,
+        EntityKind::Boat,
+        EntityKind::Minecart,
+        EntityKind::FallingBlock,
+    ];
+
+    /// Returns the namespaced identifier of this type.
+    pub fn identifier(self) -> &'static str {
+        match self {
+            EntityKind::Player => "minecraft:player",
+            EntityKind::Item => "minecraft:item",
+            EntityKind::Arrow => "minecraft:arrow",
+            EntityKind::Tnt => "minecraft:tnt",
+            EntityKind::Boat => "minecraft:boat",
+            EntityKind::Minecart => "minecraft:minecart",
+            EntityKind::FallingBlock => "minecraft:falling_block",
+        }
+    }
+
+    /// Returns the type with the given namespaced identifier.
+    pub fn from_identifier(identifier: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|kind| kind.identifier() == identifier)
+    }
+}
+
+/// Zero-sized component used to mark hostile mobs,
+/// which prevent nearby players from sleeping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Monster;
+
 /// Position of an entity on the last tick.
 ///
 /// This is updated by `position_reset` system.
@@ -142,6 +214,51 @@ impl CreationPacketCreator {
     }
 }
 
+pub trait EntityDataCreatorFn:
+    Fn(&EntityAccessor, &PreparedWorld) -> EntityData + Send + Sync + 'static
+{
+}
+impl<F> EntityDataCreatorFn for F where
+    F: Fn(&EntityAccessor, &PreparedWorld) -> EntityData + Send + Sync + 'static
+{
+}
+
+/// Component which defines a function returning the data
+/// to save for the entity when its chunk is unloaded.
+///
+/// Entities without this component are not saved, and
+/// entities with it are removed when their chunk unloads.
+pub struct EntityDataCreator(pub &'static dyn EntityDataCreatorFn);
+
+impl EntityDataCreator {
+    /// Returns the data to save for the entity.
+    pub fn get(&self, accessor: &EntityAccessor, world: &PreparedWorld) -> EntityData {
+        let f = self.0;
+
+        f(accessor, world)
+    }
+}
+
+/// Event handler which recreates the entities
+/// saved in a chunk when it is loaded.
+#[event_handler]
+fn entity_load(event: &ChunkLoadEvent, state: &State) {
+    let state = state.dimension(event.dimension);
+    for data in &event.entities {
+        let builder = match data {
+            EntityData::Arrow(data) => arrow::load(&state, data),
+            EntityData::PrimedTnt(data) => tnt::load(&state, data),
+            EntityData::Boat(data) => boat::load(&state, data),
+            EntityData::Minecart(data) => minecart::load(&state, data),
+            _ => None,
+        };
+
+        if let Some(builder) = builder {
+            builder.build();
+        }
+    }
+}
+
 #[event_handler]
 pub fn position_reset(
     events: &[EntityMoveEvent],
@@ -163,14 +280,18 @@ pub fn position_reset(
 /// This currently includes:
 /// * Velocity (0)
 /// * Entity ID
+/// * EntityKind
 /// * Position and previous position
+/// * DimensionId
 /// * Triggers `EntityCreateEvent`
-pub fn base(state: &State, position: Position) -> EntityBuilder {
+pub fn base(state: &DimensionState, kind: EntityKind, position: Position) -> EntityBuilder {
     let id = ENTITY_ID_COUNTER.fetch_add(1, Ordering::Relaxed);
     state
         .create_entity()
         .with_component(EntityId(id))
+        .with_component(kind)
         .with_component(position)
+        .with_component(state.dimension)
         .with_component(PreviousPosition(position))
         .with_component(Velocity::default())
         .with_exec(|_, scheduler, entity| scheduler.trigger(EntityCreateEvent { entity }))
@@ -0,0 +1,186 @@
+//! Primed TNT entities.
+//!
+//! TNT blocks are primed by redstone power, fire, flint and
+//! steel or other explosions. Priming replaces the block with
+//! a TNT entity which explodes once its fuse runs out.
+
+use crate::block::direction::Direction;
+use crate::block::{BlockUpdateCause, BlockUpdateEvent};
+use crate::entity;
+use crate::entity::{EntityDataCreator, EntityId, EntityKind, SpawnPacketCreator, Velocity};
+use crate::explosion::{ExplosionEvent, TNT_POWER};
+use crate::lazy::EntityBuilder;
+use crate::metadata::Metadata;
+use crate::physics::PhysicsBuilder;
+use crate::state::{DimensionState, State};
+use crate::util::{degrees_to_stops, protocol_velocity};
+use crate::worlds::DimensionId;
+use crossbeam::queue::SegQueue;
+use feather_core::entity::{BaseEntityData, EntityData, PrimedTntEntityData};
+use feather_core::network::packet::implementation::SpawnObject;
+use feather_core::{Block, BlockPosition, Packet, Position};
+use legion::query::{Read, Write};
+use rand::Rng;
+use tonks::{EntityAccessor, PreparedWorld, Query, Trigger};
+use uuid::Uuid;
+
+/// The number of ticks before primed TNT explodes.
+pub const DEFAULT_FUSE: u64 = 80;
+
+/// Component for primed TNT entities.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrimedTnt {
+    /// The number of ticks until the TNT explodes.
+    pub fuse: u64,
+}
+
+/// Replaces the TNT block at `pos` with a primed
+/// TNT entity which explodes after `fuse` ticks.
+pub fn prime(state: &DimensionState, pos: BlockPosition, fuse: u64, cause: BlockUpdateCause) {
+    state.set_block_at(pos, Block::Air, cause);
+
+    let pos = position!(
+        f64::from(pos.x) + 0.5,
+        f64::from(pos.y),
+        f64::from(pos.z) + 0.5
+    );
+
+    // Primed TNT jumps up and in a random direction.
+    let angle = rand::thread_rng().gen_range(0.0, std::f64::consts::PI * 2.0);
+    let velocity = glm::vec3(-angle.sin() * 0.02, 0.2, -angle.cos() * 0.02);
+
+    create(state, pos, fuse)
+        .with_component(Velocity(velocity))
+        .build();
+}
+
+/// System which counts down the fuses of primed
+/// TNT entities and makes them explode.
+#[system]
+fn tnt_tick(
+    state: &State,
+    query: &mut Query<(Read<Position>, Read<DimensionId>, Write<PrimedTnt>)>,
+    world: &mut PreparedWorld,
+    explosions: &mut Trigger<ExplosionEvent>,
+) {
+    let exploded = SegQueue::new();
+
+    query.par_entities_for_each(world, |(entity, (pos, dimension, mut tnt))| {
+        if tnt.fuse > 0 {
+            tnt.fuse -= 1;
+        } else {
+            exploded.push((entity, *pos, *dimension));
+        }
+    });
+
+    while let Ok((entity, pos, dimension)) = exploded.pop() {
+        state.delete_entity(entity);
+        explosions.trigger(ExplosionEvent {
+            dimension,
+            pos: pos + glm::vec3(0.0, 0.06125, 0.0),
+            power: TNT_POWER,
+            source: Some(entity),
+            fire: false,
+        });
+    }
+}
+
+/// Event handler which primes TNT touching fire.
+#[event_handler]
+fn tnt_ignite(events: &[BlockUpdateEvent], state: &State) {
+    let is_fire = |block: Option<Block>| match block {
+        Some(Block::Fire(_)) => true,
+        _ => false,
+    };
+
+    for event in events {
+        let state = &state.dimension(event.dimension);
+        match event.new_block {
+            Block::Fire(_) => {
+                for dir in &Direction::UPDATE_ORDER {
+                    let neighbor = dir.adjacent(event.pos);
+                    if let Some(Block::Tnt(_)) = state.block_at(neighbor) {
+                        prime(state, neighbor, DEFAULT_FUSE, BlockUpdateCause::Neighbor);
+                    }
+                }
+            }
+            Block::Tnt(_) => {
+                if Direction::UPDATE_ORDER
+                    .iter()
+                    .any(|dir| is_fire(state.block_at(dir.adjacent(event.pos))))
+                {
+                    prime(state, event.pos, DEFAULT_FUSE, BlockUpdateCause::Neighbor);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Returns an entity builder to create a primed
+/// TNT entity which explodes after `fuse` ticks.
+pub fn create(state: &DimensionState, pos: Position, fuse: u64) -> EntityBuilder {
+    let meta = {
+        let mut meta = crate::metadata::PrimedTnt::default();
+        meta.set_fuse_time(fuse as i32);
+        Metadata::PrimedTnt(meta)
+    };
+
+    entity::base(state, EntityKind::Tnt, pos)
+        .with_component(PrimedTnt { fuse })
+        .with_component(SpawnPacketCreator(&create_spawn_packet))
+        .with_component(EntityDataCreator(&create_entity_data))
+        .with_component(meta)
+        .with_component(
+            PhysicsBuilder::new()
+                .bbox(0.98, 0.98, 0.98)
+                .drag(0.98)
+                .gravity(-0.04)
+                .build(),
+        )
+}
+
+/// Returns an entity builder to recreate primed
+/// TNT from saved entity data.
+pub fn load(state: &DimensionState, data: &PrimedTntEntityData) -> Option<EntityBuilder> {
+    let pos = data.entity.read_position()?;
+    let velocity = data.entity.read_velocity()?;
+
+    Some(create(state, pos, data.fuse.max(0) as u64).with_component(Velocity(velocity)))
+}
+
+fn create_spawn_packet(accessor: &EntityAccessor, world: &PreparedWorld) -> Box<dyn Packet> {
+    let position = *accessor.get_component::<Position>(world).unwrap();
+    let velocity = *accessor.get_component::<Velocity>(world).unwrap();
+    let entity_id = accessor.get_component::<EntityId>(world).unwrap().0;
+
+    let (velocity_x, velocity_y, velocity_z) = protocol_velocity(velocity.0);
+
+    let packet = SpawnObject {
+        entity_id,
+        object_uuid: Uuid::new_v4(),
+        ty: 50, // Type 50 for primed TNT
+        x: position.x,
+        y: position.y,
+        z: position.z,
+        pitch: degrees_to_stops(position.pitch),
+        yaw: degrees_to_stops(position.yaw),
+        data: 1, // Has velocity
+        velocity_x,
+        velocity_y,
+        velocity_z,
+    };
+
+    Box::new(packet)
+}
+
+fn create_entity_data(accessor: &EntityAccessor, world: &PreparedWorld) -> EntityData {
+    let position = *accessor.get_component::<Position>(world).unwrap();
+    let velocity = *accessor.get_component::<Velocity>(world).unwrap();
+    let tnt = *accessor.get_component::<PrimedTnt>(world).unwrap();
+
+    EntityData::PrimedTnt(PrimedTntEntityData {
+        entity: BaseEntityData::new(position, velocity.0),
+        fuse: tnt.fuse as i16,
+    })
+}
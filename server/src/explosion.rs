@@ -0,0 +1,375 @@
+//! Explosions.
+//!
+//! The blocks destroyed by an explosion are found by casting rays
+//! outward from its center. Each ray starts with an intensity
+//! proportional to the explosion's power and loses some of it for
+//! every step it travels and for every block it passes through,
+//! according to the block's blast resistance. Blocks are destroyed
+//! wherever a ray still has intensity left.
+//!
+//! Entities nearby are damaged and knocked back depending on their
+//! distance from the explosion and on how exposed they are to it.
+
+use crate::block::direction::Direction;
+use crate::block::BlockUpdateCause;
+use crate::chunk_logic::ChunkHolders;
+use crate::difficulty::WorldDifficulty;
+use crate::entity::{item, tnt, Velocity, VelocityUpdateEvent};
+use crate::game_rules::{GameRule, WorldGameRules};
+use crate::health::{DamageCause, EntityDamageEvent, Health};
+use crate::network::Network;
+use crate::physics::{block_impacted_by_ray, nearby_entities, AABBExt, Physics};
+use crate::player::{Player, PLAYER_EYE_HEIGHT};
+use crate::state::{DimensionState, State};
+use crate::worlds::DimensionId;
+use crate::TickCount;
+use feather_blocks::FireData;
+use feather_core::network::packet::implementation::Explosion;
+use feather_core::{Block, BlockExt, BlockPosition, ItemStack, Position};
+use feather_item_block::BlockToItem;
+use glm::DVec3;
+use hashbrown::{HashMap, HashSet};
+use legion::entity::Entity;
+use legion::query::{Read, Write};
+use rand::Rng;
+use tonks::{PreparedWorld, Query, Trigger};
+
+/// The number of rays cast along each edge
+/// of the cube around an explosion.
+const RAY_GRID: i32 = 16;
+/// The distance a ray travels in each step.
+const RAY_STEP: f32 = 0.3;
+/// The power of a TNT explosion.
+pub const TNT_POWER: f32 = 4.0;
+
+/// Event which causes an explosion.
+#[derive(Debug, Clone)]
+pub struct ExplosionEvent {
+    /// The dimension of the explosion.
+    pub dimension: DimensionId,
+    /// The center of the explosion.
+    pub pos: Position,
+    /// The power of the explosion, which is
+    /// roughly its radius in blocks.
+    pub power: f32,
+    /// The entity which exploded, if any.
+    pub source: Option<Entity>,
+    /// Whether the explosion sets fire to the
+    /// blocks it destroys.
+    pub fire: bool,
+}
+
+/// Returns the directions of the rays cast by an explosion:
+/// one through each point on the surface of a cube.
+fn ray_directions() -> Vec<DVec3> {
+    let max = RAY_GRID - 1;
+    let mut directions = vec![];
+
+    for x in 0..RAY_GRID {
+        for y in 0..RAY_GRID {
+            for z in 0..RAY_GRID {
+                if x != 0 && x != max && y != 0 && y != max && z != 0 && z != max {
+                    continue;
+                }
+
+                let direction = glm::vec3(
+                    f64::from(x) / f64::from(max) * 2.0 - 1.0,
+                    f64::from(y) / f64::from(max) * 2.0 - 1.0,
+                    f64::from(z) / f64::from(max) * 2.0 - 1.0,
+                );
+                directions.push(direction.normalize());
+            }
+        }
+    }
+
+    directions
+}
+
+/// Returns the positions of the blocks destroyed by an
+/// explosion with the given center and power.
+pub fn affected_blocks(state: &DimensionState, center: DVec3, power: f32) -> Vec<BlockPosition> {
+    let mut rng = rand::thread_rng();
+    let mut seen = HashSet::new();
+    let mut blocks = vec![];
+
+    for direction in ray_directions() {
+        let mut intensity = power * rng.gen_range(0.7, 1.3);
+        let mut pos = center;
+
+        while intensity > 0.0 {
+            let block_pos = Position::from(pos).block_pos();
+            let block = match state.block_at(block_pos) {
+                Some(block) => block,
+                None => break,
+            };
+
+            let is_air = match block {
+                Block::Air | Block::CaveAir | Block::VoidAir => true,
+                _ => false,
+            };
+
+            if !is_air {
+                intensity -= (block.blast_resistance() + RAY_STEP) * RAY_STEP;
+            }
+
+            if intensity > 0.0 && !is_air && seen.insert(block_pos) {
+                blocks.push(block_pos);
+            }
+
+            pos += direction * f64::from(RAY_STEP);
+            intensity -= RAY_STEP * 0.75;
+        }
+    }
+
+    blocks
+}
+
+/// Returns the fraction, from 0 to 1, of an entity's
+/// bounding box which can be seen from `center`.
+fn exposure(state: &DimensionState, center: DVec3, pos: Position, size: DVec3) -> f64 {
+    let mut visible = 0;
+    let mut total = 0;
+
+    for x in 0..=2 {
+        for y in 0..=2 {
+            for z in 0..=2 {
+                let point = glm::vec3(
+                    pos.x - size.x / 2.0 + size.x * f64::from(x) / 2.0,
+                    pos.y + size.y * f64::from(y) / 2.0,
+                    pos.z - size.z / 2.0 + size.z * f64::from(z) / 2.0,
+                );
+
+                let ray = center - point;
+                if block_impacted_by_ray(state, point, ray, glm::length2(&ray)).is_none() {
+                    visible += 1;
+                }
+                total += 1;
+            }
+        }
+    }
+
+    f64::from(visible) / f64::from(total)
+}
+
+/// Returns the damage dealt by an explosion with the given
+/// power to an entity with the given impact, which is
+/// between 0 and 1.
+fn explosion_damage(impact: f64, power: f32) -> u32 {
+    ((impact * impact + impact) / 2.0 * 7.0 * f64::from(power) * 2.0 + 1.0) as u32
+}
+
+/// Event handler which performs explosions.
+#[event_handler]
+fn explosion_handle(
+    events: &[ExplosionEvent],
+    state: &State,
+    tick_count: &TickCount,
+    holders: &ChunkHolders,
+    rules: &WorldGameRules,
+    difficulty: &WorldDifficulty,
+    _query: &mut Query<(
+        Read<Position>,
+        Write<Velocity>,
+        Read<Physics>,
+        Read<Player>,
+        Read<Health>,
+        Read<ItemStack>,
+        Read<Network>,
+    )>,
+    world: &mut PreparedWorld,
+    velocity_events: &mut Trigger<VelocityUpdateEvent>,
+    damage_events: &mut Trigger<EntityDamageEvent>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for event in events {
+        let state = &state.dimension(event.dimension);
+        let rules = rules.get(event.dimension.world);
+        let difficulty = difficulty.get(event.dimension.world);
+        let center: DVec3 = event.pos.into();
+        let blocks = affected_blocks(state, center, event.power);
+
+        // Damage and knock back entities.
+        let radius = f64::from(event.power) * 2.0;
+        let mut player_motion = HashMap::new();
+
+        let entities = nearby_entities(
+            state.chunk_entities,
+            world,
+            event.pos,
+            glm::vec3(radius, radius, radius),
+        );
+        for entity in entities {
+            if Some(entity) == event.source {
+                continue;
+            }
+
+            let pos = *world.get_component::<Position>(entity).unwrap();
+            let distance = (Into::<DVec3>::into(pos) - center).magnitude() / radius;
+            if distance > 1.0 {
+                continue;
+            }
+
+            let is_player = world.get_component::<Player>(entity).is_some();
+            let (size, eye_height) = if is_player {
+                (glm::vec3(0.6, 1.8, 0.6), PLAYER_EYE_HEIGHT)
+            } else {
+                let size = world
+                    .get_component::<Physics>(entity)
+                    .map_or_else(|| glm::vec3(0.5, 0.5, 0.5), |physics| physics.bbox.size());
+                (size, 0.0)
+            };
+
+            let direction = Into::<DVec3>::into(pos) + glm::vec3(0.0, eye_height, 0.0) - center;
+            if direction.magnitude() < std::f64::EPSILON {
+                continue;
+            }
+
+            let impact = (1.0 - distance) * exposure(state, center, pos, size);
+            let knockback = direction.normalize() * impact;
+
+            if world.get_component::<Health>(entity).is_some() {
+                let mut damage = explosion_damage(impact, event.power);
+                if is_player {
+                    damage = difficulty.scale_damage(damage);
+                }
+                damage_events.trigger(EntityDamageEvent {
+                    entity,
+                    damage,
+                    cause: DamageCause::Explosion {
+                        source: event.source,
+                    },
+                });
+            }
+
+            if is_player {
+                // Players are knocked back by the client
+                // using the motion in the Explosion packet.
+                player_motion.insert(entity, knockback);
+            } else if world.get_component::<ItemStack>(entity).is_some() {
+                // Items are destroyed by explosions.
+                state.delete_entity(entity);
+            } else if let Some(mut velocity) = world.get_component_mut::<Velocity>(entity) {
+                velocity.0 += knockback;
+                velocity_events.trigger(VelocityUpdateEvent { entity });
+            }
+        }
+
+        // Destroy blocks. Each block drops with
+        // a chance inversely proportional to the power.
+        let drop_chance = if rules.get_bool(GameRule::DoTileDrops) {
+            f64::from(1.0 / event.power).min(1.0)
+        } else {
+            0.0
+        };
+        for pos in &blocks {
+            let block = match state.block_at(*pos) {
+                Some(block) => block,
+                None => continue,
+            };
+
+            if let Block::Tnt(_) = block {
+                // Chain reaction: TNT hit by an explosion
+                // is primed with a shorter fuse.
+                let fuse = rng.gen_range(tnt::DEFAULT_FUSE / 8, tnt::DEFAULT_FUSE * 3 / 8);
+                tnt::prime(state, *pos, fuse, BlockUpdateCause::Explosion);
+                continue;
+            }
+
+            if rng.gen_bool(drop_chance) {
+                if let Some(item) = block.to_item() {
+                    let drop_pos = position!(
+                        f64::from(pos.x) + 0.5,
+                        f64::from(pos.y) + 0.5,
+                        f64::from(pos.z) + 0.5
+                    );
+                    item::create(state, drop_pos, ItemStack::new(item, 1), tick_count.0 + 10)
+                        .build();
+                }
+            }
+
+            state.set_block_at(*pos, Block::Air, BlockUpdateCause::Explosion);
+        }
+
+        // Fiery explosions, such as those of beds in the Nether,
+        // set fire to some of the blocks they destroyed.
+        if event.fire {
+            for pos in &blocks {
+                let below = state.block_at(Direction::Down.adjacent(*pos));
+                if state.block_at(*pos) == Some(Block::Air)
+                    && below.map_or(false, |block| block.is_opaque())
+                    && rng.gen_range(0, 3) == 0
+                {
+                    state.set_block_at(
+                        *pos,
+                        Block::Fire(FireData::default()),
+                        BlockUpdateCause::Explosion,
+                    );
+                }
+            }
+        }
+
+        // Send the explosion to players who can see it.
+        let center_block = event.pos.block_pos();
+        let records: Vec<(i8, i8, i8)> = blocks
+            .iter()
+            .map(|pos| {
+                (
+                    (pos.x - center_block.x) as i8,
+                    (pos.y - center_block.y) as i8,
+                    (pos.z - center_block.z) as i8,
+                )
+            })
+            .collect();
+
+        for player in holders
+            .holders_for(event.dimension, event.pos.chunk_pos())
+            .unwrap_or(&[])
+        {
+            let network = match world.get_component::<Network>(*player) {
+                Some(network) => network,
+                None => continue,
+            };
+            let motion = player_motion
+                .get(player)
+                .copied()
+                .unwrap_or_else(|| glm::vec3(0.0, 0.0, 0.0));
+
+            network.send(Explosion {
+                x: event.pos.x as f32,
+                y: event.pos.y as f32,
+                z: event.pos.z as f32,
+                radius: event.power,
+                records: records.clone(),
+                player_motion_x: motion.x as f32,
+                player_motion_y: motion.y as f32,
+                player_motion_z: motion.z as f32,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ray_directions() {
+        let directions = ray_directions();
+        // The number of points on the surface of a 16x16x16 cube.
+        assert_eq!(directions.len(), 16 * 16 * 16 - 14 * 14 * 14);
+
+        for direction in directions {
+            assert!((direction.magnitude() - 1.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_explosion_damage() {
+        // Full impact from TNT.
+        assert_eq!(explosion_damage(1.0, TNT_POWER), 57);
+        // No impact still deals one damage.
+        assert_eq!(explosion_damage(0.0, TNT_POWER), 1);
+        assert!(explosion_damage(0.5, TNT_POWER) < explosion_damage(0.6, TNT_POWER));
+    }
+}
@@ -0,0 +1,493 @@
+//! Fluid simulation for water and lava.
+//!
+//! Fluids are driven by scheduled ticks: whenever a block
+//! next to a fluid changes, the fluid is queued in `FluidTicks`
+//! to be ticked after a delay which depends on the fluid type.
+//! When ticked, a fluid recomputes its level from its neighbors
+//! and then flows downward or spreads out horizontally.
+//!
+//! Pending ticks are saved with their chunk in the `LiquidsToBeTicked`
+//! tag and rescheduled when the chunk is loaded again.
+
+use crate::block::tick::{DimensionTicks, TickPriority};
+use crate::block::{BlockUpdateCause, BlockUpdateEvent};
+use crate::chunk_logic::ChunkLoadEvent;
+use crate::state::{DimensionState, State};
+use crate::TickCount;
+use feather_blocks::{LavaData, WaterData};
+use feather_core::{Block, BlockExt, BlockPosition};
+use smallvec::SmallVec;
+
+/// Offset to the block above a position.
+const UP: BlockPosition = BlockPosition::new(0, 1, 0);
+/// Offset to the block below a position.
+const DOWN: BlockPosition = BlockPosition::new(0, -1, 0);
+/// Offsets to horizontally adjacent blocks. Opposite
+/// directions are stored next to each other, so the
+/// opposite of index `i` is `i ^ 1`.
+const HORIZONTAL: [BlockPosition; 4] = [
+    BlockPosition::new(0, 0, -1),
+    BlockPosition::new(0, 0, 1),
+    BlockPosition::new(-1, 0, 0),
+    BlockPosition::new(1, 0, 0),
+];
+
+/// Level value which indicates a falling fluid.
+const FALLING: i32 = 8;
+
+/// A kind of fluid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fluid {
+    Water,
+    Lava,
+}
+
+impl Fluid {
+    /// Returns the fluid and level of the given block,
+    /// or `None` if it is not a fluid.
+    pub fn from_block(block: Block) -> Option<(Fluid, i32)> {
+        match block {
+            Block::Water(data) => Some((Fluid::Water, data.level)),
+            Block::Lava(data) => Some((Fluid::Lava, data.level)),
+            _ => None,
+        }
+    }
+
+    /// Returns the block for this fluid with the given level.
+    pub fn block(self, level: i32) -> Block {
+        match self {
+            Fluid::Water => Block::Water(WaterData { level }),
+            Fluid::Lava => Block::Lava(LavaData { level }),
+        }
+    }
+
+    /// Returns the number of ticks between
+    /// a fluid being updated and it flowing.
+    pub fn tick_delay(self) -> u64 {
+        match self {
+            Fluid::Water => 5,
+            Fluid::Lava => 30,
+        }
+    }
+
+    /// Returns the amount by which the level
+    /// increases for each block the fluid spreads.
+    fn level_drop(self) -> i32 {
+        match self {
+            Fluid::Water => 1,
+            Fluid::Lava => 2,
+        }
+    }
+
+    /// Returns the maximum distance searched
+    /// when looking for a hole to flow toward.
+    fn slope_find_distance(self) -> i32 {
+        match self {
+            Fluid::Water => 4,
+            Fluid::Lava => 2,
+        }
+    }
+}
+
+/// The queues of scheduled fluid ticks.
+#[derive(Debug, Default, Resource, Deref, DerefMut)]
+pub struct FluidTicks(pub DimensionTicks<()>);
+
+/// Event handler which schedules ticks for any fluids
+/// at or next to an updated block.
+#[event_handler]
+fn fluid_schedule_on_update(
+    events: &[BlockUpdateEvent],
+    state: &State,
+    ticks: &mut FluidTicks,
+    tick_count: &TickCount,
+) {
+    for event in events {
+        let state = state.dimension(event.dimension);
+        let neighbors = HORIZONTAL.iter().chain(&[UP, DOWN]).map(|o| event.pos + *o);
+
+        for pos in std::iter::once(event.pos).chain(neighbors) {
+            schedule_if_fluid(&state, ticks, tick_count, pos);
+        }
+    }
+}
+
+/// Event handler which reschedules fluid ticks
+/// which were pending when a chunk was saved.
+#[event_handler]
+fn fluid_schedule_on_chunk_load(
+    event: &ChunkLoadEvent,
+    state: &State,
+    ticks: &mut FluidTicks,
+    tick_count: &TickCount,
+) {
+    let state = state.dimension(event.dimension);
+    for pos in &event.ticks.liquids {
+        schedule_if_fluid(&state, ticks, tick_count, *pos);
+    }
+}
+
+/// System which runs fluid ticks which are due.
+#[system]
+fn fluid_tick(state: &State, ticks: &mut FluidTicks, tick_count: &TickCount) {
+    for (dimension, tick) in ticks.drain_due(tick_count.0) {
+        tick_fluid(&state.dimension(dimension), tick.pos);
+    }
+}
+
+/// Schedules a tick for the block at `pos` if it is a fluid.
+fn schedule_if_fluid(
+    state: &DimensionState,
+    ticks: &mut FluidTicks,
+    tick_count: &TickCount,
+    pos: BlockPosition,
+) {
+    let fluid = match state.block_at(pos).and_then(Fluid::from_block) {
+        Some((fluid, _)) => fluid,
+        None => return,
+    };
+
+    // Lava hardens as soon as it touches water, rather
+    // than waiting for its (rather long) flow delay.
+    let delay = if fluid == Fluid::Lava && touches_water(state, pos) {
+        1
+    } else {
+        fluid.tick_delay()
+    };
+
+    ticks
+        .queue(state.dimension)
+        .schedule(pos, tick_count.0 + delay, TickPriority::Normal, ());
+}
+
+/// Runs a fluid tick for the block at the given position.
+fn tick_fluid(state: &DimensionState, pos: BlockPosition) {
+    let (fluid, mut level) = match state.block_at(pos).and_then(Fluid::from_block) {
+        Some(fluid) => fluid,
+        None => return, // Fluid was removed since the tick was scheduled
+    };
+
+    if fluid == Fluid::Lava && touches_water(state, pos) {
+        let hardened = if level == 0 {
+            Block::Obsidian
+        } else {
+            Block::Cobblestone
+        };
+        state.set_block_at(pos, hardened, BlockUpdateCause::Fluid);
+        return;
+    }
+
+    // Sources keep their level; other fluids are fed by their neighbors.
+    if level != 0 {
+        let mut horizontal = [None; 4];
+        for (block, offset) in horizontal.iter_mut().zip(&HORIZONTAL) {
+            *block = state.block_at(pos + *offset);
+        }
+
+        match flowing_level(
+            fluid,
+            state.block_at(pos + UP),
+            &horizontal,
+            state.block_at(pos + DOWN),
+        ) {
+            Some(new_level) => {
+                if new_level != level {
+                    state.set_block_at(pos, fluid.block(new_level), BlockUpdateCause::Fluid);
+                    level = new_level;
+                }
+            }
+            None => {
+                state.set_block_at(pos, Block::Air, BlockUpdateCause::Fluid);
+                return;
+            }
+        }
+    }
+
+    let below_pos = pos + DOWN;
+    let below = match state.block_at(below_pos) {
+        Some(below) => below,
+        None => return,
+    };
+
+    if fluid == Fluid::Lava {
+        if let Some((Fluid::Water, _)) = Fluid::from_block(below) {
+            state.set_block_at(below_pos, Block::Stone, BlockUpdateCause::Fluid);
+            return;
+        }
+    }
+
+    if can_flow_into(fluid, below) {
+        let falling = fluid.block(FALLING);
+        if below != falling {
+            state.set_block_at(below_pos, falling, BlockUpdateCause::Fluid);
+        }
+    } else if level == 0 || Fluid::from_block(below).is_none() {
+        spread_horizontally(state, fluid, level, pos);
+    }
+}
+
+/// Spreads a fluid to adjacent blocks on the same layer,
+/// preferring directions which lead toward a hole.
+fn spread_horizontally(state: &DimensionState, fluid: Fluid, level: i32, pos: BlockPosition) {
+    let spread_level = if level >= FALLING {
+        fluid.level_drop()
+    } else {
+        level + fluid.level_drop()
+    };
+
+    if spread_level >= FALLING {
+        return;
+    }
+
+    for target_pos in flow_targets(state, fluid, pos) {
+        let target = match state.block_at(target_pos) {
+            Some(target) => target,
+            None => continue,
+        };
+
+        let replace = match Fluid::from_block(target) {
+            Some((other, other_level)) if other == fluid => {
+                other_level != 0 && other_level < FALLING && other_level > spread_level
+            }
+            Some(_) => false,
+            None => true,
+        };
+
+        if replace {
+            state.set_block_at(
+                target_pos,
+                fluid.block(spread_level),
+                BlockUpdateCause::Fluid,
+            );
+        }
+    }
+}
+
+/// Determines which adjacent positions a fluid should spread to.
+///
+/// Like vanilla, directions are weighted by the distance
+/// to the nearest hole the fluid could fall into, and
+/// only the directions with the shortest distance are used.
+fn flow_targets(
+    state: &DimensionState,
+    fluid: Fluid,
+    pos: BlockPosition,
+) -> SmallVec<[BlockPosition; 4]> {
+    let mut costs = [None; 4];
+
+    for (i, offset) in HORIZONTAL.iter().enumerate() {
+        let target = pos + *offset;
+        match state.block_at(target) {
+            Some(block) if can_flow_into(fluid, block) => (),
+            _ => continue,
+        }
+
+        costs[i] = Some(if can_fall_from(state, fluid, target) {
+            0
+        } else {
+            slope_distance(state, fluid, target, 1, i)
+        });
+    }
+
+    let min = costs.iter().filter_map(|cost| *cost).min();
+
+    HORIZONTAL
+        .iter()
+        .zip(&costs)
+        .filter(|(_, cost)| cost.is_some() && **cost == min)
+        .map(|(offset, _)| pos + *offset)
+        .collect()
+}
+
+/// Returns the distance from `pos` to the nearest position
+/// from which the fluid could fall, without going back in
+/// the direction `from`. Returns a large value if no such
+/// position is found within the fluid's search distance.
+fn slope_distance(
+    state: &DimensionState,
+    fluid: Fluid,
+    pos: BlockPosition,
+    distance: i32,
+    from: usize,
+) -> i32 {
+    let mut min = 1000;
+
+    for (i, offset) in HORIZONTAL.iter().enumerate() {
+        if i == from ^ 1 {
+            continue;
+        }
+
+        let next = pos + *offset;
+        match state.block_at(next) {
+            Some(block) if can_flow_into(fluid, block) => (),
+            _ => continue,
+        }
+
+        if can_fall_from(state, fluid, next) {
+            return distance;
+        }
+
+        if distance < fluid.slope_find_distance() {
+            min = min.min(slope_distance(state, fluid, next, distance + 1, i));
+        }
+    }
+
+    min
+}
+
+/// Computes the level of a non-source fluid block based on
+/// its neighbors, or `None` if the fluid is no longer fed
+/// and should disappear.
+fn flowing_level(
+    fluid: Fluid,
+    above: Option<Block>,
+    horizontal: &[Option<Block>],
+    below: Option<Block>,
+) -> Option<i32> {
+    if let Some((above, _)) = above.and_then(Fluid::from_block) {
+        if above == fluid {
+            return Some(FALLING);
+        }
+    }
+
+    let mut sources = 0;
+    let mut min_level = None;
+
+    for (other, level) in horizontal
+        .iter()
+        .filter_map(|block| block.and_then(Fluid::from_block))
+    {
+        if other != fluid {
+            continue;
+        }
+
+        if level == 0 {
+            sources += 1;
+        }
+
+        // Falling fluids feed their neighbors like sources.
+        let level = if level >= FALLING { 0 } else { level };
+        min_level = Some(min_level.map_or(level, |min: i32| min.min(level)));
+    }
+
+    // Water between two sources becomes a source
+    // itself, provided it is resting on something.
+    if fluid == Fluid::Water && sources >= 2 {
+        let supported = below.map_or(false, |below| {
+            below.is_solid() || Fluid::from_block(below) == Some((Fluid::Water, 0))
+        });
+        if supported {
+            return Some(0);
+        }
+    }
+
+    let level = min_level? + fluid.level_drop();
+    if level >= FALLING {
+        None
+    } else {
+        Some(level)
+    }
+}
+
+/// Returns whether the given fluid can flow into
+/// (and replace) the given block.
+fn can_flow_into(fluid: Fluid, block: Block) -> bool {
+    match block {
+        Block::Air | Block::CaveAir | Block::VoidAir => true,
+        Block::Water(data) => fluid == Fluid::Water && data.level != 0,
+        Block::Lava(data) => fluid == Fluid::Lava && data.level != 0,
+        Block::Sign(_)
+        | Block::WallSign(_)
+        | Block::Ladder(_)
+        | Block::SugarCane(_)
+        | Block::Kelp(_)
+        | Block::KelpPlant
+        | Block::Seagrass
+        | Block::TallSeagrass(_) => false,
+        block => !block.is_solid(),
+    }
+}
+
+/// Returns whether fluid at `pos` would be able to flow downward.
+fn can_fall_from(state: &DimensionState, fluid: Fluid, pos: BlockPosition) -> bool {
+    state
+        .block_at(pos + DOWN)
+        .map_or(false, |below| can_flow_into(fluid, below))
+}
+
+/// Returns whether lava at `pos` is touching water
+/// from above or from the side.
+fn touches_water(state: &DimensionState, pos: BlockPosition) -> bool {
+    HORIZONTAL.iter().chain(&[UP]).any(|offset| {
+        state
+            .block_at(pos + *offset)
+            .and_then(Fluid::from_block)
+            .map_or(false, |(fluid, _)| fluid == Fluid::Water)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flowing_level() {
+        let water = |level| Some(Fluid::Water.block(level));
+        let lava = |level| Some(Fluid::Lava.block(level));
+        let stone = Some(Block::Stone);
+
+        // Fed by a single source
+        assert_eq!(
+            flowing_level(Fluid::Water, None, &[water(0), None, None, None], stone),
+            Some(1)
+        );
+        assert_eq!(
+            flowing_level(Fluid::Lava, None, &[lava(0), None, None, None], stone),
+            Some(2)
+        );
+
+        // Falling
+        assert_eq!(
+            flowing_level(Fluid::Water, water(3), &[None; 4], None),
+            Some(FALLING)
+        );
+        assert_eq!(
+            flowing_level(
+                Fluid::Water,
+                None,
+                &[water(FALLING), None, None, None],
+                None
+            ),
+            Some(1)
+        );
+
+        // Source creation
+        assert_eq!(
+            flowing_level(Fluid::Water, None, &[water(0), water(0), None, None], stone),
+            Some(0)
+        );
+        assert_eq!(
+            flowing_level(Fluid::Water, None, &[water(0), water(0), None, None], None),
+            Some(1)
+        );
+        assert_eq!(
+            flowing_level(Fluid::Lava, None, &[lava(0), lava(0), None, None], stone),
+            Some(2)
+        );
+
+        // Drying up
+        assert_eq!(flowing_level(Fluid::Water, None, &[None; 4], stone), None);
+        assert_eq!(
+            flowing_level(Fluid::Water, None, &[water(7), None, None, None], stone),
+            None
+        );
+        assert_eq!(
+            flowing_level(Fluid::Lava, lava(0), &[None; 4], stone),
+            Some(FALLING)
+        );
+        assert_eq!(
+            flowing_level(Fluid::Water, lava(0), &[None; 4], stone),
+            None
+        );
+    }
+}
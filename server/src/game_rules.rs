@@ -0,0 +1,346 @@
+//! Game rules.
+//!
+//! Each world has its own game rules, which are stored in
+//! its `level.dat` as a compound of strings. On load, they are
+//! parsed into typed values; rules missing from `level.dat` take
+//! their vanilla defaults, and rules unknown to the server are
+//! kept so they are saved back.
+
+use crate::entity::EntityId;
+use crate::network::Network;
+use crate::worlds::{DimensionId, PerWorld, WorldId};
+use feather_core::level::LevelData;
+use feather_core::packet::EntityStatus;
+use hashbrown::HashMap;
+use legion::query::Read;
+use std::fmt;
+use tonks::{PreparedWorld, Query};
+
+/// Entity status which enables reduced debug info.
+const ENABLE_REDUCED_DEBUG_INFO: i8 = 22;
+/// Entity status which disables reduced debug info.
+const DISABLE_REDUCED_DEBUG_INFO: i8 = 23;
+
+/// The value of a game rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameRuleValue {
+    Bool(bool),
+    Int(i32),
+}
+
+impl GameRuleValue {
+    /// Parses a value of the same type as `self` from a string.
+    fn parse_like(self, s: &str) -> Option<Self> {
+        match self {
+            GameRuleValue::Bool(_) => s.parse().ok().map(GameRuleValue::Bool),
+            GameRuleValue::Int(_) => s.parse().ok().map(GameRuleValue::Int),
+        }
+    }
+
+    fn same_type(self, other: Self) -> bool {
+        match (self, other) {
+            (GameRuleValue::Bool(_), GameRuleValue::Bool(_))
+            | (GameRuleValue::Int(_), GameRuleValue::Int(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for GameRuleValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameRuleValue::Bool(value) => write!(f, "{}", value),
+            GameRuleValue::Int(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+macro_rules! game_rules {
+    ($($rule:ident => $name:literal, $default:expr;)*) => {
+        /// A game rule known to the server.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum GameRule {
+            $($rule,)*
+        }
+
+        impl GameRule {
+            /// All game rules.
+            pub const ALL: &'static [GameRule] = &[$(GameRule::$rule,)*];
+
+            /// Returns the name of this rule as used
+            /// in `level.dat` and commands.
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(GameRule::$rule => $name,)*
+                }
+            }
+
+            /// Returns the default value of this rule.
+            pub fn default_value(self) -> GameRuleValue {
+                match self {
+                    $(GameRule::$rule => $default,)*
+                }
+            }
+        }
+    };
+}
+
+game_rules! {
+    AnnounceAdvancements => "announceAdvancements", GameRuleValue::Bool(true);
+    CommandBlockOutput => "commandBlockOutput", GameRuleValue::Bool(true);
+    DisableElytraMovementCheck => "disableElytraMovementCheck", GameRuleValue::Bool(false);
+    DoDaylightCycle => "doDaylightCycle", GameRuleValue::Bool(true);
+    DoEntityDrops => "doEntityDrops", GameRuleValue::Bool(true);
+    DoFireTick => "doFireTick", GameRuleValue::Bool(true);
+    DoLimitedCrafting => "doLimitedCrafting", GameRuleValue::Bool(false);
+    DoMobLoot => "doMobLoot", GameRuleValue::Bool(true);
+    DoMobSpawning => "doMobSpawning", GameRuleValue::Bool(true);
+    DoTileDrops => "doTileDrops", GameRuleValue::Bool(true);
+    DoWeatherCycle => "doWeatherCycle", GameRuleValue::Bool(true);
+    KeepInventory => "keepInventory", GameRuleValue::Bool(false);
+    LogAdminCommands => "logAdminCommands", GameRuleValue::Bool(true);
+    MaxCommandChainLength => "maxCommandChainLength", GameRuleValue::Int(65536);
+    MaxEntityCramming => "maxEntityCramming", GameRuleValue::Int(24);
+    MobGriefing => "mobGriefing", GameRuleValue::Bool(true);
+    NaturalRegeneration => "naturalRegeneration", GameRuleValue::Bool(true);
+    RandomTickSpeed => "randomTickSpeed", GameRuleValue::Int(3);
+    ReducedDebugInfo => "reducedDebugInfo", GameRuleValue::Bool(false);
+    SendCommandFeedback => "sendCommandFeedback", GameRuleValue::Bool(true);
+    ShowDeathMessages => "showDeathMessages", GameRuleValue::Bool(true);
+    SpawnRadius => "spawnRadius", GameRuleValue::Int(10);
+    SpectatorsGenerateChunks => "spectatorsGenerateChunks", GameRuleValue::Bool(true);
+}
+
+impl GameRule {
+    /// Returns the rule with the given name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        GameRule::ALL
+            .iter()
+            .copied()
+            .find(|rule| rule.name() == name)
+    }
+}
+
+/// An error occurring when setting a game rule.
+#[derive(Debug, Fail)]
+pub enum GameRuleError {
+    #[fail(display = "Invalid value for game rule {}: {}", _0, _1)]
+    InvalidValue(&'static str, String),
+}
+
+/// The game rules of a world.
+#[derive(Debug, Clone)]
+pub struct GameRules {
+    values: HashMap<GameRule, GameRuleValue>,
+    /// Rules from `level.dat` which the server doesn't know.
+    unknown: HashMap<String, String>,
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        Self {
+            values: GameRule::ALL
+                .iter()
+                .map(|rule| (*rule, rule.default_value()))
+                .collect(),
+            unknown: HashMap::new(),
+        }
+    }
+}
+
+impl GameRules {
+    /// Creates the game rules from the values stored in `level.dat`.
+    ///
+    /// Values which fail to parse are replaced by the defaults.
+    pub fn from_level(level: &LevelData) -> Self {
+        let mut rules = Self::default();
+
+        for (name, value) in &level.game_rules {
+            match GameRule::from_name(name) {
+                Some(rule) => {
+                    if let Err(e) = rules.set_from_str(rule, value) {
+                        warn!("{}; using default value", e);
+                    }
+                }
+                None => {
+                    rules.unknown.insert(name.clone(), value.clone());
+                }
+            }
+        }
+
+        rules
+    }
+
+    /// Writes the game rules into `level` so they can be saved to `level.dat`.
+    pub fn write_to_level(&self, level: &mut LevelData) {
+        level.game_rules = self
+            .unknown
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .chain(
+                self.values
+                    .iter()
+                    .map(|(rule, value)| (rule.name().to_string(), value.to_string())),
+            )
+            .collect();
+    }
+
+    /// Returns the value of a game rule.
+    pub fn get(&self, rule: GameRule) -> GameRuleValue {
+        self.values[&rule]
+    }
+
+    /// Returns the value of a boolean game rule.
+    ///
+    /// # Panics
+    /// Panics if the rule is not a boolean rule.
+    pub fn get_bool(&self, rule: GameRule) -> bool {
+        match self.get(rule) {
+            GameRuleValue::Bool(value) => value,
+            value => panic!("game rule {} is not a boolean: {}", rule.name(), value),
+        }
+    }
+
+    /// Returns the value of an integer game rule.
+    ///
+    /// # Panics
+    /// Panics if the rule is not an integer rule.
+    pub fn get_int(&self, rule: GameRule) -> i32 {
+        match self.get(rule) {
+            GameRuleValue::Int(value) => value,
+            value => panic!("game rule {} is not an integer: {}", rule.name(), value),
+        }
+    }
+
+    /// Sets the value of a game rule.
+    ///
+    /// Callers should trigger a `GameRuleChangeEvent`
+    /// so the change is propagated to players.
+    pub fn set(&mut self, rule: GameRule, value: GameRuleValue) -> Result<(), GameRuleError> {
+        if !rule.default_value().same_type(value) {
+            return Err(GameRuleError::InvalidValue(rule.name(), value.to_string()));
+        }
+
+        self.values.insert(rule, value);
+        Ok(())
+    }
+
+    /// Sets the value of a game rule from its string representation.
+    pub fn set_from_str(&mut self, rule: GameRule, value: &str) -> Result<(), GameRuleError> {
+        let value = rule
+            .default_value()
+            .parse_like(value)
+            .ok_or_else(|| GameRuleError::InvalidValue(rule.name(), value.to_string()))?;
+        self.set(rule, value)
+    }
+
+    /// Returns the entity status packet which tells a
+    /// player whether `reducedDebugInfo` is enabled.
+    pub fn reduced_debug_info_packet(&self, player: EntityId) -> EntityStatus {
+        let entity_status = if self.get_bool(GameRule::ReducedDebugInfo) {
+            ENABLE_REDUCED_DEBUG_INFO
+        } else {
+            DISABLE_REDUCED_DEBUG_INFO
+        };
+
+        EntityStatus {
+            entity_id: player.0,
+            entity_status,
+        }
+    }
+}
+
+/// The game rules of each world.
+#[derive(Debug, Clone, Resource, Deref, DerefMut)]
+pub struct WorldGameRules(pub PerWorld<GameRules>);
+
+/// Event triggered when a game rule is changed at runtime.
+#[derive(Debug, Clone)]
+pub struct GameRuleChangeEvent {
+    /// The world whose rule was changed.
+    pub world: WorldId,
+    pub rule: GameRule,
+}
+
+/// Event handler which sends changes to
+/// `reducedDebugInfo` to the players in the changed world.
+#[event_handler]
+fn reduced_debug_info_change(
+    events: &[GameRuleChangeEvent],
+    rules: &WorldGameRules,
+    query: &mut Query<(Read<EntityId>, Read<Network>, Read<DimensionId>)>,
+    world: &mut PreparedWorld,
+) {
+    let changed: Vec<_> = events
+        .iter()
+        .filter(|event| event.rule == GameRule::ReducedDebugInfo)
+        .map(|event| event.world)
+        .collect();
+    if changed.is_empty() {
+        return;
+    }
+
+    query.par_entities_for_each(world, |(_, (id, network, dimension))| {
+        if changed.contains(&dimension.world) {
+            network.send(rules.get(dimension.world).reduced_debug_info_packet(*id));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_name() {
+        for rule in GameRule::ALL {
+            assert_eq!(GameRule::from_name(rule.name()), Some(*rule));
+        }
+        assert_eq!(GameRule::from_name("notARule"), None);
+    }
+
+    #[test]
+    fn test_level_round_trip() {
+        let mut level = LevelData::default();
+        level
+            .game_rules
+            .insert("keepInventory".to_string(), "true".to_string());
+        level
+            .game_rules
+            .insert("randomTickSpeed".to_string(), "not a number".to_string());
+        level
+            .game_rules
+            .insert("customRule".to_string(), "value".to_string());
+
+        let rules = GameRules::from_level(&level);
+        assert!(rules.get_bool(GameRule::KeepInventory));
+        assert_eq!(rules.get_int(GameRule::RandomTickSpeed), 3);
+        assert!(rules.get_bool(GameRule::DoDaylightCycle));
+
+        let mut saved = LevelData::default();
+        rules.write_to_level(&mut saved);
+        assert_eq!(saved.game_rules["keepInventory"], "true");
+        assert_eq!(saved.game_rules["randomTickSpeed"], "3");
+        assert_eq!(saved.game_rules["customRule"], "value");
+        assert_eq!(saved.game_rules.len(), GameRule::ALL.len() + 1);
+    }
+
+    #[test]
+    fn test_set() {
+        let mut rules = GameRules::default();
+
+        rules.set_from_str(GameRule::MobGriefing, "false").unwrap();
+        assert!(!rules.get_bool(GameRule::MobGriefing));
+
+        rules
+            .set(GameRule::SpawnRadius, GameRuleValue::Int(5))
+            .unwrap();
+        assert_eq!(rules.get_int(GameRule::SpawnRadius), 5);
+
+        assert!(rules.set_from_str(GameRule::SpawnRadius, "true").is_err());
+        assert!(rules
+            .set(GameRule::DoFireTick, GameRuleValue::Int(1))
+            .is_err());
+        assert_eq!(rules.get_int(GameRule::SpawnRadius), 5);
+    }
+}
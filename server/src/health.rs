@@ -0,0 +1,134 @@
+//! Health and damage of living entities.
+//!
+//! Entities with a `Health` component are living: they are
+//! damaged by triggering an `EntityDamageEvent`, and they die
+//! once their health reaches zero. Players in creative or
+//! spectator mode are never damaged.
+//!
+//! Hunger is not implemented, so players' food stays full.
+
+use crate::entity::EntityId;
+use crate::network::Network;
+use crate::player::death::Dead;
+use crate::state::State;
+use crate::worlds::DimensionId;
+use feather_core::network::packet::implementation::{AnimationClientbound, UpdateHealth};
+use feather_core::{ClientboundAnimation, Gamemode};
+use legion::entity::Entity;
+use legion::query::{Read, Write};
+use tonks::{PreparedWorld, Query};
+
+/// The health of a player at full health.
+pub const MAX_HEALTH: f32 = 20.0;
+/// The food level sent to players.
+const MAX_FOOD: i32 = 20;
+/// The food saturation sent to players.
+const INITIAL_SATURATION: f32 = 5.0;
+
+/// The health of a living entity, in half hearts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Health(pub f32);
+
+/// The cause of damage dealt to an entity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DamageCause {
+    /// An arrow shot by `shooter`, if any.
+    Arrow { shooter: Option<Entity> },
+    /// An explosion caused by `source`, if any.
+    Explosion { source: Option<Entity> },
+    /// Being outside the world border.
+    WorldBorder,
+    /// A falling block, such as an anvil, landing on the entity.
+    FallingBlock,
+}
+
+/// Event which damages a living entity.
+#[derive(Debug, Clone)]
+pub struct EntityDamageEvent {
+    /// The entity to damage.
+    pub entity: Entity,
+    /// The damage to deal, in half hearts.
+    pub damage: u32,
+    /// What caused the damage.
+    pub cause: DamageCause,
+}
+
+/// Returns the Update Health packet which
+/// tells a player their health.
+pub fn health_packet(health: f32) -> UpdateHealth {
+    UpdateHealth {
+        health,
+        food: MAX_FOOD,
+        food_saturation: INITIAL_SATURATION,
+    }
+}
+
+/// Returns whether `entity` can't be damaged
+/// because of its gamemode or because it is dead.
+fn is_invulnerable(world: &PreparedWorld, entity: Entity) -> bool {
+    let gamemode = world
+        .get_component::<Gamemode>(entity)
+        .map(|gamemode| *gamemode);
+    match gamemode {
+        Some(Gamemode::Creative) | Some(Gamemode::Spectator) => return true,
+        _ => (),
+    }
+
+    world
+        .get_component::<Dead>(entity)
+        .map_or(false, |dead| dead.0)
+}
+
+/// Event handler which damages living entities. Players
+/// die at zero health; other entities are removed.
+#[event_handler]
+fn entity_damage(
+    events: &[EntityDamageEvent],
+    state: &State,
+    _query: &mut Query<(
+        Write<Health>,
+        Write<Dead>,
+        Read<Gamemode>,
+        Read<Network>,
+        Read<EntityId>,
+        Read<DimensionId>,
+    )>,
+    world: &mut PreparedWorld,
+) {
+    for event in events {
+        let entity = event.entity;
+        if event.damage == 0 || is_invulnerable(world, entity) {
+            continue;
+        }
+
+        let health = match world.get_component_mut::<Health>(entity) {
+            // Entities at zero health are already being removed.
+            Some(mut health) if health.0 > 0.0 => {
+                health.0 = (health.0 - event.damage as f32).max(0.0);
+                health.0
+            }
+            _ => continue,
+        };
+
+        let dimension = *world.get_component::<DimensionId>(entity).unwrap();
+        let state = state.dimension(dimension);
+
+        if let Some(id) = world.get_component::<EntityId>(entity) {
+            let packet = AnimationClientbound {
+                entity_id: id.0,
+                animation: ClientboundAnimation::TakeDamage,
+            };
+            state.broadcast_entity_update(entity, packet, None);
+        }
+        if let Some(network) = world.get_component::<Network>(entity) {
+            network.send(health_packet(health));
+        }
+
+        if health <= 0.0 {
+            match world.get_component_mut::<Dead>(entity) {
+                Some(mut dead) => dead.0 = true,
+                None => state.delete_entity(entity),
+            }
+        }
+    }
+}
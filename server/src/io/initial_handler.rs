@@ -28,6 +28,7 @@ use feather_core::network::packet::implementation::{
     LoginSuccess, Ping, Pong, Request, Response, SetCompression,
 };
 use feather_core::network::packet::{Packet, PacketStage, PacketType};
+use feather_core::TextComponent;
 
 use crate::config::{Config, ProxyMode};
 use crate::{PlayerCount, PROTOCOL_VERSION, SERVER_VERSION};
@@ -309,9 +310,7 @@ fn handle_request(ih: &mut InitialHandler, packet: &Request) -> Result<(), Error
             "max": ih.config.server.max_players,
             "online": ih.player_count.0.load(Ordering::SeqCst),
         },
-        "description": {
-            "text": ih.config.server.motd,
-        },
+        "description": TextComponent::from_legacy(&ih.config.server.motd),
         "favicon": server_icon,
     });
 
@@ -512,12 +511,7 @@ fn check_stage(ih: &InitialHandler, expected: Stage, packet_ty: PacketType) -> R
 /// Disconnects the initial handler, sending
 /// a disconnect packet containing the reason.
 fn disconnect_login(ih: &mut InitialHandler, reason: &str) {
-    let json = json!({
-        "text": reason,
-    })
-    .to_string();
-
-    let packet = DisconnectLogin::new(json);
+    let packet = DisconnectLogin::new(TextComponent::text(reason));
     send_packet(ih, packet);
 
     ih.action_queue.push(Action::Disconnect);
@@ -12,7 +12,7 @@ use crate::io::{ListenerToServerMessage, NewClientInfo, ServerToWorkerMessage};
 use crate::PlayerCount;
 use feather_core::network::codec::MinecraftCodec;
 use feather_core::network::packet::PacketDirection;
-use feather_core::player_data::PlayerData;
+use feather_core::player_data::{self, PlayerData};
 use futures::{select, StreamExt};
 use futures::{FutureExt, SinkExt};
 use std::net::SocketAddr;
@@ -131,8 +131,9 @@ async fn _run_worker(
                                     }
                                     Action::SetStage(stage) => framed.codec_mut().set_stage(stage),
                                     Action::JoinGame(res) => {
-                                        // let data = load_player_data(&config, res.uuid).await?;
-                                        let data = PlayerData::default();
+                                        let data = load_player_data(&config, res.uuid)
+                                            .await
+                                            .map_err(|_| Error::PlayerData)?;
                                         let info = NewClientInfo {
                                             ip,
                                             username: res.username.ok_or(Error::PlayerData)?,
@@ -140,11 +141,10 @@ async fn _run_worker(
                                             uuid: res.uuid,
                                             sender: tx_server_to_worker.clone(),
                                             receiver: rx_worker_to_server.take().unwrap(),
-                                            /*position: data
-                                            .entity
-                                            .read_position()
-                                            .ok_or_else(|| Error::PlayerData)?,*/
-                                            position: position!(0.0, 80.0, 0.0),
+                                            position: data
+                                                .entity
+                                                .read_position()
+                                                .unwrap_or(position!(0.0, 80.0, 0.0)),
                                             data,
                                         };
                                         global_sender
@@ -165,6 +165,12 @@ async fn _run_worker(
     }
 }
 
+/// Loads the data of a player from the main world,
+/// or returns the default data if they have none yet.
 async fn load_player_data(config: &Config, uuid: Uuid) -> Result<PlayerData, nbt::Error> {
-    feather_core::player_data::load_player_data(Path::new(&config.world.name), uuid).await
+    let world_dir = Path::new(&config.world.name);
+    if !player_data::file_path(world_dir, uuid).exists() {
+        return Ok(PlayerData::default());
+    }
+    player_data::load_player_data(world_dir, uuid).await
 }
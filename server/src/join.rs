@@ -2,11 +2,14 @@
 //! by sending Spawn Position, Player Position and Look, and inventory,
 //! among others. This is handled by the event handler `join`.
 
+use crate::difficulty::{WorldDifficulty, HARDCORE_FLAG};
 use crate::entity::EntityId;
+use crate::game_rules::{GameRule, WorldGameRules};
 use crate::network::Network;
 use crate::player::PlayerJoinEvent;
 use crate::state::State;
 use crate::view::ChunkSendEvent;
+use crate::worlds::DimensionId;
 use feather_core::network::packet::implementation::{
     JoinGame, PlayerPositionAndLookClientbound, SpawnPosition,
 };
@@ -23,10 +26,17 @@ pub struct Joined(pub bool);
 /// System to run the join sequence. To determine when a player is ready to join,
 /// we wait for the chunk that the player is in to be sent—this appears to work
 /// well with the client.
+///
+/// The sequence is run again after a player changes dimension.
 #[event_handler]
 fn join(
     events: &[ChunkSendEvent],
-    _query: &mut Query<(Write<Joined>, Read<Position>, Read<Network>)>,
+    _query: &mut Query<(
+        Write<Joined>,
+        Read<Position>,
+        Read<DimensionId>,
+        Read<Network>,
+    )>,
     world: &mut PreparedWorld,
     state: &State,
 ) {
@@ -36,9 +46,10 @@ fn join(
             let world = world.read();
 
             let pos = world.get_component::<Position>(event.player).unwrap();
+            let dimension = world.get_component::<DimensionId>(event.player).unwrap();
             let joined = world.get_component::<Joined>(event.player).unwrap();
 
-            if pos.chunk_pos() != event.chunk || joined.0 {
+            if pos.chunk_pos() != event.chunk || *dimension != event.dimension || joined.0 {
                 return;
             }
 
@@ -55,12 +66,9 @@ fn join(
         let world = world.read();
         let network = world.get_component::<Network>(event.player).unwrap();
 
+        let level = &state.world(event.dimension.world).level;
         let packet = SpawnPosition {
-            location: BlockPosition::new(
-                state.level.spawn_x,
-                state.level.spawn_y,
-                state.level.spawn_z,
-            ),
+            location: BlockPosition::new(level.spawn_x, level.spawn_y, level.spawn_z),
         };
         network.send(packet);
 
@@ -80,21 +88,40 @@ fn join(
 #[event_handler]
 fn send_join_game(
     event: &PlayerJoinEvent,
-    _query: &mut Query<(Read<EntityId>, Read<Network>)>,
+    state: &State,
+    rules: &WorldGameRules,
+    difficulty: &WorldDifficulty,
+    _query: &mut Query<(
+        Read<EntityId>,
+        Read<DimensionId>,
+        Read<Gamemode>,
+        Read<Network>,
+    )>,
     world: &mut PreparedWorld,
 ) {
     let network = world.get_component::<Network>(event.player).unwrap();
     let id = world.get_component::<EntityId>(event.player).unwrap();
+    let dimension = *world.get_component::<DimensionId>(event.player).unwrap();
+    let rules = rules.get(dimension.world);
+    let difficulty = difficulty.get(dimension.world);
+    let level = &state.world(dimension.world).level;
+
+    let mut gamemode = world
+        .get_component::<Gamemode>(event.player)
+        .unwrap()
+        .get_id();
+    if difficulty.hardcore {
+        gamemode |= HARDCORE_FLAG;
+    }
 
-    // TODO
     let packet = JoinGame {
         entity_id: id.0,
-        gamemode: Gamemode::Creative.get_id(),
-        dimension: 0,
-        difficulty: 0,
+        gamemode,
+        dimension: dimension.kind.get_id(),
+        difficulty: difficulty.difficulty.get_id(),
         max_players: 0,
-        level_type: "default".to_string(),
-        reduced_debug_info: false,
+        level_type: level.generator_type().level_type().to_string(),
+        reduced_debug_info: rules.get_bool(GameRule::ReducedDebugInfo),
     };
     network.send(packet);
 }
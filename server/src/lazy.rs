@@ -1,4 +1,5 @@
 use crate::entity::{EntityDeleteEvent, EntityId};
+use crate::worlds::DimensionId;
 use crossbeam::queue::SegQueue;
 use feather_core::Position;
 use legion::entity::Entity;
@@ -49,25 +50,7 @@ impl Lazy {
     /// Deletes an entity, triggering the necessary event as well.
     pub fn delete_entity(&self, entity: Entity) {
         self.exec_with_scheduler(move |world, scheduler| {
-            if !world.is_alive(entity) {
-                return;
-            }
-
-            let position = world.get_component::<Position>(entity).map(|pos| *pos);
-            let id = *world.get_component::<EntityId>(entity).unwrap();
-            let uuid = world
-                .get_component::<Uuid>(entity)
-                .map(|u| *u)
-                .unwrap_or(Uuid::new_v4());
-
-            scheduler.trigger(EntityDeleteEvent {
-                entity,
-                position,
-                id,
-                uuid,
-            });
-
-            world.delete(entity);
+            delete_entity_now(world, scheduler, entity);
         });
     }
 
@@ -81,6 +64,33 @@ impl Lazy {
     }
 }
 
+/// Deletes an entity immediately, triggering the necessary event
+/// as well. Unlike `Lazy::delete_entity`, this requires access to
+/// the world, such as in a lazy closure.
+pub fn delete_entity_now(world: &mut World, scheduler: &mut Scheduler, entity: Entity) {
+    if !world.is_alive(entity) {
+        return;
+    }
+
+    let position = world.get_component::<Position>(entity).map(|pos| *pos);
+    let dimension = world.get_component::<DimensionId>(entity).map(|dim| *dim);
+    let id = *world.get_component::<EntityId>(entity).unwrap();
+    let uuid = world
+        .get_component::<Uuid>(entity)
+        .map(|u| *u)
+        .unwrap_or(Uuid::new_v4());
+
+    scheduler.trigger(EntityDeleteEvent {
+        entity,
+        position,
+        dimension,
+        id,
+        uuid,
+    });
+
+    world.delete(entity);
+}
+
 /// An action which the lazy updater may perform.
 enum Action {
     Exec(Box<dyn LazyFnWithScheduler>),
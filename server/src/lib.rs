@@ -126,19 +126,33 @@ use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use crate::chunk_logic::ChunkWorkerHandle;
+use crate::access::AccessLists;
+use crate::chunk_logic::{ChunkWorkerHandle, ChunkWorkers};
+use crate::command::CommandDispatcher;
 use crate::config::Config;
+use crate::console::{Console, ConsoleCommands};
+use crate::difficulty::{DifficultySettings, WorldDifficulty};
+use crate::game_rules::{GameRules, WorldGameRules};
 use crate::io::NetworkIoManager;
-use crate::state::StateInner;
+use crate::locale::{Translations, LANG_DIR};
+use crate::permissions::{Permissions, PERMISSIONS_FILE};
+use crate::shutdown::ShutdownHandle;
+use crate::state::{StateInner, WorldData};
+use crate::time::{Time, WorldTimes};
+use crate::weather::{Weather, WorldWeather};
+use crate::world_border::{WorldBorder, WorldBorders};
 use crate::worldgen::{
-    ComposableGenerator, EmptyWorldGenerator, SuperflatWorldGenerator, WorldGenerator,
+    ComposableGenerator, EmptyWorldGenerator, EndWorldGenerator, NetherWorldGenerator,
+    SuperflatWorldGenerator, WorldGenerator,
 };
+use crate::worlds::PerWorld;
 use feather_core::level;
 use feather_core::level::{deserialize_level_file, save_level_file, LevelData, LevelGeneratorType};
-use feather_core::world::ChunkMap;
+use feather_core::Dimension;
 use legion::world::World;
 use rand::Rng;
 use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
 use std::fs::File;
 use std::hash::{Hash, Hasher};
 use std::io::{Read, Write};
@@ -149,28 +163,44 @@ use tonks::{Resources, Scheduler};
 #[global_allocator]
 static ALLOC: System = System;
 
+pub mod access;
 pub mod block;
 pub mod broadcasters;
 pub mod chunk_entities;
 pub mod chunk_logic;
 pub mod chunk_worker;
+pub mod command;
 pub mod config;
+pub mod console;
+pub mod difficulty;
 pub mod entity;
+pub mod explosion;
+pub mod fluid;
+pub mod game_rules;
+pub mod health;
 pub mod io;
 pub mod join;
 pub mod lazy;
+pub mod locale;
 pub mod metadata;
 pub mod network;
 pub mod p_inventory; // Prefixed to avoid conflict with inventory crate
 pub mod packet_handlers;
+pub mod permissions;
 pub mod physics;
 pub mod player;
+pub mod portal;
+pub mod redstone;
 pub mod shutdown;
 pub mod state;
 pub mod time;
 pub mod util;
+pub mod vehicle;
 pub mod view;
+pub mod weather;
+pub mod world_border;
 pub mod worldgen;
+pub mod worlds;
 
 pub const TPS: u64 = 20;
 pub const PROTOCOL_VERSION: u32 = 404;
@@ -191,7 +221,7 @@ fn tick_count_increment(tick: &mut TickCount) {
 
 pub fn main() {
     let config = Arc::new(load_config());
-    init_log(&config);
+    let mut console = init_log(&config);
 
     info!("Starting Feather; please wait...");
 
@@ -205,41 +235,42 @@ pub fn main() {
         Arc::clone(&server_icon),
     );
 
-    let world_name = &config.world.name;
-    let world_dir = Path::new(world_name.as_str());
-    let level_file = &world_dir.join("level.dat");
-    if !world_dir.is_dir() {
-        info!(
-            "World directory '{}' not found, creating it",
-            world_dir.display()
-        );
-        // Create directory
-        std::fs::create_dir(world_dir).unwrap();
-
-        let level = create_level(&config);
-        let root = level::Root { data: level };
-        let mut level_file = File::create(level_file).unwrap();
-        save_level_file(&root, &mut level_file).unwrap();
+    let main_world = &config.world;
+    let mut worlds = vec![load_world(
+        &main_world.name,
+        &main_world.generator,
+        &main_world.seed,
+    )];
+    for extra in &config.extra_worlds {
+        if worlds.iter().any(|(world, _)| world.name == extra.name) {
+            error!("World '{}' is configured more than once", extra.name);
+            exit(1);
+        }
+        worlds.push(load_world(&extra.name, &extra.generator, &extra.seed));
     }
+    let (worlds, chunk_workers) = worlds.into_iter().unzip();
 
-    info!("Loading {}", level_file.to_str().unwrap());
-    let level = load_level(level_file).unwrap_or_else(|e| {
-        error!("Error occurred while loading level.dat: {}", e);
-        error!("Please ensure that the world directory exists and is not corrupt.");
-        exit(1)
-    });
+    // Channel used by the shutdown handler to notify the server thread.
+    let (shutdown_tx, shutdown_rx) = crossbeam::unbounded();
 
-    let chunk_worker_handle = init_chunk_worker(world_dir, &level);
+    // The console completes commands against the server's dispatcher.
+    let dispatcher = CommandDispatcher::builtin();
+    let console_commands = console.start(dispatcher.clone(), shutdown_tx.clone());
 
-    let mut scheduler = init_scheduler(Arc::clone(&config), chunk_worker_handle, level, io_manager);
+    let mut scheduler = init_scheduler(
+        Arc::clone(&config),
+        ChunkWorkers(chunk_workers),
+        worlds,
+        dispatcher,
+        io_manager,
+        ShutdownHandle::new(shutdown_tx.clone()),
+        console_commands,
+    );
     let mut world = World::new();
 
-    // Channel used by the shutdown handler to notify the server thread.
-    let (shutdown_tx, shutdown_rx) = crossbeam::unbounded();
-
     shutdown::init(shutdown_tx);
 
-    info!("Initialized world");
+    info!("Initialized worlds");
 
     info!("Generating RSA keypair");
     io::init();
@@ -252,14 +283,15 @@ pub fn main() {
 
     info!("Shutting down");
 
-    info!("Saving chunks");
-    shutdown::save_chunks(&mut world);
     info!("Saving level.dat");
-    shutdown::save_level(&world);
+    shutdown::save_levels(scheduler.resources());
     info!("Saving player data");
-    shutdown::save_player_data(&world);
+    shutdown::save_players(&world, scheduler.resources());
+    info!("Saving chunks");
+    shutdown::save_chunks(&mut world, &mut scheduler);
 
     info!("Goodbye");
+    console.close();
     exit(0);
 }
 
@@ -303,25 +335,82 @@ fn run_loop(world: &mut World, scheduler: &mut Scheduler, shutdown_rx: Receiver<
 }
 
 /// Initializes the scheduler and resources.
+///
+/// The time, weather and other state of each
+/// world are loaded from its level data.
 fn init_scheduler(
     config: Arc<Config>,
-    chunk_worker_handle: ChunkWorkerHandle,
-    level: LevelData,
+    chunk_workers: ChunkWorkers,
+    worlds: Vec<WorldData>,
+    dispatcher: CommandDispatcher,
     io_manager: NetworkIoManager,
+    shutdown: ShutdownHandle,
+    console: ConsoleCommands,
 ) -> Scheduler {
     // Insert resources which don't have a `Default` impl.
     let mut resources = Resources::new();
-    let chunk_map = ChunkMap::new();
-    resources.insert(StateInner::new(config, chunk_map, level));
-    resources.insert(chunk_worker_handle);
+    resources.insert(WorldDifficulty(PerWorld::from_levels(&worlds, |level| {
+        DifficultySettings::from_level(level, &config)
+    })));
+    resources.insert(WorldGameRules(PerWorld::from_levels(
+        &worlds,
+        GameRules::from_level,
+    )));
+    resources.insert(WorldTimes(PerWorld::from_levels(&worlds, Time::from_level)));
+    resources.insert(WorldWeather(PerWorld::from_levels(
+        &worlds,
+        Weather::from_level,
+    )));
+    resources.insert(WorldBorders(PerWorld::from_levels(
+        &worlds,
+        WorldBorder::from_level,
+    )));
+    resources.insert(AccessLists::load(&config));
+    resources.insert(Permissions::load(PERMISSIONS_FILE));
+    resources.insert(Translations::load(LANG_DIR));
+    resources.insert(StateInner::new(config, worlds));
+    resources.insert(chunk_workers);
+    resources.insert(dispatcher);
     resources.insert(io_manager);
+    resources.insert(shutdown);
+    resources.insert(console);
 
     tonks::build_scheduler().build(resources)
 }
 
-/// Initializes the chunk worker.
+/// Loads the world with the given name, creating it if it does
+/// not exist, and starts its chunk worker.
+fn load_world(name: &str, generator: &str, seed: &str) -> (WorldData, ChunkWorkerHandle) {
+    let world_dir = Path::new(name);
+    let level_file = &world_dir.join("level.dat");
+    if !world_dir.is_dir() {
+        info!(
+            "World directory '{}' not found, creating it",
+            world_dir.display()
+        );
+        // Create directory
+        std::fs::create_dir(world_dir).unwrap();
+
+        let level = create_level(name, generator, seed);
+        let root = level::Root { data: level };
+        let mut level_file = File::create(level_file).unwrap();
+        save_level_file(&root, &mut level_file).unwrap();
+    }
+
+    info!("Loading {}", level_file.to_str().unwrap());
+    let level = load_level(level_file).unwrap_or_else(|e| {
+        error!("Error occurred while loading level.dat: {}", e);
+        error!("Please ensure that the world directory exists and is not corrupt.");
+        exit(1)
+    });
+
+    let chunk_worker = init_chunk_worker(world_dir, &level);
+    (WorldData::new(name.to_string(), level), chunk_worker)
+}
+
+/// Initializes the chunk worker for a world.
 fn init_chunk_worker(world_dir: &Path, level: &LevelData) -> ChunkWorkerHandle {
-    let generator: Arc<dyn WorldGenerator> = match level.generator_type() {
+    let overworld: Arc<dyn WorldGenerator> = match level.generator_type() {
         LevelGeneratorType::Flat => Arc::new(SuperflatWorldGenerator {
             options: level.clone().generator_options.unwrap_or_default(),
         }),
@@ -331,7 +420,15 @@ fn init_chunk_worker(world_dir: &Path, level: &LevelData) -> ChunkWorkerHandle {
         _ => Arc::new(EmptyWorldGenerator {}),
     };
 
-    let (tx, rx) = chunk_worker::start(world_dir, generator);
+    let mut generators: HashMap<Dimension, Arc<dyn WorldGenerator>> = HashMap::new();
+    generators.insert(Dimension::Overworld, overworld);
+    generators.insert(
+        Dimension::Nether,
+        Arc::new(NetherWorldGenerator::new(level.seed as u64)),
+    );
+    generators.insert(Dimension::End, Arc::new(EndWorldGenerator));
+
+    let (tx, rx) = chunk_worker::start(world_dir, generators);
     ChunkWorkerHandle {
         sender: tx,
         receiver: rx,
@@ -376,7 +473,7 @@ fn init_io_manager(
     )
 }
 
-fn init_log(config: &Config) {
+fn init_log(config: &Config) -> Console {
     let level = match config.log.level.as_str() {
         "trace" => log::Level::Trace,
         "debug" => log::Level::Debug,
@@ -386,28 +483,32 @@ fn init_log(config: &Config) {
         _ => panic!("Unknown log level {}", config.log.level),
     };
 
-    simple_logger::init_with_level(level).unwrap();
+    console::init(level)
 }
 
-fn create_level(config: &Config) -> LevelData {
-    let seed = get_seed(config);
-    let world_name = &config.world.name;
-    debug!("Using seed {} for world '{}'", seed, world_name);
+fn create_level(name: &str, generator: &str, seed: &str) -> LevelData {
+    let seed = get_seed(seed);
+    debug!("Using seed {} for world '{}'", seed, name);
 
     // TODO: Generate spawn position properly
     LevelData {
         allow_commands: false,
         border_center_x: 0.0,
         border_center_z: 0.0,
-        border_damage_per_block: 0.0,
-        border_safe_zone: 0.0,
-        border_size: 0.0,
+        border_damage_per_block: 0.2,
+        border_safe_zone: 5.0,
+        border_size: world_border::DEFAULT_SIZE,
+        border_size_lerp_target: world_border::DEFAULT_SIZE,
+        border_size_lerp_time: 0,
+        border_warning_blocks: 5.0,
+        border_warning_time: 15.0,
         clear_weather_time: 0,
         data_version: 0,
         day_time: 0,
         difficulty: 0,
         difficulty_locked: 0,
         game_type: 0,
+        game_rules: HashMap::new(),
         hardcore: false,
         initialized: false,
         last_played: 0,
@@ -421,13 +522,12 @@ fn create_level(config: &Config) -> LevelData {
         thunder_time: 0,
         time: 0,
         version: Default::default(),
-        generator_name: config.world.generator.to_string(),
+        generator_name: generator.to_string(),
         generator_options: None,
     }
 }
 
-fn get_seed(config: &Config) -> i64 {
-    let seed_raw = &config.world.seed;
+fn get_seed(seed_raw: &str) -> i64 {
     // Empty seed: random
     // Seed is valid i64: parse
     // Seed is something else: hash
@@ -436,7 +536,7 @@ fn get_seed(config: &Config) -> i64 {
     } else {
         match seed_raw.parse::<i64>() {
             Ok(seed_int) => seed_int,
-            Err(_) => hash_seed(seed_raw.as_str()),
+            Err(_) => hash_seed(seed_raw),
         }
     }
 }
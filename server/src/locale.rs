@@ -0,0 +1,293 @@
+//! Per-player languages and the server's translation catalogue.
+//!
+//! Clients report their language in the Client Settings packet,
+//! which is stored in each player's `Locale` component. Messages
+//! the server sends may contain `translate` components; those whose
+//! keys are in the catalogue are rendered on the server in the
+//! recipient's language, while all others, such as vanilla keys,
+//! are left for the client to translate.
+//!
+//! The catalogue starts out with the server's built-in English
+//! messages. Files in the `lang` directory of the server directory,
+//! named after their locale, as in `lang/de_de.json`, add languages
+//! or override messages. They use the same format as the client's
+//! language files: an object mapping keys to formats, in which `%s`
+//! and `%1$s` are replaced with arguments and `%%` is a percent sign.
+
+use feather_core::text::Content;
+use feather_core::TextComponent;
+use legion::entity::Entity;
+use legion::world::World;
+use std::collections::HashMap;
+use std::fs;
+use std::mem;
+use std::path::Path;
+
+pub const LANG_DIR: &str = "lang";
+
+/// The locale of the built-in messages, which is used
+/// when a message is missing in a player's language.
+pub const DEFAULT_LOCALE: &str = "en_us";
+
+const DEFAULT_LANG_STR: &str = include_str!("../lang/en_us.json");
+
+lazy_static! {
+    static ref BUILTIN: Translations = Translations::builtin();
+}
+
+/// A player's language, as reported by their client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locale(pub String);
+
+impl Default for Locale {
+    fn default() -> Self {
+        Locale(DEFAULT_LOCALE.to_string())
+    }
+}
+
+impl Locale {
+    /// Creates a locale from one sent by a client,
+    /// which older clients send in mixed case.
+    pub fn new(locale: &str) -> Self {
+        Locale(locale.to_lowercase())
+    }
+}
+
+/// Resource holding the translations of the server's messages.
+#[derive(Debug, Clone, Resource)]
+pub struct Translations {
+    /// Maps locales to the formats of their keys.
+    languages: HashMap<String, HashMap<String, String>>,
+}
+
+impl Translations {
+    /// Returns a catalogue with only the built-in messages.
+    pub fn builtin() -> Self {
+        let mut languages = HashMap::new();
+        languages.insert(
+            DEFAULT_LOCALE.to_string(),
+            serde_json::from_str(DEFAULT_LANG_STR).unwrap(),
+        );
+        Self { languages }
+    }
+
+    /// Returns a shared catalogue with only the built-in messages,
+    /// for rendering messages where no resources are available.
+    pub fn builtin_ref() -> &'static Self {
+        &BUILTIN
+    }
+
+    /// Returns the built-in messages, extended with
+    /// the language files in the given directory.
+    pub fn load(dir: impl AsRef<Path>) -> Self {
+        let mut translations = Self::builtin();
+        let dir = dir.as_ref();
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => return translations,
+        };
+
+        for path in entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+        {
+            if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+                continue;
+            }
+            let locale = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(stem) => Locale::new(stem).0,
+                None => continue,
+            };
+
+            let formats = fs::read_to_string(&path)
+                .map_err(failure::Error::from)
+                .and_then(|json| {
+                    serde_json::from_str::<HashMap<String, String>>(&json)
+                        .map_err(failure::Error::from)
+                });
+            match formats {
+                Ok(formats) => translations.add(&locale, formats),
+                Err(e) => error!("Failed to load {}: {}", path.display(), e),
+            }
+        }
+
+        translations
+    }
+
+    /// Adds translations to a language, replacing existing ones.
+    pub fn add(&mut self, locale: &str, formats: HashMap<String, String>) {
+        self.languages
+            .entry(locale.to_string())
+            .or_default()
+            .extend(formats);
+    }
+
+    /// Returns the format of a key in the given locale,
+    /// or in the default locale if it has none.
+    pub fn get(&self, locale: &str, key: &str) -> Option<&str> {
+        [locale, DEFAULT_LOCALE]
+            .iter()
+            .filter_map(|locale| self.languages.get(*locale))
+            .find_map(|formats| formats.get(key))
+            .map(String::as_str)
+    }
+
+    /// Renders the translations in a component in the
+    /// language of the player who will receive it.
+    pub fn render_for(
+        &self,
+        world: &World,
+        player: Entity,
+        component: &TextComponent,
+    ) -> TextComponent {
+        let locale = world.get_component::<Locale>(player);
+        let locale = locale
+            .as_ref()
+            .map_or(DEFAULT_LOCALE, |locale| locale.0.as_str());
+        self.render(locale, component)
+    }
+
+    /// Renders the translations in a component which the
+    /// catalogue knows in the given locale. Other translations
+    /// are kept, so that clients translate them instead.
+    pub fn render(&self, locale: &str, component: &TextComponent) -> TextComponent {
+        let mut component = component.clone();
+        self.render_in_place(locale, &mut component);
+        component
+    }
+
+    fn render_in_place(&self, locale: &str, component: &mut TextComponent) {
+        for child in &mut component.extra {
+            self.render_in_place(locale, child);
+        }
+
+        let parts = match &mut component.content {
+            Content::Translate { translate, with } => {
+                for arg in with.iter_mut() {
+                    self.render_in_place(locale, arg);
+                }
+                self.get(locale, translate)
+                    .map(|format| substitute(format, with))
+            }
+            _ => None,
+        };
+
+        // The rendered text replaces the translation, keeping
+        // its style, and comes before any existing children.
+        if let Some(mut parts) = parts {
+            component.content = Content::default();
+            parts.extend(mem::take(&mut component.extra));
+            component.extra = parts;
+        }
+    }
+}
+
+/// Splits a format into literal text and the arguments
+/// replacing its placeholders, the way clients do.
+fn substitute(format: &str, args: &[TextComponent]) -> Vec<TextComponent> {
+    let mut parts = vec![];
+    let mut literal = String::new();
+    let mut next_arg = 0;
+    let mut rest = format;
+
+    while let Some(start) = rest.find('%') {
+        literal.push_str(&rest[..start]);
+        let spec = &rest[start + 1..];
+
+        let index = if spec.starts_with('%') {
+            literal.push('%');
+            rest = &spec[1..];
+            continue;
+        } else if spec.starts_with('s') {
+            rest = &spec[1..];
+            next_arg += 1;
+            next_arg - 1
+        } else {
+            let position = spec
+                .find("$s")
+                .and_then(|end| spec[..end].parse::<usize>().ok().map(|n| (n, end)));
+            match position {
+                Some((n, end)) if n > 0 => {
+                    rest = &spec[end + 2..];
+                    n - 1
+                }
+                _ => {
+                    literal.push('%');
+                    rest = spec;
+                    continue;
+                }
+            }
+        };
+
+        if !literal.is_empty() {
+            parts.push(TextComponent::text(mem::take(&mut literal)));
+        }
+        parts.push(args.get(index).cloned().unwrap_or_default());
+    }
+
+    literal.push_str(rest);
+    if !literal.is_empty() {
+        parts.push(TextComponent::text(literal));
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::text::Color;
+
+    fn translations() -> Translations {
+        let mut translations = Translations::builtin();
+        let mut german = HashMap::new();
+        german.insert(
+            "feather.chat.muted".to_string(),
+            "Du bist stumm geschaltet. Grund: %s".to_string(),
+        );
+        translations.add("de_de", german);
+        translations
+    }
+
+    #[test]
+    fn test_substitute() {
+        let args = vec![TextComponent::text("a"), TextComponent::text("b")];
+        let plain = |format| {
+            TextComponent::text("")
+                .children(substitute(format, &args))
+                .to_plain()
+        };
+
+        assert_eq!(plain("%s and %s"), "a and b");
+        assert_eq!(plain("%2$s before %1$s"), "b before a");
+        assert_eq!(plain("100%% %s"), "100% a");
+        assert_eq!(plain("%s %s %s"), "a b ");
+        assert_eq!(plain("50% off"), "50% off");
+    }
+
+    #[test]
+    fn test_render() {
+        let translations = translations();
+        let message =
+            TextComponent::translate("feather.chat.muted", vec![TextComponent::text("Spam")])
+                .color(Color::Red);
+
+        let rendered = translations.render("de_de", &message);
+        assert_eq!(rendered.to_plain(), "Du bist stumm geschaltet. Grund: Spam");
+        assert_eq!(rendered.style.color, Some(Color::Red));
+
+        // Missing languages fall back to the built-in messages.
+        let rendered = translations.render("fr_fr", &message);
+        assert_eq!(rendered.to_plain(), "You are muted. Reason: Spam");
+
+        // Keys the server doesn't know are translated by clients.
+        let vanilla = TextComponent::translate("chat.type.text", vec![message]);
+        let rendered = translations.render("de_de", &vanilla);
+        match &rendered.content {
+            Content::Translate { translate, with } => {
+                assert_eq!(translate, "chat.type.text");
+                assert_eq!(with[0].to_plain(), "Du bist stumm geschaltet. Grund: Spam");
+            }
+            content => panic!("expected a translation, got {:?}", content),
+        }
+    }
+}
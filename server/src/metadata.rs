@@ -67,4 +67,24 @@ entity_metadata! {
     FallingBlock: Entity {
         spawn_position: BlockPosition() = 6,
     },
+    PrimedTnt: Entity {
+        fuse_time: VarInt(80) = 6,
+    },
+    Boat: Entity {
+        time_since_hit: VarInt() = 6,
+        forward_direction: VarInt(1) = 7,
+        damage_taken: f32() = 8,
+        boat_type: VarInt() = 9,
+        right_paddle_turning: bool() = 10,
+        left_paddle_turning: bool() = 11,
+        splash_timer: VarInt() = 12,
+    },
+    Minecart: Entity {
+        shaking_power: VarInt() = 6,
+        shaking_direction: VarInt(1) = 7,
+        shaking_multiplier: f32() = 8,
+        custom_block: VarInt() = 9,
+        custom_block_offset: VarInt(6) = 10,
+        show_custom_block: bool() = 11,
+    },
 }
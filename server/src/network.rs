@@ -6,12 +6,18 @@
 //! from players and allows systems to poll for packets
 //! received of a given type.
 
+use crate::access::AccessLists;
 use crate::entity::{EntityDeleteEvent, EntityId};
 use crate::io::{ListenerToServerMessage, NetworkIoManager, ServerToWorkerMessage};
+use crate::locale::{Translations, DEFAULT_LOCALE};
+use crate::permissions::Permissions;
 use crate::player;
+use crate::shutdown;
 use crate::state::State;
+use crate::worlds::DimensionId;
 use crossbeam::Receiver;
 use feather_core::network::cast_packet;
+use feather_core::network::packet::implementation::DisconnectPlay;
 use feather_core::{Packet, PacketType, Position};
 use futures::channel::mpsc::UnboundedSender;
 use legion::entity::Entity;
@@ -167,11 +173,15 @@ impl Network {
 /// The network system. This system is responsible for:
 /// * Handling player disconnects.
 /// * Pushing received packets to the packet queue.
-/// * Accepting new clients and creating entities for them.
+/// * Accepting new clients and creating entities for them,
+/// unless they are banned or not whitelisted.
 #[system]
 pub fn network_(
     state: &State,
     io: &NetworkIoManager,
+    access: &mut AccessLists,
+    permissions: &mut Permissions,
+    translations: &Translations,
     packet_queue: &PacketQueue,
     query: &mut Query<Read<Network>>,
     world: &mut PreparedWorld,
@@ -182,12 +192,16 @@ pub fn network_(
             match msg {
                 ServerToWorkerMessage::NotifyDisconnect(_) => {
                     state.exec_with_scheduler(move |world, scheduler| {
+                        shutdown::save_player(world, scheduler.resources(), entity);
+
                         let position = *world.get_component::<Position>(entity).unwrap();
+                        let dimension = *world.get_component::<DimensionId>(entity).unwrap();
                         let id = *world.get_component::<EntityId>(entity).unwrap();
                         let uuid = *world.get_component::<Uuid>(entity).unwrap();
                         scheduler.trigger(EntityDeleteEvent {
                             entity,
                             position: Some(position),
+                            dimension: Some(dimension),
                             id,
                             uuid,
                         });
@@ -207,6 +221,25 @@ pub fn network_(
         match msg {
             ListenerToServerMessage::NewClient(info) => {
                 debug!("Server received connection from {}", info.username);
+                if let Err(reason) = access.check_join(info.uuid, &info.username) {
+                    // Clients only report their language after joining.
+                    let reason = translations.render(DEFAULT_LOCALE, &reason);
+                    info!(
+                        "Refused {} ({}): {}",
+                        info.username,
+                        info.ip,
+                        reason.to_plain()
+                    );
+                    let packet = DisconnectPlay { reason };
+                    let _ = info
+                        .sender
+                        .unbounded_send(ServerToWorkerMessage::SendPacket(Box::new(packet)));
+                    let _ = info
+                        .sender
+                        .unbounded_send(ServerToWorkerMessage::Disconnect);
+                    continue;
+                }
+                permissions.update_profile(info.uuid, &info.username);
                 player::create(state, info);
             }
         }
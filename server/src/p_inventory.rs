@@ -35,6 +35,18 @@ impl EntityInventory {
         self.inventory
             .set_item_at(SLOT_HOTBAR_OFFSET + self.held_item, item);
     }
+
+    /// Removes one item from the stack in this inventory's
+    /// main hand and returns the slot which changed.
+    pub fn consume_item_in_main_hand(&mut self) -> SlotIndex {
+        let slot = SLOT_HOTBAR_OFFSET + self.held_item;
+        if let Some(stack) = self.inventory.item_at(slot).copied() {
+            let amount = stack.amount.saturating_sub(1);
+            self.inventory
+                .set_item_at(slot, ItemStack::new(stack.ty, amount));
+        }
+        slot
+    }
 }
 
 impl Default for EntityInventory {
@@ -1,16 +1,29 @@
+use crate::command::{CommandEvent, CommandSender};
 use crate::network::PacketQueue;
 use crate::player::chat::PlayerChatEvent;
 use feather_core::network::packet::implementation::ChatMessageServerbound;
 use tonks::Trigger;
 
-/// Handles animation packets.
+/// Handles chat packets. Messages beginning
+/// with a slash are treated as commands.
 #[system]
-fn handle_chat(queue: &PacketQueue, trigger: &mut Trigger<PlayerChatEvent>) {
+fn handle_chat(
+    queue: &PacketQueue,
+    trigger: &mut Trigger<PlayerChatEvent>,
+    command_trigger: &mut Trigger<CommandEvent>,
+) {
     queue
         .received::<ChatMessageServerbound>()
         .for_each(|(player, packet)| {
             let message = packet.message;
 
-            trigger.trigger(PlayerChatEvent { player, message });
+            if message.starts_with('/') {
+                command_trigger.trigger(CommandEvent {
+                    sender: CommandSender::Player(player),
+                    command: message[1..].to_string(),
+                });
+            } else {
+                trigger.trigger(PlayerChatEvent { player, message });
+            }
         });
 }
@@ -0,0 +1,32 @@
+//! Handling of Client Settings packets, which clients
+//! send when joining and when their settings change.
+
+use crate::locale::Locale;
+use crate::network::PacketQueue;
+use crate::player::chat::{ChatMode, ChatSettings};
+use feather_core::network::packet::implementation::ClientSettings;
+use legion::query::Write;
+use tonks::{PreparedWorld, Query};
+
+/// Handles Client Settings packets, storing
+/// the player's chat settings and language.
+#[system]
+fn handle_client_settings(
+    queue: &PacketQueue,
+    _query: &mut Query<(Write<ChatSettings>, Write<Locale>)>,
+    world: &mut PreparedWorld,
+) {
+    queue
+        .received::<ClientSettings>()
+        .for_each(|(player, packet)| {
+            if let Some(mut settings) = world.get_component_mut::<ChatSettings>(player) {
+                *settings = ChatSettings {
+                    mode: ChatMode::from_id(packet.chat_mode),
+                    colors: packet.chat_colors,
+                };
+            }
+            if let Some(mut locale) = world.get_component_mut::<Locale>(player) {
+                *locale = Locale::new(&packet.locale);
+            }
+        });
+}
@@ -4,18 +4,27 @@
 //! for actions mostly unrelated to digging including eating, shooting bows,
 //! swapping items out to the offhand, and dropping items.
 
+use crate::access::AccessLists;
 use crate::block::BlockUpdateCause;
 use crate::entity::item::ItemDropEvent;
-use crate::network::PacketQueue;
+use crate::entity::Name;
+use crate::network::{Network, PacketQueue};
 use crate::p_inventory::{EntityInventory, InventoryUpdateEvent};
-use crate::state::State;
+use crate::permissions::{self, Permissions};
+use crate::player::bow::{self, BowCharge};
+use crate::state::{DimensionState, State};
 use crate::util::disconnect_player;
+use crate::worlds::DimensionId;
+use crate::TickCount;
 use feather_core::inventory::SLOT_HOTBAR_OFFSET;
-use feather_core::network::packet::implementation::{PlayerDigging, PlayerDiggingStatus};
-use feather_core::{Block, Gamemode, Item, ItemStack, Position};
+use feather_core::network::packet::implementation::{
+    BlockChange, PlayerDigging, PlayerDiggingStatus,
+};
+use feather_core::{Block, BlockExt, Gamemode, Item, ItemStack, Position, TextComponent};
 use legion::entity::Entity;
 use legion::query::{Read, Write};
 use tonks::{PreparedWorld, Query, Trigger};
+use uuid::Uuid;
 
 /// System responsible for polling for PlayerDigging
 /// packets and writing the corresponding events.
@@ -23,10 +32,22 @@ use tonks::{PreparedWorld, Query, Trigger};
 fn handle_player_digging(
     state: &State,
     queue: &PacketQueue,
-    _query: &mut Query<(Write<EntityInventory>, Read<Position>, Read<Gamemode>)>,
+    _query: &mut Query<(
+        Write<EntityInventory>,
+        Read<Position>,
+        Read<DimensionId>,
+        Read<Gamemode>,
+        Write<BowCharge>,
+        Read<Uuid>,
+        Read<Name>,
+        Read<Network>,
+    )>,
     world: &mut PreparedWorld,
     inventory_updates: &mut Trigger<InventoryUpdateEvent>,
     item_drops: &mut Trigger<ItemDropEvent>,
+    tick_count: &TickCount,
+    access: &AccessLists,
+    permissions: &Permissions,
 ) {
     use PlayerDiggingStatus::*;
 
@@ -34,16 +55,26 @@ fn handle_player_digging(
 
     for (player, packet) in packets {
         let gamemode = *world.get_component::<Gamemode>(player).unwrap();
+        let dimension = *world.get_component::<DimensionId>(player).unwrap();
+        let state = &state.dimension(dimension);
         let mut inventory = world.get_component_mut::<EntityInventory>(player).unwrap();
 
         match packet.status {
-            StartedDigging | FinishedDigging | CancelledDigging => handle_digging(
-                packet,
-                state,
-                player,
-                gamemode,
-                inventory.item_in_main_hand(),
-            ),
+            StartedDigging | FinishedDigging | CancelledDigging => {
+                let uuid = *world.get_component::<Uuid>(player).unwrap();
+                let name = world.get_component::<Name>(player).unwrap();
+                let network = world.get_component::<Network>(player).unwrap();
+                let can_build = permissions.has(access, uuid, &name.0, permissions::BUILD, 0);
+                handle_digging(
+                    packet,
+                    state,
+                    player,
+                    &network,
+                    gamemode,
+                    can_build,
+                    inventory.item_in_main_hand(),
+                )
+            }
             DropItem | DropItemStack => handle_drop_item_stack(
                 packet,
                 player,
@@ -51,17 +82,23 @@ fn handle_player_digging(
                 item_drops,
                 &mut inventory,
             ),
-            /*
-            ConsumeItem => handle_consume_item(
-                packet,
-                players.get(player).unwrap(),
-                player,
-                inventories.get_mut(player).unwrap(),
-                &mut inventory_updates,
-                positions.get(player).unwrap().current,
-                &mut shoot_arrow_events,
-            ),
-            */
+            ConsumeItem => {
+                let charge = world
+                    .get_component_mut::<BowCharge>(player)
+                    .and_then(|mut charge| charge.0.take());
+                let position = *world.get_component::<Position>(player).unwrap();
+                let uuid = *world.get_component::<Uuid>(player).unwrap();
+                handle_consume_item(
+                    state,
+                    player,
+                    uuid,
+                    gamemode,
+                    position,
+                    &mut inventory,
+                    charge.map(|start| tick_count.0 - start),
+                    inventory_updates,
+                )
+            }
             status => warn!("Unhandled Player Digging status {:?}", status),
         }
     }
@@ -69,9 +106,11 @@ fn handle_player_digging(
 
 fn handle_digging(
     packet: PlayerDigging,
-    state: &State,
+    state: &DimensionState,
     player: Entity,
+    network: &Network,
     gamemode: Gamemode,
+    can_build: bool,
     item_in_main_hand: Option<&ItemStack>,
 ) {
     // Return early if needed
@@ -99,12 +138,27 @@ fn handle_digging(
         }
     }
 
+    if !can_build {
+        // The client has already broken the block.
+        if let Some(block) = state.block_at(packet.location) {
+            network.send(BlockChange {
+                location: packet.location,
+                block_id: block.native_state_id() as i32,
+            });
+        }
+        return;
+    }
+
     if !state.set_block_at(
         packet.location,
         Block::Air,
         BlockUpdateCause::Player(player),
     ) {
-        disconnect_player(state, player, "Attempted to break block in unloaded chunk");
+        disconnect_player(
+            state,
+            player,
+            TextComponent::translate("feather.disconnect.breakUnloaded", vec![]),
+        );
         return;
     }
 }
@@ -168,110 +222,35 @@ fn handle_drop_item_stack(
     }
 }
 
-/*
 /// Handles food consumption and shooting arrows.
+///
+/// `bow_charge` is the number of ticks for which
+/// the player has been drawing a bow, if any.
+#[allow(clippy::too_many_arguments)]
 fn handle_consume_item(
-    packet: &PlayerDigging,
-    player: &PlayerComponent,
-    entity: Entity,
-    inventory: &mut InventoryComponent,
-    inventory_updates: &mut EventChannel<InventoryUpdateEvent>,
+    state: &DimensionState,
+    player: Entity,
+    uuid: Uuid,
+    gamemode: Gamemode,
     position: Position,
-    shoot_arrow_events: &mut EventChannel<ShootArrowEvent>,
+    inventory: &mut EntityInventory,
+    bow_charge: Option<u64>,
+    inventory_updates: &mut Trigger<InventoryUpdateEvent>,
 ) {
-    assert_eq!(packet.status, PlayerDiggingStatus::ConsumeItem);
-
     // TODO: Fallback to off-hand if main-hand is not a consumable
-    let used_item = inventory.item_in_main_hand();
-
-    if let Some(item) = used_item {
-        if item.ty == Item::Bow {
-            handle_shoot_bow(
-                player,
-                entity,
-                inventory,
-                inventory_updates,
-                position,
-                shoot_arrow_events,
-            );
-        }
-        // TODO: Food, potions
-    }
-}
-
-fn handle_shoot_bow(
-    player: &PlayerComponent,
-    entity: Entity,
-    inventory: &mut InventoryComponent,
-    inventory_updates: &mut EventChannel<InventoryUpdateEvent>,
-    position: Position,
-    shoot_arrow_events: &mut EventChannel<ShootArrowEvent>,
-) {
-    let arrow_to_consume: Option<(SlotIndex, ItemStack)> = find_arrow(&inventory);
-    if player.gamemode == Gamemode::Survival || player.gamemode == Gamemode::Adventure {
-        // If no arrow was found, don't shoot
-        let arrow_to_consume = arrow_to_consume.clone();
-        if arrow_to_consume.is_none() {
-            debug!("Tried to shoot bow with no arrows.");
-            return;
-        }
-
-        // Consume arrow
-        let (arrow_slot, arrow_stack) = arrow_to_consume.unwrap();
-        let mut arrow_stack: ItemStack = arrow_stack;
-        arrow_stack.amount -= 1;
-
-        inventory.set_item_at(arrow_slot, arrow_stack);
-        inventory_updates.single_write(InventoryUpdateEvent {
-            slots: smallvec![arrow_slot],
-            player: entity,
-        });
-    }
-
-    let arrow_type: Item = match arrow_to_consume {
-        None => Item::Arrow, // Default to generic arrow in creative mode with none in inventory
-        Some((_, arrow_stack)) => arrow_stack.ty,
-    };
-
-    shoot_arrow_events.single_write(ShootArrowEvent {
-        shooter: Some(entity),
-        position,
-        arrow_type,
-        critical: false, // TODO: Determine critical based on how long bow was pulled back
-    });
-}
-
-fn find_arrow(inventory: &InventoryComponent) -> Option<(SlotIndex, ItemStack)> {
-    // Order of priority is: off-hand, hotbar (0 to 8), rest of inventory
-
-    if let Some(offhand) = inventory.item_at(SLOT_OFFHAND) {
-        if is_arrow_item(offhand.ty) {
-            return Some((SLOT_OFFHAND, offhand.clone()));
-        }
-    }
-
-    for hotbar_slot in 0..9 {
-        if let Some(hotbar_stack) = inventory.item_at(SLOT_HOTBAR_OFFSET + hotbar_slot) {
-            if is_arrow_item(hotbar_stack.ty) {
-                return Some((SLOT_HOTBAR_OFFSET + hotbar_slot, hotbar_stack.clone()));
-            }
-        }
-    }
-
-    for inv_slot in 9..=35 {
-        if let Some(inv_stack) = inventory.item_at(inv_slot) {
-            if is_arrow_item(inv_stack.ty) {
-                return Some((inv_slot, inv_stack.clone()));
-            }
-        }
-    }
-    None
-}
+    let used_item = inventory.item_in_main_hand().map(|stack| stack.ty);
 
-fn is_arrow_item(item: Item) -> bool {
-    match item {
-        Item::Arrow | Item::SpectralArrow | Item::TippedArrow => true,
-        _ => false,
+    // TODO: Food, potions
+    if let (Some(Item::Bow), Some(ticks)) = (used_item, bow_charge) {
+        bow::shoot(
+            state,
+            player,
+            uuid,
+            gamemode,
+            position,
+            inventory,
+            ticks,
+            inventory_updates,
+        );
     }
 }
-*/
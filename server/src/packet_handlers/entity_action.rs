@@ -0,0 +1,46 @@
+//! Handling of entity action packets.
+
+use crate::entity::EntityId;
+use crate::network::PacketQueue;
+use crate::player::sleep::{wake_up, Sleeping};
+use crate::player::Sneaking;
+use crate::state::State;
+use crate::worlds::DimensionId;
+use feather_core::network::packet::implementation::{EntityAction, EntityActionType};
+use legion::query::{Read, Write};
+use tonks::{PreparedWorld, Query};
+
+/// Handles entity action packets.
+#[system]
+fn handle_entity_action(
+    state: &State,
+    queue: &PacketQueue,
+    _query: &mut Query<(
+        Read<EntityId>,
+        Read<DimensionId>,
+        Write<Sneaking>,
+        Write<Sleeping>,
+    )>,
+    world: &mut PreparedWorld,
+) {
+    for (player, packet) in queue.received::<EntityAction>() {
+        let sneaking = match packet.action_id {
+            EntityActionType::StartSneaking => true,
+            EntityActionType::StopSneaking => false,
+            EntityActionType::LeaveBed => {
+                let id = *world.get_component::<EntityId>(player).unwrap();
+                let dimension = *world.get_component::<DimensionId>(player).unwrap();
+                if let Some(mut sleeping) = world.get_component_mut::<Sleeping>(player) {
+                    wake_up(state, dimension, player, id, &mut sleeping);
+                }
+                continue;
+            }
+            // TODO: sprinting, elytra flight, horses
+            _ => continue,
+        };
+
+        if let Some(mut component) = world.get_component_mut::<Sneaking>(player) {
+            component.0 = sneaking;
+        }
+    }
+}
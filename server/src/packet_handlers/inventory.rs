@@ -10,7 +10,7 @@ use feather_core::inventory::{HOTBAR_SIZE, SLOT_HOTBAR_OFFSET};
 use feather_core::network::packet::implementation::{
     CreativeInventoryAction, HeldItemChangeServerbound,
 };
-use feather_core::Gamemode;
+use feather_core::{Gamemode, TextComponent};
 use legion::prelude::Read;
 use legion::query::Write;
 use tonks::{PreparedWorld, Query, Trigger};
@@ -35,7 +35,7 @@ fn handle_creative_inventory_action(
             disconnect_player(
                 state,
                 player,
-                "Attempted to use Creative Inventory Action while not in creative mode",
+                TextComponent::translate("feather.disconnect.creativeAction", vec![]),
             );
             continue;
         }
@@ -63,7 +63,11 @@ fn handle_creative_inventory_action(
         }
 
         if packet.slot >= inventory.slot_count() as i16 || packet.slot < -1 {
-            disconnect_player(state, player, "Slot index out of bounds");
+            disconnect_player(
+                state,
+                player,
+                TextComponent::translate("feather.disconnect.slotOutOfBounds", vec![]),
+            );
             continue;
         }
 
@@ -98,7 +102,11 @@ fn handle_held_item_change(
 
     for (player, packet) in packets {
         if packet.slot as usize >= HOTBAR_SIZE {
-            disconnect_player(state, player, "Hotbar index out of bounds");
+            disconnect_player(
+                state,
+                player,
+                TextComponent::translate("feather.disconnect.hotbarOutOfBounds", vec![]),
+            );
             continue;
         }
 
@@ -2,7 +2,13 @@
 
 mod animation;
 mod chat;
+mod client_settings;
 mod digging;
+mod entity_action;
 mod inventory;
 mod movement;
 mod placement;
+mod tab_complete;
+mod use_entity;
+mod use_item;
+mod vehicle;
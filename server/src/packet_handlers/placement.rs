@@ -1,61 +1,210 @@
 //! Handling of player block placement packets.
 
+use crate::access::AccessLists;
+use crate::block::direction::Direction;
+use crate::block::interaction::{changes_blocks, interact, Interaction};
+use crate::block::placement::{can_place_into, resolve_placement, PlacementContext};
+use crate::block::tick::BlockTicks;
 use crate::block::BlockUpdateCause;
-use crate::network::PacketQueue;
+use crate::entity::Name;
+use crate::network::{Network, PacketQueue};
 use crate::p_inventory::{EntityInventory, InventoryUpdateEvent};
-use crate::state::State;
+use crate::permissions::{self, Permissions};
+use crate::player::Sneaking;
+use crate::redstone::{ComparatorOutputs, Redstone};
+use crate::state::{DimensionState, State};
 use crate::util::disconnect_player;
+use crate::vehicle;
+use crate::world_border::WorldBorders;
+use crate::worlds::DimensionId;
+use crate::TickCount;
 use feather_core::inventory::SLOT_HOTBAR_OFFSET;
-use feather_core::network::packet::implementation::PlayerBlockPlacement;
-use feather_core::{Block, Gamemode, ItemStack};
+use feather_core::network::packet::implementation::{BlockChange, PlayerBlockPlacement};
+use feather_core::{BlockExt, BlockPosition, Gamemode, ItemStack, Position, TextComponent};
 use feather_item_block::ItemToBlock;
 use legion::query::{Read, Write};
 use tonks::{PreparedWorld, Query, Trigger};
+use uuid::Uuid;
 
 /// System for handling Player Block Placement packets
 /// and updating the world accordingly.
+///
+/// If the clicked block can be interacted with, the
+/// interaction is performed instead of placing a block.
+/// Players without the build permission may use blocks
+/// such as doors and levers, but not place or change them.
 #[system]
 fn handle_player_block_placement(
     state: &State,
     queue: &PacketQueue,
-    _query: &mut Query<(Write<EntityInventory>, Read<Gamemode>)>,
+    _query: &mut Query<(
+        Write<EntityInventory>,
+        Read<Gamemode>,
+        Read<Position>,
+        Read<DimensionId>,
+        Read<Sneaking>,
+        Read<Uuid>,
+        Read<Name>,
+        Read<Network>,
+    )>,
     world: &mut PreparedWorld,
     inventory_update_events: &mut Trigger<InventoryUpdateEvent>,
+    ticks: &mut BlockTicks,
+    tick_count: &TickCount,
+    comparators: &mut ComparatorOutputs,
+    borders: &WorldBorders,
+    access: &AccessLists,
+    permissions: &Permissions,
 ) {
     let packets = queue.received::<PlayerBlockPlacement>();
 
     for (player, packet) in packets {
-        // TODO: handle slabs, blocks with directions, etc.
         let gamemode = *world.get_component::<Gamemode>(player).unwrap();
+        let player_pos = *world.get_component::<Position>(player).unwrap();
+        let dimension = *world.get_component::<DimensionId>(player).unwrap();
+        let state = &state.dimension(dimension);
+        let sneaking = world.get_component::<Sneaking>(player).unwrap().0;
+        let uuid = *world.get_component::<Uuid>(player).unwrap();
+        let can_build = {
+            let name = world.get_component::<Name>(player).unwrap();
+            permissions.has(access, uuid, &name.0, permissions::BUILD, 0)
+        };
         let mut inventory = world.get_component_mut::<EntityInventory>(player).unwrap();
 
+        // Sneaking players holding an item skip
+        // the interaction and place the item instead.
+        let holding_item = inventory.item_in_main_hand().is_some();
+        if packet.hand == 0 && !(sneaking && holding_item) {
+            let interaction = Interaction {
+                player,
+                gamemode,
+                yaw: player_pos.yaw,
+                pos: packet.location,
+                face: Direction::from_face(packet.face),
+                item: inventory.item_in_main_hand().map(|stack| stack.ty),
+            };
+
+            // Players without the build permission may use
+            // blocks, but not change them, such as by lighting TNT.
+            let allowed = can_build
+                || state
+                    .block_at(packet.location)
+                    .map_or(true, |block| !changes_blocks(block, interaction.item));
+
+            if allowed {
+                let mut redstone = Redstone::new(*state, ticks, tick_count, comparators);
+                let border = borders.get(dimension.world);
+                if interact(&mut redstone, state, border, &interaction) {
+                    continue;
+                }
+            }
+        }
+
+        // The client has already placed the block, at either
+        // the clicked position or the one next to it, so
+        // rejected placements have to be undone.
+        let predicted = [
+            packet.location,
+            packet.location + packet.face.placement_offset(),
+        ];
+
+        if !can_build {
+            let network = world.get_component::<Network>(player).unwrap();
+            resend_blocks(state, &network, &predicted);
+            continue;
+        }
+
         let item = match inventory.item_in_main_hand() {
             Some(item) => item,
             None => continue, // No block to place
         };
 
+        // Minecarts are placed on the clicked rail.
+        if packet.hand == 0 && vehicle::place(state, item.ty, packet.location) {
+            if gamemode == Gamemode::Survival {
+                let slot = inventory.consume_item_in_main_hand();
+                inventory_update_events.trigger(InventoryUpdateEvent {
+                    slots: smallvec![slot],
+                    player,
+                });
+            }
+            continue;
+        }
+
         let block = match item.ty.to_block() {
             Some(block) => block,
-            None => continue, // Item is not a block
+            None => {
+                // Item is not a block, but the client may have used it
+                // anyway, such as to light fire outside the world border.
+                let network = world.get_component::<Network>(player).unwrap();
+                resend_blocks(state, &network, &predicted);
+                continue;
+            }
         };
 
         let placed_on = match state.block_at(packet.location) {
             Some(block) => block,
             None => {
-                disconnect_player(state, player, "Attempted to place block in unloaded chunk");
+                disconnect_player(
+                    state,
+                    player,
+                    TextComponent::translate("feather.disconnect.placeUnloaded", vec![]),
+                );
                 continue;
             }
         };
 
-        // TODO: waterlogged blocks, more
-        let pos = match placed_on {
-            Block::Grass | Block::TallGrass(_) | Block::Water(_) | Block::Lava(_) => {
-                packet.location
+        let mut ctx = PlacementContext {
+            pos: packet.location,
+            face: Direction::from_face(packet.face),
+            replacing_clicked: true,
+            cursor: (
+                packet.cursor_position_x,
+                packet.cursor_position_y,
+                packet.cursor_position_z,
+            ),
+            yaw: player_pos.yaw,
+            pitch: player_pos.pitch,
+        };
+
+        // Blocks such as grass and water are replaced
+        // by the placed block; otherwise, it is placed
+        // next to the clicked face.
+        if !can_place_into(placed_on, block, &ctx) {
+            ctx.pos = packet.location + packet.face.placement_offset();
+            ctx.replacing_clicked = false;
+
+            match state.block_at(ctx.pos) {
+                Some(existing) if can_place_into(existing, block, &ctx) => (),
+                _ => {
+                    let network = world.get_component::<Network>(player).unwrap();
+                    resend_blocks(state, &network, &predicted);
+                    continue;
+                }
+            }
+        }
+
+        let placement = match resolve_placement(state, block, &ctx) {
+            Some(placement) => placement,
+            None => {
+                let network = world.get_component::<Network>(player).unwrap();
+                resend_blocks(state, &network, &predicted);
+                continue;
             }
-            _ => packet.location + packet.face.placement_offset(),
         };
 
-        state.set_block_at(pos, block, BlockUpdateCause::Player(player));
+        if placement
+            .iter()
+            .any(|(pos, _)| !borders.get(dimension.world).contains_block(*pos))
+        {
+            let network = world.get_component::<Network>(player).unwrap();
+            resend_blocks(state, &network, &predicted);
+            continue;
+        }
+
+        for (pos, block) in placement {
+            state.set_block_at(pos, block, BlockUpdateCause::Player(player));
+        }
 
         // Update player's inventory if in survival
         if gamemode == Gamemode::Survival {
@@ -63,7 +212,7 @@ fn handle_player_block_placement(
                 disconnect_player(
                     state,
                     player,
-                    "Attempted to place block with 0-sized item stack",
+                    TextComponent::translate("feather.disconnect.placeEmptyStack", vec![]),
                 );
             }
 
@@ -78,3 +227,16 @@ fn handle_player_block_placement(
         }
     }
 }
+
+/// Sends the actual blocks at the given positions
+/// to a player whose placement was rejected.
+fn resend_blocks(state: &DimensionState, network: &Network, positions: &[BlockPosition]) {
+    for pos in positions {
+        if let Some(block) = state.block_at(*pos) {
+            network.send(BlockChange {
+                location: *pos,
+                block_id: block.native_state_id() as i32,
+            });
+        }
+    }
+}
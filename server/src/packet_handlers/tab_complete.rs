@@ -0,0 +1,68 @@
+//! Handling of Tab-Complete packets, which clients send
+//! to complete command arguments such as player names.
+
+use crate::access::AccessLists;
+use crate::command::{CommandDispatcher, SuggestionCtx};
+use crate::entity::Name;
+use crate::network::{Network, PacketQueue};
+use crate::permissions::Permissions;
+use crossbeam::queue::SegQueue;
+use feather_core::network::packet::implementation::{
+    TabCompleteClientbound, TabCompleteMatch, TabCompleteServerbound,
+};
+use legion::query::Read;
+use tonks::{PreparedWorld, Query};
+use uuid::Uuid;
+
+/// Handles Tab-Complete packets.
+#[system]
+fn handle_tab_complete(
+    queue: &PacketQueue,
+    dispatcher: &CommandDispatcher,
+    access: &AccessLists,
+    permissions: &Permissions,
+    query: &mut Query<(Read<Name>, Read<Uuid>, Read<Network>)>,
+    world: &mut PreparedWorld,
+) {
+    let packets: Vec<_> = queue.received::<TabCompleteServerbound>().collect();
+    if packets.is_empty() {
+        return;
+    }
+
+    let names = SegQueue::new();
+    query.par_entities_for_each(world, |(_, (name, _, _))| {
+        names.push(name.0.clone());
+    });
+    let mut ctx = SuggestionCtx::default();
+    while let Ok(name) = names.pop() {
+        ctx.player_names.push(name);
+    }
+
+    for (player, packet) in packets {
+        if !packet.text.starts_with('/') {
+            continue;
+        }
+
+        let uuid = *world.get_component::<Uuid>(player).unwrap();
+        let name = world.get_component::<Name>(player).unwrap();
+        ctx.permissions = permissions.for_player(access, uuid, &name.0);
+
+        let suggestions = dispatcher.suggest(&packet.text[1..], &ctx);
+        let start = suggestions.start + 1;
+
+        let network = world.get_component::<Network>(player).unwrap();
+        network.send(TabCompleteClientbound {
+            transaction_id: packet.transaction_id,
+            start: start as i32,
+            length: (packet.text.len() - start) as i32,
+            matches: suggestions
+                .matches
+                .into_iter()
+                .map(|text| TabCompleteMatch {
+                    text,
+                    tooltip: None,
+                })
+                .collect(),
+        });
+    }
+}
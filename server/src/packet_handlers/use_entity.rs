@@ -0,0 +1,80 @@
+//! Handling of the Use Entity packet, which is sent
+//! when a player right- or left-clicks an entity.
+
+use crate::entity::EntityId;
+use crate::metadata::Metadata;
+use crate::network::PacketQueue;
+use crate::physics::nearby_entities;
+use crate::state::State;
+use crate::vehicle::{self, Vehicle};
+use crate::worlds::DimensionId;
+use crate::TickCount;
+use feather_core::network::packet::implementation::{UseEntity, UseEntityType};
+use feather_core::{Gamemode, Position};
+use legion::entity::Entity;
+use legion::query::{Read, Write};
+use tonks::{PreparedWorld, Query};
+
+/// The maximum distance from which players can use entities.
+const REACH_DISTANCE: f64 = 6.0;
+
+/// Handles Use Entity packets.
+///
+/// TODO: attacking players and mobs
+#[system]
+fn handle_use_entity(
+    state: &State,
+    queue: &PacketQueue,
+    _query: &mut Query<(
+        Read<Position>,
+        Read<DimensionId>,
+        Read<EntityId>,
+        Read<Gamemode>,
+        Write<Vehicle>,
+        Write<Metadata>,
+    )>,
+    world: &mut PreparedWorld,
+    tick_count: &TickCount,
+) {
+    for (player, packet) in queue.received::<UseEntity>() {
+        let target = match find_target(state, world, player, packet.target) {
+            Some(target) => target,
+            None => continue,
+        };
+
+        if world.get_component::<Vehicle>(target).is_none() {
+            continue;
+        }
+
+        match packet.ty {
+            UseEntityType::Interact => vehicle::mount(state, player, target),
+            UseEntityType::Attack => {
+                let gamemode = *world.get_component::<Gamemode>(player).unwrap();
+                vehicle::attack(state, world, target, gamemode, tick_count.0);
+            }
+            // Clients send Interact along with Interact At.
+            UseEntityType::InteractAt(..) => (),
+        }
+    }
+}
+
+/// Finds the entity with ID `target` within reach of `player`.
+fn find_target(
+    state: &State,
+    world: &PreparedWorld,
+    player: Entity,
+    target: i32,
+) -> Option<Entity> {
+    let pos = *world.get_component::<Position>(player)?;
+    let dimension = *world.get_component::<DimensionId>(player)?;
+    let state = state.dimension(dimension);
+
+    let radius = glm::vec3(REACH_DISTANCE, REACH_DISTANCE, REACH_DISTANCE);
+    nearby_entities(state.chunk_entities, world, pos, radius)
+        .into_iter()
+        .find(|entity| {
+            world
+                .get_component::<EntityId>(*entity)
+                .map_or(false, |id| id.0 == target)
+        })
+}
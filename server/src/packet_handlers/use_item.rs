@@ -0,0 +1,73 @@
+//! Handling of the Use Item packet, which is sent
+//! when a player right-clicks without targeting a block.
+
+use crate::entity::boat::{self, BoatType};
+use crate::network::PacketQueue;
+use crate::p_inventory::{EntityInventory, InventoryUpdateEvent};
+use crate::player::bow::BowCharge;
+use crate::state::State;
+use crate::worlds::DimensionId;
+use crate::TickCount;
+use feather_core::inventory::SLOT_OFFHAND;
+use feather_core::network::packet::implementation::UseItem;
+use feather_core::{Gamemode, Item, Position};
+use legion::query::{Read, Write};
+use tonks::{PreparedWorld, Query, Trigger};
+
+/// Handles Use Item packets.
+#[system]
+fn handle_use_item(
+    state: &State,
+    queue: &PacketQueue,
+    _query: &mut Query<(
+        Write<EntityInventory>,
+        Write<BowCharge>,
+        Read<Position>,
+        Read<DimensionId>,
+        Read<Gamemode>,
+    )>,
+    world: &mut PreparedWorld,
+    tick_count: &TickCount,
+    inventory_update_events: &mut Trigger<InventoryUpdateEvent>,
+) {
+    for (player, packet) in queue.received::<UseItem>() {
+        let item = {
+            let inventory = world.get_component::<EntityInventory>(player).unwrap();
+            let stack = match packet.hand {
+                0 => inventory.item_in_main_hand(),
+                _ => inventory.item_at(SLOT_OFFHAND),
+            };
+            stack.map(|stack| stack.ty)
+        };
+
+        // Boats are placed here rather than with block placement,
+        // since they can be placed on water, which is not targeted.
+        if let Some(ty) = item
+            .filter(|_| packet.hand == 0)
+            .and_then(BoatType::from_item)
+        {
+            let pos = *world.get_component::<Position>(player).unwrap();
+            let dimension = *world.get_component::<DimensionId>(player).unwrap();
+            if boat::place(&state.dimension(dimension), pos, ty)
+                && *world.get_component::<Gamemode>(player).unwrap() == Gamemode::Survival
+            {
+                let mut inventory = world.get_component_mut::<EntityInventory>(player).unwrap();
+                let slot = inventory.consume_item_in_main_hand();
+                inventory_update_events.trigger(InventoryUpdateEvent {
+                    slots: smallvec![slot],
+                    player,
+                });
+            }
+            continue;
+        }
+
+        // TODO: food, potions, throwable items
+        if item == Some(Item::Bow) {
+            // Start drawing the bow. The arrow is shot when
+            // the player releases it; see `digging.rs`.
+            if let Some(mut charge) = world.get_component_mut::<BowCharge>(player) {
+                charge.0 = Some(tick_count.0);
+            }
+        }
+    }
+}
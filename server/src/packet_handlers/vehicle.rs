@@ -0,0 +1,208 @@
+//! Handling of packets sent by players riding vehicles.
+//!
+//! Players dismount by sneaking, which is sent as Steer Vehicle.
+//! The driver of a boat moves it on the client and reports the new
+//! position with Vehicle Move, once per tick. Moves which are faster
+//! than a boat can go, end inside a block or in the air, or leave the
+//! world border are rejected by sending the boat back to its position
+//! on the server.
+
+use crate::entity::{EntityId, EntityMoveEvent, Velocity};
+use crate::metadata::Metadata;
+use crate::network::{Network, PacketQueue};
+use crate::state::{DimensionState, State};
+use crate::vehicle::{self, Riding, Vehicle, VehicleKind};
+use crate::world_border::WorldBorders;
+use crate::worlds::DimensionId;
+use feather_core::network::packet::implementation::{
+    PacketEntityMetadata, SteerBoat, SteerVehicle, VehicleMoveClientbound, VehicleMoveServerbound,
+};
+use feather_core::{Block, BlockExt, Position};
+use legion::entity::Entity;
+use legion::query::{Read, Write};
+use tonks::{PreparedWorld, Query, Trigger};
+
+/// The flag in Steer Vehicle set when the player sneaks.
+const UNMOUNT_FLAG: u8 = 0x2;
+/// The maximum horizontal distance a boat may move in a tick on ice.
+const MAX_ICE_SPEED: f64 = 4.0;
+/// The maximum horizontal distance a boat may move in a tick elsewhere.
+const MAX_SPEED: f64 = 0.6;
+/// The maximum distance a boat may rise in a tick.
+const MAX_RISE_SPEED: f64 = 0.5;
+/// The maximum distance a boat may fall in a tick.
+const MAX_FALL_SPEED: f64 = 4.0;
+/// The speed added to a minecart when its passenger walks forward.
+const MINECART_PUSH_SPEED: f64 = 0.1;
+/// The speed below which minecarts can be pushed by their passenger.
+const MINECART_PUSH_MAX_SPEED: f64 = 0.01;
+
+/// Handles Steer Vehicle packets.
+#[system]
+fn handle_steer_vehicle(
+    state: &State,
+    queue: &PacketQueue,
+    _query: &mut Query<(Read<Riding>, Read<Position>, Read<Vehicle>, Write<Velocity>)>,
+    world: &mut PreparedWorld,
+) {
+    for (player, packet) in queue.received::<SteerVehicle>() {
+        let vehicle = match world
+            .get_component::<Riding>(player)
+            .and_then(|riding| riding.0)
+        {
+            Some(vehicle) => vehicle,
+            None => continue,
+        };
+
+        if packet.flags & UNMOUNT_FLAG != 0 {
+            vehicle::dismount(state, player);
+            continue;
+        }
+
+        // Walking forward pushes a stationary minecart
+        // in the direction the player is looking.
+        let is_minecart = world
+            .get_component::<Vehicle>(vehicle)
+            .map_or(false, |component| component.kind == VehicleKind::Minecart);
+        if !is_minecart || packet.forward <= 0.0 {
+            continue;
+        }
+
+        let direction = world.get_component::<Position>(player).unwrap().direction();
+        if let Some(mut velocity) = world.get_component_mut::<Velocity>(vehicle) {
+            if glm::length(&velocity.0) < MINECART_PUSH_MAX_SPEED {
+                velocity.0 += glm::vec3(direction.x, 0.0, direction.z) * MINECART_PUSH_SPEED;
+            }
+        }
+    }
+}
+
+/// Handles Steer Boat packets, which update
+/// the paddles shown on other clients.
+#[system]
+fn handle_steer_boat(
+    state: &State,
+    queue: &PacketQueue,
+    _query: &mut Query<(Read<Riding>, Read<EntityId>, Write<Metadata>)>,
+    world: &mut PreparedWorld,
+) {
+    for (player, packet) in queue.received::<SteerBoat>() {
+        let boat = match driven_vehicle(world, player, VehicleKind::Boat) {
+            Some(boat) => boat,
+            None => continue,
+        };
+
+        let mut meta = world.get_component_mut::<Metadata>(boat).unwrap();
+        if let Metadata::Boat(boat_meta) = &mut *meta {
+            boat_meta.set_left_paddle_turning(packet.left_paddle_turning);
+            boat_meta.set_right_paddle_turning(packet.right_paddle_turning);
+        }
+
+        let packet = PacketEntityMetadata {
+            entity_id: world.get_component::<EntityId>(boat).unwrap().0,
+            metadata: meta.to_full_raw_metadata(),
+        };
+        state.broadcast_entity_update(boat, packet, Some(player));
+    }
+}
+
+/// Handles Vehicle Move packets sent by boat drivers.
+#[system]
+fn handle_vehicle_move(
+    state: &State,
+    queue: &PacketQueue,
+    _query: &mut Query<(
+        Read<Riding>,
+        Read<Vehicle>,
+        Read<DimensionId>,
+        Read<Network>,
+        Write<Position>,
+    )>,
+    world: &mut PreparedWorld,
+    borders: &WorldBorders,
+    move_events: &mut Trigger<EntityMoveEvent>,
+) {
+    for (player, packet) in queue.received::<VehicleMoveServerbound>() {
+        let boat = match driven_vehicle(world, player, VehicleKind::Boat) {
+            Some(boat) => boat,
+            None => continue,
+        };
+
+        let old = *world.get_component::<Position>(boat).unwrap();
+        let dimension = *world.get_component::<DimensionId>(boat).unwrap();
+        let new = position!(packet.x, packet.y, packet.z, packet.pitch, packet.yaw);
+
+        let state = state.dimension(dimension);
+        let valid =
+            is_valid_move(&state, old, new) && borders.get(dimension.world).contains(new.x, new.z);
+
+        if !valid {
+            let network = world.get_component::<Network>(player).unwrap();
+            network.send(VehicleMoveClientbound {
+                x: old.x,
+                y: old.y,
+                z: old.z,
+                yaw: old.yaw,
+                pitch: old.pitch,
+            });
+            continue;
+        }
+
+        *world.get_component_mut::<Position>(boat).unwrap() = new;
+        move_events.trigger(EntityMoveEvent { entity: boat });
+    }
+}
+
+/// Returns whether a boat may move from `old` to `new` in one tick.
+///
+/// The boat must end up in water or on top of water or a solid
+/// block, unless it is falling, and may not move faster than boats
+/// can go on the block below it.
+fn is_valid_move(state: &DimensionState, old: Position, new: Position) -> bool {
+    let block_at = |pos: Position| state.block_at(pos.block_pos());
+    let below_pos = |pos: Position| pos - glm::vec3(0.0, 0.01, 0.0);
+    let (inside, below) = match (block_at(new), block_at(below_pos(new))) {
+        (Some(inside), Some(below)) => (inside, below),
+        _ => return false, // Unloaded chunk
+    };
+    if inside.is_solid() {
+        return false;
+    }
+
+    let supported = is_water(inside) || is_water(below) || below.is_solid();
+    if !supported && new.y >= old.y {
+        return false;
+    }
+
+    let max_speed = match block_at(below_pos(old)) {
+        Some(Block::Ice)
+        | Some(Block::PackedIce)
+        | Some(Block::BlueIce)
+        | Some(Block::FrostedIce(_)) => MAX_ICE_SPEED,
+        _ => MAX_SPEED,
+    };
+    let horizontal = ((new.x - old.x).powi(2) + (new.z - old.z).powi(2)).sqrt();
+    let vertical = new.y - old.y;
+
+    horizontal <= max_speed && vertical <= MAX_RISE_SPEED && vertical >= -MAX_FALL_SPEED
+}
+
+fn is_water(block: Block) -> bool {
+    match block {
+        Block::Water(_) => true,
+        _ => false,
+    }
+}
+
+/// Returns the vehicle of kind `kind` which `player` is
+/// driving, or `None` if the player is not driving one.
+fn driven_vehicle(world: &PreparedWorld, player: Entity, kind: VehicleKind) -> Option<Entity> {
+    let vehicle = world.get_component::<Riding>(player)?.0?;
+    let component = world.get_component::<Vehicle>(vehicle)?;
+
+    if component.kind == kind && component.driver() == Some(player) {
+        Some(vehicle)
+    } else {
+        None
+    }
+}
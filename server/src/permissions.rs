@@ -0,0 +1,519 @@
+//! Permissions: named nodes, such as `minecraft.command.tp`
+//! or `feather.build`, which players are granted or denied.
+//!
+//! Nodes are set to `true` or `false` for groups and for single
+//! players. A node ending in `.*` sets every node below it, and `*`
+//! alone sets every node; the most specific node set wins. A player's
+//! own nodes take precedence over those of their groups, which take
+//! precedence over those of the groups they inherit from. Every
+//! player is in the `default` group, which is consulted last.
+//!
+//! Nodes set nowhere fall back to op levels: each check gives the
+//! op level which grants the node by default (see `AccessLists`),
+//! so without a permissions file the server behaves like vanilla.
+//!
+//! Permissions are stored in `permissions.json` in the server
+//! directory and are saved whenever they change.
+
+use crate::access::{AccessLists, Profile, MAX_OP_LEVEL};
+use hashbrown::HashSet;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+pub const PERMISSIONS_FILE: &str = "permissions.json";
+
+/// The group every player is in.
+pub const DEFAULT_GROUP: &str = "default";
+
+/// Node allowing players to place and break blocks,
+/// which everyone has unless it is denied.
+pub const BUILD: &str = "feather.build";
+
+/// Nodes set to `true` or `false`, sorted so that
+/// the file is written in a stable order.
+pub type Nodes = BTreeMap<String, bool>;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Group {
+    /// The groups whose nodes this group inherits,
+    /// most important first.
+    #[serde(default)]
+    pub inherits: Vec<String>,
+    #[serde(default)]
+    pub permissions: Nodes,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerPermissions {
+    #[serde(flatten)]
+    pub profile: Profile,
+    /// The groups the player is in, other
+    /// than `default`, most important first.
+    #[serde(default)]
+    pub groups: Vec<String>,
+    #[serde(default)]
+    pub permissions: Nodes,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct PermissionsFile {
+    #[serde(default)]
+    groups: BTreeMap<String, Group>,
+    #[serde(default)]
+    players: Vec<PlayerPermissions>,
+}
+
+/// Resource storing the groups and the nodes set for players.
+#[derive(Resource)]
+pub struct Permissions {
+    path: PathBuf,
+    file: PermissionsFile,
+    /// Whether the file failed to parse, in which case it
+    /// is never saved so that its permissions aren't lost.
+    invalid: bool,
+}
+
+impl Permissions {
+    /// Loads the permissions stored at `path`. There are no
+    /// permissions if the file does not exist or is invalid.
+    /// An invalid file is left untouched until it is fixed
+    /// and the server restarted.
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let (file, invalid) = match fs::read_to_string(&path) {
+            Ok(json) => match serde_json::from_str(&json) {
+                Ok(file) => (file, false),
+                Err(e) => {
+                    error!(
+                        "Failed to parse {}: {}. Changes to it won't be saved.",
+                        path.display(),
+                        e
+                    );
+                    (PermissionsFile::default(), true)
+                }
+            },
+            Err(_) => (PermissionsFile::default(), false),
+        };
+
+        Self {
+            path,
+            file,
+            invalid,
+        }
+    }
+
+    /// Writes the permissions to their file, unless
+    /// the file failed to parse when loaded.
+    pub fn save(&self) {
+        if self.invalid {
+            warn!(
+                "Not saving {}, since it failed to parse",
+                self.path.display()
+            );
+            return;
+        }
+
+        let result = serde_json::to_string_pretty(&self.file)
+            .map_err(failure::Error::from)
+            .and_then(|json| fs::write(&self.path, json).map_err(failure::Error::from));
+        if let Err(e) = result {
+            error!("Failed to save {}: {}", self.path.display(), e);
+        }
+    }
+
+    pub fn group(&self, name: &str) -> Option<&Group> {
+        self.file.groups.get(name)
+    }
+
+    /// Returns the nodes and groups of the given player.
+    pub fn player(&self, uuid: Option<Uuid>, name: &str) -> Option<&PlayerPermissions> {
+        self.file
+            .players
+            .iter()
+            .find(|player| player.profile.matches(uuid, name))
+    }
+
+    /// Returns whether the given player has a node,
+    /// which is granted to operators of at least
+    /// `default_level` if it is set nowhere.
+    pub fn has(
+        &self,
+        access: &AccessLists,
+        uuid: Uuid,
+        name: &str,
+        node: &str,
+        default_level: u8,
+    ) -> bool {
+        self.value(Some(uuid), name, node)
+            .unwrap_or_else(|| access.op_level(uuid, name) >= default_level)
+    }
+
+    /// Returns the value of a node for the given
+    /// player, or `None` if it is set nowhere.
+    pub fn value(&self, uuid: Option<Uuid>, name: &str, node: &str) -> Option<bool> {
+        self.layers(uuid, name)
+            .into_iter()
+            .find_map(|nodes| lookup(nodes, node))
+    }
+
+    /// Returns the permissions of the given player, such
+    /// as to check many nodes without these resources.
+    pub fn for_player(&self, access: &AccessLists, uuid: Uuid, name: &str) -> PermissionSet {
+        self.layers(Some(uuid), name).into_iter().fold(
+            PermissionSet::new(access.op_level(uuid, name)),
+            |set, nodes| set.with_layer(nodes.clone()),
+        )
+    }
+
+    /// Returns the groups of a player in order of inheritance:
+    /// each of their groups followed by the groups it inherits
+    /// from, and then the default group.
+    pub fn groups(&self, uuid: Option<Uuid>, name: &str) -> Vec<&str> {
+        let mut groups = vec![];
+        let mut visited = HashSet::new();
+
+        if let Some(player) = self.player(uuid, name) {
+            for group in &player.groups {
+                self.push_group(group, &mut groups, &mut visited);
+            }
+        }
+        self.push_group(DEFAULT_GROUP, &mut groups, &mut visited);
+
+        groups
+    }
+
+    fn push_group<'a>(
+        &'a self,
+        name: &'a str,
+        groups: &mut Vec<&'a str>,
+        visited: &mut HashSet<&'a str>,
+    ) {
+        // Groups may inherit from each other in a cycle.
+        if !visited.insert(name) {
+            return;
+        }
+
+        groups.push(name);
+        if let Some(group) = self.group(name) {
+            for parent in &group.inherits {
+                self.push_group(parent, groups, visited);
+            }
+        }
+    }
+
+    /// Returns the nodes set for a player, and then those
+    /// of their groups in order of inheritance.
+    fn layers(&self, uuid: Option<Uuid>, name: &str) -> Vec<&Nodes> {
+        let mut layers = vec![];
+        if let Some(player) = self.player(uuid, name) {
+            layers.push(&player.permissions);
+        }
+        layers.extend(
+            self.groups(uuid, name)
+                .into_iter()
+                .filter_map(|group| self.group(group))
+                .map(|group| &group.permissions),
+        );
+        layers
+    }
+
+    /// Sets a node for a player, or unsets it if `value` is
+    /// `None`. Returns whether anything changed.
+    pub fn set_player(&mut self, profile: Profile, node: &str, value: Option<bool>) -> bool {
+        if value.is_none() && self.player(profile.uuid, &profile.name).is_none() {
+            return false;
+        }
+        let player = self.player_mut(profile);
+        let changed = set_node(&mut player.permissions, node, value);
+        self.save_if(changed)
+    }
+
+    /// Sets a node for a group, which is created if it does not
+    /// exist, or unsets it if `value` is `None`. Returns whether
+    /// anything changed.
+    pub fn set_group(&mut self, group: &str, node: &str, value: Option<bool>) -> bool {
+        if value.is_none() && self.group(group).is_none() {
+            return false;
+        }
+        let group = self.file.groups.entry(group.to_string()).or_default();
+        let changed = set_node(&mut group.permissions, node, value);
+        self.save_if(changed)
+    }
+
+    /// Adds a player to a group. Returns whether
+    /// the player was not already in it.
+    pub fn add_player_group(&mut self, profile: Profile, group: &str) -> bool {
+        let player = self.player_mut(profile);
+        let changed = !player.groups.iter().any(|g| g == group);
+        if changed {
+            player.groups.push(group.to_string());
+        }
+        self.save_if(changed)
+    }
+
+    /// Removes a player from a group. Returns
+    /// whether the player was in it.
+    pub fn remove_player_group(&mut self, name: &str, group: &str) -> bool {
+        let changed = match self
+            .file
+            .players
+            .iter_mut()
+            .find(|player| player.profile.name.eq_ignore_ascii_case(name))
+        {
+            Some(player) => remove(&mut player.groups, group),
+            None => false,
+        };
+        self.save_if(changed)
+    }
+
+    /// Makes a group inherit the nodes of another. Returns
+    /// whether it did not already inherit them.
+    pub fn add_group_parent(&mut self, group: &str, parent: &str) -> bool {
+        let group = self.file.groups.entry(group.to_string()).or_default();
+        let changed = !group.inherits.iter().any(|g| g == parent);
+        if changed {
+            group.inherits.push(parent.to_string());
+        }
+        self.save_if(changed)
+    }
+
+    /// Stops a group from inheriting the nodes of
+    /// another. Returns whether it inherited them.
+    pub fn remove_group_parent(&mut self, group: &str, parent: &str) -> bool {
+        let changed = match self.file.groups.get_mut(group) {
+            Some(group) => remove(&mut group.inherits, parent),
+            None => false,
+        };
+        self.save_if(changed)
+    }
+
+    /// Records the UUID and current name of a player
+    /// with permissions, who may have been added by name.
+    pub fn update_profile(&mut self, uuid: Uuid, name: &str) {
+        let player = match self
+            .file
+            .players
+            .iter_mut()
+            .find(|player| player.profile.matches(Some(uuid), name))
+        {
+            Some(player) => player,
+            None => return,
+        };
+
+        let profile = &mut player.profile;
+        if profile.uuid != Some(uuid) || profile.name != name {
+            profile.uuid = Some(uuid);
+            profile.name = name.to_string();
+            self.save();
+        }
+    }
+
+    /// Returns the entry of a player, adding one if needed.
+    fn player_mut(&mut self, profile: Profile) -> &mut PlayerPermissions {
+        let players = &mut self.file.players;
+        let index = match players
+            .iter()
+            .position(|player| player.profile.matches(profile.uuid, &profile.name))
+        {
+            Some(index) => index,
+            None => {
+                players.push(PlayerPermissions {
+                    profile,
+                    groups: vec![],
+                    permissions: Nodes::new(),
+                });
+                players.len() - 1
+            }
+        };
+        &mut players[index]
+    }
+
+    fn save_if(&self, changed: bool) -> bool {
+        if changed {
+            self.save();
+        }
+        changed
+    }
+}
+
+/// The permissions of a player or of the console.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PermissionSet {
+    /// The op level, which grants nodes set nowhere.
+    pub op_level: u8,
+    /// The nodes set for the player and for
+    /// their groups, most important first.
+    layers: Vec<Nodes>,
+}
+
+impl PermissionSet {
+    /// Creates a set with the given op level and no nodes.
+    pub fn new(op_level: u8) -> Self {
+        Self {
+            op_level,
+            layers: vec![],
+        }
+    }
+
+    /// The permissions of the console, which has every node.
+    pub fn console() -> Self {
+        Self::new(MAX_OP_LEVEL)
+    }
+
+    /// Adds nodes which are less important
+    /// than those already in this set.
+    pub fn with_layer(mut self, nodes: Nodes) -> Self {
+        self.layers.push(nodes);
+        self
+    }
+
+    /// Returns the value of a node, or `None` if it is set nowhere.
+    pub fn value(&self, node: &str) -> Option<bool> {
+        self.layers.iter().find_map(|nodes| lookup(nodes, node))
+    }
+
+    /// Returns whether a node is granted. Nodes set nowhere are
+    /// granted if the op level is at least `default_level`.
+    pub fn has(&self, node: &str, default_level: u8) -> bool {
+        self.value(node)
+            .unwrap_or_else(|| self.op_level >= default_level)
+    }
+}
+
+/// Returns the value of the most specific of `nodes`
+/// matching `node`, either exactly or with a wildcard.
+fn lookup(nodes: &Nodes, node: &str) -> Option<bool> {
+    if let Some(value) = nodes.get(node) {
+        return Some(*value);
+    }
+
+    let mut prefix = node;
+    while let Some(index) = prefix.rfind('.') {
+        prefix = &prefix[..index];
+        if let Some(value) = nodes.get(&format!("{}.*", prefix)) {
+            return Some(*value);
+        }
+    }
+
+    nodes.get("*").copied()
+}
+
+fn set_node(nodes: &mut Nodes, node: &str, value: Option<bool>) -> bool {
+    match value {
+        Some(value) => nodes.insert(node.to_string(), value) != Some(value),
+        None => nodes.remove(node).is_some(),
+    }
+}
+
+fn remove(names: &mut Vec<String>, name: &str) -> bool {
+    let len = names.len();
+    names.retain(|n| n != name);
+    names.len() != len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::{OpEntry, PlayerList, BANS_FILE, MUTES_FILE, OPS_FILE, WHITELIST_FILE};
+
+    fn temp_file(file: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("feather-{}-{}", Uuid::new_v4(), file))
+    }
+
+    #[test]
+    fn test_lookup() {
+        let mut nodes = Nodes::new();
+        nodes.insert("minecraft.command.*".to_string(), true);
+        nodes.insert("minecraft.command.stop".to_string(), false);
+
+        assert_eq!(lookup(&nodes, "minecraft.command.tp"), Some(true));
+        assert_eq!(lookup(&nodes, "minecraft.command.stop"), Some(false));
+        assert_eq!(lookup(&nodes, "feather.build"), None);
+
+        nodes.insert("*".to_string(), false);
+        assert_eq!(lookup(&nodes, "feather.build"), Some(false));
+    }
+
+    #[test]
+    fn test_groups_and_overrides() {
+        let mut permissions = Permissions::load(temp_file(PERMISSIONS_FILE));
+        let uuid = Uuid::new_v4();
+        let notch = Profile::new(None, "Notch");
+
+        permissions.set_group(DEFAULT_GROUP, BUILD, Some(false));
+        permissions.set_group("builder", BUILD, Some(true));
+        permissions.set_group("admin", "minecraft.command.*", Some(true));
+        assert!(permissions.add_group_parent("admin", "builder"));
+        // Cycles are ignored.
+        permissions.add_group_parent("builder", "admin");
+
+        assert_eq!(permissions.value(Some(uuid), "Notch", BUILD), Some(false));
+        assert!(permissions.add_player_group(notch.clone(), "admin"));
+        assert!(!permissions.add_player_group(notch.clone(), "admin"));
+        assert_eq!(
+            permissions.groups(Some(uuid), "Notch"),
+            vec!["admin", "builder", DEFAULT_GROUP]
+        );
+        assert_eq!(permissions.value(Some(uuid), "Notch", BUILD), Some(true));
+        assert_eq!(
+            permissions.value(Some(uuid), "Notch", "minecraft.command.tp"),
+            Some(true)
+        );
+
+        permissions.set_player(notch, "minecraft.command.tp", Some(false));
+        permissions.update_profile(uuid, "Notch");
+        assert_eq!(
+            permissions.value(Some(uuid), "jeb_", "minecraft.command.tp"),
+            Some(false)
+        );
+
+        let reloaded = Permissions::load(permissions.path.clone());
+        assert_eq!(reloaded.file, permissions.file);
+
+        assert!(permissions.remove_player_group("notch", "admin"));
+        assert_eq!(permissions.value(Some(uuid), "Notch", BUILD), Some(false));
+        fs::remove_file(&permissions.path).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_file_not_saved() {
+        let path = temp_file(PERMISSIONS_FILE);
+        fs::write(&path, "{").unwrap();
+
+        let mut permissions = Permissions::load(path.clone());
+        permissions.set_group(DEFAULT_GROUP, BUILD, Some(false));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_op_levels() {
+        let permissions = Permissions::load(temp_file(PERMISSIONS_FILE));
+        let ops_file = temp_file(OPS_FILE);
+        let mut access = AccessLists {
+            bans: PlayerList::load(temp_file(BANS_FILE)),
+            ops: PlayerList::load(ops_file.clone()),
+            whitelist: PlayerList::load(temp_file(WHITELIST_FILE)),
+            mutes: PlayerList::load(temp_file(MUTES_FILE)),
+            whitelist_enabled: false,
+        };
+        let uuid = Uuid::new_v4();
+
+        assert!(permissions.has(&access, uuid, "Notch", BUILD, 0));
+        assert!(!permissions.has(&access, uuid, "Notch", "minecraft.command.tp", 2));
+
+        access.ops.add(OpEntry {
+            profile: Profile::new(Some(uuid), "Notch"),
+            level: 2,
+            bypasses_player_limit: false,
+        });
+        let set = permissions.for_player(&access, uuid, "Notch");
+        assert!(set.has("minecraft.command.tp", 2));
+        assert!(!set.has("minecraft.command.stop", 4));
+        assert!(PermissionSet::console().has("minecraft.command.stop", 4));
+
+        fs::remove_file(&ops_file).unwrap();
+    }
+}
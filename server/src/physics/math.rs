@@ -4,7 +4,7 @@
 use crate::chunk_entities::ChunkEntities;
 use crate::physics::block_bboxes::bbox_for_block;
 use crate::physics::AABBExt;
-use crate::state::State;
+use crate::state::DimensionState;
 use feather_blocks::Block;
 use feather_core::world::{BlockPosition, Position};
 use feather_core::{BlockExt, ChunkPosition};
@@ -90,7 +90,7 @@ pub struct RayImpact {
 /// Traces up to `max_distance` before returning `None`
 /// if no block was found.
 pub fn block_impacted_by_ray(
-    state: &State,
+    state: &DimensionState,
     origin: DVec3,
     ray: DVec3,
     max_distance_squared: f64,
@@ -240,6 +240,23 @@ pub fn nearby_entities(
     world: &PreparedWorld,
     pos: Position,
     radius: DVec3,
+) -> SmallVec<[Entity; 4]> {
+    nearby_entities_with(chunk_entities, pos, radius, |e| {
+        world.get_component::<Position>(e).map(|epos| *epos)
+    })
+}
+
+/// Like `nearby_entities`, but finds the positions of entities
+/// with `position_of`, so that it can be used outside systems,
+/// such as in lazy closures with full access to the `World`.
+///
+/// # Panics
+/// Panics if either coordinate of the radius is negative.
+pub fn nearby_entities_with(
+    chunk_entities: &ChunkEntities,
+    pos: Position,
+    radius: DVec3,
+    position_of: impl Fn(Entity) -> Option<Position>,
 ) -> SmallVec<[Entity; 4]> {
     assert!(radius.x >= 0.0);
     assert!(radius.y >= 0.0);
@@ -253,8 +270,7 @@ pub fn nearby_entities(
             .iter()
             .copied()
             .filter(|e| {
-                let epos = world.get_component::<Position>(*e);
-                if let Some(epos) = epos {
+                if let Some(epos) = position_of(*e) {
                     (epos.x - pos.x).abs() <= radius.x
                         && (epos.y - pos.y).abs() <= radius.y
                         && (epos.z - pos.z).abs() <= radius.z
@@ -315,7 +331,7 @@ impl BlockIntersect {
 /// than 1 are not supported. If the bounding box's size
 /// is more than 1, this function will panic.
 pub fn blocks_intersecting_bbox(
-    state: &State,
+    state: &DimensionState,
     mut from: Position,
     mut dest: Position,
     bbox: &AABB<f64>,
@@ -423,7 +439,7 @@ pub fn adjacent_to_bbox(
     sign: i32,
     bbox: &AABB<f64>,
     pos: Position,
-    state: &State,
+    state: &DimensionState,
     checked: &mut heapless::FnvIndexSet<BlockPosition, U32>,
 ) -> Compound<f64> {
     assert!(axis <= 2);
@@ -0,0 +1,145 @@
+//! Charging and shooting bows.
+//!
+//! A player starts drawing a bow by sending Use Item,
+//! and releases it by sending Player Digging with the
+//! `ConsumeItem` status. The time in between determines
+//! the speed of the arrow.
+
+use crate::entity::arrow::{self, Arrow, ArrowPickup};
+use crate::entity::Velocity;
+use crate::p_inventory::{EntityInventory, InventoryUpdateEvent};
+use crate::player::PLAYER_EYE_HEIGHT;
+use crate::state::DimensionState;
+use feather_core::inventory::{SlotIndex, SLOT_HOTBAR_OFFSET, SLOT_INVENTORY_OFFSET, SLOT_OFFHAND};
+use feather_core::{Gamemode, Item, ItemStack, Position};
+use legion::entity::Entity;
+use tonks::Trigger;
+use uuid::Uuid;
+
+/// Component storing the tick at which a player
+/// started drawing their bow, if they are drawing one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BowCharge(pub Option<u64>);
+
+/// The speed, in blocks per tick, of an arrow
+/// shot from a fully drawn bow.
+const MAX_ARROW_SPEED: f64 = 3.0;
+
+/// Returns the force, from 0 to 1, of a bow
+/// which has been drawn for `ticks` ticks.
+pub fn bow_force(ticks: u64) -> f64 {
+    let seconds = ticks as f64 / 20.0;
+    let force = (seconds * seconds + seconds * 2.0) / 3.0;
+    force.min(1.0)
+}
+
+/// Returns whether `item` can be shot from a bow.
+fn is_arrow_item(item: Item) -> bool {
+    match item {
+        Item::Arrow | Item::SpectralArrow | Item::TippedArrow => true,
+        _ => false,
+    }
+}
+
+/// Finds the arrow to shoot from the player's inventory.
+///
+/// The order of priority is the off-hand, the hotbar
+/// from left to right, then the rest of the inventory.
+fn find_arrow(inventory: &EntityInventory) -> Option<SlotIndex> {
+    std::iter::once(SLOT_OFFHAND)
+        .chain(SLOT_HOTBAR_OFFSET..SLOT_HOTBAR_OFFSET + 9)
+        .chain(SLOT_INVENTORY_OFFSET..SLOT_HOTBAR_OFFSET)
+        .find(|slot| {
+            inventory
+                .item_at(*slot)
+                .map_or(false, |stack| is_arrow_item(stack.ty))
+        })
+}
+
+/// Shoots an arrow from a bow which was drawn
+/// for `ticks` ticks.
+///
+/// In survival and adventure mode, an arrow is
+/// taken from the player's inventory.
+#[allow(clippy::too_many_arguments)]
+pub fn shoot(
+    state: &DimensionState,
+    player: Entity,
+    uuid: Uuid,
+    gamemode: Gamemode,
+    position: Position,
+    inventory: &mut EntityInventory,
+    ticks: u64,
+    inventory_updates: &mut Trigger<InventoryUpdateEvent>,
+) {
+    let force = bow_force(ticks);
+    if force < 0.1 {
+        return;
+    }
+
+    let creative = gamemode == Gamemode::Creative;
+    let pickup = match find_arrow(inventory) {
+        Some(slot) if !creative => {
+            let stack = *inventory.item_at(slot).unwrap();
+            inventory.set_item_at(slot, ItemStack::new(stack.ty, stack.amount - 1));
+            inventory_updates.trigger(InventoryUpdateEvent {
+                slots: smallvec![slot],
+                player,
+            });
+            ArrowPickup::Allowed
+        }
+        None if !creative => {
+            debug!("Tried to shoot bow with no arrows.");
+            return;
+        }
+        _ => ArrowPickup::CreativeOnly,
+    };
+
+    // TODO: spectral and tipped arrows
+    let velocity = position.direction() * force * MAX_ARROW_SPEED;
+
+    let mut pos = position + glm::vec3(0.0, PLAYER_EYE_HEIGHT - 0.1, 0.0);
+    let (yaw, pitch) = arrow::rotation(velocity);
+    pos.yaw = yaw;
+    pos.pitch = pitch;
+    pos.on_ground = false;
+
+    let critical = force >= 1.0;
+    arrow::create(
+        state,
+        pos,
+        Arrow::new(Some(player), critical, pickup),
+        Some(uuid),
+    )
+    .with_component(Velocity(velocity))
+    .build();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bow_force() {
+        assert_eq!(bow_force(0), 0.0);
+        assert!(bow_force(2) < 0.1);
+        assert!(bow_force(10) > 0.1 && bow_force(10) < 1.0);
+        assert_eq!(bow_force(20), 1.0);
+        assert_eq!(bow_force(100), 1.0);
+    }
+
+    #[test]
+    fn test_find_arrow() {
+        let mut inventory = EntityInventory::default();
+        assert_eq!(find_arrow(&inventory), None);
+
+        inventory.set_item_at(20, ItemStack::new(Item::Arrow, 3));
+        assert_eq!(find_arrow(&inventory), Some(20));
+
+        inventory.set_item_at(SLOT_HOTBAR_OFFSET + 4, ItemStack::new(Item::TippedArrow, 1));
+        assert_eq!(find_arrow(&inventory), Some(SLOT_HOTBAR_OFFSET + 4));
+
+        inventory.set_item_at(SLOT_OFFHAND, ItemStack::new(Item::SpectralArrow, 1));
+        assert_eq!(find_arrow(&inventory), Some(SLOT_OFFHAND));
+    }
+}
@@ -1,7 +1,43 @@
+//! Chat messages sent by players.
+//!
+//! A message passes through the `ChatPipeline`: handlers run in
+//! order of priority, each of which may change the message, change
+//! who receives it, or cancel it. The built-in handlers reject
+//! messages from players who hide chat, kick players who spam,
+//! cancel messages from muted players, strip formatting codes typed
+//! by players, filter words, move messages into local chat and
+//! format them. A message which is not
+//! cancelled is delivered to each recipient whose chat mode shows it.
+//!
+//! Private messages (`/tell`) and team messages (`/teammsg`) pass
+//! through the same pipeline. Teams are configured in the `[chat.teams]`
+//! section of the config, which lists the players on each team.
+
+use crate::access::AccessLists;
+use crate::command::{CommandCtx, CommandSender};
 use crate::entity::Name;
+use crate::network::Network;
+use crate::permissions::Permissions;
+use crate::state::{State, StateInner};
+use crate::util::disconnect_player_now;
+use crate::worlds::DimensionId;
+use feather_core::network::packet::implementation::ChatMessageClientbound;
+use feather_core::text::{strip_legacy, ClickEvent, Color, Content};
+use feather_core::{Position, TextComponent};
 use legion::entity::Entity;
-use legion::query::Read;
-use tonks::{PreparedWorld, Query, Trigger};
+use legion::query::{IntoQuery, Read, Write};
+use legion::world::World;
+use std::collections::HashMap;
+use tonks::{PreparedWorld, Query};
+use uuid::Uuid;
+
+/// How much sending a message adds to a player's `ChatSpam`.
+const SPAM_PER_MESSAGE: u32 = 20;
+/// The `ChatSpam` above which a player is kicked.
+const SPAM_LIMIT: u32 = 200;
+
+/// Permission node exempting a player from being kicked for spam.
+pub const SPAM_BYPASS: &str = "feather.chat.spam_bypass";
 
 /// Event triggered when a player sends a chat message
 #[derive(Debug, Clone)]
@@ -15,15 +51,15 @@ pub struct PlayerChatEvent {
 
 /// Event that will result in a chat message being broadcasted
 pub struct ChatBroadcastEvent {
-    // TODO: Use composable chat component here
-    /// A JSON string representing the Chat component to sent
-    pub json_data: String,
+    /// The message to send
+    pub message: TextComponent,
 
     /// The position
     pub position: ChatPosition,
 }
 
 /// Different positions a chat message can be displayed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ChatPosition {
     /// Simple message displayed in the chat box
     Chat,
@@ -35,29 +71,593 @@ pub enum ChatPosition {
     GameInfo,
 }
 
-/// System that broadcasts chat messages to all players
+impl ChatPosition {
+    pub fn id(self) -> u8 {
+        match self {
+            ChatPosition::Chat => 0,
+            ChatPosition::SystemMessage => 1,
+            ChatPosition::GameInfo => 2,
+        }
+    }
+}
+
+/// Which messages a player has chosen to see in their chat box.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatMode {
+    Enabled,
+    /// Only system messages, such as command feedback.
+    CommandsOnly,
+    Hidden,
+}
+
+impl ChatMode {
+    pub fn from_id(id: i32) -> Self {
+        match id {
+            1 => ChatMode::CommandsOnly,
+            2 => ChatMode::Hidden,
+            _ => ChatMode::Enabled,
+        }
+    }
+
+    /// Returns whether messages at the given position are shown.
+    pub fn shows(self, position: ChatPosition) -> bool {
+        match position {
+            ChatPosition::Chat => self == ChatMode::Enabled,
+            ChatPosition::SystemMessage => self != ChatMode::Hidden,
+            ChatPosition::GameInfo => true,
+        }
+    }
+}
+
+/// Component storing the chat settings a
+/// player sent in a Client Settings packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChatSettings {
+    pub mode: ChatMode,
+    /// Whether the player sees colors in chat.
+    pub colors: bool,
+}
+
+impl ChatSettings {
+    /// Removes the colors and formatting from a message
+    /// sent to the player if they turned chat colors off.
+    pub fn apply_colors(&self, message: TextComponent) -> TextComponent {
+        if self.colors {
+            message
+        } else {
+            message.without_formatting()
+        }
+    }
+}
+
+impl Default for ChatSettings {
+    fn default() -> Self {
+        Self {
+            mode: ChatMode::Enabled,
+            colors: true,
+        }
+    }
+}
+
+/// Component measuring how much a player has chatted
+/// recently. It decreases by one each tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ChatSpam(pub u32);
+
+/// Who receives a chat message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChatKind {
+    /// Every player.
+    Public,
+    /// Players within the given distance of the sender.
+    Local(f64),
+    /// The given players, privately.
+    Whisper(Vec<Entity>),
+    /// The players on the given team.
+    Team(String),
+}
+
+/// A chat message passing through the `ChatPipeline`.
+#[derive(Debug, Clone)]
+pub struct ChatMessage {
+    pub sender: CommandSender,
+    /// The name of the sender.
+    pub name: String,
+    /// The text the sender typed.
+    pub text: String,
+    pub kind: ChatKind,
+    /// The component shown to recipients, set when the message
+    /// is formatted. If unset, the text is shown unformatted.
+    pub component: Option<TextComponent>,
+    cancelled: bool,
+}
+
+impl ChatMessage {
+    pub fn new(sender: CommandSender, name: String, text: String, kind: ChatKind) -> Self {
+        Self {
+            sender,
+            name,
+            text,
+            kind,
+            component: None,
+            cancelled: false,
+        }
+    }
+
+    /// Cancels the message. Handlers with a lower
+    /// priority don't run, and it is not delivered.
+    pub fn cancel(&mut self) {
+        self.cancelled = true;
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+}
+
+/// A function which handles chat messages. It runs lazily, with
+/// the context of the message's sender, as commands do.
+pub type ChatHandler = fn(&mut CommandCtx, &mut ChatMessage);
+
+/// The priorities of the built-in handlers.
+/// Handlers with lower priorities run first.
+pub mod priority {
+    pub const CHAT_MODE: i32 = 100;
+    pub const SPAM: i32 = 200;
+    pub const MUTE: i32 = 300;
+    pub const FILTER: i32 = 400;
+    pub const CHANNEL: i32 = 500;
+    pub const FORMAT: i32 = 600;
+}
+
+/// Resource storing the chat handlers in order of priority.
+#[derive(Resource)]
+pub struct ChatPipeline {
+    handlers: Vec<(i32, ChatHandler)>,
+}
+
+impl Default for ChatPipeline {
+    /// Creates a pipeline with the built-in handlers registered.
+    fn default() -> Self {
+        let mut pipeline = Self::new();
+        pipeline.register(priority::CHAT_MODE, check_chat_mode);
+        pipeline.register(priority::SPAM, limit_spam);
+        pipeline.register(priority::MUTE, check_mute);
+        pipeline.register(priority::FILTER, strip_codes);
+        pipeline.register(priority::FILTER, filter_words);
+        pipeline.register(priority::CHANNEL, choose_channel);
+        pipeline.register(priority::FORMAT, format_message);
+        pipeline
+    }
+}
+
+impl ChatPipeline {
+    /// Creates a pipeline with no handlers.
+    pub fn new() -> Self {
+        Self { handlers: vec![] }
+    }
+
+    /// Registers a handler. Handlers with the same
+    /// priority run in the order they were registered.
+    pub fn register(&mut self, priority: i32, handler: ChatHandler) {
+        let index = self
+            .handlers
+            .iter()
+            .position(|(p, _)| *p > priority)
+            .unwrap_or(self.handlers.len());
+        self.handlers.insert(index, (priority, handler));
+    }
+}
+
+/// Passes a message through the pipeline and delivers it
+/// unless a handler cancels it. Returns whether it was delivered.
+pub fn send_chat(ctx: &mut CommandCtx, mut message: ChatMessage) -> bool {
+    let handlers: Vec<ChatHandler> = ctx
+        .resource::<ChatPipeline>()
+        .handlers
+        .iter()
+        .map(|(_, handler)| *handler)
+        .collect();
+
+    for handler in handlers {
+        handler(ctx, &mut message);
+        if message.is_cancelled() {
+            return false;
+        }
+    }
+
+    deliver(ctx, message);
+    true
+}
+
+/// Returns the configured team listing the player with the given
+/// name. If several teams list them, the first by name is returned.
+pub fn team(teams: &HashMap<String, Vec<String>>, name: &str) -> Option<String> {
+    teams
+        .iter()
+        .filter(|(_, players)| players.iter().any(|p| p.eq_ignore_ascii_case(name)))
+        .map(|(team, _)| team)
+        .min()
+        .cloned()
+}
+
+/// Passes the messages of players through the pipeline.
 #[event_handler]
-fn broadcast_chat(
-    event: &PlayerChatEvent,
-    world: &mut PreparedWorld,
-    _query: &mut Query<Read<Name>>,
-    trigger: &mut Trigger<ChatBroadcastEvent>,
-) {
-    let player_name = &world.get_component::<Name>(event.player).unwrap().0;
-
-    let json_data = json!({
-        "translate": "chat.type.text",
-        "with": [
-            {"text": player_name},
-            {"text": event.message}
-        ]
-    })
-    .to_string();
-
-    trigger.trigger(ChatBroadcastEvent {
-        json_data,
-        position: ChatPosition::Chat,
+fn handle_chat(event: &PlayerChatEvent, state: &State) {
+    let player = event.player;
+    let text = event.message.clone();
+
+    state.exec_with_scheduler(move |world, scheduler| {
+        if !world.is_alive(player) {
+            return;
+        }
+
+        let mut ctx = CommandCtx {
+            sender: CommandSender::Player(player),
+            world,
+            scheduler,
+        };
+        let message = ChatMessage::new(ctx.sender, ctx.sender_name(), text, ChatKind::Public);
+        send_chat(&mut ctx, message);
+    });
+}
+
+/// Lets players chat more as time passes.
+#[system]
+fn decay_chat_spam(query: &mut Query<Write<ChatSpam>>, world: &mut PreparedWorld) {
+    query.par_entities_for_each(world, |(_, mut spam)| {
+        spam.0 = spam.0.saturating_sub(1);
+    });
+}
+
+/// Players who hide chat may not chat either,
+/// though they may still send messages by command.
+fn check_chat_mode(ctx: &mut CommandCtx, message: &mut ChatMessage) {
+    let player = match message.sender {
+        CommandSender::Player(player) if message.kind == ChatKind::Public => player,
+        _ => return,
+    };
+
+    let mode = ctx
+        .world
+        .get_component::<ChatSettings>(player)
+        .map(|settings| settings.mode);
+    if mode == Some(ChatMode::Hidden) {
+        ctx.send_component(TextComponent::translate("chat.cannotSend", vec![]).color(Color::Red));
+        message.cancel();
+    }
+}
+
+/// Kicks players who send messages too quickly.
+fn limit_spam(ctx: &mut CommandCtx, message: &mut ChatMessage) {
+    let player = match message.sender {
+        CommandSender::Player(player) => player,
+        CommandSender::Console => return,
+    };
+    if ctx.has_permission(SPAM_BYPASS, 1) {
+        return;
+    }
+
+    let spam = match ctx.world.get_component_mut::<ChatSpam>(player) {
+        Some(mut spam) => {
+            spam.0 += SPAM_PER_MESSAGE;
+            spam.0
+        }
+        None => return,
+    };
+    if spam > SPAM_LIMIT {
+        let reason = TextComponent::translate("feather.disconnect.spam", vec![]);
+        disconnect_player_now(ctx.world, ctx.scheduler, player, &reason);
+        message.cancel();
+    }
+}
+
+/// Cancels messages from muted players.
+fn check_mute(ctx: &mut CommandCtx, message: &mut ChatMessage) {
+    let player = match message.sender {
+        CommandSender::Player(player) => player,
+        CommandSender::Console => return,
+    };
+
+    let uuid = ctx.world.get_component::<Uuid>(player).map(|uuid| *uuid);
+    let reason = ctx
+        .resource::<AccessLists>()
+        .mutes
+        .get(uuid, &message.name)
+        .map(|mute| mute.reason.clone());
+    if let Some(reason) = reason {
+        ctx.send_component(
+            TextComponent::translate("feather.chat.muted", vec![reason.into()]).color(Color::Red),
+        );
+        message.cancel();
+    }
+}
+
+/// Removes legacy formatting codes from the messages of players,
+/// which clients would otherwise render.
+fn strip_codes(_ctx: &mut CommandCtx, message: &mut ChatMessage) {
+    if let CommandSender::Player(_) = message.sender {
+        message.text = strip_legacy(&message.text);
+    }
+}
+
+/// Replaces filtered words with asterisks.
+fn filter_words(ctx: &mut CommandCtx, message: &mut ChatMessage) {
+    let words = &ctx.resource::<StateInner>().config.chat.filtered_words;
+    if !words.is_empty() {
+        message.text = filter(&message.text, words);
+    }
+}
+
+/// Makes public messages local if local chat is enabled,
+/// except for those starting with `!`.
+fn choose_channel(ctx: &mut CommandCtx, message: &mut ChatMessage) {
+    let radius = ctx.resource::<StateInner>().config.chat.local_radius;
+    if message.kind != ChatKind::Public || radius <= 0.0 {
+        return;
+    }
+
+    if message.text.starts_with('!') {
+        message.text.remove(0);
+    } else if let CommandSender::Player(_) = message.sender {
+        message.kind = ChatKind::Local(radius);
+    }
+}
+
+/// Formats messages with the configured format, or like vanilla.
+fn format_message(ctx: &mut CommandCtx, message: &mut ChatMessage) {
+    let prefix = prefix(ctx, message);
+    let name = TextComponent::from_legacy(&format!("{}{}", prefix, message.name))
+        .insertion(message.name.clone())
+        .on_click(ClickEvent::SuggestCommand(format!(
+            "/tell {} ",
+            message.name
+        )));
+    let text = TextComponent::text(message.text.clone());
+
+    let component = match &message.kind {
+        ChatKind::Whisper(_) => {
+            TextComponent::translate("commands.message.display.incoming", vec![name, text])
+                .color(Color::Gray)
+                .italic(true)
+        }
+        ChatKind::Team(team) => TextComponent::translate(
+            "chat.type.team.text",
+            vec![TextComponent::text(team.clone()), name, text],
+        ),
+        ChatKind::Public | ChatKind::Local(_) => {
+            let format = &ctx.resource::<StateInner>().config.chat.format;
+            if format.is_empty() {
+                TextComponent::translate("chat.type.text", vec![name, text])
+            } else {
+                apply_format(format, &prefix, &message.name, &message.text)
+            }
+        }
+    };
+    message.component = Some(component);
+}
+
+/// Returns the prefix configured for the first
+/// permission group of the sender which has one.
+fn prefix(ctx: &CommandCtx, message: &ChatMessage) -> String {
+    let player = match message.sender {
+        CommandSender::Player(player) => player,
+        CommandSender::Console => return String::new(),
+    };
+
+    let uuid = ctx.world.get_component::<Uuid>(player).map(|uuid| *uuid);
+    let prefixes = &ctx.resource::<StateInner>().config.chat.prefixes;
+    ctx.resource::<Permissions>()
+        .groups(uuid, &message.name)
+        .into_iter()
+        .find_map(|group| prefixes.get(group))
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Delivers a message to each of its recipients whose chat mode shows it.
+fn deliver(ctx: &mut CommandCtx, message: ChatMessage) {
+    let component = message.component.clone().unwrap_or_else(|| {
+        TextComponent::translate(
+            "chat.type.text",
+            vec![message.name.as_str().into(), message.text.as_str().into()],
+        )
     });
 
-    info!("<{}> {}", player_name, event.message);
+    let (position, recipients) = match &message.kind {
+        ChatKind::Public => {
+            info!("<{}> {}", message.name, message.text);
+            (ChatPosition::Chat, all_players(ctx.world))
+        }
+        ChatKind::Local(radius) => {
+            info!("[Local] <{}> {}", message.name, message.text);
+            (
+                ChatPosition::Chat,
+                nearby_players(ctx.world, message.sender, *radius),
+            )
+        }
+        ChatKind::Whisper(targets) => (ChatPosition::SystemMessage, targets.clone()),
+        ChatKind::Team(team) => {
+            info!("[{}] <{}> {}", team, message.name, message.text);
+            let teams = &ctx.resource::<StateInner>().config.chat.teams;
+            (ChatPosition::Chat, team_players(ctx.world, teams, team))
+        }
+    };
+
+    for recipient in recipients {
+        let settings = ctx
+            .world
+            .get_component::<ChatSettings>(recipient)
+            .map_or_else(ChatSettings::default, |settings| *settings);
+        if !settings.mode.shows(position) {
+            continue;
+        }
+
+        if let Some(network) = ctx.world.get_component::<Network>(recipient) {
+            network.send(ChatMessageClientbound {
+                message: settings.apply_colors(ctx.localize(recipient, &component)),
+                position: position.id(),
+            });
+        }
+    }
+
+    // Show senders of private messages what they sent.
+    if let ChatKind::Whisper(targets) = &message.kind {
+        for target in targets {
+            let target_name = ctx.describe(&[*target]);
+            ctx.send_component(
+                TextComponent::translate(
+                    "feather.chat.whisper.outgoing",
+                    vec![target_name.into(), message.text.as_str().into()],
+                )
+                .color(Color::Gray)
+                .italic(true),
+            );
+        }
+    }
+}
+
+fn all_players(world: &World) -> Vec<Entity> {
+    <Read<Network>>::query()
+        .iter_entities_immutable(world)
+        .map(|(player, _)| player)
+        .collect()
+}
+
+/// Returns the players within `radius` of the sender, in its dimension.
+fn nearby_players(world: &World, sender: CommandSender, radius: f64) -> Vec<Entity> {
+    let sender = match sender {
+        CommandSender::Player(player) => player,
+        CommandSender::Console => return all_players(world),
+    };
+    let origin = *world.get_component::<Position>(sender).unwrap();
+    let dimension = *world.get_component::<DimensionId>(sender).unwrap();
+
+    <(Read<Network>, Read<Position>, Read<DimensionId>)>::query()
+        .iter_entities_immutable(world)
+        .filter(|(_, (_, position, player_dimension))| {
+            **player_dimension == dimension && position.distance(origin) <= radius
+        })
+        .map(|(player, _)| player)
+        .collect()
+}
+
+fn team_players(
+    world: &World,
+    teams: &HashMap<String, Vec<String>>,
+    team_name: &str,
+) -> Vec<Entity> {
+    <(Read<Network>, Read<Name>)>::query()
+        .iter_entities_immutable(world)
+        .filter(|(_, (_, name))| team(teams, &name.0).as_deref() == Some(team_name))
+        .map(|(player, _)| player)
+        .collect()
+}
+
+/// Replaces each filtered word in `text` with
+/// asterisks. Whole words are matched, ignoring case.
+fn filter(text: &str, words: &[String]) -> String {
+    let mut filtered = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        let end = rest
+            .find(|c: char| !c.is_alphanumeric())
+            .unwrap_or(rest.len());
+        let (word, after) = rest.split_at(end);
+
+        let lowercase = word.to_lowercase();
+        if !word.is_empty() && words.iter().any(|w| w.to_lowercase() == lowercase) {
+            filtered.extend(word.chars().map(|_| '*'));
+        } else {
+            filtered.push_str(word);
+        }
+
+        // Keep the character separating this word from the next.
+        let mut chars = after.chars();
+        if let Some(separator) = chars.next() {
+            filtered.push(separator);
+        }
+        rest = chars.as_str();
+    }
+
+    filtered
+}
+
+/// Formats a message with a configured format. The message
+/// is inserted after legacy codes are converted, so players
+/// cannot format their messages with them.
+fn apply_format(format: &str, prefix: &str, name: &str, text: &str) -> TextComponent {
+    let format = format.replace("{prefix}", prefix).replace("{name}", name);
+    let mut component = TextComponent::from_legacy(&format);
+    replace_text(&mut component, "{message}", text);
+    component
+}
+
+fn replace_text(component: &mut TextComponent, from: &str, to: &str) {
+    if let Content::Text { text } = &mut component.content {
+        if text.contains(from) {
+            *text = text.replace(from, to);
+        }
+    }
+    for child in &mut component.extra {
+        replace_text(child, from, to);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter() {
+        let words = vec!["darn".to_string(), "heck".to_string()];
+        assert_eq!(
+            filter("Darn it, what the heck!", &words),
+            "**** it, what the ****!"
+        );
+        assert_eq!(filter("darned hecks", &words), "darned hecks");
+        assert_eq!(filter("", &words), "");
+    }
+
+    #[test]
+    fn test_team() {
+        let mut teams = HashMap::new();
+        teams.insert("red".to_string(), vec!["Notch".to_string()]);
+        teams.insert(
+            "blue".to_string(),
+            vec!["notch".to_string(), "jeb_".to_string()],
+        );
+        assert_eq!(team(&teams, "Notch").as_deref(), Some("blue"));
+        assert_eq!(team(&teams, "jeb_").as_deref(), Some("blue"));
+        assert_eq!(team(&teams, "Dinnerbone"), None);
+    }
+
+    #[test]
+    fn test_apply_format() {
+        let component = apply_format(
+            "{prefix}§7{name}:§r {message}",
+            "§c[Admin] ",
+            "Notch",
+            "§ahi",
+        );
+        assert_eq!(component.to_plain(), "[Admin] Notch: §ahi");
+        assert_eq!(
+            component.extra,
+            vec![
+                TextComponent::text("[Admin] ").color(Color::Red),
+                TextComponent::text("Notch:").color(Color::Gray),
+                TextComponent::text(" §ahi"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_chat_modes() {
+        assert!(ChatMode::Enabled.shows(ChatPosition::Chat));
+        assert!(!ChatMode::CommandsOnly.shows(ChatPosition::Chat));
+        assert!(ChatMode::CommandsOnly.shows(ChatPosition::SystemMessage));
+        assert!(!ChatMode::Hidden.shows(ChatPosition::SystemMessage));
+        assert!(ChatMode::Hidden.shows(ChatPosition::GameInfo));
+    }
 }
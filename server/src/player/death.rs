@@ -0,0 +1,135 @@
+//! Player death and respawning.
+//!
+//! Players die when their health reaches zero or when killed,
+//! such as with `/kill`. A dead player sees the death screen
+//! until they choose to respawn, after which they return to
+//! their spawn point in the overworld of their world.
+
+use crate::health::{health_packet, Health, MAX_HEALTH};
+use crate::network::{Network, PacketQueue};
+use crate::player::sleep::{is_bed, SpawnPoint};
+use crate::state::State;
+use crate::util::teleport_now;
+use crate::worlds::{move_player_now, respawn_packet, DimensionId};
+use feather_core::network::packet::implementation::ClientStatus;
+use feather_core::{Dimension, Gamemode, Position};
+use legion::entity::Entity;
+use legion::query::Read;
+use legion::world::World;
+use tonks::{PreparedWorld, Query, Scheduler};
+
+/// The Client Status action sent when
+/// a player clicks "Respawn".
+const RESPAWN_ACTION: i32 = 0;
+
+/// Component storing whether a player is dead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Dead(pub bool);
+
+/// Kills a player immediately, such as in a lazy closure.
+pub fn kill_now(world: &mut World, player: Entity) {
+    match world.get_component_mut::<Dead>(player) {
+        Some(mut dead) => dead.0 = true,
+        None => return,
+    }
+
+    if let Some(mut health) = world.get_component_mut::<Health>(player) {
+        health.0 = 0.0;
+    }
+
+    if let Some(network) = world.get_component::<Network>(player) {
+        network.send(health_packet(0.0));
+    }
+}
+
+/// Returns where a player respawns: next to their bed if
+/// it still exists (or the spawn point is forced), or else
+/// at the spawn point of the given overworld.
+///
+/// If the chunk containing the bed isn't loaded, the bed
+/// can't be checked and is assumed to still exist.
+fn spawn_position(state: &State, overworld: DimensionId, spawn_point: SpawnPoint) -> Position {
+    if let Some(bed) = spawn_point.bed {
+        let exists = state
+            .dimension(overworld)
+            .block_at(bed)
+            .map_or(true, is_bed);
+        if exists || spawn_point.forced {
+            return position!(
+                f64::from(bed.x) + 0.5,
+                f64::from(bed.y) + 1.0,
+                f64::from(bed.z) + 0.5
+            );
+        }
+    }
+
+    let level = &state.world(overworld.world).level;
+    position!(
+        f64::from(level.spawn_x) + 0.5,
+        f64::from(level.spawn_y),
+        f64::from(level.spawn_z) + 0.5
+    )
+}
+
+/// Respawns dead players who ask to respawn.
+#[system]
+fn respawn(
+    state: &State,
+    queue: &PacketQueue,
+    _query: &mut Query<(Read<Dead>, Read<SpawnPoint>, Read<DimensionId>)>,
+    world: &mut PreparedWorld,
+) {
+    queue
+        .received::<ClientStatus>()
+        .filter(|(_, packet)| packet.action_id == RESPAWN_ACTION)
+        .for_each(|(player, _)| {
+            if !world
+                .get_component::<Dead>(player)
+                .map_or(false, |dead| dead.0)
+            {
+                return;
+            }
+
+            let dimension = *world.get_component::<DimensionId>(player).unwrap();
+            let spawn_point = *world.get_component::<SpawnPoint>(player).unwrap();
+            let overworld = dimension.with_kind(Dimension::Overworld);
+            let position = spawn_position(state, overworld, spawn_point);
+
+            state.exec_with_scheduler(move |world, scheduler| {
+                respawn_now(world, scheduler, player, overworld, position);
+            });
+        });
+}
+
+fn respawn_now(
+    world: &mut World,
+    scheduler: &mut Scheduler,
+    player: Entity,
+    dimension: DimensionId,
+    position: Position,
+) {
+    let current = match world.get_component::<DimensionId>(player) {
+        Some(current) => *current,
+        None => return,
+    };
+
+    if current == dimension {
+        // Respawning into the same dimension
+        // only recreates the client's player.
+        let gamemode = *world.get_component::<Gamemode>(player).unwrap();
+        world
+            .get_component::<Network>(player)
+            .unwrap()
+            .send(respawn_packet(scheduler, dimension, gamemode));
+        teleport_now(world, scheduler, player, position);
+    } else {
+        move_player_now(world, scheduler, player, dimension, position);
+    }
+
+    world.get_component_mut::<Dead>(player).unwrap().0 = false;
+    world.get_component_mut::<Health>(player).unwrap().0 = MAX_HEALTH;
+    world
+        .get_component::<Network>(player)
+        .unwrap()
+        .send(health_packet(MAX_HEALTH));
+}
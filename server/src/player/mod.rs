@@ -3,21 +3,29 @@
 use crate::broadcasters::movement::LastKnownPositions;
 use crate::chunk_logic::ChunkHolder;
 use crate::entity;
-use crate::entity::{CreationPacketCreator, EntityId, Name, SpawnPacketCreator};
+use crate::entity::{CreationPacketCreator, EntityId, EntityKind, Name, SpawnPacketCreator};
+use crate::health::{Health, MAX_HEALTH};
 use crate::io::NewClientInfo;
 use crate::join::Joined;
+use crate::locale::Locale;
 use crate::network::Network;
 use crate::p_inventory::EntityInventory;
+use crate::portal::PortalState;
 use crate::state::State;
 use crate::util::degrees_to_stops;
+use crate::vehicle::Riding;
+use crate::worlds::{DimensionId, WorldId};
 use feather_core::network::packet::implementation::{PlayerInfo, PlayerInfoAction, SpawnPlayer};
-use feather_core::{ClientboundAnimation, Gamemode, Packet, Position};
+use feather_core::{ClientboundAnimation, Dimension, Gamemode, Packet, Position, TextComponent};
 use legion::entity::Entity;
 use mojang_api::ProfileProperty;
 use tonks::{EntityAccessor, PreparedWorld};
 use uuid::Uuid;
 
+pub mod bow;
 pub mod chat;
+pub mod death;
+pub mod sleep;
 
 pub const PLAYER_EYE_HEIGHT: f64 = 1.62;
 
@@ -29,6 +37,10 @@ pub struct ProfileProperties(pub Vec<ProfileProperty>);
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Player;
 
+/// Component storing whether a player is sneaking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Sneaking(pub bool);
+
 /// Event triggered when a player joins.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct PlayerJoinEvent {
@@ -44,9 +56,19 @@ pub struct PlayerAnimationEvent {
 
 /// Creates a new player from the given `NewClientInfo`.
 ///
-/// This function also triggers the `PlayerJoinEvent` for this player.
+/// Players join the main world. This function also
+/// triggers the `PlayerJoinEvent` for this player.
 pub fn create(state: &State, info: NewClientInfo) {
-    entity::base(state, info.position)
+    // Players whose world no longer exists join the main world.
+    let world = info
+        .data
+        .world
+        .as_ref()
+        .and_then(|name| state.world_by_name(name))
+        .unwrap_or(WorldId::MAIN);
+    let dimension = DimensionId::new(world, Dimension::from_id(info.data.dimension));
+    let state = state.dimension(dimension);
+    entity::base(&state, EntityKind::Player, info.position)
         .with_component(info.uuid)
         .with_component(Network {
             sender: info.sender,
@@ -62,6 +84,17 @@ pub fn create(state: &State, info: NewClientInfo) {
         .with_component(CreationPacketCreator(&create_initialization_packet))
         .with_component(Gamemode::Creative) // TOOD: proper gamemode handling
         .with_component(EntityInventory::default())
+        .with_component(Sneaking::default())
+        .with_component(bow::BowCharge::default())
+        .with_component(sleep::Sleeping::default())
+        .with_component(sleep::SpawnPoint::from_data(&info.data))
+        .with_component(death::Dead::default())
+        .with_component(Health(MAX_HEALTH))
+        .with_component(chat::ChatSettings::default())
+        .with_component(chat::ChatSpam::default())
+        .with_component(Locale::default())
+        .with_component(PortalState::default())
+        .with_component(Riding::default())
         .with_component(Player)
         .with_exec(|_, scheduler, player| {
             scheduler.trigger(PlayerJoinEvent { player });
@@ -98,6 +131,7 @@ fn create_initialization_packet(
     let name = accessor.get_component::<Name>(world).unwrap();
     let props = accessor.get_component::<ProfileProperties>(world).unwrap();
     let uuid = *accessor.get_component::<Uuid>(world).unwrap();
+    let gamemode = *accessor.get_component::<Gamemode>(world).unwrap();
 
     let props = props
         .0
@@ -111,13 +145,9 @@ fn create_initialization_packet(
         })
         .collect::<Vec<_>>();
 
-    let display_name = json!({
-        "text": name.0
-    })
-    .to_string();
+    let display_name = Some(TextComponent::text(name.0.clone()));
 
-    let action =
-        PlayerInfoAction::AddPlayer(name.0.clone(), props, Gamemode::Creative, 50, display_name);
+    let action = PlayerInfoAction::AddPlayer(name.0.clone(), props, gamemode, 50, display_name);
 
     let packet = PlayerInfo { action, uuid };
     Box::new(packet)
@@ -0,0 +1,456 @@
+//! Beds, sleeping and player spawn points.
+//!
+//! Right-clicking a bed at night or during a thunderstorm puts
+//! the player to sleep and sets their spawn point to the bed. Once
+//! every player in a world's overworld has slept for `SLEEP_TICKS`,
+//! the night is skipped in that world. Beds only work in the
+//! overworld; elsewhere, they explode.
+
+use crate::block::direction::Direction;
+use crate::block::{facing, with_prop, BlockUpdateCause};
+use crate::entity::{EntityId, Monster};
+use crate::explosion::ExplosionEvent;
+use crate::game_rules::{GameRule, WorldGameRules};
+use crate::network::Network;
+use crate::physics::nearby_entities;
+use crate::player::Player;
+use crate::state::{DimensionState, State};
+use crate::time::{Time, WorldTimes};
+use crate::weather::{Weather, WorldWeather};
+use crate::worlds::DimensionId;
+use crossbeam::queue::SegQueue;
+use feather_core::network::packet::implementation::{
+    AnimationClientbound, ChatMessageClientbound, UseBed,
+};
+use feather_core::player_data::PlayerData;
+use feather_core::{
+    Block, BlockPosition, ClientboundAnimation, Dimension, Position, TextComponent,
+};
+use hashbrown::HashSet;
+use legion::entity::Entity;
+use legion::query::{Read, Write};
+use tonks::{PreparedWorld, Query, Trigger};
+
+/// The first tick of the night during
+/// which players can sleep.
+const NIGHT_START: u64 = 12541;
+/// The last tick of the night during
+/// which players can sleep.
+const NIGHT_END: u64 = 23458;
+/// The number of ticks players need to sleep
+/// before the night is skipped.
+pub const SLEEP_TICKS: u32 = 100;
+/// The power of a bed exploding outside the overworld.
+const BED_EXPLOSION_POWER: f32 = 5.0;
+
+/// The player's personal spawn point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SpawnPoint {
+    /// The bed the player last slept in, or `None`
+    /// if the player spawns at the world spawn.
+    pub bed: Option<BlockPosition>,
+    /// Whether the player spawns at `bed`
+    /// even if the bed is no longer there.
+    pub forced: bool,
+}
+
+impl SpawnPoint {
+    /// Reads the spawn point from a player's data.
+    pub fn from_data(data: &PlayerData) -> Self {
+        let bed = match (data.spawn_x, data.spawn_y, data.spawn_z) {
+            (Some(x), Some(y), Some(z)) => Some(BlockPosition::new(x, y, z)),
+            _ => None,
+        };
+
+        Self {
+            bed,
+            forced: data.spawn_forced,
+        }
+    }
+
+    /// Writes the spawn point into a player's data
+    /// so it can be saved.
+    pub fn write_to_data(self, data: &mut PlayerData) {
+        data.spawn_x = self.bed.map(|pos| pos.x);
+        data.spawn_y = self.bed.map(|pos| pos.y);
+        data.spawn_z = self.bed.map(|pos| pos.z);
+        data.spawn_forced = self.forced;
+    }
+}
+
+/// Component storing the bed a player is sleeping in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Sleeping {
+    /// The head of the bed, or `None` if
+    /// the player is awake.
+    pub bed: Option<BlockPosition>,
+    /// The number of ticks the player has been asleep,
+    /// up to `SLEEP_TICKS`.
+    pub ticks: u32,
+}
+
+impl Sleeping {
+    /// Returns whether the player is asleep.
+    pub fn is_sleeping(self) -> bool {
+        self.bed.is_some()
+    }
+
+    /// Returns whether the player has slept long
+    /// enough for the night to be skipped.
+    pub fn is_fully_asleep(self) -> bool {
+        self.is_sleeping() && self.ticks >= SLEEP_TICKS
+    }
+}
+
+/// Event triggered when a player right-clicks a bed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BedUseEvent {
+    pub player: Entity,
+    /// The position of the clicked half of the bed.
+    pub pos: BlockPosition,
+}
+
+/// Returns whether players can sleep at the given
+/// time of day and weather.
+pub fn can_sleep(time: Time, weather: &Weather) -> bool {
+    let time_of_day = time.time_of_day();
+    weather.is_thundering() || (time_of_day >= NIGHT_START && time_of_day <= NIGHT_END)
+}
+
+/// Returns whether the block is a bed.
+pub fn is_bed(block: Block) -> bool {
+    match block {
+        Block::WhiteBed(_)
+        | Block::OrangeBed(_)
+        | Block::MagentaBed(_)
+        | Block::LightBlueBed(_)
+        | Block::YellowBed(_)
+        | Block::LimeBed(_)
+        | Block::PinkBed(_)
+        | Block::GrayBed(_)
+        | Block::LightGrayBed(_)
+        | Block::CyanBed(_)
+        | Block::PurpleBed(_)
+        | Block::BlueBed(_)
+        | Block::BrownBed(_)
+        | Block::GreenBed(_)
+        | Block::RedBed(_)
+        | Block::BlackBed(_) => true,
+        _ => false,
+    }
+}
+
+/// Returns the positions of the head and foot
+/// of the bed with one half at `pos`.
+fn bed_halves(block: Block, pos: BlockPosition) -> (BlockPosition, BlockPosition) {
+    let facing = facing(block).unwrap_or(Direction::North);
+    match block.property("part").as_ref().map(String::as_str) {
+        Some("head") => (pos, facing.opposite().adjacent(pos)),
+        _ => (facing.adjacent(pos), pos),
+    }
+}
+
+/// Sets the `occupied` property of both halves
+/// of the bed whose head is at `head`.
+fn set_occupied(
+    state: &DimensionState,
+    head: BlockPosition,
+    occupied: bool,
+    cause: BlockUpdateCause,
+) {
+    let block = match state.block_at(head) {
+        Some(block) if is_bed(block) => block,
+        _ => return,
+    };
+
+    let (head, foot) = bed_halves(block, head);
+    for pos in &[head, foot] {
+        if let Some(block) = state.block_at(*pos) {
+            if is_bed(block) {
+                let block = with_prop(block, "occupied", occupied);
+                state.set_block_at(*pos, block, cause.clone());
+            }
+        }
+    }
+}
+
+/// Sends a status message shown above the player's hotbar.
+fn send_status(network: &Network, translate: &str) {
+    network.send(ChatMessageClientbound {
+        message: TextComponent::translate(translate, vec![]),
+        position: 2,
+    });
+}
+
+/// Makes a sleeping player in the given dimension leave their bed.
+pub fn wake_up(
+    state: &State,
+    dimension: DimensionId,
+    player: Entity,
+    id: EntityId,
+    sleeping: &mut Sleeping,
+) {
+    if let Some(bed) = sleeping.bed.take() {
+        let state = state.dimension(dimension);
+        set_occupied(&state, bed, false, BlockUpdateCause::Player(player));
+        state.broadcast_entity_update(
+            player,
+            AnimationClientbound {
+                entity_id: id.0,
+                animation: ClientboundAnimation::LeaveBed,
+            },
+            None,
+        );
+    }
+    sleeping.ticks = 0;
+}
+
+/// Event handler which puts players to sleep
+/// when they use a bed.
+#[event_handler]
+fn bed_use(
+    events: &[BedUseEvent],
+    state: &State,
+    times: &WorldTimes,
+    weather: &WorldWeather,
+    _query: &mut Query<(
+        Read<Position>,
+        Read<DimensionId>,
+        Read<EntityId>,
+        Read<Network>,
+        Read<Monster>,
+        Write<Sleeping>,
+        Write<SpawnPoint>,
+    )>,
+    world: &mut PreparedWorld,
+    explosions: &mut Trigger<ExplosionEvent>,
+) {
+    for event in events {
+        let dimension = *world.get_component::<DimensionId>(event.player).unwrap();
+        let state = &state.dimension(dimension);
+        let block = match state.block_at(event.pos) {
+            Some(block) if is_bed(block) => block,
+            _ => continue,
+        };
+        let (head, foot) = bed_halves(block, event.pos);
+        let center = position!(
+            f64::from(head.x) + 0.5,
+            f64::from(head.y) + 0.5,
+            f64::from(head.z) + 0.5
+        );
+
+        // Beds explode outside the overworld.
+        if dimension.kind != Dimension::Overworld {
+            let cause = BlockUpdateCause::Player(event.player);
+            state.set_block_at(foot, Block::Air, cause.clone());
+            state.set_block_at(head, Block::Air, cause);
+            explosions.trigger(ExplosionEvent {
+                dimension,
+                pos: center,
+                power: BED_EXPLOSION_POWER,
+                source: None,
+                fire: true,
+            });
+            continue;
+        }
+
+        if world
+            .get_component::<Sleeping>(event.player)
+            .unwrap()
+            .is_sleeping()
+        {
+            continue;
+        }
+
+        let network = world.get_component::<Network>(event.player).unwrap();
+        let pos = *world.get_component::<Position>(event.player).unwrap();
+
+        // The `occupied` property isn't reliable, since players
+        // may disconnect while asleep, so look for a sleeper instead.
+        let occupied = nearby_entities(
+            state.chunk_entities,
+            world,
+            center,
+            glm::vec3(1.5, 1.5, 1.5),
+        )
+        .into_iter()
+        .any(|entity| {
+            world
+                .get_component::<Sleeping>(entity)
+                .map_or(false, |sleeping| sleeping.bed == Some(head))
+        });
+        if occupied {
+            send_status(&network, "block.minecraft.bed.occupied");
+            continue;
+        }
+
+        if !can_sleep(*times.get(dimension.world), weather.get(dimension.world)) {
+            send_status(&network, "block.minecraft.bed.no_sleep");
+            continue;
+        }
+
+        if (pos.x - center.x).abs() > 3.0
+            || (pos.y - center.y).abs() > 2.0
+            || (pos.z - center.z).abs() > 3.0
+        {
+            send_status(&network, "block.minecraft.bed.too_far_away");
+            continue;
+        }
+
+        let monsters_nearby = nearby_entities(
+            state.chunk_entities,
+            world,
+            center,
+            glm::vec3(8.0, 5.0, 8.0),
+        )
+        .into_iter()
+        .any(|entity| world.get_component::<Monster>(entity).is_some());
+        if monsters_nearby {
+            send_status(&network, "block.minecraft.bed.not_safe");
+            continue;
+        }
+
+        let id = *world.get_component::<EntityId>(event.player).unwrap();
+        drop(network);
+
+        *world.get_component_mut::<Sleeping>(event.player).unwrap() = Sleeping {
+            bed: Some(head),
+            ticks: 0,
+        };
+        *world.get_component_mut::<SpawnPoint>(event.player).unwrap() = SpawnPoint {
+            bed: Some(head),
+            forced: false,
+        };
+
+        set_occupied(state, head, true, BlockUpdateCause::Player(event.player));
+        state.broadcast_entity_update(
+            event.player,
+            UseBed {
+                entity_id: id.0,
+                location: head,
+            },
+            None,
+        );
+    }
+}
+
+/// System which advances sleeping players and skips the night
+/// in each world whose overworld players are all fully asleep.
+///
+/// Players wake up when it becomes day
+/// or their bed is removed.
+#[system]
+fn sleep_tick(
+    state: &State,
+    times: &mut WorldTimes,
+    weather: &mut WorldWeather,
+    rules: &WorldGameRules,
+    query: &mut Query<(
+        Read<Player>,
+        Read<DimensionId>,
+        Read<EntityId>,
+        Write<Sleeping>,
+    )>,
+    world: &mut PreparedWorld,
+) {
+    let players = SegQueue::new();
+    query.par_entities_for_each(world, |(entity, (_, dimension, id, sleeping))| {
+        if dimension.kind == Dimension::Overworld {
+            players.push((entity, *dimension, *id, *sleeping));
+        }
+    });
+
+    // Worlds with players in their overworld, and
+    // those in which some of these players are awake.
+    let mut worlds = HashSet::new();
+    let mut awake = HashSet::new();
+    let mut sleepers = vec![];
+
+    while let Ok((player, dimension, id, sleeping)) = players.pop() {
+        worlds.insert(dimension.world);
+        let bed = match sleeping.bed {
+            Some(bed) => bed,
+            None => {
+                awake.insert(dimension.world);
+                continue;
+            }
+        };
+        let can_sleep = can_sleep(*times.get(dimension.world), weather.get(dimension.world));
+
+        let mut sleeping = world.get_component_mut::<Sleeping>(player).unwrap();
+        let bed_exists = state
+            .dimension(dimension)
+            .block_at(bed)
+            .map_or(false, is_bed);
+        if !can_sleep || !bed_exists {
+            wake_up(state, dimension, player, id, &mut sleeping);
+            awake.insert(dimension.world);
+            continue;
+        }
+
+        sleeping.ticks = (sleeping.ticks + 1).min(SLEEP_TICKS);
+        if !sleeping.is_fully_asleep() {
+            awake.insert(dimension.world);
+        }
+        sleepers.push((player, dimension, id));
+    }
+
+    for &world_id in worlds.difference(&awake) {
+        // Everyone is asleep: skip to the next morning.
+        let rules = rules.get(world_id);
+        let daylight_cycle = rules.get_bool(GameRule::DoDaylightCycle);
+        if daylight_cycle {
+            let time = times.get_mut(world_id);
+            time.day_time = (time.day_time / 24_000 + 1) * 24_000;
+            state.broadcast_world(world_id, time.update_packet(daylight_cycle), None);
+        }
+        if rules.get_bool(GameRule::DoWeatherCycle) {
+            weather.get_mut(world_id).clear();
+        }
+
+        for &(player, dimension, id) in &sleepers {
+            if dimension.world == world_id {
+                let mut sleeping = world.get_component_mut::<Sleeping>(player).unwrap();
+                wake_up(state, dimension, player, id, &mut sleeping);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::level::LevelData;
+
+    #[test]
+    fn test_spawn_point_data() {
+        let spawn = SpawnPoint {
+            bed: Some(BlockPosition::new(10, 64, -20)),
+            forced: false,
+        };
+
+        let mut data = PlayerData::default();
+        spawn.write_to_data(&mut data);
+        assert_eq!(data.spawn_y, Some(64));
+        assert_eq!(SpawnPoint::from_data(&data), spawn);
+
+        SpawnPoint::default().write_to_data(&mut data);
+        assert_eq!(data.spawn_x, None);
+        assert_eq!(SpawnPoint::from_data(&data), SpawnPoint::default());
+    }
+
+    #[test]
+    fn test_can_sleep() {
+        let weather = Weather::from_level(&LevelData::default());
+        let time = |day_time| Time {
+            world_age: 0,
+            day_time,
+        };
+
+        assert!(!can_sleep(time(6000), &weather));
+        assert!(!can_sleep(time(12540), &weather));
+        assert!(can_sleep(time(12541), &weather));
+        assert!(can_sleep(time(24_000 + 18000), &weather));
+        assert!(!can_sleep(time(23459), &weather));
+    }
+}
@@ -0,0 +1,665 @@
+//! Nether and end portals.
+//!
+//! Nether portals are lit by using flint and steel or a fire charge
+//! inside an obsidian frame. Players standing in a nether portal long
+//! enough travel to the other side, where coordinates are scaled 8:1;
+//! the nearest existing portal is used, or a new one is built. End portals
+//! teleport players immediately: to the obsidian platform in the End,
+//! or from the End back to the world spawn.
+//!
+//! Only players travel through portals for now.
+
+use crate::block::direction::Direction;
+use crate::block::{BlockUpdateCause, BlockUpdateEvent};
+use crate::entity::EntityMoveEvent;
+use crate::network::Network;
+use crate::player::Player;
+use crate::state::{DimensionState, State};
+use crate::worlds::{move_player, DimensionId};
+use crossbeam::queue::SegQueue;
+use feather_blocks::{NetherPortalAxis, NetherPortalData};
+use feather_core::network::packet::implementation::PlayerPositionAndLookClientbound;
+use feather_core::{
+    Block, BlockExt, BlockPosition, ChunkPosition, ChunkSection, Dimension, Gamemode, Position,
+};
+use legion::query::{Read, Write};
+use tonks::{PreparedWorld, Query, Trigger};
+
+/// Minimum inner width of a nether portal frame.
+pub const MIN_PORTAL_WIDTH: i32 = 2;
+/// Maximum inner width of a nether portal frame.
+pub const MAX_PORTAL_WIDTH: i32 = 21;
+/// Minimum inner height of a nether portal frame.
+pub const MIN_PORTAL_HEIGHT: i32 = 3;
+/// Maximum inner height of a nether portal frame.
+pub const MAX_PORTAL_HEIGHT: i32 = 21;
+/// Ratio of overworld distances to Nether distances.
+pub const NETHER_SCALE: f64 = 8.0;
+/// Number of ticks a survival player needs to stand
+/// in a nether portal before being teleported.
+const PORTAL_TICKS: u32 = 80;
+/// Horizontal radius, in blocks, searched for an existing
+/// portal at the destination. This is smaller than vanilla's
+/// to keep the search cheap.
+const PORTAL_SEARCH_RADIUS: i32 = 32;
+/// The obsidian platform players arrive on in the End.
+pub const END_SPAWN: BlockPosition = BlockPosition {
+    x: 100,
+    y: 49,
+    z: 0,
+};
+
+/// Component tracking a player's use of portals.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PortalState {
+    /// Number of ticks the player has stood in a nether portal.
+    pub ticks: u32,
+    /// Whether the player arrived through a portal and has
+    /// not yet left it. Portals can't be used until then.
+    pub cooldown: bool,
+    /// The portal to find or build once the destination is loaded.
+    pub link: Option<PortalLink>,
+}
+
+/// A portal which has to be found or built
+/// at a player's destination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortalLink {
+    /// A nether portal in the given dimension.
+    Nether(DimensionId),
+    /// The obsidian platform in the given End.
+    EndPlatform(DimensionId),
+}
+
+impl PortalLink {
+    fn dimension(self) -> DimensionId {
+        match self {
+            PortalLink::Nether(dimension) | PortalLink::EndPlatform(dimension) => dimension,
+        }
+    }
+}
+
+/// The inside of a nether portal frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PortalFrame {
+    /// The lowest inner block on the negative side of the frame.
+    pub origin: BlockPosition,
+    /// The axis along which the portal is oriented.
+    pub axis: NetherPortalAxis,
+    /// The inner width of the frame.
+    pub width: i32,
+    /// The inner height of the frame.
+    pub height: i32,
+}
+
+impl PortalFrame {
+    /// Finds an empty obsidian frame around `pos` along either axis.
+    pub fn find(state: &DimensionState, pos: BlockPosition) -> Option<Self> {
+        Self::find_along(state, pos, NetherPortalAxis::X)
+            .or_else(|| Self::find_along(state, pos, NetherPortalAxis::Z))
+    }
+
+    fn find_along(
+        state: &DimensionState,
+        pos: BlockPosition,
+        axis: NetherPortalAxis,
+    ) -> Option<Self> {
+        let dir = axis_direction(axis);
+        let is_obsidian = |pos| state.block_at(pos) == Some(Block::Obsidian);
+        let is_empty = |pos| state.block_at(pos).map_or(false, is_portal_interior);
+
+        // Move down to the bottom of the frame, then
+        // to its negative side.
+        let mut origin = pos;
+        for _ in 0..MAX_PORTAL_HEIGHT {
+            let below = Direction::Down.adjacent(origin);
+            if is_obsidian(below) {
+                break;
+            }
+            if !is_empty(below) {
+                return None;
+            }
+            origin = below;
+        }
+        for _ in 0..MAX_PORTAL_WIDTH {
+            let next = dir.opposite().adjacent(origin);
+            if is_obsidian(next) {
+                break;
+            }
+            if !is_empty(next) {
+                return None;
+            }
+            origin = next;
+        }
+        if !is_empty(origin) || !is_obsidian(Direction::Down.adjacent(origin)) {
+            return None;
+        }
+
+        let mut width = 0;
+        while !is_obsidian(offset(origin, dir, width)) {
+            let pos = offset(origin, dir, width);
+            if width >= MAX_PORTAL_WIDTH
+                || !is_empty(pos)
+                || !is_obsidian(Direction::Down.adjacent(pos))
+            {
+                return None;
+            }
+            width += 1;
+        }
+        if width < MIN_PORTAL_WIDTH {
+            return None;
+        }
+
+        // Check each row upward until reaching the top of the frame.
+        for height in 0..=MAX_PORTAL_HEIGHT {
+            let row = offset(origin, Direction::Up, height);
+            // The corners of the top row needn't be obsidian.
+            if (0..width).all(|i| is_obsidian(offset(row, dir, i))) {
+                return if height >= MIN_PORTAL_HEIGHT {
+                    Some(Self {
+                        origin,
+                        axis,
+                        width,
+                        height,
+                    })
+                } else {
+                    None
+                };
+            }
+            if !is_obsidian(offset(row, dir, -1)) || !is_obsidian(offset(row, dir, width)) {
+                return None;
+            }
+            if !(0..width).all(|i| is_empty(offset(row, dir, i))) {
+                return None;
+            }
+        }
+
+        None
+    }
+
+    /// Returns the positions inside the frame.
+    pub fn interior(self) -> impl Iterator<Item = BlockPosition> {
+        let dir = axis_direction(self.axis);
+        (0..self.height).flat_map(move |y| {
+            (0..self.width).map(move |i| offset(offset(self.origin, Direction::Up, y), dir, i))
+        })
+    }
+
+    /// Fills the frame with portal blocks.
+    pub fn fill(self, state: &DimensionState, cause: BlockUpdateCause) {
+        let block = Block::NetherPortal(NetherPortalData { axis: self.axis });
+        for pos in self.interior() {
+            state.set_block_at(pos, block, cause.clone());
+        }
+    }
+}
+
+/// Returns the horizontal direction in which
+/// a portal with the given axis extends.
+fn axis_direction(axis: NetherPortalAxis) -> Direction {
+    match axis {
+        NetherPortalAxis::X => Direction::East,
+        NetherPortalAxis::Z => Direction::South,
+    }
+}
+
+/// Returns the position `n` blocks from `pos` in the given direction.
+fn offset(pos: BlockPosition, dir: Direction, n: i32) -> BlockPosition {
+    let offset = dir.offset();
+    BlockPosition::new(
+        pos.x + offset.x * n,
+        pos.y + offset.y * n,
+        pos.z + offset.z * n,
+    )
+}
+
+/// Returns whether a block may be inside a portal frame being lit.
+fn is_portal_interior(block: Block) -> bool {
+    match block {
+        Block::Air | Block::CaveAir | Block::Fire(_) => true,
+        _ => false,
+    }
+}
+
+/// Returns whether a portal block is still enclosed by
+/// obsidian or other portal blocks in its plane.
+fn is_portal_supported(state: &DimensionState, pos: BlockPosition, axis: NetherPortalAxis) -> bool {
+    let dir = axis_direction(axis);
+    [Direction::Up, Direction::Down, dir, dir.opposite()]
+        .iter()
+        .all(|side| match state.block_at(side.adjacent(pos)) {
+            Some(Block::Obsidian) | Some(Block::NetherPortal(_)) => true,
+            _ => false,
+        })
+}
+
+/// Event handler which breaks nether portals when their frame changes.
+///
+/// Each removed portal block causes another block update,
+/// so the whole portal disappears.
+#[event_handler]
+fn portal_break(event: &BlockUpdateEvent, state: &State) {
+    let state = &state.dimension(event.dimension);
+    for dir in &Direction::UPDATE_ORDER {
+        let pos = dir.adjacent(event.pos);
+        if let Some(Block::NetherPortal(data)) = state.block_at(pos) {
+            if !is_portal_supported(state, pos, data.axis) {
+                state.set_block_at(pos, Block::Air, BlockUpdateCause::Neighbor);
+            }
+        }
+    }
+}
+
+/// Returns the position in `to` corresponding to
+/// `pos` in `from`, according to the Nether's scale.
+pub fn scale_position(pos: Position, from: Dimension, to: Dimension) -> Position {
+    let scale = match (from, to) {
+        (Dimension::Overworld, Dimension::Nether) => 1.0 / NETHER_SCALE,
+        (Dimension::Nether, Dimension::Overworld) => NETHER_SCALE,
+        _ => 1.0,
+    };
+    Position {
+        x: pos.x * scale,
+        z: pos.z * scale,
+        ..pos
+    }
+}
+
+/// System which sends players standing in
+/// portals to the appropriate dimension.
+#[system]
+fn portal_tick(
+    state: &State,
+    query: &mut Query<(
+        Read<Player>,
+        Read<Position>,
+        Read<DimensionId>,
+        Read<Gamemode>,
+        Write<PortalState>,
+    )>,
+    world: &mut PreparedWorld,
+) {
+    let players = SegQueue::new();
+    query.par_entities_for_each(world, |(entity, (_, pos, dimension, gamemode, _))| {
+        players.push((entity, *pos, *dimension, *gamemode));
+    });
+
+    while let Ok((player, pos, dimension, gamemode)) = players.pop() {
+        let mut portal = world.get_component_mut::<PortalState>(player).unwrap();
+        if portal.link.is_some() {
+            continue;
+        }
+
+        let scoped = state.dimension(dimension);
+        let feet = pos.block_pos();
+        let blocks = [
+            scoped.block_at(feet),
+            scoped.block_at(Direction::Up.adjacent(feet)),
+        ];
+        let in_end_portal = blocks.contains(&Some(Block::EndPortal));
+        let in_nether_portal = blocks.iter().any(|block| match block {
+            Some(Block::NetherPortal(_)) => true,
+            _ => false,
+        });
+
+        if !in_end_portal && !in_nether_portal {
+            *portal = PortalState::default();
+            continue;
+        }
+        if portal.cooldown {
+            continue;
+        }
+
+        let (target, position, link) = if in_end_portal {
+            match dimension.kind {
+                Dimension::End => {
+                    let level = &state.world(dimension.world).level;
+                    let spawn = position!(
+                        f64::from(level.spawn_x) + 0.5,
+                        f64::from(level.spawn_y),
+                        f64::from(level.spawn_z) + 0.5
+                    );
+                    (dimension.with_kind(Dimension::Overworld), spawn, None)
+                }
+                _ => {
+                    let platform = position!(
+                        f64::from(END_SPAWN.x) + 0.5,
+                        f64::from(END_SPAWN.y),
+                        f64::from(END_SPAWN.z) + 0.5
+                    );
+                    let end = dimension.with_kind(Dimension::End);
+                    (end, platform, Some(PortalLink::EndPlatform(end)))
+                }
+            }
+        } else {
+            portal.ticks += 1;
+            let required = match gamemode {
+                Gamemode::Creative => 1,
+                _ => PORTAL_TICKS,
+            };
+            if portal.ticks < required {
+                continue;
+            }
+
+            let target = match dimension.kind {
+                Dimension::Overworld => dimension.with_kind(Dimension::Nether),
+                Dimension::Nether => dimension.with_kind(Dimension::Overworld),
+                Dimension::End => continue,
+            };
+            let position = scale_position(pos, dimension.kind, target.kind);
+            (target, position, Some(PortalLink::Nether(target)))
+        };
+
+        *portal = PortalState {
+            ticks: 0,
+            cooldown: true,
+            link,
+        };
+        move_player(state, player, target, position);
+    }
+}
+
+/// System which finds or builds the portal at a player's
+/// destination once the surrounding chunks have loaded,
+/// then moves the player into it.
+#[system]
+fn portal_link(
+    state: &State,
+    query: &mut Query<(
+        Read<Player>,
+        Read<DimensionId>,
+        Read<Network>,
+        Write<Position>,
+        Write<PortalState>,
+    )>,
+    world: &mut PreparedWorld,
+    move_events: &mut Trigger<EntityMoveEvent>,
+) {
+    let pending = SegQueue::new();
+    query.par_entities_for_each(world, |(entity, (_, dimension, _, pos, portal))| {
+        if let Some(link) = portal.link {
+            if link.dimension() == *dimension {
+                pending.push((entity, link, *pos));
+            }
+        }
+    });
+
+    while let Ok((player, link, pos)) = pending.pop() {
+        let scoped = state.dimension(link.dimension());
+        let radius = match link {
+            PortalLink::Nether(_) => {
+                (PORTAL_SEARCH_RADIUS / 16).min(i32::from(state.config.server.view_distance))
+            }
+            PortalLink::EndPlatform(_) => 0,
+        };
+        if !chunks_loaded(&scoped, pos.chunk_pos(), radius) {
+            continue;
+        }
+
+        let destination = match link {
+            PortalLink::Nether(_) => find_portal(&scoped, pos.block_pos())
+                .unwrap_or_else(|| build_portal(&scoped, pos.block_pos())),
+            PortalLink::EndPlatform(_) => {
+                build_end_platform(&scoped);
+                END_SPAWN
+            }
+        };
+        let new_pos = Position {
+            x: f64::from(destination.x) + 0.5,
+            y: f64::from(destination.y),
+            z: f64::from(destination.z) + 0.5,
+            ..pos
+        };
+
+        *world.get_component_mut::<Position>(player).unwrap() = new_pos;
+        world.get_component_mut::<PortalState>(player).unwrap().link = None;
+        world
+            .get_component::<Network>(player)
+            .unwrap()
+            .send(PlayerPositionAndLookClientbound {
+                x: new_pos.x,
+                y: new_pos.y,
+                z: new_pos.z,
+                yaw: new_pos.yaw,
+                pitch: new_pos.pitch,
+                flags: 0,
+                teleport_id: 0,
+            });
+        move_events.trigger(EntityMoveEvent { entity: player });
+    }
+}
+
+/// Returns whether all chunks within `radius` of `center` are loaded.
+fn chunks_loaded(state: &DimensionState, center: ChunkPosition, radius: i32) -> bool {
+    (-radius..=radius).all(|x| {
+        (-radius..=radius).all(|z| {
+            state
+                .chunk_at(ChunkPosition::new(center.x + x, center.z + z))
+                .is_some()
+        })
+    })
+}
+
+/// Returns the height of the highest block a portal may be built at.
+fn max_portal_y(dimension: Dimension) -> i32 {
+    match dimension {
+        Dimension::Nether => 120,
+        _ => 250,
+    }
+}
+
+/// Finds the bottom of the nether portal nearest to `pos`.
+///
+/// Chunks are scanned section by section, skipping
+/// sections whose palette contains no portal blocks.
+fn find_portal(state: &DimensionState, pos: BlockPosition) -> Option<BlockPosition> {
+    let min = BlockPosition::new(
+        pos.x - PORTAL_SEARCH_RADIUS,
+        1,
+        pos.z - PORTAL_SEARCH_RADIUS,
+    );
+    let max = BlockPosition::new(
+        pos.x + PORTAL_SEARCH_RADIUS,
+        max_portal_y(state.dimension.kind) - 1,
+        pos.z + PORTAL_SEARCH_RADIUS,
+    );
+
+    let mut nearest: Option<(i32, BlockPosition)> = None;
+    for chunk_x in (min.x >> 4)..=(max.x >> 4) {
+        for chunk_z in (min.z >> 4)..=(max.z >> 4) {
+            let chunk = match state.chunk_at(ChunkPosition::new(chunk_x, chunk_z)) {
+                Some(chunk) => chunk,
+                None => continue,
+            };
+
+            for (index, section) in chunk.sections().into_iter().enumerate() {
+                let section = match section {
+                    Some(section) if may_contain_portal(section) => section,
+                    _ => continue,
+                };
+
+                for (x, y, z) in section_positions() {
+                    let candidate = BlockPosition::new(
+                        chunk_x * 16 + x as i32,
+                        index as i32 * 16 + y as i32,
+                        chunk_z * 16 + z as i32,
+                    );
+                    if candidate.x < min.x
+                        || candidate.x > max.x
+                        || candidate.y < min.y
+                        || candidate.y > max.y
+                        || candidate.z < min.z
+                        || candidate.z > max.z
+                    {
+                        continue;
+                    }
+
+                    match section.block_at(x, y, z) {
+                        Block::NetherPortal(_) => (),
+                        _ => continue,
+                    }
+                    // Only consider the bottom of each portal column.
+                    if let Block::NetherPortal(_) = chunk.block_at(x, candidate.y as usize - 1, z) {
+                        continue;
+                    }
+
+                    let distance = (candidate.x - pos.x).pow(2)
+                        + (candidate.y - pos.y).pow(2)
+                        + (candidate.z - pos.z).pow(2);
+                    if nearest.map_or(true, |(nearest, _)| distance < nearest) {
+                        nearest = Some((distance, candidate));
+                    }
+                }
+            }
+        }
+    }
+    nearest.map(|(_, pos)| pos)
+}
+
+/// Returns whether a chunk section may contain nether portal blocks.
+/// Sections using the global palette always may.
+fn may_contain_portal(section: &ChunkSection) -> bool {
+    if section.empty() {
+        return false;
+    }
+
+    section.palette().map_or(true, |palette| {
+        palette
+            .iter()
+            .any(|id| match Block::from_native_state_id(*id) {
+                Some(Block::NetherPortal(_)) => true,
+                _ => false,
+            })
+    })
+}
+
+/// Returns the local coordinates of the blocks in a chunk section.
+fn section_positions() -> impl Iterator<Item = (usize, usize, usize)> {
+    (0..16).flat_map(|y| (0..16).flat_map(move |z| (0..16).map(move |x| (x, y, z))))
+}
+
+/// Builds a new nether portal near `pos`, returning
+/// the position of its bottom inner block.
+///
+/// The portal is placed on the first solid floor below
+/// `pos`; if there is none, it stands on an obsidian platform.
+fn build_portal(state: &DimensionState, pos: BlockPosition) -> BlockPosition {
+    let max_y = max_portal_y(state.dimension.kind);
+    let start = pos.y.max(2).min(max_y);
+    let y = (2..=start)
+        .rev()
+        .find(|y| {
+            let pos = BlockPosition::new(pos.x, *y, pos.z);
+            state.block_at(pos).map_or(false, is_portal_interior)
+                && state
+                    .block_at(Direction::Down.adjacent(pos))
+                    .map_or(false, |block| block.is_solid())
+        })
+        .unwrap_or(start);
+    let origin = BlockPosition::new(pos.x, y, pos.z);
+
+    let cause = BlockUpdateCause::Portal;
+    let dir = axis_direction(NetherPortalAxis::X);
+    let side = Direction::South;
+
+    // Clear space on both sides of the portal and
+    // give it a floor so players can walk out.
+    for i in -1..=MIN_PORTAL_WIDTH {
+        for s in &[-1, 1] {
+            let column = offset(offset(origin, dir, i), side, *s);
+            state.set_block_at(
+                Direction::Down.adjacent(column),
+                Block::Obsidian,
+                cause.clone(),
+            );
+            for h in 0..MIN_PORTAL_HEIGHT {
+                state.set_block_at(offset(column, Direction::Up, h), Block::Air, cause.clone());
+            }
+        }
+    }
+
+    // Build the frame.
+    for i in -1..=MIN_PORTAL_WIDTH {
+        state.set_block_at(
+            Direction::Down.adjacent(offset(origin, dir, i)),
+            Block::Obsidian,
+            cause.clone(),
+        );
+        state.set_block_at(
+            offset(offset(origin, dir, i), Direction::Up, MIN_PORTAL_HEIGHT),
+            Block::Obsidian,
+            cause.clone(),
+        );
+    }
+    for h in 0..MIN_PORTAL_HEIGHT {
+        let row = offset(origin, Direction::Up, h);
+        state.set_block_at(offset(row, dir, -1), Block::Obsidian, cause.clone());
+        state.set_block_at(
+            offset(row, dir, MIN_PORTAL_WIDTH),
+            Block::Obsidian,
+            cause.clone(),
+        );
+    }
+
+    PortalFrame {
+        origin,
+        axis: NetherPortalAxis::X,
+        width: MIN_PORTAL_WIDTH,
+        height: MIN_PORTAL_HEIGHT,
+    }
+    .fill(state, cause);
+
+    origin
+}
+
+/// Builds the obsidian platform players arrive on in the End,
+/// clearing the space above it.
+fn build_end_platform(state: &DimensionState) {
+    let cause = BlockUpdateCause::Portal;
+    for x in -2..=2 {
+        for z in -2..=2 {
+            let floor = BlockPosition::new(END_SPAWN.x + x, END_SPAWN.y - 1, END_SPAWN.z + z);
+            state.set_block_at(floor, Block::Obsidian, cause.clone());
+            for y in 0..3 {
+                state.set_block_at(
+                    offset(floor, Direction::Up, y + 1),
+                    Block::Air,
+                    cause.clone(),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scale_position() {
+        let pos = position!(80.0, 64.0, -16.0);
+
+        let nether = scale_position(pos, Dimension::Overworld, Dimension::Nether);
+        assert_eq!((nether.x, nether.y, nether.z), (10.0, 64.0, -2.0));
+
+        let overworld = scale_position(nether, Dimension::Nether, Dimension::Overworld);
+        assert_eq!((overworld.x, overworld.z), (pos.x, pos.z));
+
+        let end = scale_position(pos, Dimension::Overworld, Dimension::End);
+        assert_eq!((end.x, end.z), (pos.x, pos.z));
+    }
+
+    #[test]
+    fn test_section_positions() {
+        let positions: Vec<_> = section_positions().collect();
+        assert_eq!(positions.len(), 4096);
+        assert_eq!(positions[0], (0, 0, 0));
+        assert_eq!(positions[4095], (15, 15, 15));
+    }
+
+    #[test]
+    fn test_axis_direction() {
+        assert_eq!(axis_direction(NetherPortalAxis::X).axis(), "x");
+        assert_eq!(axis_direction(NetherPortalAxis::Z).axis(), "z");
+    }
+}
@@ -0,0 +1,230 @@
+//! Components which change after a delay: redstone
+//! torches, repeaters and comparators.
+//!
+//! Repeaters and comparators have a `facing` property which
+//! points toward their input; they output in the opposite
+//! direction.
+
+use super::power::{output_direction, received_power, strong_power, weak_power};
+use super::{power, Component, Redstone};
+use crate::block::tick::TickPriority;
+use crate::block::{facing, prop_bool, prop_int, with_prop};
+use feather_core::{Block, BlockPosition};
+use std::cmp::max;
+
+/// The delay, in ticks, between a redstone
+/// torch's input changing and the torch toggling.
+const TORCH_DELAY: u64 = 2;
+/// The delay, in ticks, of a comparator.
+const COMPARATOR_DELAY: u64 = 2;
+
+/// Returns whether the torch at `pos` receives
+/// power from the block it is attached to.
+fn torch_powered(redstone: &Redstone, pos: BlockPosition, block: Block) -> bool {
+    let attached = power::attached_direction(block);
+    received_power(redstone, pos, attached, true) > 0
+}
+
+pub fn update_torch(redstone: &mut Redstone, pos: BlockPosition, block: Block) {
+    if prop_bool(block, "lit") == torch_powered(redstone, pos, block) {
+        redstone.schedule(pos, block, TORCH_DELAY, TickPriority::Normal);
+    }
+}
+
+pub fn tick_torch(redstone: &mut Redstone, pos: BlockPosition, block: Block) {
+    let lit = !torch_powered(redstone, pos, block);
+    redstone.set_block(pos, with_prop(block, "lit", lit));
+}
+
+/// Returns the power received by the input
+/// of the repeater or comparator at `pos`.
+fn rear_input(redstone: &Redstone, pos: BlockPosition, block: Block) -> u8 {
+    let input = match facing(block) {
+        Some(input) => input,
+        None => return 0,
+    };
+
+    let received = received_power(redstone, pos, input, true);
+    match redstone.block_at(input.adjacent(pos)) {
+        Block::RedstoneWire(data) => max(received, data.power as u8),
+        _ => received,
+    }
+}
+
+/// Returns the highest power received by the sides
+/// of the repeater or comparator at `pos`.
+///
+/// Repeaters are only affected by other repeaters
+/// and comparators, while comparators accept power
+/// from any component.
+fn side_input(redstone: &Redstone, pos: BlockPosition, block: Block, diodes_only: bool) -> u8 {
+    let input = match facing(block) {
+        Some(input) => input,
+        None => return 0,
+    };
+
+    [input.rotate_cw(), input.rotate_ccw()]
+        .iter()
+        .map(|side| {
+            let source_pos = side.adjacent(pos);
+            let source = redstone.block_at(source_pos);
+            let toward = side.opposite();
+
+            match Component::of(source) {
+                Some(Component::Repeater) | Some(Component::Comparator) => {
+                    weak_power(redstone, source_pos, toward, true)
+                }
+                _ if diodes_only => 0,
+                Some(Component::RedstoneBlock) => 15,
+                Some(Component::Wire) => prop_int(source, "power") as u8,
+                Some(_) => strong_power(redstone, source_pos, toward, true),
+                None => 0,
+            }
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Returns the tick priority for a repeater or comparator,
+/// which is higher if it outputs into another diode which
+/// does not point back at it.
+fn diode_priority(
+    redstone: &Redstone,
+    pos: BlockPosition,
+    block: Block,
+    facing_diode: TickPriority,
+    otherwise: TickPriority,
+) -> TickPriority {
+    let output = output_direction(block);
+    let front = redstone.block_at(output.adjacent(pos));
+    match Component::of(front) {
+        Some(Component::Repeater) | Some(Component::Comparator)
+            if facing(front) != Some(output) =>
+        {
+            facing_diode
+        }
+        _ => otherwise,
+    }
+}
+
+fn repeater_delay(block: Block) -> u64 {
+    prop_int(block, "delay").max(1) as u64 * 2
+}
+
+pub fn update_repeater(redstone: &mut Redstone, pos: BlockPosition, mut block: Block) {
+    let locked = side_input(redstone, pos, block, true) > 0;
+    if locked != prop_bool(block, "locked") {
+        block = with_prop(block, "locked", locked);
+        redstone.set_block(pos, block);
+    }
+
+    if locked {
+        return;
+    }
+
+    let powered = prop_bool(block, "powered");
+    let should_be_powered = rear_input(redstone, pos, block) > 0;
+    if powered != should_be_powered {
+        let priority = if powered {
+            TickPriority::VeryHigh
+        } else {
+            TickPriority::High
+        };
+        let priority = diode_priority(redstone, pos, block, TickPriority::ExtremelyHigh, priority);
+        redstone.schedule(pos, block, repeater_delay(block), priority);
+    }
+}
+
+pub fn tick_repeater(redstone: &mut Redstone, pos: BlockPosition, block: Block) {
+    if prop_bool(block, "locked") {
+        return;
+    }
+
+    let powered = prop_bool(block, "powered");
+    let should_be_powered = rear_input(redstone, pos, block) > 0;
+
+    if powered && !should_be_powered {
+        redstone.set_block(pos, with_prop(block, "powered", false));
+    } else if !powered {
+        let block = with_prop(block, "powered", true);
+        redstone.set_block(pos, block);
+
+        // Pulses shorter than the delay are extended
+        // to the full delay.
+        if !should_be_powered {
+            redstone.schedule(pos, block, repeater_delay(block), TickPriority::VeryHigh);
+        }
+    }
+}
+
+/// Computes the output of a comparator from its inputs.
+fn comparator_output(subtract: bool, rear: u8, side: u8) -> u8 {
+    if subtract {
+        rear.saturating_sub(side)
+    } else if side > rear {
+        0
+    } else {
+        rear
+    }
+}
+
+/// Computes the output of the comparator at `pos`.
+fn calculate_comparator(redstone: &Redstone, pos: BlockPosition, block: Block) -> u8 {
+    let subtract = block
+        .property("mode")
+        .map_or(false, |mode| mode == "subtract");
+    comparator_output(
+        subtract,
+        rear_input(redstone, pos, block),
+        side_input(redstone, pos, block, false),
+    )
+}
+
+pub fn update_comparator(redstone: &mut Redstone, pos: BlockPosition, block: Block) {
+    let current = redstone
+        .comparators
+        .get(&(redstone.state.dimension, pos))
+        .copied()
+        .unwrap_or(0);
+    if calculate_comparator(redstone, pos, block) != current {
+        let priority = diode_priority(
+            redstone,
+            pos,
+            block,
+            TickPriority::High,
+            TickPriority::Normal,
+        );
+        redstone.schedule(pos, block, COMPARATOR_DELAY, priority);
+    }
+}
+
+pub fn tick_comparator(redstone: &mut Redstone, pos: BlockPosition, block: Block) {
+    let output = calculate_comparator(redstone, pos, block);
+    let previous = redstone
+        .comparators
+        .insert((redstone.state.dimension, pos), output)
+        .unwrap_or(0);
+
+    let new_block = with_prop(block, "powered", output > 0);
+    if new_block != block {
+        redstone.set_block(pos, new_block);
+    } else if output != previous {
+        // The output level is not part of the block state,
+        // so neighbors need to be notified explicitly.
+        redstone.notify_neighbors(pos);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comparator_output() {
+        assert_eq!(comparator_output(false, 10, 5), 10);
+        assert_eq!(comparator_output(false, 10, 10), 10);
+        assert_eq!(comparator_output(false, 10, 11), 0);
+        assert_eq!(comparator_output(true, 10, 4), 6);
+        assert_eq!(comparator_output(true, 4, 10), 0);
+    }
+}
@@ -0,0 +1,170 @@
+//! Components which are switched by players
+//! and entities: levers, buttons, pressure plates
+//! and detector rails.
+
+use super::{Component, Redstone};
+use crate::block::tick::TickPriority;
+use crate::block::{block_name, prop_bool, with_prop};
+use crate::entity::minecart::Minecart;
+use crate::physics::nearby_entities;
+use feather_core::{Block, BlockPosition, ItemStack};
+use tonks::PreparedWorld;
+
+/// The number of ticks a stone button stays pressed.
+const STONE_BUTTON_DELAY: u64 = 20;
+/// The number of ticks a wooden button stays pressed.
+const WOODEN_BUTTON_DELAY: u64 = 30;
+/// The number of ticks after which a pressed
+/// pressure plate checks whether it is still pressed.
+const PRESSURE_PLATE_DELAY: u64 = 20;
+/// The same delay for weighted pressure plates.
+const WEIGHTED_PRESSURE_PLATE_DELAY: u64 = 10;
+/// The same delay for detector rails.
+const DETECTOR_RAIL_DELAY: u64 = 20;
+
+/// Toggles the lever at `pos`. Returns `false`
+/// if there is no lever at the position.
+///
+/// Call `Redstone::run` afterward to propagate the change.
+pub fn toggle_lever(redstone: &mut Redstone, pos: BlockPosition) -> bool {
+    let block = redstone.block_at(pos);
+    if Component::of(block) != Some(Component::Lever) {
+        return false;
+    }
+
+    let powered = prop_bool(block, "powered");
+    redstone.set_block(pos, with_prop(block, "powered", !powered));
+    true
+}
+
+/// Presses the button at `pos`, scheduling it to be
+/// released after a delay. Returns `false` if there is
+/// no button at the position or it is already pressed.
+///
+/// Call `Redstone::run` afterward to propagate the change.
+pub fn press_button(redstone: &mut Redstone, pos: BlockPosition) -> bool {
+    let block = redstone.block_at(pos);
+    if Component::of(block) != Some(Component::Button) || prop_bool(block, "powered") {
+        return false;
+    }
+
+    let delay = if block_name(block) == "stone_button" {
+        STONE_BUTTON_DELAY
+    } else {
+        WOODEN_BUTTON_DELAY
+    };
+
+    let block = with_prop(block, "powered", true);
+    redstone.set_block(pos, block);
+    redstone.schedule(pos, block, delay, TickPriority::Normal);
+    true
+}
+
+pub fn release_button(redstone: &mut Redstone, pos: BlockPosition, block: Block) {
+    redstone.set_block(pos, with_prop(block, "powered", false));
+}
+
+/// Computes the power of a pressure plate
+/// with `count` entities on it.
+fn plate_power(name: &str, count: usize) -> u8 {
+    let power = match name {
+        "light_weighted_pressure_plate" => count,
+        "heavy_weighted_pressure_plate" => (count + 9) / 10,
+        _ if count > 0 => 15,
+        _ => 0,
+    };
+    power.min(15) as u8
+}
+
+/// Updates the pressure plate at `pos` according to
+/// the entities currently on it. While the plate is pressed,
+/// it checks again periodically.
+pub fn update_pressure_plate(
+    redstone: &mut Redstone,
+    world: &PreparedWorld,
+    pos: BlockPosition,
+    block: Block,
+) {
+    let name = block_name(block);
+    let center = position!(
+        f64::from(pos.x) + 0.5,
+        f64::from(pos.y),
+        f64::from(pos.z) + 0.5
+    );
+
+    // Stone pressure plates are not triggered by items.
+    let count = nearby_entities(
+        redstone.state.chunk_entities,
+        world,
+        center,
+        glm::vec3(0.5, 0.25, 0.5),
+    )
+    .into_iter()
+    .filter(|entity| {
+        name != "stone_pressure_plate" || world.get_component::<ItemStack>(*entity).is_none()
+    })
+    .count();
+
+    let power = plate_power(name, count);
+    let (new_block, delay) = match Component::of(block) {
+        Some(Component::WeightedPressurePlate) => (
+            with_prop(block, "power", power),
+            WEIGHTED_PRESSURE_PLATE_DELAY,
+        ),
+        _ => (with_prop(block, "powered", power > 0), PRESSURE_PLATE_DELAY),
+    };
+
+    redstone.set_block(pos, new_block);
+
+    if power > 0 {
+        redstone.schedule(pos, new_block, delay, TickPriority::Normal);
+    }
+}
+
+/// Updates the detector rail at `pos` according to whether
+/// a minecart is on it. While the rail is powered, it checks
+/// again periodically.
+pub fn update_detector_rail(
+    redstone: &mut Redstone,
+    world: &PreparedWorld,
+    pos: BlockPosition,
+    block: Block,
+) {
+    let center = position!(
+        f64::from(pos.x) + 0.5,
+        f64::from(pos.y),
+        f64::from(pos.z) + 0.5
+    );
+
+    let powered = nearby_entities(
+        redstone.state.chunk_entities,
+        world,
+        center,
+        glm::vec3(0.5, 0.5, 0.5),
+    )
+    .into_iter()
+    .any(|entity| world.get_component::<Minecart>(entity).is_some());
+
+    let new_block = with_prop(block, "powered", powered);
+    redstone.set_block(pos, new_block);
+
+    if powered {
+        redstone.schedule(pos, new_block, DETECTOR_RAIL_DELAY, TickPriority::Normal);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plate_power() {
+        assert_eq!(plate_power("oak_pressure_plate", 0), 0);
+        assert_eq!(plate_power("stone_pressure_plate", 3), 15);
+        assert_eq!(plate_power("light_weighted_pressure_plate", 3), 3);
+        assert_eq!(plate_power("light_weighted_pressure_plate", 40), 15);
+        assert_eq!(plate_power("heavy_weighted_pressure_plate", 1), 1);
+        assert_eq!(plate_power("heavy_weighted_pressure_plate", 11), 2);
+        assert_eq!(plate_power("heavy_weighted_pressure_plate", 500), 15);
+    }
+}
@@ -0,0 +1,447 @@
+//! Redstone simulation.
+//!
+//! Redstone components keep all of their state (power level,
+//! whether they are lit or powered, and so on) in their block
+//! state. Whenever a block changes, the components around it
+//! are re-evaluated; components which change notify their own
+//! neighbors in turn, in the same order as vanilla. Components
+//! with a delay, such as torches and repeaters, schedule a
+//! block tick and change once it runs.
+//!
+//! An update is processed to completion within a single
+//! event handler invocation using a worklist, so that chains
+//! of wire settle within one tick.
+
+use crate::block::direction::Direction;
+use crate::block::tick::{schedule_block_tick, BlockTickEvent, BlockTicks, TickPriority};
+use crate::block::{BlockUpdateCause, BlockUpdateEvent};
+use crate::entity::minecart::Minecart;
+use crate::entity::EntityMoveEvent;
+use crate::state::{DimensionState, State};
+use crate::worlds::DimensionId;
+use crate::TickCount;
+use feather_core::{Block, BlockPosition, ItemStack, Position};
+use hashbrown::HashMap;
+use legion::query::Read;
+use std::collections::VecDeque;
+use tonks::{PreparedWorld, Query};
+
+mod diode;
+mod input;
+mod output;
+mod power;
+mod wire;
+
+pub use input::{press_button, toggle_lever};
+
+/// The maximum number of positions evaluated during
+/// a single redstone update. This guards against
+/// circuits which would otherwise never settle.
+const MAX_UPDATES: usize = 65536;
+
+/// The output levels of comparators, which
+/// are not stored in the comparator block state.
+#[derive(Debug, Default, Resource, Deref, DerefMut)]
+pub struct ComparatorOutputs(pub HashMap<(DimensionId, BlockPosition), u8>);
+
+/// The kind of a redstone component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Component {
+    Wire,
+    Torch,
+    WallTorch,
+    Repeater,
+    Comparator,
+    Lever,
+    Button,
+    PressurePlate,
+    WeightedPressurePlate,
+    DetectorRail,
+    RedstoneBlock,
+    Lamp,
+    Door,
+    Trapdoor,
+    FenceGate,
+    Piston,
+    Dispenser,
+    NoteBlock,
+    Tnt,
+    PoweredRail,
+}
+
+impl Component {
+    /// Returns the kind of component `block` is,
+    /// or `None` if it does not interact with redstone.
+    pub fn of(block: Block) -> Option<Self> {
+        let component = match block {
+            Block::RedstoneWire(_) => Component::Wire,
+            Block::RedstoneTorch(_) => Component::Torch,
+            Block::RedstoneWallTorch(_) => Component::WallTorch,
+            Block::Repeater(_) => Component::Repeater,
+            Block::Comparator(_) => Component::Comparator,
+            Block::Lever(_) => Component::Lever,
+            Block::RedstoneBlock => Component::RedstoneBlock,
+            Block::RedstoneLamp(_) => Component::Lamp,
+            Block::Piston(_) | Block::StickyPiston(_) => Component::Piston,
+            Block::Dispenser(_) | Block::Dropper(_) => Component::Dispenser,
+            Block::NoteBlock(_) => Component::NoteBlock,
+            Block::Tnt(_) => Component::Tnt,
+            Block::DetectorRail(_) => Component::DetectorRail,
+            Block::PoweredRail(_) | Block::ActivatorRail(_) => Component::PoweredRail,
+            Block::StoneButton(_)
+            | Block::OakButton(_)
+            | Block::SpruceButton(_)
+            | Block::BirchButton(_)
+            | Block::JungleButton(_)
+            | Block::AcaciaButton(_)
+            | Block::DarkOakButton(_) => Component::Button,
+            Block::LightWeightedPressurePlate(_) | Block::HeavyWeightedPressurePlate(_) => {
+                Component::WeightedPressurePlate
+            }
+            Block::StonePressurePlate(_)
+            | Block::OakPressurePlate(_)
+            | Block::SprucePressurePlate(_)
+            | Block::BirchPressurePlate(_)
+            | Block::JunglePressurePlate(_)
+            | Block::AcaciaPressurePlate(_)
+            | Block::DarkOakPressurePlate(_) => Component::PressurePlate,
+            Block::OakTrapdoor(_)
+            | Block::SpruceTrapdoor(_)
+            | Block::BirchTrapdoor(_)
+            | Block::JungleTrapdoor(_)
+            | Block::AcaciaTrapdoor(_)
+            | Block::DarkOakTrapdoor(_)
+            | Block::IronTrapdoor(_) => Component::Trapdoor,
+            Block::OakDoor(_)
+            | Block::SpruceDoor(_)
+            | Block::BirchDoor(_)
+            | Block::JungleDoor(_)
+            | Block::AcaciaDoor(_)
+            | Block::DarkOakDoor(_)
+            | Block::IronDoor(_) => Component::Door,
+            Block::OakFenceGate(_)
+            | Block::SpruceFenceGate(_)
+            | Block::BirchFenceGate(_)
+            | Block::JungleFenceGate(_)
+            | Block::AcaciaFenceGate(_)
+            | Block::DarkOakFenceGate(_) => Component::FenceGate,
+            _ => return None,
+        };
+        Some(component)
+    }
+
+    /// Returns whether this component emits power.
+    pub fn is_source(self) -> bool {
+        match self {
+            Component::Wire
+            | Component::Torch
+            | Component::WallTorch
+            | Component::Repeater
+            | Component::Comparator
+            | Component::Lever
+            | Component::Button
+            | Component::PressurePlate
+            | Component::WeightedPressurePlate
+            | Component::DetectorRail
+            | Component::RedstoneBlock => true,
+            _ => false,
+        }
+    }
+}
+
+/// State used while processing a redstone update
+/// within a single dimension.
+pub struct Redstone<'a> {
+    state: DimensionState<'a>,
+    ticks: &'a mut BlockTicks,
+    tick_count: &'a TickCount,
+    comparators: &'a mut ComparatorOutputs,
+    /// Positions waiting to be re-evaluated.
+    queue: VecDeque<BlockPosition>,
+}
+
+impl<'a> Redstone<'a> {
+    pub fn new(
+        state: DimensionState<'a>,
+        ticks: &'a mut BlockTicks,
+        tick_count: &'a TickCount,
+        comparators: &'a mut ComparatorOutputs,
+    ) -> Self {
+        Self {
+            state,
+            ticks,
+            tick_count,
+            comparators,
+            queue: VecDeque::new(),
+        }
+    }
+
+    /// Returns the block at `pos`, treating
+    /// unloaded blocks as air.
+    fn block_at(&self, pos: BlockPosition) -> Block {
+        self.state.block_at(pos).unwrap_or(Block::Air)
+    }
+
+    /// Sets the block at `pos` and notifies its
+    /// neighbors if it changed.
+    fn set_block(&mut self, pos: BlockPosition, block: Block) {
+        if self.state.block_at(pos) == Some(block) {
+            return;
+        }
+
+        if self
+            .state
+            .set_block_at(pos, block, BlockUpdateCause::Redstone)
+        {
+            self.notify_neighbors(pos);
+        }
+    }
+
+    /// Queues the neighbors of `pos` for re-evaluation.
+    ///
+    /// Since solid blocks conduct power, the neighbors
+    /// of solid neighbors are notified as well. Blocks diagonally
+    /// above and below are included so that wire climbing
+    /// up or down a block is updated.
+    pub fn notify_neighbors(&mut self, pos: BlockPosition) {
+        for dir in &Direction::UPDATE_ORDER {
+            let neighbor = dir.adjacent(pos);
+            self.queue.push_back(neighbor);
+
+            if power::is_conductor(self.block_at(neighbor)) {
+                for dir in &Direction::UPDATE_ORDER {
+                    let next = dir.adjacent(neighbor);
+                    if next != pos {
+                        self.queue.push_back(next);
+                    }
+                }
+            }
+        }
+
+        for dir in &Direction::HORIZONTAL {
+            let neighbor = dir.adjacent(pos);
+            self.queue.push_back(Direction::Up.adjacent(neighbor));
+            self.queue.push_back(Direction::Down.adjacent(neighbor));
+        }
+    }
+
+    /// Schedules a tick for the component at `pos`,
+    /// unless one is already pending.
+    fn schedule(&mut self, pos: BlockPosition, block: Block, delay: u64, priority: TickPriority) {
+        let dimension = self.state.dimension;
+        if self.ticks.queue(dimension).is_scheduled(pos) {
+            return;
+        }
+        schedule_block_tick(
+            self.ticks,
+            self.tick_count,
+            dimension,
+            pos,
+            block,
+            delay,
+            priority,
+        );
+    }
+
+    /// Re-evaluates the component at `pos`.
+    fn update(&mut self, pos: BlockPosition) {
+        let block = self.block_at(pos);
+        let component = match Component::of(block) {
+            Some(component) => component,
+            None => return,
+        };
+
+        match component {
+            Component::Wire => wire::update(self, pos, block),
+            Component::Torch | Component::WallTorch => diode::update_torch(self, pos, block),
+            Component::Repeater => diode::update_repeater(self, pos, block),
+            Component::Comparator => diode::update_comparator(self, pos, block),
+            Component::Lamp => output::update_lamp(self, pos, block),
+            Component::Door => output::update_door(self, pos, block),
+            Component::Trapdoor | Component::FenceGate => output::update_openable(self, pos, block),
+            Component::Piston => output::update_piston(self, pos, block),
+            Component::Dispenser => output::update_dispenser(self, pos, block),
+            Component::NoteBlock => output::update_note_block(self, pos, block),
+            Component::Tnt => output::update_tnt(self, pos),
+            Component::PoweredRail => output::update_rail(self, pos, block),
+            // Inputs only change when interacted with or ticked.
+            Component::Lever
+            | Component::Button
+            | Component::PressurePlate
+            | Component::WeightedPressurePlate
+            | Component::DetectorRail
+            | Component::RedstoneBlock => (),
+        }
+    }
+
+    /// Runs a scheduled tick for the component at `pos`.
+    fn tick(&mut self, pos: BlockPosition, block: Block) {
+        match Component::of(block) {
+            Some(Component::Torch) | Some(Component::WallTorch) => {
+                diode::tick_torch(self, pos, block)
+            }
+            Some(Component::Repeater) => diode::tick_repeater(self, pos, block),
+            Some(Component::Comparator) => diode::tick_comparator(self, pos, block),
+            Some(Component::Button) => input::release_button(self, pos, block),
+            Some(Component::Lamp) => output::tick_lamp(self, pos, block),
+            _ => (),
+        }
+    }
+
+    /// Processes queued positions until the circuit settles.
+    pub fn run(&mut self) {
+        let mut count = 0;
+        while let Some(pos) = self.queue.pop_front() {
+            count += 1;
+            if count > MAX_UPDATES {
+                warn!("Redstone update at {:?} did not settle; aborting", pos);
+                self.queue.clear();
+                break;
+            }
+
+            self.update(pos);
+        }
+    }
+}
+
+/// Event handler which propagates redstone
+/// updates caused by block changes.
+#[event_handler]
+fn redstone_on_block_update(
+    events: &[BlockUpdateEvent],
+    state: &State,
+    ticks: &mut BlockTicks,
+    tick_count: &TickCount,
+    comparators: &mut ComparatorOutputs,
+) {
+    for dimension in state.dimension_ids() {
+        let mut redstone =
+            Redstone::new(state.dimension(dimension), ticks, tick_count, comparators);
+
+        // Changes made by redstone have already been propagated.
+        for event in events
+            .iter()
+            .filter(|event| event.dimension == dimension)
+            .filter(|event| event.cause != BlockUpdateCause::Redstone)
+        {
+            if Component::of(event.old_block) == Some(Component::Comparator) {
+                redstone.comparators.remove(&(dimension, event.pos));
+            }
+
+            redstone.queue.push_back(event.pos);
+            redstone.notify_neighbors(event.pos);
+        }
+
+        redstone.run();
+    }
+}
+
+/// Event handler which runs scheduled ticks
+/// for redstone components.
+#[event_handler]
+fn redstone_on_block_tick(
+    events: &[BlockTickEvent],
+    state: &State,
+    ticks: &mut BlockTicks,
+    tick_count: &TickCount,
+    comparators: &mut ComparatorOutputs,
+    _query: &mut Query<(Read<Position>, Read<ItemStack>, Read<Minecart>)>,
+    world: &mut PreparedWorld,
+) {
+    for dimension in state.dimension_ids() {
+        let mut redstone =
+            Redstone::new(state.dimension(dimension), ticks, tick_count, comparators);
+
+        for event in events.iter().filter(|event| event.dimension == dimension) {
+            match Component::of(event.block) {
+                Some(Component::PressurePlate) | Some(Component::WeightedPressurePlate) => {
+                    input::update_pressure_plate(&mut redstone, world, event.pos, event.block)
+                }
+                Some(Component::DetectorRail) => {
+                    input::update_detector_rail(&mut redstone, world, event.pos, event.block)
+                }
+                _ => redstone.tick(event.pos, event.block),
+            }
+        }
+
+        redstone.run();
+    }
+}
+
+/// Event handler which activates pressure plates and
+/// detector rails when entities move onto them.
+#[event_handler]
+fn redstone_on_entity_move(
+    events: &[EntityMoveEvent],
+    state: &State,
+    ticks: &mut BlockTicks,
+    tick_count: &TickCount,
+    comparators: &mut ComparatorOutputs,
+    _query: &mut Query<(
+        Read<Position>,
+        Read<DimensionId>,
+        Read<ItemStack>,
+        Read<Minecart>,
+    )>,
+    world: &mut PreparedWorld,
+) {
+    for event in events {
+        let pos = match world.get_component::<Position>(event.entity) {
+            Some(pos) => pos.block_pos(),
+            None => continue,
+        };
+        let dimension = match world.get_component::<DimensionId>(event.entity) {
+            Some(dimension) => *dimension,
+            None => continue,
+        };
+
+        let mut redstone =
+            Redstone::new(state.dimension(dimension), ticks, tick_count, comparators);
+
+        let block = redstone.block_at(pos);
+        match Component::of(block) {
+            Some(Component::PressurePlate) | Some(Component::WeightedPressurePlate) => {
+                input::update_pressure_plate(&mut redstone, world, pos, block)
+            }
+            Some(Component::DetectorRail) => {
+                input::update_detector_rail(&mut redstone, world, pos, block)
+            }
+            _ => (),
+        }
+
+        redstone.run();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_component_of() {
+        assert_eq!(
+            Component::of(Block::from_name_and_default_props("minecraft:redstone_wire").unwrap()),
+            Some(Component::Wire)
+        );
+        assert_eq!(
+            Component::of(Block::from_name_and_default_props("minecraft:oak_trapdoor").unwrap()),
+            Some(Component::Trapdoor)
+        );
+        assert_eq!(
+            Component::of(Block::from_name_and_default_props("minecraft:iron_door").unwrap()),
+            Some(Component::Door)
+        );
+        assert_eq!(
+            Component::of(
+                Block::from_name_and_default_props("minecraft:heavy_weighted_pressure_plate")
+                    .unwrap()
+            ),
+            Some(Component::WeightedPressurePlate)
+        );
+        assert_eq!(
+            Component::of(Block::from_name_and_default_props("minecraft:activator_rail").unwrap()),
+            Some(Component::PoweredRail)
+        );
+        assert_eq!(Component::of(Block::Stone), None);
+    }
+}
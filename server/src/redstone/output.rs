@@ -0,0 +1,294 @@
+//! Blocks which react to redstone power: lamps, doors, trapdoors,
+//! fence gates, pistons, dispensers, note blocks, TNT
+//! and powered and activator rails.
+
+use super::power::{is_powered, received_power};
+use super::Redstone;
+use crate::block::direction::Direction;
+use crate::block::rail::RailShape;
+use crate::block::tick::TickPriority;
+use crate::block::{block_name, facing, prop_bool, with_prop, BlockUpdateCause};
+use crate::entity::tnt;
+use feather_core::world::is_in_world_bounds;
+use feather_core::{Block, BlockPosition};
+
+/// The number of ticks a redstone lamp stays
+/// lit after losing power.
+const LAMP_OFF_DELAY: u64 = 4;
+/// The maximum number of blocks a piston can push.
+const PISTON_PUSH_LIMIT: usize = 12;
+/// The number of rails along which a powered
+/// or activator rail passes on its power.
+const RAIL_POWER_DISTANCE: usize = 8;
+
+pub fn update_lamp(redstone: &mut Redstone, pos: BlockPosition, block: Block) {
+    let lit = prop_bool(block, "lit");
+    let powered = is_powered(redstone, pos);
+
+    if powered && !lit {
+        redstone.set_block(pos, with_prop(block, "lit", true));
+    } else if !powered && lit {
+        redstone.schedule(pos, block, LAMP_OFF_DELAY, TickPriority::Normal);
+    }
+}
+
+pub fn tick_lamp(redstone: &mut Redstone, pos: BlockPosition, block: Block) {
+    if prop_bool(block, "lit") && !is_powered(redstone, pos) {
+        redstone.set_block(pos, with_prop(block, "lit", false));
+    }
+}
+
+/// Returns `block` opened or closed according to `powered`.
+fn set_open(block: Block, powered: bool) -> Block {
+    with_prop(with_prop(block, "open", powered), "powered", powered)
+}
+
+/// Updates a door. Both halves of the door
+/// open if either of them is powered.
+pub fn update_door(redstone: &mut Redstone, pos: BlockPosition, block: Block) {
+    let other_pos = match block.property("half").as_ref().map(String::as_str) {
+        Some("upper") => Direction::Down.adjacent(pos),
+        _ => Direction::Up.adjacent(pos),
+    };
+    let other = redstone.block_at(other_pos);
+
+    let powered = is_powered(redstone, pos) || is_powered(redstone, other_pos);
+    if powered == prop_bool(block, "powered") {
+        return;
+    }
+
+    redstone.set_block(pos, set_open(block, powered));
+    if block_name(other) == block_name(block) {
+        redstone.set_block(other_pos, set_open(other, powered));
+    }
+}
+
+/// Updates a trapdoor or fence gate.
+pub fn update_openable(redstone: &mut Redstone, pos: BlockPosition, block: Block) {
+    let powered = is_powered(redstone, pos);
+    if powered != prop_bool(block, "powered") {
+        redstone.set_block(pos, set_open(block, powered));
+    }
+}
+
+pub fn update_note_block(redstone: &mut Redstone, pos: BlockPosition, block: Block) {
+    let powered = is_powered(redstone, pos);
+    if powered != prop_bool(block, "powered") {
+        redstone.set_block(pos, with_prop(block, "powered", powered));
+    }
+}
+
+/// Updates a dispenser or dropper. Like pistons, these
+/// are also powered by power to the block above them.
+///
+/// Since containers are not implemented, a dispenser
+/// only switches its `triggered` state.
+pub fn update_dispenser(redstone: &mut Redstone, pos: BlockPosition, block: Block) {
+    let powered = is_powered(redstone, pos) || is_powered(redstone, Direction::Up.adjacent(pos));
+    if powered != prop_bool(block, "triggered") {
+        redstone.set_block(pos, with_prop(block, "triggered", powered));
+    }
+}
+
+/// Primes TNT when it is powered.
+pub fn update_tnt(redstone: &mut Redstone, pos: BlockPosition) {
+    if is_powered(redstone, pos) {
+        tnt::prime(
+            &redstone.state,
+            pos,
+            tnt::DEFAULT_FUSE,
+            BlockUpdateCause::Redstone,
+        );
+        redstone.notify_neighbors(pos);
+    }
+}
+
+/// Updates a powered or activator rail. Rails are also
+/// powered by a directly powered rail of the same kind up to
+/// eight rails away along the track.
+pub fn update_rail(redstone: &mut Redstone, pos: BlockPosition, block: Block) {
+    let powered = is_powered(redstone, pos) || {
+        let (a, b) = RailShape::of(block)
+            .unwrap_or(RailShape::NorthSouth)
+            .exits();
+        rail_powered_along(redstone, pos, block, a) || rail_powered_along(redstone, pos, block, b)
+    };
+
+    if powered != prop_bool(block, "powered") {
+        redstone.set_block(pos, with_prop(block, "powered", powered));
+    }
+}
+
+/// Returns whether a directly powered rail of the same kind
+/// as `block` lies along the track from `pos` toward `dir`.
+fn rail_powered_along(
+    redstone: &Redstone,
+    pos: BlockPosition,
+    block: Block,
+    dir: Direction,
+) -> bool {
+    let mut pos = pos;
+    for _ in 0..RAIL_POWER_DISTANCE {
+        // The track may continue one block up or down a slope.
+        let next = [
+            dir.adjacent(pos),
+            Direction::Up.adjacent(dir.adjacent(pos)),
+            Direction::Down.adjacent(dir.adjacent(pos)),
+        ]
+        .iter()
+        .copied()
+        .find(|next| {
+            let rail = redstone.block_at(*next);
+            block_name(rail) == block_name(block)
+                && RailShape::of(rail).map_or(false, |shape| {
+                    let (a, b) = shape.exits();
+                    a.axis() == dir.axis() && b.axis() == dir.axis()
+                })
+        });
+
+        pos = match next {
+            Some(next) => next,
+            None => return false,
+        };
+        if is_powered(redstone, pos) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Returns whether pistons move into `block`
+/// by destroying it rather than pushing it.
+fn is_replaceable(block: Block) -> bool {
+    match block {
+        Block::Air | Block::CaveAir | Block::VoidAir | Block::Water(_) | Block::Lava(_) => true,
+        _ => false,
+    }
+}
+
+/// Returns whether `block` can be pushed by a piston.
+fn is_movable(block: Block) -> bool {
+    match block_name(block) {
+        "bedrock" | "obsidian" | "barrier" | "end_portal_frame" | "piston_head"
+        | "moving_piston" | "chest" | "trapped_chest" | "ender_chest" | "furnace" | "dispenser"
+        | "dropper" | "hopper" | "beacon" | "spawner" | "enchanting_table" => false,
+        "piston" | "sticky_piston" => !prop_bool(block, "extended"),
+        _ => true,
+    }
+}
+
+/// Returns whether the piston at `pos`, which faces
+/// `facing`, is powered. Power from the front is ignored.
+fn piston_powered(redstone: &Redstone, pos: BlockPosition, facing: Direction) -> bool {
+    let above = Direction::Up.adjacent(pos);
+    Direction::UPDATE_ORDER.iter().any(|dir| {
+        (*dir != facing && received_power(redstone, pos, *dir, true) > 0)
+            || (*dir != Direction::Down && received_power(redstone, above, *dir, true) > 0)
+    })
+}
+
+pub fn update_piston(redstone: &mut Redstone, pos: BlockPosition, block: Block) {
+    let facing = match facing(block) {
+        Some(facing) => facing,
+        None => return,
+    };
+
+    let extended = prop_bool(block, "extended");
+    let powered = piston_powered(redstone, pos, facing);
+
+    if powered && !extended {
+        if push(redstone, pos, facing) {
+            place_head(redstone, pos, block, facing);
+            redstone.set_block(pos, with_prop(block, "extended", true));
+        }
+    } else if !powered && extended {
+        retract(redstone, pos, block, facing);
+        redstone.set_block(pos, with_prop(block, "extended", false));
+    }
+}
+
+/// Pushes the blocks in front of the piston at `pos` one block
+/// forward. Returns `false` if the blocks cannot be moved.
+fn push(redstone: &mut Redstone, pos: BlockPosition, facing: Direction) -> bool {
+    let mut line = vec![];
+    let mut current = facing.adjacent(pos);
+
+    loop {
+        if !is_in_world_bounds(current) {
+            return false;
+        }
+
+        let block = redstone.block_at(current);
+        if is_replaceable(block) {
+            break;
+        }
+        if !is_movable(block) || line.len() == PISTON_PUSH_LIMIT {
+            return false;
+        }
+
+        line.push((current, block));
+        current = facing.adjacent(current);
+    }
+
+    // Move blocks starting from the far end
+    // so that none are overwritten.
+    for (from, block) in line.into_iter().rev() {
+        redstone.set_block(facing.adjacent(from), block);
+    }
+
+    true
+}
+
+fn place_head(redstone: &mut Redstone, pos: BlockPosition, piston: Block, facing: Direction) {
+    let ty = if block_name(piston) == "sticky_piston" {
+        "sticky"
+    } else {
+        "normal"
+    };
+
+    if let Some(head) = Block::from_name_and_default_props("minecraft:piston_head") {
+        let head = with_prop(with_prop(head, "facing", facing.name()), "type", ty);
+        redstone.set_block(facing.adjacent(pos), head);
+    }
+}
+
+/// Removes the head of the piston at `pos`. Sticky
+/// pistons pull back the block in front of the head.
+fn retract(redstone: &mut Redstone, pos: BlockPosition, piston: Block, facing: Direction) {
+    let head_pos = facing.adjacent(pos);
+    if block_name(redstone.block_at(head_pos)) != "piston_head" {
+        return;
+    }
+
+    let pulled_pos = facing.adjacent(head_pos);
+    let pulled = redstone.block_at(pulled_pos);
+
+    if block_name(piston) == "sticky_piston" && !is_replaceable(pulled) && is_movable(pulled) {
+        redstone.set_block(pulled_pos, Block::Air);
+        redstone.set_block(head_pos, pulled);
+    } else {
+        redstone.set_block(head_pos, Block::Air);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_blocks::{PistonData, PistonFacing};
+
+    #[test]
+    fn test_is_movable() {
+        assert!(is_movable(Block::Stone));
+        assert!(!is_movable(Block::Obsidian));
+        assert!(!is_movable(Block::Bedrock));
+
+        let piston = |extended| {
+            Block::Piston(PistonData {
+                extended,
+                facing: PistonFacing::Up,
+            })
+        };
+        assert!(is_movable(piston(false)));
+        assert!(!is_movable(piston(true)));
+    }
+}
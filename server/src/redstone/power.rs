@@ -0,0 +1,184 @@
+//! Queries for the power emitted and received by blocks.
+//!
+//! Throughout this module, `toward` is the direction
+//! from the block emitting power to the block receiving it.
+
+use super::{wire, Component, Redstone};
+use crate::block::direction::Direction;
+use crate::block::{block_name, facing, prop_bool, prop_int};
+use feather_core::{Block, BlockExt, BlockPosition};
+
+/// Returns whether `block` conducts power, i.e.
+/// whether it can be strongly powered by a component.
+pub fn is_conductor(block: Block) -> bool {
+    if !block.is_opaque() || Component::of(block).is_some() {
+        return false;
+    }
+
+    let name = block_name(block);
+    !(name.ends_with("_slab")
+        || name.ends_with("_stairs")
+        || name.ends_with("_leaves")
+        || name.ends_with("glass")
+        || name.ends_with("_pane")
+        || name == "ice"
+        || name == "glowstone"
+        || name == "sea_lantern")
+}
+
+/// Returns the direction from a lever, button or
+/// torch to the block it is attached to.
+pub fn attached_direction(block: Block) -> Direction {
+    match Component::of(block) {
+        Some(Component::Torch) => Direction::Down,
+        Some(Component::WallTorch) => facing(block).map_or(Direction::Down, Direction::opposite),
+        _ => match block.property("face").as_ref().map(String::as_str) {
+            Some("floor") => Direction::Down,
+            Some("ceiling") => Direction::Up,
+            _ => facing(block).map_or(Direction::Down, Direction::opposite),
+        },
+    }
+}
+
+/// Returns the direction in which a repeater
+/// or comparator outputs power.
+pub fn output_direction(block: Block) -> Direction {
+    facing(block).map_or(Direction::North, Direction::opposite)
+}
+
+/// Returns the weak power emitted by the
+/// component at `pos` toward `toward`.
+pub fn weak_power(
+    redstone: &Redstone,
+    pos: BlockPosition,
+    toward: Direction,
+    include_wire: bool,
+) -> u8 {
+    let block = redstone.block_at(pos);
+    match Component::of(block) {
+        Some(Component::RedstoneBlock) => 15,
+        Some(Component::Lever)
+        | Some(Component::Button)
+        | Some(Component::PressurePlate)
+        | Some(Component::DetectorRail) => {
+            if prop_bool(block, "powered") {
+                15
+            } else {
+                0
+            }
+        }
+        Some(Component::WeightedPressurePlate) => prop_int(block, "power") as u8,
+        Some(Component::Torch) | Some(Component::WallTorch) => {
+            if prop_bool(block, "lit") && toward != attached_direction(block) {
+                15
+            } else {
+                0
+            }
+        }
+        Some(Component::Repeater) => {
+            if prop_bool(block, "powered") && toward == output_direction(block) {
+                15
+            } else {
+                0
+            }
+        }
+        Some(Component::Comparator) => {
+            if toward == output_direction(block) {
+                redstone
+                    .comparators
+                    .get(&(redstone.state.dimension, pos))
+                    .copied()
+                    .unwrap_or(0)
+            } else {
+                0
+            }
+        }
+        Some(Component::Wire) if include_wire => wire::weak_power(block, toward),
+        _ => 0,
+    }
+}
+
+/// Returns the strong power emitted by the
+/// component at `pos` toward `toward`.
+pub fn strong_power(
+    redstone: &Redstone,
+    pos: BlockPosition,
+    toward: Direction,
+    include_wire: bool,
+) -> u8 {
+    let block = redstone.block_at(pos);
+    match Component::of(block) {
+        Some(Component::Lever) | Some(Component::Button) => {
+            if toward == attached_direction(block) {
+                weak_power(redstone, pos, toward, include_wire)
+            } else {
+                0
+            }
+        }
+        Some(Component::PressurePlate)
+        | Some(Component::WeightedPressurePlate)
+        | Some(Component::DetectorRail) => {
+            if toward == Direction::Down {
+                weak_power(redstone, pos, toward, include_wire)
+            } else {
+                0
+            }
+        }
+        Some(Component::Torch) | Some(Component::WallTorch) => {
+            if toward == Direction::Up {
+                weak_power(redstone, pos, toward, include_wire)
+            } else {
+                0
+            }
+        }
+        Some(Component::RedstoneBlock) => 0,
+        Some(_) => weak_power(redstone, pos, toward, include_wire),
+        None => 0,
+    }
+}
+
+/// Returns the power with which a conductor at
+/// `pos` is strongly powered by its neighbors.
+pub fn strongly_powered_level(redstone: &Redstone, pos: BlockPosition, include_wire: bool) -> u8 {
+    Direction::UPDATE_ORDER
+        .iter()
+        .map(|dir| strong_power(redstone, dir.adjacent(pos), dir.opposite(), include_wire))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Returns the power which the block at `pos`
+/// receives from its neighbor in direction `from`.
+///
+/// A conductor passes on the strong power it receives;
+/// any other block emits its own weak power.
+pub fn received_power(
+    redstone: &Redstone,
+    pos: BlockPosition,
+    from: Direction,
+    include_wire: bool,
+) -> u8 {
+    let source = from.adjacent(pos);
+    if is_conductor(redstone.block_at(source)) {
+        strongly_powered_level(redstone, source, include_wire)
+    } else {
+        weak_power(redstone, source, from.opposite(), include_wire)
+    }
+}
+
+/// Returns the highest power received by
+/// the block at `pos` from any direction.
+pub fn power_from_neighbors(redstone: &Redstone, pos: BlockPosition, include_wire: bool) -> u8 {
+    Direction::UPDATE_ORDER
+        .iter()
+        .map(|dir| received_power(redstone, pos, *dir, include_wire))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Returns whether the block at `pos` is powered.
+pub fn is_powered(redstone: &Redstone, pos: BlockPosition) -> bool {
+    Direction::UPDATE_ORDER
+        .iter()
+        .any(|dir| received_power(redstone, pos, *dir, true) > 0)
+}
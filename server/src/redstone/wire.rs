@@ -0,0 +1,189 @@
+//! Redstone wire.
+
+use super::power::{is_conductor, power_from_neighbors};
+use super::{Component, Redstone};
+use crate::block::direction::Direction;
+use crate::block::{facing, prop_int, with_prop};
+use feather_core::{Block, BlockExt, BlockPosition};
+use std::cmp::max;
+
+/// Returns the power level of `block` if it is wire.
+fn wire_power(block: Block) -> u8 {
+    match block {
+        Block::RedstoneWire(data) => data.power as u8,
+        _ => 0,
+    }
+}
+
+/// Returns whether wire connects horizontally to `block`,
+/// which lies in direction `dir` from the wire.
+fn connects_to(block: Block, dir: Direction) -> bool {
+    match Component::of(block) {
+        Some(Component::Repeater) => {
+            facing(block).map_or(false, |facing| facing == dir || facing == dir.opposite())
+        }
+        Some(component) => component.is_source(),
+        None => false,
+    }
+}
+
+/// Computes the value of the wire's connection
+/// property for direction `dir`.
+fn connection(redstone: &Redstone, pos: BlockPosition, dir: Direction) -> &'static str {
+    let neighbor_pos = dir.adjacent(pos);
+    let neighbor = redstone.block_at(neighbor_pos);
+
+    let above = redstone.block_at(Direction::Up.adjacent(pos));
+    if !is_conductor(above)
+        && neighbor.is_solid()
+        && Component::of(redstone.block_at(Direction::Up.adjacent(neighbor_pos)))
+            == Some(Component::Wire)
+    {
+        return if is_conductor(neighbor) { "up" } else { "side" };
+    }
+
+    let below_neighbor = redstone.block_at(Direction::Down.adjacent(neighbor_pos));
+    if connects_to(neighbor, dir)
+        || (!is_conductor(neighbor) && Component::of(below_neighbor) == Some(Component::Wire))
+    {
+        "side"
+    } else {
+        "none"
+    }
+}
+
+/// Computes the power level the wire at `pos` should have.
+fn target_power(redstone: &Redstone, pos: BlockPosition) -> u8 {
+    let from_sources = power_from_neighbors(redstone, pos, false);
+    if from_sources >= 15 {
+        return 15;
+    }
+
+    let above_is_conductor = is_conductor(redstone.block_at(Direction::Up.adjacent(pos)));
+    let mut from_wire = 0;
+    for dir in &Direction::HORIZONTAL {
+        let neighbor_pos = dir.adjacent(pos);
+        let neighbor = redstone.block_at(neighbor_pos);
+        from_wire = max(from_wire, wire_power(neighbor));
+
+        // Wire travels up and down the sides of blocks.
+        let diagonal = if is_conductor(neighbor) {
+            if above_is_conductor {
+                continue;
+            }
+            Direction::Up.adjacent(neighbor_pos)
+        } else {
+            Direction::Down.adjacent(neighbor_pos)
+        };
+        from_wire = max(from_wire, wire_power(redstone.block_at(diagonal)));
+    }
+
+    max(from_sources, from_wire.saturating_sub(1))
+}
+
+/// Recomputes the power level and connections
+/// of the wire at `pos`.
+pub fn update(redstone: &mut Redstone, pos: BlockPosition, block: Block) {
+    let mut new_block = with_prop(block, "power", target_power(redstone, pos));
+    for dir in &Direction::HORIZONTAL {
+        new_block = with_prop(new_block, dir.name(), connection(redstone, pos, *dir));
+    }
+
+    redstone.set_block(pos, new_block);
+}
+
+/// Returns the weak power emitted by wire toward `toward`.
+///
+/// Wire powers the block below it, and a horizontal
+/// neighbor only if the wire points toward it: either
+/// it has no connections at all, or it connects on the
+/// opposite side and not to either side.
+pub fn weak_power(block: Block, toward: Direction) -> u8 {
+    let power = prop_int(block, "power") as u8;
+    match toward {
+        Direction::Up => 0,
+        Direction::Down => power,
+        _ => {
+            let connected = |dir: Direction| {
+                block
+                    .property(dir.name())
+                    .map_or(false, |value| value != "none")
+            };
+
+            let back = toward.opposite();
+            let unconnected = Direction::HORIZONTAL.iter().all(|dir| !connected(*dir));
+            let points_toward =
+                connected(back) && !connected(back.rotate_cw()) && !connected(back.rotate_ccw());
+
+            if unconnected || points_toward {
+                power
+            } else {
+                0
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_blocks::{
+        RedstoneWireData, RedstoneWireEast, RedstoneWireNorth, RedstoneWireSouth, RedstoneWireWest,
+    };
+
+    fn wire(north: bool, east: bool, south: bool, west: bool) -> Block {
+        Block::RedstoneWire(RedstoneWireData {
+            north: if north {
+                RedstoneWireNorth::Side
+            } else {
+                RedstoneWireNorth::None
+            },
+            east: if east {
+                RedstoneWireEast::Side
+            } else {
+                RedstoneWireEast::None
+            },
+            south: if south {
+                RedstoneWireSouth::Side
+            } else {
+                RedstoneWireSouth::None
+            },
+            west: if west {
+                RedstoneWireWest::Side
+            } else {
+                RedstoneWireWest::None
+            },
+            power: 12,
+        })
+    }
+
+    #[test]
+    fn test_weak_power_dot() {
+        let dot = wire(false, false, false, false);
+        for dir in &Direction::HORIZONTAL {
+            assert_eq!(weak_power(dot, *dir), 12);
+        }
+        assert_eq!(weak_power(dot, Direction::Down), 12);
+        assert_eq!(weak_power(dot, Direction::Up), 0);
+    }
+
+    #[test]
+    fn test_weak_power_line() {
+        // A line running west to east powers blocks at either end only.
+        let line = wire(false, true, false, true);
+        assert_eq!(weak_power(line, Direction::East), 12);
+        assert_eq!(weak_power(line, Direction::West), 12);
+        assert_eq!(weak_power(line, Direction::North), 0);
+        assert_eq!(weak_power(line, Direction::South), 0);
+
+        // A wire coming from the west powers the block to its east.
+        let end = wire(false, false, false, true);
+        assert_eq!(weak_power(end, Direction::East), 12);
+        assert_eq!(weak_power(end, Direction::North), 0);
+
+        // A corner powers neither of its neighbors.
+        let corner = wire(true, false, false, true);
+        assert_eq!(weak_power(corner, Direction::East), 0);
+        assert_eq!(weak_power(corner, Direction::South), 0);
+    }
+}
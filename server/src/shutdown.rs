@@ -1,6 +1,44 @@
-//! Shutdown behavior.
+//! Shutdown behavior and saving of worlds and players.
+use crate::chunk_logic::{ChunkWorkers, SaveAllChunksEvent};
+use crate::chunk_worker::Request;
+use crate::difficulty::WorldDifficulty;
+use crate::game_rules::WorldGameRules;
+use crate::p_inventory::EntityInventory;
+use crate::player::sleep::SpawnPoint;
+use crate::player::Player;
+use crate::state::StateInner;
+use crate::time::WorldTimes;
+use crate::weather::WorldWeather;
+use crate::world_border::WorldBorders;
+use crate::worlds::{DimensionId, WorldId};
 use crossbeam::Sender;
+use feather_core::entity::BaseEntityData;
+use feather_core::inventory::{SLOT_ARMOR_MIN, SLOT_OFFHAND};
+use feather_core::level::{self, save_level_file};
+use feather_core::player_data::{save_player_data, InventorySlot, PlayerData};
+use feather_core::{Gamemode, Position};
+use legion::entity::Entity;
+use legion::query::{IntoQuery, Read};
 use legion::world::World;
+use std::fs::File;
+use std::path::Path;
+use tonks::{Resources, Scheduler};
+use uuid::Uuid;
+
+/// Resource through which the server can be shut down.
+#[derive(Resource)]
+pub struct ShutdownHandle(Sender<()>);
+
+impl ShutdownHandle {
+    pub fn new(tx: Sender<()>) -> Self {
+        Self(tx)
+    }
+
+    /// Requests that the server shut down before its next tick.
+    pub fn shut_down(&self) {
+        let _ = self.0.send(());
+    }
+}
 
 pub fn init(tx: Sender<()>) {
     ctrlc::set_handler(move || {
@@ -9,14 +47,113 @@ pub fn init(tx: Sender<()>) {
     .unwrap();
 }
 
-pub fn save_chunks(_world: &mut World) {
-    unimplemented!()
+/// Saves all loaded chunks, waiting until
+/// the chunk workers have written them.
+pub fn save_chunks(world: &mut World, scheduler: &mut Scheduler) {
+    // Entities can only be saved by systems, so
+    // the chunks are saved during one last tick.
+    scheduler.trigger(SaveAllChunksEvent);
+    scheduler.execute(world);
+
+    // Workers handle requests in order, so they stop once the chunks
+    // are written, which closes their end of the reply channels.
+    for handle in &scheduler.resources().get::<ChunkWorkers>().0 {
+        let _ = handle.sender.send(Request::ShutDown);
+        while handle.receiver.recv().is_ok() {}
+    }
+}
+
+/// Writes the level.dat of every world, updated
+/// with the world's time, weather and other state.
+pub fn save_levels(resources: &Resources) {
+    let state = resources.get::<StateInner>();
+    for id in state.world_ids() {
+        let world = state.world(id);
+        let mut data = world.level.clone();
+        resources
+            .get::<WorldDifficulty>()
+            .get(id)
+            .write_to_level(&mut data);
+        resources
+            .get::<WorldGameRules>()
+            .get(id)
+            .write_to_level(&mut data);
+        resources
+            .get::<WorldTimes>()
+            .get(id)
+            .write_to_level(&mut data);
+        resources
+            .get::<WorldWeather>()
+            .get(id)
+            .write_to_level(&mut data);
+        resources
+            .get::<WorldBorders>()
+            .get(id)
+            .write_to_level(&mut data);
+
+        let path = Path::new(&world.name).join("level.dat");
+        if let Err(e) = write_level(&level::Root { data }, &path) {
+            error!("Failed to save {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Writes the data of every online player
+/// to the player data directory of the main world.
+pub fn save_players(world: &World, resources: &Resources) {
+    let players: Vec<Entity> = <Read<Player>>::query()
+        .iter_entities_immutable(world)
+        .map(|(entity, _)| entity)
+        .collect();
+
+    for player in players {
+        save_player(world, resources, player);
+    }
+}
+
+/// Writes the data of `player` to the player data directory
+/// of the main world, along with the name of their world.
+pub fn save_player(world: &World, resources: &Resources, player: Entity) {
+    let uuid = *world.get_component::<Uuid>(player).unwrap();
+    let position = *world.get_component::<Position>(player).unwrap();
+    let gamemode = *world.get_component::<Gamemode>(player).unwrap();
+    let inventory = world.get_component::<EntityInventory>(player).unwrap();
+    let dimension = *world.get_component::<DimensionId>(player).unwrap();
+    let spawn = *world.get_component::<SpawnPoint>(player).unwrap();
+
+    let mut data = PlayerData {
+        entity: BaseEntityData::new(position, glm::vec3(0.0, 0.0, 0.0)),
+        gamemode: i32::from(gamemode.get_id()),
+        inventory: inventory_slots(&inventory),
+        dimension: dimension.kind.get_id(),
+        ..Default::default()
+    };
+    spawn.write_to_data(&mut data);
+
+    let state = resources.get::<StateInner>();
+    data.world = Some(state.world(dimension.world).name.clone());
+
+    let dir = Path::new(&state.world(WorldId::MAIN).name);
+    if let Err(e) = save_player_data(dir, uuid, data) {
+        error!("Failed to save player data for {}: {}", uuid, e);
+    }
 }
 
-pub fn save_level(_world: &World) {
-    unimplemented!()
+/// Converts a player's inventory to the slots stored
+/// in their data. The crafting grid isn't saved.
+fn inventory_slots(inventory: &EntityInventory) -> Vec<InventorySlot> {
+    inventory
+        .inventory
+        .items()
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| (SLOT_ARMOR_MIN..=SLOT_OFFHAND).contains(index))
+        .filter_map(|(index, stack)| Some(InventorySlot::from_network_index(index, (*stack)?)))
+        .collect()
 }
 
-pub fn save_player_data(_world: &World) {
-    unimplemented!()
+fn write_level(root: &level::Root, path: &Path) -> Result<(), failure::Error> {
+    let mut file = File::create(path)?;
+    save_level_file(root, &mut file)?;
+    Ok(())
 }
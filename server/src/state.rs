@@ -6,10 +6,12 @@ use crate::config::Config;
 use crate::entity::EntitySendEvent;
 use crate::lazy::{EntityBuilder, Lazy};
 use crate::network::Network;
+use crate::worlds::{DimensionId, WorldId};
 use feather_blocks::Block;
 use feather_core::level::LevelData;
 use feather_core::world::ChunkMap;
-use feather_core::{BlockPosition, Chunk, ChunkPosition, Packet, Position};
+use feather_core::{BlockPosition, Chunk, ChunkPosition, Dimension, Packet, Position};
+use hashbrown::HashMap;
 use legion::borrow::AtomicRefCell;
 use legion::entity::Entity;
 use legion::query::{IntoQuery, Read};
@@ -22,28 +24,109 @@ use tonks::{
     MacroData, ResourceId, Resources, Scheduler, SystemCtx, SystemData, SystemDataOutput, Trigger,
 };
 
+/// One of the server's worlds.
+pub struct WorldData {
+    /// The name of the world's directory.
+    pub name: String,
+    pub level: LevelData,
+
+    dimensions: HashMap<Dimension, DimensionData>,
+}
+
+impl WorldData {
+    pub fn new(name: String, level: LevelData) -> Self {
+        Self {
+            name,
+            level,
+            dimensions: Dimension::ALL
+                .iter()
+                .map(|dimension| (*dimension, DimensionData::default()))
+                .collect(),
+        }
+    }
+}
+
+/// The chunks and entities of a single dimension.
+pub struct DimensionData {
+    pub chunk_map: ChunkMap,
+    pub chunk_entities: ChunkEntities,
+}
+
+impl Default for DimensionData {
+    fn default() -> Self {
+        Self {
+            chunk_map: ChunkMap::new(),
+            chunk_entities: ChunkEntities::default(),
+        }
+    }
+}
+
 /// Resource used internally by `State`.
 #[derive(Resource)]
 pub struct StateInner {
     pub config: Arc<Config>,
-    pub chunk_map: ChunkMap,
-    pub level: LevelData,
-    pub chunk_entities: ChunkEntities,
 
+    worlds: Vec<WorldData>,
     lazy: Lazy,
 }
 
 impl StateInner {
-    pub fn new(config: Arc<Config>, chunk_map: ChunkMap, level: LevelData) -> Self {
+    /// Creates the state for the given worlds, the
+    /// first of which is the main world.
+    pub fn new(config: Arc<Config>, worlds: Vec<WorldData>) -> Self {
+        assert!(!worlds.is_empty(), "at least one world is required");
         Self {
             config,
-            chunk_map,
-            level,
-            chunk_entities: ChunkEntities::default(),
+            worlds,
             lazy: Lazy::default(),
         }
     }
 
+    /// Returns the world with the given ID.
+    pub fn world(&self, world: WorldId) -> &WorldData {
+        &self.worlds[world.0]
+    }
+
+    /// Returns the world with the given ID mutably, such
+    /// as to change its spawn point.
+    pub fn world_mut(&mut self, world: WorldId) -> &mut WorldData {
+        &mut self.worlds[world.0]
+    }
+
+    /// Returns the IDs of all worlds.
+    pub fn world_ids(&self) -> impl Iterator<Item = WorldId> {
+        (0..self.worlds.len()).map(WorldId)
+    }
+
+    /// Returns the ID of the world with the given name, if any.
+    pub fn world_by_name(&self, name: &str) -> Option<WorldId> {
+        self.worlds
+            .iter()
+            .position(|world| world.name == name)
+            .map(WorldId)
+    }
+
+    /// Returns the IDs of all dimensions of all worlds.
+    pub fn dimension_ids(&self) -> impl Iterator<Item = DimensionId> {
+        self.world_ids().flat_map(|world| {
+            Dimension::ALL
+                .iter()
+                .map(move |kind| DimensionId::new(world, *kind))
+        })
+    }
+
+    /// Returns the chunks and entities of the given dimension.
+    pub fn dimension_data(&self, dimension: DimensionId) -> &DimensionData {
+        &self.world(dimension.world).dimensions[&dimension.kind]
+    }
+
+    fn dimension_data_mut(&mut self, dimension: DimensionId) -> &mut DimensionData {
+        self.worlds[dimension.world.0]
+            .dimensions
+            .get_mut(&dimension.kind)
+            .unwrap()
+    }
+
     /// See `Lazy::flush()`.
     pub fn flush(&self, world: &mut World, scheduler: &mut Scheduler) {
         self.lazy.flush(world, scheduler);
@@ -165,8 +248,11 @@ impl State {
             // chunk, which would allow them to see the entity.
             let chunk_holders = scheduler.resources().get::<ChunkHolders>();
 
-            if let Some(position) = world.get_component::<Position>(entity) {
-                let holders = chunk_holders.holders_for(position.chunk_pos());
+            let dimension = world.get_component::<DimensionId>(entity).map(|d| *d);
+            if let (Some(position), Some(dimension)) =
+                (world.get_component::<Position>(entity), dimension)
+            {
+                let holders = chunk_holders.holders_for(dimension, position.chunk_pos());
 
                 holders.map(|entities| {
                     for entity in entities {
@@ -195,8 +281,11 @@ impl State {
             // chunk, which would allow them to see the entity.
             let chunk_holders = scheduler.resources().get::<ChunkHolders>();
 
-            if let Some(position) = world.get_component::<Position>(entity) {
-                let holders = chunk_holders.holders_for(position.chunk_pos());
+            let dimension = world.get_component::<DimensionId>(entity).map(|d| *d);
+            if let (Some(position), Some(dimension)) =
+                (world.get_component::<Position>(entity), dimension)
+            {
+                let holders = chunk_holders.holders_for(dimension, position.chunk_pos());
 
                 holders.map(|entities| {
                     for entity in entities {
@@ -211,34 +300,6 @@ impl State {
         });
     }
 
-    /// Lazily broadcasts a packet to all players able to see the given chunk.
-    ///
-    /// The packet will not be sent to `neq`.
-    pub fn broadcast_chunk_update(
-        &self,
-        chunk: ChunkPosition,
-        packet: impl Packet + Clone,
-        neq: Option<Entity>,
-    ) {
-        self.exec_with_scheduler(move |world, scheduler| {
-            // Use ChunkHolders to determine which players have a hold on the
-            // chunk, which would allow them to see the entity.
-            let chunk_holders = scheduler.resources().get::<ChunkHolders>();
-
-            let holders = chunk_holders.holders_for(chunk);
-
-            holders.map(|entities| {
-                for entity in entities {
-                    if let Some(network) = world.get_component::<Network>(*entity) {
-                        if neq.map_or(true, |neq| *entity != neq) {
-                            network.send(packet.clone());
-                        }
-                    }
-                }
-            });
-        });
-    }
-
     /// Lazily broadcasts a packet to all clients.
     pub fn broadcast_global<P: Packet + Clone>(&self, packet: P, neq: Option<Entity>) {
         self.exec(move |world| {
@@ -253,6 +314,24 @@ impl State {
         });
     }
 
+    /// Lazily broadcasts a packet to all clients in the given world.
+    pub fn broadcast_world<P: Packet + Clone>(
+        &self,
+        world_id: WorldId,
+        packet: P,
+        neq: Option<Entity>,
+    ) {
+        self.exec(move |world| {
+            let query = <(Read<Network>, Read<DimensionId>)>::query();
+
+            query.par_entities_for_each(world, |(entity, (network, dimension))| {
+                if dimension.world == world_id && neq.map_or(true, |neq| entity != neq) {
+                    network.send(packet.clone());
+                }
+            });
+        });
+    }
+
     /// Lazily broadcasts a boxed packet to all clients.
     pub fn broadcast_global_boxed(&self, packet: Box<dyn Packet>, neq: Option<Entity>) {
         self.exec(move |world| {
@@ -267,6 +346,106 @@ impl State {
         });
     }
 
+    /// Returns a view of the state scoped to the given dimension,
+    /// through which its blocks, chunks and entities are accessed.
+    pub fn dimension(&self, dimension: DimensionId) -> DimensionState<'_> {
+        let data = self.dimension_data(dimension);
+        DimensionState {
+            state: self,
+            dimension,
+            chunk_map: &data.chunk_map,
+            chunk_entities: &data.chunk_entities,
+        }
+    }
+
+    /// Returns views of the state scoped to each dimension of each world.
+    pub fn dimensions(&self) -> impl Iterator<Item = DimensionState<'_>> + '_ {
+        self.dimension_ids()
+            .map(move |dimension| self.dimension(dimension))
+    }
+
+    /// Registers that an entity was sent to a player, updating some
+    /// data structures, such as LastKnownPositions.
+    pub fn register_entity_send(&self, entity: Entity, to: Entity) {
+        self.exec_with_scheduler(move |world, scheduler| {
+            let pos = *world.get_component(entity).unwrap();
+            if let Some(mut positions) = world.get_component_mut::<LastKnownPositions>(to) {
+                positions.0.insert(entity, pos);
+            }
+
+            scheduler.trigger(EntitySendEvent { entity, to });
+        });
+    }
+
+    /// The opposite of `register_entity_send`.
+    pub fn register_entity_unload(&self, entity: Entity, on: Entity) {
+        self.exec(move |world| {
+            if let Some(mut positions) = world.get_component_mut::<LastKnownPositions>(on) {
+                positions.0.remove(&entity);
+            }
+        })
+    }
+}
+
+impl Deref for State {
+    type Target = StateInner;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.inner }
+    }
+}
+
+impl DerefMut for State {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.inner }
+    }
+}
+
+/// A view of the `State` scoped to one dimension.
+///
+/// Blocks, chunks and chunk entities are accessed through
+/// this view, since each dimension has its own chunk map.
+/// Other functionality is available through `Deref`
+/// to the `State`.
+#[derive(Clone, Copy)]
+pub struct DimensionState<'a> {
+    state: &'a State,
+    /// The dimension this view is scoped to.
+    pub dimension: DimensionId,
+    pub chunk_map: &'a ChunkMap,
+    pub chunk_entities: &'a ChunkEntities,
+}
+
+impl<'a> DimensionState<'a> {
+    /// Lazily broadcasts a packet to all players able to see the given chunk.
+    ///
+    /// The packet will not be sent to `neq`.
+    pub fn broadcast_chunk_update(
+        &self,
+        chunk: ChunkPosition,
+        packet: impl Packet + Clone,
+        neq: Option<Entity>,
+    ) {
+        let dimension = self.dimension;
+        self.exec_with_scheduler(move |world, scheduler| {
+            // Use ChunkHolders to determine which players have a hold on the
+            // chunk, which would allow them to see the entity.
+            let chunk_holders = scheduler.resources().get::<ChunkHolders>();
+
+            let holders = chunk_holders.holders_for(dimension, chunk);
+
+            holders.map(|entities| {
+                for entity in entities {
+                    if let Some(network) = world.get_component::<Network>(*entity) {
+                        if neq.map_or(true, |neq| *entity != neq) {
+                            network.send(packet.clone());
+                        }
+                    }
+                }
+            });
+        });
+    }
+
     /// Retrieves the block at the given position,
     /// or `None` if the block's chunk is not loaded.
     pub fn block_at(&self, pos: BlockPosition) -> Option<Block> {
@@ -285,11 +464,12 @@ impl State {
 
         let event = BlockUpdateEvent {
             cause,
+            dimension: self.dimension,
             pos,
             old_block,
             new_block: block,
         };
-        self.trigger.get_mut().trigger(event);
+        self.state.trigger.get_mut().trigger(event);
 
         self.chunk_map.set_block_at(pos, block)
     }
@@ -302,10 +482,12 @@ impl State {
 
     /// Lazily inserts the given chunk into the chunk map.
     pub fn lazy_insert_chunk(&self, chunk: Chunk) {
+        let dimension = self.dimension;
         self.lazy.exec_with_scheduler(move |_, scheduler| unsafe {
             scheduler
                 .resources()
                 .get_mut_unchecked::<StateInner>(tonks::resource_id_for::<StateInner>())
+                .dimension_data_mut(dimension)
                 .chunk_map
                 .insert(chunk);
         });
@@ -313,49 +495,23 @@ impl State {
 
     /// Lazily removes the given chunk from the chunk map.
     pub fn lazy_remove_chunk(&self, pos: ChunkPosition) {
+        let dimension = self.dimension;
         self.lazy
             .exec_with_scheduler(move |_: &mut World, scheduler: &mut Scheduler| unsafe {
                 scheduler
                     .resources()
                     .get_mut_unchecked::<StateInner>(tonks::resource_id_for::<StateInner>())
+                    .dimension_data_mut(dimension)
                     .chunk_map
                     .remove(pos);
             });
     }
-
-    /// Registers that an entity was sent to a player, updating some
-    /// data structures, such as LastKnownPositions.
-    pub fn register_entity_send(&self, entity: Entity, to: Entity) {
-        self.exec_with_scheduler(move |world, scheduler| {
-            let pos = *world.get_component(entity).unwrap();
-            if let Some(mut positions) = world.get_component_mut::<LastKnownPositions>(to) {
-                positions.0.insert(entity, pos);
-            }
-
-            scheduler.trigger(EntitySendEvent { entity, to });
-        });
-    }
-
-    /// The opposite of `register_entity_send`.
-    pub fn register_entity_unload(&self, entity: Entity, on: Entity) {
-        self.exec(move |world| {
-            if let Some(mut positions) = world.get_component_mut::<LastKnownPositions>(on) {
-                positions.0.remove(&entity);
-            }
-        })
-    }
 }
 
-impl Deref for State {
-    type Target = StateInner;
+impl<'a> Deref for DimensionState<'a> {
+    type Target = State;
 
     fn deref(&self) -> &Self::Target {
-        unsafe { &*self.inner }
-    }
-}
-
-impl DerefMut for State {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        unsafe { &mut *self.inner }
+        self.state
     }
 }
@@ -1,49 +1,137 @@
-//! Handles world time.
+//! Handles world time. Each world has its own time.
 
+use crate::game_rules::{GameRule, WorldGameRules};
 use crate::network::Network;
 use crate::player::PlayerJoinEvent;
+use crate::state::State;
+use crate::worlds::{DimensionId, PerWorld};
+use crate::TickCount;
+use feather_core::level::LevelData;
 use feather_core::packet::TimeUpdate;
 use legion::query::Read;
 use tonks::{PreparedWorld, Query};
 
-/// The current time of the world.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deref, DerefMut, Default, Resource)]
-pub struct Time(pub u64);
+/// The number of ticks between time updates sent to players.
+const TIME_UPDATE_INTERVAL: u64 = 20;
+
+/// The current time of a world.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Time {
+    /// The number of ticks since the world was created.
+    pub world_age: u64,
+    /// The time of the day/night cycle. Unlike `world_age`,
+    /// this does not advance while `doDaylightCycle` is disabled.
+    pub day_time: u64,
+}
 
 impl Time {
+    /// Creates the time from the values stored in `level.dat`.
+    pub fn from_level(level: &LevelData) -> Self {
+        Self {
+            world_age: level.time.max(0) as u64,
+            day_time: level.day_time.max(0) as u64,
+        }
+    }
+
+    /// Writes the time into `level` so it can be saved to `level.dat`.
+    pub fn write_to_level(self, level: &mut LevelData) {
+        level.time = self.world_age as i64;
+        level.day_time = self.day_time as i64;
+    }
+
     /// Returns the time of day. This is calculated
-    /// as `time.0 % 24_000`.
+    /// as `day_time % 24_000`.
     pub fn time_of_day(self) -> u64 {
-        self.0 % 24_000
+        self.day_time % 24_000
     }
 
-    /// Returns the age of the world in ticks. Equivalent to `time.0`.
+    /// Returns the age of the world in ticks.
     pub fn world_age(self) -> u64 {
-        self.0
+        self.world_age
+    }
+
+    /// Returns the packet which sends this time to a player.
+    ///
+    /// A negative time of day tells the client
+    /// that the day/night cycle is frozen.
+    pub fn update_packet(self, daylight_cycle: bool) -> TimeUpdate {
+        let day_time = self.day_time as i64;
+        TimeUpdate {
+            world_age: self.world_age as i64,
+            time_of_day: if daylight_cycle {
+                day_time
+            } else {
+                -day_time.max(1)
+            },
+        }
     }
 }
 
-/// System for incrementing time each tick.
+/// The time of each world.
+#[derive(Debug, Clone, Resource, Deref, DerefMut)]
+pub struct WorldTimes(pub PerWorld<Time>);
+
+/// System for incrementing time each tick
+/// and periodically sending it to players.
 #[system]
-pub fn time_increment(time: &mut Time) {
-    time.0 += 1;
+pub fn time_increment(
+    state: &State,
+    times: &mut WorldTimes,
+    rules: &WorldGameRules,
+    tick_count: &TickCount,
+) {
+    for (world, time) in times.iter_mut() {
+        let daylight_cycle = rules.get(world).get_bool(GameRule::DoDaylightCycle);
+
+        time.world_age += 1;
+        if daylight_cycle {
+            time.day_time += 1;
+        }
+
+        if tick_count.0 % TIME_UPDATE_INTERVAL == 0 {
+            state.broadcast_world(world, time.update_packet(daylight_cycle), None);
+        }
+    }
 }
 
 /// Event handler for sending world time to players.
 #[event_handler]
 pub fn time_send(
     event: &PlayerJoinEvent,
-    time: &Time,
-    _query: &mut Query<Read<Network>>,
+    times: &WorldTimes,
+    rules: &WorldGameRules,
+    _query: &mut Query<(Read<Network>, Read<DimensionId>)>,
     world: &mut PreparedWorld,
 ) {
     let network = world.get_component::<Network>(event.player).unwrap();
+    let dimension = world.get_component::<DimensionId>(event.player).unwrap();
 
     // Send time to player.
-    let packet = TimeUpdate {
-        world_age: time.world_age() as i64,
-        time_of_day: time.time_of_day() as i64,
-    };
+    let daylight_cycle = rules
+        .get(dimension.world)
+        .get_bool(GameRule::DoDaylightCycle);
+    network.send(times.get(dimension.world).update_packet(daylight_cycle));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    network.send(packet);
+    #[test]
+    fn test_update_packet() {
+        let mut time = Time {
+            world_age: 50_000,
+            day_time: 30_000,
+        };
+        assert_eq!(time.time_of_day(), 6000);
+
+        let packet = time.update_packet(true);
+        assert_eq!(packet.world_age, 50_000);
+        assert_eq!(packet.time_of_day, 30_000);
+
+        assert_eq!(time.update_packet(false).time_of_day, -30_000);
+
+        time.day_time = 0;
+        assert_eq!(time.update_packet(false).time_of_day, -1);
+    }
 }
@@ -1,13 +1,20 @@
 //! Assorted utility functions.
 
-use crate::entity::{EntityDeleteEvent, EntityId, Name};
+use crate::entity::{EntityDeleteEvent, EntityId, EntityMoveEvent, Name};
 use crate::io::ServerToWorkerMessage;
+use crate::locale::{Translations, DEFAULT_LOCALE};
 use crate::network::Network;
+use crate::shutdown;
 use crate::state::State;
-use feather_core::Position;
+use crate::worlds::DimensionId;
+use feather_core::network::packet::implementation::{
+    DisconnectPlay, PlayerPositionAndLookClientbound,
+};
+use feather_core::{Position, TextComponent};
 use glm::DVec3;
 use legion::entity::Entity;
-use std::borrow::Cow;
+use legion::world::World;
+use tonks::Scheduler;
 use uuid::Uuid;
 
 /// Calculates the relative move fields
@@ -35,41 +42,80 @@ pub fn protocol_velocity(vel: DVec3) -> (i16, i16, i16) {
     )
 }
 
-/// Disconnects a player.
-pub fn disconnect_player(state: &State, player: Entity, reason: impl Into<Cow<'static, str>>) {
-    let reason = reason.into();
-
+/// Disconnects a player, showing them `reason` in their
+/// language. This is applied lazily, at the end of the tick.
+pub fn disconnect_player(state: &State, player: Entity, reason: TextComponent) {
     state.exec_with_scheduler(move |world, scheduler| {
-        {
-            let username = world.get_component::<Name>(player).unwrap();
-            info!("Disconnecting player {}: {}", username.0, reason);
+        disconnect_player_now(world, scheduler, player, &reason);
+    });
+}
 
-            let network = world.get_component::<Network>(player).unwrap();
-            network
-                .sender
-                .unbounded_send(ServerToWorkerMessage::Disconnect)
-                .unwrap();
+/// Disconnects a player immediately. Unlike `disconnect_player`,
+/// this requires access to the world, such as in a lazy closure.
+pub fn disconnect_player_now(
+    world: &mut World,
+    scheduler: &mut Scheduler,
+    player: Entity,
+    reason: &TextComponent,
+) {
+    if !world.is_alive(player) {
+        return;
+    }
 
-            let position = *world.get_component::<Position>(player).unwrap();
-            let id = *world.get_component::<EntityId>(player).unwrap();
-            let uuid = *world.get_component::<Uuid>(player).unwrap();
+    let translations = scheduler.resources().get::<Translations>();
+    let logged = translations.render(DEFAULT_LOCALE, reason).to_plain();
+    let reason = translations.render_for(world, player, reason);
 
-            scheduler.trigger(EntityDeleteEvent {
-                entity: player,
-                position: Some(position),
-                id,
-                uuid,
-            });
+    {
+        let username = world.get_component::<Name>(player).unwrap();
+        info!("Disconnecting player {}: {}", username.0, logged);
 
-            let event = EntityDeleteEvent {
-                entity: player,
-                position: Some(position),
-                id,
-                uuid,
-            };
-            scheduler.trigger(event);
-        }
+        let network = world.get_component::<Network>(player).unwrap();
+        network.send(DisconnectPlay { reason });
+        // The worker may already have shut down if
+        // the player disconnected by themselves.
+        let _ = network
+            .sender
+            .unbounded_send(ServerToWorkerMessage::Disconnect);
 
-        world.delete(player);
-    });
+        shutdown::save_player(world, scheduler.resources(), player);
+
+        let position = *world.get_component::<Position>(player).unwrap();
+        let dimension = *world.get_component::<DimensionId>(player).unwrap();
+        let id = *world.get_component::<EntityId>(player).unwrap();
+        let uuid = *world.get_component::<Uuid>(player).unwrap();
+
+        scheduler.trigger(EntityDeleteEvent {
+            entity: player,
+            position: Some(position),
+            dimension: Some(dimension),
+            id,
+            uuid,
+        });
+    }
+
+    world.delete(player);
+}
+
+/// Moves an entity to another position in its dimension
+/// immediately, such as in a lazy closure. Players are
+/// sent their new position.
+pub fn teleport_now(world: &mut World, scheduler: &mut Scheduler, entity: Entity, pos: Position) {
+    match world.get_component_mut::<Position>(entity) {
+        Some(mut position) => *position = pos,
+        None => return,
+    }
+
+    if let Some(network) = world.get_component::<Network>(entity) {
+        network.send(PlayerPositionAndLookClientbound {
+            x: pos.x,
+            y: pos.y,
+            z: pos.z,
+            yaw: pos.yaw,
+            pitch: pos.pitch,
+            flags: 0,
+            teleport_id: 0,
+        });
+    }
+    scheduler.trigger(EntityMoveEvent { entity });
 }
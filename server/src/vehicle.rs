@@ -0,0 +1,442 @@
+//! Vehicles: boats and minecarts, and the entities riding them.
+//!
+//! A vehicle lists its passengers in the `Vehicle` component,
+//! and each passenger stores its vehicle in `Riding`. Passengers
+//! are moved along with their vehicle every tick. Players mount
+//! a vehicle by right-clicking it and dismount by sneaking.
+//!
+//! Minecarts are moved by the server. Boats are steered by their
+//! driver's client, which reports the new position of the boat;
+//! see `packet_handlers::vehicle` for how these reports are checked.
+
+use crate::chunk_logic::ChunkHolders;
+use crate::entity::{item, minecart, EntityId, EntityMoveEvent, EntitySendEvent};
+use crate::metadata::Metadata;
+use crate::network::Network;
+use crate::state::{DimensionState, State};
+use crate::worlds::DimensionId;
+use crossbeam::queue::SegQueue;
+use feather_core::network::packet::implementation::{
+    PacketEntityMetadata, PlayerPositionAndLookClientbound, SetPassengers,
+};
+use feather_core::{BlockPosition, Gamemode, Item, ItemStack, Position};
+use legion::entity::Entity;
+use legion::query::{Read, Write};
+use legion::world::World;
+use smallvec::SmallVec;
+use tonks::{PreparedWorld, Query, Trigger};
+
+/// The damage a vehicle takes from a single hit.
+const HIT_DAMAGE: f32 = 10.0;
+/// The damage at which a vehicle breaks.
+const BREAK_DAMAGE: f32 = 40.0;
+
+/// The kind of a vehicle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VehicleKind {
+    Boat,
+    Minecart,
+}
+
+impl VehicleKind {
+    /// Returns the number of entities which can ride the vehicle.
+    pub fn max_passengers(self) -> usize {
+        match self {
+            VehicleKind::Boat => 2,
+            VehicleKind::Minecart => 1,
+        }
+    }
+
+    /// Returns whether the vehicle is moved by its
+    /// driver's client rather than by the server.
+    pub fn is_client_steered(self) -> bool {
+        self == VehicleKind::Boat
+    }
+
+    /// Returns the height of the vehicle's bounding box.
+    pub fn height(self) -> f64 {
+        match self {
+            VehicleKind::Boat => 0.5625,
+            VehicleKind::Minecart => 0.7,
+        }
+    }
+
+    /// Returns the offset of a passenger's feet
+    /// from the position of the vehicle.
+    fn seat_height(self) -> f64 {
+        match self {
+            VehicleKind::Boat => -0.45,
+            VehicleKind::Minecart => -0.35,
+        }
+    }
+}
+
+/// Component for entities which other entities can ride.
+#[derive(Debug, Clone)]
+pub struct Vehicle {
+    pub kind: VehicleKind,
+    /// The entities riding the vehicle. The
+    /// first passenger steers the vehicle.
+    pub passengers: SmallVec<[Entity; 2]>,
+    /// The item dropped when the vehicle is broken.
+    pub item: Item,
+    /// The damage the vehicle has taken, which decays over time.
+    pub damage: f32,
+}
+
+impl Vehicle {
+    pub fn new(kind: VehicleKind, item: Item) -> Self {
+        Self {
+            kind,
+            passengers: SmallVec::new(),
+            item,
+            damage: 0.0,
+        }
+    }
+
+    /// Returns the passenger steering the vehicle, if any.
+    pub fn driver(&self) -> Option<Entity> {
+        self.passengers.first().copied()
+    }
+
+    /// Returns whether no more passengers fit in the vehicle.
+    pub fn is_full(&self) -> bool {
+        self.passengers.len() >= self.kind.max_passengers()
+    }
+}
+
+/// Component for entities which can ride vehicles,
+/// containing the vehicle the entity is riding, if any.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Riding(pub Option<Entity>);
+
+/// Places a vehicle when a player uses `item` on the block
+/// at `pos`. Returns whether a vehicle was placed.
+///
+/// Minecarts are placed on rails. Boats are placed
+/// through `boat::place` instead, since they can be
+/// placed on water, which players cannot click.
+pub fn place(state: &DimensionState, item: Item, pos: BlockPosition) -> bool {
+    match item {
+        Item::Minecart => minecart::place(state, pos),
+        _ => false,
+    }
+}
+
+/// Makes `passenger` ride `vehicle`, unless the vehicle is full
+/// or the passenger is already riding a vehicle. This is
+/// applied lazily, at the end of the tick.
+pub fn mount(state: &State, passenger: Entity, vehicle: Entity) {
+    state.exec_with_scheduler(move |world, scheduler| {
+        if passenger == vehicle || !world.is_alive(vehicle) {
+            return;
+        }
+        match world.get_component::<Riding>(passenger) {
+            Some(riding) if riding.0.is_none() => (),
+            _ => return,
+        }
+
+        match world.get_component_mut::<Vehicle>(vehicle) {
+            Some(mut component) if !component.is_full() => component.passengers.push(passenger),
+            _ => return,
+        }
+        world.get_component_mut::<Riding>(passenger).unwrap().0 = Some(vehicle);
+
+        let holders = scheduler.resources().get::<ChunkHolders>();
+        send_passengers(world, &holders, vehicle);
+    });
+}
+
+/// Makes `passenger` leave its vehicle, placing it on
+/// top of the vehicle. This is applied lazily, at the
+/// end of the tick.
+pub fn dismount(state: &State, passenger: Entity) {
+    state.exec_with_scheduler(move |world, scheduler| {
+        let vehicle = match remove_passenger(world, passenger) {
+            Some(vehicle) => vehicle,
+            None => return,
+        };
+        {
+            let holders = scheduler.resources().get::<ChunkHolders>();
+            send_passengers(world, &holders, vehicle);
+        }
+
+        let (vehicle_pos, kind) = match (
+            world.get_component::<Position>(vehicle),
+            world.get_component::<Vehicle>(vehicle),
+        ) {
+            (Some(pos), Some(component)) => (*pos, component.kind),
+            _ => return,
+        };
+
+        let mut pos = match world.get_component::<Position>(passenger) {
+            Some(pos) => *pos,
+            None => return,
+        };
+        pos.x = vehicle_pos.x;
+        pos.y = vehicle_pos.y + kind.height();
+        pos.z = vehicle_pos.z;
+        *world.get_component_mut::<Position>(passenger).unwrap() = pos;
+
+        if let Some(network) = world.get_component::<Network>(passenger) {
+            network.send(PlayerPositionAndLookClientbound {
+                x: pos.x,
+                y: pos.y,
+                z: pos.z,
+                yaw: pos.yaw,
+                pitch: pos.pitch,
+                flags: 0,
+                teleport_id: 0,
+            });
+        }
+        scheduler.trigger(EntityMoveEvent { entity: passenger });
+    });
+}
+
+/// Makes all passengers of `vehicle` leave it.
+pub fn dismount_all(state: &State, world: &PreparedWorld, vehicle: Entity) {
+    if let Some(component) = world.get_component::<Vehicle>(vehicle) {
+        for passenger in &component.passengers {
+            dismount(state, *passenger);
+        }
+    }
+}
+
+/// Removes `passenger` from its vehicle without moving it,
+/// returning the vehicle.
+fn remove_passenger(world: &mut World, passenger: Entity) -> Option<Entity> {
+    let vehicle = world.get_component_mut::<Riding>(passenger)?.0.take()?;
+    if let Some(mut component) = world.get_component_mut::<Vehicle>(vehicle) {
+        component.passengers.retain(|entity| *entity != passenger);
+    }
+    Some(vehicle)
+}
+
+/// Sends the passengers of `vehicle` to all
+/// clients able to see the vehicle.
+fn send_passengers(world: &World, holders: &ChunkHolders, vehicle: Entity) {
+    let (pos, dimension, entity_id) = match (
+        world.get_component::<Position>(vehicle),
+        world.get_component::<DimensionId>(vehicle),
+        world.get_component::<EntityId>(vehicle),
+    ) {
+        (Some(pos), Some(dimension), Some(id)) => (*pos, *dimension, id.0),
+        _ => return,
+    };
+    let passengers = match world.get_component::<Vehicle>(vehicle) {
+        Some(component) => component
+            .passengers
+            .iter()
+            .filter_map(|passenger| world.get_component::<EntityId>(*passenger).map(|id| id.0))
+            .collect(),
+        None => return,
+    };
+
+    let packet = SetPassengers {
+        entity_id,
+        passengers,
+    };
+    for client in holders
+        .holders_for(dimension, pos.chunk_pos())
+        .unwrap_or(&[])
+    {
+        if let Some(network) = world.get_component::<Network>(*client) {
+            network.send(packet.clone());
+        }
+    }
+}
+
+/// Damages `vehicle` after it was hit by a player.
+///
+/// Vehicles break after a few hits, or immediately when
+/// hit by a player in creative mode, in which case no item
+/// is dropped.
+pub fn attack(
+    state: &State,
+    world: &PreparedWorld,
+    vehicle: Entity,
+    gamemode: Gamemode,
+    tick_count: u64,
+) {
+    let (broken, item) = {
+        let mut component = match world.get_component_mut::<Vehicle>(vehicle) {
+            Some(component) => component,
+            None => return,
+        };
+        component.damage += HIT_DAMAGE;
+        (
+            gamemode == Gamemode::Creative || component.damage > BREAK_DAMAGE,
+            component.item,
+        )
+    };
+
+    if broken {
+        dismount_all(state, world, vehicle);
+        state.delete_entity(vehicle);
+
+        if gamemode != Gamemode::Creative {
+            let pos = *world.get_component::<Position>(vehicle).unwrap();
+            let dimension = *world.get_component::<DimensionId>(vehicle).unwrap();
+            item::create(
+                &state.dimension(dimension),
+                pos,
+                ItemStack::new(item, 1),
+                tick_count + 10,
+            )
+            .build();
+        }
+        return;
+    }
+
+    // Make the vehicle shake on clients.
+    let damage = world.get_component::<Vehicle>(vehicle).unwrap().damage;
+    let mut meta = world.get_component_mut::<Metadata>(vehicle).unwrap();
+    match &mut *meta {
+        Metadata::Boat(boat) => {
+            boat.set_time_since_hit(10);
+            boat.set_damage_taken(damage);
+        }
+        Metadata::Minecart(minecart) => {
+            minecart.set_shaking_power(10);
+            minecart.set_shaking_multiplier(damage);
+        }
+        _ => (),
+    }
+
+    let entity_id = world.get_component::<EntityId>(vehicle).unwrap().0;
+    let packet = PacketEntityMetadata {
+        entity_id,
+        metadata: meta.to_full_raw_metadata(),
+    };
+    state.broadcast_entity_update(vehicle, packet, None);
+}
+
+/// System which moves passengers along with their vehicles
+/// and detaches passengers whose vehicle no longer exists
+/// or is in another dimension.
+#[system]
+fn vehicle_passengers(
+    state: &State,
+    query: &mut Query<(Read<Riding>, Read<DimensionId>)>,
+    _query: &mut Query<(Read<Vehicle>, Write<Position>)>,
+    world: &mut PreparedWorld,
+    move_events: &mut Trigger<EntityMoveEvent>,
+) {
+    let riders = SegQueue::new();
+    query.par_entities_for_each(world, |(entity, (riding, dimension))| {
+        if let Some(vehicle) = riding.0 {
+            riders.push((entity, vehicle, *dimension));
+        }
+    });
+
+    while let Ok((rider, vehicle, dimension)) = riders.pop() {
+        let seat = match (
+            world.get_component::<Vehicle>(vehicle),
+            world.get_component::<Position>(vehicle),
+            world.get_component::<DimensionId>(vehicle),
+        ) {
+            (Some(component), Some(pos), Some(vehicle_dimension))
+                if *vehicle_dimension == dimension =>
+            {
+                *pos + glm::vec3(0.0, component.kind.seat_height(), 0.0)
+            }
+            _ => {
+                detach(state, rider);
+                continue;
+            }
+        };
+
+        let mut pos = world.get_component_mut::<Position>(rider).unwrap();
+        if (pos.x, pos.y, pos.z) != (seat.x, seat.y, seat.z) {
+            pos.x = seat.x;
+            pos.y = seat.y;
+            pos.z = seat.z;
+            move_events.trigger(EntityMoveEvent { entity: rider });
+        }
+    }
+}
+
+/// Lazily detaches `rider` from its vehicle
+/// without moving it.
+fn detach(state: &State, rider: Entity) {
+    state.exec_with_scheduler(move |world, scheduler| {
+        if let Some(vehicle) = remove_passenger(world, rider) {
+            let holders = scheduler.resources().get::<ChunkHolders>();
+            send_passengers(world, &holders, vehicle);
+        }
+    });
+}
+
+/// System which lets the damage of vehicles
+/// decay and forgets passengers which no longer exist.
+#[system]
+fn vehicle_tick(query: &mut Query<Write<Vehicle>>, world: &mut PreparedWorld) {
+    let vehicles = SegQueue::new();
+    query.par_entities_for_each(world, |(entity, mut vehicle)| {
+        if vehicle.damage > 0.0 {
+            vehicle.damage = (vehicle.damage - 1.0).max(0.0);
+        }
+        if !vehicle.passengers.is_empty() {
+            vehicles.push(entity);
+        }
+    });
+
+    while let Ok(vehicle) = vehicles.pop() {
+        let passengers = world
+            .get_component::<Vehicle>(vehicle)
+            .unwrap()
+            .passengers
+            .clone();
+        let alive: SmallVec<[Entity; 2]> = passengers
+            .iter()
+            .copied()
+            .filter(|passenger| world.is_alive(*passenger))
+            .collect();
+        if alive.len() != passengers.len() {
+            world
+                .get_component_mut::<Vehicle>(vehicle)
+                .unwrap()
+                .passengers = alive;
+        }
+    }
+}
+
+/// Event handler which sends the passengers of a vehicle
+/// when it, or one of its passengers, is sent to a client.
+#[event_handler]
+fn send_vehicle_passengers(
+    event: &EntitySendEvent,
+    _query: &mut Query<(Read<Vehicle>, Read<Riding>, Read<EntityId>, Read<Network>)>,
+    world: &mut PreparedWorld,
+) {
+    let vehicle = match world.get_component::<Riding>(event.entity) {
+        Some(riding) => match riding.0 {
+            Some(vehicle) => vehicle,
+            None => return,
+        },
+        None => event.entity,
+    };
+
+    let passengers: Vec<i32> = match world.get_component::<Vehicle>(vehicle) {
+        Some(component) => component
+            .passengers
+            .iter()
+            .filter_map(|passenger| world.get_component::<EntityId>(*passenger).map(|id| id.0))
+            .collect(),
+        None => return,
+    };
+    if passengers.is_empty() {
+        return;
+    }
+
+    let entity_id = match world.get_component::<EntityId>(vehicle) {
+        Some(id) => id.0,
+        None => return,
+    };
+    if let Some(network) = world.get_component::<Network>(event.to) {
+        network.send(SetPassengers {
+            entity_id,
+            passengers,
+        });
+    }
+}
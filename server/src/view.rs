@@ -16,16 +16,25 @@
 //! crossed a chunk boundary. If so, a `ViewUpdateEvent` is triggered.
 //! * Various systems listen to `ViewUpdateEvent` and send necessary packets.
 //! This includes systems to load/unload chunks and send entities.
+//!
+//! When a player changes dimension, the client discards all chunks and
+//! entities, so the player's view is rebuilt as if it had just joined.
+//! Holds on chunks in the old dimension are released.
 
+use crate::broadcasters::movement::LastKnownPositions;
 use crate::chunk_logic;
 use crate::chunk_logic::{
-    ChunkHolder, ChunkHolderReleaseEvent, ChunkHolders, ChunkLoadEvent, ChunkWorkerHandle,
+    ChunkHolder, ChunkHolderReleaseEvent, ChunkHolders, ChunkLoadEvent, ChunkWorkers,
 };
 use crate::config::Config;
-use crate::entity::{EntityId, EntityMoveEvent, PreviousPosition, SpawnPacketCreator};
+use crate::entity::{
+    DimensionChangeEvent, EntityId, EntityMoveEvent, PreviousPosition, SpawnPacketCreator,
+};
 use crate::network::Network;
 use crate::player::{Player, PlayerJoinEvent};
-use crate::state::State;
+use crate::state::{DimensionState, State};
+use crate::world_border::{WorldBorder, WorldBorders};
+use crate::worlds::DimensionId;
 use chashmap::CHashMap;
 use feather_core::network::packet::implementation::{ChunkData, DestroyEntities, UnloadChunk};
 use feather_core::{Chunk, ChunkPosition, Position};
@@ -42,6 +51,8 @@ use tonks::{PreparedWorld, Query, QueryAccessor, Trigger};
 pub struct ViewUpdateEvent {
     /// The player whose view was updated.
     pub player: Entity,
+    /// The dimension the player is in.
+    pub dimension: DimensionId,
     /// The new chunk.
     pub new_chunk: ChunkPosition,
     /// The old chunk, or `None` if there was no old chunk
@@ -56,6 +67,7 @@ pub struct ViewUpdateEvent {
 /// Event triggered when a chunk is sent to a player.
 #[derive(Debug)]
 pub struct ChunkSendEvent {
+    pub dimension: DimensionId,
     pub chunk: ChunkPosition,
     pub player: Entity,
 }
@@ -65,7 +77,12 @@ pub struct ChunkSendEvent {
 #[event_handler]
 fn view_update(
     events: &[EntityMoveEvent],
-    _query: &mut Query<(Read<Position>, Read<PreviousPosition>, Read<Player>)>,
+    _query: &mut Query<(
+        Read<Position>,
+        Read<PreviousPosition>,
+        Read<DimensionId>,
+        Read<Player>,
+    )>,
     world: &mut PreparedWorld,
     state: &State,
     trigger: &mut Trigger<ViewUpdateEvent>,
@@ -82,6 +99,7 @@ fn view_update(
             .get_component::<PreviousPosition>(event.entity)
             .unwrap()
             .0;
+        let dimension = *world.get_component::<DimensionId>(event.entity).unwrap();
 
         // Find the old chunks and new chunks.
         let visible_new = chunks_within_view_distance(&state.config, pos.chunk_pos());
@@ -91,6 +109,7 @@ fn view_update(
             // New chunk: trigger view update.
             let event = ViewUpdateEvent {
                 player: event.entity,
+                dimension,
                 new_chunk: pos.chunk_pos(),
                 old_chunk: Some(prev_pos.chunk_pos()),
                 visible_old,
@@ -105,18 +124,20 @@ fn view_update(
 #[event_handler]
 fn view_update_on_join(
     event: &PlayerJoinEvent,
-    _query: &mut Query<Read<Position>>,
+    _query: &mut Query<(Read<Position>, Read<DimensionId>)>,
     world: &mut PreparedWorld,
     trigger: &mut Trigger<ViewUpdateEvent>,
     state: &State,
 ) {
     let position = *world.get_component::<Position>(event.player).unwrap();
+    let dimension = *world.get_component::<DimensionId>(event.player).unwrap();
 
     // Find the visible chunks.
     let visible_new = chunks_within_view_distance(&state.config, position.chunk_pos());
 
     trigger.trigger(ViewUpdateEvent {
         player: event.player,
+        dimension,
         new_chunk: position.chunk_pos(),
         old_chunk: None,
         visible_new,
@@ -124,6 +145,64 @@ fn view_update_on_join(
     });
 }
 
+/// System which releases a player's holds on the chunks of its
+/// old dimension and rebuilds its view when it changes dimension.
+#[event_handler]
+fn view_update_on_dimension_change(
+    events: &[DimensionChangeEvent],
+    _query: &mut Query<(
+        Read<Position>,
+        Read<Player>,
+        Write<ChunkHolder>,
+        Write<LastKnownPositions>,
+    )>,
+    world: &mut PreparedWorld,
+    holders: &mut ChunkHolders,
+    state: &State,
+    holder_release_trigger: &mut Trigger<ChunkHolderReleaseEvent>,
+    trigger: &mut Trigger<ViewUpdateEvent>,
+) {
+    for event in events {
+        if world.get_component::<Player>(event.entity).is_none() {
+            continue;
+        }
+
+        // The client has already discarded its chunks and
+        // entities, so there is nothing to unload on its side.
+        {
+            let mut holder = world
+                .get_component_mut::<ChunkHolder>(event.entity)
+                .unwrap();
+            let held: Vec<ChunkPosition> = holder.holds.iter().copied().collect();
+            for chunk in held {
+                chunk_logic::release_chunk(
+                    event.entity,
+                    &mut holder,
+                    holders,
+                    event.old_dimension,
+                    chunk,
+                    holder_release_trigger,
+                );
+            }
+        }
+        if let Some(mut positions) = world.get_component_mut::<LastKnownPositions>(event.entity) {
+            positions.0.clear();
+        }
+
+        let position = *world.get_component::<Position>(event.entity).unwrap();
+        let visible_new = chunks_within_view_distance(&state.config, position.chunk_pos());
+
+        trigger.trigger(ViewUpdateEvent {
+            player: event.entity,
+            dimension: event.new_dimension,
+            new_chunk: position.chunk_pos(),
+            old_chunk: None,
+            visible_new,
+            visible_old: HashSet::new(),
+        });
+    }
+}
+
 /// System which sends new chunks and unloads old chunks on the client
 /// when the view is updated.
 #[event_handler]
@@ -134,13 +213,16 @@ fn view_handle_chunks(
     holders: &mut ChunkHolders,
     state: &State,
     chunks_to_send: &ChunksToSend,
-    handle: &ChunkWorkerHandle,
+    workers: &ChunkWorkers,
+    borders: &WorldBorders,
     holder_release_trigger: &mut Trigger<ChunkHolderReleaseEvent>,
     chunk_send_trigger: &mut Trigger<ChunkSendEvent>,
 ) {
     events.iter().for_each(|event| {
         let to_send = event.visible_new.difference(&event.visible_old);
         let to_unload = event.visible_old.difference(&event.visible_new);
+        let state = state.dimension(event.dimension);
+        let border = borders.get(event.dimension.world);
 
         let network = world.get_component::<Network>(event.player).unwrap();
         let mut holder =
@@ -155,14 +237,15 @@ fn view_handle_chunks(
         // Send new chunks.
         to_send.into_iter().for_each(|chunk| {
             send_chunk_to_player(
-                state,
+                &state,
                 event.player,
                 &network,
                 &mut holder,
                 holders,
                 chunk,
                 chunks_to_send,
-                handle,
+                workers,
+                border,
                 chunk_send_trigger,
             );
         });
@@ -175,6 +258,7 @@ fn view_handle_chunks(
                 holder_release_trigger,
                 &mut holder,
                 holders,
+                event.dimension,
                 *chunk,
             );
         });
@@ -199,6 +283,7 @@ fn view_handle_entities(
     events.par_iter().for_each(|event: &ViewUpdateEvent| {
         let to_send = event.visible_new.difference(&event.visible_old);
         let to_unload = event.visible_old.difference(&event.visible_new);
+        let state = state.dimension(event.dimension);
 
         let network = world.get_component::<Network>(event.player).unwrap();
 
@@ -253,47 +338,52 @@ fn view_handle_entities(
 /// Resource containing a mapping from chunks -> sets of players indicating
 /// which chunks are pending to send to a given player.
 #[derive(Default, Resource)]
-pub struct ChunksToSend(CHashMap<ChunkPosition, SmallVec<[Entity; 2]>>);
+pub struct ChunksToSend(CHashMap<(DimensionId, ChunkPosition), SmallVec<[Entity; 2]>>);
 
 /// Asynchronously sends a chunk to a player.
 #[allow(clippy::too_many_arguments)]
 fn send_chunk_to_player(
-    state: &State,
+    state: &DimensionState,
     player: Entity,
     network: &Network,
     holder: &mut ChunkHolder,
     holders: &mut ChunkHolders,
     chunk: ChunkPosition,
     chunks_to_send: &ChunksToSend,
-    handle: &ChunkWorkerHandle,
+    workers: &ChunkWorkers,
+    border: &WorldBorder,
     trigger: &mut Trigger<ChunkSendEvent>,
 ) {
     // Ensure that the chunk isn't unloaded while the player has it loaded.
-    chunk_logic::hold_chunk(player, holder, holders, chunk);
+    let dimension = state.dimension;
+    chunk_logic::hold_chunk(player, holder, holders, dimension, chunk);
 
     // If the chunk is already loaded, send it. Otherwise, we need to
     // queue it for loading.
     if let Some(chunk) = state.chunk_at(chunk) {
         network.send(create_chunk_data(&chunk));
         trigger.trigger(ChunkSendEvent {
+            dimension,
             chunk: chunk.position(),
             player,
         });
-    } else {
-        let contains = chunks_to_send.0.contains_key(&chunk);
+    } else if border.contains_chunk(chunk) {
+        // Chunks outside the world border are never loaded or generated.
+        let key = (dimension, chunk);
+        let contains = chunks_to_send.0.contains_key(&key);
 
-        let mut vec = match chunks_to_send.0.get_mut(&chunk) {
+        let mut vec = match chunks_to_send.0.get_mut(&key) {
             Some(vec) => vec,
             None => {
-                chunks_to_send.0.insert(chunk, smallvec![]);
-                chunks_to_send.0.get_mut(&chunk).unwrap()
+                chunks_to_send.0.insert(key, smallvec![]);
+                chunks_to_send.0.get_mut(&key).unwrap()
             }
         };
         vec.push(player);
 
         if !contains {
             // Queue chunk for loading if it isn't already.
-            chunk_logic::load_chunk(handle, chunk);
+            chunk_logic::load_chunk(workers, dimension, chunk);
         }
     }
 }
@@ -305,10 +395,11 @@ fn unload_chunk_for_player(
     trigger: &mut Trigger<ChunkHolderReleaseEvent>,
     holder: &mut ChunkHolder,
     holders: &mut ChunkHolders,
+    dimension: DimensionId,
     chunk: ChunkPosition,
 ) {
     // Release hold on chunk so it can be unloaded on the server
-    chunk_logic::release_chunk(player, holder, holders, chunk, trigger);
+    chunk_logic::release_chunk(player, holder, holders, dimension, chunk, trigger);
 
     // Send Unload Chunk packet.
     network.send(UnloadChunk {
@@ -323,25 +414,33 @@ fn chunk_send(
     event: &ChunkLoadEvent,
     state: &State,
     to_send: &ChunksToSend,
-    _query: &mut Query<Read<Network>>,
+    _query: &mut Query<(Read<Network>, Read<DimensionId>)>,
     world: &mut PreparedWorld,
     trigger: &mut Trigger<ChunkSendEvent>,
 ) {
-    if let Some(players) = to_send.0.get(&event.pos) {
+    let key = (event.dimension, event.pos);
+    if let Some(players) = to_send.0.get(&key) {
         let chunk = state
+            .dimension(event.dimension)
             .chunk_at(event.pos)
             .expect("chunk not loaded, but load event was triggered");
         players.iter().for_each(|player| {
+            // The player may have changed dimension while the chunk was loading.
+            if world.get_component::<DimensionId>(*player).map(|d| *d) != Some(event.dimension) {
+                return;
+            }
+
             let network = world.get_component::<Network>(*player).unwrap();
             network.send(create_chunk_data(&chunk));
             trigger.trigger(ChunkSendEvent {
+                dimension: event.dimension,
                 chunk: chunk.position(),
                 player: *player,
             });
         });
     }
 
-    to_send.0.remove(&event.pos);
+    to_send.0.remove(&key);
 }
 
 /// Creates a chunk data packet for the given chunk.
@@ -0,0 +1,544 @@
+//! Weather: rain, snow and thunderstorms.
+//!
+//! Weather alternates between clear and rainy periods, and
+//! independently between calm and thundering periods, with
+//! vanilla durations. A thunderstorm only occurs while it is
+//! both raining and thundering. Whether it rains or snows in
+//! a given place depends on the biome.
+//!
+//! Clients render weather themselves; the server notifies them
+//! when rain begins or ends and as the rain and thunder levels
+//! fade in and out. Each world has its own weather, which only
+//! has effects in its overworld.
+
+use crate::block::BlockUpdateCause;
+use crate::difficulty::{DifficultySettings, WorldDifficulty};
+use crate::entity::ENTITY_ID_COUNTER;
+use crate::game_rules::{GameRule, GameRules, WorldGameRules};
+use crate::network::Network;
+use crate::player::PlayerJoinEvent;
+use crate::state::{DimensionState, State};
+use crate::worlds::{DimensionId, PerWorld};
+use feather_blocks::{FireData, SnowData};
+use feather_core::level::LevelData;
+use feather_core::packet::{ChangeGameState, SpawnGlobalEntity};
+use feather_core::{Biome, Block, BlockExt, BlockPosition, Chunk, Difficulty, Dimension};
+use legion::query::Read;
+use rand::Rng;
+use std::sync::atomic::Ordering;
+use tonks::{PreparedWorld, Query};
+
+/// `ChangeGameState` reason for the beginning of rain.
+const BEGIN_RAIN: u8 = 1;
+/// `ChangeGameState` reason for the end of rain.
+const END_RAIN: u8 = 2;
+/// `ChangeGameState` reason for the rain level.
+const RAIN_LEVEL: u8 = 7;
+/// `ChangeGameState` reason for the thunder level.
+const THUNDER_LEVEL: u8 = 8;
+
+/// The amount by which the rain and thunder
+/// levels change each tick.
+const FADE_STEP: f32 = 0.01;
+/// The rain level above which clients
+/// consider it to be raining.
+const RAINING_THRESHOLD: f32 = 0.2;
+
+/// One in this many chunks is struck
+/// by lightning each tick during a storm.
+const LIGHTNING_CHANCE: u32 = 100_000;
+/// One in this many chunks accumulates
+/// snow each tick while it is raining.
+const SNOW_CHANCE: u32 = 16;
+
+/// Global entity type ID of lightning bolts.
+const LIGHTNING_TYPE: u8 = 1;
+
+/// The weather of a world.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Weather {
+    /// Whether it is raining or will start to rain.
+    pub raining: bool,
+    /// The number of ticks until `raining` is toggled.
+    pub rain_time: i32,
+    /// Whether it is thundering or will start to thunder.
+    pub thundering: bool,
+    /// The number of ticks until `thundering` is toggled.
+    pub thunder_time: i32,
+    /// The number of ticks for which the weather
+    /// is forced to stay clear, as set by `/weather clear`.
+    pub clear_weather_time: i32,
+    /// The current rain level, from 0 to 1.
+    pub rain_level: f32,
+    /// The current thunder level, from 0 to 1.
+    pub thunder_level: f32,
+}
+
+impl Weather {
+    /// Creates the weather from the values stored in `level.dat`.
+    pub fn from_level(level: &LevelData) -> Self {
+        Self {
+            raining: level.raining,
+            rain_time: level.rain_time,
+            thundering: level.thundering,
+            thunder_time: level.thunder_time,
+            clear_weather_time: level.clear_weather_time,
+            rain_level: if level.raining { 1.0 } else { 0.0 },
+            thunder_level: if level.thundering { 1.0 } else { 0.0 },
+        }
+    }
+
+    /// Writes the weather into `level` so it can be saved to `level.dat`.
+    pub fn write_to_level(&self, level: &mut LevelData) {
+        level.raining = self.raining;
+        level.rain_time = self.rain_time;
+        level.thundering = self.thundering;
+        level.thunder_time = self.thunder_time;
+        level.clear_weather_time = self.clear_weather_time;
+    }
+
+    /// Sends the weather to a player, if it is raining.
+    pub fn send(&self, network: &Network) {
+        if !self.is_raining() {
+            return;
+        }
+
+        network.send(ChangeGameState {
+            reason: BEGIN_RAIN,
+            value: 0.0,
+        });
+        network.send(ChangeGameState {
+            reason: RAIN_LEVEL,
+            value: self.rain_level,
+        });
+        network.send(ChangeGameState {
+            reason: THUNDER_LEVEL,
+            value: self.thunder_level,
+        });
+    }
+
+    /// Returns whether clients consider it to be raining.
+    pub fn is_raining(&self) -> bool {
+        self.rain_level > RAINING_THRESHOLD
+    }
+
+    /// Returns whether a thunderstorm is in progress.
+    pub fn is_thundering(&self) -> bool {
+        self.is_raining() && self.thunder_level > 0.9
+    }
+
+    /// Stops rain and thunder, as happens when players
+    /// sleep through the night. The levels fade out over
+    /// the following ticks.
+    pub fn clear(&mut self) {
+        self.raining = false;
+        self.rain_time = 0;
+        self.thundering = false;
+        self.thunder_time = 0;
+    }
+
+    /// Keeps the weather clear for `duration` ticks.
+    pub fn set_clear(&mut self, duration: i32) {
+        self.clear();
+        self.clear_weather_time = duration;
+    }
+
+    /// Makes it rain for `duration` ticks.
+    pub fn set_rain(&mut self, duration: i32) {
+        self.clear_weather_time = 0;
+        self.raining = true;
+        self.rain_time = duration;
+        self.thundering = false;
+        self.thunder_time = duration;
+    }
+
+    /// Starts a thunderstorm lasting `duration` ticks.
+    pub fn set_thunder(&mut self, duration: i32) {
+        self.set_rain(duration);
+        self.thundering = true;
+    }
+
+    /// Advances the weather cycle by one tick.
+    fn update_cycle(&mut self, rng: &mut impl Rng) {
+        if self.clear_weather_time > 0 {
+            self.clear_weather_time -= 1;
+            self.thunder_time = if self.thundering { 0 } else { 1 };
+            self.rain_time = if self.raining { 0 } else { 1 };
+            self.thundering = false;
+            self.raining = false;
+            return;
+        }
+
+        if self.thunder_time <= 0 {
+            self.thunder_time = if self.thundering {
+                rng.gen_range(3600, 15600)
+            } else {
+                rng.gen_range(12000, 180_000)
+            };
+        } else {
+            self.thunder_time -= 1;
+            if self.thunder_time <= 0 {
+                self.thundering = !self.thundering;
+            }
+        }
+
+        if self.rain_time <= 0 {
+            self.rain_time = if self.raining {
+                rng.gen_range(12000, 24000)
+            } else {
+                rng.gen_range(12000, 180_000)
+            };
+        } else {
+            self.rain_time -= 1;
+            if self.rain_time <= 0 {
+                self.raining = !self.raining;
+            }
+        }
+    }
+
+    /// Fades the rain and thunder levels toward
+    /// the current weather by one tick.
+    fn update_levels(&mut self) {
+        self.thunder_level = fade(self.thunder_level, self.thundering);
+        self.rain_level = fade(self.rain_level, self.raining);
+    }
+}
+
+fn fade(level: f32, increase: bool) -> f32 {
+    let step = if increase { FADE_STEP } else { -FADE_STEP };
+    (level + step).max(0.0).min(1.0)
+}
+
+/// The kind of precipitation which falls in a biome.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precipitation {
+    None,
+    Rain,
+    Snow,
+}
+
+/// Returns the precipitation which falls in `biome`.
+pub fn precipitation(biome: Biome) -> Precipitation {
+    match biome {
+        Biome::SnowyTundra
+        | Biome::IceSpikes
+        | Biome::SnowyTaiga
+        | Biome::SnowyTaigaHills
+        | Biome::SnowyTaigaMountains
+        | Biome::SnowyBeach
+        | Biome::SnowyMountains
+        | Biome::FrozenRiver
+        | Biome::FrozenOcean
+        | Biome::DeepFrozenOcean => Precipitation::Snow,
+        Biome::Desert
+        | Biome::DesertHills
+        | Biome::DesertLakes
+        | Biome::Savanna
+        | Biome::SavannaPlateau
+        | Biome::ShatteredSavanna
+        | Biome::ShatteredSavannaPlateau
+        | Biome::Badlands
+        | Biome::BadlandsPlateau
+        | Biome::ErodedBadlands
+        | Biome::ModifiedBadlandsPlateau
+        | Biome::WoodedBadlandsPlateau
+        | Biome::ModifiedWoodedBadlandsPlateau
+        | Biome::Nether
+        | Biome::TheEnd
+        | Biome::EndBarrens
+        | Biome::EndHighlands
+        | Biome::EndMidlands
+        | Biome::SmallEndIslands
+        | Biome::TheVoid => Precipitation::None,
+        _ => Precipitation::Rain,
+    }
+}
+
+/// Returns the lowest Y coordinate in a column of
+/// a chunk which precipitation can reach.
+fn precipitation_height(chunk: &Chunk, x: usize, z: usize) -> usize {
+    (0..256)
+        .rev()
+        .find(|y| {
+            let block = chunk.block_at(x, *y, z);
+            block.is_solid() || is_liquid(block)
+        })
+        .map_or(0, |y| y + 1)
+}
+
+fn is_liquid(block: Block) -> bool {
+    match block {
+        Block::Water(_) | Block::Lava(_) => true,
+        _ => false,
+    }
+}
+
+/// Returns whether snow can accumulate at the given
+/// position in a chunk.
+fn can_snow_at(chunk: &Chunk, x: usize, y: usize, z: usize) -> bool {
+    if y == 0 || y > 255 || chunk.block_light_at(x, y, z) >= 10 {
+        return false;
+    }
+
+    let below = chunk.block_at(x, y - 1, z);
+    let supported = match below {
+        Block::Ice | Block::PackedIce => false,
+        below => below.is_solid(),
+    };
+
+    chunk.block_at(x, y, z) == Block::Air && supported
+}
+
+/// The weather of each world.
+#[derive(Debug, Clone, Resource, Deref, DerefMut)]
+pub struct WorldWeather(pub PerWorld<Weather>);
+
+/// System which advances the weather cycle and
+/// notifies players of changes to the weather.
+#[system]
+fn weather_tick(state: &State, weather: &mut WorldWeather, rules: &WorldGameRules) {
+    for (world, weather) in weather.iter_mut() {
+        let was_raining = weather.is_raining();
+        let old_rain_level = weather.rain_level;
+        let old_thunder_level = weather.thunder_level;
+
+        if rules.get(world).get_bool(GameRule::DoWeatherCycle) {
+            weather.update_cycle(&mut rand::thread_rng());
+        }
+        weather.update_levels();
+
+        if (weather.rain_level - old_rain_level).abs() > std::f32::EPSILON {
+            state.broadcast_world(
+                world,
+                ChangeGameState {
+                    reason: RAIN_LEVEL,
+                    value: weather.rain_level,
+                },
+                None,
+            );
+        }
+        if (weather.thunder_level - old_thunder_level).abs() > std::f32::EPSILON {
+            state.broadcast_world(
+                world,
+                ChangeGameState {
+                    reason: THUNDER_LEVEL,
+                    value: weather.thunder_level,
+                },
+                None,
+            );
+        }
+
+        if was_raining != weather.is_raining() {
+            let reason = if was_raining { END_RAIN } else { BEGIN_RAIN };
+            state.broadcast_world(world, ChangeGameState { reason, value: 0.0 }, None);
+        }
+    }
+}
+
+/// System which strikes lightning during thunderstorms
+/// and covers cold biomes with snow while it rains.
+#[system]
+fn weather_effects(
+    state: &State,
+    weather: &WorldWeather,
+    rules: &WorldGameRules,
+    difficulty: &WorldDifficulty,
+) {
+    let mut rng = rand::thread_rng();
+
+    for (world, weather) in weather.iter() {
+        if !weather.is_raining() {
+            continue;
+        }
+
+        let thundering = weather.is_thundering();
+        let rules = rules.get(world);
+        let difficulty = *difficulty.get(world);
+        let state = &state.dimension(DimensionId::new(world, Dimension::Overworld));
+
+        // Collect positions first, since updating
+        // blocks requires write access to the chunk map.
+        let mut strikes = vec![];
+        let mut snow = vec![];
+        for chunk in state.chunk_map.iter_chunks() {
+            let strike = thundering && rng.gen_range(0, LIGHTNING_CHANCE) == 0;
+            let snowfall = rng.gen_range(0, SNOW_CHANCE) == 0;
+            if !strike && !snowfall {
+                continue;
+            }
+
+            let chunk = chunk.read();
+            let chunk_pos = chunk.position();
+            let (x, z) = (rng.gen_range(0, 16), rng.gen_range(0, 16));
+            let y = precipitation_height(&chunk, x, z);
+
+            let pos = BlockPosition::new(
+                chunk_pos.x * 16 + x as i32,
+                y as i32,
+                chunk_pos.z * 16 + z as i32,
+            );
+
+            match precipitation(chunk.biome_at(x, z)) {
+                Precipitation::Rain if strike => strikes.push(pos),
+                Precipitation::Snow if snowfall && can_snow_at(&chunk, x, y, z) => snow.push(pos),
+                _ => (),
+            }
+        }
+
+        for pos in strikes {
+            strike_lightning(state, rules, difficulty, pos);
+        }
+
+        for pos in snow {
+            state.set_block_at(
+                pos,
+                Block::Snow(SnowData { layers: 1 }),
+                BlockUpdateCause::Weather,
+            );
+        }
+    }
+}
+
+/// Strikes lightning at the given position.
+///
+/// Lightning sets fire to the block it strikes on normal
+/// and hard difficulty, unless `doFireTick` is disabled.
+pub fn strike_lightning(
+    state: &DimensionState,
+    rules: &GameRules,
+    difficulty: DifficultySettings,
+    pos: BlockPosition,
+) {
+    let packet = SpawnGlobalEntity {
+        entity_id: ENTITY_ID_COUNTER.fetch_add(1, Ordering::Relaxed),
+        ty: LIGHTNING_TYPE,
+        x: f64::from(pos.x) + 0.5,
+        y: f64::from(pos.y),
+        z: f64::from(pos.z) + 0.5,
+    };
+    state.broadcast_chunk_update(pos.chunk_pos(), packet, None);
+
+    let sets_fire = match difficulty.difficulty {
+        Difficulty::Medium | Difficulty::Hard => true,
+        Difficulty::Peaceful | Difficulty::Easy => false,
+    };
+
+    if sets_fire && rules.get_bool(GameRule::DoFireTick) && state.block_at(pos) == Some(Block::Air)
+    {
+        state.set_block_at(
+            pos,
+            Block::Fire(FireData::default()),
+            BlockUpdateCause::Weather,
+        );
+    }
+}
+
+/// Event handler for sending the weather to players.
+#[event_handler]
+fn weather_send(
+    event: &PlayerJoinEvent,
+    weather: &WorldWeather,
+    _query: &mut Query<(Read<Network>, Read<DimensionId>)>,
+    world: &mut PreparedWorld,
+) {
+    let network = world.get_component::<Network>(event.player).unwrap();
+    let dimension = world.get_component::<DimensionId>(event.player).unwrap();
+
+    weather.get(dimension.world).send(&network);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clear() -> Weather {
+        Weather {
+            raining: false,
+            rain_time: 0,
+            thundering: false,
+            thunder_time: 0,
+            clear_weather_time: 0,
+            rain_level: 0.0,
+            thunder_level: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_update_cycle_durations() {
+        let mut rng = rand::thread_rng();
+        let mut weather = clear();
+
+        weather.update_cycle(&mut rng);
+        assert!(weather.rain_time >= 12000 && weather.rain_time < 180_000);
+        assert!(weather.thunder_time >= 12000 && weather.thunder_time < 180_000);
+
+        weather.rain_time = 1;
+        weather.update_cycle(&mut rng);
+        assert!(weather.raining);
+        assert_eq!(weather.rain_time, 0);
+
+        weather.update_cycle(&mut rng);
+        assert!(weather.rain_time >= 12000 && weather.rain_time < 24000);
+    }
+
+    #[test]
+    fn test_update_cycle_forced_clear() {
+        let mut weather = clear();
+        weather.raining = true;
+        weather.thundering = true;
+        weather.clear_weather_time = 2;
+
+        weather.update_cycle(&mut rand::thread_rng());
+        assert!(!weather.raining);
+        assert!(!weather.thundering);
+        assert_eq!(weather.clear_weather_time, 1);
+    }
+
+    #[test]
+    fn test_set_weather() {
+        let mut rng = rand::thread_rng();
+        let mut weather = clear();
+
+        weather.set_thunder(100);
+        weather.update_cycle(&mut rng);
+        assert!(weather.raining);
+        assert!(weather.thundering);
+        assert_eq!(weather.rain_time, 99);
+
+        weather.set_clear(100);
+        weather.update_cycle(&mut rng);
+        assert!(!weather.raining);
+        assert!(!weather.thundering);
+        assert_eq!(weather.clear_weather_time, 99);
+    }
+
+    #[test]
+    fn test_update_levels() {
+        let mut weather = clear();
+        weather.raining = true;
+
+        for _ in 0..20 {
+            weather.update_levels();
+        }
+        assert!(weather.is_raining());
+        assert!(!weather.is_thundering());
+
+        for _ in 0..200 {
+            weather.update_levels();
+        }
+        assert_eq!(weather.rain_level, 1.0);
+
+        weather.raining = false;
+        for _ in 0..200 {
+            weather.update_levels();
+        }
+        assert_eq!(weather.rain_level, 0.0);
+    }
+
+    #[test]
+    fn test_precipitation() {
+        assert_eq!(precipitation(Biome::Plains), Precipitation::Rain);
+        assert_eq!(precipitation(Biome::SnowyTundra), Precipitation::Snow);
+        assert_eq!(precipitation(Biome::Desert), Precipitation::None);
+    }
+}
@@ -0,0 +1,414 @@
+//! The world border.
+//!
+//! The border is a square centered on `center_x`/`center_z`. Its
+//! size can be changed instantly or interpolated over time. Players
+//! outside the border (beyond its safe zone) take damage, blocks
+//! can't be placed beyond it, and chunks lying completely outside it
+//! are never loaded or generated. Each world has its own border.
+
+use crate::health::{DamageCause, EntityDamageEvent};
+use crate::network::Network;
+use crate::player::{Player, PlayerJoinEvent};
+use crate::state::State;
+use crate::worlds::{DimensionId, PerWorld, WorldId};
+use crate::TickCount;
+use crossbeam::queue::SegQueue;
+use feather_core::level::LevelData;
+use feather_core::packet::{WorldBorder as WorldBorderPacket, WorldBorderAction};
+use feather_core::{BlockPosition, ChunkPosition, Gamemode, Position};
+use legion::query::Read;
+use tonks::{PreparedWorld, Query, Trigger};
+
+/// The default and maximum size of the border.
+pub const DEFAULT_SIZE: f64 = 60_000_000.0;
+/// The distance from the origin beyond which
+/// portals don't teleport entities.
+const PORTAL_TELEPORT_BOUNDARY: i32 = 29_999_984;
+/// The number of ticks between damage checks
+/// for players outside the border.
+const DAMAGE_INTERVAL: u64 = 10;
+
+/// The border of a world.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorldBorder {
+    /// The X coordinate of the center.
+    pub center_x: f64,
+    /// The Z coordinate of the center.
+    pub center_z: f64,
+    /// The distance beyond the border which
+    /// players can be without taking damage.
+    pub safe_zone: f64,
+    /// Damage dealt each second per block
+    /// beyond the safe zone.
+    pub damage_per_block: f64,
+    /// The distance from the border at which
+    /// clients show a warning.
+    pub warning_blocks: i32,
+    /// The time in seconds before a shrinking
+    /// border reaches a player at which clients
+    /// show a warning.
+    pub warning_time: i32,
+    /// The size at the start of the current resize.
+    old_size: f64,
+    /// The size at the end of the current resize.
+    target_size: f64,
+    /// The duration of the current resize in ticks.
+    lerp_ticks: u64,
+    /// The number of ticks elapsed in the current resize.
+    lerp_elapsed: u64,
+}
+
+impl Default for WorldBorder {
+    fn default() -> Self {
+        Self {
+            center_x: 0.0,
+            center_z: 0.0,
+            safe_zone: 5.0,
+            damage_per_block: 0.2,
+            warning_blocks: 5,
+            warning_time: 15,
+            old_size: DEFAULT_SIZE,
+            target_size: DEFAULT_SIZE,
+            lerp_ticks: 0,
+            lerp_elapsed: 0,
+        }
+    }
+}
+
+impl WorldBorder {
+    /// Creates the world border from the values stored in `level.dat`.
+    pub fn from_level(level: &LevelData) -> Self {
+        let size = clamp_size(level.border_size);
+        let lerp_ticks = (level.border_size_lerp_time.max(0) / 50) as u64;
+        let target_size = if lerp_ticks > 0 {
+            clamp_size(level.border_size_lerp_target)
+        } else {
+            size
+        };
+
+        Self {
+            center_x: level.border_center_x,
+            center_z: level.border_center_z,
+            safe_zone: level.border_safe_zone,
+            damage_per_block: level.border_damage_per_block,
+            warning_blocks: level.border_warning_blocks as i32,
+            warning_time: level.border_warning_time as i32,
+            old_size: size,
+            target_size,
+            lerp_ticks,
+            lerp_elapsed: 0,
+        }
+    }
+
+    /// Writes the world border into `level` so it can be saved to `level.dat`.
+    pub fn write_to_level(&self, level: &mut LevelData) {
+        level.border_center_x = self.center_x;
+        level.border_center_z = self.center_z;
+        level.border_safe_zone = self.safe_zone;
+        level.border_damage_per_block = self.damage_per_block;
+        level.border_warning_blocks = f64::from(self.warning_blocks);
+        level.border_warning_time = f64::from(self.warning_time);
+        level.border_size = self.size();
+        level.border_size_lerp_target = self.target_size;
+        level.border_size_lerp_time = self.remaining_millis();
+    }
+
+    /// Returns the current size of the border.
+    pub fn size(&self) -> f64 {
+        if self.lerp_elapsed >= self.lerp_ticks {
+            self.target_size
+        } else {
+            let progress = self.lerp_elapsed as f64 / self.lerp_ticks as f64;
+            self.old_size + (self.target_size - self.old_size) * progress
+        }
+    }
+
+    /// Returns the number of milliseconds until
+    /// the current resize is complete.
+    pub fn remaining_millis(&self) -> i64 {
+        (self.lerp_ticks.saturating_sub(self.lerp_elapsed) * 50) as i64
+    }
+
+    /// Returns the minimum and maximum X and Z
+    /// coordinates inside the border.
+    fn bounds(&self) -> (f64, f64, f64, f64) {
+        let radius = self.size() / 2.0;
+        (
+            self.center_x - radius,
+            self.center_x + radius,
+            self.center_z - radius,
+            self.center_z + radius,
+        )
+    }
+
+    /// Returns whether the given point is inside the border.
+    pub fn contains(&self, x: f64, z: f64) -> bool {
+        let (min_x, max_x, min_z, max_z) = self.bounds();
+        x > min_x && x < max_x && z > min_z && z < max_z
+    }
+
+    /// Returns whether any part of the given block is inside the border.
+    pub fn contains_block(&self, pos: BlockPosition) -> bool {
+        let (min_x, max_x, min_z, max_z) = self.bounds();
+        let (x, z) = (f64::from(pos.x), f64::from(pos.z));
+        x + 1.0 > min_x && x < max_x && z + 1.0 > min_z && z < max_z
+    }
+
+    /// Returns whether any part of the given chunk is inside the border.
+    pub fn contains_chunk(&self, pos: ChunkPosition) -> bool {
+        let (min_x, max_x, min_z, max_z) = self.bounds();
+        let (x, z) = (f64::from(pos.x * 16), f64::from(pos.z * 16));
+        x + 16.0 > min_x && x < max_x && z + 16.0 > min_z && z < max_z
+    }
+
+    /// Returns the distance from the given point to the
+    /// closest edge of the border. The distance is negative
+    /// if the point is outside the border.
+    pub fn distance_inside(&self, x: f64, z: f64) -> f64 {
+        let (min_x, max_x, min_z, max_z) = self.bounds();
+        (x - min_x).min(max_x - x).min(z - min_z).min(max_z - z)
+    }
+
+    /// Returns the damage dealt to a player at the given
+    /// point each time the border damages players, or
+    /// `None` if the player is not to be damaged.
+    fn damage_at(&self, x: f64, z: f64) -> Option<u32> {
+        let distance = self.distance_inside(x, z) + self.safe_zone;
+        if distance >= 0.0 || self.damage_per_block <= 0.0 {
+            return None;
+        }
+
+        Some((-distance * self.damage_per_block).floor().max(1.0) as u32)
+    }
+
+    /// Sets the size of the border immediately.
+    pub fn set_size(&mut self, state: &State, world: WorldId, size: f64) {
+        let size = clamp_size(size);
+        self.old_size = size;
+        self.target_size = size;
+        self.lerp_ticks = 0;
+        self.lerp_elapsed = 0;
+
+        broadcast(state, world, WorldBorderAction::SetSize(size));
+    }
+
+    /// Gradually resizes the border to `size`
+    /// over the given number of milliseconds.
+    pub fn lerp_size(&mut self, state: &State, world: WorldId, size: f64, millis: u64) {
+        let old_size = self.size();
+        let size = clamp_size(size);
+        self.old_size = old_size;
+        self.target_size = size;
+        self.lerp_ticks = millis / 50;
+        self.lerp_elapsed = 0;
+
+        broadcast(
+            state,
+            world,
+            WorldBorderAction::LerpSize(old_size, size, millis as i64),
+        );
+    }
+
+    /// Moves the center of the border.
+    pub fn set_center(&mut self, state: &State, world: WorldId, x: f64, z: f64) {
+        self.center_x = x;
+        self.center_z = z;
+
+        broadcast(state, world, WorldBorderAction::SetCenter(x, z));
+    }
+
+    /// Sets the distance at which clients show a warning.
+    pub fn set_warning_blocks(&mut self, state: &State, world: WorldId, blocks: i32) {
+        self.warning_blocks = blocks;
+
+        broadcast(state, world, WorldBorderAction::SetWarningBlocks(blocks));
+    }
+
+    /// Sets the time at which clients show a warning
+    /// about a shrinking border.
+    pub fn set_warning_time(&mut self, state: &State, world: WorldId, seconds: i32) {
+        self.warning_time = seconds;
+
+        broadcast(state, world, WorldBorderAction::SetWarningTime(seconds));
+    }
+
+    /// Returns the packet which sends the whole border to a player.
+    pub fn initialize_packet(&self) -> WorldBorderPacket {
+        WorldBorderPacket {
+            action: WorldBorderAction::Initialize {
+                x: self.center_x,
+                z: self.center_z,
+                old_diameter: self.size(),
+                new_diameter: self.target_size,
+                speed: self.remaining_millis(),
+                portal_teleport_boundary: PORTAL_TELEPORT_BOUNDARY,
+                warning_time: self.warning_time,
+                warning_blocks: self.warning_blocks,
+            },
+        }
+    }
+}
+
+fn clamp_size(size: f64) -> f64 {
+    if size > 0.0 {
+        size.min(DEFAULT_SIZE)
+    } else {
+        DEFAULT_SIZE
+    }
+}
+
+fn broadcast(state: &State, world: WorldId, action: WorldBorderAction) {
+    state.broadcast_world(world, WorldBorderPacket { action }, None);
+}
+
+/// The border of each world.
+#[derive(Debug, Clone, Resource, Deref, DerefMut)]
+pub struct WorldBorders(pub PerWorld<WorldBorder>);
+
+/// System which advances resizes of the borders.
+#[system]
+fn world_border_tick(borders: &mut WorldBorders) {
+    for (_, border) in borders.iter_mut() {
+        if border.lerp_elapsed < border.lerp_ticks {
+            border.lerp_elapsed += 1;
+        }
+    }
+}
+
+/// System which damages players outside the border of their world.
+#[system]
+fn world_border_damage(
+    borders: &WorldBorders,
+    tick_count: &TickCount,
+    query: &mut Query<(
+        Read<Position>,
+        Read<DimensionId>,
+        Read<Gamemode>,
+        Read<Player>,
+    )>,
+    world: &mut PreparedWorld,
+    trigger: &mut Trigger<EntityDamageEvent>,
+) {
+    if tick_count.0 % DAMAGE_INTERVAL != 0 {
+        return;
+    }
+
+    let damaged = SegQueue::new();
+
+    query.par_entities_for_each(world, |(player, (pos, dimension, gamemode, _))| {
+        match *gamemode {
+            Gamemode::Survival | Gamemode::Adventure => (),
+            _ => return,
+        }
+
+        if let Some(damage) = borders.get(dimension.world).damage_at(pos.x, pos.z) {
+            damaged.push(EntityDamageEvent {
+                entity: player,
+                damage,
+                cause: DamageCause::WorldBorder,
+            });
+        }
+    });
+
+    while let Ok(event) = damaged.pop() {
+        trigger.trigger(event);
+    }
+}
+
+/// Event handler for sending the world border to players.
+#[event_handler]
+fn world_border_send(
+    event: &PlayerJoinEvent,
+    borders: &WorldBorders,
+    _query: &mut Query<(Read<Network>, Read<DimensionId>)>,
+    world: &mut PreparedWorld,
+) {
+    let network = world.get_component::<Network>(event.player).unwrap();
+    let dimension = world.get_component::<DimensionId>(event.player).unwrap();
+    network.send(borders.get(dimension.world).initialize_packet());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn border(size: f64) -> WorldBorder {
+        WorldBorder {
+            old_size: size,
+            target_size: size,
+            ..WorldBorder::default()
+        }
+    }
+
+    #[test]
+    fn test_contains() {
+        let border = border(32.0);
+
+        assert!(border.contains(15.9, -15.9));
+        assert!(!border.contains(16.1, 0.0));
+
+        assert!(border.contains_block(BlockPosition::new(15, 64, -16)));
+        assert!(!border.contains_block(BlockPosition::new(16, 64, 0)));
+        assert!(!border.contains_block(BlockPosition::new(0, 64, -17)));
+
+        assert!(border.contains_chunk(ChunkPosition::new(0, -1)));
+        assert!(!border.contains_chunk(ChunkPosition::new(1, 0)));
+        assert!(!border.contains_chunk(ChunkPosition::new(0, -2)));
+    }
+
+    #[test]
+    fn test_distance_and_damage() {
+        let border = border(32.0);
+
+        assert_eq!(border.distance_inside(0.0, 10.0), 6.0);
+        assert_eq!(border.distance_inside(20.0, 0.0), -4.0);
+
+        // Within the safe zone.
+        assert_eq!(border.damage_at(20.0, 0.0), None);
+        // Rounded up to at least one damage.
+        assert_eq!(border.damage_at(22.0, 0.0), Some(1));
+        assert_eq!(border.damage_at(36.0, 0.0), Some(3));
+    }
+
+    #[test]
+    fn test_lerp() {
+        let mut border = border(100.0);
+        border.target_size = 200.0;
+        border.lerp_ticks = 20;
+
+        assert_eq!(border.size(), 100.0);
+        assert_eq!(border.remaining_millis(), 1000);
+
+        border.lerp_elapsed = 10;
+        assert_eq!(border.size(), 150.0);
+
+        border.lerp_elapsed = 20;
+        assert_eq!(border.size(), 200.0);
+        assert_eq!(border.remaining_millis(), 0);
+    }
+
+    #[test]
+    fn test_level_round_trip() {
+        let mut level = LevelData::default();
+        level.border_size = 1000.0;
+        level.border_size_lerp_target = 500.0;
+        level.border_size_lerp_time = 10_000;
+        level.border_center_x = 8.0;
+        level.border_warning_blocks = 5.0;
+
+        let border = WorldBorder::from_level(&level);
+        assert_eq!(border.size(), 1000.0);
+        assert_eq!(border.center_x, 8.0);
+        assert_eq!(border.warning_blocks, 5);
+
+        let mut saved = LevelData::default();
+        border.write_to_level(&mut saved);
+        assert_eq!(saved.border_size, 1000.0);
+        assert_eq!(saved.border_size_lerp_target, 500.0);
+        assert_eq!(saved.border_size_lerp_time, 10_000);
+
+        // A missing border size means the default border.
+        let border = WorldBorder::from_level(&LevelData::default());
+        assert_eq!(border.size(), DEFAULT_SIZE);
+    }
+}
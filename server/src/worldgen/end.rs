@@ -0,0 +1,70 @@
+//! A simple generator for the End.
+
+use crate::worldgen::WorldGenerator;
+use feather_blocks::Block;
+use feather_core::{Biome, Chunk, ChunkPosition};
+
+/// Radius of the main island, in blocks.
+const ISLAND_RADIUS: f64 = 80.0;
+/// Height of the top of the main island.
+const ISLAND_HEIGHT: f64 = 60.0;
+/// Maximum depth of the main island below its top.
+const ISLAND_DEPTH: f64 = 40.0;
+
+/// World generator for the End.
+///
+/// Generates a single end stone island around the origin,
+/// surrounded by the void.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct EndWorldGenerator;
+
+impl WorldGenerator for EndWorldGenerator {
+    fn generate_chunk(&self, position: ChunkPosition) -> Chunk {
+        let mut chunk = Chunk::new_with_default_biome(position, Biome::TheEnd);
+
+        for x in 0..16 {
+            for z in 0..16 {
+                let world_x = f64::from(position.x * 16 + x as i32);
+                let world_z = f64::from(position.z * 16 + z as i32);
+                let distance = (world_x * world_x + world_z * world_z).sqrt();
+                if distance >= ISLAND_RADIUS {
+                    continue;
+                }
+
+                // The island tapers off towards its edges, both
+                // at the top and the bottom.
+                let falloff = 1.0 - distance / ISLAND_RADIUS;
+                let top = ISLAND_HEIGHT - (1.0 - falloff) * 8.0;
+                let bottom = top - ISLAND_DEPTH * falloff * falloff;
+
+                for y in bottom as usize..=top as usize {
+                    chunk.set_block_at(x, y, z, Block::EndStone);
+                }
+            }
+        }
+
+        chunk
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_end_island() {
+        let generator = EndWorldGenerator;
+
+        let chunk = generator.generate_chunk(ChunkPosition::new(0, 0));
+        assert_eq!(
+            chunk.block_at(0, ISLAND_HEIGHT as usize, 0),
+            Block::EndStone
+        );
+        assert_eq!(chunk.biome_at(0, 0), Biome::TheEnd);
+
+        let chunk = generator.generate_chunk(ChunkPosition::new(20, 20));
+        for y in 0..256 {
+            assert_eq!(chunk.block_at(8, y, 8), Block::Air);
+        }
+    }
+}
@@ -8,7 +8,9 @@ use feather_core::{Biome, Block, Chunk, ChunkPosition};
 mod biomes;
 mod composition;
 mod density_map;
+mod end;
 mod finishers;
+mod nether;
 pub mod noise;
 mod superflat;
 mod util;
@@ -20,6 +22,8 @@ use bitvec::slice::BitSlice;
 use bitvec::vec::BitVec;
 pub use composition::BasicCompositionGenerator;
 pub use density_map::{DensityMapGeneratorImpl, HeightMapGenerator};
+pub use end::EndWorldGenerator;
+pub use nether::NetherWorldGenerator;
 pub use noise::NoiseLerper;
 use num_traits::ToPrimitive;
 use rand::{Rng, SeedableRng};
@@ -0,0 +1,70 @@
+//! A simple generator for the Nether.
+
+use crate::worldgen::WorldGenerator;
+use feather_blocks::{Block, LavaData};
+use feather_core::{Biome, Chunk, ChunkPosition};
+use simdnoise::NoiseBuilder;
+
+/// Height of the Nether's bedrock roof.
+pub const NETHER_HEIGHT: usize = 127;
+/// Level of the Nether's lava sea.
+const LAVA_LEVEL: usize = 31;
+
+/// World generator for the Nether.
+///
+/// Generates a netherrack floor and ceiling, both shaped
+/// using two-dimensional noise, with a lava sea at the bottom
+/// and bedrock layers at the top and bottom of the world.
+#[derive(Debug, Clone, Copy)]
+pub struct NetherWorldGenerator {
+    pub seed: u64,
+}
+
+impl NetherWorldGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+}
+
+impl WorldGenerator for NetherWorldGenerator {
+    fn generate_chunk(&self, position: ChunkPosition) -> Chunk {
+        let mut chunk = Chunk::new_with_default_biome(position, Biome::Nether);
+
+        let x_offset = (position.x * 16) as f32;
+        let z_offset = (position.z * 16) as f32;
+        let (floor, _, _) = NoiseBuilder::gradient_2d_offset(x_offset, 16, z_offset, 16)
+            .with_seed(self.seed as i32)
+            .with_freq(0.03)
+            .generate();
+        let (ceiling, _, _) = NoiseBuilder::gradient_2d_offset(x_offset, 16, z_offset, 16)
+            .with_seed(self.seed as i32 + 1)
+            .with_freq(0.03)
+            .generate();
+
+        let lava = Block::Lava(LavaData { level: 0 });
+
+        for x in 0..16 {
+            for z in 0..16 {
+                let index = (z << 4) | x;
+                let floor_height = (floor[index] * 600.0 + 40.0) as usize;
+                let ceiling_height = (ceiling[index] * 600.0 + 100.0) as usize;
+
+                chunk.set_block_at(x, 0, z, Block::Bedrock);
+                chunk.set_block_at(x, NETHER_HEIGHT, z, Block::Bedrock);
+
+                for y in 1..NETHER_HEIGHT {
+                    let block = if y <= floor_height || y >= ceiling_height {
+                        Block::Netherrack
+                    } else if y <= LAVA_LEVEL {
+                        lava
+                    } else {
+                        continue;
+                    };
+                    chunk.set_block_at(x, y, z, block);
+                }
+            }
+        }
+
+        chunk
+    }
+}
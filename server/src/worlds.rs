@@ -0,0 +1,282 @@
+//! Multiple named worlds hosted by one server.
+//!
+//! Each world is a directory with its own level.dat, generator
+//! and chunk worker, and contains an overworld, a Nether and an End.
+//! Players join the main world, which is the one configured in the
+//! `[world]` section, and can be moved between worlds with `move_player`.
+//!
+//! Each world also has its own time, weather, game rules, difficulty
+//! and world border, which are stored in its level.dat and kept in
+//! `PerWorld` resources. Players are only sent those of their world.
+
+use crate::difficulty::WorldDifficulty;
+use crate::entity::{DimensionChangeEvent, EntityId, PreviousPosition};
+use crate::game_rules::{GameRule, WorldGameRules};
+use crate::join::Joined;
+use crate::network::Network;
+use crate::state::{State, StateInner, WorldData};
+use crate::time::WorldTimes;
+use crate::weather::WorldWeather;
+use crate::world_border::WorldBorders;
+use feather_core::level::LevelData;
+use feather_core::network::packet::implementation::Respawn;
+use feather_core::{Dimension, Gamemode, Position};
+use legion::entity::Entity;
+use legion::query::Read;
+use legion::world::World;
+use tonks::{PreparedWorld, Query, Scheduler};
+
+/// Identifies one of the server's worlds by its
+/// index in the order the worlds are configured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WorldId(pub usize);
+
+impl WorldId {
+    /// The main world, which players join.
+    pub const MAIN: WorldId = WorldId(0);
+}
+
+/// Identifies a dimension of one of the server's worlds.
+///
+/// Each entity has this as a component, which
+/// determines the chunks and players it can interact with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DimensionId {
+    /// The world containing the dimension.
+    pub world: WorldId,
+    /// The kind of dimension.
+    pub kind: Dimension,
+}
+
+impl DimensionId {
+    pub fn new(world: WorldId, kind: Dimension) -> Self {
+        Self { world, kind }
+    }
+
+    /// Returns the dimension of the given kind in the same world.
+    pub fn with_kind(self, kind: Dimension) -> Self {
+        Self::new(self.world, kind)
+    }
+}
+
+/// A value of which each world has its own,
+/// such as its time or weather.
+#[derive(Debug, Clone)]
+pub struct PerWorld<T>(Vec<T>);
+
+impl<T> PerWorld<T> {
+    /// Creates the values of the given worlds from their level data.
+    pub fn from_levels(worlds: &[WorldData], f: impl FnMut(&LevelData) -> T) -> Self {
+        Self(worlds.iter().map(|world| &world.level).map(f).collect())
+    }
+
+    /// Returns the value of the given world.
+    pub fn get(&self, world: WorldId) -> &T {
+        &self.0[world.0]
+    }
+
+    /// Returns the value of the given world mutably.
+    pub fn get_mut(&mut self, world: WorldId) -> &mut T {
+        &mut self.0[world.0]
+    }
+
+    /// Returns an iterator over the worlds and their values.
+    pub fn iter(&self) -> impl Iterator<Item = (WorldId, &T)> {
+        self.0
+            .iter()
+            .enumerate()
+            .map(|(index, value)| (WorldId(index), value))
+    }
+
+    /// Returns an iterator over the worlds and their values, mutably.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (WorldId, &mut T)> {
+        self.0
+            .iter_mut()
+            .enumerate()
+            .map(|(index, value)| (WorldId(index), value))
+    }
+}
+
+/// Moves a player to another dimension, possibly in another world.
+///
+/// This sends the player a `Respawn` packet, after which the join
+/// sequence runs again once their new chunk has been sent. The
+/// change is applied lazily, at the end of the tick.
+pub fn move_player(state: &State, player: Entity, dimension: DimensionId, position: Position) {
+    state.exec_with_scheduler(move |world, scheduler| {
+        move_player_now(world, scheduler, player, dimension, position);
+    });
+}
+
+/// Moves a player to another dimension immediately. Unlike
+/// `move_player`, this requires access to the world, such
+/// as in a lazy closure.
+pub fn move_player_now(
+    world: &mut World,
+    scheduler: &mut Scheduler,
+    player: Entity,
+    dimension: DimensionId,
+    position: Position,
+) {
+    let old_dimension = match world.get_component::<DimensionId>(player) {
+        Some(old_dimension) => *old_dimension,
+        None => return,
+    };
+    if old_dimension == dimension {
+        return;
+    }
+    let old_position = *world.get_component::<Position>(player).unwrap();
+    let gamemode = *world.get_component::<Gamemode>(player).unwrap();
+
+    *world.get_component_mut::<DimensionId>(player).unwrap() = dimension;
+    *world.get_component_mut::<Position>(player).unwrap() = position;
+    world
+        .get_component_mut::<PreviousPosition>(player)
+        .unwrap()
+        .0 = position;
+    world.get_component_mut::<Joined>(player).unwrap().0 = false;
+
+    let network = world.get_component::<Network>(player).unwrap();
+    // The client ignores respawning into the dimension it is
+    // already in, so moving between worlds requires a detour.
+    if old_dimension.kind == dimension.kind {
+        let detour = match dimension.kind {
+            Dimension::Overworld => Dimension::Nether,
+            _ => Dimension::Overworld,
+        };
+        network.send(respawn_packet(
+            scheduler,
+            dimension.with_kind(detour),
+            gamemode,
+        ));
+    }
+    network.send(respawn_packet(scheduler, dimension, gamemode));
+    drop(network);
+
+    scheduler.trigger(DimensionChangeEvent {
+        entity: player,
+        old_dimension,
+        old_position,
+        new_dimension: dimension,
+    });
+}
+
+/// Returns the `Respawn` packet which moves a player into the
+/// given dimension, with the difficulty and level type of its world.
+pub fn respawn_packet(
+    scheduler: &Scheduler,
+    dimension: DimensionId,
+    gamemode: Gamemode,
+) -> Respawn {
+    let resources = scheduler.resources();
+    let difficulty = resources
+        .get::<WorldDifficulty>()
+        .get(dimension.world)
+        .difficulty;
+    let level_type = resources
+        .get::<StateInner>()
+        .world(dimension.world)
+        .level
+        .generator_type()
+        .level_type();
+
+    Respawn {
+        dimension: dimension.kind.get_id(),
+        difficulty: difficulty.get_id(),
+        gamemode: gamemode.get_id(),
+        level_type: level_type.to_string(),
+    }
+}
+
+/// Event handler which sends players the time, weather,
+/// world border and game rules of the dimension they moved to,
+/// since clients reset them when they respawn.
+#[event_handler]
+fn send_world_state(
+    event: &DimensionChangeEvent,
+    times: &WorldTimes,
+    weather: &WorldWeather,
+    rules: &WorldGameRules,
+    borders: &WorldBorders,
+    _query: &mut Query<(Read<EntityId>, Read<Network>)>,
+    world: &mut PreparedWorld,
+) {
+    let network = match world.get_component::<Network>(event.entity) {
+        Some(network) => network,
+        None => return,
+    };
+    let id = *world.get_component::<EntityId>(event.entity).unwrap();
+    let world_id = event.new_dimension.world;
+    let rules = rules.get(world_id);
+
+    network.send(
+        times
+            .get(world_id)
+            .update_packet(rules.get_bool(GameRule::DoDaylightCycle)),
+    );
+    weather.get(world_id).send(&network);
+    network.send(borders.get(world_id).initialize_packet());
+    network.send(rules.reduced_debug_info_packet(id));
+}
+
+/// Moves a player to the spawn point of the given world's overworld.
+pub fn move_player_to_world(state: &State, player: Entity, world: WorldId) {
+    let level = &state.world(world).level;
+    let position = position!(
+        f64::from(level.spawn_x) + 0.5,
+        f64::from(level.spawn_y),
+        f64::from(level.spawn_z) + 0.5
+    );
+    let dimension = DimensionId::new(world, Dimension::Overworld);
+    move_player(state, player, dimension, position);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_worlds() {
+        let worlds = vec![
+            WorldData::new("world".to_string(), LevelData::default()),
+            WorldData::new("lobby".to_string(), LevelData::default()),
+        ];
+        let state = StateInner::new(Arc::new(Config::default()), worlds);
+
+        assert_eq!(state.world_by_name("world"), Some(WorldId::MAIN));
+        assert_eq!(state.world_by_name("lobby"), Some(WorldId(1)));
+        assert_eq!(state.world_by_name("creative"), None);
+
+        let dimensions: Vec<_> = state.dimension_ids().collect();
+        assert_eq!(dimensions.len(), 6);
+        assert!(dimensions.contains(&DimensionId::new(WorldId(1), Dimension::Nether)));
+    }
+
+    #[test]
+    fn test_per_world() {
+        let mut lobby = LevelData::default();
+        lobby.time = 100;
+        let worlds = vec![
+            WorldData::new("world".to_string(), LevelData::default()),
+            WorldData::new("lobby".to_string(), lobby),
+        ];
+
+        let mut times = PerWorld::from_levels(&worlds, |level| level.time);
+        *times.get_mut(WorldId::MAIN) += 1;
+        assert_eq!(*times.get(WorldId::MAIN), 1);
+        assert_eq!(
+            times.iter().collect::<Vec<_>>(),
+            vec![(WorldId::MAIN, &1), (WorldId(1), &100)]
+        );
+    }
+
+    #[test]
+    fn test_with_kind() {
+        let overworld = DimensionId::new(WorldId(2), Dimension::Overworld);
+        let end = overworld.with_kind(Dimension::End);
+        assert_eq!(end.world, WorldId(2));
+        assert_eq!(end.kind, Dimension::End);
+    }
+}